@@ -0,0 +1,348 @@
+extern crate docopt;
+extern crate regex;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::process;
+
+use docopt::Docopt;
+use regex::Regex;
+
+const USAGE: &'static str = "
+Convert benchmark output to JSON and compare benchmark results.
+
+The json command reads the output of `cargo bench` (as printed by the `run`
+script in this directory) from <file>, or stdin if <file> is absent, and
+writes a JSON array with one result per benchmark to stdout. <engine> names
+the regex engine that was benchmarked, e.g., `rust` or `pcre2`.
+
+The compare command reads two files produced by the json command for the
+same engine and reports every benchmark in either one. A benchmark regresses
+if its time per iteration in <new> exceeds its time in <old> by more than the
+threshold percentage. Benchmarks that are only in one of the files are marked
+as missing or new. If any benchmark regresses, then this exits with status 1.
+Comparing results for different engines is an error.
+
+Neither command runs any benchmarks, so no regex engine needs to be installed
+to use them.
+
+Usage:
+    regex-bench-results json <engine> [<file>]
+    regex-bench-results compare [options] <old> <new>
+    regex-bench-results (-h | --help)
+
+Options:
+    -h, --help             Show this usage message.
+    --threshold <percent>  The percentage by which a benchmark must slow down
+                           to be considered a regression. [default: 5]
+";
+
+#[derive(Debug, Deserialize)]
+struct Args {
+    cmd_json: bool,
+    cmd_compare: bool,
+    arg_engine: String,
+    arg_file: Option<String>,
+    arg_old: String,
+    arg_new: String,
+    flag_threshold: f64,
+}
+
+/// The result of running a single benchmark.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct Benchmark {
+    /// The regex engine that was benchmarked.
+    engine: String,
+    /// The full name of the benchmark, e.g., `misc::easy0_1K`.
+    benchmark: String,
+    /// The median time, in nanoseconds, of a single iteration.
+    ns_per_iter: u64,
+    /// The spread, in nanoseconds, of the iteration times.
+    deviation: u64,
+    /// The throughput in megabytes (10^6 bytes) per second.
+    throughput: Option<u64>,
+}
+
+type Result<T> = ::std::result::Result<T, Box<dyn Error>>;
+
+fn main() {
+    let args: Args = Docopt::new(USAGE)
+        .and_then(|d| d.deserialize())
+        .unwrap_or_else(|e| e.exit());
+    let result = if args.cmd_json {
+        args.run_json()
+    } else if args.cmd_compare {
+        args.run_compare().map(|regressed| {
+            if regressed {
+                process::exit(1);
+            }
+        })
+    } else {
+        unreachable!()
+    };
+    if let Err(err) = result {
+        let _ = writeln!(&mut io::stderr(), "{}", err);
+        process::exit(2);
+    }
+}
+
+impl Args {
+    fn run_json(&self) -> Result<()> {
+        let benchmarks = match self.arg_file {
+            None => {
+                let stdin = io::stdin();
+                parse_bench_output(&self.arg_engine, stdin.lock())?
+            }
+            Some(ref path) => {
+                let file = File::open(path)
+                    .map_err(|err| format!("{}: {}", path, err))?;
+                let file = BufReader::new(file);
+                parse_bench_output(&self.arg_engine, file)?
+            }
+        };
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        serde_json::to_writer_pretty(&mut stdout, &benchmarks)?;
+        writeln!(stdout)?;
+        Ok(())
+    }
+
+    fn run_compare(&self) -> Result<bool> {
+        let old = read_results(&self.arg_old)?;
+        let new = read_results(&self.arg_new)?;
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        compare(&old, &new, self.flag_threshold, &mut stdout)
+    }
+}
+
+/// Writes a comparison of two sets of results to `wtr` and returns whether
+/// any benchmark regressed by more than `threshold` percent.
+///
+/// It is an error if `old` and `new` were produced for different engines.
+fn compare<W: Write>(
+    old: &[Benchmark],
+    new: &[Benchmark],
+    threshold: f64,
+    mut wtr: W,
+) -> Result<bool> {
+    let old_engines = engines(old);
+    let new_engines = engines(new);
+    if !old.is_empty() && !new.is_empty() && old_engines != new_engines {
+        return Err(format!(
+            "engine mismatch: old results are for {}, new results are for {}",
+            old_engines.join(", "),
+            new_engines.join(", "),
+        )
+        .into());
+    }
+    let new_by_name: HashMap<(&str, &str), &Benchmark> =
+        new.iter().map(|b| ((&*b.engine, &*b.benchmark), b)).collect();
+    let old_by_name: HashMap<(&str, &str), &Benchmark> =
+        old.iter().map(|b| ((&*b.engine, &*b.benchmark), b)).collect();
+
+    let mut regressed = false;
+    writeln!(
+        wtr,
+        "{:<50} {:>14} {:>14} {:>9}",
+        "name", "old ns/iter", "new ns/iter", "diff %"
+    )?;
+    for b in new {
+        let before = match old_by_name.get(&(&*b.engine, &*b.benchmark)) {
+            None => {
+                writeln!(
+                    wtr,
+                    "{:<50} {:>14} {:>14} {:>9}  NEW",
+                    b.benchmark, "-", b.ns_per_iter, "-"
+                )?;
+                continue;
+            }
+            Some(before) => before,
+        };
+        let diff = percent_change(before.ns_per_iter, b.ns_per_iter);
+        let flag = if diff > threshold {
+            regressed = true;
+            "  REGRESSION"
+        } else {
+            ""
+        };
+        writeln!(
+            wtr,
+            "{:<50} {:>14} {:>14} {:>+9.2}{}",
+            b.benchmark, before.ns_per_iter, b.ns_per_iter, diff, flag
+        )?;
+    }
+    for b in old {
+        if !new_by_name.contains_key(&(&*b.engine, &*b.benchmark)) {
+            writeln!(
+                wtr,
+                "{:<50} {:>14} {:>14} {:>9}  MISSING",
+                b.benchmark, b.ns_per_iter, "-", "-"
+            )?;
+        }
+    }
+    Ok(regressed)
+}
+
+/// Returns the distinct engines in the given results, in sorted order.
+fn engines(benchmarks: &[Benchmark]) -> Vec<&str> {
+    let mut engines: Vec<&str> =
+        benchmarks.iter().map(|b| &*b.engine).collect();
+    engines.sort();
+    engines.dedup();
+    engines
+}
+
+/// Parses the benchmark results in the output of `cargo bench`.
+///
+/// Lines that don't report a benchmark result are ignored.
+fn parse_bench_output<R: BufRead>(
+    engine: &str,
+    rdr: R,
+) -> Result<Vec<Benchmark>> {
+    let re = Regex::new(
+        r"(?x)
+        ^test\s+(?P<name>\S+)\s+\.\.\.\s+bench:
+        \s+(?P<ns>[0-9,]+)\s+ns/iter
+        \s+\(\+/-\s+(?P<dev>[0-9,]+)\)
+        (?:\s+=\s+(?P<mbs>[0-9,]+)\s+MB/s)?
+        ",
+    )
+    .unwrap();
+    let mut benchmarks = vec![];
+    for line in rdr.lines() {
+        let line = line?;
+        let caps = match re.captures(&line) {
+            None => continue,
+            Some(caps) => caps,
+        };
+        let throughput = match caps.name("mbs") {
+            None => None,
+            Some(m) => Some(parse_number(m.as_str())?),
+        };
+        benchmarks.push(Benchmark {
+            engine: engine.to_string(),
+            benchmark: caps["name"].to_string(),
+            ns_per_iter: parse_number(&caps["ns"])?,
+            deviation: parse_number(&caps["dev"])?,
+            throughput: throughput,
+        });
+    }
+    Ok(benchmarks)
+}
+
+/// Parses a number printed by libtest, which may contain commas.
+fn parse_number(s: &str) -> Result<u64> {
+    Ok(s.replace(",", "").parse()?)
+}
+
+/// Reads a file written by the json command.
+fn read_results(path: &str) -> Result<Vec<Benchmark>> {
+    let mut data = String::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_string(&mut data))
+        .map_err(|err| format!("{}: {}", path, err))?;
+    let results = serde_json::from_str(&data)
+        .map_err(|err| format!("{}: {}", path, err))?;
+    Ok(results)
+}
+
+/// Returns the change from `old` to `new` as a percentage of `old`.
+///
+/// A positive number means `new` is slower.
+fn percent_change(old: u64, new: u64) -> f64 {
+    if old == 0 {
+        return if new == 0 { 0.0 } else { ::std::f64::INFINITY };
+    }
+    (new as f64 - old as f64) / old as f64 * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare, parse_bench_output, percent_change, Benchmark};
+
+    fn bench(engine: &str, name: &str, ns_per_iter: u64) -> Benchmark {
+        Benchmark {
+            engine: engine.to_string(),
+            benchmark: name.to_string(),
+            ns_per_iter: ns_per_iter,
+            deviation: 0,
+            throughput: None,
+        }
+    }
+
+    #[test]
+    fn parse_output() {
+        let output = "\
+running 3 tests
+test misc::easy0_1K    ... bench:          12 ns/iter (+/- 1) = 87,583 MB/s
+test misc::hard_1MB    ... bench:   1,234,567 ns/iter (+/- 12,345) = 849 MB/s
+test rust_compile::one ... bench:       1,000 ns/iter (+/- 50)
+
+test result: ok. 0 passed; 0 failed; 0 ignored; 3 measured
+";
+        let got = parse_bench_output("rust", output.as_bytes()).unwrap();
+        assert_eq!(got.len(), 3);
+
+        assert_eq!(got[0].engine, "rust");
+        assert_eq!(got[0].benchmark, "misc::easy0_1K");
+        assert_eq!(got[0].ns_per_iter, 12);
+        assert_eq!(got[0].deviation, 1);
+        assert_eq!(got[0].throughput, Some(87_583));
+
+        assert_eq!(got[1].benchmark, "misc::hard_1MB");
+        assert_eq!(got[1].ns_per_iter, 1_234_567);
+        assert_eq!(got[1].deviation, 12_345);
+        assert_eq!(got[1].throughput, Some(849));
+
+        assert_eq!(got[2].benchmark, "rust_compile::one");
+        assert_eq!(got[2].ns_per_iter, 1_000);
+        assert_eq!(got[2].deviation, 50);
+        assert_eq!(got[2].throughput, None);
+    }
+
+    #[test]
+    fn percent_changes() {
+        assert_eq!(percent_change(100, 100), 0.0);
+        assert_eq!(percent_change(100, 150), 50.0);
+        assert_eq!(percent_change(200, 100), -50.0);
+        assert_eq!(percent_change(0, 0), 0.0);
+        assert_eq!(percent_change(0, 1), ::std::f64::INFINITY);
+    }
+
+    #[test]
+    fn compare_missing_and_new() {
+        let old = vec![bench("rust", "a", 100), bench("rust", "b", 100)];
+        let new = vec![bench("rust", "a", 120), bench("rust", "c", 100)];
+        let mut out = vec![];
+        assert!(compare(&old, &new, 5.0, &mut out).unwrap());
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1].starts_with("a "));
+        assert!(lines[1].ends_with("REGRESSION"));
+        assert!(lines[2].starts_with("c ") && lines[2].ends_with("NEW"));
+        assert!(lines[3].starts_with("b ") && lines[3].ends_with("MISSING"));
+
+        let mut out = vec![];
+        assert!(!compare(&old, &new, 50.0, &mut out).unwrap());
+    }
+
+    #[test]
+    fn compare_engine_mismatch() {
+        let old = vec![bench("pcre2", "a", 100)];
+        let new = vec![bench("rust", "a", 100)];
+        let err = compare(&old, &new, 5.0, vec![]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "engine mismatch: old results are for pcre2, \
+             new results are for rust"
+        );
+    }
+}
@@ -0,0 +1,192 @@
+use std::fmt;
+
+use regex::internal::ExecBuilder;
+
+/// A match span, given as a pair of byte offsets.
+pub type Span = (usize, usize);
+
+/// The result of searching a haystack with a single engine.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Outcome {
+    /// Whether the regex matches anywhere in the haystack.
+    pub is_match: bool,
+    /// The spans of all successive non-overlapping matches.
+    pub matches: Vec<Span>,
+    /// The location of every capture group (including the implicit group
+    /// for the entire match) of all successive non-overlapping matches.
+    pub captures: Vec<Vec<Option<Span>>>,
+}
+
+/// A search API that two engines disagree on.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Mismatch {
+    /// `is_match`
+    IsMatch,
+    /// `find_iter`
+    FindIter,
+    /// `captures_iter`
+    CapturesIter,
+}
+
+impl Outcome {
+    /// Returns the first search API on which this outcome and `other`
+    /// disagree, if any.
+    pub fn mismatch(&self, other: &Outcome) -> Option<Mismatch> {
+        if self.is_match != other.is_match {
+            Some(Mismatch::IsMatch)
+        } else if self.matches != other.matches {
+            Some(Mismatch::FindIter)
+        } else if self.captures != other.captures {
+            Some(Mismatch::CapturesIter)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Mismatch::IsMatch => write!(f, "is_match"),
+            Mismatch::FindIter => write!(f, "find_iter"),
+            Mismatch::CapturesIter => write!(f, "captures_iter"),
+        }
+    }
+}
+
+/// A compiled regex using a specific matching engine.
+#[derive(Clone, Debug)]
+pub enum Searcher {
+    /// A regex that searches `&str` haystacks.
+    Unicode(regex::Regex),
+    /// A regex that searches `&[u8]` haystacks and may match invalid UTF-8.
+    Bytes(regex::bytes::Regex),
+}
+
+impl Searcher {
+    /// Run every search API on the given haystack.
+    pub fn search(&self, haystack: &str) -> Outcome {
+        match *self {
+            Searcher::Unicode(ref re) => Outcome {
+                is_match: re.is_match(haystack),
+                matches: re
+                    .find_iter(haystack)
+                    .map(|m| (m.start(), m.end()))
+                    .collect(),
+                captures: re
+                    .captures_iter(haystack)
+                    .map(|caps| {
+                        caps.iter()
+                            .map(|m| m.map(|m| (m.start(), m.end())))
+                            .collect()
+                    })
+                    .collect(),
+            },
+            Searcher::Bytes(ref re) => {
+                let haystack = haystack.as_bytes();
+                Outcome {
+                    is_match: re.is_match(haystack),
+                    matches: re
+                        .find_iter(haystack)
+                        .map(|m| (m.start(), m.end()))
+                        .collect(),
+                    captures: re
+                        .captures_iter(haystack)
+                        .map(|caps| {
+                            caps.iter()
+                                .map(|m| m.map(|m| (m.start(), m.end())))
+                                .collect()
+                        })
+                        .collect(),
+                }
+            }
+        }
+    }
+}
+
+/// A matching engine configuration under test.
+pub struct Engine {
+    /// The name of this engine, used when reporting a divergence.
+    pub name: &'static str,
+    /// Compile a pattern with this engine.
+    pub build: fn(&str) -> Option<Searcher>,
+}
+
+/// Engines searching `&str` haystacks, which must agree with one another.
+///
+/// The first engine is the PikeVM, which is used as the reference that every
+/// other engine is compared against.
+pub const UNICODE: &[Engine] = &[
+    Engine { name: "pikevm", build: pikevm },
+    Engine { name: "backtrack", build: backtrack },
+    Engine { name: "default", build: default },
+    Engine { name: "simplified", build: simplified },
+    Engine { name: "pikevm_utf8bytes", build: pikevm_utf8bytes },
+    Engine { name: "backtrack_utf8bytes", build: backtrack_utf8bytes },
+    Engine { name: "default_utf8bytes", build: default_utf8bytes },
+];
+
+/// Engines searching `&[u8]` haystacks, which must agree with one another.
+///
+/// These are compared separately from the engines in `UNICODE`, since they
+/// may report empty matches that split a UTF-8 encoded codepoint.
+pub const BYTES: &[Engine] = &[
+    Engine { name: "pikevm_bytes", build: pikevm_bytes },
+    Engine { name: "backtrack_bytes", build: backtrack_bytes },
+    Engine { name: "default_bytes", build: default_bytes },
+];
+
+fn unicode(builder: ExecBuilder) -> Option<Searcher> {
+    builder.build().ok().map(|exec| Searcher::Unicode(exec.into_regex()))
+}
+
+fn bytes(builder: ExecBuilder) -> Option<Searcher> {
+    builder
+        .only_utf8(false)
+        .build()
+        .ok()
+        .map(|exec| Searcher::Bytes(exec.into_byte_regex()))
+}
+
+fn pikevm(re: &str) -> Option<Searcher> {
+    unicode(ExecBuilder::new(re).nfa())
+}
+
+fn backtrack(re: &str) -> Option<Searcher> {
+    unicode(ExecBuilder::new(re).bounded_backtracking())
+}
+
+// The default configuration picks the lazy DFA and literal searchers
+// whenever it can, and only falls back to the PikeVM or backtracker to
+// resolve capture groups.
+fn default(re: &str) -> Option<Searcher> {
+    unicode(ExecBuilder::new(re))
+}
+
+fn simplified(re: &str) -> Option<Searcher> {
+    unicode(ExecBuilder::new(re).simplify(true))
+}
+
+fn pikevm_utf8bytes(re: &str) -> Option<Searcher> {
+    unicode(ExecBuilder::new(re).nfa().bytes(true))
+}
+
+fn backtrack_utf8bytes(re: &str) -> Option<Searcher> {
+    unicode(ExecBuilder::new(re).bounded_backtracking().bytes(true))
+}
+
+fn default_utf8bytes(re: &str) -> Option<Searcher> {
+    unicode(ExecBuilder::new(re).bytes(true))
+}
+
+fn pikevm_bytes(re: &str) -> Option<Searcher> {
+    bytes(ExecBuilder::new(re).nfa())
+}
+
+fn backtrack_bytes(re: &str) -> Option<Searcher> {
+    bytes(ExecBuilder::new(re).bounded_backtracking())
+}
+
+fn default_bytes(re: &str) -> Option<Searcher> {
+    bytes(ExecBuilder::new(re))
+}
@@ -0,0 +1,370 @@
+/*!
+Differential fuzzing of the matching engines in the `regex` crate.
+
+Every regex can be executed by several engines: the PikeVM, the bounded
+backtracker, the lazy DFA and the literal searchers (the latter two are
+selected automatically by the default configuration), each of which may run
+on Unicode codepoints or on bytes. All of them must report the same matches
+and capture group locations.
+
+A fuzz input is decoded into a [`Case`](struct.Case.html): a pattern, built
+as a `regex-syntax` `Ast`, and a haystack, which is seeded with a string
+matching the pattern when possible so that matches are actually exercised.
+Every engine is then run on the haystack and compared with the PikeVM. When
+two engines disagree, the case is minimized and reported as a test that can
+be pasted into `tests/regression.rs`.
+
+The fuzz target is run with [`cargo fuzz`](https://github.com/rust-fuzz/cargo-fuzz)
+from the root of the `regex` repository:
+
+```text
+$ cargo fuzz run differential
+```
+*/
+
+use std::fmt;
+
+use regex_syntax::hir::generate::Generator;
+use regex_syntax::Parser;
+
+use crate::engine::{Engine, Mismatch, Outcome, BYTES, UNICODE};
+pub use crate::pattern::{Node, Source, ALPHABET};
+
+mod engine;
+mod pattern;
+
+/// The maximum nesting depth of a generated pattern.
+const MAX_DEPTH: usize = 4;
+
+/// The maximum number of characters in a generated haystack, not counting
+/// any matching string spliced into it.
+const MAX_HAYSTACK: usize = 64;
+
+/// Decode the given fuzz input, check that every engine agrees and panic
+/// with a minimized regression test if they don't.
+pub fn run(data: &[u8]) {
+    let case = Case::from_bytes(data);
+    if let Some(divergence) = check(&case) {
+        let (_, divergence) = minimize(case, divergence, check);
+        panic!("{}", divergence);
+    }
+}
+
+/// A pattern and a haystack to search.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Case {
+    /// The pattern to compile.
+    pub pattern: Node,
+    /// The haystack to search.
+    pub haystack: String,
+}
+
+impl Case {
+    /// Decode a case from the raw bytes given to a fuzz target.
+    ///
+    /// The leading bytes determine the pattern and the remaining bytes
+    /// determine the haystack. If the first of the remaining bytes is odd,
+    /// then a random string matching the pattern is spliced into the
+    /// haystack.
+    pub fn from_bytes(data: &[u8]) -> Case {
+        let mut src = Source::new(data);
+        let pattern = Node::generate(&mut src, MAX_DEPTH);
+        let splice = src.byte();
+        let mut haystack = String::new();
+        while !src.is_empty() && haystack.chars().count() < MAX_HAYSTACK {
+            haystack.push(src.char());
+        }
+        if splice & 1 == 1 {
+            let seed = u64::from(splice >> 1);
+            if let Some(sample) = sample(&pattern.to_ast().to_string(), seed) {
+                let at = haystack
+                    .char_indices()
+                    .map(|(i, _)| i)
+                    .nth(haystack.chars().count() / 2)
+                    .unwrap_or(haystack.len());
+                haystack.insert_str(at, &sample);
+            }
+        }
+        Case { pattern: pattern, haystack: haystack }
+    }
+
+    /// Returns the concrete syntax of this case's pattern.
+    pub fn regex(&self) -> String {
+        self.pattern.to_ast().to_string()
+    }
+
+    /// Returns every case that can be obtained from this one by making a
+    /// single simplification to either its pattern or its haystack.
+    pub fn shrink(&self) -> Vec<Case> {
+        let mut shrunk: Vec<Case> = self
+            .pattern
+            .shrink()
+            .into_iter()
+            .map(|pattern| Case {
+                pattern: pattern,
+                haystack: self.haystack.clone(),
+            })
+            .collect();
+        for (i, c) in self.haystack.char_indices() {
+            let mut haystack = self.haystack.clone();
+            haystack.replace_range(i..i + c.len_utf8(), "");
+            shrunk.push(Case {
+                pattern: self.pattern.clone(),
+                haystack: haystack,
+            });
+        }
+        shrunk
+    }
+}
+
+/// Sample a string matching the given pattern, if it can be parsed and if
+/// a valid UTF-8 matching string can be found.
+fn sample(pattern: &str, seed: u64) -> Option<String> {
+    let hir = Parser::new().parse(pattern).ok()?;
+    let bytes = Generator::new().max_repeat(3).sampler(&hir, seed).sample()?;
+    String::from_utf8(bytes).ok()
+}
+
+/// Two engines that disagree on the result of searching a haystack.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Divergence {
+    /// The pattern that was compiled.
+    pub pattern: String,
+    /// The haystack that was searched.
+    pub haystack: String,
+    /// The engine that disagrees with the reference engine.
+    pub engine: &'static str,
+    /// The reference engine.
+    pub reference: &'static str,
+    /// The first search API on which the engines disagree.
+    pub mismatch: Mismatch,
+    /// The result of the reference engine.
+    pub expected: Outcome,
+    /// The result of the engine under test.
+    pub got: Outcome,
+}
+
+/// Run every engine on the given case and return the first divergence
+/// from the reference engine, if any.
+///
+/// Engines searching `&str` haystacks and engines searching `&[u8]`
+/// haystacks are compared separately. If the pattern is rejected by any
+/// engine in a group, then there is nothing to compare in that group.
+///
+/// Patterns like `(?-u:\W)` are accepted by `Regex` even though they can
+/// match invalid UTF-8, so the engines searching `&str` haystacks are free
+/// to disagree on them. Those engines are therefore only compared when the
+/// pattern always matches valid UTF-8.
+pub fn check(case: &Case) -> Option<Divergence> {
+    let pattern = case.regex();
+    let utf8 = Parser::new()
+        .parse(&pattern)
+        .map(|hir| hir.is_always_utf8())
+        .unwrap_or(false);
+    if utf8 {
+        if let Some(divergence) = check_group(UNICODE, &pattern, case) {
+            return Some(divergence);
+        }
+    }
+    check_group(BYTES, &pattern, case)
+}
+
+/// Compare every engine in the given group with the first one.
+fn check_group(
+    engines: &[Engine],
+    pattern: &str,
+    case: &Case,
+) -> Option<Divergence> {
+    let mut searchers = vec![];
+    for engine in engines {
+        searchers.push((engine.name, (engine.build)(pattern)?));
+    }
+    let (reference, ref re) = searchers[0];
+    let expected = re.search(&case.haystack);
+    for &(engine, ref re) in &searchers[1..] {
+        let got = re.search(&case.haystack);
+        if let Some(mismatch) = expected.mismatch(&got) {
+            return Some(Divergence {
+                pattern: pattern.to_string(),
+                haystack: case.haystack.clone(),
+                engine: engine,
+                reference: reference,
+                mismatch: mismatch,
+                expected: expected,
+                got: got,
+            });
+        }
+    }
+    None
+}
+
+/// Repeatedly simplify a case for as long as `check` still finds a
+/// divergence in it.
+///
+/// The simplest case found is returned along with the divergence found in
+/// it. This never returns a larger case than the one given.
+pub fn minimize<T, F>(mut case: Case, mut found: T, mut check: F) -> (Case, T)
+where
+    F: FnMut(&Case) -> Option<T>,
+{
+    'SHRINK: loop {
+        for candidate in case.shrink() {
+            if let Some(next) = check(&candidate) {
+                case = candidate;
+                found = next;
+                continue 'SHRINK;
+            }
+        }
+        return (case, found);
+    }
+}
+
+/// Prints a divergence as a test case for `tests/regression.rs`, which
+/// asserts the result of the reference engine.
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} disagrees with {} on {}: expected {:?} but got {:?}",
+            self.engine,
+            self.reference,
+            self.mismatch,
+            self.selected(&self.expected),
+            self.selected(&self.got),
+        )?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "// Found by differential fuzzing: {} disagrees with {}.",
+            self.engine, self.reference
+        )?;
+        let name = format!("fuzz_{:08x}", self.id());
+        match self.mismatch {
+            Mismatch::IsMatch => writeln!(
+                f,
+                "ismatch!({}, {:?}, {:?}, {});",
+                name, self.pattern, self.haystack, self.expected.is_match
+            ),
+            Mismatch::FindIter => {
+                writeln!(f, "#[test]")?;
+                writeln!(f, "fn {}() {{", name)?;
+                writeln!(f, "    let re = regex!({:?});", self.pattern)?;
+                writeln!(
+                    f,
+                    "    assert_eq!(vec!{:?}, findall!(re, {:?}));",
+                    self.expected.matches, self.haystack
+                )?;
+                writeln!(f, "}}")
+            }
+            Mismatch::CapturesIter => {
+                writeln!(f, "#[test]")?;
+                writeln!(f, "fn {}() {{", name)?;
+                writeln!(f, "    let re = regex!({:?});", self.pattern)?;
+                writeln!(
+                    f,
+                    "    let got: Vec<Vec<_>> = re\n\
+                     \x20       .captures_iter(text!({:?}))\n\
+                     \x20       .map(|caps| {{\n\
+                     \x20           caps.iter()\n\
+                     \x20               .map(|m| m.map(|m| (m.start(), m.end())))\n\
+                     \x20               .collect()\n\
+                     \x20       }})\n\
+                     \x20       .collect();",
+                    self.haystack
+                )?;
+                writeln!(
+                    f,
+                    "    assert_eq!(vec!{:?}, got);",
+                    self.expected.captures
+                )?;
+                writeln!(f, "}}")
+            }
+        }
+    }
+}
+
+impl Divergence {
+    /// Returns the part of an outcome that the engines disagree on.
+    fn selected(&self, outcome: &Outcome) -> String {
+        match self.mismatch {
+            Mismatch::IsMatch => format!("{:?}", outcome.is_match),
+            Mismatch::FindIter => format!("{:?}", outcome.matches),
+            Mismatch::CapturesIter => format!("{:?}", outcome.captures),
+        }
+    }
+
+    /// Returns a stable identifier for this divergence's pattern and
+    /// haystack, computed with 32-bit FNV-1a.
+    fn id(&self) -> u32 {
+        let bytes =
+            self.pattern.bytes().chain(Some(0)).chain(self.haystack.bytes());
+        bytes.fold(0x811c9dc5, |h, b| {
+            (h ^ u32::from(b)).wrapping_mul(0x01000193)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, minimize, Case, Node};
+
+    #[test]
+    fn generated_patterns_parse() {
+        let mut parsed = 0;
+        for i in 0..1000u32 {
+            let data = i.wrapping_mul(2654435761).to_le_bytes();
+            let data: Vec<u8> =
+                data.iter().cycle().cloned().take(24).collect();
+            let case = Case::from_bytes(&data);
+            if regex::Regex::new(&case.regex()).is_ok() {
+                parsed += 1;
+            }
+        }
+        // Only a few patterns, like `(?-u:.)`, should be rejected.
+        assert!(parsed > 900, "only {} of 1000 patterns parsed", parsed);
+    }
+
+    #[test]
+    fn engines_agree() {
+        for i in 0..2000u32 {
+            let mut data = vec![];
+            let mut x = i.wrapping_add(1);
+            for _ in 0..48 {
+                // xorshift32
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                data.push(x as u8);
+            }
+            let case = Case::from_bytes(&data);
+            if let Some(divergence) = check(&case) {
+                let (_, divergence) = minimize(case, divergence, check);
+                panic!("{}", divergence);
+            }
+        }
+    }
+
+    #[test]
+    fn minimize_pattern_and_haystack() {
+        let case = Case {
+            pattern: Node::Concat(vec![
+                Node::Literal('a'),
+                Node::Alternation(vec![Node::Literal('b'), Node::Dot]),
+                Node::Literal('c'),
+            ]),
+            haystack: "xxabcxx".to_string(),
+        };
+        // Pretend that an engine fails on every case whose pattern contains
+        // `b` and whose haystack contains a `b`.
+        let fails = |case: &Case| {
+            if case.regex().contains('b') && case.haystack.contains('b') {
+                Some(())
+            } else {
+                None
+            }
+        };
+        let (case, ()) = minimize(case, (), fails);
+        assert_eq!(Node::Literal('b'), case.pattern);
+        assert_eq!("b", case.haystack);
+    }
+}
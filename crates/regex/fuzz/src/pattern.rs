@@ -0,0 +1,433 @@
+use regex_syntax::ast::{self, Ast, Position, Span};
+
+/// The characters that generated patterns and haystacks are built from.
+///
+/// The alphabet is deliberately tiny so that patterns and haystacks have a
+/// reasonable chance of agreeing with one another. It includes characters
+/// that exercise case folding (including the non-ASCII simple case folds of
+/// `s` and `k`), word boundaries, line anchors and multi-byte UTF-8.
+pub const ALPHABET: &[char] = &[
+    'a', 'b', 'c', 'A', 'B', 's', 'S', 'ſ', 'k', 'K', '\u{212A}', '0', '9',
+    '_', ' ', '\n', '-', '.', 'é', 'β', 'Δ', '☃',
+];
+
+/// The flags that may be toggled by a non-capturing group.
+const FLAGS: &[ast::Flag] = &[
+    ast::Flag::CaseInsensitive,
+    ast::Flag::MultiLine,
+    ast::Flag::DotMatchesNewLine,
+    ast::Flag::SwapGreed,
+    ast::Flag::Unicode,
+];
+
+/// A source of decisions, driven by the raw bytes given to a fuzz target.
+///
+/// Once the bytes are exhausted, every decision is `0`, which always leads
+/// to the smallest choice. This guarantees that generation terminates.
+#[derive(Clone, Debug)]
+pub struct Source<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Source<'a> {
+    /// Create a new source of decisions from the given bytes.
+    pub fn new(data: &'a [u8]) -> Source<'a> {
+        Source { data: data }
+    }
+
+    /// Returns true if and only if all bytes have been consumed.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Consume the next byte.
+    pub fn byte(&mut self) -> u8 {
+        match self.data.split_first() {
+            None => 0,
+            Some((&b, rest)) => {
+                self.data = rest;
+                b
+            }
+        }
+    }
+
+    /// Consume the next byte as a number in the range `[0, n)`.
+    pub fn below(&mut self, n: usize) -> usize {
+        self.byte() as usize % n
+    }
+
+    /// Consume the next byte as a character from the alphabet.
+    pub fn char(&mut self) -> char {
+        ALPHABET[self.below(ALPHABET.len())]
+    }
+}
+
+/// A simplified regular expression.
+///
+/// This covers a subset of the syntax supported by `regex-syntax`'s `Ast`,
+/// but unlike an `Ast`, every value of this type can be printed as a pattern
+/// without worrying about precedence and every sub-expression can be replaced
+/// by another one. This makes it suitable for both generation and
+/// minimization.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Node {
+    /// The empty regex.
+    Empty,
+    /// A single character.
+    Literal(char),
+    /// `.`
+    Dot,
+    /// `\d`, `\s` or `\w` or their negations.
+    Perl { kind: ast::ClassPerlKind, negated: bool },
+    /// A bracketed class made up of the given inclusive ranges.
+    Class { negated: bool, ranges: Vec<(char, char)> },
+    /// `^`, `$`, `\A`, `\z`, `\b` or `\B`.
+    Assertion(ast::AssertionKind),
+    /// A repetition operator.
+    Repetition { kind: ast::RepetitionKind, greedy: bool, node: Box<Node> },
+    /// A capturing group when `flags` is `None`, otherwise a non-capturing
+    /// group that sets the given flags.
+    Group { flags: Option<Vec<ast::FlagsItemKind>>, node: Box<Node> },
+    /// A concatenation.
+    Concat(Vec<Node>),
+    /// An alternation.
+    Alternation(Vec<Node>),
+}
+
+impl Node {
+    /// Generate a regular expression from the given source, nested at most
+    /// `depth` levels deep.
+    pub fn generate(src: &mut Source, depth: usize) -> Node {
+        if depth == 0 || src.is_empty() {
+            return Node::generate_leaf(src);
+        }
+        match src.below(10) {
+            0..=3 => Node::generate_leaf(src),
+            4 => {
+                let kind = match src.below(6) {
+                    0 => ast::RepetitionKind::ZeroOrOne,
+                    1 => ast::RepetitionKind::ZeroOrMore,
+                    2 => ast::RepetitionKind::OneOrMore,
+                    3 => ast::RepetitionKind::Range(
+                        ast::RepetitionRange::Exactly(src.below(4) as u32),
+                    ),
+                    4 => ast::RepetitionKind::Range(
+                        ast::RepetitionRange::AtLeast(src.below(3) as u32),
+                    ),
+                    _ => {
+                        let min = src.below(3) as u32;
+                        let max = min + src.below(3) as u32;
+                        ast::RepetitionKind::Range(
+                            ast::RepetitionRange::Bounded(min, max),
+                        )
+                    }
+                };
+                let greedy = src.byte() & 1 == 0;
+                let node = Box::new(Node::generate(src, depth - 1));
+                Node::Repetition { kind: kind, greedy: greedy, node: node }
+            }
+            5 => {
+                let flags = if src.byte() & 1 == 1 {
+                    None
+                } else {
+                    Some(generate_flags(src))
+                };
+                let node = Box::new(Node::generate(src, depth - 1));
+                Node::Group { flags: flags, node: node }
+            }
+            6 | 7 => {
+                let len = 2 + src.below(2);
+                Node::Concat(
+                    (0..len).map(|_| Node::generate(src, depth - 1)).collect(),
+                )
+            }
+            _ => {
+                let len = 2 + src.below(2);
+                Node::Alternation(
+                    (0..len).map(|_| Node::generate(src, depth - 1)).collect(),
+                )
+            }
+        }
+    }
+
+    fn generate_leaf(src: &mut Source) -> Node {
+        match src.below(8) {
+            0..=2 => Node::Literal(src.char()),
+            3 => Node::Dot,
+            4 => {
+                let kind = match src.below(3) {
+                    0 => ast::ClassPerlKind::Digit,
+                    1 => ast::ClassPerlKind::Space,
+                    _ => ast::ClassPerlKind::Word,
+                };
+                Node::Perl { kind: kind, negated: src.byte() & 1 == 1 }
+            }
+            5 => {
+                let negated = src.byte() & 1 == 1;
+                let ranges = (0..1 + src.below(3))
+                    .map(|_| {
+                        let (a, b) = (src.char(), src.char());
+                        if a <= b {
+                            (a, b)
+                        } else {
+                            (b, a)
+                        }
+                    })
+                    .collect();
+                Node::Class { negated: negated, ranges: ranges }
+            }
+            6 => Node::Assertion(match src.below(6) {
+                0 => ast::AssertionKind::StartLine,
+                1 => ast::AssertionKind::EndLine,
+                2 => ast::AssertionKind::StartText,
+                3 => ast::AssertionKind::EndText,
+                4 => ast::AssertionKind::WordBoundary,
+                _ => ast::AssertionKind::NotWordBoundary,
+            }),
+            _ => Node::Empty,
+        }
+    }
+
+    /// Returns the number of non-empty expressions in this regex.
+    pub fn size(&self) -> usize {
+        match *self {
+            Node::Empty => 0,
+            Node::Literal(_)
+            | Node::Dot
+            | Node::Perl { .. }
+            | Node::Class { .. }
+            | Node::Assertion(_) => 1,
+            Node::Repetition { ref node, .. }
+            | Node::Group { ref node, .. } => 1 + node.size(),
+            Node::Concat(ref nodes) | Node::Alternation(ref nodes) => {
+                1 + nodes.iter().map(|n| n.size()).sum::<usize>()
+            }
+        }
+    }
+
+    /// Returns every regex that can be obtained from this one by making a
+    /// single simplification.
+    ///
+    /// A simplification replaces a sub-expression with the empty regex or
+    /// with one of its own sub-expressions, removes an element of a
+    /// concatenation or alternation, or removes a range from a class.
+    pub fn shrink(&self) -> Vec<Node> {
+        let mut shrunk = vec![];
+        if *self != Node::Empty {
+            shrunk.push(Node::Empty);
+        }
+        match *self {
+            Node::Empty
+            | Node::Literal(_)
+            | Node::Dot
+            | Node::Perl { .. }
+            | Node::Assertion(_) => {}
+            Node::Class { negated, ref ranges } => {
+                for i in 0..ranges.len() {
+                    if ranges.len() > 1 {
+                        let mut ranges = ranges.clone();
+                        ranges.remove(i);
+                        shrunk.push(Node::Class {
+                            negated: negated,
+                            ranges: ranges,
+                        });
+                    }
+                }
+            }
+            Node::Repetition { ref kind, greedy, ref node } => {
+                shrunk.push((**node).clone());
+                for node in node.shrink() {
+                    shrunk.push(Node::Repetition {
+                        kind: kind.clone(),
+                        greedy: greedy,
+                        node: Box::new(node),
+                    });
+                }
+            }
+            Node::Group { ref flags, ref node } => {
+                shrunk.push((**node).clone());
+                for node in node.shrink() {
+                    shrunk.push(Node::Group {
+                        flags: flags.clone(),
+                        node: Box::new(node),
+                    });
+                }
+            }
+            Node::Concat(ref nodes) => {
+                shrink_all(nodes, &mut shrunk, Node::Concat)
+            }
+            Node::Alternation(ref nodes) => {
+                shrink_all(nodes, &mut shrunk, Node::Alternation)
+            }
+        }
+        shrunk
+    }
+
+    /// Convert this regex to an `Ast`.
+    ///
+    /// Every span in the `Ast` returned is empty and points to the beginning
+    /// of the pattern, since there is no concrete syntax to point into.
+    pub fn to_ast(&self) -> Ast {
+        self.to_ast_with(&mut 0)
+    }
+
+    fn to_ast_with(&self, next_capture: &mut u32) -> Ast {
+        match *self {
+            Node::Empty => Ast::Empty(span()),
+            Node::Literal(c) => Ast::Literal(literal(c)),
+            Node::Dot => Ast::Dot(span()),
+            Node::Perl { ref kind, negated } => {
+                Ast::Class(ast::Class::Perl(ast::ClassPerl {
+                    span: span(),
+                    kind: kind.clone(),
+                    negated: negated,
+                }))
+            }
+            Node::Class { negated, ref ranges } => {
+                let items = ranges
+                    .iter()
+                    .map(|&(start, end)| {
+                        if start == end {
+                            ast::ClassSetItem::Literal(literal(start))
+                        } else {
+                            ast::ClassSetItem::Range(ast::ClassSetRange {
+                                span: span(),
+                                start: literal(start),
+                                end: literal(end),
+                            })
+                        }
+                    })
+                    .collect();
+                let union = ast::ClassSetUnion { span: span(), items: items };
+                Ast::Class(ast::Class::Bracketed(ast::ClassBracketed {
+                    span: span(),
+                    negated: negated,
+                    kind: ast::ClassSet::union(union),
+                }))
+            }
+            Node::Assertion(ref kind) => Ast::Assertion(ast::Assertion {
+                span: span(),
+                kind: kind.clone(),
+            }),
+            Node::Repetition { ref kind, greedy, ref node } => {
+                Ast::Repetition(ast::Repetition {
+                    span: span(),
+                    op: ast::RepetitionOp { span: span(), kind: kind.clone() },
+                    greedy: greedy,
+                    ast: Box::new(atom(node.to_ast_with(next_capture))),
+                })
+            }
+            Node::Group { flags: None, ref node } => {
+                *next_capture += 1;
+                let kind = ast::GroupKind::CaptureIndex(*next_capture);
+                group(kind, node.to_ast_with(next_capture))
+            }
+            Node::Group { flags: Some(ref items), ref node } => {
+                let items = items
+                    .iter()
+                    .map(|kind| ast::FlagsItem {
+                        span: span(),
+                        kind: kind.clone(),
+                    })
+                    .collect();
+                let flags = ast::Flags { span: span(), items: items };
+                let kind = ast::GroupKind::NonCapturing(flags);
+                group(kind, node.to_ast_with(next_capture))
+            }
+            Node::Concat(ref nodes) => Ast::Concat(ast::Concat {
+                span: span(),
+                asts: nodes
+                    .iter()
+                    .map(|n| match n.to_ast_with(next_capture) {
+                        ast @ Ast::Alternation(_) => non_capturing(ast),
+                        ast => ast,
+                    })
+                    .collect(),
+            }),
+            Node::Alternation(ref nodes) => {
+                Ast::Alternation(ast::Alternation {
+                    span: span(),
+                    asts: nodes
+                        .iter()
+                        .map(|n| n.to_ast_with(next_capture))
+                        .collect(),
+                })
+            }
+        }
+    }
+}
+
+/// Generate the items of a non-capturing group's flags. The items may be
+/// empty, e.g., `(?:a)`.
+fn generate_flags(src: &mut Source) -> Vec<ast::FlagsItemKind> {
+    let on = src.byte();
+    let off = src.byte() & !on;
+    let mut items = vec![];
+    for (i, &flag) in FLAGS.iter().enumerate() {
+        if on & (1 << i) != 0 {
+            items.push(ast::FlagsItemKind::Flag(flag));
+        }
+    }
+    if (0..FLAGS.len()).any(|i| off & (1 << i) != 0) {
+        items.push(ast::FlagsItemKind::Negation);
+        for (i, &flag) in FLAGS.iter().enumerate() {
+            if off & (1 << i) != 0 {
+                items.push(ast::FlagsItemKind::Flag(flag));
+            }
+        }
+    }
+    items
+}
+
+/// Push every simplification of a concatenation or alternation of `nodes`
+/// to `shrunk`, where `make` builds the concatenation or alternation.
+fn shrink_all(
+    nodes: &[Node],
+    shrunk: &mut Vec<Node>,
+    make: fn(Vec<Node>) -> Node,
+) {
+    for (i, node) in nodes.iter().enumerate() {
+        shrunk.push(node.clone());
+        if nodes.len() > 1 {
+            let mut nodes = nodes.to_vec();
+            nodes.remove(i);
+            shrunk.push(make(nodes));
+        }
+        for node in node.shrink() {
+            let mut nodes = nodes.to_vec();
+            nodes[i] = node;
+            shrunk.push(make(nodes));
+        }
+    }
+}
+
+fn span() -> Span {
+    Span::splat(Position::new(0, 1, 1))
+}
+
+fn literal(c: char) -> ast::Literal {
+    let kind = if regex_syntax::is_meta_character(c) {
+        ast::LiteralKind::Punctuation
+    } else {
+        ast::LiteralKind::Verbatim
+    };
+    ast::Literal { span: span(), kind: kind, c: c }
+}
+
+fn group(kind: ast::GroupKind, ast: Ast) -> Ast {
+    Ast::Group(ast::Group { span: span(), kind: kind, ast: Box::new(ast) })
+}
+
+fn non_capturing(ast: Ast) -> Ast {
+    let flags = ast::Flags { span: span(), items: vec![] };
+    group(ast::GroupKind::NonCapturing(flags), ast)
+}
+
+/// Wrap the given expression in a non-capturing group unless a repetition
+/// operator can be applied to it directly.
+fn atom(ast: Ast) -> Ast {
+    match ast {
+        Ast::Literal(_) | Ast::Dot(_) | Ast::Class(_) | Ast::Group(_) => ast,
+        ast => non_capturing(ast),
+    }
+}
@@ -208,6 +208,222 @@ done:
     return passed;
 }
 
+bool test_cache() {
+    bool passed = true;
+    const char *haystack = "abc  xyz";
+    size_t haystack_len = strlen(haystack);
+
+    rure *re = rure_compile_must("(\\w+)\\s+(q)?(\\w+)");
+    rure_cache *cache = rure_cache_new(re);
+    rure_captures *caps = rure_captures_new(re);
+    rure_match match = {0};
+    size_t end = 0;
+
+    if (!rure_is_match_with_cache(re, cache, (const uint8_t *)haystack,
+                                  haystack_len, 0)) {
+        if (DEBUG) {
+            fprintf(stderr, "[test_cache] expected match with is_match\n");
+        }
+        passed = false;
+    }
+    if (!rure_shortest_match_with_cache(re, cache, (const uint8_t *)haystack,
+                                        haystack_len, 0, &end)
+        || end != 6) {
+        if (DEBUG) {
+            fprintf(stderr,
+                    "[test_cache] expected shortest match end 6, "
+                    "but got %zu\n", end);
+        }
+        passed = false;
+    }
+    if (!rure_find_with_cache(re, cache, (const uint8_t *)haystack,
+                              haystack_len, 0, &match)
+        || match.start != 0 || match.end != 8) {
+        if (DEBUG) {
+            fprintf(stderr,
+                    "[test_cache] expected match at (0, 8), but got "
+                    "(%zu, %zu)\n", match.start, match.end);
+        }
+        passed = false;
+    }
+    if (!rure_find_captures_with_cache(re, cache, (const uint8_t *)haystack,
+                                       haystack_len, 0, caps)
+        || !rure_captures_at(caps, 3, &match)
+        || match.start != 5 || match.end != 8) {
+        if (DEBUG) {
+            fprintf(stderr,
+                    "[test_cache] expected capture 3 at (5, 8), but got "
+                    "(%zu, %zu)\n", match.start, match.end);
+        }
+        passed = false;
+    }
+    if (rure_cache_memory_usage(cache) == 0) {
+        if (DEBUG) {
+            fprintf(stderr, "[test_cache] expected non-zero memory usage\n");
+        }
+        passed = false;
+    }
+
+    {
+        size_t slots[8];
+        const size_t expect[8] = {
+            0, 8, 0, 3, RURE_SLOT_NONE, RURE_SLOT_NONE, 5, 8
+        };
+        int i;
+
+        if (!rure_find_slots(re, cache, (const uint8_t *)haystack,
+                             haystack_len, 0, slots, 8)) {
+            if (DEBUG) {
+                fprintf(stderr, "[test_cache] expected match with slots\n");
+            }
+            passed = false;
+            goto done;
+        }
+        for (i = 0; i < 8; i++) {
+            if (slots[i] != expect[i]) {
+                if (DEBUG) {
+                    fprintf(stderr,
+                            "[test_cache] expected slot %d to be %zu, "
+                            "but got %zu\n", i, expect[i], slots[i]);
+                }
+                passed = false;
+            }
+        }
+
+        if (!rure_find_slots(re, cache, (const uint8_t *)haystack,
+                             haystack_len, 1, slots, 2)
+            || slots[0] != 1 || slots[1] != 8) {
+            if (DEBUG) {
+                fprintf(stderr,
+                        "[test_cache] expected slots (1, 8), but got "
+                        "(%zu, %zu)\n", slots[0], slots[1]);
+            }
+            passed = false;
+        }
+        if (rure_find_slots(re, cache, (const uint8_t *)"abc", 3, 0,
+                            slots, 8)) {
+            if (DEBUG) {
+                fprintf(stderr, "[test_cache] expected no match\n");
+            }
+            passed = false;
+        }
+    }
+done:
+    rure_captures_free(caps);
+    rure_cache_free(cache);
+    rure_free(re);
+    return passed;
+}
+
+bool test_replace_one(const char *name, bool all, const char *pattern,
+                      const char *haystack, const char *replacement,
+                      const char *expect) {
+    bool passed = true;
+    rure *re = rure_compile_must(pattern);
+    size_t result_len = 0;
+    uint8_t *result;
+    if (all) {
+        result = rure_replace_all(re, (const uint8_t *)haystack,
+                                  strlen(haystack),
+                                  (const uint8_t *)replacement,
+                                  strlen(replacement), &result_len);
+    } else {
+        result = rure_replace(re, (const uint8_t *)haystack,
+                              strlen(haystack),
+                              (const uint8_t *)replacement,
+                              strlen(replacement), &result_len);
+    }
+    if (result_len != strlen(expect)
+        || memcmp(result, expect, result_len) != 0) {
+        if (DEBUG) {
+            fprintf(stderr,
+                    "[%s] expected \"%s\", but got \"%.*s\"\n",
+                    name, expect, (int)result_len, (const char *)result);
+        }
+        passed = false;
+    }
+    rure_bytes_free(result, result_len);
+    rure_free(re);
+    return passed;
+}
+
+bool test_replace() {
+    bool passed = true;
+    passed = test_replace_one("test_replace", false, "[0-9]+",
+                              "a1 b22 c333", "#", "a# b22 c333")
+             && passed;
+    passed = test_replace_one("test_replace", true, "[0-9]+",
+                              "a1 b22 c333", "#", "a# b# c#")
+             && passed;
+    passed = test_replace_one("test_replace", true,
+                              "(?P<first>\\w+)\\s+(\\w+)",
+                              "deep fried", "$2 ${first} $$", "fried deep $")
+             && passed;
+    passed = test_replace_one("test_replace", true, "z",
+                              "no match", "Z", "no match")
+             && passed;
+    passed = test_replace_one("test_replace", true, "[a-z]+",
+                              "abc", "", "")
+             && passed;
+    return passed;
+}
+
+bool test_split_one(const char *name, rure_iter_split *it,
+                    const char *haystack, const char **expect,
+                    size_t expect_len) {
+    bool passed = true;
+    rure_match piece = {0};
+    size_t i = 0;
+    while (rure_iter_split_next(it, (const uint8_t *)haystack,
+                                strlen(haystack), &piece)) {
+        size_t len = piece.end - piece.start;
+        if (i >= expect_len
+            || len != strlen(expect[i])
+            || memcmp(haystack + piece.start, expect[i], len) != 0) {
+            if (DEBUG) {
+                fprintf(stderr,
+                        "[%s] unexpected piece %zu: \"%.*s\"\n",
+                        name, i, (int)len, haystack + piece.start);
+            }
+            passed = false;
+        }
+        i++;
+    }
+    if (i != expect_len) {
+        if (DEBUG) {
+            fprintf(stderr,
+                    "[%s] expected %zu pieces, but got %zu\n",
+                    name, expect_len, i);
+        }
+        passed = false;
+    }
+    rure_iter_split_free(it);
+    return passed;
+}
+
+bool test_split() {
+    bool passed = true;
+    const char *haystack = "a,b,,c,";
+    rure *re = rure_compile_must(",");
+
+    const char *all[] = { "a", "b", "", "c" };
+    passed = test_split_one("test_split", rure_iter_split_new(re),
+                            haystack, all, 4)
+             && passed;
+
+    const char *two[] = { "a", "b,,c," };
+    passed = test_split_one("test_splitn", rure_iter_splitn_new(re, 2),
+                            haystack, two, 2)
+             && passed;
+
+    passed = test_split_one("test_splitn", rure_iter_splitn_new(re, 0),
+                            haystack, NULL, 0)
+             && passed;
+
+    rure_free(re);
+    return passed;
+}
+
 bool test_iter_capture_name(char *expect, char *given) {
     bool passed = true;
     if (strcmp(expect, given)) {
@@ -495,6 +711,62 @@ done2:
 #undef PAT_COUNT
 }
 
+bool test_regex_set_shortest_match() {
+
+#define PAT_COUNT 2
+
+    bool passed = true;
+    const char *patterns[] = {
+        "a+", "[0-9]{3}"
+    };
+    const size_t patterns_lengths[] = {
+        2, 8
+    };
+
+    rure_set *re = rure_compile_set((const uint8_t **) patterns,
+                                    patterns_lengths,
+                                    PAT_COUNT,
+                                    0,
+                                    NULL,
+                                    NULL);
+    if (re == NULL) {
+        passed = false;
+        goto done;
+    }
+
+    size_t end = 0;
+    const char *haystack = "xx123aaa";
+    if (!rure_set_shortest_match(re, (const uint8_t *)haystack,
+                                 strlen(haystack), 0, &end)) {
+        if (DEBUG) {
+            fprintf(stderr,
+                    "[test_regex_set_shortest_match] expected match, "
+                    "but got no match\n");
+        }
+        passed = false;
+    } else if (end != 5) {
+        if (DEBUG) {
+            fprintf(stderr,
+                    "[test_regex_set_shortest_match] expected match end "
+                    "location 5 but got %zu\n", end);
+        }
+        passed = false;
+    }
+    if (rure_set_shortest_match(re, (const uint8_t *)"xyz", 3, 0, NULL)) {
+        if (DEBUG) {
+            fprintf(stderr,
+                    "[test_regex_set_shortest_match] expected no match, "
+                    "but got a match\n");
+        }
+        passed = false;
+    }
+    rure_set_free(re);
+done:
+    return passed;
+
+#undef PAT_COUNT
+}
+
 bool test_regex_set_options() {
 
     bool passed = true;
@@ -573,6 +845,9 @@ int main() {
     run_test(test_find, "test_find", &passed);
     run_test(test_captures, "test_captures", &passed);
     run_test(test_iter, "test_iter", &passed);
+    run_test(test_cache, "test_cache", &passed);
+    run_test(test_replace, "test_replace", &passed);
+    run_test(test_split, "test_split", &passed);
     run_test(test_iter_capture_names, "test_iter_capture_names", &passed);
     run_test(test_flags, "test_flags", &passed);
     run_test(test_compile_error, "test_compile_error", &passed);
@@ -582,6 +857,8 @@ int main() {
     run_test(test_regex_set_options, "test_regex_set_options", &passed);
     run_test(test_regex_set_match_start, "test_regex_set_match_start",
              &passed);
+    run_test(test_regex_set_shortest_match, "test_regex_set_shortest_match",
+             &passed);
     run_test(test_escape, "test_escape", &passed);
 
     if (!passed) {
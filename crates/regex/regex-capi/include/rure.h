@@ -65,6 +65,12 @@ typedef struct rure_match {
     size_t end;
 } rure_match;
 
+/*
+ * RURE_SLOT_NONE is written to a slot given to rure_find_slots when the
+ * corresponding capturing group did not participate in the match.
+ */
+#define RURE_SLOT_NONE ((size_t)-1)
+
 /*
  * rure_captures represents storage for sub-capture locations of a match.
  *
@@ -82,6 +88,23 @@ typedef struct rure_match {
  */
 typedef struct rure_captures rure_captures;
 
+/*
+ * rure_cache is scratch space used by the regex engines while searching.
+ *
+ * Functions that don't accept an rure_cache find scratch space on their own,
+ * which may require synchronization or allocation when the same rure is used
+ * from many threads. Giving each thread its own rure_cache avoids both.
+ *
+ * An rure_cache value should only be used with the compiled regular
+ * expression that created it. (Using it with any other rure is safe, but
+ * resets the cache.) An rure_cache value may outlive its corresponding rure
+ * and can be freed independently.
+ *
+ * It is not safe to use from multiple threads simultaneously, but it may be
+ * moved between threads.
+ */
+typedef struct rure_cache rure_cache;
+
 /*
  * rure_iter is an iterator over successive non-overlapping matches in a
  * particular haystack.
@@ -93,6 +116,17 @@ typedef struct rure_captures rure_captures;
  */
 typedef struct rure_iter rure_iter;
 
+/*
+ * rure_iter_split is an iterator over the pieces of a particular haystack
+ * that are delimited by successive non-overlapping matches.
+ *
+ * An rure_iter_split value may not outlive its corresponding rure and should
+ * be freed before its corresponding rure is freed.
+ *
+ * It is not safe to use from multiple threads simultaneously.
+ */
+typedef struct rure_iter_split rure_iter_split;
+
 /*
  * rure_iter_capture_names is an iterator over the list of capture group names
  * in this particular rure.
@@ -398,6 +432,185 @@ bool rure_captures_at(rure_captures *captures, size_t i, rure_match *match);
  */
 size_t rure_captures_len(rure_captures *captures);
 
+/*
+ * rure_cache_new allocates scratch space for searching with re.
+ *
+ * An rure_cache value may be reused on subsequent calls to any of the
+ * *_with_cache functions and to rure_find_slots, so long as they are given
+ * the re given here.
+ *
+ * It is not safe to use an rure_cache value from multiple threads
+ * simultaneously.
+ */
+rure_cache *rure_cache_new(rure *re);
+
+/*
+ * rure_cache_free frees the given cache.
+ *
+ * This must be called at most once.
+ */
+void rure_cache_free(rure_cache *cache);
+
+/*
+ * rure_cache_memory_usage returns the approximate number of bytes of heap
+ * memory used by the given cache.
+ *
+ * Scratch space grows as needed while searching. The share of it used by the
+ * DFA is bounded by rure_options_dfa_size_limit.
+ */
+size_t rure_cache_memory_usage(rure_cache *cache);
+
+/*
+ * rure_is_match_with_cache is like rure_is_match, except it uses the given
+ * cache for scratch space.
+ */
+bool rure_is_match_with_cache(rure *re, rure_cache *cache,
+                              const uint8_t *haystack, size_t length,
+                              size_t start);
+
+/*
+ * rure_shortest_match_with_cache is like rure_shortest_match, except it uses
+ * the given cache for scratch space.
+ */
+bool rure_shortest_match_with_cache(rure *re, rure_cache *cache,
+                                    const uint8_t *haystack, size_t length,
+                                    size_t start, size_t *end);
+
+/*
+ * rure_find_with_cache is like rure_find, except it uses the given cache for
+ * scratch space.
+ */
+bool rure_find_with_cache(rure *re, rure_cache *cache,
+                          const uint8_t *haystack, size_t length,
+                          size_t start, rure_match *match);
+
+/*
+ * rure_find_captures_with_cache is like rure_find_captures, except it uses
+ * the given cache for scratch space.
+ */
+bool rure_find_captures_with_cache(rure *re, rure_cache *cache,
+                                   const uint8_t *haystack, size_t length,
+                                   size_t start, rure_captures *captures);
+
+/*
+ * rure_find_slots returns true if and only if re matches anywhere in
+ * haystack. If a match is found, then its capture locations are written to
+ * the caller provided slots, using the given cache for scratch space. Aside
+ * from growing the scratch space in the cache, no memory is allocated.
+ *
+ * slots_length is the number of entries in slots. Each capturing group uses
+ * two consecutive entries: the start and end location (in bytes) of the
+ * group. So slots[0] and slots[1] correspond to the full match, slots[2] and
+ * slots[3] to the first explicit capturing group, and so on. Groups that
+ * don't fit in slots are not reported. Entries for groups that did not
+ * participate in the match (or that don't exist) are set to RURE_SLOT_NONE.
+ *
+ * If slots_length is at most 2, then capture locations are not computed at
+ * all, which makes this as fast as rure_find.
+ *
+ * haystack may contain arbitrary bytes, but ASCII compatible text is more
+ * useful. UTF-8 is even more useful. Other text encodings aren't supported.
+ * length should be the number of bytes in haystack.
+ *
+ * start is the position at which to start searching. Note that setting the
+ * start position is distinct from incrementing the pointer, since the regex
+ * engine may look at bytes before the start position to determine match
+ * information. For example, if the start position is greater than 0, then the
+ * \A ("begin text") anchor can never match.
+ */
+bool rure_find_slots(rure *re, rure_cache *cache,
+                     const uint8_t *haystack, size_t length, size_t start,
+                     size_t *slots, size_t slots_length);
+
+/*
+ * rure_replace replaces the leftmost-first match of re in haystack with
+ * replacement and returns the result as a new sequence of bytes. The number
+ * of bytes in the result is written to result_length. If there is no match,
+ * then the result is a copy of haystack.
+ *
+ * haystack may contain arbitrary bytes, but ASCII compatible text is more
+ * useful. UTF-8 is even more useful. Other text encodings aren't supported.
+ * length should be the number of bytes in haystack.
+ *
+ * All instances of $name in replacement are replaced with the corresponding
+ * capture group name. name may be an integer corresponding to the index of
+ * the capture group (counted by order of opening parenthesis where 0 is the
+ * entire match) or it can be a name (consisting of letters, digits or
+ * underscores) corresponding to a named capture group. If name isn't a valid
+ * capture group (whether the name doesn't exist or isn't a valid index), then
+ * it is replaced with the empty string. The longest possible name is used,
+ * e.g., $1a looks up the capture group named 1a and not the capture group at
+ * index 1. To exert more precise control over the name, use braces, e.g.,
+ * ${1}a. To write a literal $, use $$.
+ *
+ * The bytes returned must not be freed directly. Instead, they should be
+ * freed by calling rure_bytes_free with the same result_length.
+ */
+uint8_t *rure_replace(rure *re, const uint8_t *haystack, size_t length,
+                      const uint8_t *replacement, size_t replacement_length,
+                      size_t *result_length);
+
+/*
+ * rure_replace_all is like rure_replace, except it replaces all
+ * non-overlapping matches of re in haystack.
+ *
+ * The bytes returned must not be freed directly. Instead, they should be
+ * freed by calling rure_bytes_free with the same result_length.
+ */
+uint8_t *rure_replace_all(rure *re, const uint8_t *haystack, size_t length,
+                          const uint8_t *replacement,
+                          size_t replacement_length,
+                          size_t *result_length);
+
+/*
+ * rure_bytes_free frees the bytes given. length must be the number of bytes
+ * reported when they were returned.
+ *
+ * This must be called at most once per sequence of bytes.
+ */
+void rure_bytes_free(uint8_t *bytes, size_t length);
+
+/*
+ * rure_iter_split_new creates a new split iterator.
+ *
+ * The iterator reports each piece of a haystack that is delimited by a match
+ * of re. When calling iterator functions, the same haystack and length must
+ * be supplied to all invocations. (Strict pointer equality is, however, not
+ * required.)
+ */
+rure_iter_split *rure_iter_split_new(rure *re);
+
+/*
+ * rure_iter_splitn_new is like rure_iter_split_new, except the iterator
+ * reports at most limit pieces. The last piece reported contains the
+ * remainder of the haystack.
+ */
+rure_iter_split *rure_iter_splitn_new(rure *re, size_t limit);
+
+/*
+ * rure_iter_split_free frees the iterator given.
+ *
+ * It must be called at most once.
+ */
+void rure_iter_split_free(rure_iter_split *it);
+
+/*
+ * rure_iter_split_next advances the iterator and returns true if and only if
+ * another piece exists. If one exists, then the piece pointer is set with the
+ * start and end location of the piece, in bytes.
+ *
+ * If there are no more pieces, then subsequent calls will return false
+ * indefinitely.
+ *
+ * haystack may contain arbitrary bytes, but ASCII compatible text is more
+ * useful. UTF-8 is even more useful. Other text encodings aren't supported.
+ * length should be the number of bytes in haystack. The given haystack must
+ * be logically equivalent to all other haystacks given to this iterator.
+ */
+bool rure_iter_split_next(rure_iter_split *it,
+                          const uint8_t *haystack, size_t length,
+                          rure_match *piece);
+
 /*
  * rure_options_new allocates space for options.
  *
@@ -520,6 +733,26 @@ bool rure_set_is_match(rure_set *re, const uint8_t *haystack, size_t length,
 bool rure_set_matches(rure_set *re, const uint8_t *haystack, size_t length,
                       size_t start, bool *matches);
 
+/*
+ * rure_set_shortest_match returns true if and only if any regex within the
+ * set matches anywhere in haystack. If a match is found, then its end
+ * location is stored in the pointer given. The end location is the place at
+ * which the regex engine determined that a match exists, but may occur before
+ * the end of the proper leftmost-first match of any regex in the set.
+ *
+ * haystack may contain arbitrary bytes, but ASCII compatible text is more
+ * useful. UTF-8 is even more useful. Other text encodings aren't supported.
+ * length should be the number of bytes in haystack.
+ *
+ * start is the position at which to start searching. Note that setting the
+ * start position is distinct from incrementing the pointer, since the regex
+ * engine may look at bytes before the start position to determine match
+ * information. For example, if the start position is greater than 0, then the
+ * \A ("begin text") anchor can never match.
+ */
+bool rure_set_shortest_match(rure_set *re, const uint8_t *haystack,
+                             size_t length, size_t start, size_t *end);
+
 /*
  * rure_set_len returns the number of patterns rure_set was compiled with.
  */
@@ -17,6 +17,7 @@ const RURE_FLAG_SWAP_GREED: u32 = 1 << 3;
 const RURE_FLAG_SPACE: u32 = 1 << 4;
 const RURE_FLAG_UNICODE: u32 = 1 << 5;
 const RURE_DEFAULT_FLAGS: u32 = RURE_FLAG_UNICODE;
+const RURE_SLOT_NONE: size_t = !0;
 
 pub struct Regex {
     re: bytes::Regex,
@@ -43,12 +44,23 @@ pub struct rure_match {
 
 pub struct Captures(bytes::Locations);
 
+pub struct Cache {
+    cache: regex::Cache,
+    locs: bytes::Locations,
+}
+
 pub struct Iter {
     re: *const Regex,
     last_end: usize,
     last_match: Option<usize>,
 }
 
+pub struct IterSplit {
+    it: Iter,
+    last: usize,
+    limit: Option<usize>,
+}
+
 pub struct IterCaptureNames {
     capture_names: bytes::CaptureNames<'static>,
     name_ptrs: Vec<*mut c_char>,
@@ -226,6 +238,160 @@ ffi_fn! {
     }
 }
 
+ffi_fn! {
+    fn rure_cache_new(re: *const Regex) -> *mut Cache {
+        let re = unsafe { &*re };
+        Box::into_raw(Box::new(Cache {
+            cache: re.create_cache(),
+            locs: re.locations(),
+        }))
+    }
+}
+
+ffi_fn! {
+    fn rure_cache_free(cache: *mut Cache) {
+        unsafe { Box::from_raw(cache); }
+    }
+}
+
+ffi_fn! {
+    fn rure_cache_memory_usage(cache: *const Cache) -> size_t {
+        unsafe { (*cache).cache.memory_usage() }
+    }
+}
+
+ffi_fn! {
+    fn rure_is_match_with_cache(
+        re: *const Regex,
+        cache: *mut Cache,
+        haystack: *const u8,
+        len: size_t,
+        start: size_t,
+    ) -> bool {
+        let re = unsafe { &*re };
+        let cache = unsafe { &mut (*cache).cache };
+        let haystack = unsafe { slice::from_raw_parts(haystack, len) };
+        re.shortest_match_at_with_cache(cache, haystack, start).is_some()
+    }
+}
+
+ffi_fn! {
+    fn rure_shortest_match_with_cache(
+        re: *const Regex,
+        cache: *mut Cache,
+        haystack: *const u8,
+        len: size_t,
+        start: size_t,
+        end: *mut usize,
+    ) -> bool {
+        let re = unsafe { &*re };
+        let cache = unsafe { &mut (*cache).cache };
+        let haystack = unsafe { slice::from_raw_parts(haystack, len) };
+        match re.shortest_match_at_with_cache(cache, haystack, start) {
+            None => false,
+            Some(i) => {
+                if !end.is_null() {
+                    unsafe {
+                        *end = i;
+                    }
+                }
+                true
+            }
+        }
+    }
+}
+
+ffi_fn! {
+    fn rure_find_with_cache(
+        re: *const Regex,
+        cache: *mut Cache,
+        haystack: *const u8,
+        len: size_t,
+        start: size_t,
+        match_info: *mut rure_match,
+    ) -> bool {
+        let re = unsafe { &*re };
+        let cache = unsafe { &mut (*cache).cache };
+        let haystack = unsafe { slice::from_raw_parts(haystack, len) };
+        re.find_at_with_cache(cache, haystack, start).map(|m| unsafe {
+            if !match_info.is_null() {
+                (*match_info).start = m.start();
+                (*match_info).end = m.end();
+            }
+        }).is_some()
+    }
+}
+
+ffi_fn! {
+    fn rure_find_captures_with_cache(
+        re: *const Regex,
+        cache: *mut Cache,
+        haystack: *const u8,
+        len: size_t,
+        start: size_t,
+        captures: *mut Captures,
+    ) -> bool {
+        let re = unsafe { &*re };
+        let cache = unsafe { &mut (*cache).cache };
+        let haystack = unsafe { slice::from_raw_parts(haystack, len) };
+        let slots = unsafe { &mut (*captures).0 };
+        re.captures_read_at_with_cache(cache, slots, haystack, start).is_some()
+    }
+}
+
+ffi_fn! {
+    fn rure_find_slots(
+        re: *const Regex,
+        cache: *mut Cache,
+        haystack: *const u8,
+        len: size_t,
+        start: size_t,
+        slots: *mut size_t,
+        slots_len: size_t,
+    ) -> bool {
+        let re = unsafe { &*re };
+        let cache = unsafe { &mut *cache };
+        let haystack = unsafe { slice::from_raw_parts(haystack, len) };
+        let slots = unsafe { slice::from_raw_parts_mut(slots, slots_len) };
+        for slot in slots.iter_mut() {
+            *slot = RURE_SLOT_NONE;
+        }
+        // If only the overall match is wanted, then skip the (potentially
+        // much slower) search for capture locations.
+        if slots.len() <= 2 {
+            let m = match re.find_at_with_cache(
+                &mut cache.cache, haystack, start)
+            {
+                None => return false,
+                Some(m) => m,
+            };
+            for (slot, pos) in slots.iter_mut().zip(&[m.start(), m.end()]) {
+                *slot = *pos;
+            }
+            return true;
+        }
+        // The capture locations are only reallocated if this cache was
+        // created for a different regex.
+        if cache.locs.len() != re.captures_len() {
+            cache.locs = re.locations();
+        }
+        if re.captures_read_at_with_cache(
+            &mut cache.cache, &mut cache.locs, haystack, start).is_none()
+        {
+            return false;
+        }
+        for (i, pair) in slots.chunks_mut(2).enumerate() {
+            if let Some((s, e)) = cache.locs.pos(i) {
+                pair[0] = s;
+                if pair.len() > 1 {
+                    pair[1] = e;
+                }
+            }
+        }
+        true
+    }
+}
+
 ffi_fn! {
     fn rure_capture_name_index(
         re: *const Regex,
@@ -439,6 +605,142 @@ ffi_fn! {
     }
 }
 
+ffi_fn! {
+    fn rure_replace(
+        re: *const Regex,
+        haystack: *const u8,
+        len: size_t,
+        replacement: *const u8,
+        replacement_len: size_t,
+        result_len: *mut size_t,
+    ) -> *mut u8 {
+        rure_replacen(
+            re, haystack, len, 1, replacement, replacement_len, result_len)
+    }
+}
+
+ffi_fn! {
+    fn rure_replace_all(
+        re: *const Regex,
+        haystack: *const u8,
+        len: size_t,
+        replacement: *const u8,
+        replacement_len: size_t,
+        result_len: *mut size_t,
+    ) -> *mut u8 {
+        rure_replacen(
+            re, haystack, len, 0, replacement, replacement_len, result_len)
+    }
+}
+
+/// A helper function that implements `rure_replace` and `rure_replace_all`.
+///
+/// A `limit` of `0` replaces every match. The bytes returned must be freed
+/// with `rure_bytes_free`.
+fn rure_replacen(
+    re: *const Regex,
+    haystack: *const u8,
+    len: size_t,
+    limit: usize,
+    replacement: *const u8,
+    replacement_len: size_t,
+    result_len: *mut size_t,
+) -> *mut u8 {
+    let re = unsafe { &*re };
+    let haystack = unsafe { slice::from_raw_parts(haystack, len) };
+    let replacement =
+        unsafe { slice::from_raw_parts(replacement, replacement_len) };
+    let result = re.replacen(haystack, limit, replacement);
+    let result = result.into_owned().into_boxed_slice();
+    if !result_len.is_null() {
+        unsafe {
+            *result_len = result.len();
+        }
+    }
+    Box::into_raw(result) as *mut u8
+}
+
+ffi_fn! {
+    fn rure_bytes_free(bytes: *mut u8, len: size_t) {
+        unsafe {
+            Box::from_raw(slice::from_raw_parts_mut(bytes, len) as *mut [u8]);
+        }
+    }
+}
+
+ffi_fn! {
+    fn rure_iter_split_new(re: *const Regex) -> *mut IterSplit {
+        Box::into_raw(Box::new(IterSplit {
+            it: Iter { re: re, last_end: 0, last_match: None },
+            last: 0,
+            limit: None,
+        }))
+    }
+}
+
+ffi_fn! {
+    fn rure_iter_splitn_new(
+        re: *const Regex,
+        limit: size_t,
+    ) -> *mut IterSplit {
+        Box::into_raw(Box::new(IterSplit {
+            it: Iter { re: re, last_end: 0, last_match: None },
+            last: 0,
+            limit: Some(limit),
+        }))
+    }
+}
+
+ffi_fn! {
+    fn rure_iter_split_free(it: *mut IterSplit) {
+        unsafe { Box::from_raw(it); }
+    }
+}
+
+ffi_fn! {
+    fn rure_iter_split_next(
+        it: *mut IterSplit,
+        haystack: *const u8,
+        len: size_t,
+        piece: *mut rure_match,
+    ) -> bool {
+        let it = unsafe { &mut *it };
+        let (s, e) = match it.limit {
+            Some(0) => return false,
+            Some(1) => {
+                // The last piece is the rest of the haystack, regardless of
+                // whether it contains any more matches.
+                it.limit = Some(0);
+                (it.last, len)
+            }
+            _ => {
+                if let Some(ref mut limit) = it.limit {
+                    *limit -= 1;
+                }
+                let mut m = rure_match { start: 0, end: 0 };
+                if rure_iter_next(&mut it.it, haystack, len, &mut m) {
+                    let piece = (it.last, m.start);
+                    it.last = m.end;
+                    piece
+                } else if it.last >= len {
+                    return false;
+                } else {
+                    let piece = (it.last, len);
+                    it.last = len;
+                    piece
+                }
+            }
+        };
+        if !piece.is_null() {
+            unsafe {
+                (*piece).start = s;
+                (*piece).end = e;
+            }
+        }
+        true
+    }
+}
+
 ffi_fn! {
     fn rure_options_new() -> *mut Options {
         Box::into_raw(Box::new(Options::default()))
@@ -566,6 +868,30 @@ ffi_fn! {
     }
 }
 
+ffi_fn! {
+    fn rure_set_shortest_match(
+        re: *const RegexSet,
+        haystack: *const u8,
+        len: size_t,
+        start: size_t,
+        end: *mut usize,
+    ) -> bool {
+        let re = unsafe { &*re };
+        let haystack = unsafe { slice::from_raw_parts(haystack, len) };
+        match re.shortest_match_at(haystack, start) {
+            None => false,
+            Some(i) => {
+                if !end.is_null() {
+                    unsafe {
+                        *end = i;
+                    }
+                }
+                true
+            }
+        }
+    }
+}
+
 ffi_fn! {
     fn rure_set_len(re: *const RegexSet) -> size_t {
         unsafe { (*re).len() }
@@ -0,0 +1,596 @@
+use std::collections::HashMap;
+
+use regex::internal::{EmptyLook, Inst, Program};
+use serde_json;
+
+use escape_byte;
+use program::escape_dot;
+use Result;
+
+/// A fully materialized DFA built from a byte based program.
+///
+/// This is built with the classical powerset construction, so it only
+/// answers whether a match exists. (The lazy DFA in the regex crate does
+/// the same thing, but only ever builds the states it needs.)
+///
+/// Zero-width assertions are resolved the same way the lazy DFA resolves
+/// them. An assertion that can't be decided when a state is entered stays
+/// in the state, and is decided when the next byte (or the end of the
+/// haystack) is seen. For ASCII word boundaries, every state also records
+/// whether the byte that led to it was a word byte. Since an assertion may
+/// only be satisfied right before a byte, such a match is reported one byte
+/// late, by the state that byte leads to. Unicode word boundaries are not
+/// supported.
+#[derive(Serialize)]
+pub struct Dfa {
+    /// The index of the start state. This is always `0`.
+    start: usize,
+    /// All states, indexed by their identifier.
+    states: Vec<State>,
+}
+
+/// A single DFA state.
+#[derive(Serialize)]
+struct State {
+    /// The sorted NFA instructions that make up this state.
+    insts: Vec<usize>,
+    /// Whether the byte that led to this state is an ASCII word byte. This
+    /// is always false for programs without word boundaries.
+    is_word: bool,
+    /// Whether this state contains a `Match` instruction, or an assertion
+    /// satisfied before the byte that led to this state completed a match.
+    is_match: bool,
+    /// Transitions out of this state. Bytes without a transition lead to
+    /// the dead state, which is never materialized.
+    transitions: Vec<Transition>,
+    /// The state entered at the end of the haystack, if a match can only be
+    /// found there (e.g., because of a `$`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    eof: Option<usize>,
+}
+
+/// A transition on an inclusive range of bytes.
+#[derive(Serialize)]
+struct Transition {
+    start: u8,
+    end: u8,
+    next: usize,
+}
+
+/// The key identifying a DFA state: its instructions, whether the previous
+/// byte was a word byte and whether a match was found before that byte.
+type StateKey = (Vec<usize>, bool, bool);
+
+/// The zero-width assertions that hold at a position in the haystack.
+#[derive(Clone, Copy, Debug, Default)]
+struct EmptyFlags {
+    start: bool,
+    end: bool,
+    start_line: bool,
+    end_line: bool,
+    word_boundary: bool,
+    not_word_boundary: bool,
+}
+
+/// The state needed while materializing a DFA.
+struct Builder<'a> {
+    prog: &'a Program,
+    state_limit: usize,
+    /// Whether states need to track if the previous byte was a word byte.
+    track_words: bool,
+    dfa: Dfa,
+    ids: HashMap<StateKey, usize>,
+    /// States whose transitions have yet to be computed.
+    todo: Vec<usize>,
+}
+
+impl Dfa {
+    /// Materialize the DFA for the given program.
+    ///
+    /// An error is returned if the program isn't byte based, contains
+    /// Unicode word boundaries or if more than `state_limit` states would
+    /// be created.
+    pub fn new(prog: &Program, state_limit: usize) -> Result<Dfa> {
+        if !prog.uses_bytes() {
+            return Err("DFAs can only be built from byte based programs \
+                        (use --bytes or --dfa)"
+                .into());
+        }
+        let mut track_words = false;
+        for inst in prog.iter() {
+            let look = match *inst {
+                Inst::EmptyLook(ref inst) => inst.look,
+                _ => continue,
+            };
+            match look {
+                EmptyLook::WordBoundaryAscii
+                | EmptyLook::NotWordBoundaryAscii => track_words = true,
+                EmptyLook::WordBoundary
+                | EmptyLook::NotWordBoundary
+                | EmptyLook::WordBoundarySegment
+                | EmptyLook::NotWordBoundarySegment => {
+                    return Err("DFAs cannot be materialized for programs \
+                                with Unicode word boundaries (use (?-u:\\b))"
+                        .into());
+                }
+                _ => {}
+            }
+        }
+        let mut builder = Builder {
+            prog: prog,
+            state_limit: state_limit,
+            track_words: track_words,
+            dfa: Dfa { start: 0, states: vec![] },
+            ids: HashMap::new(),
+            todo: vec![],
+        };
+        let flags = EmptyFlags {
+            start: true,
+            start_line: true,
+            ..EmptyFlags::default()
+        };
+        let start = closure(prog, &[prog.start], flags);
+        builder.add((start, false, false))?;
+        while let Some(id) = builder.todo.pop() {
+            builder.compute_transitions(id)?;
+        }
+        Ok(builder.dfa)
+    }
+
+    /// Render this DFA as human readable text.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for (id, state) in self.states.iter().enumerate() {
+            let mut marks = vec![];
+            if id == self.start {
+                marks.push("start");
+            }
+            if state.is_match {
+                marks.push("match");
+            }
+            if state.is_word {
+                marks.push("word");
+            }
+            text.push_str(&format!("S{:04} {:?}", id, state.insts));
+            if !marks.is_empty() {
+                text.push_str(&format!(" ({})", marks.join(", ")));
+            }
+            text.push('\n');
+            for t in &state.transitions {
+                text.push_str(&format!(
+                    "  {} => S{:04}\n",
+                    range_label(t.start, t.end),
+                    t.next
+                ));
+            }
+            if let Some(next) = state.eof {
+                text.push_str(&format!("  EOF => S{:04}\n", next));
+            }
+        }
+        text
+    }
+
+    /// Render this DFA as a Graphviz DOT digraph.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        dot.push_str("digraph dfa {\n");
+        dot.push_str("    rankdir=LR;\n");
+        dot.push_str("    node [shape=circle];\n");
+        dot.push_str("    start [shape=point];\n");
+        dot.push_str(&format!("    start -> s{};\n", self.start));
+        for (id, state) in self.states.iter().enumerate() {
+            if state.is_match {
+                dot.push_str(&format!(
+                    "    s{} [label=\"S{}\", shape=doublecircle];\n",
+                    id, id
+                ));
+            } else {
+                dot.push_str(&format!("    s{} [label=\"S{}\"];\n", id, id));
+            }
+            // Group all ranges leading to the same state on one edge.
+            let mut labels: Vec<(usize, Vec<String>)> = vec![];
+            for t in &state.transitions {
+                let label = range_label(t.start, t.end);
+                match labels.iter_mut().find(|&&mut (next, _)| next == t.next)
+                {
+                    Some(&mut (_, ref mut ls)) => ls.push(label),
+                    None => labels.push((t.next, vec![label])),
+                }
+            }
+            for (next, ls) in labels {
+                dot.push_str(&format!(
+                    "    s{} -> s{} [label=\"{}\"];\n",
+                    id,
+                    next,
+                    escape_dot(&ls.join(", "))
+                ));
+            }
+            if let Some(next) = state.eof {
+                dot.push_str(&format!(
+                    "    s{} -> s{} [label=\"EOF\", style=dashed];\n",
+                    id, next
+                ));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Render this DFA as a pretty printed JSON document.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+impl<'a> Builder<'a> {
+    /// Return the identifier of the state with the given key, adding it if
+    /// it doesn't exist yet.
+    fn add(&mut self, key: StateKey) -> Result<usize> {
+        if let Some(&id) = self.ids.get(&key) {
+            return Ok(id);
+        }
+        if self.dfa.states.len() >= self.state_limit {
+            return Err(
+                format!("DFA exceeds state limit of {}", self.state_limit)
+                    .into(),
+            );
+        }
+        let id = self.dfa.states.len();
+        let (ref insts, is_word, matched) = key;
+        let is_match =
+            matched || insts.iter().any(|&pc| self.prog[pc].is_match());
+        self.dfa.states.push(State {
+            insts: insts.clone(),
+            is_word: is_word,
+            is_match: is_match,
+            transitions: vec![],
+            eof: None,
+        });
+        self.ids.insert(key, id);
+        self.todo.push(id);
+        Ok(id)
+    }
+
+    /// Compute the transitions out of the given state, adding every state
+    /// they lead to.
+    fn compute_transitions(&mut self, id: usize) -> Result<()> {
+        let prog = self.prog;
+        let insts = self.dfa.states[id].insts.clone();
+        let is_word = self.dfa.states[id].is_word;
+        let has_empty = insts.iter().any(|&pc| match prog[pc] {
+            Inst::EmptyLook(_) => true,
+            _ => false,
+        });
+        let mut next_by_byte: Vec<Option<usize>> = vec![None; 256];
+        for (b, members) in representatives(&prog.byte_classes) {
+            // Resolve the assertions that depend on this byte first, just
+            // like the lazy DFA.
+            let cur = if has_empty {
+                closure(prog, &insts, EmptyFlags::before(Some(b), is_word))
+            } else {
+                insts.clone()
+            };
+            let matched = new_match(prog, &insts, &cur);
+            let mut gotos = vec![];
+            for &pc in &cur {
+                if let Inst::Bytes(ref inst) = prog[pc] {
+                    if inst.matches(b) {
+                        gotos.push(inst.goto);
+                    }
+                }
+            }
+            if gotos.is_empty() && !matched {
+                continue;
+            }
+            let next_insts = closure(prog, &gotos, EmptyFlags::after(b));
+            let next_word = self.track_words && is_word_byte(b);
+            let next = self.add((next_insts, next_word, matched))?;
+            for m in members {
+                next_by_byte[m as usize] = Some(next);
+            }
+        }
+        self.dfa.states[id].transitions = ranges(&next_by_byte);
+        if has_empty {
+            let cur = closure(prog, &insts, EmptyFlags::before(None, is_word));
+            if new_match(prog, &insts, &cur) {
+                let next = self.add((vec![], false, true))?;
+                self.dfa.states[id].eof = Some(next);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl EmptyFlags {
+    /// The assertions that hold immediately before the given byte, where
+    /// `None` is the end of the haystack.
+    ///
+    /// Assertions about the start of a line or the haystack are decided when
+    /// a state is entered, so they never hold here.
+    fn before(b: Option<u8>, is_word_last: bool) -> EmptyFlags {
+        let is_word = b.map_or(false, is_word_byte);
+        EmptyFlags {
+            end: b.is_none(),
+            end_line: b.map_or(true, |b| b == b'\n'),
+            word_boundary: is_word_last != is_word,
+            not_word_boundary: is_word_last == is_word,
+            ..EmptyFlags::default()
+        }
+    }
+
+    /// The assertions that are known to hold immediately after the given
+    /// byte.
+    fn after(b: u8) -> EmptyFlags {
+        EmptyFlags { start_line: b == b'\n', ..EmptyFlags::default() }
+    }
+
+    /// Whether the given assertion holds.
+    fn is_satisfied(&self, look: EmptyLook) -> bool {
+        match look {
+            EmptyLook::StartLine => self.start_line,
+            EmptyLook::EndLine => self.end_line,
+            EmptyLook::StartText => self.start,
+            EmptyLook::EndText => self.end,
+            EmptyLook::WordBoundaryAscii => self.word_boundary,
+            EmptyLook::NotWordBoundaryAscii => self.not_word_boundary,
+            EmptyLook::WordBoundary
+            | EmptyLook::NotWordBoundary
+            | EmptyLook::WordBoundarySegment
+            | EmptyLook::NotWordBoundarySegment => false,
+        }
+    }
+}
+
+/// Compute the sorted set of instructions reachable from the given
+/// instructions, following only the assertions that hold according to
+/// `flags`.
+///
+/// The result contains every instruction that consumes input, every `Match`
+/// instruction and every assertion that doesn't hold (yet).
+fn closure(prog: &Program, pcs: &[usize], flags: EmptyFlags) -> Vec<usize> {
+    let mut seen = vec![false; prog.len()];
+    let mut set = vec![];
+    let mut stack: Vec<usize> = pcs.iter().rev().cloned().collect();
+    while let Some(pc) = stack.pop() {
+        if seen[pc] {
+            continue;
+        }
+        seen[pc] = true;
+        match prog[pc] {
+            Inst::Save(ref inst) => stack.push(inst.goto),
+            Inst::Split(ref inst) => {
+                stack.push(inst.goto2);
+                stack.push(inst.goto1);
+            }
+            Inst::EmptyLook(ref inst) => {
+                if flags.is_satisfied(inst.look) {
+                    stack.push(inst.goto);
+                } else {
+                    set.push(pc);
+                }
+            }
+            Inst::Match(_)
+            | Inst::Char(_)
+            | Inst::Ranges(_)
+            | Inst::Bytes(_) => set.push(pc),
+        }
+    }
+    set.sort();
+    set
+}
+
+/// Whether resolving the assertions in `insts` reached a `Match`
+/// instruction that isn't in `insts` itself.
+fn new_match(prog: &Program, insts: &[usize], resolved: &[usize]) -> bool {
+    resolved
+        .iter()
+        .any(|&pc| prog[pc].is_match() && insts.binary_search(&pc).is_err())
+}
+
+fn is_word_byte(b: u8) -> bool {
+    b == b'_' || (b as char).is_ascii_alphanumeric()
+}
+
+/// Group all bytes by their equivalence class, returning one representative
+/// byte for each class along with every byte in that class.
+fn representatives(byte_classes: &[u8]) -> Vec<(u8, Vec<u8>)> {
+    let mut classes: Vec<(u8, Vec<u8>)> = vec![];
+    for b in 0..256usize {
+        let class = byte_classes[b] as usize;
+        if class >= classes.len() {
+            classes.push((b as u8, vec![]));
+        }
+        classes[class].1.push(b as u8);
+    }
+    classes
+}
+
+/// Collapse a byte indexed transition table into inclusive byte ranges.
+fn ranges(next_by_byte: &[Option<usize>]) -> Vec<Transition> {
+    let mut transitions: Vec<Transition> = vec![];
+    for (b, next) in next_by_byte.iter().enumerate() {
+        let next = match *next {
+            None => continue,
+            Some(next) => next,
+        };
+        if let Some(last) = transitions.last_mut() {
+            if last.next == next && last.end as usize + 1 == b {
+                last.end = b as u8;
+                continue;
+            }
+        }
+        transitions.push(Transition {
+            start: b as u8,
+            end: b as u8,
+            next: next,
+        });
+    }
+    transitions
+}
+
+fn range_label(start: u8, end: u8) -> String {
+    if start == end {
+        escape_byte(start)
+    } else {
+        format!("{}-{}", escape_byte(start), escape_byte(end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::internal::Compiler;
+
+    use super::Dfa;
+    use parse;
+
+    fn dfa(pattern: &str) -> Dfa {
+        let prog = Compiler::new()
+            .only_utf8(false)
+            .dfa(true)
+            .compile(&[parse(pattern).unwrap()])
+            .unwrap();
+        Dfa::new(&prog, 100).unwrap()
+    }
+
+    #[test]
+    fn text_end_text() {
+        let expected = r"S0000 [1, 2] (start)
+  \x00-` => S0000
+  a => S0001
+  b-\xff => S0000
+S0001 [1, 2, 3]
+  \x00-` => S0000
+  a => S0001
+  b-\xff => S0000
+  EOF => S0002
+S0002 [] (match)
+";
+        assert_eq!(expected, dfa(r"(?-u)a$").to_text());
+    }
+
+    #[test]
+    fn text_word_boundary() {
+        let expected = r"S0000 [1, 2] (start)
+  \x00-/ => S0000
+  0-9 => S0001
+  :-@ => S0000
+  A-Z => S0001
+  [-^ => S0000
+  _ => S0001
+  ` => S0000
+  a => S0002
+  b-z => S0001
+  {-\xff => S0000
+S0001 [1, 2] (word)
+  \x00-/ => S0000
+  0-9 => S0001
+  :-@ => S0000
+  A-Z => S0001
+  [-^ => S0000
+  _ => S0001
+  ` => S0000
+  a-z => S0001
+  {-\xff => S0000
+S0002 [1, 2, 4] (match, word)
+  \x00-/ => S0000
+  0-9 => S0001
+  :-@ => S0000
+  A-Z => S0001
+  [-^ => S0000
+  _ => S0001
+  ` => S0000
+  a-z => S0001
+  {-\xff => S0000
+";
+        assert_eq!(expected, dfa(r"(?-u:\b)a").to_text());
+    }
+
+    #[test]
+    fn dot_end_text() {
+        let expected = r#"digraph dfa {
+    rankdir=LR;
+    node [shape=circle];
+    start [shape=point];
+    start -> s0;
+    s0 [label="S0"];
+    s0 -> s0 [label="\\x00-`, b-\\xff"];
+    s0 -> s1 [label="a"];
+    s1 [label="S1"];
+    s1 -> s0 [label="\\x00-`, b-\\xff"];
+    s1 -> s1 [label="a"];
+    s1 -> s2 [label="EOF", style=dashed];
+    s2 [label="S2", shape=doublecircle];
+}
+"#;
+        assert_eq!(expected, dfa(r"(?-u)a$").to_dot());
+    }
+
+    #[test]
+    fn json_anchored() {
+        let expected = r#"{
+  "start": 0,
+  "states": [
+    {
+      "insts": [
+        1
+      ],
+      "is_word": false,
+      "is_match": false,
+      "transitions": [
+        {
+          "start": 97,
+          "end": 97,
+          "next": 1
+        }
+      ]
+    },
+    {
+      "insts": [
+        2
+      ],
+      "is_word": false,
+      "is_match": false,
+      "transitions": [],
+      "eof": 2
+    },
+    {
+      "insts": [],
+      "is_word": false,
+      "is_match": true,
+      "transitions": []
+    }
+  ]
+}"#;
+        assert_eq!(expected, dfa(r"^a$").to_json().unwrap());
+    }
+
+    #[test]
+    fn end_line_match_is_delayed() {
+        // The match of `a$` is only known once the `\n` after it is seen,
+        // so it's reported by the state that `\n` leads to.
+        let text = dfa(r"(?-u)(?m)^a$").to_text();
+        assert!(text.contains("  \\n => S0003\n"), "{}", text);
+        assert!(text.contains("S0003 [1, 3] (match)\n"), "{}", text);
+    }
+
+    #[test]
+    fn unicode_word_boundary_unsupported() {
+        let prog = Compiler::new()
+            .dfa(true)
+            .compile(&[parse(r"\ba").unwrap()])
+            .unwrap();
+        assert!(Dfa::new(&prog, 100).is_err());
+    }
+
+    #[test]
+    fn state_limit() {
+        let prog = Compiler::new()
+            .only_utf8(false)
+            .dfa(true)
+            .compile(&[parse(r"(?-u)a$").unwrap()])
+            .unwrap();
+        assert!(Dfa::new(&prog, 3).is_ok());
+        let err = Dfa::new(&prog, 2).err().unwrap();
+        assert_eq!(err.to_string(), "DFA exceeds state limit of 2");
+    }
+}
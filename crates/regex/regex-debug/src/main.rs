@@ -4,17 +4,23 @@ extern crate regex_syntax as syntax;
 extern crate serde;
 #[macro_use]
 extern crate serde_derive;
+extern crate serde_json;
 
+use std::cmp;
 use std::error;
 use std::io::{self, Write};
 use std::process;
 use std::result;
 
 use docopt::Docopt;
-use regex::internal::{Compiler, LiteralSearcher};
+use regex::internal::{Compiler, ExecBuilder, LiteralSearcher};
 use syntax::hir::literal::Literals;
 use syntax::hir::Hir;
 
+mod dfa;
+mod program;
+mod trace;
+
 const USAGE: &'static str = "
 Usage:
     regex-debug [options] ast <pattern>
@@ -24,6 +30,10 @@ Usage:
     regex-debug [options] anchors <pattern>
     regex-debug [options] captures <pattern>
     regex-debug [options] compile <patterns> ...
+    regex-debug [options] dfa <patterns> ...
+    regex-debug [options] match <pattern> <haystack>
+    regex-debug [options] lint <pattern>
+    regex-debug [options] explain <pattern>
     regex-debug [options] utf8-ranges <class>
     regex-debug [options] utf8-ranges-rev <class>
     regex-debug --help
@@ -51,6 +61,10 @@ Options:
                          extracted.
     --searcher           Show the debug output for the literal searcher
                          constructed by the literals found.
+    --format ARG         The output format of the compile and dfa commands.
+                         One of text, dot or json. [default: text]
+    --state-limit ARG    A limit on the number of states materialized by the
+                         dfa command. [default: 10000]
 ";
 
 #[derive(Deserialize)]
@@ -62,12 +76,17 @@ struct Args {
     cmd_anchors: bool,
     cmd_captures: bool,
     cmd_compile: bool,
+    cmd_dfa: bool,
+    cmd_match: bool,
+    cmd_lint: bool,
+    cmd_explain: bool,
     cmd_utf8_ranges: bool,
     cmd_utf8_ranges_rev: bool,
 
     arg_pattern: String,
     arg_patterns: Vec<String>,
     arg_class: String,
+    arg_haystack: String,
 
     flag_size_limit: usize,
     flag_bytes: bool,
@@ -80,6 +99,8 @@ struct Args {
     flag_lcp: bool,
     flag_lcs: bool,
     flag_searcher: bool,
+    flag_format: String,
+    flag_state_limit: usize,
 }
 
 type Result<T> = result::Result<T, Box<dyn error::Error + Send + Sync>>;
@@ -115,6 +136,14 @@ fn run(args: &Args) -> Result<()> {
         cmd_captures(args)
     } else if args.cmd_compile {
         cmd_compile(args)
+    } else if args.cmd_dfa {
+        cmd_dfa(args)
+    } else if args.cmd_match {
+        cmd_match(args)
+    } else if args.cmd_lint {
+        cmd_lint(args)
+    } else if args.cmd_explain {
+        cmd_explain(args)
     } else if args.cmd_utf8_ranges {
         cmd_utf8_ranges(args)
     } else if args.cmd_utf8_ranges_rev {
@@ -214,7 +243,99 @@ fn cmd_compile(args: &Args) -> Result<()> {
         .dfa(args.flag_dfa)
         .reverse(args.flag_dfa_reverse);
     let prog = compiler.compile(&exprs)?;
+    match &*args.flag_format {
+        "text" => print!("{:?}", prog),
+        "dot" => print!("{}", program::to_dot(&prog)),
+        "json" => println!("{}", program::to_json(&prog)?),
+        _ => return Err(args.unknown_format()),
+    }
+    Ok(())
+}
+
+fn cmd_dfa(args: &Args) -> Result<()> {
+    let exprs = args.parse_many()?;
+    let prog = args
+        .compiler()
+        .only_utf8(!args.flag_bytes)
+        .dfa(true)
+        .reverse(args.flag_dfa_reverse)
+        .compile(&exprs)?;
+    let dfa = dfa::Dfa::new(&prog, args.flag_state_limit)?;
+    match &*args.flag_format {
+        "text" => print!("{}", dfa.to_text()),
+        "dot" => print!("{}", dfa.to_dot()),
+        "json" => println!("{}", dfa.to_json()?),
+        _ => return Err(args.unknown_format()),
+    }
+    Ok(())
+}
+
+fn cmd_match(args: &Args) -> Result<()> {
+    // Build the regex the way `Regex::new` does, so that the program traced
+    // is the one (with the same literal prefixes) that a search would run.
+    let exec = ExecBuilder::new(&args.arg_pattern).build()?;
+    let prog = exec.nfa();
     print!("{:?}", prog);
+    println!("----------------------------");
+    let stdout = io::stdout();
+    match trace::trace(stdout.lock(), prog, &args.arg_haystack)? {
+        None => println!("no match"),
+        Some(slots) => {
+            for (i, pair) in slots.chunks(2).enumerate() {
+                match (pair[0], pair[1]) {
+                    (Some(s), Some(e)) => println!(
+                        "{}: {}..{} {:?}",
+                        i,
+                        s,
+                        e,
+                        &args.arg_haystack[s..e]
+                    ),
+                    _ => println!("{}: none", i),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn cmd_lint(args: &Args) -> Result<()> {
+    use syntax::ast::lint::Linter;
+    use syntax::ast::parse::Parser;
+
+    let ast = Parser::new().parse(&args.arg_pattern)?;
+    let lints = Linter::new().lint(&ast);
+    for lint in &lints {
+        let span = lint.span();
+        println!(
+            "warning: {} (line {}, column {})",
+            lint, span.start.line, span.start.column
+        );
+        if !args.arg_pattern.contains('\n') {
+            let width = cmp::max(1, span.end.offset - span.start.offset);
+            println!("    {}", args.arg_pattern);
+            println!(
+                "    {}{}",
+                " ".repeat(
+                    args.arg_pattern[..span.start.offset].chars().count()
+                ),
+                "^".repeat(width)
+            );
+        }
+    }
+    if !lints.is_empty() {
+        return Err(format!("{} lint(s) found", lints.len()).into());
+    }
+    Ok(())
+}
+
+fn cmd_explain(args: &Args) -> Result<()> {
+    use syntax::ast::explain::Explainer;
+    use syntax::ast::parse::Parser;
+
+    let ast = Parser::new().parse(&args.arg_pattern)?;
+    let mut explanation = String::new();
+    Explainer::new().explain(&ast, &mut explanation)?;
+    print!("{}", explanation);
     Ok(())
 }
 
@@ -325,6 +446,14 @@ impl Args {
     fn compiler(&self) -> Compiler {
         Compiler::new().size_limit(self.flag_size_limit)
     }
+
+    fn unknown_format(&self) -> Box<dyn error::Error + Send + Sync> {
+        format!(
+            "unknown format '{}', expected text, dot or json",
+            self.flag_format
+        )
+        .into()
+    }
 }
 
 fn parse(re: &str) -> Result<Hir> {
@@ -0,0 +1,257 @@
+use regex::internal::{EmptyLook, Inst, Program};
+use serde_json;
+
+use escape_byte;
+use Result;
+
+/// A JSON representation of a compiled program.
+#[derive(Serialize)]
+struct ProgramJson {
+    start: usize,
+    is_bytes: bool,
+    is_dfa: bool,
+    is_reverse: bool,
+    is_anchored_start: bool,
+    is_anchored_end: bool,
+    captures: Vec<Option<String>>,
+    insts: Vec<InstJson>,
+}
+
+/// A JSON representation of a single instruction.
+#[derive(Serialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum InstJson {
+    Match { pc: usize, slot: usize },
+    Save { pc: usize, goto: usize, slot: usize },
+    Split { pc: usize, goto1: usize, goto2: usize },
+    EmptyLook { pc: usize, goto: usize, look: String },
+    Char { pc: usize, goto: usize, c: char },
+    Ranges { pc: usize, goto: usize, ranges: Vec<(char, char)> },
+    Bytes { pc: usize, goto: usize, start: u8, end: u8 },
+}
+
+/// Render the given program as a pretty printed JSON document.
+pub fn to_json(prog: &Program) -> Result<String> {
+    let insts = prog
+        .iter()
+        .enumerate()
+        .map(|(pc, inst)| match *inst {
+            Inst::Match(slot) => InstJson::Match { pc: pc, slot: slot },
+            Inst::Save(ref i) => {
+                InstJson::Save { pc: pc, goto: i.goto, slot: i.slot }
+            }
+            Inst::Split(ref i) => {
+                InstJson::Split { pc: pc, goto1: i.goto1, goto2: i.goto2 }
+            }
+            Inst::EmptyLook(ref i) => InstJson::EmptyLook {
+                pc: pc,
+                goto: i.goto,
+                look: look_name(i.look).to_string(),
+            },
+            Inst::Char(ref i) => {
+                InstJson::Char { pc: pc, goto: i.goto, c: i.c }
+            }
+            Inst::Ranges(ref i) => InstJson::Ranges {
+                pc: pc,
+                goto: i.goto,
+                ranges: i.ranges.clone(),
+            },
+            Inst::Bytes(ref i) => InstJson::Bytes {
+                pc: pc,
+                goto: i.goto,
+                start: i.start,
+                end: i.end,
+            },
+        })
+        .collect();
+    let json = ProgramJson {
+        start: prog.start,
+        is_bytes: prog.is_bytes,
+        is_dfa: prog.is_dfa,
+        is_reverse: prog.is_reverse,
+        is_anchored_start: prog.is_anchored_start,
+        is_anchored_end: prog.is_anchored_end,
+        captures: prog.captures.clone(),
+        insts: insts,
+    };
+    Ok(serde_json::to_string_pretty(&json)?)
+}
+
+/// Render the given program as a Graphviz DOT digraph.
+///
+/// Every instruction becomes a node. Split instructions have two outgoing
+/// edges, labeled by their priority (`1` is preferred over `2`).
+pub fn to_dot(prog: &Program) -> String {
+    let mut dot = String::new();
+    dot.push_str("digraph program {\n");
+    dot.push_str("    rankdir=LR;\n");
+    dot.push_str("    node [shape=box];\n");
+    dot.push_str("    start [shape=point];\n");
+    dot.push_str(&format!("    start -> i{};\n", prog.start));
+    for (pc, inst) in prog.iter().enumerate() {
+        let shape = if inst.is_match() { ", shape=doublecircle" } else { "" };
+        dot.push_str(&format!(
+            "    i{} [label=\"{:04} {}\"{}];\n",
+            pc,
+            pc,
+            escape_dot(&inst_label(inst)),
+            shape
+        ));
+        match *inst {
+            Inst::Match(_) => {}
+            Inst::Split(ref i) => {
+                dot.push_str(&format!(
+                    "    i{} -> i{} [label=\"1\"];\n",
+                    pc, i.goto1
+                ));
+                dot.push_str(&format!(
+                    "    i{} -> i{} [label=\"2\", style=dashed];\n",
+                    pc, i.goto2
+                ));
+            }
+            Inst::Save(ref i) => edge(&mut dot, pc, i.goto),
+            Inst::EmptyLook(ref i) => edge(&mut dot, pc, i.goto),
+            Inst::Char(ref i) => edge(&mut dot, pc, i.goto),
+            Inst::Ranges(ref i) => edge(&mut dot, pc, i.goto),
+            Inst::Bytes(ref i) => edge(&mut dot, pc, i.goto),
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Return a short, single line description of an instruction.
+pub fn inst_label(inst: &Inst) -> String {
+    match *inst {
+        Inst::Match(slot) => format!("Match({})", slot),
+        Inst::Save(ref i) => format!("Save({})", i.slot),
+        Inst::Split(ref i) => format!("Split({}, {})", i.goto1, i.goto2),
+        Inst::EmptyLook(ref i) => look_name(i.look).to_string(),
+        Inst::Char(ref i) => format!("{:?}", i.c),
+        Inst::Ranges(ref i) => i
+            .ranges
+            .iter()
+            .map(|r| format!("{:?}-{:?}", r.0, r.1))
+            .collect::<Vec<String>>()
+            .join(", "),
+        Inst::Bytes(ref i) => {
+            format!("Bytes({}, {})", escape_byte(i.start), escape_byte(i.end))
+        }
+    }
+}
+
+fn edge(dot: &mut String, from: usize, to: usize) {
+    dot.push_str(&format!("    i{} -> i{};\n", from, to));
+}
+
+fn look_name(look: EmptyLook) -> &'static str {
+    match look {
+        EmptyLook::StartLine => "StartLine",
+        EmptyLook::EndLine => "EndLine",
+        EmptyLook::StartText => "StartText",
+        EmptyLook::EndText => "EndText",
+        EmptyLook::WordBoundary => "WordBoundary",
+        EmptyLook::NotWordBoundary => "NotWordBoundary",
+        EmptyLook::WordBoundaryAscii => "WordBoundaryAscii",
+        EmptyLook::NotWordBoundaryAscii => "NotWordBoundaryAscii",
+        EmptyLook::WordBoundarySegment => "WordBoundarySegment",
+        EmptyLook::NotWordBoundarySegment => "NotWordBoundarySegment",
+    }
+}
+
+/// Escape a string for use inside a double quoted DOT identifier.
+pub fn escape_dot(s: &str) -> String {
+    let mut escaped = String::new();
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::internal::{Compiler, Program};
+
+    use super::{to_dot, to_json};
+    use parse;
+
+    fn program(pattern: &str) -> Program {
+        Compiler::new().compile(&[parse(pattern).unwrap()]).unwrap()
+    }
+
+    #[test]
+    fn dot() {
+        let expected = r#"digraph program {
+    rankdir=LR;
+    node [shape=box];
+    start [shape=point];
+    start -> i0;
+    i0 [label="0000 Save(0)"];
+    i0 -> i1;
+    i1 [label="0001 Split(2, 3)"];
+    i1 -> i2 [label="1"];
+    i1 -> i3 [label="2", style=dashed];
+    i2 [label="0002 'a'"];
+    i2 -> i4;
+    i3 [label="0003 StartText"];
+    i3 -> i4;
+    i4 [label="0004 Save(1)"];
+    i4 -> i5;
+    i5 [label="0005 Match(0)", shape=doublecircle];
+}
+"#;
+        assert_eq!(expected, to_dot(&program(r"a|\A")));
+    }
+
+    #[test]
+    fn json() {
+        let expected = r#"{
+  "start": 0,
+  "is_bytes": false,
+  "is_dfa": false,
+  "is_reverse": false,
+  "is_anchored_start": true,
+  "is_anchored_end": false,
+  "captures": [
+    null
+  ],
+  "insts": [
+    {
+      "op": "save",
+      "pc": 0,
+      "goto": 1,
+      "slot": 0
+    },
+    {
+      "op": "empty_look",
+      "pc": 1,
+      "goto": 2,
+      "look": "StartText"
+    },
+    {
+      "op": "char",
+      "pc": 2,
+      "goto": 3,
+      "c": "a"
+    },
+    {
+      "op": "save",
+      "pc": 3,
+      "goto": 4,
+      "slot": 1
+    },
+    {
+      "op": "match",
+      "pc": 4,
+      "slot": 0
+    }
+  ]
+}"#;
+        assert_eq!(expected, to_json(&program(r"^a")).unwrap());
+    }
+}
@@ -0,0 +1,126 @@
+use std::io::{self, Write};
+
+use regex::internal::{self, ByteInput, CharInput, Program, Trace};
+
+use escape_unicode;
+use program::inst_label;
+
+/// Run the Pike VM of the regex crate over `haystack` and write its thread
+/// list to `wtr` before it consumes each character.
+///
+/// This traces the same search that `Regex::captures` runs when it uses the
+/// Pike VM, including skipping ahead to literal prefixes, so positions that
+/// are skipped don't show up in the trace. The capture slots of the match
+/// are returned, if there is one.
+pub fn trace<W: Write>(
+    mut wtr: W,
+    prog: &Program,
+    haystack: &str,
+) -> io::Result<Option<Vec<Option<usize>>>> {
+    let mut slots = vec![None; prog.captures.len() * 2];
+    let mut result = Ok(());
+    let matched = {
+        let mut tracer = |event: Trace| {
+            if result.is_ok() {
+                result = write_event(&mut wtr, prog, haystack, event);
+            }
+        };
+        let text = haystack.as_bytes();
+        if prog.uses_bytes() {
+            let input = ByteInput::new(text, prog.only_utf8);
+            internal::trace(prog, input, &mut slots, &mut tracer)
+        } else {
+            let input = CharInput::new(text);
+            internal::trace(prog, input, &mut slots, &mut tracer)
+        }
+    };
+    result?;
+    Ok(if matched { Some(slots) } else { None })
+}
+
+fn write_event<W: Write>(
+    wtr: &mut W,
+    prog: &Program,
+    haystack: &str,
+    event: Trace,
+) -> io::Result<()> {
+    match event {
+        Trace::Step { at, threads } => {
+            let c = match haystack[at..].chars().next() {
+                None => "EOF".to_string(),
+                Some(c) => {
+                    format!("'{}'", escape_unicode(c.to_string().as_bytes()))
+                }
+            };
+            writeln!(wtr, "at {} {}", at, c)?;
+            for (pc, slots) in threads {
+                writeln!(
+                    wtr,
+                    "    {:04} {} {:?}",
+                    pc,
+                    inst_label(&prog[pc]),
+                    slots
+                )?;
+            }
+        }
+        Trace::Match { pc, slots, .. } => {
+            writeln!(wtr, "    match at {:04}: {:?}", pc, slots)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::internal::ExecBuilder;
+
+    use super::trace;
+
+    fn run(
+        pattern: &str,
+        haystack: &str,
+    ) -> (String, Option<Vec<Option<usize>>>) {
+        let exec = ExecBuilder::new(pattern).build().unwrap();
+        let mut out = vec![];
+        let slots = trace(&mut out, exec.nfa(), haystack).unwrap();
+        (String::from_utf8(out).unwrap(), slots)
+    }
+
+    #[test]
+    fn match_trace() {
+        // The search skips ahead to the literal prefix `a`.
+        let expected = "\
+at 1 'a'
+    0001 'a' [Some(1), None, None, None]
+at 2 'b'
+    0003 'b' [Some(1), None, Some(2), None]
+    0001 'a' [Some(2), None, None, None]
+at 3 EOF
+    0006 Match(0) [Some(1), Some(3), Some(2), Some(3)]
+    0001 'a' [Some(3), None, None, None]
+    match at 0006: [Some(1), Some(3), Some(2), Some(3)]
+";
+        let (out, slots) = run(r"a(b)", "xab");
+        assert_eq!(expected, out);
+        assert_eq!(Some(vec![Some(1), Some(3), Some(2), Some(3)]), slots);
+    }
+
+    #[test]
+    fn no_match_trace() {
+        let expected = r"at 0 'a'
+    0001 '\0'-'\t', '\u{b}'-'\u{10ffff}' [Some(0), None]
+at 1 'c'
+    0002 'b' [Some(0), None]
+    0001 '\0'-'\t', '\u{b}'-'\u{10ffff}' [Some(1), None]
+at 2 EOF
+    0002 'b' [Some(1), None]
+    0001 '\0'-'\t', '\u{b}'-'\u{10ffff}' [Some(2), None]
+";
+        let (out, slots) = run(r".b", "ac");
+        assert_eq!(expected, out);
+        assert_eq!(None, slots);
+
+        // The literal prefix `a` never occurs, so no step is taken.
+        assert_eq!((String::new(), None), run(r"^a", "b"));
+    }
+}
@@ -0,0 +1,1229 @@
+/*!
+This module provides front-ends for the concrete syntax of other regex
+engines.
+
+Each front-end translates a pattern into this crate's concrete syntax, which
+is then parsed as usual. Every span in the resulting `Ast` (or error) is then
+mapped back to the original pattern, so that error messages point at the
+syntax the user actually wrote.
+*/
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::char;
+use core::cmp;
+
+use ast::{self, Ast, Position, Span};
+use is_meta_character;
+
+type Result<T> = ::core::result::Result<T, ast::Error>;
+
+/// The concrete syntax that a pattern is written in.
+///
+/// Patterns written in a dialect other than `Rust` are translated into this
+/// crate's syntax before they are parsed. Constructs that cannot be
+/// translated, such as backreferences, look-around or PCRE's atomic groups,
+/// result in an error that points at the offending syntax.
+///
+/// Only the syntax is translated, not the semantics. In particular, matching
+/// is always leftmost-first (even for POSIX patterns, where POSIX mandates
+/// leftmost-longest), and `.`, `^` and `$` behave as documented by this
+/// crate.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Dialect {
+    /// The syntax documented by this crate. This is the default.
+    Rust,
+    /// The syntax of PCRE, which is largely shared by Perl.
+    ///
+    /// This supports, for example, `(?<name>re)` and `(?'name're)` for named
+    /// groups, `\Q...\E` quoting, `(?#...)` comments, `\h`, `\v`, `\N` and
+    /// `\R`, `\e`, `\cX`, `\o{...}` and octal escapes, and `\p{^...}`
+    /// negated properties. A `{` that does not start a counted repetition is
+    /// a literal.
+    Pcre,
+    /// POSIX extended regular expressions, as used by `grep -E`.
+    ///
+    /// In bracket expressions, a `\` is a literal and `[=c=]` and `[.c.]`
+    /// are supported for single characters. The GNU extensions `\w`, `\W`,
+    /// `\s`, `\S`, `\b`, `\B`, `` \` `` and `\'` are supported as well.
+    PosixEre,
+    /// JavaScript (ECMAScript) regular expressions, without the surrounding
+    /// `/` delimiters and flags.
+    ///
+    /// `\d`, `\w` and `\s` are translated to their ASCII (or, for `\s`,
+    /// ECMAScript specific) definitions. `\uXXXX` (including surrogate
+    /// pairs), `\u{...}`, `(?<name>re)` and `[^]` are supported, as are the
+    /// legacy identity and octal escapes. Since this crate rejects empty
+    /// classes, `[]` results in an error.
+    JavaScript,
+    /// Hints that destructuring should not be exhaustive.
+    ///
+    /// This enum may grow additional variants, so this makes sure clients
+    /// don't count on exhaustive matching. (Otherwise, adding a new variant
+    /// could break existing code.)
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+impl Default for Dialect {
+    fn default() -> Dialect {
+        Dialect::Rust
+    }
+}
+
+/// PCRE's `\h`.
+const PCRE_HORIZONTAL_SPACE: &'static str =
+    r"\t\x20\xA0\x{1680}\x{180E}\x{2000}-\x{200A}\x{202F}\x{205F}\x{3000}";
+/// PCRE's `\v`.
+const PCRE_VERTICAL_SPACE: &'static str = r"\n\x0B\f\r\x{85}\x{2028}\x{2029}";
+/// PCRE's `\R`, sans the atomic group.
+const PCRE_NEWLINE: &'static str =
+    r"(?:\r\n|[\n\x0B\f\r\x{85}\x{2028}\x{2029}])";
+/// JavaScript's `\d`.
+const JS_DIGIT: &'static str = "0-9";
+/// JavaScript's `\w`.
+const JS_WORD: &'static str = "0-9A-Za-z_";
+/// JavaScript's `\s`.
+const JS_SPACE: &'static str = concat!(
+    r"\t\n\x0B\f\r\x20\xA0\x{1680}\x{2000}-\x{200A}",
+    r"\x{2028}\x{2029}\x{202F}\x{205F}\x{3000}\x{FEFF}",
+);
+
+/// A pattern translated into this crate's syntax, along with a map from
+/// offsets in the translation to offsets in the original pattern.
+#[derive(Clone, Debug)]
+pub struct Translation {
+    /// The pattern as written by the user.
+    original: String,
+    /// The pattern in this crate's syntax.
+    translated: String,
+    /// The pieces that make up the translation, in order.
+    chunks: Vec<Chunk>,
+    /// The offset at which each line of the original pattern starts.
+    lines: Vec<usize>,
+}
+
+/// A piece of a translated pattern, along with the part of the original
+/// pattern that it was translated from.
+#[derive(Clone, Debug)]
+struct Chunk {
+    /// The start offset of this piece in the translated pattern.
+    start: usize,
+    /// The end offset of this piece in the translated pattern.
+    end: usize,
+    /// The start offset in the original pattern.
+    original_start: usize,
+    /// The end offset in the original pattern.
+    original_end: usize,
+    /// Whether this piece was copied from the original pattern unchanged.
+    /// If so, offsets inside it can be mapped exactly.
+    verbatim: bool,
+}
+
+/// Translate the given pattern from the given dialect into this crate's
+/// syntax.
+///
+/// # Panics
+///
+/// This panics when given `Dialect::Rust`, which needs no translation.
+pub fn translate(dialect: Dialect, pattern: &str) -> Result<Translation> {
+    assert!(dialect != Dialect::Rust, "cannot translate from Rust syntax");
+    Translator {
+        dialect: dialect,
+        pattern: pattern,
+        pos: 0,
+        out: String::with_capacity(pattern.len()),
+        chunks: vec![],
+        in_class: false,
+        last_dash: false,
+    }
+    .translate()
+}
+
+impl Translation {
+    /// Return the translated pattern.
+    pub fn pattern(&self) -> &str {
+        &self.translated
+    }
+
+    /// Map every span in the result of parsing the translated pattern back
+    /// to the original pattern.
+    pub fn remap(
+        &self,
+        result: Result<ast::WithComments>,
+    ) -> Result<ast::WithComments> {
+        match result {
+            Ok(mut astc) => {
+                self.remap_ast(&mut astc.ast);
+                for comment in &mut astc.comments {
+                    self.remap_span(&mut comment.span);
+                }
+                Ok(astc)
+            }
+            Err(mut err) => {
+                self.remap_span(&mut err.span);
+                match err.kind {
+                    ast::ErrorKind::FlagDuplicate { ref mut original }
+                    | ast::ErrorKind::FlagRepeatedNegation {
+                        ref mut original,
+                    }
+                    | ast::ErrorKind::GroupNameDuplicate {
+                        ref mut original,
+                    } => self.remap_span(original),
+                    _ => {}
+                }
+                err.pattern = self.original.clone();
+                Err(err)
+            }
+        }
+    }
+
+    fn remap_ast(&self, ast: &mut Ast) {
+        // Like everything else in this crate, use constant stack space.
+        let mut stack: Vec<&mut Ast> = vec![ast];
+        while let Some(ast) = stack.pop() {
+            match *ast {
+                Ast::Empty(ref mut span)
+                | Ast::Dot(ref mut span)
+                | Ast::GraphemeCluster(ref mut span) => self.remap_span(span),
+                Ast::Flags(ref mut x) => {
+                    self.remap_span(&mut x.span);
+                    self.remap_flags(&mut x.flags);
+                }
+                Ast::Literal(ref mut x) => self.remap_span(&mut x.span),
+                Ast::Assertion(ref mut x) => self.remap_span(&mut x.span),
+                Ast::Class(ast::Class::Unicode(ref mut x)) => {
+                    self.remap_span(&mut x.span)
+                }
+                Ast::Class(ast::Class::Perl(ref mut x)) => {
+                    self.remap_span(&mut x.span)
+                }
+                Ast::Class(ast::Class::Bracketed(ref mut x)) => {
+                    self.remap_class(x)
+                }
+                Ast::Repetition(ref mut x) => {
+                    self.remap_span(&mut x.span);
+                    self.remap_span(&mut x.op.span);
+                    stack.push(&mut x.ast);
+                }
+                Ast::Group(ref mut x) => {
+                    self.remap_span(&mut x.span);
+                    match x.kind {
+                        ast::GroupKind::CaptureIndex(_) => {}
+                        ast::GroupKind::CaptureName(ref mut name) => {
+                            self.remap_span(&mut name.span)
+                        }
+                        ast::GroupKind::NonCapturing(ref mut flags) => {
+                            self.remap_flags(flags)
+                        }
+                    }
+                    stack.push(&mut x.ast);
+                }
+                Ast::Alternation(ref mut x) => {
+                    self.remap_span(&mut x.span);
+                    stack.extend(x.asts.iter_mut());
+                }
+                Ast::Concat(ref mut x) => {
+                    self.remap_span(&mut x.span);
+                    stack.extend(x.asts.iter_mut());
+                }
+            }
+        }
+    }
+
+    fn remap_class(&self, class: &mut ast::ClassBracketed) {
+        enum Frame<'a> {
+            Set(&'a mut ast::ClassSet),
+            Item(&'a mut ast::ClassSetItem),
+        }
+
+        self.remap_span(&mut class.span);
+        let mut stack = vec![Frame::Set(&mut class.kind)];
+        while let Some(frame) = stack.pop() {
+            match frame {
+                Frame::Set(&mut ast::ClassSet::Item(ref mut item)) => {
+                    stack.push(Frame::Item(item));
+                }
+                Frame::Set(&mut ast::ClassSet::BinaryOp(ref mut op)) => {
+                    self.remap_span(&mut op.span);
+                    stack.push(Frame::Set(&mut op.rhs));
+                    stack.push(Frame::Set(&mut op.lhs));
+                }
+                Frame::Item(item) => match *item {
+                    ast::ClassSetItem::Empty(ref mut span) => {
+                        self.remap_span(span)
+                    }
+                    ast::ClassSetItem::Literal(ref mut x) => {
+                        self.remap_span(&mut x.span)
+                    }
+                    ast::ClassSetItem::Range(ref mut x) => {
+                        self.remap_span(&mut x.span);
+                        self.remap_span(&mut x.start.span);
+                        self.remap_span(&mut x.end.span);
+                    }
+                    ast::ClassSetItem::Ascii(ref mut x) => {
+                        self.remap_span(&mut x.span)
+                    }
+                    ast::ClassSetItem::Unicode(ref mut x) => {
+                        self.remap_span(&mut x.span)
+                    }
+                    ast::ClassSetItem::Perl(ref mut x) => {
+                        self.remap_span(&mut x.span)
+                    }
+                    ast::ClassSetItem::Bracketed(ref mut x) => {
+                        self.remap_span(&mut x.span);
+                        stack.push(Frame::Set(&mut x.kind));
+                    }
+                    ast::ClassSetItem::Union(ref mut x) => {
+                        self.remap_span(&mut x.span);
+                        stack.extend(x.items.iter_mut().map(Frame::Item));
+                    }
+                },
+            }
+        }
+    }
+
+    fn remap_flags(&self, flags: &mut ast::Flags) {
+        self.remap_span(&mut flags.span);
+        for item in &mut flags.items {
+            self.remap_span(&mut item.span);
+        }
+    }
+
+    fn remap_span(&self, span: &mut Span) {
+        let start = self.remap_start(span.start.offset);
+        let end = if span.is_empty() {
+            start
+        } else {
+            cmp::max(start, self.remap_end(span.end.offset))
+        };
+        *span = Span::new(self.position(start), self.position(end));
+    }
+
+    /// Map an offset in the translation that starts a span to an offset in
+    /// the original pattern.
+    fn remap_start(&self, offset: usize) -> usize {
+        match self.chunk(offset) {
+            None => self.original.len(),
+            Some(c) if c.verbatim => c.original_start + (offset - c.start),
+            Some(c) => c.original_start,
+        }
+    }
+
+    /// Map an offset in the translation that ends a span to an offset in the
+    /// original pattern.
+    fn remap_end(&self, offset: usize) -> usize {
+        if offset == 0 {
+            return 0;
+        }
+        match self.chunk(offset - 1) {
+            None => self.original.len(),
+            Some(c) if c.verbatim => c.original_start + (offset - c.start),
+            Some(c) => c.original_end,
+        }
+    }
+
+    /// Return the chunk containing the given offset of the translation.
+    fn chunk(&self, offset: usize) -> Option<&Chunk> {
+        self.chunks
+            .binary_search_by(|c| {
+                if c.end <= offset {
+                    cmp::Ordering::Less
+                } else if c.start > offset {
+                    cmp::Ordering::Greater
+                } else {
+                    cmp::Ordering::Equal
+                }
+            })
+            .ok()
+            .map(|i| &self.chunks[i])
+    }
+
+    /// Return the position of the given offset in the original pattern.
+    fn position(&self, offset: usize) -> Position {
+        let line = match self.lines.binary_search(&offset) {
+            Ok(i) => i,
+            Err(i) => i - 1,
+        };
+        let column = self.original[self.lines[line]..offset].chars().count();
+        Position::new(offset, line + 1, column + 1)
+    }
+}
+
+/// The state of a single translation.
+struct Translator<'p> {
+    dialect: Dialect,
+    /// The original pattern.
+    pattern: &'p str,
+    /// The current offset into the original pattern.
+    pos: usize,
+    /// The translated pattern so far.
+    out: String,
+    /// The pieces of `out`.
+    chunks: Vec<Chunk>,
+    /// Whether we're inside a bracketed character class.
+    in_class: bool,
+    /// Whether the last item of the current class was an unescaped `-`.
+    last_dash: bool,
+}
+
+impl<'p> Translator<'p> {
+    fn translate(mut self) -> Result<Translation> {
+        while !self.is_eof() {
+            if self.in_class {
+                self.translate_class_item()?;
+            } else {
+                self.translate_item()?;
+            }
+        }
+        let mut lines = vec![0];
+        lines.extend(self.pattern.match_indices('\n').map(|(i, _)| i + 1));
+        Ok(Translation {
+            original: self.pattern.to_string(),
+            translated: self.out,
+            chunks: self.chunks,
+            lines: lines,
+        })
+    }
+
+    /// Translate a single item outside of a character class.
+    fn translate_item(&mut self) -> Result<()> {
+        let start = self.pos;
+        match self.bump() {
+            '\\' => self.translate_escape(start),
+            '[' => self.translate_open_class(start),
+            '(' => self.translate_open_group(start),
+            '{' => self.translate_open_brace(start),
+            '*' | '+' | '?' => {
+                self.push_verbatim(start);
+                self.check_possessive(start)
+            }
+            _ => {
+                self.push_verbatim(start);
+                Ok(())
+            }
+        }
+    }
+
+    /// Translate a single item inside a character class.
+    fn translate_class_item(&mut self) -> Result<()> {
+        let start = self.pos;
+        let last_dash = self.last_dash;
+        self.last_dash = false;
+        match self.bump() {
+            ']' => {
+                self.push_verbatim(start);
+                self.in_class = false;
+                Ok(())
+            }
+            '[' => self.translate_class_bracket(start),
+            '\\' if self.dialect == Dialect::PosixEre => {
+                self.push(start, r"\\");
+                Ok(())
+            }
+            '\\' => self.translate_escape(start),
+            // These are class set operators in this crate's syntax, but
+            // literals everywhere else.
+            c @ '&' | c @ '~' => {
+                self.push_literal(start, c);
+                Ok(())
+            }
+            '-' if last_dash => {
+                self.push(start, r"\-");
+                Ok(())
+            }
+            '-' => {
+                self.push_verbatim(start);
+                self.last_dash = true;
+                Ok(())
+            }
+            _ => {
+                self.push_verbatim(start);
+                Ok(())
+            }
+        }
+    }
+
+    /// Translate the opening bracket of a character class. The bracket has
+    /// already been consumed.
+    fn translate_open_class(&mut self, start: usize) -> Result<()> {
+        let negated = self.bump_if("^");
+        if self.dialect == Dialect::JavaScript && self.bump_if("]") {
+            // `[^]` matches any character while `[]` never matches. The
+            // latter is rejected when translating to HIR.
+            if negated {
+                self.push(start, "(?s:.)");
+            } else {
+                self.push(start, r"[^\x00-\x{10FFFF}]");
+            }
+            return Ok(());
+        }
+        self.push_verbatim(start);
+        self.in_class = true;
+        // A `]` right after the opening bracket is a literal. This crate
+        // agrees, but we must not treat it as the end of the class.
+        let start = self.pos;
+        if self.bump_if("]") {
+            self.push_verbatim(start);
+        }
+        Ok(())
+    }
+
+    /// Translate a `[` inside a character class, which may start a POSIX
+    /// bracket expression like `[:alpha:]`. The `[` has already been
+    /// consumed.
+    fn translate_class_bracket(&mut self, start: usize) -> Result<()> {
+        let kind = match self.peek() {
+            Some(c) if self.dialect != Dialect::JavaScript => c,
+            _ => {
+                self.push(start, r"\[");
+                return Ok(());
+            }
+        };
+        let close = match kind {
+            ':' => ":]",
+            '=' => "=]",
+            '.' => ".]",
+            _ => {
+                self.push(start, r"\[");
+                return Ok(());
+            }
+        };
+        let body = match self.rest()[1..].find(close) {
+            Some(i) if !self.rest()[1..1 + i].contains(']') => {
+                &self.pattern[self.pos + 1..self.pos + 1 + i]
+            }
+            _ => {
+                self.push(start, r"\[");
+                return Ok(());
+            }
+        };
+        self.pos += 1 + body.len() + close.len();
+        if kind == ':' {
+            // This crate supports ASCII classes in the same syntax.
+            self.push_verbatim(start);
+            return Ok(());
+        }
+        let mut chars = body.chars();
+        match (self.dialect, chars.next(), chars.next()) {
+            (Dialect::PosixEre, Some(c), None) => {
+                self.push_literal(start, c);
+                Ok(())
+            }
+            (_, _, _) if kind == '=' => {
+                Err(self.unsupported(start, "equivalence classes"))
+            }
+            (_, _, _) => Err(self.unsupported(start, "collating elements")),
+        }
+    }
+
+    /// Translate a group opening. The `(` has already been consumed.
+    fn translate_open_group(&mut self, start: usize) -> Result<()> {
+        if self.dialect == Dialect::PosixEre {
+            // This is undefined in POSIX, but it must not be mistaken for
+            // a flag group.
+            let q = self.pos;
+            if self.bump_if("?") {
+                return Err(self.error(q, ast::ErrorKind::RepetitionMissing));
+            }
+            self.push_verbatim(start);
+            return Ok(());
+        }
+        let pcre = self.dialect == Dialect::Pcre;
+        if pcre && self.bump_if("*") {
+            self.skip_past(')');
+            return Err(self.unsupported(start, "backtracking control verbs"));
+        }
+        if !self.bump_if("?") {
+            self.push_verbatim(start);
+            return Ok(());
+        }
+        if self.bump_if("=")
+            || self.bump_if("!")
+            || self.bump_if("<=")
+            || self.bump_if("<!")
+        {
+            return Err(
+                self.error(start, ast::ErrorKind::UnsupportedLookAround)
+            );
+        }
+        if self.bump_if("<") || (pcre && self.bump_if("P<")) {
+            return self.translate_group_name(start, '>');
+        }
+        if !pcre {
+            self.push_verbatim(start);
+            return Ok(());
+        }
+        if self.bump_if("'") {
+            return self.translate_group_name(start, '\'');
+        }
+        if self.bump_if("#") {
+            // A comment, which is dropped entirely.
+            if !self.skip_past(')') {
+                return Err(self.error(start, ast::ErrorKind::GroupUnclosed));
+            }
+            return Ok(());
+        }
+        if self.bump_if("P=") {
+            self.skip_past(')');
+            return Err(
+                self.error(start, ast::ErrorKind::UnsupportedBackreference)
+            );
+        }
+        let rest = self.rest();
+        let what = if rest.starts_with('>') {
+            "atomic groups"
+        } else if rest.starts_with('|') {
+            "branch reset groups"
+        } else if rest.starts_with('(') {
+            "conditional groups"
+        } else if rest.starts_with('C') {
+            "callouts"
+        } else if rest.starts_with('^') {
+            "flag groups that reset flags"
+        } else if rest.starts_with('R')
+            || rest.starts_with('&')
+            || rest.starts_with("P>")
+            || starts_with_number(rest)
+        {
+            "subroutine calls"
+        } else {
+            // A non-capturing group or a flag group, which are written the
+            // same in this crate.
+            self.push_verbatim(start);
+            return Ok(());
+        };
+        self.bump();
+        Err(self.unsupported(start, what))
+    }
+
+    /// Translate a named group. The opening syntax up to and including the
+    /// opening delimiter of the name has already been consumed.
+    fn translate_group_name(
+        &mut self,
+        start: usize,
+        terminator: char,
+    ) -> Result<()> {
+        self.push(start, "(?P<");
+        while let Some(c) = self.peek() {
+            let start = self.pos;
+            self.bump();
+            if c == terminator {
+                self.push(start, ">");
+                return Ok(());
+            }
+            self.push_verbatim(start);
+        }
+        // Let the parser report the unclosed name.
+        Ok(())
+    }
+
+    /// Translate a `{`, which has already been consumed. In all dialects but
+    /// POSIX, a `{` that doesn't start a counted repetition is a literal.
+    fn translate_open_brace(&mut self, start: usize) -> Result<()> {
+        match counted_repetition_len(self.rest()) {
+            Some(len) => {
+                self.pos += len;
+                self.push_verbatim(start);
+                self.check_possessive(start)
+            }
+            None if self.dialect == Dialect::PosixEre => {
+                self.push_verbatim(start);
+                Ok(())
+            }
+            None => {
+                self.push(start, r"\{");
+                Ok(())
+            }
+        }
+    }
+
+    /// Return an error if the repetition operator starting at `start`, which
+    /// has just been consumed, is followed by a `+` in PCRE.
+    fn check_possessive(&mut self, start: usize) -> Result<()> {
+        if self.dialect == Dialect::Pcre && self.bump_if("+") {
+            return Err(
+                self.unsupported(start, "possessive repetition operators")
+            );
+        }
+        Ok(())
+    }
+
+    /// Translate an escape sequence. The `\` has already been consumed.
+    fn translate_escape(&mut self, start: usize) -> Result<()> {
+        let c = match self.peek() {
+            None => {
+                // Let the parser report the incomplete escape.
+                self.push_verbatim(start);
+                return Ok(());
+            }
+            Some(c) => c,
+        };
+        self.bump();
+        match self.dialect {
+            Dialect::Pcre => self.translate_pcre_escape(start, c),
+            Dialect::PosixEre => self.translate_ere_escape(start, c),
+            Dialect::JavaScript => self.translate_js_escape(start, c),
+            _ => unreachable!(),
+        }
+    }
+
+    fn translate_pcre_escape(&mut self, start: usize, c: char) -> Result<()> {
+        match c {
+            'b' if self.in_class => self.push(start, r"\x08"),
+            'd' | 'D' | 'w' | 'W' | 's' | 'S' | 'b' | 'B' | 'A' | 'z'
+            | 'a' | 'f' | 'n' | 'r' | 't' => self.push_verbatim(start),
+            'h' => self.push_class(start, false, PCRE_HORIZONTAL_SPACE),
+            'H' => self.push_class(start, true, PCRE_HORIZONTAL_SPACE),
+            'v' => self.push_class(start, false, PCRE_VERTICAL_SPACE),
+            'V' => self.push_class(start, true, PCRE_VERTICAL_SPACE),
+            'N' if !self.in_class => self.push(start, r"[^\n]"),
+            'R' if !self.in_class => self.push(start, PCRE_NEWLINE),
+            'e' => self.push(start, r"\x1B"),
+            'c' => return self.translate_control(start),
+            'x' if self.bump_if("{") => {
+                // This crate uses the same syntax.
+                self.skip_past('}');
+                self.push_verbatim(start);
+            }
+            'x' => {
+                let n = self.take_digits(16, 2);
+                self.push_codepoint(start, n)?;
+            }
+            'o' => {
+                if !self.bump_if("{") {
+                    return Err(
+                        self.error(start, ast::ErrorKind::EscapeUnrecognized)
+                    );
+                }
+                let n = self.take_digits(8, 8);
+                if !self.bump_if("}") {
+                    self.skip_past('}');
+                    return Err(
+                        self.error(start, ast::ErrorKind::DecimalInvalid)
+                    );
+                }
+                self.push_codepoint(start, n)?;
+            }
+            '0' => {
+                let n = self.take_digits(8, 2);
+                self.push_codepoint(start, n)?;
+            }
+            '1'..='9' if self.in_class => {
+                // Inside a class, this can only be an octal escape.
+                self.pos = start + 1;
+                match self.take_digits(8, 3) {
+                    0 => {
+                        self.bump();
+                        self.push_literal(start, c);
+                    }
+                    n => self.push_codepoint(start, n)?,
+                }
+            }
+            '1'..='9' | 'g' | 'k' => {
+                self.skip_reference();
+                return Err(self
+                    .error(start, ast::ErrorKind::UnsupportedBackreference));
+            }
+            'p' | 'P' => self.translate_property(start, c),
+            'Q' => {
+                // Everything up to the next `\E` is a literal.
+                while !self.is_eof() && !self.rest().starts_with(r"\E") {
+                    let start = self.pos;
+                    let c = self.bump();
+                    self.push_literal(start, c);
+                }
+                self.bump_if(r"\E");
+            }
+            // A `\E` without a `\Q` is ignored.
+            'E' => {}
+            'G' => return Err(self.unsupported(start, "\\G assertions")),
+            'K' => return Err(self.unsupported(start, "\\K match resets")),
+            // This crate uses the same syntax for grapheme clusters.
+            'X' if !self.in_class => self.push_verbatim(start),
+            'C' => return Err(self.unsupported(start, "\\C code units")),
+            'Z' => return Err(self.unsupported(start, "\\Z assertions")),
+            // Let the parser decide whether it knows this escape.
+            c if c.is_alphanumeric() => self.push_verbatim(start),
+            c => self.push_literal(start, c),
+        }
+        Ok(())
+    }
+
+    fn translate_ere_escape(&mut self, start: usize, c: char) -> Result<()> {
+        match c {
+            '1'..='9' => {
+                Err(self
+                    .error(start, ast::ErrorKind::UnsupportedBackreference))
+            }
+            'w' | 'W' | 's' | 'S' | 'b' | 'B' => {
+                self.push_verbatim(start);
+                Ok(())
+            }
+            '`' => {
+                self.push(start, r"\A");
+                Ok(())
+            }
+            '\'' => {
+                self.push(start, r"\z");
+                Ok(())
+            }
+            '<' | '>' => {
+                Err(self.unsupported(start, "\\< and \\> assertions"))
+            }
+            c if c.is_alphanumeric() => {
+                Err(self.error(start, ast::ErrorKind::EscapeUnrecognized))
+            }
+            c => {
+                self.push_literal(start, c);
+                Ok(())
+            }
+        }
+    }
+
+    fn translate_js_escape(&mut self, start: usize, c: char) -> Result<()> {
+        match c {
+            'd' => self.push_class(start, false, JS_DIGIT),
+            'D' => self.push_class(start, true, JS_DIGIT),
+            'w' => self.push_class(start, false, JS_WORD),
+            'W' => self.push_class(start, true, JS_WORD),
+            's' => self.push_class(start, false, JS_SPACE),
+            'S' => self.push_class(start, true, JS_SPACE),
+            'b' if self.in_class => self.push(start, r"\x08"),
+            'b' | 'B' | 'f' | 'n' | 'r' | 't' | 'v' => {
+                self.push_verbatim(start)
+            }
+            'c' if self.peek().map_or(false, |c| c.is_ascii_alphabetic()) => {
+                return self.translate_control(start);
+            }
+            // Legacy octal escapes.
+            '0'..='7' if c == '0' || self.in_class => {
+                self.pos = start + 1;
+                let n = self.take_digits(8, 3);
+                self.push_codepoint(start, n)?;
+            }
+            '1'..='9' if !self.in_class => {
+                self.skip_reference();
+                return Err(self
+                    .error(start, ast::ErrorKind::UnsupportedBackreference));
+            }
+            'k' if self.peek() == Some('<') => {
+                self.skip_reference();
+                return Err(self
+                    .error(start, ast::ErrorKind::UnsupportedBackreference));
+            }
+            'x' if self.rest().len() >= 2
+                && self.rest().chars().take(2).all(|c| c.is_digit(16)) =>
+            {
+                self.pos += 2;
+                self.push_verbatim(start);
+            }
+            'u' if self.bump_if("{") => {
+                let n = self.take_digits(16, 8);
+                if !self.bump_if("}") {
+                    self.skip_past('}');
+                    return Err(self
+                        .error(start, ast::ErrorKind::EscapeHexInvalidDigit));
+                }
+                self.push_codepoint(start, n)?;
+            }
+            'u' if self.rest().len() >= 4
+                && self.rest().chars().take(4).all(|c| c.is_digit(16)) =>
+            {
+                let mut n = self.take_digits(16, 4);
+                if 0xD800 <= n && n <= 0xDBFF {
+                    // Combine a surrogate pair into a single codepoint. A
+                    // lone surrogate is rejected by `push_codepoint`.
+                    let rest = self.rest();
+                    let low = match rest.get(2..6) {
+                        Some(hex)
+                            if rest.starts_with(r"\u")
+                                && hex.chars().all(|c| c.is_digit(16)) =>
+                        {
+                            u32::from_str_radix(hex, 16).ok()
+                        }
+                        _ => None,
+                    };
+                    if let Some(low) = low {
+                        if 0xDC00 <= low && low <= 0xDFFF {
+                            self.pos += 6;
+                            n = 0x10000
+                                + ((n - 0xD800) << 10)
+                                + (low - 0xDC00);
+                        }
+                    }
+                }
+                self.push_codepoint(start, n)?;
+            }
+            'p' | 'P' => self.translate_property(start, c),
+            // Any other escaped character is a literal.
+            c => self.push_literal(start, c),
+        }
+        Ok(())
+    }
+
+    /// Translate a Unicode property escape. The `\p` or `\P` has already been
+    /// consumed.
+    fn translate_property(&mut self, start: usize, c: char) {
+        if !self.bump_if("{") {
+            // A one letter property, which this crate supports as is.
+            if !self.is_eof() {
+                self.bump();
+            }
+            self.push_verbatim(start);
+            return;
+        }
+        let name_start = self.pos;
+        if !self.skip_past('}') {
+            self.push_verbatim(start);
+            return;
+        }
+        let name = &self.pattern[name_start..self.pos - 1];
+        if name.starts_with('^') {
+            // PCRE permits negating a property with `\p{^Greek}`.
+            let negated = if c == 'p' { 'P' } else { 'p' };
+            self.push(start, &format!("\\{}{{{}}}", negated, &name[1..]));
+        } else {
+            self.push_verbatim(start);
+        }
+    }
+
+    /// Translate a control character escape, `\cX`. The `\c` has already
+    /// been consumed.
+    fn translate_control(&mut self, start: usize) -> Result<()> {
+        let c = match self.peek() {
+            None => {
+                return Err(
+                    self.error(start, ast::ErrorKind::EscapeUnexpectedEof)
+                )
+            }
+            Some(c) => c,
+        };
+        self.bump();
+        if !c.is_ascii() {
+            return Err(self.error(start, ast::ErrorKind::EscapeUnrecognized));
+        }
+        let n = (c.to_ascii_uppercase() as u32) ^ 0x40;
+        self.push_codepoint(start, n)
+    }
+
+    /// Skip the name or number of a backreference, for error reporting.
+    fn skip_reference(&mut self) {
+        match self.peek() {
+            Some('{') => self.skip_past('}'),
+            Some('<') => self.skip_past('>'),
+            Some('\'') => {
+                self.bump();
+                self.skip_past('\'')
+            }
+            _ => {
+                self.bump_if("-");
+                self.take_digits(10, ::core::usize::MAX);
+                true
+            }
+        };
+    }
+
+    /// Consume up to `limit` digits in the given radix and return their
+    /// value. If there are no digits, then `0` is returned.
+    ///
+    /// The value saturates, which makes it an invalid codepoint.
+    fn take_digits(&mut self, radix: u32, limit: usize) -> u32 {
+        let mut n: u32 = 0;
+        let mut count = 0;
+        while count < limit {
+            let d = match self.peek().and_then(|c| c.to_digit(radix)) {
+                None => break,
+                Some(d) => d,
+            };
+            self.bump();
+            n = n.saturating_mul(radix).saturating_add(d);
+            count += 1;
+        }
+        n
+    }
+
+    /// Consume everything up to and including the given character. If the
+    /// character isn't found, then everything is consumed and `false` is
+    /// returned.
+    fn skip_past(&mut self, c: char) -> bool {
+        match self.rest().find(c) {
+            None => {
+                self.pos = self.pattern.len();
+                false
+            }
+            Some(i) => {
+                self.pos += i + c.len_utf8();
+                true
+            }
+        }
+    }
+
+    fn is_eof(&self) -> bool {
+        self.pos >= self.pattern.len()
+    }
+
+    fn rest(&self) -> &'p str {
+        &self.pattern[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> char {
+        let c = self.peek().expect("unexpected eof");
+        self.pos += c.len_utf8();
+        c
+    }
+
+    fn bump_if(&mut self, prefix: &str) -> bool {
+        if self.rest().starts_with(prefix) {
+            self.pos += prefix.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Append the given translation of everything consumed since `start`.
+    fn push(&mut self, start: usize, s: &str) {
+        self.push_chunk(start, s, false);
+    }
+
+    /// Append everything consumed since `start` without changing it.
+    fn push_verbatim(&mut self, start: usize) {
+        let s = &self.pattern[start..self.pos];
+        self.push_chunk(start, s, true);
+    }
+
+    fn push_chunk(&mut self, start: usize, s: &str, verbatim: bool) {
+        if s.is_empty() {
+            return;
+        }
+        let out_start = self.out.len();
+        self.out.push_str(s);
+        self.chunks.push(Chunk {
+            start: out_start,
+            end: self.out.len(),
+            original_start: start,
+            original_end: self.pos,
+            verbatim: verbatim,
+        });
+    }
+
+    /// Append the given character as a literal, escaping it if necessary.
+    fn push_literal(&mut self, start: usize, c: char) {
+        let mut s = String::new();
+        if is_meta_character(c) {
+            s.push('\\');
+        }
+        s.push(c);
+        self.push(start, &s);
+    }
+
+    /// Append the given codepoint as a hexadecimal escape, or return an
+    /// error if it isn't a Unicode scalar value.
+    fn push_codepoint(&mut self, start: usize, n: u32) -> Result<()> {
+        if char::from_u32(n).is_none() {
+            return Err(self.error(start, ast::ErrorKind::EscapeHexInvalid));
+        }
+        self.push(start, &format!("\\x{{{:X}}}", n));
+        Ok(())
+    }
+
+    /// Append a bracketed class with the given items. Since classes nest in
+    /// this crate's syntax, this works inside a class too.
+    fn push_class(&mut self, start: usize, negated: bool, items: &str) {
+        let neg = if negated { "^" } else { "" };
+        self.push(start, &format!("[{}{}]", neg, items));
+    }
+
+    /// Create an error for everything consumed since `start`.
+    fn error(&self, start: usize, kind: ast::ErrorKind) -> ast::Error {
+        let position = |offset: usize| {
+            let before = &self.pattern[..offset];
+            let line = before.matches('\n').count() + 1;
+            let column = before.rsplit('\n').next().unwrap().chars().count();
+            Position::new(offset, line, column + 1)
+        };
+        ast::Error {
+            kind: kind,
+            pattern: self.pattern.to_string(),
+            span: Span::new(position(start), position(self.pos)),
+        }
+    }
+
+    fn unsupported(&self, start: usize, what: &'static str) -> ast::Error {
+        self.error(start, ast::ErrorKind::UnsupportedSyntax(what))
+    }
+}
+
+/// If the given string (which follows a `{`) is the rest of a counted
+/// repetition, then return its length, including the closing `}`.
+fn counted_repetition_len(s: &str) -> Option<usize> {
+    let digits =
+        |s: &str| s.bytes().take_while(|b| b.is_ascii_digit()).count();
+    let mut len = digits(s);
+    if len == 0 {
+        return None;
+    }
+    if s[len..].starts_with(',') {
+        len += 1;
+        len += digits(&s[len..]);
+    }
+    if s[len..].starts_with('}') {
+        Some(len + 1)
+    } else {
+        None
+    }
+}
+
+/// Returns true if the given string starts with an optionally signed
+/// number, like PCRE's `(?1)` and `(?-1)`.
+fn starts_with_number(s: &str) -> bool {
+    let s = if s.starts_with('+') || s.starts_with('-') { &s[1..] } else { s };
+    s.bytes().next().map_or(false, |b| b.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{translate, Dialect};
+    use ast::parse::ParserBuilder;
+    use ast::print::Printer;
+    use ast::{self, Position, Span};
+
+    fn tr(dialect: Dialect, pattern: &str) -> String {
+        translate(dialect, pattern).unwrap().pattern().to_string()
+    }
+
+    fn parse(dialect: Dialect, pattern: &str) -> ast::Ast {
+        ParserBuilder::new().dialect(dialect).build().parse(pattern).unwrap()
+    }
+
+    fn parse_err(dialect: Dialect, pattern: &str) -> ast::Error {
+        ParserBuilder::new()
+            .dialect(dialect)
+            .build()
+            .parse(pattern)
+            .unwrap_err()
+    }
+
+    fn span(range: ::core::ops::Range<usize>) -> Span {
+        let start = Position::new(range.start, 1, range.start + 1);
+        let end = Position::new(range.end, 1, range.end + 1);
+        Span::new(start, end)
+    }
+
+    #[test]
+    fn translate_pcre() {
+        let pcre = Dialect::Pcre;
+        assert_eq!(
+            tr(pcre, r"(?<x>a)(?'y'b)(?P<z>c)"),
+            r"(?P<x>a)(?P<y>b)(?P<z>c)"
+        );
+        assert_eq!(tr(pcre, r"a(?#comment)b"), "ab");
+        assert_eq!(tr(pcre, r"\Q.*\E+"), r"\.\*+");
+        assert_eq!(
+            tr(pcre, r"\e\cA\x7\x{263A}\o{101}\012"),
+            r"\x1B\x{1}\x{7}\x{263A}\x{41}\x{A}"
+        );
+        assert_eq!(
+            tr(pcre, r"\h\N"),
+            r"[\t\x20\xA0\x{1680}\x{180E}\x{2000}-\x{200A}\x{202F}\x{205F}\x{3000}][^\n]"
+        );
+        assert_eq!(tr(pcre, r"\p{^Greek}\pL"), r"\P{Greek}\pL");
+        assert_eq!(tr(pcre, r"a{b}c{2,}"), r"a\{b}c{2,}");
+        assert_eq!(
+            tr(pcre, r"[]a&&b~[:alpha:]\b\1-]"),
+            r"[]a\&\&b\~[:alpha:]\x08\x{1}-]"
+        );
+        assert_eq!(tr(pcre, r"\@\-"), r"@\-");
+        assert_eq!(tr(pcre, r"\X+"), r"\X+");
+    }
+
+    #[test]
+    fn translate_posix_ere() {
+        let ere = Dialect::PosixEre;
+        assert_eq!(tr(ere, r"[[:alpha:]\]+"), r"[[:alpha:]\\]+");
+        assert_eq!(tr(ere, r"[[=a=][.-.]x]"), r"[a\-x]");
+        assert_eq!(tr(ere, r"\`a\.b\'"), r"\Aa\.b\z");
+    }
+
+    #[test]
+    fn translate_javascript() {
+        let js = Dialect::JavaScript;
+        assert_eq!(tr(js, r"(?<x>\d)"), r"(?P<x>[0-9])");
+        assert_eq!(tr(js, r"[\w-]"), r"[[0-9A-Za-z_]-]");
+        assert_eq!(
+            tr(js, r"\uD83D\uDE00\u{1F600}\u0041"),
+            r"\x{1F600}\x{1F600}\x{41}"
+        );
+        assert_eq!(tr(js, r"[^]|[]"), r"(?s:.)|[^\x00-\x{10FFFF}]");
+        assert_eq!(tr(js, r"\/\0[\b[]"), r"/\x{0}[\x08\[]");
+    }
+
+    #[test]
+    fn parse_translated() {
+        let ast = parse(Dialect::Pcre, "(?<x>a)\\Qb.\\E");
+        let mut printed = String::new();
+        Printer::new().print(&ast, &mut printed).unwrap();
+        assert_eq!(printed, r"(?P<x>a)b\.");
+
+        // Spans refer to the original pattern.
+        let concat = match ast {
+            ast::Ast::Concat(ref concat) => concat,
+            ref ast => panic!("expected concat, got {:?}", ast),
+        };
+        // The trailing `\E` has no equivalent, so it isn't included.
+        assert_eq!(concat.span, span(0..11));
+        assert_eq!(concat.asts[0].span(), &span(0..7));
+        assert_eq!(concat.asts[1].span(), &span(9..10));
+        assert_eq!(concat.asts[2].span(), &span(10..11));
+    }
+
+    #[test]
+    fn parse_grapheme_cluster() {
+        let ast = parse(Dialect::Pcre, r"a\X");
+        let concat = match ast {
+            ast::Ast::Concat(ref concat) => concat,
+            ref ast => panic!("expected concat, got {:?}", ast),
+        };
+        assert_eq!(concat.asts[1], ast::Ast::GraphemeCluster(span(1..3)));
+    }
+
+    #[test]
+    fn parse_errors() {
+        let err = parse_err(Dialect::Pcre, "a(?>b)");
+        assert_eq!(
+            err.kind(),
+            &ast::ErrorKind::UnsupportedSyntax("atomic groups")
+        );
+        assert_eq!(err.span(), &span(1..4));
+        assert_eq!(err.pattern(), "a(?>b)");
+
+        let err = parse_err(Dialect::Pcre, "a*+");
+        assert_eq!(
+            err.kind(),
+            &ast::ErrorKind::UnsupportedSyntax(
+                "possessive repetition operators"
+            )
+        );
+        assert_eq!(err.span(), &span(1..3));
+
+        let err = parse_err(Dialect::JavaScript, r"(a)\1");
+        assert_eq!(err.kind(), &ast::ErrorKind::UnsupportedBackreference);
+        assert_eq!(err.span(), &span(3..5));
+
+        let err = parse_err(Dialect::JavaScript, "(?<!a)");
+        assert_eq!(err.kind(), &ast::ErrorKind::UnsupportedLookAround);
+
+        // Errors found by the parser are mapped back too.
+        let err = parse_err(Dialect::Pcre, r"\Q.\E(?'n'a)(?<n>b)");
+        assert_eq!(
+            err.kind(),
+            &ast::ErrorKind::GroupNameDuplicate { original: span(8..9) }
+        );
+        assert_eq!(err.span(), &span(15..16));
+    }
+}
@@ -0,0 +1,286 @@
+/*!
+This module provides a human readable explanation of an `Ast`.
+*/
+
+use alloc::string::String;
+use core::fmt;
+
+use ast::print::Printer;
+use ast::visitor::{self, Visitor};
+use ast::{self, Ast};
+
+/// An explainer for a regular expression abstract syntax tree.
+///
+/// An explainer renders an AST as an indented outline in plain English,
+/// with one line per node. Character classes are shown in their concrete
+/// syntax rather than explained item by item. This explainer uses constant
+/// stack space and heap space proportional to the size of the AST.
+#[derive(Debug)]
+pub struct Explainer {
+    _priv: (),
+}
+
+impl Explainer {
+    /// Create a new explainer.
+    pub fn new() -> Explainer {
+        Explainer { _priv: () }
+    }
+
+    /// Write an explanation of the given `Ast` to the given writer. The
+    /// writer must implement `fmt::Write`, e.g., a `&mut String`.
+    pub fn explain<W: fmt::Write>(
+        &mut self,
+        ast: &Ast,
+        wtr: W,
+    ) -> fmt::Result {
+        visitor::visit(ast, Writer { wtr: wtr, depth: 0 })
+    }
+}
+
+#[derive(Debug)]
+struct Writer<W> {
+    wtr: W,
+    /// The current indentation level.
+    depth: usize,
+}
+
+impl<W: fmt::Write> Visitor for Writer<W> {
+    type Output = ();
+    type Err = fmt::Error;
+
+    fn finish(self) -> fmt::Result {
+        Ok(())
+    }
+
+    fn visit_pre(&mut self, ast: &Ast) -> fmt::Result {
+        for _ in 0..self.depth {
+            self.wtr.write_str("  ")?;
+        }
+        match *ast {
+            Ast::Empty(_) => self.wtr.write_str("the empty string")?,
+            Ast::Flags(ref x) => {
+                self.wtr.write_str("set flags for the rest of the group: ")?;
+                self.explain_flags(&x.flags)?;
+            }
+            Ast::Literal(ref x) => write!(self.wtr, "the literal {:?}", x.c)?,
+            Ast::Dot(_) => self.wtr.write_str(
+                "any character except \\n (including \\n if the s flag \
+                 is set)",
+            )?,
+            Ast::GraphemeCluster(_) => {
+                self.wtr.write_str("a single extended grapheme cluster")?
+            }
+            Ast::Assertion(ref x) => self.explain_assertion(x)?,
+            Ast::Class(ref x) => self.explain_class(ast, x)?,
+            Ast::Repetition(ref x) => self.explain_repetition(x)?,
+            Ast::Group(ref x) => self.explain_group(x)?,
+            Ast::Alternation(_) => {
+                self.wtr.write_str("one of the following alternatives:")?
+            }
+            Ast::Concat(_) => {
+                self.wtr.write_str("the following in sequence:")?
+            }
+        }
+        self.wtr.write_str("\n")?;
+        match *ast {
+            Ast::Repetition(_)
+            | Ast::Group(_)
+            | Ast::Alternation(_)
+            | Ast::Concat(_) => self.depth += 1,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn visit_post(&mut self, ast: &Ast) -> fmt::Result {
+        match *ast {
+            Ast::Repetition(_)
+            | Ast::Group(_)
+            | Ast::Alternation(_)
+            | Ast::Concat(_) => self.depth -= 1,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+impl<W: fmt::Write> Writer<W> {
+    fn explain_flags(&mut self, ast: &ast::Flags) -> fmt::Result {
+        use ast::Flag::*;
+
+        let mut enabled = true;
+        let mut first = true;
+        for item in &ast.items {
+            let flag = match item.kind {
+                ast::FlagsItemKind::Negation => {
+                    enabled = false;
+                    continue;
+                }
+                ast::FlagsItemKind::Flag(flag) => flag,
+            };
+            if !first {
+                self.wtr.write_str(", ")?;
+            }
+            first = false;
+            self.wtr.write_str(if enabled {
+                "enable "
+            } else {
+                "disable "
+            })?;
+            self.wtr.write_str(match flag {
+                CaseInsensitive => "case insensitive matching (i)",
+                MultiLine => "multi-line mode (m)",
+                DotMatchesNewLine => "'.' matching \\n (s)",
+                SwapGreed => "swapped greediness (U)",
+                Unicode => "Unicode support (u)",
+                IgnoreWhitespace => "verbose mode (x)",
+            })?;
+        }
+        Ok(())
+    }
+
+    fn explain_assertion(&mut self, ast: &ast::Assertion) -> fmt::Result {
+        use ast::AssertionKind::*;
+
+        self.wtr.write_str(match ast.kind {
+            StartLine => {
+                "the start of the text (or of a line if the m flag is set)"
+            }
+            EndLine => {
+                "the end of the text (or of a line if the m flag is set)"
+            }
+            StartText => "the start of the text",
+            EndText => "the end of the text",
+            WordBoundary => "a word boundary",
+            NotWordBoundary => "a position that is not a word boundary",
+            WordBoundarySegment => "a Unicode (UAX#29) word boundary",
+            NotWordBoundarySegment => {
+                "a position that is not a Unicode (UAX#29) word boundary"
+            }
+        })
+    }
+
+    fn explain_class(&mut self, ast: &Ast, class: &ast::Class) -> fmt::Result {
+        let mut concrete = String::new();
+        Printer::new().print(ast, &mut concrete)?;
+        match *class {
+            ast::Class::Perl(ref x) => {
+                let name = match x.kind {
+                    ast::ClassPerlKind::Digit => "digit",
+                    ast::ClassPerlKind::Space => "whitespace",
+                    ast::ClassPerlKind::Word => "word character",
+                };
+                if x.negated {
+                    write!(self.wtr, "any non-{} ({})", name, concrete)
+                } else {
+                    write!(self.wtr, "any {} ({})", name, concrete)
+                }
+            }
+            ast::Class::Unicode(ref x) => {
+                if x.is_negated() {
+                    write!(self.wtr, "any character not in {}", concrete)
+                } else {
+                    write!(self.wtr, "any character in {}", concrete)
+                }
+            }
+            ast::Class::Bracketed(ref x) => {
+                if x.negated {
+                    write!(self.wtr, "any character not in {}", concrete)
+                } else {
+                    write!(self.wtr, "any character in {}", concrete)
+                }
+            }
+        }
+    }
+
+    fn explain_repetition(&mut self, ast: &ast::Repetition) -> fmt::Result {
+        use ast::RepetitionKind::*;
+        use ast::RepetitionRange::*;
+
+        match ast.op.kind {
+            ZeroOrOne => self.wtr.write_str("optionally")?,
+            ZeroOrMore => self.wtr.write_str("zero or more times")?,
+            OneOrMore => self.wtr.write_str("one or more times")?,
+            Range(Exactly(n)) => write!(self.wtr, "exactly {} times", n)?,
+            Range(AtLeast(n)) => write!(self.wtr, "at least {} times", n)?,
+            Range(Bounded(m, n)) => {
+                write!(self.wtr, "between {} and {} times", m, n)?
+            }
+        }
+        match ast.op.kind {
+            Range(Exactly(_)) => {}
+            _ if ast.greedy => self.wtr.write_str(" (greedy)")?,
+            _ => self.wtr.write_str(" (lazy)")?,
+        }
+        self.wtr.write_str(":")
+    }
+
+    fn explain_group(&mut self, ast: &ast::Group) -> fmt::Result {
+        match ast.kind {
+            ast::GroupKind::CaptureIndex(i) => {
+                write!(self.wtr, "capture group {}:", i)
+            }
+            ast::GroupKind::CaptureName(ref x) => {
+                write!(
+                    self.wtr,
+                    "capture group {} named {:?}:",
+                    x.index, x.name
+                )
+            }
+            ast::GroupKind::NonCapturing(ref flags) => {
+                if flags.items.is_empty() {
+                    self.wtr.write_str("non-capturing group:")
+                } else {
+                    self.wtr.write_str("non-capturing group that will ")?;
+                    self.explain_flags(flags)?;
+                    self.wtr.write_str(":")
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Explainer;
+    use ast::parse::ParserBuilder;
+
+    fn explain(pattern: &str) -> String {
+        let ast = ParserBuilder::new().build().parse(pattern).unwrap();
+        let mut dst = String::new();
+        Explainer::new().explain(&ast, &mut dst).unwrap();
+        dst
+    }
+
+    #[test]
+    fn explain_simple() {
+        assert_eq!(explain("a"), "the literal 'a'\n");
+        assert_eq!(explain(r"\d"), "any digit (\\d)\n");
+        assert_eq!(explain(r"[^a-z]"), "any character not in [^a-z]\n");
+    }
+
+    #[test]
+    fn explain_nested() {
+        let expected = "\
+the following in sequence:
+  the start of the text (or of a line if the m flag is set)
+  capture group 1 named \"x\":
+    one of the following alternatives:
+      the literal 'a'
+      one or more times (lazy):
+        the literal 'b'
+  exactly 2 times:
+    any character in \\pL
+";
+        assert_eq!(explain(r"^(?P<x>a|b+?)\pL{2}"), expected);
+    }
+
+    #[test]
+    fn explain_flags() {
+        assert_eq!(
+            explain("(?i-s:a)"),
+            "non-capturing group that will enable case insensitive \
+             matching (i), disable '.' matching \\n (s):\n  the literal 'a'\n"
+        );
+    }
+}
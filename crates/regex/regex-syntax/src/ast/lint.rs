@@ -0,0 +1,472 @@
+/*!
+This module provides a linter for `Ast`.
+
+A linter reports constructs that are valid regular expression syntax, but
+which are likely mistakes or are needlessly complicated.
+*/
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use ast::visitor::{self, Visitor};
+use ast::{self, Ast, Span};
+
+/// A single lint reported by a [`Linter`](struct.Linter.html).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Lint {
+    /// The kind of this lint.
+    kind: LintKind,
+    /// The span of the concrete syntax that this lint points to.
+    span: Span,
+}
+
+impl Lint {
+    /// Return the type of this lint.
+    pub fn kind(&self) -> &LintKind {
+        &self.kind
+    }
+
+    /// Return the span of the concrete syntax that this lint points to.
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+}
+
+/// The type of a lint.
+///
+/// This enum may grow additional variants, so callers should not count on
+/// exhaustive matching.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LintKind {
+    /// A punctuation character was escaped even though it has no special
+    /// meaning in its position, e.g., `[\.]` or `\-`.
+    RedundantEscape(char),
+    /// An alternation contains an empty alternative, e.g., `a||b` or `(a|)`.
+    /// This is usually a typo, and if it isn't, `a?` is clearer.
+    EmptyAlternative,
+    /// A `.` appears between literals in a way that suggests a domain name
+    /// or file name, e.g., `example.com`. This was probably meant to be
+    /// `example\.com`.
+    UnescapedDot,
+    /// An unbounded repetition is applied directly to another unbounded
+    /// repetition, e.g., `(a+)*`. The inner repetition is redundant.
+    NestedRepetition,
+    /// The case insensitive flag is enabled, but no letters occur in its
+    /// scope, so it has no effect, e.g., `(?i)[0-9]+`.
+    CaseInsensitiveWithoutLetters,
+    /// Hints that destructuring should not be exhaustive.
+    ///
+    /// This enum may grow additional variants, so this makes sure clients
+    /// don't count on exhaustive matching. (Otherwise, adding a new variant
+    /// could break existing code.)
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.kind.fmt(f)
+    }
+}
+
+impl fmt::Display for LintKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::LintKind::*;
+        match *self {
+            RedundantEscape(c) => write!(
+                f,
+                "escaping '{}' is redundant since it is not special here",
+                c
+            ),
+            EmptyAlternative => write!(
+                f,
+                "empty alternative in alternation (use '?' to make an \
+                 expression optional)"
+            ),
+            UnescapedDot => write!(
+                f,
+                "unescaped '.' matches any character (use '\\.' to match \
+                 a literal '.')"
+            ),
+            NestedRepetition => write!(
+                f,
+                "unbounded repetition of an unbounded repetition is \
+                 redundant"
+            ),
+            CaseInsensitiveWithoutLetters => write!(
+                f,
+                "case insensitive flag has no effect since there are no \
+                 letters in its scope"
+            ),
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// A linter for a regular expression abstract syntax tree.
+///
+/// A linter uses constant stack space and heap space proportional to the
+/// size of the AST.
+#[derive(Debug)]
+pub struct Linter {
+    _priv: (),
+}
+
+impl Linter {
+    /// Create a new linter.
+    pub fn new() -> Linter {
+        Linter { _priv: () }
+    }
+
+    /// Lint the given `Ast` and return all lints found, sorted by the start
+    /// of their spans.
+    pub fn lint(&mut self, ast: &Ast) -> Vec<Lint> {
+        let lints = visitor::visit(
+            ast,
+            LintVisitor {
+                lints: vec![],
+                groups: vec![vec![]],
+                scopes: vec![],
+            },
+        );
+        // Our visitor never returns an error.
+        let mut lints = lints.unwrap();
+        lints.sort_by(|a, b| a.span.cmp(&b.span));
+        lints
+    }
+}
+
+/// The scope of a case insensitive flag.
+#[derive(Debug)]
+struct CaseScope {
+    /// The span of the `i` flag item.
+    span: Span,
+    /// Whether a letter has been seen in this scope.
+    saw_letter: bool,
+}
+
+#[derive(Debug)]
+struct LintVisitor {
+    /// All lints found so far.
+    lints: Vec<Lint>,
+    /// A stack of groups, where each group records the indices into
+    /// `scopes` for all case insensitive flags that end with the group.
+    groups: Vec<Vec<usize>>,
+    /// All case insensitive flag scopes found so far.
+    scopes: Vec<CaseScope>,
+}
+
+impl Visitor for LintVisitor {
+    type Output = Vec<Lint>;
+    type Err = ();
+
+    fn finish(mut self) -> Result<Vec<Lint>, ()> {
+        self.close_group();
+        Ok(self.lints)
+    }
+
+    fn visit_pre(&mut self, ast: &Ast) -> Result<(), ()> {
+        match *ast {
+            Ast::Group(ref x) => {
+                self.groups.push(vec![]);
+                if let Some(flags) = x.flags() {
+                    self.open_case_scope(flags);
+                }
+            }
+            Ast::Alternation(ref x) => {
+                for ast in &x.asts {
+                    if let Ast::Empty(span) = *ast {
+                        self.push(LintKind::EmptyAlternative, span);
+                    }
+                }
+            }
+            Ast::Concat(ref x) => self.check_dots(&x.asts),
+            Ast::Repetition(ref x) => {
+                if is_unbounded(x) {
+                    let mut inner = &*x.ast;
+                    while let Ast::Group(ref g) = *inner {
+                        inner = &*g.ast;
+                    }
+                    if let Ast::Repetition(ref inner) = *inner {
+                        if is_unbounded(inner) {
+                            self.push(LintKind::NestedRepetition, x.span);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn visit_post(&mut self, ast: &Ast) -> Result<(), ()> {
+        match *ast {
+            Ast::Group(_) => self.close_group(),
+            Ast::Flags(ref x) => self.open_case_scope(&x.flags),
+            Ast::Literal(ref x) => {
+                if x.kind == ast::LiteralKind::Punctuation
+                    && !needs_escape(x.c)
+                {
+                    self.push(LintKind::RedundantEscape(x.c), x.span);
+                }
+                if x.c.is_alphabetic() {
+                    self.saw_letter();
+                }
+            }
+            Ast::Class(ast::Class::Perl(ref x)) => {
+                if x.kind == ast::ClassPerlKind::Word {
+                    self.saw_letter();
+                }
+            }
+            Ast::Class(ast::Class::Unicode(_)) => self.saw_letter(),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn visit_class_set_item_post(
+        &mut self,
+        ast: &ast::ClassSetItem,
+    ) -> Result<(), ()> {
+        use ast::ClassSetItem::*;
+
+        match *ast {
+            Literal(ref x) => {
+                if x.kind == ast::LiteralKind::Punctuation
+                    && !needs_class_escape(x.c)
+                {
+                    self.push(LintKind::RedundantEscape(x.c), x.span);
+                }
+                if x.c.is_alphabetic() {
+                    self.saw_letter();
+                }
+            }
+            Range(ref x) => {
+                if range_has_letter(x.start.c, x.end.c) {
+                    self.saw_letter();
+                }
+            }
+            Ascii(ref x) => {
+                if ascii_class_has_letter(&x.kind) {
+                    self.saw_letter();
+                }
+            }
+            Unicode(_) => self.saw_letter(),
+            Perl(ref x) => {
+                if x.kind == ast::ClassPerlKind::Word {
+                    self.saw_letter();
+                }
+            }
+            Empty(_) | Bracketed(_) | Union(_) => {}
+        }
+        Ok(())
+    }
+}
+
+impl LintVisitor {
+    fn push(&mut self, kind: LintKind, span: Span) {
+        self.lints.push(Lint { kind: kind, span: span });
+    }
+
+    /// If the given flags enable case insensitivity, then open a new scope
+    /// that ends with the current group.
+    fn open_case_scope(&mut self, flags: &ast::Flags) {
+        if flags.flag_state(ast::Flag::CaseInsensitive) != Some(true) {
+            return;
+        }
+        let item = flags.items.iter().find(|item| {
+            item.kind == ast::FlagsItemKind::Flag(ast::Flag::CaseInsensitive)
+        });
+        let span = match item {
+            None => return,
+            Some(item) => item.span,
+        };
+        self.scopes.push(CaseScope { span: span, saw_letter: false });
+        let i = self.scopes.len() - 1;
+        self.groups.last_mut().unwrap().push(i);
+    }
+
+    /// Close all case insensitive scopes opened in the current group.
+    fn close_group(&mut self) {
+        let group = match self.groups.pop() {
+            None => return,
+            Some(group) => group,
+        };
+        for i in group {
+            if !self.scopes[i].saw_letter {
+                let span = self.scopes[i].span;
+                self.push(LintKind::CaseInsensitiveWithoutLetters, span);
+            }
+        }
+    }
+
+    /// Record that a letter was seen in every scope that is currently open.
+    fn saw_letter(&mut self) {
+        for group in &self.groups {
+            for &i in group {
+                self.scopes[i].saw_letter = true;
+            }
+        }
+    }
+
+    /// Look for `.` sandwiched between literals in a way that resembles a
+    /// domain name, e.g., `example.com`.
+    fn check_dots(&mut self, asts: &[Ast]) {
+        for (i, ast) in asts.iter().enumerate() {
+            let span = match *ast {
+                Ast::Dot(span) => span,
+                _ => continue,
+            };
+            let before =
+                i > 0 && is_verbatim(&asts[i - 1], char::is_alphanumeric);
+            let after = asts.len() > i + 2
+                && is_verbatim(&asts[i + 1], char::is_alphabetic)
+                && is_verbatim(&asts[i + 2], char::is_alphabetic);
+            if before && after {
+                self.push(LintKind::UnescapedDot, span);
+            }
+        }
+    }
+}
+
+/// Returns true if and only if the given AST is a verbatim literal whose
+/// character satisfies the given predicate.
+fn is_verbatim<F: Fn(char) -> bool>(ast: &Ast, pred: F) -> bool {
+    match *ast {
+        Ast::Literal(ref x) => {
+            x.kind == ast::LiteralKind::Verbatim && pred(x.c)
+        }
+        _ => false,
+    }
+}
+
+/// Returns true if and only if the given repetition has no upper bound.
+fn is_unbounded(rep: &ast::Repetition) -> bool {
+    match rep.op.kind {
+        ast::RepetitionKind::ZeroOrMore
+        | ast::RepetitionKind::OneOrMore
+        | ast::RepetitionKind::Range(ast::RepetitionRange::AtLeast(_)) => true,
+        _ => false,
+    }
+}
+
+/// Returns true if and only if the given ASCII class contains a letter.
+fn ascii_class_has_letter(kind: &ast::ClassAsciiKind) -> bool {
+    use ast::ClassAsciiKind::*;
+    match *kind {
+        Alnum | Alpha | Ascii | Graph | Lower | Print | Upper | Word
+        | Xdigit => true,
+        Blank | Cntrl | Digit | Punct | Space => false,
+    }
+}
+
+/// Returns true if and only if the given range contains an ASCII letter or
+/// any non-ASCII character. (Non-ASCII characters are conservatively
+/// assumed to contain letters.)
+fn range_has_letter(start: char, end: char) -> bool {
+    (start <= 'Z' && end >= 'A')
+        || (start <= 'z' && end >= 'a')
+        || end > '\x7F'
+}
+
+/// Returns true if and only if the given meta character must be escaped to
+/// be matched literally outside of a character class.
+///
+/// `#` is considered as requiring an escape since it starts a comment when
+/// the `x` flag is enabled.
+fn needs_escape(c: char) -> bool {
+    match c {
+        '\\' | '.' | '+' | '*' | '?' | '(' | ')' | '|' | '[' | ']' | '{'
+        | '}' | '^' | '$' | '#' => true,
+        _ => false,
+    }
+}
+
+/// Returns true if and only if the given meta character must be escaped to
+/// be matched literally inside of a character class.
+///
+/// This is conservative: `-` and `^` only need escaping in certain
+/// positions, but escaping them is never reported as redundant.
+fn needs_class_escape(c: char) -> bool {
+    match c {
+        '\\' | '[' | ']' | '^' | '-' | '&' | '~' | '#' => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LintKind, Linter};
+    use ast::parse::ParserBuilder;
+
+    fn lints(pattern: &str) -> Vec<(LintKind, usize, usize)> {
+        let ast = ParserBuilder::new().build().parse(pattern).unwrap();
+        Linter::new()
+            .lint(&ast)
+            .into_iter()
+            .map(|l| {
+                (l.kind().clone(), l.span().start.offset, l.span().end.offset)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lint_clean() {
+        assert_eq!(lints(r"[a-z]+\.(com|org)"), vec![]);
+        assert_eq!(lints(r"(?i)abc[0-9]"), vec![]);
+        assert_eq!(lints(r"a.b"), vec![]);
+        assert_eq!(lints(r"(a+)?"), vec![]);
+        assert_eq!(lints(r"[\]\-\^]"), vec![]);
+    }
+
+    #[test]
+    fn lint_redundant_escape() {
+        assert_eq!(
+            lints(r"[\.]"),
+            vec![(LintKind::RedundantEscape('.'), 1, 3)]
+        );
+        assert_eq!(
+            lints(r"a\-b"),
+            vec![(LintKind::RedundantEscape('-'), 1, 3)]
+        );
+        assert_eq!(lints(r"\.\*"), vec![]);
+    }
+
+    #[test]
+    fn lint_empty_alternative() {
+        assert_eq!(lints(r"a||b"), vec![(LintKind::EmptyAlternative, 2, 2)]);
+        assert_eq!(lints(r"(a|)"), vec![(LintKind::EmptyAlternative, 3, 3)]);
+    }
+
+    #[test]
+    fn lint_unescaped_dot() {
+        assert_eq!(
+            lints(r"@example.com"),
+            vec![(LintKind::UnescapedDot, 8, 9)]
+        );
+    }
+
+    #[test]
+    fn lint_nested_repetition() {
+        assert_eq!(lints(r"(a+)*"), vec![(LintKind::NestedRepetition, 0, 5)]);
+        assert_eq!(
+            lints(r"(?:a*){2,}"),
+            vec![(LintKind::NestedRepetition, 0, 10)]
+        );
+        assert_eq!(lints(r"(a{2})*"), vec![]);
+    }
+
+    #[test]
+    fn lint_case_insensitive() {
+        assert_eq!(
+            lints(r"(?i)[0-9]+"),
+            vec![(LintKind::CaseInsensitiveWithoutLetters, 2, 3)]
+        );
+        assert_eq!(
+            lints(r"(?i:\d)a"),
+            vec![(LintKind::CaseInsensitiveWithoutLetters, 2, 3)]
+        );
+        assert_eq!(lints(r"(?i:\w)"), vec![]);
+        assert_eq!(lints(r"(?-i)1"), vec![]);
+    }
+}
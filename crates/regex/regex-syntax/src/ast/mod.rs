@@ -2,12 +2,20 @@
 Defines an abstract syntax for regular expressions.
 */
 
-use std::cmp::Ordering;
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+#[cfg(feature = "std")]
 use std::error;
-use std::fmt;
 
-pub use ast::visitor::{visit, Visitor};
+pub use ast::dialect::Dialect;
+pub use ast::visitor::{visit, visit_mut, Visitor, VisitorMut};
 
+mod dialect;
+pub mod explain;
+pub mod lint;
 pub mod parse;
 pub mod print;
 mod visitor;
@@ -156,6 +164,13 @@ pub enum ErrorKind {
     /// `(?i)*`. It is, however, possible to create a repetition operating on
     /// an empty sub-expression. For example, `()*` is still considered valid.
     RepetitionMissing,
+    /// An opening `{` following `\b` or `\B` was found with no corresponding
+    /// closing `}`, e.g., `\b{wb`.
+    SpecialWordBoundaryUnclosed,
+    /// The name of a special word boundary assertion was not recognized. For
+    /// example, `\b{wb}` is a valid special word boundary but `\b{foo}` is
+    /// not.
+    SpecialWordBoundaryUnrecognized,
     /// When octal support is disabled, this error is produced when an octal
     /// escape is used. The octal escape is assumed to be an invocation of
     /// a backreference, which is the common case.
@@ -166,6 +181,12 @@ pub enum ErrorKind {
     /// `(?<!re)`. Note that all of these syntaxes are otherwise invalid; this
     /// error is used to improve the user experience.
     UnsupportedLookAround,
+    /// This error is returned when a pattern written in another
+    /// [`Dialect`](enum.Dialect.html) uses syntax that has no equivalent in
+    /// this crate, such as PCRE's atomic groups (`(?>re)`) or possessive
+    /// repetition operators (`a*+`). The string describes the unsupported
+    /// syntax.
+    UnsupportedSyntax(&'static str),
     /// Hints that destructuring should not be exhaustive.
     ///
     /// This enum may grow additional variants, so this makes sure clients
@@ -175,6 +196,7 @@ pub enum ErrorKind {
     __Nonexhaustive,
 }
 
+#[cfg(feature = "std")]
 impl error::Error for Error {
     fn description(&self) -> &str {
         use self::ErrorKind::*;
@@ -206,8 +228,13 @@ impl error::Error for Error {
             RepetitionCountInvalid => "invalid repetition count range",
             RepetitionCountUnclosed => "unclosed counted repetition",
             RepetitionMissing => "repetition operator missing expression",
+            SpecialWordBoundaryUnclosed => "unclosed special word boundary",
+            SpecialWordBoundaryUnrecognized => {
+                "unrecognized special word boundary"
+            }
             UnsupportedBackreference => "backreferences are not supported",
             UnsupportedLookAround => "look-around is not supported",
+            UnsupportedSyntax(_) => "unsupported syntax",
             _ => unreachable!(),
         }
     }
@@ -227,7 +254,7 @@ impl fmt::Display for ErrorKind {
                 f,
                 "exceeded the maximum number of \
                  capturing groups ({})",
-                ::std::u32::MAX
+                ::core::u32::MAX
             ),
             ClassEscapeInvalid => {
                 write!(f, "invalid escape sequence found in character class")
@@ -293,6 +320,16 @@ impl fmt::Display for ErrorKind {
             RepetitionMissing => {
                 write!(f, "repetition operator missing expression")
             }
+            SpecialWordBoundaryUnclosed => write!(
+                f,
+                "special word boundary assertion is either \
+                 unclosed or contains an invalid character"
+            ),
+            SpecialWordBoundaryUnrecognized => write!(
+                f,
+                "unrecognized special word boundary assertion, \
+                 valid choices are: wb"
+            ),
             UnsupportedBackreference => {
                 write!(f, "backreferences are not supported")
             }
@@ -301,6 +338,7 @@ impl fmt::Display for ErrorKind {
                 "look-around, including look-ahead and look-behind, \
                  is not supported"
             ),
+            UnsupportedSyntax(what) => write!(f, "{} are not supported", what),
             _ => unreachable!(),
         }
     }
@@ -466,6 +504,8 @@ pub enum Ast {
     Literal(Literal),
     /// The "any character" class.
     Dot(Span),
+    /// A single extended grapheme cluster, `\X`.
+    GraphemeCluster(Span),
     /// A single zero-width assertion.
     Assertion(Assertion),
     /// A single character class. This includes all forms of character classes
@@ -489,6 +529,7 @@ impl Ast {
             Ast::Flags(ref x) => &x.span,
             Ast::Literal(ref x) => &x.span,
             Ast::Dot(ref span) => span,
+            Ast::GraphemeCluster(ref span) => span,
             Ast::Assertion(ref x) => &x.span,
             Ast::Class(ref x) => x.span(),
             Ast::Repetition(ref x) => &x.span,
@@ -514,6 +555,7 @@ impl Ast {
             | Ast::Flags(_)
             | Ast::Literal(_)
             | Ast::Dot(_)
+            | Ast::GraphemeCluster(_)
             | Ast::Assertion(_) => false,
             Ast::Class(_)
             | Ast::Repetition(_)
@@ -1103,6 +1145,10 @@ pub enum AssertionKind {
     WordBoundary,
     /// `\B`
     NotWordBoundary,
+    /// `\b{wb}`
+    WordBoundarySegment,
+    /// `\B{wb}`
+    NotWordBoundarySegment,
 }
 
 /// A repetition operation applied to a regular expression.
@@ -1349,13 +1395,14 @@ pub enum Flag {
 /// space but heap space proportional to the depth of the `Ast`.
 impl Drop for Ast {
     fn drop(&mut self) {
-        use std::mem;
+        use core::mem;
 
         match *self {
             Ast::Empty(_)
             | Ast::Flags(_)
             | Ast::Literal(_)
             | Ast::Dot(_)
+            | Ast::GraphemeCluster(_)
             | Ast::Assertion(_)
             // Classes are recursive, so they get their own Drop impl.
             | Ast::Class(_) => return,
@@ -1375,6 +1422,7 @@ impl Drop for Ast {
                 | Ast::Flags(_)
                 | Ast::Literal(_)
                 | Ast::Dot(_)
+                | Ast::GraphemeCluster(_)
                 | Ast::Assertion(_)
                 // Classes are recursive, so they get their own Drop impl.
                 | Ast::Class(_) => {}
@@ -1399,7 +1447,7 @@ impl Drop for Ast {
 /// stack space but heap space proportional to the depth of the `ClassSet`.
 impl Drop for ClassSet {
     fn drop(&mut self) {
-        use std::mem;
+        use core::mem;
 
         match *self {
             ClassSet::Item(ref item) => match *item {
@@ -2,11 +2,15 @@
 This module provides a regular expression parser.
 */
 
-use std::borrow::Borrow;
-use std::cell::{Cell, RefCell};
-use std::mem;
-use std::result;
-
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::cell::{Cell, RefCell};
+use core::mem;
+use core::result;
+
+use ast::dialect::{self, Dialect};
 use ast::{self, Ast, Position, Span};
 use either::Either;
 
@@ -25,6 +29,7 @@ enum Primitive {
     Literal(ast::Literal),
     Assertion(ast::Assertion),
     Dot(Span),
+    GraphemeCluster(Span),
     Perl(ast::ClassPerl),
     Unicode(ast::ClassUnicode),
 }
@@ -36,6 +41,7 @@ impl Primitive {
             Primitive::Literal(ref x) => &x.span,
             Primitive::Assertion(ref x) => &x.span,
             Primitive::Dot(ref span) => span,
+            Primitive::GraphemeCluster(ref span) => span,
             Primitive::Perl(ref x) => &x.span,
             Primitive::Unicode(ref x) => &x.span,
         }
@@ -47,6 +53,7 @@ impl Primitive {
             Primitive::Literal(lit) => Ast::Literal(lit),
             Primitive::Assertion(assert) => Ast::Assertion(assert),
             Primitive::Dot(span) => Ast::Dot(span),
+            Primitive::GraphemeCluster(span) => Ast::GraphemeCluster(span),
             Primitive::Perl(cls) => Ast::Class(ast::Class::Perl(cls)),
             Primitive::Unicode(cls) => Ast::Class(ast::Class::Unicode(cls)),
         }
@@ -114,6 +121,7 @@ pub struct ParserBuilder {
     ignore_whitespace: bool,
     nest_limit: u32,
     octal: bool,
+    dialect: Dialect,
 }
 
 impl Default for ParserBuilder {
@@ -129,6 +137,7 @@ impl ParserBuilder {
             ignore_whitespace: false,
             nest_limit: 250,
             octal: false,
+            dialect: Dialect::Rust,
         }
     }
 
@@ -146,6 +155,7 @@ impl ParserBuilder {
             stack_class: RefCell::new(vec![]),
             capture_names: RefCell::new(vec![]),
             scratch: RefCell::new(String::new()),
+            dialect: self.dialect,
         }
     }
 
@@ -211,6 +221,17 @@ impl ParserBuilder {
         self.ignore_whitespace = yes;
         self
     }
+
+    /// Set the concrete syntax that patterns are written in.
+    ///
+    /// When set to anything other than `Dialect::Rust` (the default),
+    /// patterns are translated into this crate's syntax before being parsed.
+    /// All spans in the resulting AST, and in any error, refer to the
+    /// pattern as given. See [`Dialect`](../enum.Dialect.html) for details.
+    pub fn dialect(&mut self, dialect: Dialect) -> &mut ParserBuilder {
+        self.dialect = dialect;
+        self
+    }
 }
 
 /// A regular expression parser.
@@ -253,6 +274,8 @@ pub struct Parser {
     /// A scratch buffer used in various places. Mostly this is used to
     /// accumulate relevant characters from parts of a pattern.
     scratch: RefCell<String>,
+    /// The concrete syntax that patterns are written in.
+    dialect: Dialect,
 }
 
 /// ParserI is the internal parser implementation.
@@ -334,7 +357,10 @@ impl Parser {
 
     /// Parse the regular expression into an abstract syntax tree.
     pub fn parse(&mut self, pattern: &str) -> Result<Ast> {
-        ParserI::new(self, pattern).parse()
+        match self.dialect {
+            Dialect::Rust => ParserI::new(self, pattern).parse(),
+            _ => self.parse_with_comments(pattern).map(|astc| astc.ast),
+        }
     }
 
     /// Parse the regular expression and return an abstract syntax tree with
@@ -343,7 +369,15 @@ impl Parser {
         &mut self,
         pattern: &str,
     ) -> Result<ast::WithComments> {
-        ParserI::new(self, pattern).parse_with_comments()
+        match self.dialect {
+            Dialect::Rust => ParserI::new(self, pattern).parse_with_comments(),
+            dialect => {
+                let translation = dialect::translate(dialect, pattern)?;
+                let result = ParserI::new(&*self, translation.pattern())
+                    .parse_with_comments();
+                translation.remap(result)
+            }
+        }
     }
 
     /// Reset the internal state of a parser.
@@ -1523,6 +1557,9 @@ impl<'s, P: Borrow<Parser>> ParserI<'s, P> {
                 span: span,
                 kind: ast::AssertionKind::EndText,
             })),
+            'b' | 'B' if self.is_special_word_boundary() => {
+                self.parse_special_word_boundary(start, c == 'B')
+            }
             'b' => Ok(Primitive::Assertion(ast::Assertion {
                 span: span,
                 kind: ast::AssertionKind::WordBoundary,
@@ -1531,10 +1568,66 @@ impl<'s, P: Borrow<Parser>> ParserI<'s, P> {
                 span: span,
                 kind: ast::AssertionKind::NotWordBoundary,
             })),
+            'X' => Ok(Primitive::GraphemeCluster(span)),
             _ => Err(self.error(span, ast::ErrorKind::EscapeUnrecognized)),
         }
     }
 
+    /// Returns true if and only if the parser is positioned at the opening
+    /// brace of a special word boundary assertion such as `\b{wb}`, assuming
+    /// that the `\b` or `\B` preceding it has already been parsed.
+    ///
+    /// A `{` that is not immediately followed by an ASCII letter is not
+    /// treated as the start of a special word boundary. This preserves the
+    /// meaning of repetition operators applied to word boundaries, e.g.,
+    /// `\b{2}`.
+    fn is_special_word_boundary(&self) -> bool {
+        !self.is_eof()
+            && self.char() == '{'
+            && self.peek().map_or(false, |c| c.is_ascii_alphabetic())
+    }
+
+    /// Parse a special word boundary assertion such as `\b{wb}`. This
+    /// expects the parser to be positioned at the opening `{` and advances
+    /// the parser to the character immediately following the closing `}`.
+    ///
+    /// `start` should be the position of the `\` that started the escape and
+    /// `negated` should be true for `\B{...}`.
+    #[inline(never)]
+    fn parse_special_word_boundary(
+        &self,
+        start: Position,
+        negated: bool,
+    ) -> Result<Primitive> {
+        assert_eq!(self.char(), '{');
+        let name_start = self.pos().offset + 1;
+        while self.bump() && self.char() != '}' {
+            if !self.char().is_ascii_alphabetic() {
+                break;
+            }
+        }
+        if self.is_eof() || self.char() != '}' {
+            return Err(self.error(
+                Span::new(start, self.pos()),
+                ast::ErrorKind::SpecialWordBoundaryUnclosed,
+            ));
+        }
+        let name = &self.pattern()[name_start..self.pos().offset];
+        self.bump();
+        let span = Span::new(start, self.pos());
+        let kind = match name {
+            "wb" if negated => ast::AssertionKind::NotWordBoundarySegment,
+            "wb" => ast::AssertionKind::WordBoundarySegment,
+            _ => {
+                return Err(self.error(
+                    span,
+                    ast::ErrorKind::SpecialWordBoundaryUnrecognized,
+                ))
+            }
+        };
+        Ok(Primitive::Assertion(ast::Assertion { span: span, kind: kind }))
+    }
+
     /// Parse an octal representation of a Unicode codepoint up to 3 digits
     /// long. This expects the parser to be positioned at the first octal
     /// digit and advances the parser to the first character immediately
@@ -1544,8 +1637,8 @@ impl<'s, P: Borrow<Parser>> ParserI<'s, P> {
     /// Assuming the preconditions are met, this routine can never fail.
     #[inline(never)]
     fn parse_octal(&self) -> ast::Literal {
-        use std::char;
-        use std::u32;
+        use core::char;
+        use core::u32;
 
         assert!(self.parser().octal);
         assert!('0' <= self.char() && self.char() <= '7');
@@ -1611,8 +1704,8 @@ impl<'s, P: Borrow<Parser>> ParserI<'s, P> {
         &self,
         kind: ast::HexLiteralKind,
     ) -> Result<ast::Literal> {
-        use std::char;
-        use std::u32;
+        use core::char;
+        use core::u32;
 
         let mut scratch = self.parser().scratch.borrow_mut();
         scratch.clear();
@@ -1657,8 +1750,8 @@ impl<'s, P: Borrow<Parser>> ParserI<'s, P> {
         &self,
         kind: ast::HexLiteralKind,
     ) -> Result<ast::Literal> {
-        use std::char;
-        use std::u32;
+        use core::char;
+        use core::u32;
 
         let mut scratch = self.parser().scratch.borrow_mut();
         scratch.clear();
@@ -2151,7 +2244,7 @@ impl<'p, 's, P: Borrow<Parser>> NestLimiter<'p, 's, P> {
         let new = self.depth.checked_add(1).ok_or_else(|| {
             self.p.error(
                 span.clone(),
-                ast::ErrorKind::NestLimitExceeded(::std::u32::MAX),
+                ast::ErrorKind::NestLimitExceeded(::core::u32::MAX),
             )
         })?;
         let limit = self.p.parser().nest_limit;
@@ -2186,6 +2279,7 @@ impl<'p, 's, P: Borrow<Parser>> ast::Visitor for NestLimiter<'p, 's, P> {
             | Ast::Flags(_)
             | Ast::Literal(_)
             | Ast::Dot(_)
+            | Ast::GraphemeCluster(_)
             | Ast::Assertion(_)
             | Ast::Class(ast::Class::Unicode(_))
             | Ast::Class(ast::Class::Perl(_)) => {
@@ -2207,6 +2301,7 @@ impl<'p, 's, P: Borrow<Parser>> ast::Visitor for NestLimiter<'p, 's, P> {
             | Ast::Flags(_)
             | Ast::Literal(_)
             | Ast::Dot(_)
+            | Ast::GraphemeCluster(_)
             | Ast::Assertion(_)
             | Ast::Class(ast::Class::Unicode(_))
             | Ast::Class(ast::Class::Perl(_)) => {
@@ -2302,7 +2397,7 @@ fn specialize_err<T>(
 
 #[cfg(test)]
 mod tests {
-    use std::ops::Range;
+    use core::ops::Range;
 
     use super::{Parser, ParserBuilder, ParserI, Primitive};
     use ast::{self, Ast, Position, Span};
@@ -4208,6 +4303,58 @@ bar
         );
     }
 
+    #[test]
+    fn parse_special_word_boundary() {
+        assert_eq!(
+            parser(r"\b{wb}").parse_primitive(),
+            Ok(Primitive::Assertion(ast::Assertion {
+                span: span(0..6),
+                kind: ast::AssertionKind::WordBoundarySegment,
+            }))
+        );
+        assert_eq!(
+            parser(r"\B{wb}").parse_primitive(),
+            Ok(Primitive::Assertion(ast::Assertion {
+                span: span(0..6),
+                kind: ast::AssertionKind::NotWordBoundarySegment,
+            }))
+        );
+        // A counted repetition is still a counted repetition.
+        assert_eq!(
+            parser(r"\b{2}").parse_primitive(),
+            Ok(Primitive::Assertion(ast::Assertion {
+                span: span(0..2),
+                kind: ast::AssertionKind::WordBoundary,
+            }))
+        );
+        assert_eq!(
+            parser(r"\X").parse_primitive(),
+            Ok(Primitive::GraphemeCluster(span(0..2)))
+        );
+
+        assert_eq!(
+            parser(r"\b{wb").parse_escape().unwrap_err(),
+            TestError {
+                span: span(0..5),
+                kind: ast::ErrorKind::SpecialWordBoundaryUnclosed,
+            }
+        );
+        assert_eq!(
+            parser(r"\b{w-b}").parse_escape().unwrap_err(),
+            TestError {
+                span: span(0..4),
+                kind: ast::ErrorKind::SpecialWordBoundaryUnclosed,
+            }
+        );
+        assert_eq!(
+            parser(r"\b{foo}").parse_escape().unwrap_err(),
+            TestError {
+                span: span(0..7),
+                kind: ast::ErrorKind::SpecialWordBoundaryUnrecognized,
+            }
+        );
+    }
+
     #[test]
     fn parse_unsupported_backreference() {
         assert_eq!(
@@ -4235,7 +4382,7 @@ bar
                 Ok(Primitive::Literal(ast::Literal {
                     span: span(0..pat.len()),
                     kind: ast::LiteralKind::Octal,
-                    c: ::std::char::from_u32(i).unwrap(),
+                    c: ::core::char::from_u32(i).unwrap(),
                 }))
             );
         }
@@ -4310,7 +4457,7 @@ bar
                 Ok(Primitive::Literal(ast::Literal {
                     span: span(0..pat.len()),
                     kind: ast::LiteralKind::HexFixed(ast::HexLiteralKind::X),
-                    c: ::std::char::from_u32(i).unwrap(),
+                    c: ::core::char::from_u32(i).unwrap(),
                 }))
             );
         }
@@ -4341,7 +4488,7 @@ bar
     #[test]
     fn parse_hex_four() {
         for i in 0..65536 {
-            let c = match ::std::char::from_u32(i) {
+            let c = match ::core::char::from_u32(i) {
                 None => continue,
                 Some(c) => c,
             };
@@ -4405,7 +4552,7 @@ bar
     #[test]
     fn parse_hex_eight() {
         for i in 0..65536 {
-            let c = match ::std::char::from_u32(i) {
+            let c = match ::core::char::from_u32(i) {
                 None => continue,
                 Some(c) => c,
             };
@@ -2,7 +2,9 @@
 This module provides a regular expression printer for `Ast`.
 */
 
-use std::fmt;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec;
+use core::fmt;
 
 use ast::visitor::{self, Visitor};
 use ast::{self, Ast};
@@ -38,9 +40,10 @@ impl PrinterBuilder {
 /// pattern string. This particular printer uses constant stack space and heap
 /// space proportional to the size of the AST.
 ///
-/// This printer will not necessarily preserve the original formatting of the
-/// regular expression pattern string. For example, all whitespace and comments
-/// are ignored.
+/// The `print` method will not necessarily preserve the original formatting
+/// of the regular expression pattern string. For example, all whitespace and
+/// comments are ignored. When the original pattern is available, the
+/// `print_preserving` method can be used instead.
 #[derive(Debug)]
 pub struct Printer {
     _priv: (),
@@ -57,7 +60,105 @@ impl Printer {
     /// here are a `fmt::Formatter` (which is available in `fmt::Display`
     /// implementations) or a `&mut String`.
     pub fn print<W: fmt::Write>(&mut self, ast: &Ast, wtr: W) -> fmt::Result {
-        visitor::visit(ast, Writer { printer: self, wtr: wtr })
+        visitor::visit(ast, Writer::new(self, None, wtr))
+    }
+
+    /// Print the given `Ast` to the given writer, preserving the formatting
+    /// of `pattern` wherever possible.
+    ///
+    /// `original` must be the `Ast` that was parsed from `pattern`, and `ast`
+    /// is typically a rewritten copy of it (for example, by a
+    /// [`VisitorMut`](../trait.VisitorMut.html)). Every subtree of `ast` that
+    /// is identical to a subtree of `original` at the same span is printed
+    /// exactly as it was written in `pattern`, including any whitespace and
+    /// comments in `x` mode. Whitespace and comments between two such
+    /// subtrees that were adjacent in the same concatenation are kept too.
+    /// Everything else is printed as with `print`.
+    ///
+    /// Note that subtrees are copied as written, so their meaning may change
+    /// if the rewrite changed the flags in effect around them. For example,
+    /// removing an `(?x)` flag from the start of a pattern changes how any
+    /// whitespace after it is interpreted.
+    pub fn print_preserving<W: fmt::Write>(
+        &mut self,
+        ast: &Ast,
+        original: &Ast,
+        pattern: &str,
+        wtr: W,
+    ) -> fmt::Result {
+        let original = Original::new(original, pattern);
+        visitor::visit(ast, Writer::new(self, Some(&original), wtr))
+    }
+}
+
+/// An index of the nodes in the original `Ast` of a pattern, used to find
+/// subtrees that were left unchanged by a rewrite.
+#[derive(Debug)]
+struct Original<'o> {
+    pattern: &'o str,
+    /// Every node in the original `Ast`, keyed by the offsets of its span.
+    nodes: BTreeMap<(usize, usize), Vec<&'o Ast>>,
+    /// Pairs of offsets between which the original pattern can only contain
+    /// whitespace or comments. These are the offsets between every pair of
+    /// adjacent nodes in a concatenation, and between the start and end of a
+    /// concatenation and its first and last nodes.
+    gaps: BTreeSet<(usize, usize)>,
+}
+
+impl<'o> Original<'o> {
+    fn new(ast: &'o Ast, pattern: &'o str) -> Original<'o> {
+        let mut original = Original {
+            pattern: pattern,
+            nodes: BTreeMap::new(),
+            gaps: BTreeSet::new(),
+        };
+        // Like everything else in this crate, use constant stack space.
+        let mut stack = vec![ast];
+        while let Some(ast) = stack.pop() {
+            let span = ast.span();
+            original
+                .nodes
+                .entry((span.start.offset, span.end.offset))
+                .or_insert_with(Vec::new)
+                .push(ast);
+            match *ast {
+                Ast::Repetition(ref x) => stack.push(&x.ast),
+                Ast::Group(ref x) => stack.push(&x.ast),
+                Ast::Alternation(ref x) => stack.extend(&x.asts),
+                Ast::Concat(ref x) => {
+                    let mut end = x.span.start.offset;
+                    for ast in &x.asts {
+                        original.gaps.insert((end, ast.span().start.offset));
+                        end = ast.span().end.offset;
+                    }
+                    original.gaps.insert((end, x.span.end.offset));
+                    stack.extend(&x.asts);
+                }
+                _ => {}
+            }
+        }
+        original
+    }
+
+    /// Return the original text of the given node, if the original `Ast` has
+    /// an identical node at the same span.
+    fn source(&self, ast: &Ast) -> Option<&'o str> {
+        let span = ast.span();
+        let (start, end) = (span.start.offset, span.end.offset);
+        let nodes = self.nodes.get(&(start, end))?;
+        if !nodes.contains(&ast) {
+            return None;
+        }
+        self.pattern.get(start..end)
+    }
+
+    /// Return the original text between the given offsets, if it can only
+    /// contain whitespace or comments.
+    fn gap(&self, end: usize, start: usize) -> Option<&'o str> {
+        if !self.gaps.contains(&(end, start)) {
+            return None;
+        }
+        self.pattern.get(end..start)
     }
 }
 
@@ -65,6 +166,14 @@ impl Printer {
 struct Writer<'p, W> {
     printer: &'p mut Printer,
     wtr: W,
+    /// The original pattern, when printing with `print_preserving`.
+    original: Option<&'p Original<'p>>,
+    /// The depth of the current node within a subtree that was copied from
+    /// the original pattern, or `0` if not within one.
+    copying: usize,
+    /// The end offset of the last node that was printed, if nothing else has
+    /// been written since.
+    last_end: Option<usize>,
 }
 
 impl<'p, W: fmt::Write> Visitor for Writer<'p, W> {
@@ -76,7 +185,24 @@ impl<'p, W: fmt::Write> Visitor for Writer<'p, W> {
     }
 
     fn visit_pre(&mut self, ast: &Ast) -> fmt::Result {
+        if self.copying > 0 {
+            self.copying += 1;
+            return Ok(());
+        }
+        if let Some(original) = self.original {
+            self.fmt_original_gap(original, ast.span().start.offset)?;
+            if let Some(src) = original.source(ast) {
+                self.copying = 1;
+                return self.wtr.write_str(src);
+            }
+        }
         match *ast {
+            Ast::Concat(ref x) => {
+                // Nothing is written for a concatenation itself, so any
+                // whitespace before its first child can be kept.
+                self.last_end = Some(x.span.start.offset);
+                Ok(())
+            }
             Ast::Group(ref x) => self.fmt_group_pre(x),
             Ast::Class(ast::Class::Bracketed(ref x)) => {
                 self.fmt_class_bracketed_pre(x)
@@ -86,27 +212,27 @@ impl<'p, W: fmt::Write> Visitor for Writer<'p, W> {
     }
 
     fn visit_post(&mut self, ast: &Ast) -> fmt::Result {
-        use ast::Class;
-
-        match *ast {
-            Ast::Empty(_) => Ok(()),
-            Ast::Flags(ref x) => self.fmt_set_flags(x),
-            Ast::Literal(ref x) => self.fmt_literal(x),
-            Ast::Dot(_) => self.wtr.write_str("."),
-            Ast::Assertion(ref x) => self.fmt_assertion(x),
-            Ast::Class(Class::Perl(ref x)) => self.fmt_class_perl(x),
-            Ast::Class(Class::Unicode(ref x)) => self.fmt_class_unicode(x),
-            Ast::Class(Class::Bracketed(ref x)) => {
-                self.fmt_class_bracketed_post(x)
+        if self.copying > 0 {
+            self.copying -= 1;
+            if self.copying > 0 {
+                return Ok(());
+            }
+        } else {
+            self.fmt_post(ast)?;
+            if let (Some(original), &Ast::Concat(ref x)) = (self.original, ast)
+            {
+                self.fmt_original_gap(original, x.span.end.offset)?;
             }
-            Ast::Repetition(ref x) => self.fmt_repetition(x),
-            Ast::Group(ref x) => self.fmt_group_post(x),
-            Ast::Alternation(_) => Ok(()),
-            Ast::Concat(_) => Ok(()),
         }
+        self.last_end = Some(ast.span().end.offset);
+        Ok(())
     }
 
     fn visit_alternation_in(&mut self) -> fmt::Result {
+        if self.copying > 0 {
+            return Ok(());
+        }
+        self.last_end = None;
         self.wtr.write_str("|")
     }
 
@@ -114,6 +240,9 @@ impl<'p, W: fmt::Write> Visitor for Writer<'p, W> {
         &mut self,
         ast: &ast::ClassSetItem,
     ) -> Result<(), Self::Err> {
+        if self.copying > 0 {
+            return Ok(());
+        }
         match *ast {
             ast::ClassSetItem::Bracketed(ref x) => {
                 self.fmt_class_bracketed_pre(x)
@@ -128,6 +257,9 @@ impl<'p, W: fmt::Write> Visitor for Writer<'p, W> {
     ) -> Result<(), Self::Err> {
         use ast::ClassSetItem::*;
 
+        if self.copying > 0 {
+            return Ok(());
+        }
         match *ast {
             Empty(_) => Ok(()),
             Literal(ref x) => self.fmt_literal(x),
@@ -149,11 +281,64 @@ impl<'p, W: fmt::Write> Visitor for Writer<'p, W> {
         &mut self,
         ast: &ast::ClassSetBinaryOp,
     ) -> Result<(), Self::Err> {
+        if self.copying > 0 {
+            return Ok(());
+        }
         self.fmt_class_set_binary_op_kind(&ast.kind)
     }
 }
 
 impl<'p, W: fmt::Write> Writer<'p, W> {
+    fn new(
+        printer: &'p mut Printer,
+        original: Option<&'p Original<'p>>,
+        wtr: W,
+    ) -> Writer<'p, W> {
+        Writer {
+            printer: printer,
+            wtr: wtr,
+            original: original,
+            copying: 0,
+            last_end: None,
+        }
+    }
+
+    fn fmt_post(&mut self, ast: &Ast) -> fmt::Result {
+        use ast::Class;
+
+        match *ast {
+            Ast::Empty(_) => Ok(()),
+            Ast::Flags(ref x) => self.fmt_set_flags(x),
+            Ast::Literal(ref x) => self.fmt_literal(x),
+            Ast::Dot(_) => self.wtr.write_str("."),
+            Ast::GraphemeCluster(_) => self.wtr.write_str(r"\X"),
+            Ast::Assertion(ref x) => self.fmt_assertion(x),
+            Ast::Class(Class::Perl(ref x)) => self.fmt_class_perl(x),
+            Ast::Class(Class::Unicode(ref x)) => self.fmt_class_unicode(x),
+            Ast::Class(Class::Bracketed(ref x)) => {
+                self.fmt_class_bracketed_post(x)
+            }
+            Ast::Repetition(ref x) => self.fmt_repetition(x),
+            Ast::Group(ref x) => self.fmt_group_post(x),
+            Ast::Alternation(_) => Ok(()),
+            Ast::Concat(_) => Ok(()),
+        }
+    }
+
+    /// Write the whitespace and comments that preceded the given offset in
+    /// the original pattern, if the node that was just printed ended at the
+    /// beginning of that whitespace.
+    fn fmt_original_gap(
+        &mut self,
+        original: &Original,
+        offset: usize,
+    ) -> fmt::Result {
+        match self.last_end.take().and_then(|end| original.gap(end, offset)) {
+            None => Ok(()),
+            Some(gap) => self.wtr.write_str(gap),
+        }
+    }
+
     fn fmt_group_pre(&mut self, ast: &ast::Group) -> fmt::Result {
         use ast::GroupKind::*;
         match ast.kind {
@@ -264,6 +449,8 @@ impl<'p, W: fmt::Write> Writer<'p, W> {
             EndText => self.wtr.write_str(r"\z"),
             WordBoundary => self.wtr.write_str(r"\b"),
             NotWordBoundary => self.wtr.write_str(r"\B"),
+            WordBoundarySegment => self.wtr.write_str(r"\b{wb}"),
+            NotWordBoundarySegment => self.wtr.write_str(r"\B{wb}"),
         }
     }
 
@@ -398,6 +585,7 @@ impl<'p, W: fmt::Write> Writer<'p, W> {
 mod tests {
     use super::Printer;
     use ast::parse::ParserBuilder;
+    use ast::{self, Ast, VisitorMut};
 
     fn roundtrip(given: &str) {
         roundtrip_with(|b| b, given);
@@ -566,4 +754,123 @@ mod tests {
         roundtrip(r"\p{X!=Y}");
         roundtrip(r"\P{X!=Y}");
     }
+
+    /// Rewrite every occurrence of one literal character to another.
+    struct Replace(char, char);
+
+    impl VisitorMut for Replace {
+        type Output = ();
+        type Err = ();
+
+        fn finish(self) -> Result<(), ()> {
+            Ok(())
+        }
+
+        fn visit_post(&mut self, ast: &mut Ast) -> Result<(), ()> {
+            if let Ast::Literal(ref mut x) = *ast {
+                if x.c == self.0 {
+                    x.c = self.1;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    fn rewrite(pattern: &str, from: char, to: char) -> String {
+        let original = ParserBuilder::new().build().parse(pattern).unwrap();
+        let mut ast = original.clone();
+        ast::visit_mut(&mut ast, Replace(from, to)).unwrap();
+
+        let mut dst = String::new();
+        Printer::new()
+            .print_preserving(&ast, &original, pattern, &mut dst)
+            .unwrap();
+        dst
+    }
+
+    #[test]
+    fn print_preserving_unchanged() {
+        let patterns = &[
+            "",
+            "a",
+            "(?x) a  b # comment\n c",
+            "(?x)\n  foo # one\n  | bar # two\n",
+            "(?x) [ a-z && [^ m ] ]{ 2 , 5 } ? # class",
+            r"(?P<name>\pL+)\s*=\s*(?-u:\w)",
+        ];
+        for &pattern in patterns {
+            assert_eq!(rewrite(pattern, '!', '?'), pattern);
+        }
+    }
+
+    #[test]
+    fn print_preserving_rewrite() {
+        assert_eq!(rewrite("abc", 'b', 'x'), "axc");
+        assert_eq!(
+            rewrite("(?x)\n  a  # first\n  c  # second\n", 'a', 'b'),
+            "(?x)\n  b  # first\n  c  # second\n"
+        );
+        assert_eq!(
+            rewrite("(?x) (?: a b | c d ) + # tail", 'c', 'x'),
+            "(?x) (?: a b | x d )+ # tail"
+        );
+        // Rewritten nodes are printed as if by `print`.
+        assert_eq!(rewrite(r"\x61b", 'a', 'b'), r"\x62b");
+    }
+
+    /// Replace every non-capturing group without flags around a single
+    /// literal with the literal itself.
+    struct Ungroup;
+
+    impl VisitorMut for Ungroup {
+        type Output = ();
+        type Err = ();
+
+        fn finish(self) -> Result<(), ()> {
+            Ok(())
+        }
+
+        fn visit_pre(&mut self, ast: &mut Ast) -> Result<(), ()> {
+            let lit = match *ast {
+                Ast::Group(ref mut g) => match (&g.kind, &mut *g.ast) {
+                    (&ast::GroupKind::NonCapturing(ref flags), lit)
+                        if flags.items.is_empty() =>
+                    {
+                        match *lit {
+                            Ast::Literal(ref lit) => lit.clone(),
+                            _ => return Ok(()),
+                        }
+                    }
+                    _ => return Ok(()),
+                },
+                Ast::Literal(ref lit) if lit.c == '!' => return Err(()),
+                _ => return Ok(()),
+            };
+            *ast = Ast::Literal(lit);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn print_preserving_replace() {
+        let pattern = "(?:a)+(?:b|c)(?x:d)(?:e)";
+        let original = ParserBuilder::new().build().parse(pattern).unwrap();
+        let mut ast = original.clone();
+        ast::visit_mut(&mut ast, Ungroup).unwrap();
+
+        let mut dst = String::new();
+        Printer::new()
+            .print_preserving(&ast, &original, pattern, &mut dst)
+            .unwrap();
+        assert_eq!(dst, "a+(?:b|c)(?x:d)e");
+    }
+
+    #[test]
+    fn visit_mut_error_restores_ast() {
+        let original =
+            ParserBuilder::new().build().parse("(a|b)+c!(?:d)").unwrap();
+        let mut ast = original.clone();
+        assert_eq!(ast::visit_mut(&mut ast, Ungroup), Err(()));
+        assert_eq!(ast, original);
+    }
 }
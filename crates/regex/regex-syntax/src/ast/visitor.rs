@@ -1,4 +1,6 @@
-use std::fmt;
+use alloc::vec::{self, Vec};
+use core::fmt;
+use core::mem;
 
 use ast::{self, Ast};
 
@@ -101,6 +103,59 @@ pub trait Visitor {
     }
 }
 
+/// A trait for visiting and rewriting an abstract syntax tree (AST) in depth
+/// first order.
+///
+/// This is like [`Visitor`](trait.Visitor.html), except every `Ast` node is
+/// given to the visitor by mutable reference. This permits callers to rewrite
+/// an AST in place, either by modifying a node or by replacing it entirely.
+/// Like `Visitor`, this works in constant stack space.
+///
+/// When `visit_pre` replaces a node, the children of the replacement (if any)
+/// are what get visited next. When `visit_post` replaces a node, its children
+/// have already been visited, and the replacement is not visited again.
+///
+/// Unlike `Visitor`, there are no separate methods for the items of a
+/// character class. A bracketed character class is visited as a single
+/// `Ast::Class` node, and its items may be rewritten through it.
+///
+/// Typical usage of this trait involves providing an implementation and then
+/// running it using the [`visit_mut`](fn.visit_mut.html) function. Printing
+/// the result with
+/// [`Printer::print_preserving`](print/struct.Printer.html#method.print_preserving)
+/// keeps the original formatting of every subtree that wasn't changed.
+pub trait VisitorMut {
+    /// The result of visiting an AST.
+    type Output;
+    /// An error that visiting an AST might return.
+    type Err;
+
+    /// All implementors of `VisitorMut` must provide a `finish` method, which
+    /// yields the result of visiting the AST or an error.
+    fn finish(self) -> Result<Self::Output, Self::Err>;
+
+    /// This method is called before beginning traversal of the AST.
+    fn start(&mut self) {}
+
+    /// This method is called on an `Ast` before descending into child `Ast`
+    /// nodes.
+    fn visit_pre(&mut self, _ast: &mut Ast) -> Result<(), Self::Err> {
+        Ok(())
+    }
+
+    /// This method is called on an `Ast` after descending all of its child
+    /// `Ast` nodes.
+    fn visit_post(&mut self, _ast: &mut Ast) -> Result<(), Self::Err> {
+        Ok(())
+    }
+
+    /// This method is called between child nodes of an
+    /// [`Alternation`](struct.Alternation.html).
+    fn visit_alternation_in(&mut self) -> Result<(), Self::Err> {
+        Ok(())
+    }
+}
+
 /// Executes an implementation of `Visitor` in constant stack space.
 ///
 /// This function will visit every node in the given `Ast` while calling the
@@ -120,6 +175,23 @@ pub fn visit<V: Visitor>(ast: &Ast, visitor: V) -> Result<V::Output, V::Err> {
     HeapVisitor::new().visit(ast, visitor)
 }
 
+/// Executes an implementation of `VisitorMut` in constant stack space.
+///
+/// This function will visit every node in the given `Ast` while calling the
+/// appropriate methods provided by the
+/// [`VisitorMut`](trait.VisitorMut.html) trait, and uses heap space
+/// proportional to the size of the `Ast`.
+///
+/// If the visitor returns an error at any point, then visiting is stopped and
+/// the error is returned. Any changes made by the visitor up to that point
+/// are kept, and the `Ast` is otherwise left intact.
+pub fn visit_mut<V: VisitorMut>(
+    ast: &mut Ast,
+    visitor: V,
+) -> Result<V::Output, V::Err> {
+    HeapVisitorMut::new().visit(ast, visitor)
+}
+
 /// HeapVisitor visits every item in an `Ast` recursively using constant stack
 /// size and a heap size proportional to the size of the `Ast`.
 struct HeapVisitor<'a> {
@@ -517,3 +589,147 @@ impl<'a> fmt::Debug for ClassInduct<'a> {
         write!(f, "{}", x)
     }
 }
+
+/// HeapVisitorMut visits every node in an `Ast` using constant stack size and
+/// a heap size proportional to the size of the `Ast`.
+///
+/// Since the visitor needs a mutable borrow of each node while its ancestors
+/// are still being visited, the child nodes of every node on the stack are
+/// detached from it, and are put back once they've all been visited.
+struct HeapVisitorMut {
+    /// A stack of detached `Ast` nodes. This is roughly analogous to the call
+    /// stack used in a typical recursive visitor.
+    stack: Vec<FrameMut>,
+}
+
+/// Represents a single stack frame while visiting an `Ast` mutably.
+struct FrameMut {
+    /// The node whose children are being visited, with its children removed.
+    parent: Ast,
+    /// The child nodes that haven't been visited yet.
+    todo: vec::IntoIter<Ast>,
+    /// The child nodes that have been visited.
+    done: Vec<Ast>,
+}
+
+impl HeapVisitorMut {
+    fn new() -> HeapVisitorMut {
+        HeapVisitorMut { stack: vec![] }
+    }
+
+    fn visit<V: VisitorMut>(
+        &mut self,
+        ast: &mut Ast,
+        mut visitor: V,
+    ) -> Result<V::Output, V::Err> {
+        self.stack.clear();
+
+        visitor.start();
+        let span = *ast.span();
+        let mut cur = mem::replace(ast, Ast::Empty(span));
+        let result = self.walk(&mut cur, &mut visitor);
+        // Whether we succeeded or not, put every detached node back where it
+        // came from.
+        while let Some(frame) = self.stack.pop() {
+            let child = mem::replace(&mut cur, frame.parent);
+            let mut children = frame.done;
+            children.push(child);
+            children.extend(frame.todo);
+            attach(&mut cur, children);
+        }
+        *ast = cur;
+        result?;
+        visitor.finish()
+    }
+
+    fn walk<V: VisitorMut>(
+        &mut self,
+        cur: &mut Ast,
+        visitor: &mut V,
+    ) -> Result<(), V::Err> {
+        loop {
+            visitor.visit_pre(cur)?;
+            if let Some(children) = detach(cur) {
+                let mut todo = children.into_iter();
+                let child = todo.next().unwrap();
+                let parent = mem::replace(cur, child);
+                self.stack.push(FrameMut {
+                    parent: parent,
+                    todo: todo,
+                    done: vec![],
+                });
+                continue;
+            }
+            // No children means we have a base case, so we can post visit it
+            // now.
+            visitor.visit_post(cur)?;
+
+            // At this point, we now try to pop our call stack until it is
+            // either empty or we hit a node with more children to visit.
+            loop {
+                let mut frame = match self.stack.pop() {
+                    None => return Ok(()),
+                    Some(frame) => frame,
+                };
+                if let Some(child) = frame.todo.next() {
+                    let is_alternation = match frame.parent {
+                        Ast::Alternation(_) => true,
+                        _ => false,
+                    };
+                    frame.done.push(mem::replace(cur, child));
+                    self.stack.push(frame);
+                    if is_alternation {
+                        visitor.visit_alternation_in()?;
+                    }
+                    break;
+                }
+                // Otherwise, we've visited all the child nodes, so put them
+                // back and post visit their parent.
+                let child = mem::replace(cur, frame.parent);
+                let mut children = frame.done;
+                children.push(child);
+                attach(cur, children);
+                visitor.visit_post(cur)?;
+            }
+        }
+    }
+}
+
+/// Remove and return the child nodes of the given `Ast`, if it has any.
+///
+/// Nodes with exactly one child are left with an empty placeholder.
+fn detach(ast: &mut Ast) -> Option<Vec<Ast>> {
+    fn take(ast: &mut Ast) -> Ast {
+        let span = *ast.span();
+        mem::replace(ast, Ast::Empty(span))
+    }
+
+    match *ast {
+        Ast::Repetition(ref mut x) => Some(vec![take(&mut x.ast)]),
+        Ast::Group(ref mut x) => Some(vec![take(&mut x.ast)]),
+        Ast::Concat(ref mut x) if !x.asts.is_empty() => {
+            Some(mem::replace(&mut x.asts, vec![]))
+        }
+        Ast::Alternation(ref mut x) if !x.asts.is_empty() => {
+            Some(mem::replace(&mut x.asts, vec![]))
+        }
+        _ => None,
+    }
+}
+
+/// Put back the child nodes previously removed from `ast` by `detach`.
+fn attach(ast: &mut Ast, mut children: Vec<Ast>) {
+    match *ast {
+        Ast::Repetition(ref mut x) => {
+            assert_eq!(children.len(), 1);
+            *x.ast = children.pop().unwrap();
+        }
+        Ast::Group(ref mut x) => {
+            assert_eq!(children.len(), 1);
+            *x.ast = children.pop().unwrap();
+        }
+        Ast::Concat(ref mut x) => x.asts = children,
+        Ast::Alternation(ref mut x) => x.asts = children,
+        _ => unreachable!("only nodes with children are detached"),
+    }
+}
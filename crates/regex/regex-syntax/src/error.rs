@@ -1,10 +1,14 @@
-use std::cmp;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cmp;
+use core::fmt;
+use core::result;
+#[cfg(feature = "std")]
 use std::error;
-use std::fmt;
-use std::result;
 
 use ast;
 use hir;
+use is_meta_character;
 
 /// A type alias for dealing with errors returned by this crate.
 pub type Result<T> = result::Result<T, Error>;
@@ -39,6 +43,7 @@ impl From<hir::Error> for Error {
     }
 }
 
+#[cfg(feature = "std")]
 impl error::Error for Error {
     fn description(&self) -> &str {
         match *self {
@@ -49,6 +54,75 @@ impl error::Error for Error {
     }
 }
 
+impl Error {
+    /// The original pattern string in which this error occurred.
+    ///
+    /// Every span reported by this error is reported in terms of this string.
+    pub fn pattern(&self) -> &str {
+        match *self {
+            Error::Parse(ref x) => x.pattern(),
+            Error::Translate(ref x) => x.pattern(),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Return the span at which this error occurred.
+    pub fn span(&self) -> &ast::Span {
+        match *self {
+            Error::Parse(ref x) => x.span(),
+            Error::Translate(ref x) => x.span(),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Return an auxiliary span, for errors that point to two locations in
+    /// the pattern. For example, a duplicate capture group name has its
+    /// auxiliary span set to the first use of the name.
+    ///
+    /// Only parse errors can have an auxiliary span.
+    pub fn auxiliary_span(&self) -> Option<&ast::Span> {
+        match *self {
+            Error::Parse(ref x) => x.auxiliary_span(),
+            Error::Translate(_) => None,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Return a suggested fix for this error, if there is an obvious one.
+    ///
+    /// For example, an unclosed group like `a(b` usually means that a
+    /// literal `(` was intended, so the suggestion is to escape it as `\(`.
+    pub fn suggestion(&self) -> Option<Suggestion> {
+        match *self {
+            Error::Parse(ref x) => suggest(x),
+            Error::Translate(_) => None,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Return a value that renders this error in the style of a compiler
+    /// diagnostic, with every span of the error labeled in the lines of the
+    /// pattern that contain it, followed by a suggestion if there is one.
+    ///
+    /// For example, the pattern `(?P<a>x)(?P<a>y)` is rendered as:
+    ///
+    /// ```text
+    /// error: duplicate capture group name
+    ///  --> 1:13
+    ///   |
+    /// 1 | (?P<a>x)(?P<a>y)
+    ///   |     -       ^
+    ///   |     |
+    ///   |     first used here
+    /// ```
+    ///
+    /// The `Display` implementation of this error uses a more compact
+    /// format.
+    pub fn diagnostic(&self) -> Diagnostic {
+        Diagnostic { err: self }
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -59,6 +133,259 @@ impl fmt::Display for Error {
     }
 }
 
+/// A suggested fix for an error, which replaces a span of the pattern with
+/// new text.
+///
+/// Its `Display` implementation writes a short help message, such as
+/// ``did you mean `\{`?``.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Suggestion {
+    span: ast::Span,
+    replacement: String,
+}
+
+impl Suggestion {
+    /// Return the span of the pattern that should be replaced.
+    pub fn span(&self) -> &ast::Span {
+        &self.span
+    }
+
+    /// Return the text that should replace the span.
+    pub fn replacement(&self) -> &str {
+        &self.replacement
+    }
+
+    /// Return a copy of the given pattern with this suggestion applied.
+    ///
+    /// `pattern` should be the pattern of the error this suggestion came
+    /// from.
+    pub fn apply(&self, pattern: &str) -> String {
+        let mut fixed = String::new();
+        fixed.push_str(&pattern[..self.span.start.offset]);
+        fixed.push_str(&self.replacement);
+        fixed.push_str(&pattern[self.span.end.offset..]);
+        fixed
+    }
+}
+
+impl fmt::Display for Suggestion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "did you mean `{}`?", self.replacement)
+    }
+}
+
+/// Return a suggested fix for the given parse error, if there is one.
+///
+/// Most suggestions are for meta characters that were probably meant to be
+/// matched literally.
+fn suggest(err: &ast::Error) -> Option<Suggestion> {
+    use ast::ErrorKind::*;
+
+    let span = err.span();
+    match *err.kind() {
+        EscapeUnrecognized => {
+            let escape =
+                err.pattern().get(span.start.offset..span.end.offset)?;
+            Some(Suggestion {
+                span: *span,
+                replacement: format!("\\{}", escape),
+            })
+        }
+        ClassUnclosed
+        | GroupUnclosed
+        | GroupUnopened
+        | RepetitionCountUnclosed
+        | RepetitionMissing => suggest_escape(err.pattern(), span.start),
+        RepetitionCountDecimalEmpty => {
+            // The span points just past the opening brace.
+            let before = err.pattern().get(..span.start.offset)?;
+            if !before.ends_with('{') {
+                return None;
+            }
+            let start = ast::Position::new(
+                span.start.offset - 1,
+                span.start.line,
+                span.start.column - 1,
+            );
+            suggest_escape(err.pattern(), start)
+        }
+        _ => None,
+    }
+}
+
+/// Suggest escaping the character at the given position, if it is a meta
+/// character.
+fn suggest_escape(pattern: &str, start: ast::Position) -> Option<Suggestion> {
+    let c = pattern.get(start.offset..)?.chars().next()?;
+    if !is_meta_character(c) {
+        return None;
+    }
+    let end = ast::Position::new(
+        start.offset + c.len_utf8(),
+        start.line,
+        start.column + 1,
+    );
+    Some(Suggestion {
+        span: ast::Span::new(start, end),
+        replacement: format!("\\{}", c),
+    })
+}
+
+/// A rendering of an error in the style of a compiler diagnostic.
+///
+/// This is created by
+/// [`Error::diagnostic`](enum.Error.html#method.diagnostic), and is rendered
+/// by its `Display` implementation.
+#[derive(Debug)]
+pub struct Diagnostic<'e> {
+    err: &'e Error,
+}
+
+/// A single mark drawn beneath one line of a pattern in a diagnostic.
+struct Mark {
+    /// The column at which the mark starts, starting at `1`.
+    column: usize,
+    /// The number of columns covered by the mark. This is always at least
+    /// `1`, even for empty spans.
+    width: usize,
+    /// The character used to draw the mark. Primary spans use `^` and
+    /// secondary spans use `-`.
+    marker: char,
+    /// The label written next to or beneath the mark, if any.
+    label: Option<&'static str>,
+}
+
+impl<'e> fmt::Display for Diagnostic<'e> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Secondary spans come first so that the primary span is drawn on
+        // top of them when they overlap.
+        let mut spans = vec![];
+        let kind: &dyn fmt::Display = match *self.err {
+            Error::Parse(ref x) => {
+                if let Some(span) = x.auxiliary_span() {
+                    spans.push((*span, '-', secondary_label(x.kind())));
+                }
+                x.kind()
+            }
+            Error::Translate(ref x) => x.kind(),
+            _ => unreachable!(),
+        };
+        let primary = *self.err.span();
+        spans.push((primary, '^', None));
+
+        let lines: Vec<&str> = self.err.pattern().split('\n').collect();
+        let mut by_line: Vec<Vec<Mark>> =
+            lines.iter().map(|_| vec![]).collect();
+        for &(span, marker, label) in &spans {
+            for line in span.start.line..span.end.line + 1 {
+                let start = if line == span.start.line {
+                    span.start.column
+                } else {
+                    1
+                };
+                let end = if line == span.end.line {
+                    span.end.column
+                } else {
+                    lines[line - 1].chars().count() + 1
+                };
+                // A span that ends at the very start of a line doesn't
+                // cover anything on that line.
+                if line > span.start.line && end <= start {
+                    continue;
+                }
+                by_line[line - 1].push(Mark {
+                    column: start,
+                    width: cmp::max(1, end.saturating_sub(start)),
+                    marker: marker,
+                    label: if line == span.start.line { label } else { None },
+                });
+            }
+        }
+
+        let last_line = by_line.iter().rposition(|marks| !marks.is_empty());
+        let width = last_line.map_or(1, |i| (i + 1).to_string().len());
+        let pad = repeat_char(' ', width);
+        writeln!(f, "error: {}", kind)?;
+        writeln!(
+            f,
+            "{}--> {}:{}",
+            pad, primary.start.line, primary.start.column
+        )?;
+        writeln!(f, "{} |", pad)?;
+        let mut previous = None;
+        for (i, marks) in by_line.iter().enumerate() {
+            if marks.is_empty() {
+                continue;
+            }
+            if previous.map_or(false, |p| p + 1 < i) {
+                writeln!(f, "...")?;
+            }
+            previous = Some(i);
+            writeln!(f, "{:>width$} | {}", i + 1, lines[i], width = width)?;
+            write_marks(f, &pad, marks)?;
+        }
+        if let Some(suggestion) = self.err.suggestion() {
+            writeln!(f, "{} |", pad)?;
+            writeln!(f, "{} = help: {}", pad, suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// Write the lines that draw the given marks beneath a line of a pattern.
+///
+/// The label of the rightmost mark is written on the same line as the
+/// marks. Every other label is written on its own line, connected to its
+/// mark by a `|`.
+fn write_marks(
+    f: &mut fmt::Formatter,
+    pad: &str,
+    marks: &[Mark],
+) -> fmt::Result {
+    let mut drawn: Vec<char> = vec![];
+    for mark in marks {
+        let end = mark.column - 1 + mark.width;
+        if drawn.len() < end {
+            drawn.resize(end, ' ');
+        }
+        for c in &mut drawn[mark.column - 1..end] {
+            *c = mark.marker;
+        }
+    }
+    let mut line: String = drawn.into_iter().collect();
+    let rightmost = marks.iter().max_by_key(|m| m.column).map(|m| m.column);
+    let mut hanging = vec![];
+    for mark in marks {
+        if let Some(label) = mark.label {
+            if Some(mark.column) == rightmost {
+                line.push(' ');
+                line.push_str(label);
+            } else {
+                hanging.push((mark.column, label));
+            }
+        }
+    }
+    writeln!(f, "{} | {}", pad, line)?;
+    for &(column, label) in &hanging {
+        let indent = repeat_char(' ', column - 1);
+        writeln!(f, "{} | {}|", pad, indent)?;
+        writeln!(f, "{} | {}{}", pad, indent, label)?;
+    }
+    Ok(())
+}
+
+/// Return the label for the auxiliary span of the given parse error kind.
+fn secondary_label(kind: &ast::ErrorKind) -> Option<&'static str> {
+    match *kind {
+        ast::ErrorKind::FlagDuplicate { .. } => Some("first set here"),
+        ast::ErrorKind::FlagRepeatedNegation { .. } => {
+            Some("first negation here")
+        }
+        ast::ErrorKind::GroupNameDuplicate { .. } => Some("first used here"),
+        _ => None,
+    }
+}
+
 /// A helper type for formatting nice error messages.
 ///
 /// This type is responsible for reporting regex parse errors in a nice human
@@ -279,12 +606,29 @@ impl<'p> Spans<'p> {
 }
 
 fn repeat_char(c: char, count: usize) -> String {
-    ::std::iter::repeat(c).take(count).collect()
+    ::core::iter::repeat(c).take(count).collect()
 }
 
 #[cfg(test)]
 mod tests {
+    use super::Error;
     use ast::parse::Parser;
+    use ParserBuilder;
+
+    fn parse_error(pattern: &str) -> Error {
+        ParserBuilder::new().build().parse(pattern).unwrap_err()
+    }
+
+    fn assert_suggestion(pattern: &str, expected: Option<&str>) {
+        let err = parse_error(pattern);
+        let fixed = err.suggestion().map(|s| s.apply(err.pattern()));
+        assert_eq!(fixed.as_ref().map(|s| s.as_str()), expected);
+    }
+
+    fn assert_diagnostic(pattern: &str, expected: &str) {
+        let err = parse_error(pattern);
+        assert_eq!(err.diagnostic().to_string(), expected.trim_start());
+    }
 
     fn assert_panic_message(pattern: &str, expected_msg: &str) -> () {
         let result = Parser::new().parse(pattern);
@@ -319,4 +663,135 @@ error: repetition quantifier expects a valid decimal
 "#,
         );
     }
+
+    #[test]
+    fn suggestions() {
+        assert_suggestion(r"a\y", Some(r"a\\y"));
+        assert_suggestion("a(b", Some(r"a\(b"));
+        assert_suggestion("a)b", Some(r"a\)b"));
+        assert_suggestion("[a", Some(r"\[a"));
+        assert_suggestion("*a", Some(r"\*a"));
+        assert_suggestion("a{", Some(r"a\{"));
+        assert_suggestion("a{1", Some(r"a\{1"));
+        assert_suggestion("a{}", Some(r"a\{}"));
+        assert_suggestion("a{x}", Some(r"a\{x}"));
+        assert_suggestion("a{2,1}", None);
+        assert_suggestion("(?P<a>x)(?P<a>y)", None);
+
+        let err = parse_error("a{");
+        let suggestion = err.suggestion().unwrap();
+        assert_eq!(suggestion.replacement(), r"\{");
+        assert_eq!(
+            (suggestion.span().start.offset, suggestion.span().end.offset),
+            (1, 2)
+        );
+        assert_eq!(suggestion.to_string(), r"did you mean `\{`?");
+    }
+
+    #[test]
+    fn spans() {
+        let err = parse_error("(?P<a>x)(?P<a>y)");
+        assert_eq!(err.span().start.offset, 12);
+        assert_eq!(err.auxiliary_span().map(|s| s.start.offset), Some(4));
+
+        let err = parse_error(r"(?-u)\xFF");
+        assert_eq!((err.span().start.offset, err.span().end.offset), (5, 9));
+        assert_eq!(err.auxiliary_span(), None);
+        assert_eq!(err.suggestion(), None);
+    }
+
+    #[test]
+    fn diagnostic_single_span() {
+        assert_diagnostic(
+            "a{",
+            r"
+error: unclosed counted repetition
+ --> 1:2
+  |
+1 | a{
+  |  ^
+  |
+  = help: did you mean `\{`?
+",
+        );
+        assert_diagnostic(
+            "a{2,1}",
+            r"
+error: invalid repetition count range, the start must be <= the end
+ --> 1:2
+  |
+1 | a{2,1}
+  |  ^^^^^
+",
+        );
+    }
+
+    #[test]
+    fn diagnostic_two_spans() {
+        assert_diagnostic(
+            "(?P<a>x)(?P<a>y)",
+            r"
+error: duplicate capture group name
+ --> 1:13
+  |
+1 | (?P<a>x)(?P<a>y)
+  |     -       ^
+  |     |
+  |     first used here
+",
+        );
+        assert_diagnostic(
+            "(?i)(?ii)",
+            r"
+error: duplicate flag
+ --> 1:8
+  |
+1 | (?i)(?ii)
+  |       -^
+  |       |
+  |       first set here
+",
+        );
+    }
+
+    #[test]
+    fn diagnostic_multi_line() {
+        assert_diagnostic(
+            "a\nb\nc\nd)",
+            r"
+error: unopened group
+ --> 4:2
+  |
+4 | d)
+  |  ^
+  |
+  = help: did you mean `\)`?
+",
+        );
+        assert_diagnostic(
+            "(?x)\n(?P<n>a)\nb\nc\n(?P<n>d)",
+            r"
+error: duplicate capture group name
+ --> 5:5
+  |
+2 | (?P<n>a)
+  |     - first used here
+...
+5 | (?P<n>d)
+  |     ^
+",
+        );
+        assert_diagnostic(
+            "a{2,\n1}",
+            r"
+error: invalid repetition count range, the start must be <= the end
+ --> 1:2
+  |
+1 | a{2,
+  |  ^^^
+2 | 1}
+  | ^^
+",
+        );
+    }
 }
@@ -0,0 +1,770 @@
+/*!
+Provides routines for generating strings that match an `Hir`.
+
+This is primarily useful for testing, where one wants inputs that are known
+to match a regular expression. Two strategies are provided: exhaustive
+enumeration of the shortest matching strings (in shortlex order) and random
+sampling driven by a seeded pseudo-random number generator.
+
+Strings are generated as `Vec<u8>`, since an `Hir` may match invalid UTF-8.
+Every generated string matches the expression in its entirety, i.e., it
+matches `^(?:expr)$`. Zero-width assertions are checked against the complete
+string, where the beginning and end of the string act as the beginning and
+end of text.
+
+**WARNING**: Generation uses stack space proportional to the size of the
+`Hir` expression. To protect yourself, set a reasonable
+[`nest_limit` on your `Parser`](../../struct.ParserBuilder.html#method.nest_limit).
+This is done for you by default.
+*/
+
+use alloc::vec::Vec;
+use core::cmp;
+use core::str;
+
+use hir::{self, Hir, HirKind};
+use is_word_byte;
+use segment;
+use unicode;
+
+/// The number of attempts a sampler makes before giving up on producing a
+/// string that satisfies all assertions in an expression.
+const SAMPLE_ATTEMPTS: usize = 100;
+
+/// A generator of strings matching an `Hir` expression.
+///
+/// A generator is configured with bounds that keep generation finite, since
+/// most regular expressions match infinitely many strings. The maximum length
+/// bounds enumeration while the maximum repeat bounds the number of times an
+/// unbounded repetition operator (like `*` or `+`) is applied when sampling.
+#[derive(Clone, Debug)]
+pub struct Generator {
+    max_length: usize,
+    max_repeat: u32,
+}
+
+impl Generator {
+    /// Create a new generator with default limits.
+    ///
+    /// By default, enumerated strings are at most 32 bytes long and unbounded
+    /// repetitions are applied at most 8 times beyond their minimum when
+    /// sampling.
+    pub fn new() -> Generator {
+        Generator { max_length: 32, max_repeat: 8 }
+    }
+
+    /// Set the maximum length, in bytes, of enumerated strings.
+    pub fn max_length(&mut self, len: usize) -> &mut Generator {
+        self.max_length = len;
+        self
+    }
+
+    /// Set the maximum number of times an unbounded repetition is applied
+    /// beyond its minimum when sampling. For example, with the default of
+    /// `8`, `a+` samples strings of between 1 and 9 `a`s.
+    pub fn max_repeat(&mut self, n: u32) -> &mut Generator {
+        self.max_repeat = n;
+        self
+    }
+
+    /// Enumerate at most `limit` strings matched by the given expression.
+    ///
+    /// The strings returned are the smallest strings no longer than the
+    /// configured maximum length, in shortlex order. That is, shorter strings
+    /// come first and strings of equal length are ordered lexicographically by
+    /// their bytes. Since UTF-8 preserves the ordering of codepoints, this
+    /// means characters from Unicode classes are enumerated by codepoint.
+    ///
+    /// Every string returned matches the expression. However, when an
+    /// assertion can only be checked once its surrounding sub-expressions are
+    /// known (e.g., `(?:a$|b)c`), strings may be pruned late, in which case
+    /// fewer than `limit` strings may be returned even though more exist.
+    pub fn enumerate(&self, expr: &Hir, limit: usize) -> Vec<Vec<u8>> {
+        let mut strings: Vec<Vec<u8>> = self
+            .enumerate_expr(expr, limit)
+            .into_iter()
+            .filter(|cand| cand.is_match())
+            .map(|cand| cand.bytes)
+            .collect();
+        strings.dedup();
+        strings.truncate(limit);
+        strings
+    }
+
+    /// Return a sampler of random strings matched by the given expression.
+    ///
+    /// The sampler is deterministic: two samplers created with the same seed
+    /// for the same expression produce the same sequence of strings.
+    pub fn sampler<'h>(&self, expr: &'h Hir, seed: u64) -> Sampler<'h> {
+        Sampler { gen: self.clone(), expr: expr, rng: Rng::new(seed) }
+    }
+
+    fn enumerate_expr(&self, expr: &Hir, limit: usize) -> Vec<Candidate> {
+        match *expr.kind() {
+            HirKind::Empty => vec![Candidate::empty()],
+            HirKind::Literal(ref lit) => {
+                let mut cand = Candidate::empty();
+                cand.push_literal(lit);
+                self.finish(vec![cand], limit)
+            }
+            HirKind::Class(hir::Class::Unicode(ref cls)) => {
+                let mut cands = vec![];
+                let mut buf = [0; 4];
+                'outer: for r in cls.iter() {
+                    for cp in (r.start() as u32)..=(r.end() as u32) {
+                        if cands.len() >= limit {
+                            break 'outer;
+                        }
+                        let c = match ::core::char::from_u32(cp) {
+                            None => continue,
+                            Some(c) => c,
+                        };
+                        if c.len_utf8() > self.max_length {
+                            break 'outer;
+                        }
+                        let bytes = c.encode_utf8(&mut buf).as_bytes();
+                        cands.push(Candidate::from_bytes(bytes));
+                    }
+                }
+                cands
+            }
+            HirKind::Class(hir::Class::Bytes(ref cls)) => {
+                if self.max_length == 0 {
+                    return vec![];
+                }
+                cls.iter()
+                    .flat_map(|r| r.start()..=r.end())
+                    .take(limit)
+                    .map(|b| Candidate::from_bytes(&[b]))
+                    .collect()
+            }
+            HirKind::Anchor(ref x) => {
+                let mut cand = Candidate::empty();
+                cand.looks.push((0, Look::Anchor(x.clone())));
+                vec![cand]
+            }
+            HirKind::WordBoundary(ref x) => {
+                let mut cand = Candidate::empty();
+                cand.looks.push((0, Look::WordBoundary(x.clone())));
+                vec![cand]
+            }
+            HirKind::Repetition(ref x) => self.enumerate_repetition(x, limit),
+            HirKind::Group(ref x) => self.enumerate_expr(&x.hir, limit),
+            HirKind::Concat(ref exprs) => {
+                let mut cands = vec![Candidate::empty()];
+                for e in exprs {
+                    let rhs = self.enumerate_expr(e, limit);
+                    cands = self.concat(&cands, &rhs, limit);
+                    if cands.is_empty() {
+                        break;
+                    }
+                }
+                cands
+            }
+            HirKind::Alternation(ref exprs) => {
+                let mut cands = vec![];
+                for e in exprs {
+                    cands.extend(self.enumerate_expr(e, limit));
+                }
+                self.finish(cands, limit)
+            }
+        }
+    }
+
+    fn enumerate_repetition(
+        &self,
+        rep: &hir::Repetition,
+        limit: usize,
+    ) -> Vec<Candidate> {
+        let (min, max) = repetition_bounds(&rep.kind);
+        let sub = self.enumerate_expr(&rep.hir, limit);
+        let mut cands = vec![];
+        let mut cur = vec![Candidate::empty()];
+        let mut count = 0;
+        loop {
+            if count >= min {
+                cands.extend(cur.iter().cloned());
+            }
+            if max.map_or(false, |max| count >= max) {
+                break;
+            }
+            let next = self.concat(&cur, &sub, limit);
+            // Once the minimum has been reached, there is nothing left to
+            // find if no new strings were produced. This is always reached
+            // eventually because of the bound on the length of strings.
+            if next.is_empty() || (count >= min && next == cur) {
+                break;
+            }
+            cur = next;
+            count += 1;
+        }
+        self.finish(cands, limit)
+    }
+
+    /// Concatenate every candidate in `lhs` with every candidate in `rhs`,
+    /// keeping only the smallest `limit` results.
+    ///
+    /// This only needs the smallest `limit` candidates of each side, since
+    /// if `xy` is among the smallest `limit` concatenations, then `x` and `y`
+    /// must be among the smallest `limit` candidates of their sides.
+    fn concat(
+        &self,
+        lhs: &[Candidate],
+        rhs: &[Candidate],
+        limit: usize,
+    ) -> Vec<Candidate> {
+        let mut cands = vec![];
+        for x in lhs {
+            for y in rhs {
+                if x.bytes.len() + y.bytes.len() > self.max_length {
+                    continue;
+                }
+                let cand = x.concat(y);
+                if !cand.is_dead() {
+                    cands.push(cand);
+                }
+            }
+        }
+        self.finish(cands, limit)
+    }
+
+    /// Sort the given candidates in shortlex order, remove duplicates and
+    /// keep only the smallest `limit` of them.
+    fn finish(
+        &self,
+        mut cands: Vec<Candidate>,
+        limit: usize,
+    ) -> Vec<Candidate> {
+        cands.retain(|cand| cand.bytes.len() <= self.max_length);
+        cands.sort_by(|a, b| {
+            a.bytes
+                .len()
+                .cmp(&b.bytes.len())
+                .then_with(|| a.bytes.cmp(&b.bytes))
+        });
+        cands.dedup();
+        cands.truncate(limit);
+        cands
+    }
+}
+
+/// A sampler of random strings matching an `Hir` expression.
+///
+/// A sampler is created via
+/// [`Generator::sampler`](struct.Generator.html#method.sampler). The
+/// lifetime parameter `'h` refers to the lifetime of the expression.
+#[derive(Clone, Debug)]
+pub struct Sampler<'h> {
+    gen: Generator,
+    expr: &'h Hir,
+    rng: Rng,
+}
+
+impl<'h> Sampler<'h> {
+    /// Sample a random string matched by this sampler's expression.
+    ///
+    /// Every alternative of an alternation is equally likely, as is every
+    /// character in a class and every number of iterations permitted by a
+    /// repetition operator (subject to the generator's maximum repeat).
+    ///
+    /// Assertions are checked after a string has been produced. If no string
+    /// satisfying all assertions is found after a fixed number of attempts
+    /// (for example, because the expression can never match, as in `a^`),
+    /// then `None` is returned.
+    pub fn sample(&mut self) -> Option<Vec<u8>> {
+        for _ in 0..SAMPLE_ATTEMPTS {
+            let mut cand = Candidate::empty();
+            if !self.sample_expr(self.expr, &mut cand) {
+                return None;
+            }
+            if cand.is_match() {
+                return Some(cand.bytes);
+            }
+        }
+        None
+    }
+
+    /// Append a random string matched by `expr` to `cand`. If `expr` cannot
+    /// match anything (because it contains an empty class), then this
+    /// returns false.
+    fn sample_expr(&mut self, expr: &Hir, cand: &mut Candidate) -> bool {
+        match *expr.kind() {
+            HirKind::Empty => true,
+            HirKind::Literal(ref lit) => {
+                cand.push_literal(lit);
+                true
+            }
+            HirKind::Class(hir::Class::Unicode(ref cls)) => {
+                let ranges: Vec<(u32, u32)> = cls
+                    .iter()
+                    .map(|r| (r.start() as u32, r.end() as u32))
+                    .collect();
+                loop {
+                    let cp = match self.pick(&ranges) {
+                        None => return false,
+                        Some(cp) => cp,
+                    };
+                    // Ranges may span the surrogate codepoints, which are
+                    // not valid scalar values, so simply try again.
+                    if let Some(c) = ::core::char::from_u32(cp) {
+                        cand.push_literal(&hir::Literal::Unicode(c));
+                        return true;
+                    }
+                }
+            }
+            HirKind::Class(hir::Class::Bytes(ref cls)) => {
+                let ranges: Vec<(u32, u32)> = cls
+                    .iter()
+                    .map(|r| (r.start() as u32, r.end() as u32))
+                    .collect();
+                match self.pick(&ranges) {
+                    None => false,
+                    Some(b) => {
+                        cand.bytes.push(b as u8);
+                        true
+                    }
+                }
+            }
+            HirKind::Anchor(ref x) => {
+                cand.looks.push((cand.bytes.len(), Look::Anchor(x.clone())));
+                true
+            }
+            HirKind::WordBoundary(ref x) => {
+                let look = Look::WordBoundary(x.clone());
+                cand.looks.push((cand.bytes.len(), look));
+                true
+            }
+            HirKind::Repetition(ref x) => {
+                let (min, max) = repetition_bounds(&x.kind);
+                let max =
+                    max.unwrap_or(min.saturating_add(self.gen.max_repeat));
+                let span = (max - min) as u64 + 1;
+                let count = min as u64 + self.rng.below(span);
+                for _ in 0..count {
+                    if !self.sample_expr(&x.hir, cand) {
+                        return false;
+                    }
+                }
+                true
+            }
+            HirKind::Group(ref x) => self.sample_expr(&x.hir, cand),
+            HirKind::Concat(ref exprs) => {
+                exprs.iter().all(|e| self.sample_expr(e, cand))
+            }
+            HirKind::Alternation(ref exprs) => {
+                let i = self.rng.below(exprs.len() as u64) as usize;
+                self.sample_expr(&exprs[i], cand)
+            }
+        }
+    }
+
+    /// Pick a value uniformly at random from the given inclusive ranges.
+    fn pick(&mut self, ranges: &[(u32, u32)]) -> Option<u32> {
+        let total: u64 = ranges.iter().map(|&(s, e)| (e - s) as u64 + 1).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut i = self.rng.below(total);
+        for &(s, e) in ranges {
+            let len = (e - s) as u64 + 1;
+            if i < len {
+                return Some(s + i as u32);
+            }
+            i -= len;
+        }
+        unreachable!()
+    }
+}
+
+/// Returns the minimum and (optional) maximum number of iterations of the
+/// given repetition kind.
+fn repetition_bounds(kind: &hir::RepetitionKind) -> (u32, Option<u32>) {
+    match *kind {
+        hir::RepetitionKind::ZeroOrOne => (0, Some(1)),
+        hir::RepetitionKind::ZeroOrMore => (0, None),
+        hir::RepetitionKind::OneOrMore => (1, None),
+        hir::RepetitionKind::Range(ref r) => match *r {
+            hir::RepetitionRange::Exactly(n) => (n, Some(n)),
+            hir::RepetitionRange::AtLeast(n) => (n, None),
+            hir::RepetitionRange::Bounded(m, n) => (m, Some(cmp::max(m, n))),
+        },
+    }
+}
+
+/// A zero-width assertion that a generated string must satisfy.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Look {
+    Anchor(hir::Anchor),
+    WordBoundary(hir::WordBoundary),
+}
+
+/// A string under construction along with the assertions it must satisfy.
+///
+/// Each assertion is paired with its offset into `bytes`. Assertions that
+/// have been fully checked are dropped as soon as possible.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Candidate {
+    bytes: Vec<u8>,
+    looks: Vec<(usize, Look)>,
+}
+
+impl Candidate {
+    fn empty() -> Candidate {
+        Candidate { bytes: vec![], looks: vec![] }
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Candidate {
+        Candidate { bytes: bytes.to_vec(), looks: vec![] }
+    }
+
+    fn push_literal(&mut self, lit: &hir::Literal) {
+        match *lit {
+            hir::Literal::Unicode(c) => {
+                let mut buf = [0; 4];
+                self.bytes
+                    .extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+            hir::Literal::Byte(b) => self.bytes.push(b),
+        }
+    }
+
+    /// Return the concatenation of this candidate with `other`, dropping
+    /// every assertion that can now be checked and holds.
+    fn concat(&self, other: &Candidate) -> Candidate {
+        let mut bytes =
+            Vec::with_capacity(self.bytes.len() + other.bytes.len());
+        bytes.extend_from_slice(&self.bytes);
+        bytes.extend_from_slice(&other.bytes);
+        let offset = self.bytes.len();
+        let mut cand = Candidate { bytes: bytes, looks: vec![] };
+        let looks = self.looks.iter().cloned().chain(
+            other
+                .looks
+                .iter()
+                .map(|&(at, ref look)| (at + offset, look.clone())),
+        );
+        for (at, look) in looks {
+            if cand.check(at, &look, false) != Some(true) {
+                cand.looks.push((at, look));
+            }
+        }
+        cand
+    }
+
+    /// Returns true if some assertion in this candidate can never hold,
+    /// regardless of what is added before or after it.
+    fn is_dead(&self) -> bool {
+        self.looks
+            .iter()
+            .any(|&(at, ref look)| self.check(at, look, false) == Some(false))
+    }
+
+    /// Returns true if every assertion holds when this candidate is treated
+    /// as the entire input.
+    fn is_match(&self) -> bool {
+        self.looks
+            .iter()
+            .all(|&(at, ref look)| self.check(at, look, true) == Some(true))
+    }
+
+    /// Check whether the given assertion holds at offset `at`.
+    ///
+    /// When `complete` is false, this candidate may later be surrounded by
+    /// more bytes. In that case, `None` is returned when the result depends
+    /// on those bytes.
+    fn check(&self, at: usize, look: &Look, complete: bool) -> Option<bool> {
+        let (start, end) = (at == 0, at == self.bytes.len());
+        match *look {
+            Look::Anchor(hir::Anchor::StartText) => {
+                if !start {
+                    Some(false)
+                } else if complete {
+                    Some(true)
+                } else {
+                    None
+                }
+            }
+            Look::Anchor(hir::Anchor::EndText) => {
+                if !end {
+                    Some(false)
+                } else if complete {
+                    Some(true)
+                } else {
+                    None
+                }
+            }
+            Look::Anchor(hir::Anchor::StartLine) => {
+                if !start {
+                    Some(self.bytes[at - 1] == b'\n')
+                } else if complete {
+                    Some(true)
+                } else {
+                    None
+                }
+            }
+            Look::Anchor(hir::Anchor::EndLine) => {
+                if !end {
+                    Some(self.bytes[at] == b'\n')
+                } else if complete {
+                    Some(true)
+                } else {
+                    None
+                }
+            }
+            Look::WordBoundary(ref wb) => {
+                if (start || end) && !complete {
+                    return None;
+                }
+                let (before, after) = match *wb {
+                    hir::WordBoundary::Unicode
+                    | hir::WordBoundary::UnicodeNegate => (
+                        is_word_char(last_char(&self.bytes[..at])),
+                        is_word_char(first_char(&self.bytes[at..])),
+                    ),
+                    hir::WordBoundary::Ascii
+                    | hir::WordBoundary::AsciiNegate => (
+                        at > 0 && is_word_byte(self.bytes[at - 1]),
+                        !end && is_word_byte(self.bytes[at]),
+                    ),
+                    hir::WordBoundary::UnicodeSegment
+                    | hir::WordBoundary::UnicodeSegmentNegate => {
+                        // UAX#29 word boundaries can depend on text
+                        // arbitrarily far from `at`, so they can only be
+                        // decided once the candidate is complete.
+                        if !complete {
+                            return None;
+                        }
+                        let is_boundary =
+                            segment::is_word_boundary(&self.bytes, at)
+                                .expect("Unicode segmentation is available");
+                        return Some(wb.is_negated() != is_boundary);
+                    }
+                };
+                Some(wb.is_negated() == (before == after))
+            }
+        }
+    }
+}
+
+/// Decode the first character of the given bytes, if it is valid UTF-8.
+fn first_char(bytes: &[u8]) -> Option<char> {
+    (1..cmp::min(4, bytes.len()) + 1)
+        .filter_map(|n| str::from_utf8(&bytes[..n]).ok())
+        .next()
+        .and_then(|s| s.chars().next())
+}
+
+/// Decode the last character of the given bytes, if it is valid UTF-8.
+fn last_char(bytes: &[u8]) -> Option<char> {
+    (1..cmp::min(4, bytes.len()) + 1)
+        .filter_map(|n| str::from_utf8(&bytes[bytes.len() - n..]).ok())
+        .next()
+        .and_then(|s| s.chars().next())
+}
+
+/// Returns true if the given character is a Unicode word character. Invalid
+/// UTF-8 is never a word character.
+///
+/// An `Hir` can only contain a Unicode word boundary when the `unicode-perl`
+/// feature is enabled, so this never fails in practice.
+fn is_word_char(c: Option<char>) -> bool {
+    c.map_or(false, |c| {
+        unicode::is_word_character(c)
+            .expect("Unicode word boundaries require the unicode-perl feature")
+    })
+}
+
+/// A small, seedable pseudo-random number generator.
+///
+/// This is xorshift64* seeded via splitmix64. It is not suitable for anything
+/// other than generating test inputs.
+#[derive(Clone, Debug)]
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        let mut z = seed.wrapping_add(0x9E3779B97F4A7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z = z ^ (z >> 31);
+        // xorshift must never have a zero state.
+        Rng { state: if z == 0 { 1 } else { z } }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545F4914F6CDD1D)
+    }
+
+    /// Return a number uniformly distributed in `0..n`. `n` must be
+    /// non-zero.
+    fn below(&mut self, n: u64) -> u64 {
+        // Reject values in the final, partial block to avoid bias.
+        let zone = ::core::u64::MAX - (::core::u64::MAX % n);
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return x % n;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Generator;
+    use ParserBuilder;
+
+    fn enumerate(pattern: &str, limit: usize) -> Vec<String> {
+        let hir = ParserBuilder::new().build().parse(pattern).unwrap();
+        Generator::new()
+            .enumerate(&hir, limit)
+            .into_iter()
+            .map(|bytes| String::from_utf8(bytes).unwrap())
+            .collect()
+    }
+
+    fn sample(pattern: &str, seed: u64, count: usize) -> Vec<Vec<u8>> {
+        let hir = ParserBuilder::new()
+            .allow_invalid_utf8(true)
+            .build()
+            .parse(pattern)
+            .unwrap();
+        let mut sampler = Generator::new().sampler(&hir, seed);
+        (0..count).map(|_| sampler.sample().unwrap()).collect()
+    }
+
+    #[test]
+    fn enumerate_simple() {
+        assert_eq!(enumerate("abc", 10), vec!["abc"]);
+        assert_eq!(enumerate("c|a|b", 10), vec!["a", "b", "c"]);
+        assert_eq!(enumerate("[a-c]{2}", 4), vec!["aa", "ab", "ac", "ba"]);
+        assert_eq!(enumerate("a*", 3), vec!["", "a", "aa"]);
+        assert_eq!(enumerate("(?:ab|a)c?", 10), vec!["a", "ab", "ac", "abc"]);
+        assert_eq!(enumerate("(?:a|bb){2,}", 3), vec!["aa", "aaa", "abb"]);
+        assert_eq!(enumerate("", 10), vec![""]);
+    }
+
+    #[test]
+    fn enumerate_shortlex() {
+        assert_eq!(
+            enumerate("(?:zz|y)+", 5),
+            vec!["y", "yy", "zz", "yyy", "yzz"]
+        );
+        assert_eq!(enumerate("(?:)*", 10), vec![""]);
+    }
+
+    #[test]
+    fn enumerate_unicode() {
+        assert_eq!(enumerate(r"[\u{3B1}-\u{3B3}]", 10), vec!["α", "β", "γ"]);
+        // Shorter encodings come first, so ASCII precedes everything else.
+        assert_eq!(enumerate(r"β|z|é", 10), vec!["z", "é", "β"]);
+    }
+
+    #[test]
+    #[cfg(feature = "unicode-gencat")]
+    fn enumerate_unicode_classes() {
+        assert_eq!(enumerate(r"\pN", 3), vec!["0", "1", "2"]);
+        assert_eq!(enumerate(r"\pN\pL", 2), vec!["0A", "0B"]);
+    }
+
+    #[test]
+    fn enumerate_max_length() {
+        let hir = ParserBuilder::new().build().parse("a+").unwrap();
+        let strings = Generator::new().max_length(3).enumerate(&hir, 100);
+        assert_eq!(
+            strings,
+            vec![b"a".to_vec(), b"aa".to_vec(), b"aaa".to_vec()]
+        );
+        let hir = ParserBuilder::new().build().parse("β").unwrap();
+        assert!(Generator::new()
+            .max_length(1)
+            .enumerate(&hir, 100)
+            .is_empty());
+    }
+
+    #[test]
+    fn enumerate_assertions() {
+        assert_eq!(enumerate("^a|b$", 10), vec!["a", "b"]);
+        assert_eq!(enumerate("a^", 10), Vec::<String>::new());
+        assert_eq!(enumerate("(?:a$|b)c", 10), vec!["bc"]);
+        assert_eq!(enumerate(r"(?m)a$\n^b", 10), vec!["a\nb"]);
+        assert_eq!(enumerate(r"(?m)a$[\nb]", 10), vec!["a\n"]);
+        assert_eq!(
+            enumerate(r"[ab ](?-u:\b)[ab ]", 10),
+            vec![" a", " b", "a ", "b "]
+        );
+        assert_eq!(enumerate(r"(?-u:\b)a", 10), vec!["a"]);
+        assert_eq!(enumerate(r"(?-u:\b)β", 10), Vec::<String>::new());
+    }
+
+    #[test]
+    #[cfg(feature = "unicode-perl")]
+    fn enumerate_unicode_word_boundary() {
+        assert_eq!(
+            enumerate(r"[ab ]\b[ab ]", 10),
+            vec![" a", " b", "a ", "b "]
+        );
+        assert_eq!(enumerate(r"[a ]\B[a ]", 10), vec!["  ", "aa"]);
+        assert_eq!(enumerate(r"\bβ", 10), vec!["β"]);
+        assert_eq!(enumerate(r"β\B[β ]", 10), vec!["ββ"]);
+    }
+
+    #[test]
+    #[cfg(all(feature = "unicode-segment", feature = "unicode-bool"))]
+    fn enumerate_segment_word_boundary() {
+        assert_eq!(enumerate(r"[a.]\B{wb}[a.]", 10), vec!["aa"]);
+        assert_eq!(enumerate(r"a[.:]\B{wb}a", 10), vec!["a.a", "a:a"]);
+        assert_eq!(enumerate(r"[a.]\b{wb}[a.]", 10), vec!["..", ".a", "a."]);
+    }
+
+    #[test]
+    fn sample_deterministic() {
+        let re = r"[0-9A-Za-z\u{3B1}-\u{3C9}]+";
+        assert_eq!(sample(re, 42, 20), sample(re, 42, 20));
+        assert_ne!(sample(re, 42, 20), sample(re, 43, 20));
+    }
+
+    #[test]
+    fn sample_matches() {
+        for s in sample("[a-c]{3}|x+", 1, 100) {
+            let ok = (s.len() == 3
+                && s.iter().all(|&b| b'a' <= b && b <= b'c'))
+                || (1 <= s.len()
+                    && s.len() <= 9
+                    && s.iter().all(|&b| b == b'x'));
+            assert!(ok, "{:?}", s);
+        }
+        for s in sample(r"(?-u:[\x80-\xFF])", 3, 100) {
+            assert_eq!(s.len(), 1);
+            assert!(s[0] >= 0x80);
+        }
+        for s in sample(r"^a?(?-u:\b)b?$", 4, 100) {
+            assert!(s == b"a" || s == b"b", "{:?}", s);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "unicode-gencat")]
+    fn sample_unicode_classes() {
+        for s in sample(r"\pL", 2, 100) {
+            let s = String::from_utf8(s).unwrap();
+            assert_eq!(s.chars().count(), 1);
+            assert!(s.chars().next().unwrap().is_alphabetic());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "unicode-perl")]
+    fn sample_impossible() {
+        let hir = ParserBuilder::new().build().parse(r"a\Bb?$").unwrap();
+        let mut sampler = Generator::new().sampler(&hir, 0);
+        assert_eq!(sampler.sample(), Some(b"ab".to_vec()));
+        let hir = ParserBuilder::new().build().parse("a^").unwrap();
+        assert_eq!(Generator::new().sampler(&hir, 0).sample(), None);
+    }
+}
@@ -1,8 +1,9 @@
-use std::char;
-use std::cmp;
-use std::fmt::Debug;
-use std::slice;
-use std::u8;
+use alloc::vec::Vec;
+use core::char;
+use core::cmp;
+use core::fmt::Debug;
+use core::slice;
+use core::u8;
 
 use unicode;
 
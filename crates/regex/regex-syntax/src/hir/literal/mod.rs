@@ -1,12 +1,17 @@
 /*!
-Provides routines for extracting literal prefixes and suffixes from an `Hir`.
+Provides routines for extracting literal prefixes, suffixes and required inner
+literals from an `Hir`.
 */
 
-use std::cmp;
-use std::fmt;
-use std::iter;
-use std::mem;
-use std::ops;
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cmp;
+use core::fmt;
+use core::iter;
+use core::mem;
+use core::ops;
 
 use hir::{self, Hir, HirKind};
 
@@ -48,6 +53,36 @@ pub struct Literal {
     cut: bool,
 }
 
+/// A set of literals that every match of a regular expression must contain,
+/// along with the part of the expression that precedes them.
+///
+/// This is created by [`Literals::inner`](struct.Literals.html#method.inner).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InnerLiterals {
+    prefix: Hir,
+    lits: Literals,
+}
+
+impl InnerLiterals {
+    /// Returns the part of the expression that precedes the inner literals.
+    pub fn prefix(&self) -> &Hir {
+        &self.prefix
+    }
+
+    /// Returns the inner literals. Every literal in this set is non-empty.
+    ///
+    /// These are the prefix literals of the part of the expression that
+    /// follows `prefix`, so literals may be cut.
+    pub fn literals(&self) -> &Literals {
+        &self.lits
+    }
+
+    /// Consumes these inner literals and returns the prefix and the literals.
+    pub fn into_parts(self) -> (Hir, Literals) {
+        (self.prefix, self.lits)
+    }
+}
+
 impl Literals {
     /// Returns a new empty set of literals using default limits.
     pub fn empty() -> Literals {
@@ -68,6 +103,58 @@ impl Literals {
         lits
     }
 
+    /// Returns a set of required inner literals extracted from the given
+    /// `Hir`, along with the part of the `Hir` that precedes them.
+    ///
+    /// Inner literals are only extracted from the elements of a top-level
+    /// concatenation, excluding the first one (whose literals are just
+    /// prefixes). They are chosen such that no match of the preceding part
+    /// can contain the first byte of any literal in the set. This means the
+    /// start of the leftmost match can be found by searching for the
+    /// leftmost occurrence of a literal and matching the preceding part in
+    /// reverse from there.
+    ///
+    /// When there are several candidates, the one whose shortest literal is
+    /// longest is returned. If there are none, then `None` is returned.
+    pub fn inner(expr: &Hir) -> Option<InnerLiterals> {
+        let es = match *expr.kind() {
+            HirKind::Group(hir::Group { ref hir, .. }) => {
+                return Literals::inner(hir);
+            }
+            HirKind::Concat(ref es) => es,
+            _ => return None,
+        };
+        let mut prefix_bytes = [false; 256];
+        let mut best: Option<(usize, Literals)> = None;
+        for i in 1..es.len() {
+            add_match_bytes(&es[i - 1], &mut prefix_bytes);
+            // Splitting a literal string anywhere but at its start only
+            // yields a shorter literal.
+            if is_literal(&es[i - 1]) && is_literal(&es[i]) {
+                continue;
+            }
+            let mut lits = Literals::empty();
+            prefixes_concat(&es[i..], &mut lits);
+            if lits.is_empty() || lits.contains_empty() {
+                continue;
+            }
+            if lits.lits.iter().any(|lit| prefix_bytes[lit[0] as usize]) {
+                continue;
+            }
+            let is_better = match best {
+                None => true,
+                Some((_, ref best)) => lits.min_len() > best.min_len(),
+            };
+            if is_better {
+                best = Some((i, lits));
+            }
+        }
+        best.map(|(i, lits)| InnerLiterals {
+            prefix: Hir::concat(es[..i].to_vec()),
+            lits: lits,
+        })
+    }
+
     /// Get the approximate size limit (in bytes) of this set.
     pub fn limit_size(&self) -> usize {
         self.limit_size
@@ -383,8 +470,12 @@ impl Literals {
         if base.is_empty() {
             base = vec![Literal::empty()];
         }
-        for lits_lit in lits.literals() {
-            for mut self_lit in base.clone() {
+        // Each literal in `base` must be extended by every literal in `lits`
+        // before moving on to the next one, so that the set stays in the
+        // order of leftmost-first preference.
+        for self_lit in base {
+            for lits_lit in lits.literals() {
+                let mut self_lit = self_lit.clone();
                 self_lit.extend(&**lits_lit);
                 self_lit.cut = lits_lit.cut;
                 self.lits.push(self_lit);
@@ -467,7 +558,7 @@ impl Literals {
         cls: &hir::ClassUnicode,
         reverse: bool,
     ) -> bool {
-        use std::char;
+        use core::char;
 
         if self.class_exceeds_limits(cls_char_count(cls)) {
             return false;
@@ -476,10 +567,12 @@ impl Literals {
         if base.is_empty() {
             base = vec![Literal::empty()];
         }
-        for r in cls.iter() {
-            let (s, e) = (r.start as u32, r.end as u32 + 1);
-            for c in (s..e).filter_map(char::from_u32) {
-                for mut lit in base.clone() {
+        // See cross_product for why `base` is the outer loop.
+        for lit in base {
+            for r in cls.iter() {
+                let (s, e) = (r.start as u32, r.end as u32 + 1);
+                for c in (s..e).filter_map(char::from_u32) {
+                    let mut lit = lit.clone();
                     let mut bytes = c.to_string().into_bytes();
                     if reverse {
                         bytes.reverse();
@@ -503,10 +596,12 @@ impl Literals {
         if base.is_empty() {
             base = vec![Literal::empty()];
         }
-        for r in cls.iter() {
-            let (s, e) = (r.start as u32, r.end as u32 + 1);
-            for b in (s..e).map(|b| b as u8) {
-                for mut lit in base.clone() {
+        // See cross_product for why `base` is the outer loop.
+        for lit in base {
+            for r in cls.iter() {
+                let (s, e) = (r.start as u32, r.end as u32 + 1);
+                for b in (s..e).map(|b| b as u8) {
+                    let mut lit = lit.clone();
                     lit.push(b);
                     self.lits.push(lit);
                 }
@@ -605,7 +700,7 @@ fn prefixes(expr: &Hir, lits: &mut Literals) {
         }
         HirKind::Repetition(ref x) => match x.kind {
             hir::RepetitionKind::ZeroOrOne => {
-                repeat_zero_or_one_literals(&x.hir, lits, prefixes);
+                repeat_zero_or_one_literals(&x.hir, x.greedy, lits, prefixes);
             }
             hir::RepetitionKind::ZeroOrMore => {
                 repeat_zero_or_more_literals(&x.hir, lits, prefixes);
@@ -626,27 +721,7 @@ fn prefixes(expr: &Hir, lits: &mut Literals) {
         },
         HirKind::Concat(ref es) if es.is_empty() => {}
         HirKind::Concat(ref es) if es.len() == 1 => prefixes(&es[0], lits),
-        HirKind::Concat(ref es) => {
-            for e in es {
-                if let HirKind::Anchor(hir::Anchor::StartText) = *e.kind() {
-                    if !lits.is_empty() {
-                        lits.cut();
-                        break;
-                    }
-                    lits.add(Literal::empty());
-                    continue;
-                }
-                let mut lits2 = lits.to_empty();
-                prefixes(e, &mut lits2);
-                if !lits.cross_product(&lits2) || !lits2.any_complete() {
-                    // If this expression couldn't yield any literal that
-                    // could be extended, then we need to quit. Since we're
-                    // short-circuiting, we also need to freeze every member.
-                    lits.cut();
-                    break;
-                }
-            }
-        }
+        HirKind::Concat(ref es) => prefixes_concat(es, lits),
         HirKind::Alternation(ref es) => {
             alternate_literals(es, lits, prefixes);
         }
@@ -654,6 +729,28 @@ fn prefixes(expr: &Hir, lits: &mut Literals) {
     }
 }
 
+fn prefixes_concat(es: &[Hir], lits: &mut Literals) {
+    for e in es {
+        if let HirKind::Anchor(hir::Anchor::StartText) = *e.kind() {
+            if !lits.is_empty() {
+                lits.cut();
+                break;
+            }
+            lits.add(Literal::empty());
+            continue;
+        }
+        let mut lits2 = lits.to_empty();
+        prefixes(e, &mut lits2);
+        if !lits.cross_product(&lits2) || !lits2.any_complete() {
+            // If this expression couldn't yield any literal that could be
+            // extended, then we need to quit. Since we're short-circuiting,
+            // we also need to freeze every member.
+            lits.cut();
+            break;
+        }
+    }
+}
+
 fn suffixes(expr: &Hir, lits: &mut Literals) {
     match *expr.kind() {
         HirKind::Literal(hir::Literal::Unicode(c)) => {
@@ -681,7 +778,7 @@ fn suffixes(expr: &Hir, lits: &mut Literals) {
         }
         HirKind::Repetition(ref x) => match x.kind {
             hir::RepetitionKind::ZeroOrOne => {
-                repeat_zero_or_one_literals(&x.hir, lits, suffixes);
+                repeat_zero_or_one_literals(&x.hir, x.greedy, lits, suffixes);
             }
             hir::RepetitionKind::ZeroOrMore => {
                 repeat_zero_or_more_literals(&x.hir, lits, suffixes);
@@ -730,11 +827,75 @@ fn suffixes(expr: &Hir, lits: &mut Literals) {
     }
 }
 
+/// Marks every byte that can occur in a match of the given expression.
+///
+/// This is conservative: if a Unicode class contains any non-ASCII
+/// codepoint, then every non-ASCII byte is marked.
+fn add_match_bytes(expr: &Hir, bytes: &mut [bool; 256]) {
+    match *expr.kind() {
+        HirKind::Empty | HirKind::Anchor(_) | HirKind::WordBoundary(_) => {}
+        HirKind::Literal(hir::Literal::Unicode(c)) => {
+            let mut buf = [0; 4];
+            for &b in c.encode_utf8(&mut buf).as_bytes() {
+                bytes[b as usize] = true;
+            }
+        }
+        HirKind::Literal(hir::Literal::Byte(b)) => {
+            bytes[b as usize] = true;
+        }
+        HirKind::Class(hir::Class::Unicode(ref cls)) => {
+            for r in cls.iter() {
+                let (start, end) = (r.start() as usize, r.end() as usize);
+                for b in start..cmp::min(end, 0x7F) + 1 {
+                    bytes[b] = true;
+                }
+                if end > 0x7F {
+                    for b in &mut bytes[0x80..] {
+                        *b = true;
+                    }
+                }
+            }
+        }
+        HirKind::Class(hir::Class::Bytes(ref cls)) => {
+            for r in cls.iter() {
+                for b in r.start() as usize..r.end() as usize + 1 {
+                    bytes[b] = true;
+                }
+            }
+        }
+        HirKind::Group(hir::Group { ref hir, .. }) => {
+            add_match_bytes(hir, bytes);
+        }
+        HirKind::Repetition(ref x) => add_match_bytes(&x.hir, bytes),
+        HirKind::Concat(ref es) | HirKind::Alternation(ref es) => {
+            for e in es {
+                add_match_bytes(e, bytes);
+            }
+        }
+    }
+}
+
+fn is_literal(expr: &Hir) -> bool {
+    match *expr.kind() {
+        HirKind::Literal(_) => true,
+        _ => false,
+    }
+}
+
 fn repeat_zero_or_one_literals<F: FnMut(&Hir, &mut Literals)>(
     e: &Hir,
+    greedy: bool,
     lits: &mut Literals,
     mut f: F,
 ) {
+    if !greedy {
+        // The literals found below list the longer literal first, which is
+        // only right when `e?` is greedy. For `e??`, the shorter literal
+        // should be preferred, so treat it like `e*?` instead, which cuts
+        // the longer literals.
+        repeat_zero_or_more_literals(e, lits, f);
+        return;
+    }
     let (mut lits2, mut lits3) = (lits.clone(), lits.to_empty());
     lits3.set_limit_size(lits.limit_size() / 2);
     f(e, &mut lits3);
@@ -923,7 +1084,7 @@ fn position(needle: &[u8], mut haystack: &[u8]) -> Option<usize> {
 }
 
 fn escape_unicode(bytes: &[u8]) -> String {
-    let show = match ::std::str::from_utf8(bytes) {
+    let show = match ::core::str::from_utf8(bytes) {
         Ok(v) => v.to_string(),
         Err(_) => escape_bytes(bytes),
     };
@@ -956,7 +1117,7 @@ fn escape_bytes(bytes: &[u8]) -> String {
 }
 
 fn escape_byte(byte: u8) -> String {
-    use std::ascii::escape_default;
+    use core::ascii::escape_default;
 
     let escaped: Vec<u8> = escape_default(byte).collect();
     String::from_utf8_lossy(&escaped).into_owned()
@@ -974,7 +1135,7 @@ fn cls_byte_count(cls: &hir::ClassBytes) -> usize {
 
 #[cfg(test)]
 mod tests {
-    use std::fmt;
+    use core::fmt;
 
     use super::{escape_bytes, Literal, Literals};
     use hir::Hir;
@@ -1130,17 +1291,19 @@ mod tests {
         prefixes,
         "(?i-u)abc",
         M("ABC"),
-        M("aBC"),
-        M("AbC"),
-        M("abC"),
         M("ABc"),
-        M("aBc"),
+        M("AbC"),
         M("Abc"),
+        M("aBC"),
+        M("aBc"),
+        M("abC"),
         M("abc")
     );
     test_lit!(pfx_group1, prefixes, "(a)", M("a"));
     test_lit!(pfx_rep_zero_or_one1, prefixes, "a?");
     test_lit!(pfx_rep_zero_or_one2, prefixes, "(?:abc)?");
+    test_lit!(pfx_rep_zero_or_one_cat1, prefixes, "ab?", M("ab"), M("a"));
+    test_lit!(pfx_rep_zero_or_one_cat2, prefixes, "ab??", C("ab"), M("a"));
     test_lit!(pfx_rep_zero_or_more1, prefixes, "a*");
     test_lit!(pfx_rep_zero_or_more2, prefixes, "(?:abc)*");
     test_lit!(pfx_rep_one_or_more1, prefixes, "a+", C("a"));
@@ -1162,12 +1325,12 @@ mod tests {
         prefixes,
         "(?i-u)[ab]z",
         M("AZ"),
-        M("BZ"),
-        M("aZ"),
-        M("bZ"),
         M("Az"),
+        M("BZ"),
         M("Bz"),
+        M("aZ"),
         M("az"),
+        M("bZ"),
         M("bz")
     );
     test_lit!(
@@ -1175,8 +1338,8 @@ mod tests {
         prefixes,
         "[ab][yz]",
         M("ay"),
-        M("by"),
         M("az"),
+        M("by"),
         M("bz")
     );
     test_lit!(pfx_cat5, prefixes, "a*b", C("a"), M("b"));
@@ -1250,8 +1413,8 @@ mod tests {
         prefixes,
         r"M[ou]'?am+[ae]r .*([AEae]l[- ])?[GKQ]h?[aeu]+([dtz][dhz]?)+af[iy]",
         C("Mo\\'am"),
-        C("Mu\\'am"),
         C("Moam"),
+        C("Mu\\'am"),
         C("Muam")
     );
 
@@ -1299,8 +1462,8 @@ mod tests {
         prefixes,
         "(?i-u)foobar",
         C("FO"),
-        C("fO"),
         C("Fo"),
+        C("fO"),
         C("fo")
     );
     test_exhausted!(
@@ -1363,12 +1526,12 @@ mod tests {
         suffixes,
         "(?i-u)abc",
         M("ABC"),
-        M("ABc"),
-        M("AbC"),
-        M("Abc"),
         M("aBC"),
-        M("aBc"),
+        M("AbC"),
         M("abC"),
+        M("ABc"),
+        M("aBc"),
+        M("Abc"),
         M("abc")
     );
     test_lit!(sfx_group1, suffixes, "(a)", M("a"));
@@ -1395,12 +1558,12 @@ mod tests {
         suffixes,
         "(?i-u)[ab]z",
         M("AZ"),
-        M("Az"),
         M("BZ"),
-        M("Bz"),
         M("aZ"),
-        M("az"),
         M("bZ"),
+        M("Az"),
+        M("Bz"),
+        M("az"),
         M("bz")
     );
     test_lit!(
@@ -1408,8 +1571,8 @@ mod tests {
         suffixes,
         "[ab][yz]",
         M("ay"),
-        M("az"),
         M("by"),
+        M("az"),
         M("bz")
     );
     test_lit!(sfx_cat5, suffixes, "a*b", C("ab"), M("b"));
@@ -1486,8 +1649,8 @@ mod tests {
         suffixes,
         "(?i-u)foobar",
         C("AR"),
-        C("Ar"),
         C("aR"),
+        C("Ar"),
         C("ar")
     );
     test_exhausted!(
@@ -1517,6 +1680,48 @@ mod tests {
         C("aaaaaaaaaaaaaaaaaaaa")
     );
 
+    // ************************************************************************
+    // Tests for inner literal extraction.
+    // ************************************************************************
+
+    macro_rules! test_inner {
+        ($name:ident, $re:expr, None) => {
+            #[test]
+            fn $name() {
+                let expr = ParserBuilder::new().build().parse($re).unwrap();
+                assert_eq!(None, Literals::inner(&expr));
+            }
+        };
+        ($name:ident, $re:expr, $prefix:expr, $($lit:expr),*) => {
+            #[test]
+            fn $name() {
+                let expr = ParserBuilder::new().build().parse($re).unwrap();
+                let prefix =
+                    ParserBuilder::new().build().parse($prefix).unwrap();
+                let inner = Literals::inner(&expr).unwrap();
+                assert_eq!(&prefix, inner.prefix());
+                let expected: Vec<ULiteral> = vec![$($lit),*];
+                assert_eq!(
+                    Unicode(expected),
+                    Unicode(escape_lits(inner.literals().literals()))
+                );
+            }
+        };
+    }
+
+    test_inner!(inner1, r"\w+@example\.com\s", r"\w+", C("@example.com"));
+    test_inner!(inner2, r"\d+foo", r"\d+", M("foo"));
+    test_inner!(inner3, r"[a-z]+@[a-z]+\.com", r"[a-z]+@[a-z]+", M(".com"));
+    test_inner!(inner4, r"(\d+(?:ab|cd)\d)", r"\d+", C("ab"), C("cd"));
+    test_inner!(inner5, r"\w+@", r"\w+", M("@"));
+    test_inner!(inner6, "foo", None);
+    test_inner!(inner7, r"a\w+", None);
+    // The prefix could match the first byte of the literal.
+    test_inner!(inner8, r"\w+\s+foo", None);
+    test_inner!(inner9, r"(?:c\w@x|x)@", None);
+    // Every literal must be non-empty.
+    test_inner!(inner10, r"\d+(?:foo)?\d", None);
+
     // ************************************************************************
     // Tests for generating unambiguous literal sets.
     // ************************************************************************
@@ -1,12 +1,16 @@
 /*!
 Defines a high-level intermediate representation for regular expressions.
 */
-use std::char;
-use std::cmp;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::char;
+use core::cmp;
+use core::fmt;
+use core::result;
+use core::u8;
+#[cfg(feature = "std")]
 use std::error;
-use std::fmt;
-use std::result;
-use std::u8;
 
 use ast::Span;
 use hir::interval::{Interval, IntervalSet, IntervalSetIter};
@@ -16,8 +20,10 @@ pub use hir::visitor::{visit, Visitor};
 pub use unicode::CaseFoldError;
 
 mod interval;
+pub mod generate;
 pub mod literal;
 pub mod print;
+pub mod simplify;
 pub mod translate;
 mod visitor;
 
@@ -75,6 +81,11 @@ pub enum ErrorKind {
     /// available, and the regular expression required Unicode aware case
     /// insensitivity.
     UnicodeCaseUnavailable,
+    /// This occurs when the Unicode text segmentation tables are not
+    /// available, and the regular expression used an extended grapheme
+    /// cluster (`\X`). This can occur when the `unicode-segment` or
+    /// `unicode-bool` crate features are not enabled.
+    UnicodeSegmentUnavailable,
     /// This occurs when the translator attempts to construct a character class
     /// that is empty.
     ///
@@ -106,12 +117,18 @@ impl ErrorKind {
                 "Unicode-aware case insensitivity matching is not available \
                  (make sure the unicode-case feature is enabled)"
             }
+            UnicodeSegmentUnavailable => {
+                "Unicode text segmentation is not available \
+                 (make sure the unicode-segment and unicode-bool \
+                 features are enabled)"
+            }
             EmptyClassNotAllowed => "empty character classes are not allowed",
             __Nonexhaustive => unreachable!(),
         }
     }
 }
 
+#[cfg(feature = "std")]
 impl error::Error for Error {
     fn description(&self) -> &str {
         self.kind.description()
@@ -217,7 +234,7 @@ impl Hir {
     /// Consumes ownership of this HIR expression and returns its underlying
     /// `HirKind`.
     pub fn into_kind(mut self) -> HirKind {
-        use std::mem;
+        use core::mem;
         mem::replace(&mut self.kind, HirKind::Empty)
     }
 
@@ -1302,14 +1319,28 @@ pub enum WordBoundary {
     Ascii,
     /// Match an ASCII-only negation of a word boundary.
     AsciiNegate,
+    /// Match a word boundary as defined by the default word boundary rules
+    /// of [UAX#29](https://www.unicode.org/reports/tr29/#Word_Boundaries).
+    ///
+    /// Unlike `Unicode`, which only considers whether the adjacent characters
+    /// are word characters, this looks at the surrounding text. For example,
+    /// there is no boundary in the middle of `can't` or `3.14`, and emoji
+    /// sequences joined by a zero width joiner are kept together.
+    UnicodeSegment,
+    /// Match a negation of a UAX#29 word boundary.
+    UnicodeSegmentNegate,
 }
 
 impl WordBoundary {
     /// Returns true if and only if this word boundary assertion is negated.
     pub fn is_negated(&self) -> bool {
         match *self {
-            WordBoundary::Unicode | WordBoundary::Ascii => false,
-            WordBoundary::UnicodeNegate | WordBoundary::AsciiNegate => true,
+            WordBoundary::Unicode
+            | WordBoundary::Ascii
+            | WordBoundary::UnicodeSegment => false,
+            WordBoundary::UnicodeNegate
+            | WordBoundary::AsciiNegate
+            | WordBoundary::UnicodeSegmentNegate => true,
         }
     }
 }
@@ -1419,7 +1450,7 @@ pub enum RepetitionRange {
 /// space but heap space proportional to the depth of the total `Hir`.
 impl Drop for Hir {
     fn drop(&mut self) {
-        use std::mem;
+        use core::mem;
 
         match *self.kind() {
             HirKind::Empty
@@ -2,7 +2,7 @@
 This module provides a regular expression printer for `Hir`.
 */
 
-use std::fmt;
+use core::fmt;
 
 use hir::visitor::{self, Visitor};
 use hir::{self, Hir, HirKind};
@@ -145,6 +145,12 @@ impl<'p, W: fmt::Write> Visitor for Writer<'p, W> {
             HirKind::WordBoundary(hir::WordBoundary::AsciiNegate) => {
                 self.wtr.write_str(r"(?-u:\B)")?;
             }
+            HirKind::WordBoundary(hir::WordBoundary::UnicodeSegment) => {
+                self.wtr.write_str(r"\b{wb}")?;
+            }
+            HirKind::WordBoundary(hir::WordBoundary::UnicodeSegmentNegate) => {
+                self.wtr.write_str(r"\B{wb}")?;
+            }
             HirKind::Group(ref x) => match x.kind {
                 hir::GroupKind::CaptureIndex(_) => {
                     self.wtr.write_str("(")?;
@@ -0,0 +1,539 @@
+/*!
+This module provides a simplification pass for `Hir`.
+
+The translator produces an HIR that mirrors the structure of the concrete
+syntax. For example, `a|a`, `(?:ab)|(?:ac)` and `[a-c]|d` are all translated
+verbatim, even though they could be expressed with fewer instructions. The
+simplifier rewrites an HIR into an equivalent (under leftmost-first match
+semantics, including capture group offsets) but smaller HIR.
+*/
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::mem;
+
+use hir::visitor::{self, Visitor};
+use hir::{self, Hir, HirKind};
+
+/// A simplifier for a regular expression's high-level intermediate
+/// representation.
+///
+/// The following rewrites are applied:
+///
+/// 1. Non-capturing groups are removed. (They are reintroduced only where
+///    they are needed to preserve the meaning of the `Display` output.)
+/// 2. Nested concatenations and alternations are flattened, and empty
+///    expressions in concatenations are removed.
+/// 3. An alternative that is identical to a preceding alternative is
+///    removed, since it can never be chosen. Alternatives containing
+///    capturing groups are never removed.
+/// 4. Adjacent alternatives that each match exactly one character (literals
+///    and classes) are merged into a single class, e.g., `[a-c]|d` becomes
+///    `[a-d]`.
+/// 5. Adjacent alternatives that start with the same literals have that
+///    common prefix factored out, e.g., `abc|abd` becomes `ab(?:c|d)`.
+///
+/// Only adjacent alternatives are ever merged or factored, since reordering
+/// alternatives would change which match is preferred.
+///
+/// Like literal extraction, prefix factoring uses recursion, bounded by the
+/// depth of the tree of factored prefixes. All other rewrites use constant
+/// stack space.
+#[derive(Debug)]
+pub struct Simplifier {
+    _priv: (),
+}
+
+/// A stack frame used while simplifying an HIR bottom up.
+enum Frame {
+    /// A repetition whose child is being simplified.
+    Repetition { kind: hir::RepetitionKind, greedy: bool },
+    /// A group whose child is being simplified.
+    Group { kind: hir::GroupKind },
+    /// A concatenation. `done` holds the simplified children so far and
+    /// `todo` the remaining children to simplify.
+    Concat { done: Vec<Hir>, todo: ::alloc::vec::IntoIter<Hir> },
+    /// An alternation. `done` holds the simplified children so far and
+    /// `todo` the remaining children to simplify.
+    Alternation { done: Vec<Hir>, todo: ::alloc::vec::IntoIter<Hir> },
+}
+
+impl Simplifier {
+    /// Create a new simplifier.
+    pub fn new() -> Simplifier {
+        Simplifier { _priv: () }
+    }
+
+    /// Simplify the given HIR.
+    pub fn simplify(&self, hir: Hir) -> Hir {
+        let mut stack: Vec<Frame> = vec![];
+        let mut cur = hir;
+        loop {
+            // Descend until we hit a leaf.
+            let mut result = match cur.into_kind() {
+                HirKind::Repetition(rep) => {
+                    let hir::Repetition { kind, greedy, hir } = rep;
+                    stack.push(Frame::Repetition {
+                        kind: kind,
+                        greedy: greedy,
+                    });
+                    cur = *hir;
+                    continue;
+                }
+                HirKind::Group(group) => {
+                    let hir::Group { kind, hir } = group;
+                    stack.push(Frame::Group { kind: kind });
+                    cur = *hir;
+                    continue;
+                }
+                HirKind::Concat(exprs) => {
+                    let mut todo = exprs.into_iter();
+                    // A concatenation always has at least two children.
+                    cur = todo.next().unwrap();
+                    stack.push(Frame::Concat { done: vec![], todo: todo });
+                    continue;
+                }
+                HirKind::Alternation(exprs) => {
+                    let mut todo = exprs.into_iter();
+                    // An alternation always has at least two children.
+                    cur = todo.next().unwrap();
+                    stack
+                        .push(Frame::Alternation { done: vec![], todo: todo });
+                    continue;
+                }
+                HirKind::Empty => Hir::empty(),
+                HirKind::Literal(lit) => Hir::literal(lit),
+                HirKind::Class(cls) => Hir::class(cls),
+                HirKind::Anchor(anchor) => Hir::anchor(anchor),
+                HirKind::WordBoundary(wb) => Hir::word_boundary(wb),
+            };
+            // Ascend, rebuilding every expression whose children are done.
+            loop {
+                match stack.pop() {
+                    None => return result,
+                    Some(Frame::Repetition { kind, greedy }) => {
+                        result = Hir::repetition(hir::Repetition {
+                            kind: kind,
+                            greedy: greedy,
+                            hir: Box::new(group_if_compound(result)),
+                        });
+                    }
+                    Some(Frame::Group { kind }) => {
+                        result = match kind {
+                            hir::GroupKind::NonCapturing => result,
+                            kind => Hir::group(hir::Group {
+                                kind: kind,
+                                hir: Box::new(result),
+                            }),
+                        };
+                    }
+                    Some(Frame::Concat { mut done, mut todo }) => {
+                        done.push(result);
+                        if let Some(next) = todo.next() {
+                            stack.push(Frame::Concat {
+                                done: done,
+                                todo: todo,
+                            });
+                            cur = next;
+                            break;
+                        }
+                        result = concat(done);
+                    }
+                    Some(Frame::Alternation { mut done, mut todo }) => {
+                        done.push(result);
+                        if let Some(next) = todo.next() {
+                            stack.push(Frame::Alternation {
+                                done: done,
+                                todo: todo,
+                            });
+                            cur = next;
+                            break;
+                        }
+                        result = alternation(done);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Build a concatenation from already simplified children.
+///
+/// If every child is an empty sub-expression, then the concatenation is kept
+/// as is, with its empty children in groups. The compiler rejects empty
+/// alternatives but not concatenations of empty groups, and simplifying must
+/// not turn a valid expression into an invalid one.
+fn concat(exprs: Vec<Hir>) -> Hir {
+    if exprs.iter().all(is_empty_sub_expression) {
+        let exprs = exprs
+            .into_iter()
+            .map(|e| if e.kind().is_empty() { non_capturing(e) } else { e })
+            .collect();
+        return Hir::concat(exprs);
+    }
+    let mut flat = Vec::with_capacity(exprs.len());
+    for e in exprs {
+        match e.into_kind() {
+            HirKind::Empty => {}
+            HirKind::Concat(es) => flat.extend(es),
+            kind => flat.push(from_kind(kind)),
+        }
+    }
+    if flat.len() > 1 {
+        flat = flat.into_iter().map(group_if_alternation).collect();
+    }
+    Hir::concat(flat)
+}
+
+/// Build an alternation from already simplified children.
+fn alternation(exprs: Vec<Hir>) -> Hir {
+    let mut flat: Vec<Hir> = Vec::with_capacity(exprs.len());
+    for e in exprs {
+        let es = match e.into_kind() {
+            HirKind::Alternation(es) => es,
+            kind => vec![from_kind(kind)],
+        };
+        for e in es {
+            // A later alternative identical to an earlier one can never be
+            // chosen. We only remove it if that doesn't change the capture
+            // groups present. Alternatives that can match the empty string
+            // are kept, since inside a repetition the engines give up on an
+            // empty iteration through the first copy but not the second.
+            // (This also keeps empty alternatives, which the compiler
+            // rejects. Simplifying must not make an invalid expression
+            // valid.)
+            if !has_captures(&e) && !e.is_match_empty() && flat.contains(&e) {
+                continue;
+            }
+            flat.push(e);
+        }
+    }
+    let flat = merge_classes(flat);
+    let flat = factor_prefixes(flat);
+    Hir::alternation(flat)
+}
+
+/// Merge every run of adjacent alternatives that each match exactly one
+/// character into a single class.
+fn merge_classes(exprs: Vec<Hir>) -> Vec<Hir> {
+    let mut merged: Vec<Hir> = Vec::with_capacity(exprs.len());
+    // The class of the current run, along with the number of alternatives
+    // in the run.
+    let mut run: Option<(hir::Class, usize)> = None;
+    let mut last: Option<Hir> = None;
+    for e in exprs {
+        let cls = match as_class(&e) {
+            None => {
+                flush_run(&mut merged, run.take(), last.take());
+                merged.push(e);
+                continue;
+            }
+            Some(cls) => cls,
+        };
+        run = match run.take() {
+            None => Some((cls, 1)),
+            Some((acc, n)) => match union(&acc, &cls) {
+                Some(acc) => Some((acc, n + 1)),
+                None => {
+                    flush_run(&mut merged, Some((acc, n)), last.take());
+                    Some((cls, 1))
+                }
+            },
+        };
+        last = Some(e);
+    }
+    flush_run(&mut merged, run, last);
+    merged
+}
+
+/// Push the class for the given run. If the run consists of a single
+/// alternative, then that alternative is pushed unchanged instead, since a
+/// literal is cheaper to search for than a class.
+fn flush_run(
+    merged: &mut Vec<Hir>,
+    run: Option<(hir::Class, usize)>,
+    last: Option<Hir>,
+) {
+    match run {
+        None => {}
+        Some((_, 1)) => merged.push(last.unwrap()),
+        Some((cls, _)) => merged.push(Hir::class(cls)),
+    }
+}
+
+/// If the given expression matches exactly one character, then return it as
+/// a class.
+fn as_class(hir: &Hir) -> Option<hir::Class> {
+    match *hir.kind() {
+        HirKind::Literal(hir::Literal::Unicode(c)) => {
+            let range = hir::ClassUnicodeRange::new(c, c);
+            Some(hir::Class::Unicode(hir::ClassUnicode::new(vec![range])))
+        }
+        HirKind::Literal(hir::Literal::Byte(b)) => {
+            let range = hir::ClassBytesRange::new(b, b);
+            Some(hir::Class::Bytes(hir::ClassBytes::new(vec![range])))
+        }
+        HirKind::Class(ref cls) => Some(cls.clone()),
+        _ => None,
+    }
+}
+
+/// Return the union of two classes, or `None` if one is a Unicode class and
+/// the other is a byte class that cannot be represented as Unicode.
+fn union(a: &hir::Class, b: &hir::Class) -> Option<hir::Class> {
+    match (a, b) {
+        (&hir::Class::Unicode(ref a), &hir::Class::Unicode(ref b)) => {
+            let mut a = a.clone();
+            a.union(b);
+            Some(hir::Class::Unicode(a))
+        }
+        (&hir::Class::Bytes(ref a), &hir::Class::Bytes(ref b)) => {
+            let mut a = a.clone();
+            a.union(b);
+            Some(hir::Class::Bytes(a))
+        }
+        (&hir::Class::Unicode(ref a), &hir::Class::Bytes(ref b))
+        | (&hir::Class::Bytes(ref b), &hir::Class::Unicode(ref a)) => {
+            // ASCII is the same in both worlds, which is common when
+            // Unicode mode is disabled: `(?-u)[a-c]|d` has a byte class and
+            // a Unicode literal.
+            let a = ascii_bytes(a)?;
+            let mut b = b.clone();
+            b.union(&a);
+            Some(hir::Class::Bytes(b))
+        }
+    }
+}
+
+/// Convert the given Unicode class to a byte class if it is all ASCII.
+fn ascii_bytes(cls: &hir::ClassUnicode) -> Option<hir::ClassBytes> {
+    let mut ranges = vec![];
+    for r in cls.iter() {
+        if r.end() > '\x7F' {
+            return None;
+        }
+        ranges.push(hir::ClassBytesRange::new(r.start() as u8, r.end() as u8));
+    }
+    Some(hir::ClassBytes::new(ranges))
+}
+
+/// Factor out common literal prefixes of adjacent alternatives.
+///
+/// Only literals are factored, since a literal can only ever match in one
+/// way. Factoring a prefix that can match in several ways (like `a*`) could
+/// change which match is preferred.
+fn factor_prefixes(exprs: Vec<Hir>) -> Vec<Hir> {
+    let mut factored: Vec<Hir> = Vec::with_capacity(exprs.len());
+    let mut run: Vec<Vec<Hir>> = vec![];
+    for e in exprs {
+        let seq = into_seq(e);
+        let same = match (run.last(), seq.first()) {
+            (Some(prev), Some(first)) => {
+                is_literal(first) && prev.first() == Some(first)
+            }
+            _ => false,
+        };
+        if !same {
+            flush_prefix_run(&mut factored, mem::replace(&mut run, vec![]));
+        }
+        run.push(seq);
+    }
+    flush_prefix_run(&mut factored, run);
+    factored
+}
+
+/// Emit a run of alternatives (each given as a sequence of concatenated
+/// expressions) that all start with the same literal.
+fn flush_prefix_run(factored: &mut Vec<Hir>, mut run: Vec<Vec<Hir>>) {
+    if run.len() <= 1 {
+        factored.extend(run.pop().map(concat));
+        return;
+    }
+    // Find the longest common literal prefix. The prefix must leave at least
+    // one expression in every alternative, since the compiler rejects empty
+    // alternatives.
+    let max = run.iter().map(|seq| seq.len()).min().unwrap() - 1;
+    let mut len = 0;
+    while len < max {
+        let first = match run[0].get(len) {
+            Some(e) if is_literal(e) => e,
+            _ => break,
+        };
+        if run[1..].iter().any(|seq| seq.get(len) != Some(first)) {
+            break;
+        }
+        len += 1;
+    }
+    // A remainder made up of only empty sub-expressions, like `()`, would
+    // also be rejected, so shorten the prefix until there is none.
+    while len > 0
+        && run
+            .iter()
+            .any(|seq| seq[len..].iter().all(is_empty_sub_expression))
+    {
+        len -= 1;
+    }
+    if len == 0 {
+        factored.extend(run.into_iter().map(concat));
+        return;
+    }
+    let mut prefix: Vec<Hir> = vec![];
+    let mut rests: Vec<Hir> = Vec::with_capacity(run.len());
+    for (i, mut seq) in run.into_iter().enumerate() {
+        let rest = seq.split_off(len);
+        if i == 0 {
+            prefix = seq;
+        }
+        rests.push(concat(rest));
+    }
+    prefix.push(alternation(rests));
+    factored.push(concat(prefix));
+}
+
+/// Return the children of a concatenation, or the expression itself.
+fn into_seq(hir: Hir) -> Vec<Hir> {
+    match hir.into_kind() {
+        HirKind::Concat(es) => es,
+        HirKind::Empty => vec![],
+        kind => vec![from_kind(kind)],
+    }
+}
+
+/// Returns true if the given expression is empty, possibly inside groups.
+/// This mirrors what the compiler considers an empty alternative.
+fn is_empty_sub_expression(hir: &Hir) -> bool {
+    match *hir.kind() {
+        HirKind::Empty => true,
+        HirKind::Group(ref group) => is_empty_sub_expression(&group.hir),
+        _ => false,
+    }
+}
+
+fn is_literal(hir: &Hir) -> bool {
+    match *hir.kind() {
+        HirKind::Literal(_) => true,
+        _ => false,
+    }
+}
+
+/// Wrap the given expression in a non-capturing group if it is an
+/// alternation, which is necessary when it appears in a concatenation.
+fn group_if_alternation(hir: Hir) -> Hir {
+    match *hir.kind() {
+        HirKind::Alternation(_) => non_capturing(hir),
+        _ => hir,
+    }
+}
+
+/// Wrap the given expression in a non-capturing group if it is made up of
+/// more than one expression, which is necessary when it is repeated.
+fn group_if_compound(hir: Hir) -> Hir {
+    match *hir.kind() {
+        HirKind::Concat(_) | HirKind::Alternation(_) => non_capturing(hir),
+        _ => hir,
+    }
+}
+
+fn non_capturing(hir: Hir) -> Hir {
+    Hir::group(hir::Group {
+        kind: hir::GroupKind::NonCapturing,
+        hir: Box::new(hir),
+    })
+}
+
+/// Rebuild an expression from its kind.
+///
+/// This recomputes the analysis info of the expression, which is cheap since
+/// the info of its children is already computed.
+fn from_kind(kind: HirKind) -> Hir {
+    match kind {
+        HirKind::Empty => Hir::empty(),
+        HirKind::Literal(lit) => Hir::literal(lit),
+        HirKind::Class(cls) => Hir::class(cls),
+        HirKind::Anchor(anchor) => Hir::anchor(anchor),
+        HirKind::WordBoundary(wb) => Hir::word_boundary(wb),
+        HirKind::Repetition(rep) => Hir::repetition(rep),
+        HirKind::Group(group) => Hir::group(group),
+        HirKind::Concat(es) => Hir::concat(es),
+        HirKind::Alternation(es) => Hir::alternation(es),
+    }
+}
+
+/// Returns true if and only if the given expression contains a capturing
+/// group.
+fn has_captures(hir: &Hir) -> bool {
+    visitor::visit(hir, CaptureFinder).is_err()
+}
+
+/// A visitor that fails as soon as it sees a capturing group.
+struct CaptureFinder;
+
+impl Visitor for CaptureFinder {
+    type Output = ();
+    type Err = ();
+
+    fn finish(self) -> Result<(), ()> {
+        Ok(())
+    }
+
+    fn visit_pre(&mut self, hir: &Hir) -> Result<(), ()> {
+        match *hir.kind() {
+            HirKind::Group(ref g) => match g.kind {
+                hir::GroupKind::NonCapturing => Ok(()),
+                _ => Err(()),
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Simplifier;
+    use ParserBuilder;
+
+    fn simplify(pattern: &str) -> String {
+        let hir = ParserBuilder::new().build().parse(pattern).unwrap();
+        Simplifier::new().simplify(hir).to_string()
+    }
+
+    #[test]
+    fn simplify_groups() {
+        assert_eq!(simplify("(?:a)"), "a");
+        assert_eq!(simplify("(?:(?:ab))c"), "abc");
+        assert_eq!(simplify("(?:ab)*"), "(?:ab)*");
+        assert_eq!(simplify("(a)"), "(a)");
+        assert_eq!(simplify("(?:a|b)c"), "[a-b]c");
+        assert_eq!(simplify("(?:a|bc)d"), "(?:a|bc)d");
+    }
+
+    #[test]
+    fn simplify_duplicates() {
+        assert_eq!(simplify("a|a"), "a");
+        assert_eq!(simplify("abc|x|abc"), "abc|x");
+        assert_eq!(simplify("(a)|(a)"), "(a)|(a)");
+        assert_eq!(simplify("a||"), "a||");
+        assert_eq!(simplify("a|(?:)(?:)"), "a|(?:)(?:)");
+        assert_eq!(simplify("a|(?:())(?:)"), "a|()(?:)");
+        assert_eq!(simplify("(?:b*?|b*?)*"), "(?:b*?|b*?)*");
+    }
+
+    #[test]
+    fn simplify_classes() {
+        assert_eq!(simplify("[a-c]|d"), "[a-d]");
+        assert_eq!(simplify("a|b|cd|e|f"), "[a-b]|cd|[e-f]");
+        assert_eq!(simplify(r"(?-u)[a-c]|d"), "(?-u:[a-d])");
+        assert_eq!(simplify("a|[0-9]+|b"), "a|[0-9]+|b");
+    }
+
+    #[test]
+    fn simplify_prefixes() {
+        assert_eq!(simplify("ab|ac"), "a[b-c]");
+        assert_eq!(simplify("(?:ab)|(?:ac)"), "a[b-c]");
+        assert_eq!(simplify("abc|abd|x"), "ab[c-d]|x");
+        assert_eq!(simplify("ab|abc"), "a(?:b|bc)");
+        assert_eq!(simplify("foo|foobar|fox"), "fo(?:o|obar|x)");
+        assert_eq!(simplify("ab|x|ac"), "ab|x|ac");
+        assert_eq!(simplify("ab|a()"), "ab|a()");
+    }
+}
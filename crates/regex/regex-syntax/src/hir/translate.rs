@@ -2,8 +2,11 @@
 Defines a translator that converts an `Ast` to an `Hir`.
 */
 
-use std::cell::{Cell, RefCell};
-use std::result;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
+use core::result;
 
 use ast::{self, Ast, Span, Visitor};
 use hir::{self, Error, ErrorKind, Hir};
@@ -16,6 +19,7 @@ type Result<T> = result::Result<T, Error>;
 pub struct TranslatorBuilder {
     allow_invalid_utf8: bool,
     flags: Flags,
+    properties: Vec<(String, hir::ClassUnicode)>,
 }
 
 impl Default for TranslatorBuilder {
@@ -30,6 +34,7 @@ impl TranslatorBuilder {
         TranslatorBuilder {
             allow_invalid_utf8: false,
             flags: Flags::default(),
+            properties: vec![],
         }
     }
 
@@ -39,6 +44,7 @@ impl TranslatorBuilder {
             stack: RefCell::new(vec![]),
             flags: Cell::new(self.flags),
             allow_invalid_utf8: self.allow_invalid_utf8,
+            properties: self.properties.clone(),
         }
     }
 
@@ -91,6 +97,34 @@ impl TranslatorBuilder {
         self.flags.unicode = if yes { None } else { Some(false) };
         self
     }
+
+    /// Add a custom Unicode property that can be used in a regular
+    /// expression like any other property, e.g., `\p{OurIdentifierStart}`.
+    ///
+    /// Names are matched loosely in the same way as the names of Unicode
+    /// properties (see
+    /// [UAX44-LM3](https://www.unicode.org/reports/tr44/#UAX44-LM3)), so
+    /// `OurIdentifierStart`, `our_identifier_start` and
+    /// `our identifier start` all refer to the same property. A custom
+    /// property takes precedence over a Unicode property with the same name,
+    /// and adding a property with the same name as a previously added custom
+    /// property replaces it.
+    ///
+    /// Custom properties are available even when the Unicode data tables are
+    /// disabled via crate features. Like other Unicode classes, they can be
+    /// negated (`\P{name}`) and are case folded when case insensitive
+    /// matching is enabled, but they can only be used when the Unicode flag
+    /// (`u`) is enabled.
+    pub fn unicode_property(
+        &mut self,
+        name: &str,
+        class: hir::ClassUnicode,
+    ) -> &mut TranslatorBuilder {
+        let name = unicode::symbolic_name_normalize(name);
+        self.properties.retain(|&(ref n, _)| *n != name);
+        self.properties.push((name, class));
+        self
+    }
 }
 
 /// A translator maps abstract syntax to a high level intermediate
@@ -109,6 +143,8 @@ pub struct Translator {
     flags: Cell<Flags>,
     /// Whether we're allowed to produce HIR that can match arbitrary bytes.
     allow_invalid_utf8: bool,
+    /// Custom Unicode properties, keyed by their normalized name.
+    properties: Vec<(String, hir::ClassUnicode)>,
 }
 
 impl Translator {
@@ -129,6 +165,21 @@ impl Translator {
     pub fn translate(&mut self, pattern: &str, ast: &Ast) -> Result<Hir> {
         ast::visit(ast, TranslatorI::new(self, pattern))
     }
+
+    /// Returns the custom property named by the given query, if one exists.
+    fn property(&self, query: &ClassQuery) -> Option<hir::ClassUnicode> {
+        let name = match *query {
+            ClassQuery::OneLetter(c) => {
+                unicode::symbolic_name_normalize(&c.to_string())
+            }
+            ClassQuery::Binary(name) => unicode::symbolic_name_normalize(name),
+            ClassQuery::ByValue { .. } => return None,
+        };
+        self.properties
+            .iter()
+            .find(|&&(ref n, _)| *n == name)
+            .map(|&(_, ref class)| class.clone())
+    }
 }
 
 /// An HirFrame is a single stack frame, represented explicitly, which is
@@ -292,6 +343,9 @@ impl<'t, 'p> Visitor for TranslatorI<'t, 'p> {
             Ast::Dot(span) => {
                 self.push(HirFrame::Expr(self.hir_dot(span)?));
             }
+            Ast::GraphemeCluster(span) => {
+                self.push(HirFrame::Expr(self.hir_grapheme_cluster(span)?));
+            }
             Ast::Assertion(ref x) => {
                 self.push(HirFrame::Expr(self.hir_assertion(x)?));
             }
@@ -726,6 +780,89 @@ impl<'t, 'p> TranslatorI<'t, 'p> {
         })
     }
 
+    /// Translate `\X` into an expression that matches exactly one extended
+    /// grapheme cluster, as defined by the rules in UAX#29. This follows the
+    /// regex given in Table 1b of UAX#29:
+    ///
+    /// ```text
+    /// crlf | Control | Prepend* core [Extend ZWJ SpacingMark]*
+    /// ```
+    ///
+    /// where `core` is a Hangul syllable, a pair of regional indicators, an
+    /// emoji ZWJ sequence or any single codepoint that isn't a control.
+    fn hir_grapheme_cluster(&self, span: Span) -> Result<Hir> {
+        if !self.flags().unicode() {
+            return Err(self.error(span, ErrorKind::UnicodeNotAllowed));
+        }
+        let unavailable =
+            |_| self.error(span, ErrorKind::UnicodeSegmentUnavailable);
+        let gcb = |name| unicode::gcb(name).map_err(&unavailable);
+        let class =
+            |cls: hir::ClassUnicode| Hir::class(hir::Class::Unicode(cls));
+        let rep = |kind, hir| {
+            Hir::repetition(hir::Repetition {
+                kind: kind,
+                greedy: true,
+                hir: Box::new(hir),
+            })
+        };
+        let star = |hir| rep(hir::RepetitionKind::ZeroOrMore, hir);
+        let plus = |hir| rep(hir::RepetitionKind::OneOrMore, hir);
+
+        let (cr, lf) = (gcb("CR")?, gcb("LF")?);
+        let mut control = gcb("Control")?;
+        control.union(&cr);
+        control.union(&lf);
+        let mut not_control = control.clone();
+        not_control.negate();
+        let mut postcore = gcb("Extend")?;
+        postcore.union(&gcb("ZWJ")?);
+        postcore.union(&gcb("SpacingMark")?);
+
+        let (l, v, t) = (gcb("L")?, gcb("V")?, gcb("T")?);
+        let (lv, lvt) = (gcb("LV")?, gcb("LVT")?);
+        let hangul = Hir::alternation(vec![
+            Hir::concat(vec![
+                star(class(l.clone())),
+                Hir::alternation(vec![
+                    plus(class(v.clone())),
+                    Hir::concat(vec![class(lv), star(class(v))]),
+                    class(lvt),
+                ]),
+                star(class(t.clone())),
+            ]),
+            plus(class(l)),
+            plus(class(t)),
+        ]);
+        let ri = gcb("Regional_Indicator")?;
+        let ri_pair = Hir::concat(vec![class(ri.clone()), class(ri)]);
+        let pict = unicode::bool_property("Extended_Pictographic")
+            .map_err(&unavailable)?;
+        let pict_seq = Hir::concat(vec![
+            class(pict.clone()),
+            star(Hir::concat(vec![
+                star(class(gcb("Extend")?)),
+                class(gcb("ZWJ")?),
+                class(pict),
+            ])),
+        ]);
+        let core = Hir::alternation(vec![
+            hangul,
+            ri_pair,
+            pict_seq,
+            class(not_control),
+        ]);
+        Ok(Hir::alternation(vec![
+            Hir::concat(vec![class(cr), class(lf)]),
+            class(control),
+            Hir::concat(vec![
+                star(class(gcb("Prepend")?)),
+                core,
+                star(class(postcore)),
+            ]),
+        ]))
+    }
+
     fn hir_assertion(&self, asst: &ast::Assertion) -> Result<Hir> {
         let unicode = self.flags().unicode();
         let multi_line = self.flags().multi_line();
@@ -766,6 +903,22 @@ impl<'t, 'p> TranslatorI<'t, 'p> {
                     hir::WordBoundary::AsciiNegate
                 })
             }
+            ast::AssertionKind::WordBoundarySegment => {
+                if !unicode {
+                    return Err(
+                        self.error(asst.span, ErrorKind::UnicodeNotAllowed)
+                    );
+                }
+                Hir::word_boundary(hir::WordBoundary::UnicodeSegment)
+            }
+            ast::AssertionKind::NotWordBoundarySegment => {
+                if !unicode {
+                    return Err(
+                        self.error(asst.span, ErrorKind::UnicodeNotAllowed)
+                    );
+                }
+                Hir::word_boundary(hir::WordBoundary::UnicodeSegmentNegate)
+            }
         })
     }
 
@@ -831,10 +984,13 @@ impl<'t, 'p> TranslatorI<'t, 'p> {
                 property_value: value,
             },
         };
-        let mut result = self.convert_unicode_class_error(
-            &ast_class.span,
-            unicode::class(query),
-        );
+        let mut result = match self.trans().property(&query) {
+            Some(class) => Ok(class),
+            None => self.convert_unicode_class_error(
+                &ast_class.span,
+                unicode::class(query),
+            ),
+        };
         if let Ok(ref mut class) = result {
             self.unicode_fold_and_negate(
                 &ast_class.span,
@@ -1565,6 +1721,59 @@ mod tests {
                 ),
             }
         );
+
+        assert_eq!(t(r"\b{wb}"), hir_word(hir::WordBoundary::UnicodeSegment));
+        assert_eq!(
+            t(r"\B{wb}"),
+            hir_word(hir::WordBoundary::UnicodeSegmentNegate)
+        );
+        assert_eq!(
+            t_err(r"(?-u)\b{wb}"),
+            TestError {
+                kind: hir::ErrorKind::UnicodeNotAllowed,
+                span: Span::new(
+                    Position::new(5, 1, 6),
+                    Position::new(11, 1, 12)
+                ),
+            }
+        );
+    }
+
+    #[test]
+    fn grapheme_cluster() {
+        #[cfg(all(feature = "unicode-segment", feature = "unicode-bool"))]
+        {
+            let hir = t(r"\X");
+            assert!(hir.is_always_utf8());
+            assert!(!hir.is_match_empty());
+            assert_eq!(t(r"(?i)\X"), hir);
+            assert_eq!(t(r"(?U)\X"), hir);
+        }
+        #[cfg(not(all(
+            feature = "unicode-segment",
+            feature = "unicode-bool"
+        )))]
+        assert_eq!(
+            t_err(r"\X"),
+            TestError {
+                kind: hir::ErrorKind::UnicodeSegmentUnavailable,
+                span: Span::new(
+                    Position::new(0, 1, 1),
+                    Position::new(2, 1, 3)
+                ),
+            }
+        );
+
+        assert_eq!(
+            t_err(r"(?-u)\X"),
+            TestError {
+                kind: hir::ErrorKind::UnicodeNotAllowed,
+                span: Span::new(
+                    Position::new(5, 1, 6),
+                    Position::new(7, 1, 8)
+                ),
+            }
+        );
     }
 
     #[test]
@@ -2315,6 +2524,76 @@ mod tests {
         );
     }
 
+    #[test]
+    fn class_unicode_custom() {
+        let t_custom = |pattern: &str| {
+            let cls = hir::ClassUnicode::new(vec![
+                hir::ClassUnicodeRange::new('a', 'c'),
+                hir::ClassUnicodeRange::new('_', '_'),
+            ]);
+            TranslatorBuilder::new()
+                .unicode_property("OurIdentifierStart", cls)
+                .build()
+                .translate(pattern, &parse(pattern))
+        };
+
+        let expected = hir_uclass(&[('_', '_'), ('a', 'c')]);
+        assert_eq!(t_custom(r"\p{OurIdentifierStart}").unwrap(), expected);
+        assert_eq!(t_custom(r"\p{our_identifier_start}").unwrap(), expected);
+        assert_eq!(t_custom(r"\p{Our Identifier Start}").unwrap(), expected);
+        assert_eq!(
+            t_custom(r"\P{OurIdentifierStart}").unwrap(),
+            hir_negate(expected.clone())
+        );
+        assert_eq!(
+            t_custom(r"[^\p{OurIdentifierStart}]").unwrap(),
+            hir_negate(expected.clone())
+        );
+        #[cfg(feature = "unicode-case")]
+        assert_eq!(
+            t_custom(r"(?i)\p{OurIdentifierStart}").unwrap(),
+            hir_case_fold(expected.clone())
+        );
+        assert_eq!(
+            t_custom(r"(?-u)\p{OurIdentifierStart}").unwrap_err(),
+            TestError {
+                kind: hir::ErrorKind::UnicodeNotAllowed,
+                span: Span::new(
+                    Position::new(5, 1, 6),
+                    Position::new(27, 1, 28)
+                ),
+            }
+        );
+    }
+
+    #[test]
+    fn class_unicode_custom_precedence() {
+        let cls = hir::ClassUnicode::new(vec![hir::ClassUnicodeRange::new(
+            'x', 'x',
+        )]);
+        let mut builder = TranslatorBuilder::new();
+        builder.unicode_property("Greek", cls.clone());
+        builder.unicode_property("L", cls);
+        let mut trans = builder.build();
+        let pattern = r"\p{greek}\pL";
+        let expected = hir_uclass(&[('x', 'x')]);
+        assert_eq!(
+            trans.translate(pattern, &parse(pattern)).unwrap(),
+            hir_cat(vec![expected.clone(), expected.clone()])
+        );
+
+        // Adding a property with an existing name replaces it.
+        let cls = hir::ClassUnicode::new(vec![hir::ClassUnicodeRange::new(
+            'y', 'y',
+        )]);
+        builder.unicode_property("G R E E K", cls);
+        let pattern = r"\p{Greek}";
+        assert_eq!(
+            builder.build().translate(pattern, &parse(pattern)).unwrap(),
+            hir_uclass(&[('y', 'y')])
+        );
+    }
+
     #[test]
     fn class_bracketed() {
         assert_eq!(t("[a]"), hir_uclass(&[('a', 'a')]));
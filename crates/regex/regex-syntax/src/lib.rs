@@ -116,6 +116,10 @@ match semantics of a regular expression.
 
 The following features are available:
 
+* **std** -
+  Enables support for the standard library. This feature is enabled by
+  default. When disabled, this crate only depends on `core` and `alloc`, and
+  its error types no longer implement the `std::error::Error` trait.
 * **unicode** -
   Enables all Unicode features. This feature is enabled by default, and will
   always cover all Unicode features, even if more are added in the future.
@@ -151,14 +155,24 @@ The following features are available:
   Provide the data necessary to provide the properties used to implement the
   [Unicode text segmentation algorithms](https://www.unicode.org/reports/tr29/).
   This enables using classes like `\p{gcb=Extend}`, `\p{wb=Katakana}` and
-  `\p{sb=ATerm}`.
+  `\p{sb=ATerm}`. Together with `unicode-bool`, this also enables extended
+  grapheme clusters (`\X`) and UAX#29 word boundaries (`\b{wb}`).
 */
 
 #![deny(missing_docs)]
 #![forbid(unsafe_code)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg_attr(not(feature = "std"), macro_use)]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate core;
+
+use alloc::string::String;
 
 pub use error::{Error, Result};
 pub use parser::{Parser, ParserBuilder};
+pub use segment::UnicodeSegmentError;
 pub use unicode::UnicodeWordError;
 
 pub mod ast;
@@ -166,10 +180,19 @@ mod either;
 mod error;
 pub mod hir;
 mod parser;
+mod segment;
 mod unicode;
 mod unicode_tables;
 pub mod utf8;
 
+/// The version of Unicode that this crate's Unicode tables were generated
+/// from, as `(major, minor, update)`.
+///
+/// The tables can be regenerated from any version of the Unicode Character
+/// Database using `scripts/generate-unicode-tables` in the regex repository.
+pub const UNICODE_VERSION: (u64, u64, u64) =
+    unicode_tables::version::UNICODE_VERSION;
+
 /// Escapes all regular expression meta characters in `text`.
 ///
 /// The string returned may be safely used as a literal in a regular
@@ -246,10 +269,46 @@ pub fn is_word_character(c: char) -> bool {
 /// returns an error.
 pub fn try_is_word_character(
     c: char,
-) -> std::result::Result<bool, UnicodeWordError> {
+) -> core::result::Result<bool, UnicodeWordError> {
     unicode::is_word_character(c)
 }
 
+/// Returns true if and only if the given position in `haystack` is a word
+/// boundary according to the default word boundary rules of
+/// [UAX#29](https://www.unicode.org/reports/tr29/#Word_Boundaries).
+///
+/// This is what the `\b{wb}` assertion matches. Unlike `\b`, which only
+/// checks whether the characters on either side of a position are word
+/// characters, UAX#29 looks at the surrounding text. For example, there is
+/// no word boundary in the middle of `can't`, `3.14` or an emoji sequence
+/// joined by zero width joiners.
+///
+/// The start and end of `haystack` are always word boundaries. A position
+/// that falls inside the UTF-8 encoding of a codepoint is never a word
+/// boundary. Any byte that is not part of valid UTF-8 is treated as a single
+/// character that has no `Word_Break` property.
+///
+/// To guarantee that searching for word boundaries takes linear time, only a
+/// bounded amount of text before and after `at` is examined. As a result,
+/// the answer can differ from UAX#29 next to a run of more than 32
+/// characters ignored by rule WB4 (such as combining marks), or inside a run
+/// of more than 64 regional indicators.
+///
+/// # Errors
+///
+/// If either of the `unicode-segment` or `unicode-bool` features are not
+/// enabled, then this function always returns an error.
+///
+/// # Panics
+///
+/// If `at > haystack.len()`, then this function panics.
+pub fn try_is_word_boundary(
+    haystack: &[u8],
+    at: usize,
+) -> core::result::Result<bool, UnicodeSegmentError> {
+    segment::is_word_boundary(haystack, at)
+}
+
 /// Returns true if and only if the given character is an ASCII word character.
 ///
 /// An ASCII word character is defined by the following character class:
@@ -107,6 +107,16 @@ impl ParserBuilder {
         self
     }
 
+    /// Set the concrete syntax that patterns are written in.
+    ///
+    /// When set to anything other than `Dialect::Rust` (the default),
+    /// patterns are translated into this crate's syntax before being parsed.
+    /// See [`Dialect`](ast/enum.Dialect.html) for details.
+    pub fn dialect(&mut self, dialect: ast::Dialect) -> &mut ParserBuilder {
+        self.ast.dialect(dialect);
+        self
+    }
+
     /// Enable or disable the case insensitive flag by default.
     ///
     /// By default this is disabled. It may alternatively be selectively
@@ -155,6 +165,22 @@ impl ParserBuilder {
         self.hir.unicode(yes);
         self
     }
+
+    /// Add a custom Unicode property that can be used in a regular
+    /// expression like any other property, e.g., `\p{OurIdentifierStart}`.
+    ///
+    /// Property names are matched loosely, and custom properties take
+    /// precedence over Unicode properties with the same name. See
+    /// [`TranslatorBuilder::unicode_property`](hir/translate/struct.TranslatorBuilder.html#method.unicode_property)
+    /// for details.
+    pub fn unicode_property(
+        &mut self,
+        name: &str,
+        class: hir::ClassUnicode,
+    ) -> &mut ParserBuilder {
+        self.hir.unicode_property(name, class);
+        self
+    }
 }
 
 /// A convenience parser for regular expressions.
@@ -0,0 +1,473 @@
+/*!
+Provides routines for Unicode text segmentation, as defined by
+[UAX#29](https://www.unicode.org/reports/tr29/).
+*/
+
+use core::fmt;
+use core::result;
+#[cfg(feature = "std")]
+use std::error;
+
+/// An error that occurs when the Unicode text segmentation tables are
+/// unavailable.
+///
+/// This error can occur when the data tables necessary for finding word
+/// boundaries according to UAX#29 are unavailable. This only occurs when
+/// either of the `unicode-segment` or `unicode-bool` features are disabled.
+/// (Both features are enabled by default.)
+#[derive(Debug)]
+pub struct UnicodeSegmentError(());
+
+#[cfg(feature = "std")]
+impl error::Error for UnicodeSegmentError {}
+
+impl fmt::Display for UnicodeSegmentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Unicode text segmentation is not available \
+             (probably because the unicode-segment or unicode-bool \
+             feature is not enabled)"
+        )
+    }
+}
+
+/// Returns true if and only if the given position in `haystack` is a word
+/// boundary according to the default word boundary rules of UAX#29.
+///
+/// See [`try_is_word_boundary`](../fn.try_is_word_boundary.html) for
+/// details.
+pub fn is_word_boundary(
+    haystack: &[u8],
+    at: usize,
+) -> result::Result<bool, UnicodeSegmentError> {
+    #[cfg(not(all(feature = "unicode-segment", feature = "unicode-bool")))]
+    fn imp(_: &[u8], _: usize) -> result::Result<bool, UnicodeSegmentError> {
+        Err(UnicodeSegmentError(()))
+    }
+
+    #[cfg(all(feature = "unicode-segment", feature = "unicode-bool"))]
+    fn imp(
+        haystack: &[u8],
+        at: usize,
+    ) -> result::Result<bool, UnicodeSegmentError> {
+        Ok(word::is_boundary(haystack, at))
+    }
+
+    assert!(at <= haystack.len(), "position out of bounds");
+    imp(haystack, at)
+}
+
+#[cfg(all(feature = "unicode-segment", feature = "unicode-bool"))]
+mod word {
+    use core::cmp::{self, Ordering};
+    use core::str;
+
+    use unicode_tables::property_bool::EXTENDED_PICTOGRAPHIC;
+    use unicode_tables::word_break as wb;
+
+    use self::WordBreak::*;
+
+    /// The maximum number of characters ignored by rule WB4 that are skipped
+    /// when looking for the character on either side of a position.
+    ///
+    /// Together with `MAX_REGIONAL_INDICATORS`, this bounds the amount of
+    /// text examined for each position, which keeps searches for `\b{wb}`
+    /// linear in the length of the haystack.
+    const MAX_IGNORED: usize = 32;
+
+    /// The maximum number of regional indicators counted by rules WB15 and
+    /// WB16. A longer run is paired as if it started at the last regional
+    /// indicator counted.
+    const MAX_REGIONAL_INDICATORS: usize = 64;
+
+    /// The values of the Word_Break property.
+    ///
+    /// `Other` is also used for bytes that aren't part of valid UTF-8.
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    enum WordBreak {
+        ALetter,
+        CR,
+        DoubleQuote,
+        Extend,
+        ExtendNumLet,
+        Format,
+        HebrewLetter,
+        Katakana,
+        LF,
+        MidLetter,
+        MidNum,
+        MidNumLet,
+        Newline,
+        Numeric,
+        RegionalIndicator,
+        SingleQuote,
+        WSegSpace,
+        ZWJ,
+        Other,
+    }
+
+    const TABLES: &'static [(WordBreak, &'static [(char, char)])] = &[
+        (ALetter, wb::ALETTER),
+        (CR, wb::CR),
+        (DoubleQuote, wb::DOUBLE_QUOTE),
+        (Extend, wb::EXTEND),
+        (ExtendNumLet, wb::EXTENDNUMLET),
+        (Format, wb::FORMAT),
+        (HebrewLetter, wb::HEBREW_LETTER),
+        (Katakana, wb::KATAKANA),
+        (LF, wb::LF),
+        (MidLetter, wb::MIDLETTER),
+        (MidNum, wb::MIDNUM),
+        (MidNumLet, wb::MIDNUMLET),
+        (Newline, wb::NEWLINE),
+        (Numeric, wb::NUMERIC),
+        (RegionalIndicator, wb::REGIONAL_INDICATOR),
+        (SingleQuote, wb::SINGLE_QUOTE),
+        (WSegSpace, wb::WSEGSPACE),
+        (ZWJ, wb::ZWJ),
+    ];
+
+    impl WordBreak {
+        fn of(c: Option<char>) -> WordBreak {
+            let c = match c {
+                None => return Other,
+                Some(c) => c,
+            };
+            TABLES
+                .iter()
+                .find(|&&(_, ranges)| contains(ranges, c))
+                .map_or(Other, |&(wb, _)| wb)
+        }
+
+        /// Characters that are ignored by rule WB4.
+        fn is_ignorable(self) -> bool {
+            self == Extend || self == Format || self == ZWJ
+        }
+
+        fn is_newline(self) -> bool {
+            self == Newline || self == CR || self == LF
+        }
+
+        /// `AHLetter` in UAX#29.
+        fn is_ahletter(self) -> bool {
+            self == ALetter || self == HebrewLetter
+        }
+
+        /// `MidNumLetQ` in UAX#29.
+        fn is_midnumletq(self) -> bool {
+            self == MidNumLet || self == SingleQuote
+        }
+    }
+
+    /// Implements the default word boundary rules of UAX#29. Each `WBn`
+    /// comment refers to the corresponding rule in the specification.
+    pub fn is_boundary(haystack: &[u8], at: usize) -> bool {
+        // WB1, WB2
+        if at == 0 || at == haystack.len() {
+            return true;
+        }
+        if is_inside_char(haystack, at) {
+            return false;
+        }
+        let (prev_char, _) = decode_last(&haystack[..at]);
+        let (next_char, next_len) = decode(&haystack[at..]);
+        let (prev, next) =
+            (WordBreak::of(prev_char), WordBreak::of(next_char));
+        // WB3
+        if prev == CR && next == LF {
+            return false;
+        }
+        // WB3a, WB3b
+        if prev.is_newline() || next.is_newline() {
+            return true;
+        }
+        // WB3c
+        if prev == ZWJ && next_char.map_or(false, is_extended_pictographic) {
+            return false;
+        }
+        // WB3d
+        if prev == WSegSpace && next == WSegSpace {
+            return false;
+        }
+        // WB4
+        if next.is_ignorable() {
+            return false;
+        }
+        // From here on, the rules apply to the characters on either side of
+        // `at` after skipping anything ignored by WB4.
+        let (prev, prev_start) = skip_back(haystack, at);
+        let prev2 = || skip_back_from(haystack, prev_start);
+        let next2 = || skip_forward(haystack, at + next_len);
+
+        // WB5
+        if prev.is_ahletter() && next.is_ahletter() {
+            return false;
+        }
+        // WB6
+        if prev.is_ahletter()
+            && (next == MidLetter || next.is_midnumletq())
+            && next2().map_or(false, WordBreak::is_ahletter)
+        {
+            return false;
+        }
+        // WB7
+        if (prev == MidLetter || prev.is_midnumletq())
+            && next.is_ahletter()
+            && prev2().map_or(false, WordBreak::is_ahletter)
+        {
+            return false;
+        }
+        // WB7a
+        if prev == HebrewLetter && next == SingleQuote {
+            return false;
+        }
+        // WB7b
+        if prev == HebrewLetter
+            && next == DoubleQuote
+            && next2() == Some(HebrewLetter)
+        {
+            return false;
+        }
+        // WB7c
+        if prev == DoubleQuote
+            && next == HebrewLetter
+            && prev2() == Some(HebrewLetter)
+        {
+            return false;
+        }
+        // WB8, WB9, WB10
+        if (prev == Numeric || prev.is_ahletter())
+            && (next == Numeric || next.is_ahletter())
+        {
+            return false;
+        }
+        // WB11
+        if (prev == MidNum || prev.is_midnumletq())
+            && next == Numeric
+            && prev2() == Some(Numeric)
+        {
+            return false;
+        }
+        // WB12
+        if prev == Numeric
+            && (next == MidNum || next.is_midnumletq())
+            && next2() == Some(Numeric)
+        {
+            return false;
+        }
+        // WB13
+        if prev == Katakana && next == Katakana {
+            return false;
+        }
+        // WB13a
+        if (prev.is_ahletter()
+            || prev == Numeric
+            || prev == Katakana
+            || prev == ExtendNumLet)
+            && next == ExtendNumLet
+        {
+            return false;
+        }
+        // WB13b
+        if prev == ExtendNumLet
+            && (next.is_ahletter() || next == Numeric || next == Katakana)
+        {
+            return false;
+        }
+        // WB15, WB16
+        if prev == RegionalIndicator && next == RegionalIndicator {
+            let mut count = 1;
+            let mut start = prev_start;
+            while start > 0 && count < MAX_REGIONAL_INDICATORS {
+                match skip_back(haystack, start) {
+                    (RegionalIndicator, s) => {
+                        count += 1;
+                        start = s;
+                    }
+                    _ => break,
+                }
+            }
+            return count % 2 == 0;
+        }
+        // WB999
+        true
+    }
+
+    /// Returns the property of the character ending at `at`, along with the
+    /// position at which it starts, after skipping any characters ignored
+    /// by rule WB4. `at` must be greater than zero.
+    ///
+    /// Characters are only ignored when they follow something other than the
+    /// start of the haystack or a newline. Otherwise, the first ignored
+    /// character stands on its own, which is what is returned. The same
+    /// happens when more than `MAX_IGNORED` characters would be skipped.
+    fn skip_back(haystack: &[u8], mut at: usize) -> (WordBreak, usize) {
+        let mut skipped = 0;
+        loop {
+            let (c, len) = decode_last(&haystack[..at]);
+            let prop = WordBreak::of(c);
+            let start = at - len;
+            if !prop.is_ignorable() || start == 0 || skipped == MAX_IGNORED {
+                return (prop, start);
+            }
+            skipped += 1;
+            let (before, _) = decode_last(&haystack[..start]);
+            if WordBreak::of(before).is_newline() {
+                return (prop, start);
+            }
+            at = start;
+        }
+    }
+
+    /// Like `skip_back`, but returns `None` if `at` is the start of the
+    /// haystack.
+    fn skip_back_from(haystack: &[u8], at: usize) -> Option<WordBreak> {
+        if at == 0 {
+            None
+        } else {
+            Some(skip_back(haystack, at).0)
+        }
+    }
+
+    /// Returns the property of the first character at or after `at` that
+    /// is not ignored by rule WB4, if one exists.
+    ///
+    /// At most `MAX_IGNORED` characters are skipped. If that many are
+    /// found, then the property of the last one is returned.
+    fn skip_forward(haystack: &[u8], mut at: usize) -> Option<WordBreak> {
+        let mut skipped = 0;
+        while at < haystack.len() {
+            let (c, len) = decode(&haystack[at..]);
+            let prop = WordBreak::of(c);
+            if !prop.is_ignorable() || skipped == MAX_IGNORED {
+                return Some(prop);
+            }
+            skipped += 1;
+            at += len;
+        }
+        None
+    }
+
+    /// Returns true if `at` falls inside the UTF-8 encoding of a codepoint.
+    fn is_inside_char(haystack: &[u8], at: usize) -> bool {
+        (at.saturating_sub(3)..at).any(|start| {
+            match decode(&haystack[start..]) {
+                (Some(_), len) => start + len > at,
+                (None, _) => false,
+            }
+        })
+    }
+
+    /// Decode the first character in the given bytes, returning it along
+    /// with its length in bytes. If the bytes don't start with valid UTF-8,
+    /// then the first byte is treated as a character on its own.
+    fn decode(bytes: &[u8]) -> (Option<char>, usize) {
+        (1..cmp::min(4, bytes.len()) + 1)
+            .filter_map(|n| str::from_utf8(&bytes[..n]).ok())
+            .next()
+            .and_then(|s| s.chars().next())
+            .map_or((None, 1), |c| (Some(c), c.len_utf8()))
+    }
+
+    /// Like `decode`, but decodes the last character in the given bytes.
+    fn decode_last(bytes: &[u8]) -> (Option<char>, usize) {
+        (1..cmp::min(4, bytes.len()) + 1)
+            .filter_map(|n| str::from_utf8(&bytes[bytes.len() - n..]).ok())
+            .next()
+            .and_then(|s| s.chars().next())
+            .map_or((None, 1), |c| (Some(c), c.len_utf8()))
+    }
+
+    fn is_extended_pictographic(c: char) -> bool {
+        contains(EXTENDED_PICTOGRAPHIC, c)
+    }
+
+    fn contains(ranges: &[(char, char)], c: char) -> bool {
+        ranges
+            .binary_search_by(|&(start, end)| {
+                if start <= c && c <= end {
+                    Ordering::Equal
+                } else if start > c {
+                    Ordering::Greater
+                } else {
+                    Ordering::Less
+                }
+            })
+            .is_ok()
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "unicode-segment", feature = "unicode-bool"))]
+mod tests {
+    use super::is_word_boundary;
+
+    /// Split the given text into pieces at every word boundary.
+    fn words(text: &str) -> Vec<&str> {
+        let mut words = vec![];
+        let mut last = 0;
+        for at in 1..text.len() + 1 {
+            if is_word_boundary(text.as_bytes(), at).unwrap() {
+                words.push(&text[last..at]);
+                last = at;
+            }
+        }
+        words
+    }
+
+    #[test]
+    fn basic() {
+        assert_eq!(
+            words("Hello, world!"),
+            vec!["Hello", ",", " ", "world", "!"]
+        );
+        assert_eq!(words("can't stop"), vec!["can't", " ", "stop"]);
+        assert_eq!(
+            words("3.14 and 1,000"),
+            vec!["3.14", " ", "and", " ", "1,000"]
+        );
+        assert_eq!(words("a_b 12ab"), vec!["a_b", " ", "12ab"]);
+        assert_eq!(words("  \r\n"), vec!["  ", "\r\n"]);
+        assert!(is_word_boundary(b"", 0).unwrap());
+    }
+
+    #[test]
+    fn combining_marks_and_emoji() {
+        assert_eq!(
+            words("e\u{301}te\u{301}!"),
+            vec!["e\u{301}te\u{301}", "!"]
+        );
+        // Family: man, ZWJ, woman, ZWJ, girl.
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        assert_eq!(words(family), vec![family]);
+        // Two regional indicator pairs (two flags).
+        assert_eq!(
+            words("\u{1F1FA}\u{1F1F8}\u{1F1EB}\u{1F1F7}"),
+            vec!["\u{1F1FA}\u{1F1F8}", "\u{1F1EB}\u{1F1F7}"]
+        );
+        assert_eq!(words("\u{5D0}\"\u{5D1}"), vec!["\u{5D0}\"\u{5D1}"]);
+        assert_eq!(words("\u{30AB}\u{30BF}"), vec!["\u{30AB}\u{30BF}"]);
+    }
+
+    #[test]
+    fn long_runs() {
+        // Regional indicators are paired from the start of their run.
+        let flags = "\u{1F1FA}\u{1F1F8}".repeat(30);
+        assert_eq!(words(&flags), vec!["\u{1F1FA}\u{1F1F8}"; 30]);
+        // Combining marks stay attached to the preceding letter.
+        let word = format!("a{}b", "\u{301}".repeat(30));
+        assert_eq!(words(&word), vec![&*word]);
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let text = b"a\xFFb\xE2\x98\x83";
+        assert!(is_word_boundary(text, 1).unwrap());
+        assert!(is_word_boundary(text, 2).unwrap());
+        assert!(is_word_boundary(text, 3).unwrap());
+        assert!(!is_word_boundary(text, 4).unwrap());
+        assert!(!is_word_boundary(text, 5).unwrap());
+        assert!(is_word_boundary(text, 6).unwrap());
+    }
+}
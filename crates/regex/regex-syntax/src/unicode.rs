@@ -1,6 +1,9 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::result;
+#[cfg(feature = "std")]
 use std::error;
-use std::fmt;
-use std::result;
 
 use hir;
 
@@ -35,6 +38,7 @@ pub type FoldResult<T> = result::Result<T, CaseFoldError>;
 #[derive(Debug)]
 pub struct CaseFoldError(());
 
+#[cfg(feature = "std")]
 impl error::Error for CaseFoldError {}
 
 impl fmt::Display for CaseFoldError {
@@ -55,6 +59,7 @@ impl fmt::Display for CaseFoldError {
 #[derive(Debug)]
 pub struct UnicodeWordError(());
 
+#[cfg(feature = "std")]
 impl error::Error for UnicodeWordError {}
 
 impl fmt::Display for UnicodeWordError {
@@ -86,7 +91,7 @@ pub fn simple_fold(
         _: char,
     ) -> FoldResult<result::Result<impl Iterator<Item = char>, Option<char>>>
     {
-        use std::option::IntoIter;
+        use core::option::IntoIter;
         Err::<result::Result<IntoIter<char>, _>, _>(CaseFoldError(()))
     }
 
@@ -130,7 +135,7 @@ pub fn contains_simple_case_mapping(
 
     #[cfg(feature = "unicode-case")]
     fn imp(start: char, end: char) -> FoldResult<bool> {
-        use std::cmp::Ordering;
+        use core::cmp::Ordering;
         use unicode_tables::case_folding_simple::CASE_FOLDING_SIMPLE;
 
         assert!(start <= end);
@@ -397,8 +402,8 @@ pub fn is_word_character(c: char) -> result::Result<bool, UnicodeWordError> {
 
     #[cfg(feature = "unicode-perl")]
     fn imp(c: char) -> result::Result<bool, UnicodeWordError> {
+        use core::cmp::Ordering;
         use is_word_byte;
-        use std::cmp::Ordering;
         use unicode_tables::perl_word::PERL_WORD;
 
         if c <= 0x7F as char && is_word_byte(c as u8) {
@@ -564,7 +569,7 @@ fn property_set(
 fn ages(canonical_age: &str) -> Result<impl Iterator<Item = Range>> {
     #[cfg(not(feature = "unicode-age"))]
     fn imp(_: &str) -> Result<impl Iterator<Item = Range>> {
-        use std::option::IntoIter;
+        use core::option::IntoIter;
         Err::<IntoIter<Range>, _>(Error::PropertyNotFound)
     }
 
@@ -698,7 +703,9 @@ fn script_extension(
 ///
 /// If the given boolean property could not be found, or if the boolean
 /// property data is not available, then an error is returned.
-fn bool_property(canonical_name: &'static str) -> Result<hir::ClassUnicode> {
+pub fn bool_property(
+    canonical_name: &'static str,
+) -> Result<hir::ClassUnicode> {
     #[cfg(not(feature = "unicode-bool"))]
     fn imp(_: &'static str) -> Result<hir::ClassUnicode> {
         Err(Error::PropertyNotFound)
@@ -726,7 +733,7 @@ fn bool_property(canonical_name: &'static str) -> Result<hir::ClassUnicode> {
 ///
 /// If the given property could not be found, or if the corresponding data is
 /// not available, then an error is returned.
-fn gcb(canonical_name: &'static str) -> Result<hir::ClassUnicode> {
+pub fn gcb(canonical_name: &'static str) -> Result<hir::ClassUnicode> {
     #[cfg(not(feature = "unicode-segment"))]
     fn imp(_: &'static str) -> Result<hir::ClassUnicode> {
         Err(Error::PropertyNotFound)
@@ -792,7 +799,7 @@ fn sb(canonical_name: &'static str) -> Result<hir::ClassUnicode> {
 }
 
 /// Like symbolic_name_normalize_bytes, but operates on a string.
-fn symbolic_name_normalize(x: &str) -> String {
+pub fn symbolic_name_normalize(x: &str) -> String {
     let mut tmp = x.as_bytes().to_vec();
     let len = symbolic_name_normalize_bytes(&mut tmp).len();
     tmp.truncate(len);
@@ -55,3 +55,5 @@ pub mod sentence_break;
 
 #[cfg(feature = "unicode-segment")]
 pub mod word_break;
+
+pub mod version;
@@ -0,0 +1,7 @@
+// DO NOT EDIT THIS FILE. IT WAS AUTOMATICALLY GENERATED BY:
+//
+//  regex-ucd /tmp/ucd/12.1.0/
+//
+// regex-ucd is in the regex-ucd directory of the regex repository.
+
+pub const UNICODE_VERSION: (u64, u64, u64) = (12, 1, 0);
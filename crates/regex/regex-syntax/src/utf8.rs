@@ -82,9 +82,10 @@ which uses it for executing automata on their term index.
 
 #![deny(missing_docs)]
 
-use std::char;
-use std::fmt;
-use std::slice;
+use alloc::vec::Vec;
+use core::char;
+use core::fmt;
+use core::slice;
 
 const MAX_UTF8_BYTES: usize = 4;
 
@@ -426,7 +427,7 @@ fn max_scalar_value(nbytes: usize) -> u32 {
 
 #[cfg(test)]
 mod tests {
-    use std::char;
+    use core::char;
 
     use utf8::{Utf8Range, Utf8Sequences};
 
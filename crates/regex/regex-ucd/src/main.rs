@@ -0,0 +1,88 @@
+extern crate docopt;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+
+use std::error;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process;
+use std::result;
+
+use docopt::Docopt;
+
+use table::Table;
+use ucd::Ucd;
+
+mod ranges;
+mod table;
+mod ucd;
+
+const USAGE: &'static str = "
+Generate the Unicode tables used by regex-syntax from a local copy of the
+Unicode Character Database (UCD).
+
+The UCD for a particular version of Unicode can be downloaded from
+https://www.unicode.org/Public/zipped/. For Unicode versions before 13.0,
+emoji-data.txt is distributed separately and must be copied into the root of
+the UCD directory.
+
+Every table in <out-dir> is overwritten. The generated code is not formatted,
+so rustfmt should be run afterwards.
+
+Usage:
+    regex-ucd [options] <ucd-dir> <out-dir>
+    regex-ucd --help
+
+Options:
+    --help               Show this usage message.
+";
+
+#[derive(Deserialize)]
+struct Args {
+    arg_ucd_dir: String,
+    arg_out_dir: String,
+}
+
+type Result<T> = result::Result<T, Box<dyn error::Error + Send + Sync>>;
+
+fn main() {
+    let args: Args = Docopt::new(USAGE)
+        .and_then(|d| d.deserialize())
+        .unwrap_or_else(|e| e.exit());
+    match run(&args) {
+        Ok(_) => process::exit(0),
+        Err(err) => {
+            let _ = writeln!(&mut io::stderr(), "{}", err);
+            process::exit(1)
+        }
+    }
+}
+
+fn run(args: &Args) -> Result<()> {
+    let ucd = Ucd::open(&args.arg_ucd_dir)?;
+    let out = Path::new(&args.arg_out_dir);
+    let tables: Vec<(&str, Table)> = vec![
+        ("age", table::age(&ucd)?),
+        ("case_folding_simple", table::case_folding_simple(&ucd)?),
+        ("general_category", table::general_category(&ucd)?),
+        ("grapheme_cluster_break", table::grapheme_cluster_break(&ucd)?),
+        ("perl_decimal", table::perl_decimal(&ucd)?),
+        ("perl_space", table::perl_space(&ucd)?),
+        ("perl_word", table::perl_word(&ucd)?),
+        ("property_bool", table::property_bool(&ucd)?),
+        ("property_names", table::property_names(&ucd)?),
+        ("property_values", table::property_values(&ucd)?),
+        ("script", table::script(&ucd)?),
+        ("script_extension", table::script_extension(&ucd)?),
+        ("sentence_break", table::sentence_break(&ucd)?),
+        ("word_break", table::word_break(&ucd)?),
+        ("version", Table::Version(ucd.version()?)),
+    ];
+    let command = format!("regex-ucd {}", args.arg_ucd_dir);
+    for (name, table) in tables {
+        let path = out.join(format!("{}.rs", name));
+        table.write_file(&path, &command)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use std::cmp;
+
+/// The largest Unicode codepoint.
+pub const MAX_CODEPOINT: u32 = 0x10FFFF;
+
+/// A set of codepoints, represented as a sorted sequence of non-overlapping
+/// and non-adjacent inclusive ranges.
+///
+/// Codepoints are represented as `u32` rather than `char` since the UCD
+/// assigns properties to surrogate codepoints too.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RangeSet {
+    ranges: Vec<(u32, u32)>,
+}
+
+impl RangeSet {
+    /// Create an empty set.
+    pub fn new() -> RangeSet {
+        RangeSet::default()
+    }
+
+    /// Create a set containing every codepoint in the given ranges, which
+    /// may overlap and need not be sorted.
+    pub fn from_ranges(ranges: Vec<(u32, u32)>) -> RangeSet {
+        let mut set = RangeSet { ranges: ranges };
+        set.canonicalize();
+        set
+    }
+
+    /// Add every codepoint in the given inclusive range to this set.
+    pub fn push(&mut self, start: u32, end: u32) {
+        self.ranges.push((start, end));
+        self.canonicalize();
+    }
+
+    /// Add every codepoint in the given set to this set.
+    pub fn union(&mut self, other: &RangeSet) {
+        self.ranges.extend_from_slice(&other.ranges);
+        self.canonicalize();
+    }
+
+    /// Remove every codepoint in the given set from this set.
+    pub fn difference(&mut self, other: &RangeSet) {
+        let mut result = vec![];
+        let mut others = other.ranges.iter().peekable();
+        for &(mut start, end) in &self.ranges {
+            loop {
+                // Skip every range in `other` that ends before this one.
+                while others.peek().map_or(false, |&&(_, e)| e < start) {
+                    others.next();
+                }
+                match others.peek() {
+                    Some(&&(s, e)) if s <= end => {
+                        if s > start {
+                            result.push((start, s - 1));
+                        }
+                        if e >= end {
+                            break;
+                        }
+                        start = e + 1;
+                    }
+                    _ => {
+                        result.push((start, end));
+                        break;
+                    }
+                }
+            }
+        }
+        self.ranges = result;
+    }
+
+    /// Returns the set of all codepoints not in this set.
+    pub fn complement(&self) -> RangeSet {
+        let mut all = RangeSet { ranges: vec![(0, MAX_CODEPOINT)] };
+        all.difference(self);
+        all
+    }
+
+    /// Returns the ranges of this set as `char` ranges.
+    ///
+    /// Surrogate codepoints are not valid `char`s, so they are trimmed from
+    /// the ends of every range. A range spanning the surrogate codepoints is
+    /// kept as is, since it still covers exactly the codepoints in this set
+    /// that are valid `char`s.
+    pub fn char_ranges(&self) -> Vec<(char, char)> {
+        let mut ranges = vec![];
+        for &(mut start, mut end) in &self.ranges {
+            if 0xD800 <= start && start <= 0xDFFF {
+                start = 0xE000;
+            }
+            if 0xD800 <= end && end <= 0xDFFF {
+                end = 0xD7FF;
+            }
+            if start > end {
+                continue;
+            }
+            // Neither of these can fail since neither end point is a
+            // surrogate and both are at most MAX_CODEPOINT.
+            let start = ::std::char::from_u32(start).unwrap();
+            let end = ::std::char::from_u32(end).unwrap();
+            ranges.push((start, end));
+        }
+        ranges
+    }
+
+    /// Sort the ranges in this set and merge any ranges that overlap or are
+    /// adjacent.
+    fn canonicalize(&mut self) {
+        self.ranges.sort();
+        let mut merged: Vec<(u32, u32)> = vec![];
+        for &(start, end) in &self.ranges {
+            if let Some(last) = merged.last_mut() {
+                if start <= last.1.saturating_add(1) {
+                    last.1 = cmp::max(last.1, end);
+                    continue;
+                }
+            }
+            merged.push((start, end));
+        }
+        self.ranges = merged;
+    }
+}
@@ -0,0 +1,484 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use ranges::RangeSet;
+use ucd::Ucd;
+use Result;
+
+/// A table of Unicode data that can be written as a Rust source file in the
+/// format expected by regex-syntax's `unicode` module.
+#[derive(Debug)]
+pub enum Table {
+    /// A set of codepoints for each of the values of a property, emitted as
+    /// one constant per value along with a `BY_NAME` index.
+    ByName(Vec<(String, RangeSet)>),
+    /// A single set of codepoints emitted as a constant with the given name.
+    Set(String, RangeSet),
+    /// The simple case folding equivalence classes, emitted as a map from
+    /// every codepoint to the other members of its class.
+    CaseFolding(BTreeMap<u32, Vec<u32>>),
+    /// A map from normalized property aliases to canonical property names.
+    Names(BTreeMap<String, String>),
+    /// For each canonical property name, a map from normalized value aliases
+    /// to canonical value names.
+    Values(BTreeMap<String, BTreeMap<String, String>>),
+    /// The Unicode version as `(major, minor, update)`.
+    Version((u64, u64, u64)),
+}
+
+impl Table {
+    /// Write this table to the given file, overwriting it if it exists.
+    ///
+    /// `command` is recorded in the header of the file as the command that
+    /// generated it.
+    pub fn write_file(&self, path: &Path, command: &str) -> Result<()> {
+        let mut wtr = BufWriter::new(File::create(path)?);
+        writeln!(
+            wtr,
+            "// DO NOT EDIT THIS FILE. IT WAS AUTOMATICALLY GENERATED BY:"
+        )?;
+        writeln!(wtr, "//")?;
+        writeln!(wtr, "//  {}", command)?;
+        writeln!(wtr, "//")?;
+        writeln!(
+            wtr,
+            "// regex-ucd is in the regex-ucd directory of the regex \
+             repository."
+        )?;
+        writeln!(wtr)?;
+        self.write(&mut wtr)?;
+        wtr.flush()?;
+        Ok(())
+    }
+
+    fn write<W: Write>(&self, wtr: &mut W) -> Result<()> {
+        match *self {
+            Table::ByName(ref sets) => {
+                let mut sets: Vec<&(String, RangeSet)> = sets.iter().collect();
+                sets.sort_by(|a, b| a.0.cmp(&b.0));
+                writeln!(
+                    wtr,
+                    "pub const BY_NAME: &'static [(&'static str, \
+                     &'static [(char, char)])] = &["
+                )?;
+                for &&(ref name, _) in &sets {
+                    writeln!(wtr, "    ({:?}, {}),", name, const_name(name))?;
+                }
+                writeln!(wtr, "];")?;
+                for &&(ref name, ref set) in &sets {
+                    writeln!(wtr)?;
+                    write_set(wtr, &const_name(name), set)?;
+                }
+            }
+            Table::Set(ref name, ref set) => {
+                write_set(wtr, &const_name(name), set)?;
+            }
+            Table::CaseFolding(ref map) => {
+                writeln!(
+                    wtr,
+                    "pub const CASE_FOLDING_SIMPLE: \
+                     &'static [(char, &'static [char])] = &["
+                )?;
+                for (&cp, others) in map {
+                    let others: Vec<String> = others
+                        .iter()
+                        .map(|&cp| format!("{:?}", to_char(cp)))
+                        .collect();
+                    writeln!(
+                        wtr,
+                        "    ({:?}, &[{}]),",
+                        to_char(cp),
+                        others.join(", ")
+                    )?;
+                }
+                writeln!(wtr, "];")?;
+            }
+            Table::Names(ref map) => {
+                writeln!(
+                    wtr,
+                    "pub const PROPERTY_NAMES: \
+                     &'static [(&'static str, &'static str)] = &["
+                )?;
+                for (alias, name) in map {
+                    writeln!(wtr, "    ({:?}, {:?}),", alias, name)?;
+                }
+                writeln!(wtr, "];")?;
+            }
+            Table::Values(ref map) => {
+                writeln!(
+                    wtr,
+                    "pub const PROPERTY_VALUES: &'static [(\n    \
+                     &'static str,\n    \
+                     &'static [(&'static str, &'static str)],\n\
+                     )] = &["
+                )?;
+                for (property, values) in map {
+                    writeln!(wtr, "    (")?;
+                    writeln!(wtr, "        {:?},", property)?;
+                    writeln!(wtr, "        &[")?;
+                    for (alias, value) in values {
+                        writeln!(
+                            wtr,
+                            "            ({:?}, {:?}),",
+                            alias, value
+                        )?;
+                    }
+                    writeln!(wtr, "        ],")?;
+                    writeln!(wtr, "    ),")?;
+                }
+                writeln!(wtr, "];")?;
+            }
+            Table::Version((major, minor, update)) => {
+                writeln!(
+                    wtr,
+                    "pub const UNICODE_VERSION: (u64, u64, u64) = \
+                     ({}, {}, {});",
+                    major, minor, update
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The Age property, from `DerivedAge.txt`.
+///
+/// Each set contains only the codepoints assigned in that version, i.e.,
+/// the sets are not cumulative.
+pub fn age(ucd: &Ucd) -> Result<Table> {
+    by_value(ucd, "age", "DerivedAge.txt")
+}
+
+/// The simple case folding equivalence classes, from `CaseFolding.txt`.
+pub fn case_folding_simple(ucd: &Ucd) -> Result<Table> {
+    // Build the equivalence classes by mapping every folded codepoint to
+    // every codepoint that folds to it.
+    let mut classes: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+    for row in ucd.rows("CaseFolding.txt")? {
+        let (cp, _) = match row.range {
+            None => continue,
+            Some(range) => range,
+        };
+        if row.fields.len() < 3 {
+            continue;
+        }
+        if row.fields[1] != "C" && row.fields[1] != "S" {
+            continue;
+        }
+        let folded = u32::from_str_radix(&row.fields[2], 16)?;
+        classes.entry(folded).or_insert_with(|| vec![folded]).push(cp);
+    }
+    let mut map = BTreeMap::new();
+    for (_, mut class) in classes {
+        class.sort();
+        class.dedup();
+        for &cp in &class {
+            let others = class.iter().cloned().filter(|&c| c != cp).collect();
+            map.insert(cp, others);
+        }
+    }
+    Ok(Table::CaseFolding(map))
+}
+
+/// The General_Category property, from `UnicodeData.txt`, including the
+/// grouped categories such as `Letter`.
+///
+/// `Surrogate` is excluded since no `char` has that category.
+pub fn general_category(ucd: &Ucd) -> Result<Table> {
+    let mut sets = general_categories(ucd)?;
+    sets.retain(|&(ref name, _)| name != "Surrogate");
+    Ok(Table::ByName(sets))
+}
+
+/// The Grapheme_Cluster_Break property, from
+/// `GraphemeBreakProperty.txt`.
+pub fn grapheme_cluster_break(ucd: &Ucd) -> Result<Table> {
+    by_value(ucd, "gcb", "GraphemeBreakProperty.txt")
+}
+
+/// The Decimal_Number general category, which is what Perl's `\d` matches.
+pub fn perl_decimal(ucd: &Ucd) -> Result<Table> {
+    let mut sets = general_categories(ucd)?;
+    sets.retain(|&(ref name, _)| name == "Decimal_Number");
+    Ok(Table::ByName(sets))
+}
+
+/// The White_Space property, which is what Perl's `\s` matches.
+pub fn perl_space(ucd: &Ucd) -> Result<Table> {
+    let mut sets = properties_bool(ucd)?;
+    sets.retain(|&(ref name, _)| name == "White_Space");
+    Ok(Table::ByName(sets))
+}
+
+/// The codepoints matched by Perl's `\w`, as defined by UTS#18 Annex C.
+pub fn perl_word(ucd: &Ucd) -> Result<Table> {
+    let find = |sets: &[(String, RangeSet)], name: &str| {
+        sets.iter()
+            .find(|&&(ref n, _)| n == name)
+            .map(|&(_, ref set)| set.clone())
+            .ok_or_else(|| format!("missing property: {}", name))
+    };
+    let bools = properties_bool(ucd)?;
+    let gencats = general_categories(ucd)?;
+    let mut set = find(&bools, "Alphabetic")?;
+    set.union(&find(&bools, "Join_Control")?);
+    set.union(&find(&gencats, "Mark")?);
+    set.union(&find(&gencats, "Decimal_Number")?);
+    set.union(&find(&gencats, "Connector_Punctuation")?);
+    Ok(Table::Set("perl_word".to_string(), set))
+}
+
+/// All binary properties, from `PropList.txt`,
+/// `DerivedCoreProperties.txt` and `emoji-data.txt`.
+pub fn property_bool(ucd: &Ucd) -> Result<Table> {
+    Ok(Table::ByName(properties_bool(ucd)?))
+}
+
+/// A map from every alias of every property to its canonical name, from
+/// `PropertyAliases.txt`.
+pub fn property_names(ucd: &Ucd) -> Result<Table> {
+    let mut map = BTreeMap::new();
+    for row in ucd.rows("PropertyAliases.txt")? {
+        if row.fields.len() < 2 {
+            continue;
+        }
+        for alias in &row.fields {
+            map.insert(normalize(alias), row.fields[1].clone());
+        }
+    }
+    Ok(Table::Names(map))
+}
+
+/// A map from every alias of every value to its canonical name, for each
+/// of the enumerated properties supported by regex-syntax, from
+/// `PropertyValueAliases.txt`.
+pub fn property_values(ucd: &Ucd) -> Result<Table> {
+    let names = match property_names(ucd)? {
+        Table::Names(names) => names,
+        _ => unreachable!(),
+    };
+    let mut map = BTreeMap::new();
+    for &short in &["age", "gc", "gcb", "sb", "sc", "wb"] {
+        let name = names
+            .get(short)
+            .ok_or_else(|| format!("missing property: {}", short))?;
+        map.insert(name.clone(), value_aliases(ucd, short)?);
+    }
+    // Script_Extensions has the same values as Script, but they aren't
+    // repeated in PropertyValueAliases.txt.
+    let scx = map["Script"].clone();
+    map.insert("Script_Extensions".to_string(), scx);
+    Ok(Table::Values(map))
+}
+
+/// The Script property, from `Scripts.txt`.
+pub fn script(ucd: &Ucd) -> Result<Table> {
+    by_value(ucd, "sc", "Scripts.txt")
+}
+
+/// The Script_Extensions property, from `Scripts.txt` and
+/// `ScriptExtensions.txt`.
+///
+/// Codepoints not listed in `ScriptExtensions.txt` have the extensions
+/// `{Script}`, so each set starts out as the corresponding Script set.
+pub fn script_extension(ucd: &Ucd) -> Result<Table> {
+    let aliases = value_aliases(ucd, "sc")?;
+    let mut sets = match script(ucd)? {
+        Table::ByName(sets) => sets,
+        _ => unreachable!(),
+    };
+    let mut listed = RangeSet::new();
+    let mut extensions: BTreeMap<String, RangeSet> = BTreeMap::new();
+    for row in ucd.rows("ScriptExtensions.txt")? {
+        let (start, end) = match row.range {
+            None => continue,
+            Some(range) => range,
+        };
+        if row.fields.len() < 2 {
+            continue;
+        }
+        listed.push(start, end);
+        for short in row.fields[1].split_whitespace() {
+            let name = aliases
+                .get(&normalize(short))
+                .ok_or_else(|| format!("unknown script: {}", short))?;
+            extensions
+                .entry(name.clone())
+                .or_insert_with(RangeSet::new)
+                .push(start, end);
+        }
+    }
+    for &mut (_, ref mut set) in &mut sets {
+        set.difference(&listed);
+    }
+    for (name, ext) in extensions {
+        match sets.iter_mut().find(|&&mut (ref n, _)| *n == name) {
+            Some(&mut (_, ref mut set)) => set.union(&ext),
+            None => sets.push((name, ext)),
+        }
+    }
+    Ok(Table::ByName(sets))
+}
+
+/// The Sentence_Break property, from `SentenceBreakProperty.txt`.
+pub fn sentence_break(ucd: &Ucd) -> Result<Table> {
+    by_value(ucd, "sb", "SentenceBreakProperty.txt")
+}
+
+/// The Word_Break property, from `WordBreakProperty.txt`.
+pub fn word_break(ucd: &Ucd) -> Result<Table> {
+    by_value(ucd, "wb", "WordBreakProperty.txt")
+}
+
+/// Read a file mapping codepoints to values of the given property, and
+/// return a set for each value, named by its canonical name.
+fn by_value(ucd: &Ucd, property: &str, file: &str) -> Result<Table> {
+    let aliases = value_aliases(ucd, property)?;
+    let mut sets = vec![];
+    for (value, set) in ucd.ranges_by_value(file, 1)? {
+        let name = aliases.get(&normalize(&value)).ok_or_else(|| {
+            format!("{}: unknown {} value: {}", file, property, value)
+        })?;
+        sets.push((name.clone(), set));
+    }
+    Ok(Table::ByName(sets))
+}
+
+/// Return the set of codepoints for every General_Category value, named by
+/// its canonical name, including `Unassigned` and the grouped categories.
+fn general_categories(ucd: &Ucd) -> Result<Vec<(String, RangeSet)>> {
+    const GROUPS: &'static [(&'static str, &'static [&'static str])] = &[
+        ("LC", &["Lu", "Ll", "Lt"]),
+        ("L", &["Lu", "Ll", "Lt", "Lm", "Lo"]),
+        ("M", &["Mn", "Mc", "Me"]),
+        ("N", &["Nd", "Nl", "No"]),
+        ("P", &["Pc", "Pd", "Ps", "Pe", "Pi", "Pf", "Po"]),
+        ("S", &["Sm", "Sc", "Sk", "So"]),
+        ("Z", &["Zs", "Zl", "Zp"]),
+        ("C", &["Cc", "Cf", "Cs", "Co", "Cn"]),
+    ];
+
+    let aliases = value_aliases(ucd, "gc")?;
+    let mut by_short: BTreeMap<String, RangeSet> = BTreeMap::new();
+    let mut assigned = RangeSet::new();
+    for ((start, end), cat) in ucd.general_categories()? {
+        by_short.entry(cat).or_insert_with(RangeSet::new).push(start, end);
+        assigned.push(start, end);
+    }
+    by_short.insert("Cn".to_string(), assigned.complement());
+    for &(group, members) in GROUPS {
+        let mut set = RangeSet::new();
+        for member in members {
+            if let Some(s) = by_short.get(*member) {
+                set.union(s);
+            }
+        }
+        by_short.insert(group.to_string(), set);
+    }
+
+    let mut sets = vec![];
+    for (short, set) in by_short {
+        let name = aliases
+            .get(&normalize(&short))
+            .ok_or_else(|| format!("unknown general category: {}", short))?;
+        sets.push((name.clone(), set));
+    }
+    Ok(sets)
+}
+
+/// Return the set of codepoints for every binary property.
+fn properties_bool(ucd: &Ucd) -> Result<Vec<(String, RangeSet)>> {
+    let mut sets: Vec<(String, RangeSet)> = vec![];
+    for file in
+        &["PropList.txt", "DerivedCoreProperties.txt", "emoji-data.txt"]
+    {
+        for row in ucd.rows(file)? {
+            // Some files also contain enumerated properties, which have a
+            // third field with the property's value.
+            let (start, end) = match row.range {
+                Some(range) if row.fields.len() == 2 => range,
+                _ => continue,
+            };
+            let name = &row.fields[1];
+            match sets.iter_mut().find(|&&mut (ref n, _)| n == name) {
+                Some(&mut (_, ref mut set)) => set.push(start, end),
+                None => {
+                    let set = RangeSet::from_ranges(vec![(start, end)]);
+                    sets.push((name.clone(), set));
+                }
+            }
+        }
+    }
+    Ok(sets)
+}
+
+/// Return a map from every normalized alias of every value of the given
+/// property to the value's canonical name.
+fn value_aliases(
+    ucd: &Ucd,
+    property: &str,
+) -> Result<BTreeMap<String, String>> {
+    let mut map = BTreeMap::new();
+    for row in ucd.rows("PropertyValueAliases.txt")? {
+        if row.fields.len() < 3 || normalize(&row.fields[0]) != property {
+            continue;
+        }
+        for alias in &row.fields[1..] {
+            map.insert(normalize(alias), row.fields[2].clone());
+        }
+    }
+    if map.is_empty() {
+        return Err(
+            format!("no values found for property {}", property).into()
+        );
+    }
+    Ok(map)
+}
+
+/// Normalize a property name or value in the same way as regex-syntax
+/// does when looking up a name in the generated tables.
+///
+/// This must be kept in sync with `symbolic_name_normalize` in
+/// regex-syntax's `unicode` module.
+fn normalize(name: &str) -> String {
+    let mut name = name.as_bytes();
+    let starts_with_is =
+        name.len() >= 2 && name[..2].eq_ignore_ascii_case(b"is");
+    if starts_with_is {
+        name = &name[2..];
+    }
+    let mut norm = String::new();
+    for &b in name {
+        if b == b' ' || b == b'_' || b == b'-' {
+            continue;
+        } else if b <= 0x7F {
+            norm.push((b as char).to_ascii_lowercase());
+        }
+    }
+    // ISO_Comment's 'isc' alias would otherwise become 'c', which is an
+    // alias for the 'Other' general category.
+    if starts_with_is && norm == "c" {
+        norm = "isc".to_string();
+    }
+    norm
+}
+
+fn write_set<W: Write>(wtr: &mut W, name: &str, set: &RangeSet) -> Result<()> {
+    writeln!(wtr, "pub const {}: &'static [(char, char)] = &[", name)?;
+    for (start, end) in set.char_ranges() {
+        writeln!(wtr, "    ({:?}, {:?}),", start, end)?;
+    }
+    writeln!(wtr, "];")?;
+    Ok(())
+}
+
+fn const_name(name: &str) -> String {
+    name.to_uppercase()
+}
+
+fn to_char(cp: u32) -> char {
+    // Case folding never involves surrogates, so this can't fail.
+    ::std::char::from_u32(cp).unwrap()
+}
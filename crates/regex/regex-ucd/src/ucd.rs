@@ -0,0 +1,186 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use ranges::RangeSet;
+use Result;
+
+/// A local copy of the Unicode Character Database.
+#[derive(Debug)]
+pub struct Ucd {
+    dir: PathBuf,
+}
+
+/// A single data line from a UCD file, with its comment removed and its
+/// fields trimmed.
+#[derive(Debug)]
+pub struct Row {
+    /// The inclusive range of codepoints in the first field, if the first
+    /// field is a codepoint or a codepoint range.
+    pub range: Option<(u32, u32)>,
+    /// All fields, including the first.
+    pub fields: Vec<String>,
+}
+
+impl Ucd {
+    /// Open the UCD in the given directory.
+    ///
+    /// This only checks that the directory exists. Individual files are read
+    /// as they're needed.
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Ucd> {
+        let dir = dir.as_ref().to_path_buf();
+        if !dir.is_dir() {
+            return Err(format!("{}: not a directory", dir.display()).into());
+        }
+        Ok(Ucd { dir: dir })
+    }
+
+    /// Return the version of Unicode described by this UCD, as
+    /// `(major, minor, update)`.
+    ///
+    /// The version is read from the header of `DerivedAge.txt`, whose first
+    /// line looks like `# DerivedAge-12.1.0.txt`.
+    pub fn version(&self) -> Result<(u64, u64, u64)> {
+        let path = self.path("DerivedAge.txt")?;
+        let mut line = String::new();
+        BufReader::new(File::open(&path)?).read_line(&mut line)?;
+        let err = || format!("{}: missing version header", path.display());
+        let version = line
+            .trim()
+            .trim_start_matches("# DerivedAge-")
+            .trim_end_matches(".txt");
+        let parts: Vec<&str> = version.split('.').collect();
+        if parts.len() != 3 {
+            return Err(err().into());
+        }
+        let mut nums = [0; 3];
+        for (i, part) in parts.iter().enumerate() {
+            nums[i] = part.parse().map_err(|_| err())?;
+        }
+        Ok((nums[0], nums[1], nums[2]))
+    }
+
+    /// Read every data line from the given file in this UCD.
+    pub fn rows(&self, name: &str) -> Result<Vec<Row>> {
+        let path = self.path(name)?;
+        let rdr = BufReader::new(File::open(&path)?);
+        let mut rows = vec![];
+        for (i, line) in rdr.lines().enumerate() {
+            let line = line?;
+            let data = match line.find('#') {
+                None => &*line,
+                Some(end) => &line[..end],
+            };
+            if data.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<String> =
+                data.split(';').map(|f| f.trim().to_string()).collect();
+            let range = parse_range(&fields[0]).map_err(|err| {
+                format!("{}:{}: {}", path.display(), i + 1, err)
+            })?;
+            rows.push(Row { range: range, fields: fields });
+        }
+        Ok(rows)
+    }
+
+    /// Read a file in this UCD that maps codepoints to property values, and
+    /// return the set of codepoints for each value in the order in which the
+    /// values first appear.
+    ///
+    /// `field` is the index of the field containing the value.
+    pub fn ranges_by_value(
+        &self,
+        name: &str,
+        field: usize,
+    ) -> Result<Vec<(String, RangeSet)>> {
+        let mut sets: Vec<(String, RangeSet)> = vec![];
+        for row in self.rows(name)? {
+            let (start, end) = match row.range {
+                None => continue,
+                Some(range) => range,
+            };
+            let value = match row.fields.get(field) {
+                None => continue,
+                Some(value) => value,
+            };
+            match sets.iter_mut().find(|&&mut (ref v, _)| v == value) {
+                Some(&mut (_, ref mut set)) => set.push(start, end),
+                None => {
+                    let set = RangeSet::from_ranges(vec![(start, end)]);
+                    sets.push((value.clone(), set));
+                }
+            }
+        }
+        Ok(sets)
+    }
+
+    /// Read `UnicodeData.txt` and return each codepoint range along with its
+    /// general category.
+    ///
+    /// Ranges of codepoints that are abbreviated in `UnicodeData.txt` with
+    /// `<..., First>` and `<..., Last>` rows are expanded.
+    pub fn general_categories(&self) -> Result<Vec<((u32, u32), String)>> {
+        let mut cats = vec![];
+        let mut first: Option<u32> = None;
+        for row in self.rows("UnicodeData.txt")? {
+            let (cp, _) = match row.range {
+                None => continue,
+                Some(range) => range,
+            };
+            if row.fields.len() < 3 {
+                return Err(format!("U+{:04X}: missing category", cp).into());
+            }
+            let name = &row.fields[1];
+            let cat = row.fields[2].clone();
+            if name.ends_with(", First>") {
+                first = Some(cp);
+            } else if name.ends_with(", Last>") {
+                let start = first.take().ok_or_else(|| {
+                    format!("U+{:04X}: range end without a start", cp)
+                })?;
+                cats.push(((start, cp), cat));
+            } else {
+                cats.push(((cp, cp), cat));
+            }
+        }
+        Ok(cats)
+    }
+
+    /// Return the path to the given file in this UCD.
+    ///
+    /// Files are looked for in the root of the UCD, and then in the
+    /// `auxiliary` and `emoji` sub-directories, which is where newer versions
+    /// of the UCD put some of them.
+    fn path(&self, name: &str) -> Result<PathBuf> {
+        for dir in &["", "auxiliary", "emoji"] {
+            let path = self.dir.join(dir).join(name);
+            if path.is_file() {
+                return Ok(path);
+            }
+        }
+        Err(format!("{}: could not find {}", self.dir.display(), name).into())
+    }
+}
+
+/// Parse a codepoint (`XXXX`) or codepoint range (`XXXX..YYYY`).
+///
+/// If the field isn't a codepoint at all (e.g., in the alias files), then
+/// `None` is returned.
+fn parse_range(field: &str) -> Result<Option<(u32, u32)>> {
+    let is_hex =
+        |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_hexdigit());
+    let (start, end) = match field.find("..") {
+        None => (field, field),
+        Some(i) => (&field[..i], &field[i + 2..]),
+    };
+    if !is_hex(start) || !is_hex(end) {
+        return Ok(None);
+    }
+    let start = u32::from_str_radix(start, 16)?;
+    let end = u32::from_str_radix(end, 16)?;
+    if start > end || end > ::ranges::MAX_CODEPOINT {
+        return Err(format!("invalid codepoint range: {}", field).into());
+    }
+    Ok(Some((start, end)))
+}
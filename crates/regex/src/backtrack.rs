@@ -16,6 +16,9 @@
 // the bitset has to be zeroed on each execution, which becomes quite expensive
 // on large bitsets.
 
+use alloc::vec::Vec;
+use core::mem;
+
 use crate::exec::ProgramCache;
 use crate::input::{Input, InputAt};
 use crate::prog::{InstPtr, Program};
@@ -62,6 +65,12 @@ pub struct Cache {
     pub fn new(_prog: &Program) -> Self {
         Cache { jobs: vec![], visited: vec![] }
     }
+
+    /// Returns the approximate heap memory, in bytes, used by this cache.
+    pub fn memory_usage(&self) -> usize {
+        (self.jobs.capacity() * mem::size_of::<Job>())
+            + (self.visited.capacity() * mem::size_of::<Bits>())
+    }
 }
 
 /// A job is an explicit unit of stack space in the backtracking engine.
@@ -81,11 +90,15 @@ enum Job {
     ///
     /// If there's a match, `exec` returns `true` and populates the given
     /// captures accordingly.
+    ///
+    /// When `anchored` is true, only a match beginning at `start` is
+    /// reported.
     pub fn exec(
         prog: &'r Program,
         cache: &ProgramCache,
         matches: &'m mut [bool],
         slots: &'s mut [Slot],
+        anchored: bool,
         input: I,
         start: usize,
         end: usize,
@@ -100,7 +113,7 @@ enum Job {
             slots: slots,
             m: cache,
         };
-        b.exec_(start, end)
+        b.exec_(start, end, anchored)
     }
 
     /// Clears the cache such that the backtracking engine can be executed
@@ -137,13 +150,17 @@ enum Job {
 
     /// Start backtracking at the given position in the input, but also look
     /// for literal prefixes.
-    fn exec_(&mut self, mut at: InputAt, end: usize) -> bool {
+    fn exec_(&mut self, mut at: InputAt, end: usize, anchored: bool) -> bool {
         self.clear();
         // If this is an anchored regex at the beginning of the input, then
         // we're either already done or we only need to try backtracking once.
         if self.prog.is_anchored_start {
             return if !at.is_start() { false } else { self.backtrack(at) };
         }
+        // Likewise, an anchored search only tries the position it starts at.
+        if anchored {
+            return self.backtrack(at);
+        }
         let mut matched = false;
         loop {
             if !self.prog.prefixes.is_empty() {
@@ -281,7 +298,7 @@ enum Job {
 }
 
 #[cfg_attr(test, ::mutagen::mutate)] fn usize_to_u32(n: usize) -> u32 {
-    if (n as u64) > (::std::u32::MAX as u64) {
+    if (n as u64) > (::core::u32::MAX as u64) {
         panic!("BUG: {} is too big to fit into u32", n)
     }
     n as u32
@@ -2,7 +2,13 @@
 // The `thread_local` crate provides an extremely optimized version of this.
 // However, if the perf-cache feature is disabled, then we drop the
 // thread_local dependency and instead use a pretty naive caching mechanism
-// with a mutex.
+// with a mutex. Without std, there is neither a thread local nor a mutex, so
+// nothing is cached at all and every guard owns a freshly created value.
+// Callers that care about performance in that configuration are expected to
+// create their own cache and pass it in explicitly.
+//
+// In every configuration, a guard may also wrap a value borrowed from the
+// caller. This is what makes explicit cache passing possible.
 //
 // Strictly speaking, the CachedGuard isn't necessary for the much more
 // flexible thread_local API, but implementing thread_local's API doesn't
@@ -31,13 +37,17 @@ mod imp {
     }
 
     impl<'a, T: Send> CachedGuard<'a, T> {
+        pub fn borrowed(value: &'a T) -> CachedGuard<'a, T> {
+            CachedGuard(value)
+        }
+
         pub fn value(&self) -> &T {
             self.0
         }
     }
 }
 
-#[cfg(not(feature = "perf-cache"))]
+#[cfg(all(feature = "std", not(feature = "perf-cache")))]
 mod imp {
     use std::marker::PhantomData;
     use std::panic::UnwindSafe;
@@ -60,9 +70,9 @@ mod imp {
     }
 
     #[derive(Debug)]
-    pub struct CachedGuard<'a, T: 'a + Send> {
-        cache: &'a Cached<T>,
-        value: Option<T>,
+    pub enum CachedGuard<'a, T: 'a + Send> {
+        Pooled { cache: &'a Cached<T>, value: Option<T> },
+        Borrowed(&'a T),
     }
 
     impl<T: Send> Cached<T> {
@@ -72,10 +82,11 @@ mod imp {
 
         pub fn get_or(&self, create: impl FnOnce() -> T) -> CachedGuard<T> {
             let mut stack = self.stack.lock().unwrap();
-            match stack.pop() {
-                None => CachedGuard { cache: self, value: Some(create()) },
-                Some(value) => CachedGuard { cache: self, value: Some(value) },
-            }
+            let value = match stack.pop() {
+                None => create(),
+                Some(value) => value,
+            };
+            CachedGuard::Pooled { cache: self, value: Some(value) }
         }
 
         fn put(&self, value: T) {
@@ -85,15 +96,67 @@ mod imp {
     }
 
     impl<'a, T: Send> CachedGuard<'a, T> {
+        pub fn borrowed(value: &'a T) -> CachedGuard<'a, T> {
+            CachedGuard::Borrowed(value)
+        }
+
         pub fn value(&self) -> &T {
-            self.value.as_ref().unwrap()
+            match *self {
+                CachedGuard::Pooled { ref value, .. } => {
+                    value.as_ref().unwrap()
+                }
+                CachedGuard::Borrowed(value) => value,
+            }
         }
     }
 
     impl<'a, T: Send> Drop for CachedGuard<'a, T> {
         fn drop(&mut self) {
-            if let Some(value) = self.value.take() {
-                self.cache.put(value);
+            if let CachedGuard::Pooled { cache, ref mut value } = *self {
+                if let Some(value) = value.take() {
+                    cache.put(value);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+mod imp {
+    use core::marker::PhantomData;
+
+    #[derive(Debug)]
+    pub struct Cached<T: Send> {
+        /// Nothing is actually stored, but this type must be Send and Sync
+        /// regardless of whether `T` is.
+        _phantom: PhantomData<fn() -> T>,
+    }
+
+    #[derive(Debug)]
+    pub enum CachedGuard<'a, T: 'a> {
+        Owned(T),
+        Borrowed(&'a T),
+    }
+
+    impl<T: Send> Cached<T> {
+        pub fn new() -> Cached<T> {
+            Cached { _phantom: PhantomData }
+        }
+
+        pub fn get_or(&self, create: impl FnOnce() -> T) -> CachedGuard<T> {
+            CachedGuard::Owned(create())
+        }
+    }
+
+    impl<'a, T: Send> CachedGuard<'a, T> {
+        pub fn borrowed(value: &'a T) -> CachedGuard<'a, T> {
+            CachedGuard::Borrowed(value)
+        }
+
+        pub fn value(&self) -> &T {
+            match *self {
+                CachedGuard::Owned(ref value) => value,
+                CachedGuard::Borrowed(value) => value,
             }
         }
     }
@@ -36,11 +36,13 @@ articles on regexes is strongly recommended: https://swtch.com/~rsc/regexp/
 #[cfg_attr(test, ::mutagen::mutate)] implementation.)
 */
 
-use std::collections::HashMap;
-use std::fmt;
-use std::iter::repeat;
-use std::mem;
-use std::sync::Arc;
+use alloc::borrow::ToOwned;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::repeat;
+use core::mem;
 
 use crate::exec::ProgramCache;
 use crate::prog::{Inst, Program};
@@ -62,13 +64,24 @@ pub fn can_exec(insts: &Program) -> bool {
     // If i32::MAX is the largest positive delta,
     // then -i32::MAX == i32::MIN + 1 is the largest negative delta,
     // and we are OK to use 32 bits.
-    if insts.dfa_size_limit == 0 || insts.len() > ::std::i32::MAX as usize {
+    if insts.dfa_size_limit == 0 || insts.len() > ::core::i32::MAX as usize {
         return false;
     }
     for inst in insts {
         match *inst {
             Char(_) | Ranges(_) => return false,
-            EmptyLook(_) | Match(_) | Save(_) | Split(_) | Bytes(_) => {}
+            // UAX#29 word boundaries need more than one byte of look-behind
+            // and look-ahead, which the DFA can't provide.
+            EmptyLook(ref inst) => {
+                use crate::prog::EmptyLook::*;
+                match inst.look {
+                    WordBoundarySegment | NotWordBoundarySegment => {
+                        return false
+                    }
+                    _ => {}
+                }
+            }
+            Match(_) | Save(_) | Split(_) | Bytes(_) => {}
         }
     }
     true
@@ -178,6 +191,9 @@ pub struct Fsm<'a> {
     start: StatePtr,
     /// The current position in the input.
     at: usize,
+    /// When true, the search starts at the regex itself instead of at the
+    /// `.*?` preceding it, so a match must begin where the search begins.
+    anchored: bool,
     /// Should we quit after seeing the first match? e.g., When the caller
     /// uses `is_match` or `shortest_match`.
     quit_after_match: bool,
@@ -258,7 +274,7 @@ pub enum Result<T> {
 /// to the previous one. If a delta is in the range -127..127,
 /// it is packed into a single byte; Otherwise the byte 128 (-128 as an i8)
 /// is coded as a flag, followed by 4 bytes encoding the delta.
-#[derive(Clone, Eq, Hash, PartialEq)]
+#[derive(Clone, Eq, Hash, Ord, PartialEq, PartialOrd)]
 struct State {
     data: Arc<[u8]>,
 }
@@ -431,6 +447,14 @@ struct StateFlags(u8);
         cache.inner.reset_size();
         cache
     }
+
+    /// Returns the approximate heap memory, in bytes, used by this cache.
+    pub fn memory_usage(&self) -> usize {
+        self.inner.size
+            + (self.qcur.capacity() + self.qnext.capacity())
+                * 2
+                * mem::size_of::<usize>()
+    }
 }
 
 #[cfg_attr(test, ::mutagen::mutate)] impl CacheInner {
@@ -452,17 +476,62 @@ struct StateFlags(u8);
         at: usize,
     ) -> Result<usize> {
         let mut cache = cache.borrow_mut();
-        let cache = &mut cache.dfa;
+        Fsm::forward_with_cache(
+            prog,
+            &mut cache.dfa,
+            false,
+            quit_after_match,
+            text,
+            at,
+        )
+    }
+
+    /// Like `forward`, but only reports a match that begins at `at`.
+    ///
+    /// Anchored searches compute different start states than unanchored
+    /// ones, so they use their own cache.
+    #[cfg_attr(feature = "perf-inline", inline(always))]
+    pub fn forward_anchored(
+        prog: &'a Program,
+        cache: &ProgramCache,
+        quit_after_match: bool,
+        text: &[u8],
+        at: usize,
+    ) -> Result<usize> {
+        let mut cache = cache.borrow_mut();
+        Fsm::forward_with_cache(
+            prog,
+            &mut cache.dfa_anchored,
+            true,
+            quit_after_match,
+            text,
+            at,
+        )
+    }
+
+    #[cfg_attr(feature = "perf-inline", inline(always))]
+    fn forward_with_cache(
+        prog: &'a Program,
+        cache: &mut Cache,
+        anchored: bool,
+        quit_after_match: bool,
+        text: &[u8],
+        at: usize,
+    ) -> Result<usize> {
         let mut dfa = Fsm {
             prog: prog,
             start: 0, // filled in below
             at: at,
+            anchored: anchored,
             quit_after_match: quit_after_match,
             last_match_si: STATE_UNKNOWN,
             last_cache_flush: at,
             cache: &mut cache.inner,
         };
-        let (empty_flags, state_flags) = dfa.start_flags(text, at);
+        let (empty_flags, state_flags) = match dfa.start_flags(text, at) {
+            None => return Result::Quit,
+            Some(flags) => flags,
+        };
         dfa.start =
             match dfa.start_state(&mut cache.qcur, empty_flags, state_flags) {
                 None => return Result::Quit,
@@ -480,19 +549,105 @@ struct StateFlags(u8);
         quit_after_match: bool,
         text: &[u8],
         at: usize,
+    ) -> Result<usize> {
+        Fsm::reverse_bounded(prog, cache, quit_after_match, text, 0, at)
+    }
+
+    /// Like `reverse`, but never moves before the position `min`.
+    ///
+    /// Unlike searching `&text[min..]`, the bytes surrounding `text[min..at]`
+    /// are used to evaluate assertions (like `\b`) at `min` and `at`.
+    #[cfg_attr(feature = "perf-inline", inline(always))]
+    pub fn reverse_bounded(
+        prog: &'a Program,
+        cache: &ProgramCache,
+        quit_after_match: bool,
+        text: &[u8],
+        min: usize,
+        at: usize,
     ) -> Result<usize> {
         let mut cache = cache.borrow_mut();
-        let cache = &mut cache.dfa_reverse;
+        Fsm::reverse_with_cache(
+            prog,
+            &mut cache.dfa_reverse,
+            quit_after_match,
+            text,
+            min,
+            at,
+        )
+    }
+
+    /// Like `reverse_bounded`, but for the reverse program of the part of a
+    /// regex that precedes its inner literals, which has its own cache.
+    #[cfg_attr(feature = "perf-inline", inline(always))]
+    pub fn reverse_inner(
+        prog: &'a Program,
+        cache: &ProgramCache,
+        quit_after_match: bool,
+        text: &[u8],
+        min: usize,
+        at: usize,
+    ) -> Result<usize> {
+        let mut cache = cache.borrow_mut();
+        Fsm::reverse_with_cache(
+            prog,
+            &mut cache.dfa_reverse_inner,
+            quit_after_match,
+            text,
+            min,
+            at,
+        )
+    }
+
+    /// Like `reverse`, but for a reverse program compiled with a preceding
+    /// `.*?`, which has its own cache.
+    ///
+    /// Such a program finds the leftmost-first match of the reversed regex,
+    /// so the position returned is the start of a match that ends as close to
+    /// `at` as possible.
+    #[cfg_attr(feature = "perf-inline", inline(always))]
+    pub fn reverse_unanchored(
+        prog: &'a Program,
+        cache: &ProgramCache,
+        text: &[u8],
+        at: usize,
+    ) -> Result<usize> {
+        let mut cache = cache.borrow_mut();
+        Fsm::reverse_with_cache(
+            prog,
+            &mut cache.dfa_reverse_unanchored,
+            false,
+            text,
+            0,
+            at,
+        )
+    }
+
+    #[cfg_attr(feature = "perf-inline", inline(always))]
+    fn reverse_with_cache(
+        prog: &'a Program,
+        cache: &mut Cache,
+        quit_after_match: bool,
+        text: &[u8],
+        min: usize,
+        at: usize,
+    ) -> Result<usize> {
+        debug_assert!(min <= at);
         let mut dfa = Fsm {
             prog: prog,
             start: 0, // filled in below
             at: at,
+            anchored: false,
             quit_after_match: quit_after_match,
             last_match_si: STATE_UNKNOWN,
             last_cache_flush: at,
             cache: &mut cache.inner,
         };
-        let (empty_flags, state_flags) = dfa.start_flags_reverse(text, at);
+        let (empty_flags, state_flags) =
+            match dfa.start_flags_reverse(text, at) {
+                None => return Result::Quit,
+                Some(flags) => flags,
+            };
         dfa.start =
             match dfa.start_state(&mut cache.qcur, empty_flags, state_flags) {
                 None => return Result::Quit,
@@ -500,7 +655,7 @@ struct StateFlags(u8);
                 Some(si) => si,
             };
         debug_assert!(dfa.start != STATE_UNKNOWN);
-        dfa.exec_at_reverse(&mut cache.qcur, &mut cache.qnext, text)
+        dfa.exec_at_reverse(&mut cache.qcur, &mut cache.qnext, text, min)
     }
 
     #[cfg_attr(feature = "perf-inline", inline(always))]
@@ -518,12 +673,16 @@ struct StateFlags(u8);
             prog: prog,
             start: 0, // filled in below
             at: at,
+            anchored: false,
             quit_after_match: false,
             last_match_si: STATE_UNKNOWN,
             last_cache_flush: at,
             cache: &mut cache.inner,
         };
-        let (empty_flags, state_flags) = dfa.start_flags(text, at);
+        let (empty_flags, state_flags) = match dfa.start_flags(text, at) {
+            None => return Result::Quit,
+            Some(flags) => flags,
+        };
         dfa.start =
             match dfa.start_state(&mut cache.qcur, empty_flags, state_flags) {
                 None => return Result::Quit,
@@ -743,13 +902,14 @@ struct StateFlags(u8);
         result
     }
 
-    /// Executes the DFA on a reverse NFA.
+    /// Executes the DFA on a reverse NFA, stopping at `min`.
     #[cfg_attr(feature = "perf-inline", inline(always))]
     fn exec_at_reverse(
         &mut self,
         qcur: &mut SparseSet,
         qnext: &mut SparseSet,
         text: &[u8],
+        min: usize,
     ) -> Result<usize> {
         // The comments in `exec_at` above mostly apply here too. The main
         // difference is that we move backwards over the input and we look for
@@ -761,12 +921,12 @@ struct StateFlags(u8);
         let mut result = Result::NoMatch(self.at);
         let (mut prev_si, mut next_si) = (self.start, self.start);
         let mut at = self.at;
-        while at > 0 {
-            while next_si <= STATE_MAX && at > 0 {
+        while at > min {
+            while next_si <= STATE_MAX && at > min {
                 // Argument for safety is in the definition of next_si.
                 at -= 1;
                 prev_si = unsafe { self.next_si(next_si, text, at) };
-                if prev_si > STATE_MAX || at <= 4 {
+                if prev_si > STATE_MAX || at <= min + 4 {
                     mem::swap(&mut prev_si, &mut next_si);
                     break;
                 }
@@ -793,7 +953,7 @@ struct StateFlags(u8);
                 self.last_match_si = next_si;
                 prev_si = next_si;
                 let cur = at;
-                while (next_si & !STATE_MATCH) == prev_si && at >= 2 {
+                while (next_si & !STATE_MATCH) == prev_si && at >= min + 2 {
                     // Argument for safety is in the definition of next_si.
                     at -= 1;
                     next_si = unsafe {
@@ -830,17 +990,22 @@ struct StateFlags(u8);
             }
         }
 
-        // Run the DFA once more on the special EOF senitnel value.
-        prev_si = match self.next_state(qcur, qnext, prev_si, Byte::eof()) {
+        // Run the DFA once more on the special EOF senitnel value. If we
+        // stopped short of the beginning of the text, then run it on the
+        // preceding byte instead, which reports matches at `min` since
+        // matches are delayed by one byte.
+        let last =
+            if min == 0 { Byte::eof() } else { Byte::byte(text[min - 1]) };
+        prev_si = match self.next_state(qcur, qnext, prev_si, last) {
             None => return Result::Quit,
-            Some(STATE_DEAD) => return result.set_non_match(0),
+            Some(STATE_DEAD) => return result.set_non_match(min),
             Some(si) => si,
         };
         debug_assert!(prev_si != STATE_UNKNOWN);
         if prev_si & STATE_MATCH > 0 {
             prev_si &= !STATE_MATCH;
             self.last_match_si = prev_si;
-            result = Result::Match(0);
+            result = Result::Match(min);
         }
         result
     }
@@ -1106,9 +1271,16 @@ struct StateFlags(u8);
                             NotWordBoundary if flags.not_word_boundary => {
                                 ip = inst.goto as InstPtr;
                             }
-                            StartLine | EndLine | StartText | EndText
-                            | WordBoundaryAscii | NotWordBoundaryAscii
-                            | WordBoundary | NotWordBoundary => {
+                            StartLine
+                            | EndLine
+                            | StartText
+                            | EndText
+                            | WordBoundaryAscii
+                            | NotWordBoundaryAscii
+                            | WordBoundary
+                            | NotWordBoundary
+                            | WordBoundarySegment
+                            | NotWordBoundarySegment => {
                                 break;
                             }
                         }
@@ -1391,7 +1563,11 @@ struct StateFlags(u8);
             si => return Some(si),
         }
         q.clear();
-        let start = usize_to_u32(self.prog.start);
+        let start = if self.anchored {
+            usize_to_u32(self.prog.anchored_start)
+        } else {
+            usize_to_u32(self.prog.start)
+        };
         self.follow_epsilons(start, q, empty_flags);
         // Start states can never be match states because we delay every match
         // by one byte. Given an empty string and an empty match, the match
@@ -1409,7 +1585,20 @@ struct StateFlags(u8);
     ///
     /// This should only be used when executing the DFA forwards over the
     /// input.
-    fn start_flags(&self, text: &[u8], at: usize) -> (EmptyFlags, StateFlags) {
+    ///
+    /// If the program has a Unicode word boundary and the byte preceding the
+    /// given position isn't ASCII, then the DFA can't tell whether the search
+    /// starts after a word character, so this returns `None` and the DFA
+    /// must quit.
+    fn start_flags(
+        &self,
+        text: &[u8],
+        at: usize,
+    ) -> Option<(EmptyFlags, StateFlags)> {
+        if self.prog.has_unicode_word_boundary && at > 0 && text[at - 1] > 0x7F
+        {
+            return None;
+        }
         let mut empty_flags = EmptyFlags::default();
         let mut state_flags = StateFlags::default();
         empty_flags.start = at == 0;
@@ -1427,18 +1616,27 @@ struct StateFlags(u8);
         } else {
             empty_flags.word_boundary = true;
         }
-        (empty_flags, state_flags)
+        Some((empty_flags, state_flags))
     }
 
     /// Computes the set of starting flags for the given position in text.
     ///
     /// This should only be used when executing the DFA in reverse over the
     /// input.
+    ///
+    /// Like `start_flags`, this returns `None` if the DFA can't determine
+    /// whether the byte following the given position is part of a word.
     fn start_flags_reverse(
         &self,
         text: &[u8],
         at: usize,
-    ) -> (EmptyFlags, StateFlags) {
+    ) -> Option<(EmptyFlags, StateFlags)> {
+        if self.prog.has_unicode_word_boundary
+            && at < text.len()
+            && text[at] > 0x7F
+        {
+            return None;
+        }
         let mut empty_flags = EmptyFlags::default();
         let mut state_flags = StateFlags::default();
         empty_flags.start = at == text.len();
@@ -1457,7 +1655,7 @@ struct StateFlags(u8);
         } else {
             empty_flags.word_boundary = true;
         }
-        (empty_flags, state_flags)
+        Some((empty_flags, state_flags))
     }
 
     /// Returns a reference to a State given a pointer to it.
@@ -1547,9 +1745,11 @@ struct StateFlags(u8);
     ///
     /// On occasion, we want to avoid leftmost first semantics to find either
     /// the longest match (for reverse search) or all possible matches (for
-    /// regex sets).
+    /// regex sets). A reverse program with a preceding `.*?` would then never
+    /// stop, so it uses leftmost first semantics too.
     fn continue_past_first_match(&self) -> bool {
-        self.prog.is_reverse || self.prog.matches.len() > 1
+        (self.prog.is_reverse && !self.prog.needs_dotstar())
+            || self.prog.matches.len() > 1
     }
 
     /// Returns true if there is a prefix we can quickly search for.
@@ -1557,6 +1757,7 @@ struct StateFlags(u8);
         !self.prog.is_reverse
             && !self.prog.prefixes.is_empty()
             && !self.prog.is_anchored_start
+            && !self.anchored
     }
 
     /// Sets the STATE_START bit in the given state pointer if and only if
@@ -1582,6 +1783,13 @@ struct StateFlags(u8);
     }
 }
 
+/// The map from DFA states to their pointers. Without the standard library
+/// there is no `HashMap`, so an ordered map is used instead.
+#[cfg(feature = "std")]
+type StateHashMap = std::collections::HashMap<State, StatePtr>;
+#[cfg(not(feature = "std"))]
+type StateHashMap = alloc::collections::BTreeMap<State, StatePtr>;
+
 /// An abstraction for representing a map of states. The map supports two
 /// different ways of state lookup. One is fast constant time access via a
 /// state pointer. The other is a hashmap lookup based on the DFA's
@@ -1598,7 +1806,7 @@ struct StateMap {
     /// buffer in `states` which will never be moved except when clearing
     /// the map or on drop, in which case the keys of this map will be
     /// removed before
-    map: HashMap<State, StatePtr>,
+    map: StateHashMap,
     /// Our set of states. Note that `StatePtr / num_byte_classes` indexes
     /// this Vec rather than just a `StatePtr`.
     states: Vec<State>,
@@ -1609,7 +1817,7 @@ struct StateMap {
 #[cfg_attr(test, ::mutagen::mutate)] impl StateMap {
     fn new(num_byte_classes: usize) -> StateMap {
         StateMap {
-            map: HashMap::new(),
+            map: StateHashMap::new(),
             states: vec![],
             num_byte_classes: num_byte_classes,
         }
@@ -1807,9 +2015,9 @@ struct TransitionsRow<'a>(&'a [StatePtr]);
 
 /// Helper function for formatting a byte as a nice-to-read escaped string.
 #[cfg_attr(test, ::mutagen::mutate)] fn vb(b: usize) -> String {
-    use std::ascii::escape_default;
+    use core::ascii::escape_default;
 
-    if b > ::std::u8::MAX as usize {
+    if b > ::core::u8::MAX as usize {
         "EOF".to_owned()
     } else {
         let escaped = escape_default(b as u8).collect::<Vec<u8>>();
@@ -1818,7 +2026,7 @@ struct TransitionsRow<'a>(&'a [StatePtr]);
 }
 
 #[cfg_attr(test, ::mutagen::mutate)] fn usize_to_u32(n: usize) -> u32 {
-    if (n as u64) > (::std::u32::MAX as u64) {
+    if (n as u64) > (::core::u32::MAX as u64) {
         panic!("BUG: {} is too big to fit into u32", n)
     }
     n as u32
@@ -1896,7 +2104,7 @@ mod tests {
         State, StateFlags,
     };
     use quickcheck::{quickcheck, QuickCheck, StdGen};
-    use std::sync::Arc;
+    use alloc::sync::Arc;
 
     #[test]
     fn prop_state_encode_decode() {
@@ -1,11 +1,28 @@
-use std::fmt;
-use std::iter::repeat;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use core::fmt;
+use core::iter::repeat;
+use core::ops::Range;
+
+use crate::syntax;
+use crate::syntax::ast;
 
 /// An error that occurred during parsing or compiling a regular expression.
 #[derive(Clone, PartialEq)]
 pub enum Error {
     /// A syntax error.
+    ///
+    /// Errors reported by the parser use `Parse` instead, which also carries
+    /// the spans that the error points to. This is used for the errors that
+    /// are found while compiling a pattern, such as a Unicode word boundary
+    /// in a regex that can match invalid UTF-8.
     Syntax(String),
+    /// A syntax error reported by the parser.
+    ///
+    /// This is displayed exactly like `Syntax`. Use
+    /// [`syntax_error`](#method.syntax_error) to get the spans in the pattern
+    /// that the error points to, along with a suggested fix.
+    Parse(Box<SyntaxError>),
     /// The compiled program exceeded the set size limit.
     /// The argument is the size limit imposed.
     CompiledTooBig(usize),
@@ -18,10 +35,56 @@ pub enum Error {
     __Nonexhaustive,
 }
 
+#[cfg_attr(test, ::mutagen::mutate)] impl Error {
+    /// Convert an error from the parser.
+    pub(crate) fn from_syntax(err: syntax::Error) -> Error {
+        let kind = match err {
+            syntax::Error::Parse(ref err) => err.kind().to_string(),
+            syntax::Error::Translate(ref err) => err.kind().to_string(),
+            // There's no structure to keep, so at least keep the message.
+            err => return Error::Syntax(err.to_string()),
+        };
+        let message = err.to_string();
+        Error::Parse(Box::new(SyntaxError {
+            err: err,
+            kind: kind,
+            message: message,
+        }))
+    }
+
+    /// Return the structured form of this error, if it is an error reported
+    /// by the parser.
+    ///
+    /// It returns `None` for errors that aren't about the syntax of a
+    /// pattern, and for syntax errors that are only found while compiling a
+    /// pattern (see `Error::Syntax`).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use regex::Regex;
+    ///
+    /// let err = Regex::new(r"ab{2").unwrap_err();
+    /// let syntax_error = err.syntax_error().unwrap();
+    /// assert_eq!(2..4, syntax_error.span());
+    ///
+    /// let suggestion = syntax_error.suggestion().unwrap();
+    /// assert_eq!(r"ab\{2", suggestion.apply(syntax_error.pattern()));
+    /// ```
+    pub fn syntax_error(&self) -> Option<&SyntaxError> {
+        match *self {
+            Error::Parse(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
 #[cfg_attr(test, ::mutagen::mutate)] impl ::std::error::Error for Error {
     fn description(&self) -> &str {
         match *self {
             Error::Syntax(ref err) => err,
+            Error::Parse(ref err) => &err.message,
             Error::CompiledTooBig(_) => "compiled program too big",
             Error::__Nonexhaustive => unreachable!(),
         }
@@ -32,6 +95,7 @@ pub enum Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             Error::Syntax(ref err) => err.fmt(f),
+            Error::Parse(ref err) => err.fmt(f),
             Error::CompiledTooBig(limit) => write!(
                 f,
                 "Compiled regex exceeds size limit of {} bytes.",
@@ -48,22 +112,143 @@ pub enum Error {
 // as well format it nicely.
 #[cfg_attr(test, ::mutagen::mutate)] impl fmt::Debug for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            Error::Syntax(ref err) => {
-                let hr: String = repeat('~').take(79).collect();
-                writeln!(f, "Syntax(")?;
-                writeln!(f, "{}", hr)?;
-                writeln!(f, "{}", err)?;
-                writeln!(f, "{}", hr)?;
-                write!(f, ")")?;
-                Ok(())
-            }
+        let (name, msg) = match *self {
+            Error::Syntax(ref err) => ("Syntax", err),
+            Error::Parse(ref err) => ("Parse", &err.message),
             Error::CompiledTooBig(limit) => {
-                f.debug_tuple("CompiledTooBig").field(&limit).finish()
+                return f.debug_tuple("CompiledTooBig").field(&limit).finish()
             }
             Error::__Nonexhaustive => {
-                f.debug_tuple("__Nonexhaustive").finish()
+                return f.debug_tuple("__Nonexhaustive").finish()
             }
-        }
+        };
+        let hr: String = repeat('~').take(79).collect();
+        writeln!(f, "{}(", name)?;
+        writeln!(f, "{}", hr)?;
+        writeln!(f, "{}", msg)?;
+        writeln!(f, "{}", hr)?;
+        write!(f, ")")?;
+        Ok(())
+    }
+}
+
+/// An error in the syntax of a pattern, with the spans in the pattern that
+/// it points to.
+///
+/// This is returned by
+/// [`Error::syntax_error`](enum.Error.html#method.syntax_error).
+/// Spans are byte offsets into [`pattern`](#method.pattern).
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyntaxError {
+    /// Always a `Parse` or `Translate` error.
+    err: syntax::Error,
+    kind: String,
+    /// The rendered error, which is what `Error::Syntax` would contain.
+    message: String,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl SyntaxError {
+    /// The pattern in which this error occurred.
+    pub fn pattern(&self) -> &str {
+        self.err.pattern()
+    }
+
+    /// Return the span of the pattern at which this error occurred.
+    pub fn span(&self) -> Range<usize> {
+        to_range(self.err.span())
+    }
+
+    /// Return an auxiliary span, for errors that point to two locations in
+    /// the pattern. For example, a duplicate capture group name has its
+    /// auxiliary span set to the first use of the name.
+    pub fn auxiliary_span(&self) -> Option<Range<usize>> {
+        self.err.auxiliary_span().map(to_range)
+    }
+
+    /// Return a short description of the kind of this error, such as
+    /// `unclosed group`.
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    /// Return a suggested fix for this error, if there is an obvious one.
+    ///
+    /// For example, an unclosed group like `a(b` usually means that a
+    /// literal `(` was intended, so the suggestion is to escape it as `\(`.
+    pub fn suggestion(&self) -> Option<Suggestion> {
+        self.err.suggestion().map(|sugg| Suggestion {
+            span: to_range(sugg.span()),
+            replacement: sugg.replacement().to_string(),
+        })
+    }
+
+    /// Render this error in the style of a compiler diagnostic, with every
+    /// span of the error labeled in the lines of the pattern that contain
+    /// it, followed by a suggestion if there is one.
+    ///
+    /// For example, the pattern `(?P<a>x)(?P<a>y)` is rendered as:
+    ///
+    /// ```text
+    /// error: duplicate capture group name
+    ///  --> 1:13
+    ///   |
+    /// 1 | (?P<a>x)(?P<a>y)
+    ///   |     -       ^
+    ///   |     |
+    ///   |     first used here
+    /// ```
+    pub fn diagnostic(&self) -> String {
+        self.err.diagnostic().to_string()
+    }
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.message.fmt(f)
+    }
+}
+
+/// A suggested fix for a syntax error, which replaces a span of the pattern
+/// with new text.
+///
+/// Its `Display` implementation writes a short help message, such as
+/// ``did you mean `\{`?``.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Suggestion {
+    span: Range<usize>,
+    replacement: String,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl Suggestion {
+    /// Return the span of the pattern that should be replaced.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// Return the text that should replace the span.
+    pub fn replacement(&self) -> &str {
+        &self.replacement
+    }
+
+    /// Return a copy of the given pattern with this suggestion applied.
+    ///
+    /// `pattern` should be the pattern of the error this suggestion came
+    /// from.
+    pub fn apply(&self, pattern: &str) -> String {
+        let mut fixed = String::new();
+        fixed.push_str(&pattern[..self.span.start]);
+        fixed.push_str(&self.replacement);
+        fixed.push_str(&pattern[self.span.end..]);
+        fixed
     }
 }
+
+#[cfg_attr(test, ::mutagen::mutate)] impl fmt::Display for Suggestion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "did you mean `{}`?", self.replacement)
+    }
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] fn to_range(span: &ast::Span) -> Range<usize> {
+    span.start.offset..span.end.offset
+}
@@ -1,11 +1,15 @@
-use std::cell::RefCell;
-use std::collections::HashMap;
-use std::sync::Arc;
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt;
 
 #[cfg(feature = "perf-literal")]
 use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
-use crate::syntax::hir::literal::Literals;
-use crate::syntax::hir::Hir;
+use crate::syntax::hir::literal::{InnerLiterals, Literals};
+use crate::syntax::hir::simplify::Simplifier;
+use crate::syntax::hir::{ClassUnicode, ClassUnicodeRange, Hir};
 use crate::syntax::ParserBuilder;
 
 use crate::backtrack;
@@ -17,7 +21,7 @@ use crate::error::Error;
 use crate::input::{ByteInput, CharInput};
 use crate::literal::LiteralSearcher;
 use crate::pikevm;
-use crate::prog::Program;
+use crate::prog::{CaptureNameMap, Program};
 use crate::re_builder::RegexOptions;
 use crate::re_bytes;
 use crate::re_set;
@@ -51,6 +55,23 @@ pub struct ExecNoSync<'c> {
 /// `ExecNoSyncStr` is like `ExecNoSync`, but matches on &str instead of &[u8].
 pub struct ExecNoSyncStr<'c>(ExecNoSync<'c>);
 
+/// A cache of scratch space used while searching with a regex.
+///
+/// A cache is created with a regex's `create_cache` method and passed to its
+/// `_with_cache` search methods. This gives the caller full control over when
+/// scratch space is allocated and how it is shared between threads. A cache
+/// is `Send`, but not `Sync`.
+///
+/// A cache is tied to the regex that created it (and its clones). Using it
+/// with any other regex is allowed, but the cache is then reset for that
+/// regex, which discards all of its previously allocated scratch space.
+pub struct Cache {
+    /// The read only state of the regex this cache was created for.
+    ro: Arc<ExecReadOnly>,
+    /// Caches for the various matching engines.
+    cache: ProgramCache,
+}
+
 /// `ExecReadOnly` comprises all read only state for a regex. Namely, all such
 /// state is determined at compile time and never changes during search.
 #[derive(Debug)]
@@ -78,6 +99,24 @@ struct ExecReadOnly {
     /// Prefix literals are stored on the `Program`, since they are used inside
     /// the matching engines.
     suffixes: LiteralSearcher,
+    /// A set of literals that every match of the regex must contain, but
+    /// that aren't necessarily a prefix or suffix of the match.
+    ///
+    /// This is only non-empty for a single regex that is a concatenation
+    /// with a required literal after its first element.
+    inner: LiteralSearcher,
+    /// A reversed byte based program for the part of the regex that precedes
+    /// the inner literals. It is empty if there are no inner literals.
+    ///
+    /// After finding an inner literal, this is used to find where the
+    /// leftmost match that contains it starts.
+    dfa_reverse_inner: Program,
+    /// A reversed byte based program with a preceding `.*?`. It is empty if
+    /// the regex is anchored at the end or if this is a regex set.
+    ///
+    /// This is used by the DFA to search backwards for the match that ends
+    /// closest to a given position.
+    dfa_reverse_unanchored: Program,
     /// An Aho-Corasick automaton with leftmost-first match semantics.
     ///
     /// This is only set when the entire regex is a simple unanchored
@@ -110,6 +149,7 @@ struct Parsed {
     exprs: Vec<Hir>,
     prefixes: Literals,
     suffixes: Literals,
+    inner: Option<InnerLiterals>,
     bytes: bool,
 }
 
@@ -212,29 +252,53 @@ struct Parsed {
         self
     }
 
+    /// Simplify each expression after parsing it.
+    pub fn simplify(mut self, yes: bool) -> Self {
+        self.options.simplify = yes;
+        self
+    }
+
     /// Parse the current set of patterns into their AST and extract literals.
     fn parse(&self) -> Result<Parsed, Error> {
         let mut exprs = Vec::with_capacity(self.options.pats.len());
         let mut prefixes = Some(Literals::empty());
         let mut suffixes = Some(Literals::empty());
+        let mut inner = None;
         let mut bytes = false;
         let is_set = self.options.pats.len() > 1;
+        let dialect = match self.options.dialect.to_syntax() {
+            Some(dialect) => dialect,
+            None => {
+                return Err(Error::Syntax(
+                    "unsupported regex dialect".to_string(),
+                ))
+            }
+        };
+        let mut builder = ParserBuilder::new();
+        builder
+            .octal(self.options.octal)
+            .case_insensitive(self.options.case_insensitive)
+            .multi_line(self.options.multi_line)
+            .dot_matches_new_line(self.options.dot_matches_new_line)
+            .swap_greed(self.options.swap_greed)
+            .ignore_whitespace(self.options.ignore_whitespace)
+            .unicode(self.options.unicode)
+            .allow_invalid_utf8(!self.only_utf8)
+            .nest_limit(self.options.nest_limit)
+            .dialect(dialect);
+        for &(ref name, ref ranges) in &self.options.properties {
+            let ranges =
+                ranges.iter().map(|&(s, e)| ClassUnicodeRange::new(s, e));
+            builder.unicode_property(name, ClassUnicode::new(ranges));
+        }
         // If we're compiling a regex set and that set has any anchored
         // expressions, then disable all literal optimizations.
         for pat in &self.options.pats {
-            let mut parser = ParserBuilder::new()
-                .octal(self.options.octal)
-                .case_insensitive(self.options.case_insensitive)
-                .multi_line(self.options.multi_line)
-                .dot_matches_new_line(self.options.dot_matches_new_line)
-                .swap_greed(self.options.swap_greed)
-                .ignore_whitespace(self.options.ignore_whitespace)
-                .unicode(self.options.unicode)
-                .allow_invalid_utf8(!self.only_utf8)
-                .nest_limit(self.options.nest_limit)
-                .build();
-            let expr =
-                parser.parse(pat).map_err(|e| Error::Syntax(e.to_string()))?;
+            let mut parser = builder.build();
+            let mut expr = parser.parse(pat).map_err(Error::from_syntax)?;
+            if self.options.simplify {
+                expr = Simplifier::new().simplify(expr);
+            }
             bytes = bytes || !expr.is_always_utf8();
 
             if cfg!(feature = "perf-literal") {
@@ -271,6 +335,14 @@ struct Parsed {
                         Some(suffixes)
                     }
                 });
+
+                if !is_set {
+                    // Short inner literals are unlikely to be worth it, so
+                    // don't bother compiling a program for them.
+                    inner = Literals::inner(&expr).filter(|inner| {
+                        inner.literals().min_len().map_or(false, |n| n >= 3)
+                    });
+                }
             }
             exprs.push(expr);
         }
@@ -278,6 +350,7 @@ struct Parsed {
             exprs: exprs,
             prefixes: prefixes.unwrap_or_else(Literals::empty),
             suffixes: suffixes.unwrap_or_else(Literals::empty),
+            inner: inner,
             bytes: bytes,
         })
     }
@@ -293,13 +366,16 @@ struct Parsed {
                 dfa: Program::new(),
                 dfa_reverse: Program::new(),
                 suffixes: LiteralSearcher::empty(),
+                inner: LiteralSearcher::empty(),
+                dfa_reverse_inner: Program::new(),
+                dfa_reverse_unanchored: Program::new(),
                 #[cfg(feature = "perf-literal")]
                 ac: None,
                 match_type: MatchType::Nothing,
             });
             return Ok(Exec { ro: ro, cache: Cached::new() });
         }
-        let parsed = self.parse()?;
+        let mut parsed = self.parse()?;
         let mut nfa = Compiler::new()
             .size_limit(self.options.size_limit)
             .bytes(self.bytes || parsed.bytes)
@@ -316,6 +392,31 @@ struct Parsed {
             .only_utf8(self.only_utf8)
             .reverse(true)
             .compile(&parsed.exprs)?;
+        let (inner, mut dfa_reverse_inner) = match parsed.inner.take() {
+            None => (LiteralSearcher::empty(), Program::new()),
+            Some(inner) => {
+                let (prefix, lits) = inner.into_parts();
+                let prog = Compiler::new()
+                    .size_limit(self.options.size_limit)
+                    .dfa(true)
+                    .only_utf8(self.only_utf8)
+                    .reverse(true)
+                    .compile(&[prefix])?;
+                (LiteralSearcher::prefixes(lits), prog)
+            }
+        };
+        let mut dfa_reverse_unanchored =
+            if parsed.exprs.len() == 1 && !parsed.exprs[0].is_anchored_end() {
+                Compiler::new()
+                    .size_limit(self.options.size_limit)
+                    .dfa(true)
+                    .only_utf8(self.only_utf8)
+                    .reverse(true)
+                    .reverse_unanchored(true)
+                    .compile(&parsed.exprs)?
+            } else {
+                Program::new()
+            };
 
         #[cfg(feature = "perf-literal")]
         let ac = self.build_aho_corasick(&parsed);
@@ -323,6 +424,8 @@ struct Parsed {
         dfa.prefixes = nfa.prefixes.clone();
         dfa.dfa_size_limit = self.options.dfa_size_limit;
         dfa_reverse.dfa_size_limit = self.options.dfa_size_limit;
+        dfa_reverse_inner.dfa_size_limit = self.options.dfa_size_limit;
+        dfa_reverse_unanchored.dfa_size_limit = self.options.dfa_size_limit;
 
         let mut ro = ExecReadOnly {
             res: self.options.pats,
@@ -330,6 +433,9 @@ struct Parsed {
             dfa: dfa,
             dfa_reverse: dfa_reverse,
             suffixes: LiteralSearcher::suffixes(parsed.suffixes),
+            inner: inner,
+            dfa_reverse_inner: dfa_reverse_inner,
+            dfa_reverse_unanchored: dfa_reverse_unanchored,
             #[cfg(feature = "perf-literal")]
             ac: ac,
             match_type: MatchType::Nothing,
@@ -406,6 +512,32 @@ struct Parsed {
     }
 }
 
+#[cfg_attr(test, ::mutagen::mutate)] impl<'c> ExecNoSyncStr<'c> {
+    #[cfg_attr(feature = "perf-inline", inline(always))]
+    pub fn find_anchored_at(
+        &self,
+        text: &str,
+        start: usize,
+    ) -> Option<(usize, usize)> {
+        self.0.find_anchored_at(text.as_bytes(), start)
+    }
+
+    #[cfg_attr(feature = "perf-inline", inline(always))]
+    pub fn captures_read_anchored_at(
+        &self,
+        locs: &mut Locations,
+        text: &str,
+        start: usize,
+    ) -> Option<(usize, usize)> {
+        self.0.captures_read_anchored_at(locs, text.as_bytes(), start)
+    }
+
+    #[cfg_attr(feature = "perf-inline", inline(always))]
+    pub fn rfind_at(&self, text: &str, end: usize) -> Option<(usize, usize)> {
+        self.0.rfind_at(text.as_bytes(), end)
+    }
+}
+
 #[cfg_attr(test, ::mutagen::mutate)] impl<'c> RegularExpression for ExecNoSync<'c> {
     type Text = [u8];
 
@@ -442,11 +574,12 @@ struct Parsed {
             }
             #[cfg(feature = "perf-dfa")]
             MatchType::DfaAnchoredReverse => {
-                match dfa::Fsm::reverse(
+                match dfa::Fsm::reverse_bounded(
                     &self.ro.dfa_reverse,
                     self.cache.value(),
                     true,
-                    &text[start..],
+                    text,
+                    start,
                     text.len(),
                 ) {
                     dfa::Result::Match(_) => Some(text.len()),
@@ -462,6 +595,14 @@ struct Parsed {
                     dfa::Result::Quit => self.shortest_nfa(text, start),
                 }
             }
+            #[cfg(all(feature = "perf-dfa", feature = "perf-literal"))]
+            MatchType::DfaInner => {
+                match self.shortest_dfa_reverse_inner(text, start) {
+                    dfa::Result::Match(e) => Some(e),
+                    dfa::Result::NoMatch(_) => None,
+                    dfa::Result::Quit => self.shortest_nfa(text, start),
+                }
+            }
             MatchType::Nfa(ty) => self.shortest_nfa_type(ty, text, start),
             MatchType::Nothing => None,
         }
@@ -494,11 +635,12 @@ struct Parsed {
             }
             #[cfg(feature = "perf-dfa")]
             MatchType::DfaAnchoredReverse => {
-                match dfa::Fsm::reverse(
+                match dfa::Fsm::reverse_bounded(
                     &self.ro.dfa_reverse,
                     self.cache.value(),
                     true,
-                    &text[start..],
+                    text,
+                    start,
                     text.len(),
                 ) {
                     dfa::Result::Match(_) => true,
@@ -514,6 +656,14 @@ struct Parsed {
                     dfa::Result::Quit => self.match_nfa(text, start),
                 }
             }
+            #[cfg(all(feature = "perf-dfa", feature = "perf-literal"))]
+            MatchType::DfaInner => {
+                match self.shortest_dfa_reverse_inner(text, start) {
+                    dfa::Result::Match(_) => true,
+                    dfa::Result::NoMatch(_) => false,
+                    dfa::Result::Quit => self.match_nfa(text, start),
+                }
+            }
             MatchType::Nfa(ty) => self.match_nfa_type(ty, text, start),
             MatchType::Nothing => false,
         }
@@ -557,6 +707,16 @@ struct Parsed {
                     }
                 }
             }
+            #[cfg(all(feature = "perf-dfa", feature = "perf-literal"))]
+            MatchType::DfaInner => {
+                match self.find_dfa_reverse_inner(text, start) {
+                    dfa::Result::Match((s, e)) => Some((s, e)),
+                    dfa::Result::NoMatch(_) => None,
+                    dfa::Result::Quit => {
+                        self.find_nfa(MatchNfaType::Auto, text, start)
+                    }
+                }
+            }
             MatchType::Nfa(ty) => self.find_nfa(ty, text, start),
             MatchType::Nothing => None,
             #[cfg(feature = "perf-dfa")]
@@ -661,6 +821,20 @@ struct Parsed {
                     dfa::Result::Quit => self.captures_nfa(slots, text, start),
                 }
             }
+            #[cfg(all(feature = "perf-dfa", feature = "perf-literal"))]
+            MatchType::DfaInner => {
+                match self.find_dfa_reverse_inner(text, start) {
+                    dfa::Result::Match((s, e)) => self.captures_nfa_type(
+                        MatchNfaType::Auto,
+                        slots,
+                        text,
+                        s,
+                        e,
+                    ),
+                    dfa::Result::NoMatch(_) => None,
+                    dfa::Result::Quit => self.captures_nfa(slots, text, start),
+                }
+            }
             MatchType::Nfa(ty) => {
                 self.captures_nfa_type(ty, slots, text, start, text.len())
             }
@@ -740,14 +914,15 @@ struct Parsed {
             Match(end) => end,
         };
         // Now run the DFA in reverse to find the start of the match.
-        match dfa::Fsm::reverse(
+        match dfa::Fsm::reverse_bounded(
             &self.ro.dfa_reverse,
             self.cache.value(),
             false,
-            &text[start..],
-            end - start,
+            text,
+            start,
+            end,
         ) {
-            Match(s) => Match((start + s, end)),
+            Match(s) => Match((s, end)),
             NoMatch(i) => NoMatch(i),
             Quit => Quit,
         }
@@ -767,19 +942,98 @@ struct Parsed {
         start: usize,
     ) -> dfa::Result<(usize, usize)> {
         use crate::dfa::Result::*;
-        match dfa::Fsm::reverse(
+        match dfa::Fsm::reverse_bounded(
             &self.ro.dfa_reverse,
             self.cache.value(),
             false,
-            &text[start..],
-            text.len() - start,
+            text,
+            start,
+            text.len(),
+        ) {
+            Match(s) => Match((s, text.len())),
+            NoMatch(i) => NoMatch(i),
+            Quit => Quit,
+        }
+    }
+
+    /// Finds the leftmost-first match that begins at `start` using only the
+    /// DFA.
+    ///
+    /// If the result returned indicates that the DFA quit, then another
+    /// matching engine should be used.
+    #[cfg(feature = "perf-dfa")]
+    #[cfg_attr(feature = "perf-inline", inline(always))]
+    fn find_dfa_anchored_forward(
+        &self,
+        text: &[u8],
+        start: usize,
+    ) -> dfa::Result<(usize, usize)> {
+        use crate::dfa::Result::*;
+        match dfa::Fsm::forward_anchored(
+            &self.ro.dfa,
+            self.cache.value(),
+            false,
+            text,
+            start,
         ) {
-            Match(s) => Match((start + s, text.len())),
+            Match(end) => Match((start, end)),
             NoMatch(i) => NoMatch(i),
             Quit => Quit,
         }
     }
 
+    /// Like rfind_at, but uses the DFA.
+    ///
+    /// The reverse DFA with a preceding `.*?` runs backwards from `end` and
+    /// stops at the start of a match that ends as close to `end` as
+    /// possible. The PikeVM then only searches from there to `end` to find
+    /// where that match ends, and the reverse DFA finds the first position
+    /// at which a match ending there can begin.
+    ///
+    /// If the DFA quits, then the PikeVM searches all of `text[..end]`.
+    #[cfg(feature = "perf-dfa")]
+    fn rfind_dfa(&self, text: &[u8], end: usize) -> Option<(usize, usize)> {
+        use crate::dfa::Result::*;
+        if self.ro.nfa.is_anchored_end {
+            // A match can only end at the end of the text.
+            if end < text.len() {
+                return None;
+            }
+            return match self.find_dfa_anchored_reverse(text, 0) {
+                Match((s, e)) => Some((s, e)),
+                NoMatch(_) => None,
+                Quit => self.rfind_nfa(text, 0, end),
+            };
+        }
+        let start = match dfa::Fsm::reverse_unanchored(
+            &self.ro.dfa_reverse_unanchored,
+            self.cache.value(),
+            text,
+            end,
+        ) {
+            Match(start) => start,
+            NoMatch(_) => return None,
+            Quit => return self.rfind_nfa(text, 0, end),
+        };
+        // No match ends after the one beginning at `start`, but a match
+        // beginning after it may end at the same position.
+        let e = match self.rfind_nfa(text, start, end) {
+            Some((_, e)) => e,
+            None => return None,
+        };
+        match dfa::Fsm::reverse(
+            &self.ro.dfa_reverse,
+            self.cache.value(),
+            false,
+            text,
+            e,
+        ) {
+            Match(s) => Some((s, e)),
+            NoMatch(_) => None,
+            Quit => self.rfind_nfa(text, 0, e),
+        }
+    }
+
     /// Finds the end of the shortest match using only the DFA.
     #[cfg(feature = "perf-dfa")]
     #[cfg_attr(feature = "perf-inline", inline(always))]
@@ -894,6 +1148,95 @@ struct Parsed {
         }
     }
 
+    /// Finds the end of the shortest match using only the DFA by scanning for
+    /// inner literals.
+    #[cfg(all(feature = "perf-dfa", feature = "perf-literal"))]
+    #[cfg_attr(feature = "perf-inline", inline(always))]
+    fn shortest_dfa_reverse_inner(
+        &self,
+        text: &[u8],
+        start: usize,
+    ) -> dfa::Result<usize> {
+        match self.exec_dfa_reverse_inner(text, start) {
+            dfa::Result::Match(s) => self.shortest_dfa(text, s),
+            dfa::Result::NoMatch(i) => dfa::Result::NoMatch(i),
+            dfa::Result::Quit => dfa::Result::Quit,
+        }
+    }
+
+    /// Finds a position at which to start a forward DFA search by scanning
+    /// for inner literals and running the reverse DFA of the part of the
+    /// regex that precedes them.
+    ///
+    /// Every match of the regex must contain an inner literal, and the part
+    /// preceding the literal can never match the first byte of any of them.
+    /// So if the leftmost literal occurrence is preceded by a match of that
+    /// part, then the earliest start of such a match is at or before the
+    /// start of the leftmost match of the regex. (A match that contains a
+    /// later literal occurrence has to start after this one.) Otherwise, no
+    /// match contains this occurrence and we try the next one, without ever
+    /// scanning backwards past a previous occurrence.
+    ///
+    /// Note that the position returned is not necessarily the start of a
+    /// match, since the rest of the regex may not match after the literal.
+    /// But no match starts before it, so a forward search from it finds the
+    /// leftmost-first match.
+    ///
+    /// If the result returned indicates that the DFA quit, then another
+    /// matching engine should be used.
+    #[cfg(all(feature = "perf-dfa", feature = "perf-literal"))]
+    #[cfg_attr(feature = "perf-inline", inline(always))]
+    fn exec_dfa_reverse_inner(
+        &self,
+        text: &[u8],
+        original_start: usize,
+    ) -> dfa::Result<usize> {
+        use crate::dfa::Result::*;
+
+        let mut min = original_start;
+        let mut at = original_start;
+        loop {
+            let lit_start = match self.ro.inner.find(&text[at..]) {
+                None => return NoMatch(text.len()),
+                Some((s, _)) => at + s,
+            };
+            match dfa::Fsm::reverse_inner(
+                &self.ro.dfa_reverse_inner,
+                self.cache.value(),
+                false,
+                text,
+                min,
+                lit_start,
+            ) {
+                Match(s) => return Match(s),
+                NoMatch(_) => {
+                    min = lit_start + 1;
+                    at = lit_start + 1;
+                }
+                Quit => return Quit,
+            }
+        }
+    }
+
+    /// Finds the leftmost-first match (start and end) using only the DFA
+    /// by scanning for inner literals.
+    ///
+    /// If the result returned indicates that the DFA quit, then another
+    /// matching engine should be used.
+    #[cfg(all(feature = "perf-dfa", feature = "perf-literal"))]
+    #[cfg_attr(feature = "perf-inline", inline(always))]
+    fn find_dfa_reverse_inner(
+        &self,
+        text: &[u8],
+        start: usize,
+    ) -> dfa::Result<(usize, usize)> {
+        match self.exec_dfa_reverse_inner(text, start) {
+            dfa::Result::Match(s) => self.find_dfa_forward(text, s),
+            dfa::Result::NoMatch(i) => dfa::Result::NoMatch(i),
+            dfa::Result::Quit => dfa::Result::Quit,
+        }
+    }
+
     /// Executes the NFA engine to return whether there is a match or not.
     ///
     /// Ideally, we could use shortest_nfa(...).is_some() and get the same
@@ -917,6 +1260,7 @@ struct Parsed {
             &mut [],
             true,
             false,
+            false,
             text,
             start,
             text.len(),
@@ -943,6 +1287,7 @@ struct Parsed {
             &mut slots,
             true,
             true,
+            false,
             text,
             start,
             text.len(),
@@ -967,6 +1312,7 @@ struct Parsed {
             &mut slots,
             false,
             false,
+            false,
             text,
             start,
             text.len(),
@@ -1014,6 +1360,77 @@ struct Parsed {
             slots,
             false,
             false,
+            false,
+            text,
+            start,
+            end,
+        ) {
+            match (slots[0], slots[1]) {
+                (Some(s), Some(e)) => Some((s, e)),
+                _ => None,
+            }
+        } else {
+            None
+        }
+    }
+
+    /// Like captures_nfa_type, but only reports a match that begins at
+    /// `start`.
+    fn captures_nfa_anchored(
+        &self,
+        ty: MatchNfaType,
+        slots: &mut [Slot],
+        text: &[u8],
+        start: usize,
+    ) -> Option<(usize, usize)> {
+        if self.exec_nfa(
+            ty,
+            &mut [false],
+            slots,
+            false,
+            false,
+            true,
+            text,
+            start,
+            text.len(),
+        ) {
+            match (slots[0], slots[1]) {
+                (Some(s), Some(e)) => Some((s, e)),
+                _ => None,
+            }
+        } else {
+            None
+        }
+    }
+
+    /// Like find_anchored_at, but executes an NFA engine.
+    fn find_nfa_anchored(
+        &self,
+        ty: MatchNfaType,
+        text: &[u8],
+        start: usize,
+    ) -> Option<(usize, usize)> {
+        self.captures_nfa_anchored(ty, &mut [None, None], text, start)
+    }
+
+    /// Like rfind_at, but executes the PikeVM, and only finds matches that
+    /// begin at or after `start`.
+    ///
+    /// The backtracker stops at the first match it finds, so it can't be
+    /// used to find the last one.
+    fn rfind_nfa(
+        &self,
+        text: &[u8],
+        start: usize,
+        end: usize,
+    ) -> Option<(usize, usize)> {
+        let mut slots = [None, None];
+        if self.exec_pikevm(
+            &mut [false],
+            &mut slots,
+            false,
+            false,
+            true,
             text,
             start,
             end,
@@ -1034,6 +1451,7 @@ struct Parsed {
         slots: &mut [Slot],
         quit_after_match: bool,
         quit_after_match_with_pos: bool,
+        anchored: bool,
         text: &[u8],
         start: usize,
         end: usize,
@@ -1054,12 +1472,14 @@ struct Parsed {
                 matches,
                 slots,
                 quit_after_match,
+                anchored,
+                false,
                 text,
                 start,
                 end,
             )
         } else {
-            self.exec_backtrack(matches, slots, text, start, end)
+            self.exec_backtrack(matches, slots, anchored, text, start, end)
         }
     }
 
@@ -1069,6 +1489,8 @@ struct Parsed {
         matches: &mut [bool],
         slots: &mut [Slot],
         quit_after_match: bool,
+        anchored: bool,
+        last_match: bool,
         text: &[u8],
         start: usize,
         end: usize,
@@ -1080,6 +1502,8 @@ struct Parsed {
                 matches,
                 slots,
                 quit_after_match,
+                anchored,
+                last_match,
                 ByteInput::new(text, self.ro.nfa.only_utf8),
                 start,
                 end,
@@ -1091,6 +1515,8 @@ struct Parsed {
                 matches,
                 slots,
                 quit_after_match,
+                anchored,
+                last_match,
                 CharInput::new(text),
                 start,
                 end,
@@ -1103,6 +1529,7 @@ struct Parsed {
         &self,
         matches: &mut [bool],
         slots: &mut [Slot],
+        anchored: bool,
         text: &[u8],
         start: usize,
         end: usize,
@@ -1113,6 +1540,7 @@ struct Parsed {
                 self.cache.value(),
                 matches,
                 slots,
+                anchored,
                 ByteInput::new(text, self.ro.nfa.only_utf8),
                 start,
                 end,
@@ -1123,6 +1551,7 @@ struct Parsed {
                 self.cache.value(),
                 matches,
                 slots,
+                anchored,
                 CharInput::new(text),
                 start,
                 end,
@@ -1130,6 +1559,118 @@ struct Parsed {
         }
     }
 
+    /// Finds the leftmost-first match that begins exactly at `start`.
+    ///
+    /// Unlike `find_at`, matches beginning after `start` are never reported.
+    pub fn find_anchored_at(
+        &self,
+        text: &[u8],
+        start: usize,
+    ) -> Option<(usize, usize)> {
+        if !self.is_anchor_end_match(text) {
+            return None;
+        }
+        match self.ro.match_type {
+            #[cfg(feature = "perf-literal")]
+            MatchType::Literal(_) => {
+                self.find_nfa_anchored(MatchNfaType::Auto, text, start)
+            }
+            #[cfg(feature = "perf-dfa")]
+            MatchType::Dfa | MatchType::DfaAnchoredReverse => {
+                match self.find_dfa_anchored_forward(text, start) {
+                    dfa::Result::Match((s, e)) => Some((s, e)),
+                    dfa::Result::NoMatch(_) => None,
+                    dfa::Result::Quit => {
+                        self.find_nfa_anchored(MatchNfaType::Auto, text, start)
+                    }
+                }
+            }
+            #[cfg(all(feature = "perf-dfa", feature = "perf-literal"))]
+            MatchType::DfaSuffix | MatchType::DfaInner => {
+                match self.find_dfa_anchored_forward(text, start) {
+                    dfa::Result::Match((s, e)) => Some((s, e)),
+                    dfa::Result::NoMatch(_) => None,
+                    dfa::Result::Quit => {
+                        self.find_nfa_anchored(MatchNfaType::Auto, text, start)
+                    }
+                }
+            }
+            MatchType::Nfa(ty) => self.find_nfa_anchored(ty, text, start),
+            MatchType::Nothing => None,
+            #[cfg(feature = "perf-dfa")]
+            MatchType::DfaMany => {
+                unreachable!("BUG: RegexSet cannot be used with find")
+            }
+        }
+    }
+
+    /// Like `find_anchored_at`, but also fills in all matching capture
+    /// groups.
+    pub fn captures_read_anchored_at(
+        &self,
+        locs: &mut Locations,
+        text: &[u8],
+        start: usize,
+    ) -> Option<(usize, usize)> {
+        let slots = locs.as_slots();
+        for slot in slots.iter_mut() {
+            *slot = None;
+        }
+        match slots.len() {
+            0 => return self.find_anchored_at(text, start),
+            2 => {
+                return self.find_anchored_at(text, start).map(|(s, e)| {
+                    slots[0] = Some(s);
+                    slots[1] = Some(e);
+                    (s, e)
+                });
+            }
+            _ => {} // fallthrough
+        }
+        match self.ro.match_type {
+            MatchType::Nfa(ty) => {
+                if !self.is_anchor_end_match(text) {
+                    return None;
+                }
+                self.captures_nfa_anchored(ty, slots, text, start)
+            }
+            _ => self.find_anchored_at(text, start).and_then(|(s, e)| {
+                self.captures_nfa_type(MatchNfaType::Auto, slots, text, s, e)
+            }),
+        }
+    }
+
+    /// Finds the match that ends closest to, but not after, `end`. If more
+    /// than one match ends there, then the one that begins first is
+    /// returned.
+    ///
+    /// Assertions like `$` and `\b` still look at the text after `end`.
+    pub fn rfind_at(&self, text: &[u8], end: usize) -> Option<(usize, usize)> {
+        if !self.is_anchor_end_match(text) {
+            return None;
+        }
+        match self.ro.match_type {
+            #[cfg(all(feature = "perf-dfa", feature = "perf-literal"))]
+            MatchType::Literal(_) => self.rfind_dfa(text, end),
+            #[cfg(all(not(feature = "perf-dfa"), feature = "perf-literal"))]
+            MatchType::Literal(_) => self.rfind_nfa(text, 0, end),
+            #[cfg(feature = "perf-dfa")]
+            MatchType::Dfa | MatchType::DfaAnchoredReverse => {
+                self.rfind_dfa(text, end)
+            }
+            #[cfg(all(feature = "perf-dfa", feature = "perf-literal"))]
+            MatchType::DfaSuffix | MatchType::DfaInner => {
+                self.rfind_dfa(text, end)
+            }
+            MatchType::Nfa(_) => self.rfind_nfa(text, 0, end),
+            MatchType::Nothing => None,
+            #[cfg(feature = "perf-dfa")]
+            MatchType::DfaMany => {
+                unreachable!("BUG: RegexSet cannot be used with find")
+            }
+        }
+    }
+
     /// Finds which regular expressions match the given text.
     ///
     /// `matches` should have length equal to the number of regexes being
@@ -1171,6 +1712,7 @@ struct Parsed {
                         &mut [],
                         false,
                         false,
+                        false,
                         text,
                         start,
                         text.len(),
@@ -1178,7 +1720,7 @@ struct Parsed {
                 }
             }
             #[cfg(all(feature = "perf-dfa", feature = "perf-literal"))]
-            DfaSuffix => {
+            DfaSuffix | DfaInner => {
                 match dfa::Fsm::forward_many(
                     &self.ro.dfa,
                     self.cache.value(),
@@ -1194,6 +1736,7 @@ struct Parsed {
                         &mut [],
                         false,
                         false,
+                        false,
                         text,
                         start,
                         text.len(),
@@ -1206,6 +1749,7 @@ struct Parsed {
                 &mut [],
                 false,
                 false,
+                false,
                 text,
                 start,
                 text.len(),
@@ -1236,13 +1780,13 @@ struct Parsed {
         imp(&self.ro, text)
     }
 
-    pub fn capture_name_idx(&self) -> &Arc<HashMap<String, usize>> {
+    pub fn capture_name_idx(&self) -> &Arc<CaptureNameMap> {
         &self.ro.nfa.capture_name_idx
     }
 }
 
 #[cfg_attr(test, ::mutagen::mutate)] impl<'c> ExecNoSyncStr<'c> {
-    pub fn capture_name_idx(&self) -> &Arc<HashMap<String, usize>> {
+    pub fn capture_name_idx(&self) -> &Arc<CaptureNameMap> {
         self.0.capture_name_idx()
     }
 }
@@ -1264,6 +1808,38 @@ struct Parsed {
         ExecNoSyncStr(self.searcher())
     }
 
+    /// Create a new cache for use with `searcher_with_cache`.
+    pub fn create_cache(&self) -> Cache {
+        Cache {
+            ro: self.ro.clone(),
+            cache: RefCell::new(ProgramCacheInner::new(&self.ro)),
+        }
+    }
+
+    /// Get a searcher that uses the given cache instead of the internal one.
+    ///
+    /// If the cache was created for a different regex, then it is reset
+    /// first.
+    #[cfg_attr(feature = "perf-inline", inline(always))]
+    pub fn searcher_with_cache<'c>(
+        &'c self,
+        cache: &'c mut Cache,
+    ) -> ExecNoSync<'c> {
+        if !Arc::ptr_eq(&cache.ro, &self.ro) {
+            *cache = self.create_cache();
+        }
+        ExecNoSync { ro: &self.ro, cache: CachedGuard::borrowed(&cache.cache) }
+    }
+
+    /// Like `searcher_with_cache`, but the searcher can match on &str.
+    #[cfg_attr(feature = "perf-inline", inline(always))]
+    pub fn searcher_str_with_cache<'c>(
+        &'c self,
+        cache: &'c mut Cache,
+    ) -> ExecNoSyncStr<'c> {
+        ExecNoSyncStr(self.searcher_with_cache(cache))
+    }
+
     /// Build a Regex from this executor.
     pub fn into_regex(self) -> re_unicode::Regex {
         re_unicode::Regex::from(self)
@@ -1299,9 +1875,15 @@ struct Parsed {
 
     /// Return a reference to named groups mapping (from group name to
     /// group position).
-    pub fn capture_name_idx(&self) -> &Arc<HashMap<String, usize>> {
+    pub fn capture_name_idx(&self) -> &Arc<CaptureNameMap> {
         &self.ro.nfa.capture_name_idx
     }
+
+    /// Return the program run by the Pike VM and the backtracker.
+    #[doc(hidden)]
+    pub fn nfa(&self) -> &Program {
+        &self.ro.nfa
+    }
 }
 
 #[cfg_attr(test, ::mutagen::mutate)] impl Clone for Exec {
@@ -1310,6 +1892,31 @@ struct Parsed {
     }
 }
 
+#[cfg_attr(test, ::mutagen::mutate)] impl Cache {
+    /// Returns the approximate heap memory, in bytes, used by this cache.
+    ///
+    /// Scratch space is allocated lazily and grows as searches need it. The
+    /// largest share usually belongs to the lazy DFA, whose growth is bounded
+    /// by the `dfa_size_limit` option on `RegexBuilder`.
+    pub fn memory_usage(&self) -> usize {
+        self.cache.borrow().memory_usage()
+    }
+
+    /// Releases all scratch space held by this cache.
+    ///
+    /// The cache remains tied to the same regex and can be used again right
+    /// away. Scratch space is then allocated again as needed.
+    pub fn clear(&mut self) {
+        self.cache = RefCell::new(ProgramCacheInner::new(&self.ro));
+    }
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl fmt::Debug for Cache {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Cache").finish()
+    }
+}
+
 #[cfg_attr(test, ::mutagen::mutate)] impl ExecReadOnly {
     fn choose_match_type(&self, hint: Option<MatchType>) -> MatchType {
         if let Some(MatchType::Nfa(_)) = hint {
@@ -1404,6 +2011,11 @@ struct Parsed {
             }
             #[cfg(feature = "perf-literal")]
             {
+                // If there's a required inner literal that is longer than
+                // any prefix or suffix literal, then look for it first.
+                if ro.should_inner_scan() {
+                    return Some(MatchType::DfaInner);
+                }
                 // If there's a longish suffix literal, then it might be faster
                 // to look for that first.
                 if ro.should_suffix_scan() {
@@ -1439,6 +2051,30 @@ struct Parsed {
         let lcs_len = self.suffixes.lcs().char_len();
         lcs_len >= 3 && lcs_len > self.dfa.prefixes.lcp().char_len()
     }
+
+    /// Returns true if the program is amenable to inner literal scanning.
+    ///
+    /// When this is true, we scan for inner literals and run the reverse DFA
+    /// of the part of the regex preceding them to find where to start a
+    /// normal forward DFA search. This helps regexes like `\w+@example\.com`
+    /// that have neither prefix nor suffix literals.
+    ///
+    /// As with suffix scanning, we use literal length as a proxy for how rare
+    /// the literals are, so this is only used when the shortest inner literal
+    /// is longer than the prefix and suffix literals.
+    #[cfg(all(feature = "perf-dfa", feature = "perf-literal"))]
+    fn should_inner_scan(&self) -> bool {
+        if self.inner.is_empty()
+            || self.nfa.is_anchored_start
+            || !dfa::can_exec(&self.dfa_reverse_inner)
+        {
+            return false;
+        }
+        let len = self.inner.iter().map(|lit| lit.len()).min().unwrap_or(0);
+        len >= 3
+            && len > self.dfa.prefixes.lcp().len()
+            && len > self.suffixes.lcs().len()
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -1456,6 +2092,10 @@ enum MatchType {
     /// A reverse DFA search with suffix literal scanning.
     #[cfg(all(feature = "perf-dfa", feature = "perf-literal"))]
     DfaSuffix,
+    /// A reverse DFA search of the part of the regex preceding an inner
+    /// literal, followed by a forward DFA search.
+    #[cfg(all(feature = "perf-dfa", feature = "perf-literal"))]
+    DfaInner,
     /// Use the DFA on two or more regular expressions.
     #[cfg(feature = "perf-dfa")]
     DfaMany,
@@ -1506,7 +2146,13 @@ pub struct ProgramCacheInner {
     #[cfg(feature = "perf-dfa")]
     pub dfa: dfa::Cache,
     #[cfg(feature = "perf-dfa")]
+    pub dfa_anchored: dfa::Cache,
+    #[cfg(feature = "perf-dfa")]
     pub dfa_reverse: dfa::Cache,
+    #[cfg(feature = "perf-dfa")]
+    pub dfa_reverse_inner: dfa::Cache,
+    #[cfg(feature = "perf-dfa")]
+    pub dfa_reverse_unanchored: dfa::Cache,
 }
 
 #[cfg_attr(test, ::mutagen::mutate)] impl ProgramCacheInner {
@@ -1517,9 +2163,37 @@ pub struct ProgramCacheInner {
             #[cfg(feature = "perf-dfa")]
             dfa: dfa::Cache::new(&ro.dfa),
             #[cfg(feature = "perf-dfa")]
+            dfa_anchored: dfa::Cache::new(&ro.dfa),
+            #[cfg(feature = "perf-dfa")]
             dfa_reverse: dfa::Cache::new(&ro.dfa_reverse),
+            #[cfg(feature = "perf-dfa")]
+            dfa_reverse_inner: dfa::Cache::new(&ro.dfa_reverse_inner),
+            #[cfg(feature = "perf-dfa")]
+            dfa_reverse_unanchored: dfa::Cache::new(
+                &ro.dfa_reverse_unanchored,
+            ),
         }
     }
+
+    fn memory_usage(&self) -> usize {
+        self.pikevm.memory_usage()
+            + self.backtrack.memory_usage()
+            + self.dfa_memory_usage()
+    }
+
+    #[cfg(feature = "perf-dfa")]
+    fn dfa_memory_usage(&self) -> usize {
+        self.dfa.memory_usage()
+            + self.dfa_anchored.memory_usage()
+            + self.dfa_reverse.memory_usage()
+            + self.dfa_reverse_inner.memory_usage()
+            + self.dfa_reverse_unanchored.memory_usage()
+    }
+
+    #[cfg(not(feature = "perf-dfa"))]
+    fn dfa_memory_usage(&self) -> usize {
+        0
+    }
 }
 
 /// Alternation literals checks if the given HIR is a simple alternation of
@@ -1631,4 +2305,23 @@ mod test {
             assert_eq!(chunk1, chunk2);
         }
     }
+
+    #[test]
+    #[cfg(all(feature = "perf-dfa", feature = "perf-literal"))]
+    fn inner_literal_match_type() {
+        use super::MatchType;
+        use crate::internal::ExecBuilder;
+
+        let exec =
+            ExecBuilder::new(r"[a-z]+@example\.com[ \t]").build().unwrap();
+        match exec.ro.match_type {
+            MatchType::DfaInner => {}
+            ty => panic!("expected inner literal scan, got {:?}", ty),
+        }
+        let re = exec.into_regex();
+        assert_eq!(
+            re.find("mail bob@example.com now").map(|m| (m.start(), m.end())),
+            Some((5, 21))
+        );
+    }
 }
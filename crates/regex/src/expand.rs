@@ -1,4 +1,6 @@
-use std::str;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::str;
 
 use crate::find_byte::find_byte;
 
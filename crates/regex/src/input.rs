@@ -1,8 +1,8 @@
-use std::char;
-use std::cmp::Ordering;
-use std::fmt;
-use std::ops;
-use std::u32;
+use core::char;
+use core::cmp::Ordering;
+use core::fmt;
+use core::ops;
+use core::u32;
 
 use crate::syntax;
 
@@ -203,6 +203,14 @@ pub struct CharInput<'t>(&'t [u8]);
                 let (c1, c2) = (self.previous_char(at), self.next_char(at));
                 c1.is_word_byte() == c2.is_word_byte()
             }
+            WordBoundarySegment => {
+                syntax::try_is_word_boundary(self.as_bytes(), at.pos())
+                    .expect("Unicode text segmentation is available")
+            }
+            NotWordBoundarySegment => {
+                !syntax::try_is_word_boundary(self.as_bytes(), at.pos())
+                    .expect("Unicode text segmentation is available")
+            }
         }
     }
 
@@ -282,10 +290,30 @@ pub struct ByteInput<'t> {
             EndText => at.pos() == self.len(),
             WordBoundary => {
                 let (c1, c2) = (self.previous_char(at), self.next_char(at));
+                if self.only_utf8 {
+                    // If we must match UTF-8, then we can't match word
+                    // boundaries at invalid UTF-8.
+                    if c1.is_none() && !at.is_start() {
+                        return false;
+                    }
+                    if c2.is_none() && !at.is_end() {
+                        return false;
+                    }
+                }
                 c1.is_word_char() != c2.is_word_char()
             }
             NotWordBoundary => {
                 let (c1, c2) = (self.previous_char(at), self.next_char(at));
+                if self.only_utf8 {
+                    // If we must match UTF-8, then we can't match word
+                    // boundaries at invalid UTF-8.
+                    if c1.is_none() && !at.is_start() {
+                        return false;
+                    }
+                    if c2.is_none() && !at.is_end() {
+                        return false;
+                    }
+                }
                 c1.is_word_char() == c2.is_word_char()
             }
             WordBoundaryAscii => {
@@ -316,6 +344,23 @@ pub struct ByteInput<'t> {
                 }
                 c1.is_word_byte() == c2.is_word_byte()
             }
+            WordBoundarySegment | NotWordBoundarySegment => {
+                let (c1, c2) = (self.previous_char(at), self.next_char(at));
+                if self.only_utf8 {
+                    // If we must match UTF-8, then we can't match word
+                    // boundaries at invalid UTF-8.
+                    if c1.is_none() && !at.is_start() {
+                        return false;
+                    }
+                    if c2.is_none() && !at.is_end() {
+                        return false;
+                    }
+                }
+                let is_boundary =
+                    syntax::try_is_word_boundary(self.as_bytes(), at.pos())
+                        .expect("Unicode text segmentation is available");
+                is_boundary == (empty.look == WordBoundarySegment)
+            }
         }
     }
 
@@ -296,6 +296,7 @@ a separate crate, [`regex-syntax`](https://docs.rs/regex-syntax).
 \p{Greek}     Unicode character class (general category or script)
 \PN           Negated one-letter name Unicode character class
 \P{Greek}     negated Unicode character class (general category or script)
+\X            extended grapheme cluster (a user-perceived character, per UAX#29)
 </pre>
 
 ### Character classes
@@ -352,12 +353,14 @@ x{n}?     exactly n x
 ## Empty matches
 
 <pre class="rust">
-^     the beginning of text (or start-of-line with multi-line mode)
-$     the end of text (or end-of-line with multi-line mode)
-\A    only the beginning of text (even with multi-line mode enabled)
-\z    only the end of text (even with multi-line mode enabled)
-\b    a Unicode word boundary (\w on one side and \W, \A, or \z on other)
-\B    not a Unicode word boundary
+^      the beginning of text (or start-of-line with multi-line mode)
+$      the end of text (or end-of-line with multi-line mode)
+\A     only the beginning of text (even with multi-line mode enabled)
+\z     only the end of text (even with multi-line mode enabled)
+\b     a Unicode word boundary (\w on one side and \W, \A, or \z on other)
+\B     not a Unicode word boundary
+\b{wb} a Unicode word boundary as defined by UAX#29 (e.g., can't is one word)
+\B{wb} not a Unicode word boundary as defined by UAX#29
 </pre>
 
 ## Grouping and flags
@@ -512,9 +515,14 @@ All features below are enabled by default.
 ### Ecosystem features
 
 * **std** -
-  When enabled, this will cause `regex` to use the standard library. Currently,
-  disabling this feature will always result in a compilation error. It is
-  intended to add `alloc`-only support to regex in the future.
+  When enabled, this will cause `regex` to use the standard library. When
+  disabled, `regex` only depends on `core` and `alloc`. In that configuration,
+  there is no way to safely share scratch space between threads, so search
+  methods that don't take an explicit [`Cache`](struct.Cache.html) allocate
+  fresh scratch space on every call. Callers should create a cache with
+  `Regex::create_cache` and use the `_with_cache` search methods instead.
+  Moreover, `Error` no longer implements `std::error::Error`. The
+  `perf-cache` and `perf-literal` features require this feature.
 
 ### Performance features
 
@@ -526,7 +534,7 @@ All features below are enabled by default.
   Enables the use of very fast thread safe caching for internal match state.
   When this is disabled, caching is still used, but with a slower and simpler
   implementation. Disabling this drops the `thread_local` and `lazy_static`
-  dependencies.
+  dependencies. This feature requires the `std` feature.
 * **perf-dfa** -
   Enables the use of a lazy DFA for matching. The lazy DFA is used to compile
   portions of a regex to a very fast DFA on an as-needed basis. This can
@@ -541,6 +549,7 @@ All features below are enabled by default.
   Enables the use of literal optimizations for speeding up matches. In some
   cases, literal optimizations can result in speedups of _several_ orders of
   magnitude. Disabling this drops the `aho-corasick` and `memchr` dependencies.
+  This feature requires the `std` feature.
 
 ### Unicode features
 
@@ -579,7 +588,8 @@ All features below are enabled by default.
   Provide the data necessary to provide the properties used to implement the
   [Unicode text segmentation algorithms](https://www.unicode.org/reports/tr29/).
   This enables using classes like `\p{gcb=Extend}`, `\p{wb=Katakana}` and
-  `\p{sb=ATerm}`.
+  `\p{sb=ATerm}`. Together with `unicode-bool`, this also enables extended
+  grapheme clusters (`\X`) and UAX#29 word boundaries (`\b{wb}`).
 
 
 # Untrusted input
@@ -615,9 +625,10 @@ another matching engine with fixed memory requirements.
 #![deny(missing_docs)]
 //#![cfg_attr(test, deny(warnings))]
 #![cfg_attr(feature = "pattern", feature(pattern))]
+#![cfg_attr(not(feature = "std"), no_std)]
 
-#[cfg(not(feature = "std"))]
-compile_error!("`std` feature is currently required to build this crate");
+#[cfg_attr(not(feature = "std"), macro_use)]
+extern crate alloc;
 
 #[cfg(feature = "perf-literal")]
 extern crate aho_corasick;
@@ -635,16 +646,12 @@ extern crate thread_local;
 #[cfg(test)]
 doc_comment::doctest!("../README.md");
 
-#[cfg(feature = "std")]
-pub use crate::error::Error;
-#[cfg(feature = "std")]
+pub use crate::error::{Error, Suggestion, SyntaxError};
+pub use crate::exec::Cache;
 pub use crate::re_builder::set_unicode::*;
-#[cfg(feature = "std")]
 pub use crate::re_builder::unicode::*;
-#[cfg(feature = "std")]
+pub use crate::re_builder::Dialect;
 pub use crate::re_set::unicode::*;
-#[cfg(feature = "std")]
-#[cfg(feature = "std")]
 pub use crate::re_unicode::{
     escape, CaptureLocations, CaptureMatches, CaptureNames, Captures,
     Locations, Match, Matches, NoExpand, Regex, Replacer, ReplacerRef, Split,
@@ -738,8 +745,8 @@ When the `s` flag is enabled, `.` matches any byte.
 In general, one should expect performance on `&[u8]` to be roughly similar to
 performance on `&str`.
 */
-#[cfg(feature = "std")]
 pub mod bytes {
+    pub use crate::exec::Cache;
     pub use crate::re_builder::bytes::*;
     pub use crate::re_builder::set_bytes::*;
     pub use crate::re_bytes::*;
@@ -775,11 +782,11 @@ mod utf8;
 /// testing different matching engines and supporting the `regex-debug` CLI
 /// utility.
 #[doc(hidden)]
-#[cfg(feature = "std")]
 pub mod internal {
     pub use crate::compile::Compiler;
     pub use crate::exec::{Exec, ExecBuilder};
-    pub use crate::input::{Char, CharInput, Input, InputAt};
+    pub use crate::input::{ByteInput, Char, CharInput, Input, InputAt};
     pub use crate::literal::LiteralSearcher;
+    pub use crate::pikevm::{trace, Trace};
     pub use crate::prog::{EmptyLook, Inst, InstRanges, Program};
 }
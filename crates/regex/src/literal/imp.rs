@@ -1,5 +1,5 @@
-use std::cmp;
-use std::mem;
+use core::cmp;
+use core::mem;
 
 use aho_corasick::{self, packed, AhoCorasick, AhoCorasickBuilder};
 use memchr::{memchr, memchr2, memchr3};
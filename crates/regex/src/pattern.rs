@@ -1,4 +1,4 @@
-use std::str::pattern::{Pattern, SearchStep, Searcher};
+use core::str::pattern::{Pattern, SearchStep, Searcher};
 
 use re_unicode::{Matches, Regex};
 
@@ -15,7 +15,9 @@
 // Therefore, the Pike VM is generally treated as the fallback when the other
 // matching engines either aren't feasible to run or are insufficient.
 
-use std::mem;
+use alloc::vec::Vec;
+use core::fmt;
+use core::mem;
 
 use crate::exec::ProgramCache;
 use crate::input::{Input, InputAt};
@@ -24,7 +26,6 @@ use crate::re_trait::Slot;
 use crate::sparse::SparseSet;
 
 /// An NFA simulation matching engine.
-#[derive(Debug)]
 pub struct Fsm<'r, I> {
     /// The sequence of opcodes (among other things) that is actually executed.
     ///
@@ -35,6 +36,33 @@ pub struct Fsm<'r, I> {
     stack: &'r mut Vec<FollowEpsilon>,
     /// The input to search.
     input: I,
+    /// Called at every step of the search, if set. This is only used by
+    /// `trace`.
+    tracer: Option<&'r mut dyn FnMut(Trace)>,
+}
+
+/// An event in a search by the Pike VM, reported by `trace`.
+#[derive(Debug)]
+pub enum Trace<'a> {
+    /// The threads of the Pike VM right before it steps over the input.
+    Step {
+        /// The position in the input.
+        at: usize,
+        /// The threads, in priority order. Each thread is an instruction
+        /// along with its capture slots. Only the instructions that consume
+        /// input or match are included, since the other instructions are
+        /// only kept to mark them as visited.
+        threads: Vec<(InstPtr, &'a [Slot])>,
+    },
+    /// A thread reached a `Match` instruction.
+    Match {
+        /// The position in the input at which the match ends.
+        at: usize,
+        /// The `Match` instruction.
+        pc: InstPtr,
+        /// The capture slots reported for the match.
+        slots: &'a [Slot],
+    },
 }
 
 /// A cached allocation that can be reused on each execution.
@@ -78,6 +106,60 @@ enum FollowEpsilon {
     pub fn new(_prog: &Program) -> Self {
         Cache { clist: Threads::new(), nlist: Threads::new(), stack: vec![] }
     }
+
+    /// Returns the approximate heap memory, in bytes, used by this cache.
+    pub fn memory_usage(&self) -> usize {
+        self.clist.memory_usage()
+            + self.nlist.memory_usage()
+            + (self.stack.capacity() * mem::size_of::<FollowEpsilon>())
+    }
+}
+
+/// Search for the leftmost-first match of a single regex in `input` with the
+/// Pike VM, calling `tracer` at every step of the search.
+///
+/// The search is the same one that a `Regex` runs when it uses the Pike VM
+/// to find captures, including skipping ahead with literal prefixes. It
+/// returns true if and only if there's a match, in which case `slots` are
+/// set to its capture slots.
+#[cfg_attr(test, ::mutagen::mutate)] pub fn trace<I: Input>(
+    prog: &Program,
+    input: I,
+    slots: &mut [Slot],
+    tracer: &mut dyn FnMut(Trace),
+) -> bool {
+    let mut cache = Cache::new(prog);
+    cache.clist.resize(prog.len(), prog.captures.len());
+    cache.nlist.resize(prog.len(), prog.captures.len());
+    let at = input.at(0);
+    let end = input.len();
+    Fsm {
+        prog: prog,
+        stack: &mut cache.stack,
+        input: input,
+        tracer: Some(tracer),
+    }
+    .exec_(
+        &mut cache.clist,
+        &mut cache.nlist,
+        &mut [false],
+        slots,
+        false,
+        false,
+        false,
+        at,
+        end,
+    )
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<'r, I: fmt::Debug> fmt::Debug for Fsm<'r, I> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Fsm")
+            .field("prog", &self.prog)
+            .field("stack", &self.stack)
+            .field("input", &self.input)
+            .finish()
+    }
 }
 
 #[cfg_attr(test, ::mutagen::mutate)] impl<'r, I: Input> Fsm<'r, I> {
@@ -85,12 +167,18 @@ enum FollowEpsilon {
     ///
     /// If there's a match, `exec` returns `true` and populates the given
     /// captures accordingly.
+    ///
+    /// When `anchored` is true, only a match beginning at `start` is
+    /// reported. When `last_match` is true, the match reported is the one
+    /// ending closest to `end`, and among those, the one that begins first.
     pub fn exec(
         prog: &'r Program,
         cache: &ProgramCache,
         matches: &mut [bool],
         slots: &mut [Slot],
         quit_after_match: bool,
+        anchored: bool,
+        last_match: bool,
         input: I,
         start: usize,
         end: usize,
@@ -100,12 +188,15 @@ enum FollowEpsilon {
         cache.clist.resize(prog.len(), prog.captures.len());
         cache.nlist.resize(prog.len(), prog.captures.len());
         let at = input.at(start);
-        Fsm { prog: prog, stack: &mut cache.stack, input: input }.exec_(
+        Fsm { prog: prog, stack: &mut cache.stack, input: input, tracer: None }
+            .exec_(
             &mut cache.clist,
             &mut cache.nlist,
             matches,
             slots,
             quit_after_match,
+            anchored,
+            last_match,
             at,
             end,
         )
@@ -118,9 +209,12 @@ enum FollowEpsilon {
         matches: &mut [bool],
         slots: &mut [Slot],
         quit_after_match: bool,
+        anchored: bool,
+        last_match: bool,
         mut at: InputAt,
         end: usize,
     ) -> bool {
+        let start = at.pos();
         let mut matched = false;
         let mut all_matched = false;
         clist.set.clear();
@@ -135,11 +229,16 @@ enum FollowEpsilon {
                 //    looking for matches for multiple regexes, unless we know
                 //    they all matched.)
                 //
-                // 2. If the expression starts with a '^' we can terminate as
-                //    soon as the last thread dies.
-                if (matched && matches.len() <= 1)
+                //    When looking for the last match, there may always be
+                //    another one further along, so this doesn't apply.
+                //
+                // 2. If the expression starts with a '^' or the search is
+                //    anchored, we can terminate as soon as the last thread
+                //    dies.
+                if (matched && matches.len() <= 1 && !last_match)
                     || all_matched
                     || (!at.is_start() && self.prog.is_anchored_start)
+                    || (anchored && at.pos() > start)
                 {
                     break;
                 }
@@ -147,7 +246,7 @@ enum FollowEpsilon {
                 // 3. If there's a literal prefix for the program, try to
                 //    jump ahead quickly. If it can't be found, then we can
                 //    bail out early.
-                if !self.prog.prefixes.is_empty() {
+                if !anchored && !self.prog.prefixes.is_empty() {
                     at = match self.input.prefix_at(&self.prog.prefixes, at) {
                         None => break,
                         Some(at) => at,
@@ -159,10 +258,13 @@ enum FollowEpsilon {
             // a state starting at the current position in the input for the
             // beginning of the program only if we don't already have a match.
             if clist.set.is_empty()
-                || (!self.prog.is_anchored_start && !all_matched)
+                || (!self.prog.is_anchored_start && !anchored && !all_matched)
             {
                 self.add(&mut clist, slots, 0, at);
             }
+            if self.tracer.is_some() {
+                self.trace_step(clist, at);
+            }
             // The previous call to "add" actually inspects the position just
             // before the current character. For stepping through the machine,
             // we can to look at the current character, so we advance the
@@ -180,8 +282,31 @@ enum FollowEpsilon {
                     at_next,
                 ) {
                     matched = true;
+                    if let Some(ref mut tracer) = self.tracer {
+                        tracer(Trace::Match {
+                            at: at.pos(),
+                            pc: ip,
+                            slots: slots,
+                        });
+                    }
+                    if last_match {
+                        // Lower priority threads may still lead to a match
+                        // that ends later, so keep all of them. Since there
+                        // is only one thread per instruction and threads
+                        // that began earlier come first, the match recorded
+                        // at each position is the one that begins first.
+                        continue;
+                    }
                     all_matched = all_matched || matches.iter().all(|&b| b);
                     if quit_after_match {
+                        // Regex sets are compiled without capture groups, so
+                        // record the end of the match explicitly in case the
+                        // caller wants to know where it is.
+                        if self.prog.matches.len() > 1 {
+                            if let Some(end) = slots.get_mut(1) {
+                                *end = Some(at.pos());
+                            }
+                        }
                         // If we only care if a match occurs (not its
                         // position), then we can quit right now.
                         break 'LOOP;
@@ -209,6 +334,24 @@ enum FollowEpsilon {
         matched
     }
 
+    /// Report the threads in `clist` to the tracer.
+    fn trace_step(&mut self, clist: &Threads, at: InputAt) {
+        use crate::prog::Inst::*;
+        let prog = self.prog;
+        let threads = clist
+            .set
+            .iter()
+            .filter(|&&ip| match prog[ip] {
+                Match(_) | Char(_) | Ranges(_) | Bytes(_) => true,
+                EmptyLook(_) | Save(_) | Split(_) => false,
+            })
+            .map(|&ip| (ip, clist.thread_caps(ip)))
+            .collect();
+        if let Some(ref mut tracer) = self.tracer {
+            tracer(Trace::Step { at: at.pos(), threads: threads });
+        }
+    }
+
     /// Step through the input, one token (byte or codepoint) at a time.
     ///
     /// nlist is the set of states that will be processed on the next token
@@ -353,6 +496,16 @@ enum FollowEpsilon {
         self.caps = vec![None; self.slots_per_thread * num_insts];
     }
 
+    fn memory_usage(&self) -> usize {
+        (self.set.capacity() * 2 * mem::size_of::<usize>())
+            + (self.caps.capacity() * mem::size_of::<Slot>())
+    }
+
+    fn thread_caps(&self, pc: usize) -> &[Option<usize>] {
+        let i = pc * self.slots_per_thread;
+        &self.caps[i..i + self.slots_per_thread]
+    }
+
     fn caps(&mut self, pc: usize) -> &mut [Option<usize>] {
         let i = pc * self.slots_per_thread;
         &mut self.caps[i..i + self.slots_per_thread]
@@ -1,10 +1,11 @@
-use std::cmp::Ordering;
-use std::collections::HashMap;
-use std::fmt;
-use std::mem;
-use std::ops::Deref;
-use std::slice;
-use std::sync::Arc;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::mem;
+use core::ops::Deref;
+use core::slice;
 
 use crate::input::Char;
 use crate::literal::LiteralSearcher;
@@ -12,6 +13,15 @@ use crate::literal::LiteralSearcher;
 /// `InstPtr` represents the index of an instruction in a regex program.
 pub type InstPtr = usize;
 
+/// `CaptureNameMap` maps the names of capture groups to their indices.
+///
+/// Without the standard library there is no `HashMap`, so an ordered map is
+/// used instead.
+#[cfg(feature = "std")]
+pub type CaptureNameMap = std::collections::HashMap<String, usize>;
+#[cfg(not(feature = "std"))]
+pub type CaptureNameMap = alloc::collections::BTreeMap<String, usize>;
+
 /// Program is a sequence of instructions and various facts about thos
 /// instructions.
 #[derive(Clone)]
@@ -26,13 +36,16 @@ pub struct Program {
     /// Unnamed groups are `None`.
     pub captures: Vec<Option<String>>,
     /// Pointers to all named capture groups into `captures`.
-    pub capture_name_idx: Arc<HashMap<String, usize>>,
+    pub capture_name_idx: Arc<CaptureNameMap>,
     /// A pointer to the start instruction. This can vary depending on how
     /// the program was compiled. For example, programs for use with the DFA
     /// engine have a `.*?` inserted at the beginning of unanchored regular
     /// expressions. The actual starting point of the program is after the
     /// `.*?`.
     pub start: InstPtr,
+    /// A pointer to the first instruction of the regex itself, skipping any
+    /// `.*?` inserted before it. Anchored searches begin here.
+    pub anchored_start: InstPtr,
     /// A set of equivalence classes for discriminating bytes in the compiled
     /// program.
     pub byte_classes: Vec<u8>,
@@ -48,6 +61,10 @@ pub struct Program {
     /// When true, the program matches text in reverse (for use only in the
     /// DFA).
     pub is_reverse: bool,
+    /// When true, a reverse program for the DFA includes a preceding `.*?`
+    /// unless the regex is anchored at the end. It then finds matches ending
+    /// anywhere before the position a reverse search starts at.
+    pub is_reverse_unanchored: bool,
     /// Whether the regex must match from the start of the input.
     pub is_anchored_start: bool,
     /// Whether the regex must match at the end of the input.
@@ -82,13 +99,15 @@ pub struct Program {
             insts: vec![],
             matches: vec![],
             captures: vec![],
-            capture_name_idx: Arc::new(HashMap::new()),
+            capture_name_idx: Arc::new(CaptureNameMap::new()),
             start: 0,
+            anchored_start: 0,
             byte_classes: vec![0; 256],
             only_utf8: true,
             is_bytes: false,
             is_dfa: false,
             is_reverse: false,
+            is_reverse_unanchored: false,
             is_anchored_start: false,
             is_anchored_end: false,
             has_unicode_word_boundary: false,
@@ -126,7 +145,11 @@ pub struct Program {
     /// Returns true if the current configuration demands that an implicit
     /// `.*?` be prepended to the instruction sequence.
     pub fn needs_dotstar(&self) -> bool {
-        self.is_dfa && !self.is_reverse && !self.is_anchored_start
+        if self.is_reverse {
+            self.is_dfa && self.is_reverse_unanchored && !self.is_anchored_end
+        } else {
+            self.is_dfa && !self.is_anchored_start
+        }
     }
 
     /// Returns true if this program uses Byte instructions instead of
@@ -180,7 +203,7 @@ pub struct Program {
         }
 
         fn visible_byte(b: u8) -> String {
-            use std::ascii::escape_default;
+            use core::ascii::escape_default;
             let escaped = escape_default(b).collect::<Vec<u8>>();
             String::from_utf8_lossy(&escaped).into_owned()
         }
@@ -355,6 +378,10 @@ pub enum EmptyLook {
     WordBoundaryAscii,
     /// Not ASCII word boundary.
     NotWordBoundaryAscii,
+    /// Unicode word boundary as defined by UAX#29.
+    WordBoundarySegment,
+    /// Not Unicode word boundary as defined by UAX#29.
+    NotWordBoundarySegment,
 }
 
 /// Representation of the Char instruction.
@@ -1,3 +1,8 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::syntax;
+
 /// The set of user configurable options for compiling zero or more regexes.
 #[derive(Clone, Debug)]
 #[allow(missing_docs)]
@@ -13,6 +18,10 @@ pub struct RegexOptions {
     pub ignore_whitespace: bool,
     pub unicode: bool,
     pub octal: bool,
+    pub simplify: bool,
+    pub dialect: Dialect,
+    pub properties: Vec<(String, Vec<(char, char)>)>,
+    pub shard_size: usize,
 }
 
 #[cfg_attr(test, ::mutagen::mutate)] impl Default for RegexOptions {
@@ -29,14 +38,91 @@ pub struct RegexOptions {
             ignore_whitespace: false,
             unicode: true,
             octal: false,
+            simplify: false,
+            dialect: Dialect::Rust,
+            properties: vec![],
+            shard_size: 256,
+        }
+    }
+}
+
+/// The syntax that a pattern is written in.
+///
+/// Patterns written in a dialect other than `Rust` are translated into the
+/// syntax of this crate before they are compiled. Constructs that can't be
+/// translated, such as backreferences, look-around or PCRE's atomic groups,
+/// result in an error that points at the offending syntax.
+///
+/// Only the syntax is translated, not the semantics. In particular, matching
+/// is always leftmost-first (even for POSIX patterns, where POSIX mandates
+/// leftmost-longest), and `.`, `^` and `$` behave as documented by this
+/// crate.
+///
+/// A dialect is selected with the `dialect` method of a regex builder.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Dialect {
+    /// The syntax documented by this crate. This is the default.
+    Rust,
+    /// The syntax of PCRE, which is largely shared by Perl.
+    ///
+    /// This supports, for example, `(?<name>re)` and `(?'name're)` for named
+    /// groups, `\Q...\E` quoting, `(?#...)` comments, `\h`, `\v`, `\N` and
+    /// `\R`, `\e`, `\cX`, `\o{...}` and octal escapes, and `\p{^...}`
+    /// negated properties. A `{` that does not start a counted repetition is
+    /// a literal.
+    Pcre,
+    /// POSIX extended regular expressions, as used by `grep -E`.
+    ///
+    /// In bracket expressions, a `\` is a literal and `[=c=]` and `[.c.]`
+    /// are supported for single characters. The GNU extensions `\w`, `\W`,
+    /// `\s`, `\S`, `\b`, `\B`, `` \` `` and `\'` are supported as well.
+    PosixEre,
+    /// JavaScript (ECMAScript) regular expressions, without the surrounding
+    /// `/` delimiters and flags.
+    ///
+    /// `\d`, `\w` and `\s` are matched by their ASCII (or, for `\s`,
+    /// ECMAScript specific) definitions. `\uXXXX` (including surrogate
+    /// pairs), `\u{...}`, `(?<name>re)` and `[^]` are supported, as are the
+    /// legacy identity and octal escapes. Since this crate rejects empty
+    /// classes, `[]` results in an error.
+    JavaScript,
+    /// Hints that destructuring should not be exhaustive.
+    ///
+    /// This enum may grow additional variants, so this makes sure clients
+    /// don't count on exhaustive matching. (Otherwise, adding a new variant
+    /// could break existing code.)
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl Dialect {
+    /// Convert this to the `regex-syntax` type of the same name.
+    ///
+    /// This returns `None` for the hidden `__Nonexhaustive` variant, which
+    /// doesn't name a dialect.
+    pub(crate) fn to_syntax(self) -> Option<syntax::ast::Dialect> {
+        match self {
+            Dialect::Rust => Some(syntax::ast::Dialect::Rust),
+            Dialect::Pcre => Some(syntax::ast::Dialect::Pcre),
+            Dialect::PosixEre => Some(syntax::ast::Dialect::PosixEre),
+            Dialect::JavaScript => Some(syntax::ast::Dialect::JavaScript),
+            Dialect::__Nonexhaustive => None,
         }
     }
 }
 
+#[cfg_attr(test, ::mutagen::mutate)] impl Default for Dialect {
+    fn default() -> Dialect {
+        Dialect::Rust
+    }
+}
+
 macro_rules! define_builder {
     ($name:ident, $regex_mod:ident, $only_utf8:expr) => {
         pub mod $name {
-            use super::RegexOptions;
+            use alloc::borrow::ToOwned;
+
+            use super::{Dialect, RegexOptions};
             use crate::error::Error;
             use crate::exec::ExecBuilder;
 
@@ -222,6 +308,61 @@ macro_rules! define_builder {
                     self.0.nest_limit = limit;
                     self
                 }
+
+                /// Whether to simplify the regular expression before compiling it.
+                ///
+                /// When enabled, the parsed expression is rewritten into an
+                /// equivalent but smaller expression. For example, redundant
+                /// non-capturing groups are removed, `a|a` becomes `a`, `[a-c]|d`
+                /// becomes `[a-d]` and `abc|abd` becomes `ab(?:c|d)`. This can
+                /// make matching faster (and compiled programs smaller) at the cost
+                /// of a little extra work at construction time. Simplification
+                /// never changes what an expression matches, including the offsets
+                /// of capture groups.
+                ///
+                /// Simplification is disabled by default.
+                pub fn simplify(&mut self, yes: bool) -> &mut RegexBuilder {
+                    self.0.simplify = yes;
+                    self
+                }
+
+                /// Set the syntax that the pattern is written in.
+                ///
+                /// This makes it possible to use patterns written for other regex
+                /// engines, such as PCRE, POSIX extended regular expressions or
+                /// JavaScript, without rewriting them by hand. Patterns are
+                /// translated into this crate's syntax, and constructs with no
+                /// equivalent (like backreferences) result in an error. Only the
+                /// syntax is translated: matching semantics are always those of
+                /// this crate.
+                ///
+                /// The default is `Dialect::Rust`, the syntax documented in this
+                /// crate.
+                pub fn dialect(
+                    &mut self,
+                    dialect: Dialect,
+                ) -> &mut RegexBuilder {
+                    self.0.dialect = dialect;
+                    self
+                }
+
+                /// Add a custom Unicode property that can be used when the pattern is
+                /// Unicode-aware, e.g., `\p{OurIdentifierStart}`.
+                ///
+                /// The property is given as a list of inclusive ranges of
+                /// codepoints. Property names are matched loosely (ignoring case,
+                /// whitespace, `_` and `-`), and a custom property takes precedence
+                /// over a Unicode property with the same name. Custom properties are
+                /// available even when the Unicode data features of this crate are
+                /// disabled.
+                pub fn unicode_property(
+                    &mut self,
+                    name: &str,
+                    ranges: &[(char, char)],
+                ) -> &mut RegexBuilder {
+                    self.0.properties.push((name.to_owned(), ranges.to_vec()));
+                    self
+                }
             }
         }
     };
@@ -233,11 +374,13 @@ define_builder!(unicode, re_unicode, true);
 macro_rules! define_set_builder {
     ($name:ident, $regex_mod:ident, $only_utf8:expr) => {
         pub mod $name {
-            use super::RegexOptions;
+            use alloc::borrow::ToOwned;
+
+            use super::{Dialect, RegexOptions};
             use crate::error::Error;
             use crate::exec::ExecBuilder;
 
-            use crate::re_set::$regex_mod::RegexSet;
+            use crate::re_set::$regex_mod::{DynamicRegexSet, RegexSet};
 
             /// A configurable builder for a set of regular expressions.
             ///
@@ -271,6 +414,15 @@ macro_rules! define_set_builder {
                         .map(RegexSet::from)
                 }
 
+                /// Consume the builder and compile the regular expressions into a set
+                /// that patterns can be added to and removed from later.
+                ///
+                /// All patterns added later are compiled with the options set on this
+                /// builder.
+                pub fn build_dynamic(&self) -> Result<DynamicRegexSet, Error> {
+                    DynamicRegexSet::from_options(self.0.clone(), $only_utf8)
+                }
+
                 /// Set the value for the case insensitive (`i`) flag.
                 pub fn case_insensitive(
                     &mut self,
@@ -410,6 +562,86 @@ macro_rules! define_set_builder {
                     self.0.nest_limit = limit;
                     self
                 }
+
+                /// Whether to simplify the regular expression before compiling it.
+                ///
+                /// When enabled, the parsed expression is rewritten into an
+                /// equivalent but smaller expression. For example, redundant
+                /// non-capturing groups are removed, `a|a` becomes `a`, `[a-c]|d`
+                /// becomes `[a-d]` and `abc|abd` becomes `ab(?:c|d)`. This can
+                /// make matching faster (and compiled programs smaller) at the cost
+                /// of a little extra work at construction time. Simplification
+                /// never changes what an expression matches, including the offsets
+                /// of capture groups.
+                ///
+                /// Simplification is disabled by default.
+                pub fn simplify(
+                    &mut self,
+                    yes: bool,
+                ) -> &mut RegexSetBuilder {
+                    self.0.simplify = yes;
+                    self
+                }
+
+                /// Set the syntax that the patterns are written in.
+                ///
+                /// This makes it possible to use patterns written for other regex
+                /// engines, such as PCRE, POSIX extended regular expressions or
+                /// JavaScript, without rewriting them by hand. Patterns are
+                /// translated into this crate's syntax, and constructs with no
+                /// equivalent (like backreferences) result in an error. Only the
+                /// syntax is translated: matching semantics are always those of
+                /// this crate.
+                ///
+                /// The default is `Dialect::Rust`, the syntax documented in this
+                /// crate.
+                pub fn dialect(
+                    &mut self,
+                    dialect: Dialect,
+                ) -> &mut RegexSetBuilder {
+                    self.0.dialect = dialect;
+                    self
+                }
+
+                /// Add a custom Unicode property that can be used when the patterns are
+                /// Unicode-aware, e.g., `\p{OurIdentifierStart}`.
+                ///
+                /// The property is given as a list of inclusive ranges of
+                /// codepoints. Property names are matched loosely (ignoring case,
+                /// whitespace, `_` and `-`), and a custom property takes precedence
+                /// over a Unicode property with the same name. Custom properties are
+                /// available even when the Unicode data features of this crate are
+                /// disabled.
+                pub fn unicode_property(
+                    &mut self,
+                    name: &str,
+                    ranges: &[(char, char)],
+                ) -> &mut RegexSetBuilder {
+                    self.0.properties.push((name.to_owned(), ranges.to_vec()));
+                    self
+                }
+
+                /// Set the maximum number of patterns compiled together by a set
+                /// built with `build_dynamic`.
+                ///
+                /// A dynamic set is split into shards of at most this many patterns,
+                /// and adding or removing a pattern only recompiles its shard. Smaller
+                /// shards make changes cheaper, but every shard is searched in turn,
+                /// so they also make searching slower.
+                ///
+                /// The default is `256`.
+                ///
+                /// # Panics
+                ///
+                /// If `size` is `0`.
+                pub fn shard_size(
+                    &mut self,
+                    size: usize,
+                ) -> &mut RegexSetBuilder {
+                    assert!(size > 0, "shard size must be at least 1");
+                    self.0.shard_size = size;
+                    self
+                }
             }
         }
     };
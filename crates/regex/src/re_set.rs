@@ -2,14 +2,20 @@ macro_rules! define_set {
     ($name:ident, $builder_mod:ident, $text_ty:ty, $as_bytes:expr,
      $(#[$doc_regexset_example:meta])* ) => {
         pub mod $name {
-            use std::fmt;
-            use std::iter;
-            use std::slice;
-            use std::vec;
+            use alloc::borrow::ToOwned;
+            use alloc::collections::BTreeMap;
+            use alloc::string::String;
+            use alloc::vec::{self, Vec};
+            use core::cmp;
+            use core::fmt;
+            use core::mem;
+            use core::ops::Range;
+            use core::slice;
 
             use crate::error::Error;
-            use crate::exec::Exec;
+            use crate::exec::{Cache, Exec, ExecBuilder};
             use crate::re_builder::$builder_mod::RegexSetBuilder;
+            use crate::re_builder::RegexOptions;
             use crate::re_trait::RegularExpression;
 
 /// Match multiple (possibly overlapping) regular expressions in a single scan.
@@ -135,6 +141,40 @@ pub struct RegexSet(Exec);
         self.0.searcher().is_match_at($as_bytes(text), start)
     }
 
+    /// Returns the end location of a match of any regex in this set.
+    ///
+    /// As with `Regex::shortest_match`, the end location is the place at
+    /// which the regex engine determined that a match exists. It may occur
+    /// before the end of any proper leftmost-first match, and it says
+    /// nothing about which regex matched.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use regex::RegexSet;
+    /// let set = RegexSet::new(&[r"a+", r"[0-9]{3}"]).unwrap();
+    /// assert_eq!(set.shortest_match("xx123aaa"), Some(5));
+    /// assert_eq!(set.shortest_match("xyz"), None);
+    /// ```
+    pub fn shortest_match(&self, text: $text_ty) -> Option<usize> {
+        self.shortest_match_at(text, 0)
+    }
+
+    /// Returns the same as shortest_match, but starts the search at the
+    /// given offset.
+    ///
+    /// The significance of the starting point is that it takes the surrounding
+    /// context into consideration. For example, the `\A` anchor can only
+    /// match when `start == 0`.
+    #[doc(hidden)]
+    pub fn shortest_match_at(
+        &self,
+        text: $text_ty,
+        start: usize,
+    ) -> Option<usize> {
+        self.0.searcher().shortest_match_at($as_bytes(text), start)
+    }
+
     /// Returns the set of regular expressions that match in the given text.
     ///
     /// The set returned contains the index of each regular expression that
@@ -173,11 +213,8 @@ pub struct RegexSet(Exec);
     /// ```
     pub fn matches(&self, text: $text_ty) -> SetMatches {
         let mut matches = vec![false; self.0.regex_strings().len()];
-        let any = self.read_matches_at(&mut matches, text, 0);
-        SetMatches {
-            matched_any: any,
-            matches: matches,
-        }
+        self.read_matches_at(&mut matches, text, 0);
+        SetMatches::from_dense(&matches)
     }
 
     /// Returns the same as matches, but starts the search at the given
@@ -202,6 +239,42 @@ pub struct RegexSet(Exec);
         self.0.searcher().many_matches_at(matches, $as_bytes(text), start)
     }
 
+    /// Returns a new cache of scratch space for use with this set's
+    /// `_with_cache` search methods.
+    ///
+    /// See `Regex::create_cache` for when owning the cache is useful.
+    pub fn create_cache(&self) -> Cache {
+        self.0.create_cache()
+    }
+
+    /// Returns true if and only if one of the regexes in this set matches
+    /// the text given, using the given cache for scratch space.
+    ///
+    /// This is otherwise the same as `is_match`.
+    pub fn is_match_with_cache(
+        &self,
+        cache: &mut Cache,
+        text: $text_ty,
+    ) -> bool {
+        self.0.searcher_with_cache(cache).is_match_at($as_bytes(text), 0)
+    }
+
+    /// Returns the set of regular expressions that match in the given text,
+    /// using the given cache for scratch space.
+    ///
+    /// This is otherwise the same as `matches`.
+    pub fn matches_with_cache(
+        &self,
+        cache: &mut Cache,
+        text: $text_ty,
+    ) -> SetMatches {
+        let mut matches = vec![false; self.0.regex_strings().len()];
+        self.0
+            .searcher_with_cache(cache)
+            .many_matches_at(&mut matches, $as_bytes(text), 0);
+        SetMatches::from_dense(&matches)
+    }
+
     /// Returns the total number of regular expressions in this set.
     pub fn len(&self) -> usize {
         self.0.regex_strings().len()
@@ -239,17 +312,302 @@ pub struct RegexSet(Exec);
     }
 }
 
+/// A regex set that patterns can be added to and removed from after it is
+/// built.
+///
+/// Changing a `RegexSet` means compiling a new one from all of its patterns.
+/// A `DynamicRegexSet` instead splits its patterns into shards of a bounded
+/// size (see `RegexSetBuilder::shard_size`), and compiles each shard as its
+/// own `RegexSet`. Adding or removing a pattern only recompiles the shard
+/// that contains it. When removing a pattern leaves a shard small enough to
+/// be merged with a neighbor, the two are merged, so the number of shards
+/// stays proportional to the number of patterns.
+///
+/// Every pattern is identified by an ID that is assigned when it is added.
+/// The patterns given when the set is built get the IDs `0` through `n - 1`,
+/// and each pattern added after that gets the next unused ID. IDs are never
+/// reused, so removing a pattern doesn't change the ID of any other pattern.
+/// Matches are reported by ID.
+///
+/// # Example
+///
+/// ```rust
+/// # use regex::DynamicRegexSet;
+/// let mut set = DynamicRegexSet::new(&[r"^/admin", r"\.php$"]).unwrap();
+/// let id = set.add(r"^/wp-login").unwrap();
+/// assert_eq!(id, 2);
+///
+/// let matches: Vec<_> = set.matches("/wp-login.php").into_iter().collect();
+/// assert_eq!(matches, vec![1, 2]);
+///
+/// // Removing a pattern doesn't change the IDs of the others.
+/// set.remove(1);
+/// let matches: Vec<_> = set.matches("/wp-login.php").into_iter().collect();
+/// assert_eq!(matches, vec![2]);
+/// ```
+///
+/// # Performance
+///
+/// The shards are searched one after the other, so a search takes time
+/// proportional to the number of shards. For a set that rarely changes, a
+/// `RegexSet` of the same patterns searches faster.
+#[derive(Clone)]
+pub struct DynamicRegexSet {
+    /// The options each shard is compiled with.
+    options: RegexOptions,
+    only_utf8: bool,
+    /// The pattern of every regex in this set, keyed by ID. Removed
+    /// patterns are dropped from the map.
+    patterns: BTreeMap<usize, String>,
+    /// The ID that the next pattern added gets.
+    next_id: usize,
+    /// The shards, in ascending order of the IDs they contain.
+    shards: Vec<Shard>,
+}
+
+/// A group of patterns in a `DynamicRegexSet` that are compiled together.
+#[derive(Clone)]
+struct Shard {
+    /// The ID of each regex in `set`, in ascending order. This is never
+    /// empty.
+    ids: Vec<usize>,
+    set: RegexSet,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl DynamicRegexSet {
+    /// Create a new dynamic regex set with the given regular expressions.
+    ///
+    /// The regular expressions get the IDs `0` through `n - 1`, in the
+    /// order given. If any of them are invalid, then an error is returned.
+    pub fn new<I, S>(exprs: I) -> Result<DynamicRegexSet, Error>
+            where S: AsRef<str>, I: IntoIterator<Item=S> {
+        RegexSetBuilder::new(exprs).build_dynamic()
+    }
+
+    /// Create a new dynamic regex set whose initial patterns and compile
+    /// options are taken from `options`.
+    pub(crate) fn from_options(
+        mut options: RegexOptions,
+        only_utf8: bool,
+    ) -> Result<DynamicRegexSet, Error> {
+        let pats = mem::replace(&mut options.pats, vec![]);
+        let mut set = DynamicRegexSet {
+            options: options,
+            only_utf8: only_utf8,
+            patterns: BTreeMap::new(),
+            next_id: 0,
+            shards: vec![],
+        };
+        set.add_many(pats)?;
+        Ok(set)
+    }
+
+    /// Add a regular expression to this set and return its ID.
+    ///
+    /// If the expression is invalid, then an error is returned and the set
+    /// is left unchanged.
+    pub fn add(&mut self, expr: &str) -> Result<usize, Error> {
+        self.add_many(&[expr]).map(|ids| ids.start)
+    }
+
+    /// Add several regular expressions to this set and return the range of
+    /// IDs they were given.
+    ///
+    /// This is faster than adding each expression on its own, since every
+    /// shard that changes is compiled only once. If any of the expressions
+    /// are invalid, then an error is returned and the set is left unchanged.
+    pub fn add_many<I, S>(&mut self, exprs: I) -> Result<Range<usize>, Error>
+            where S: AsRef<str>, I: IntoIterator<Item=S> {
+        let start = self.next_id;
+        let mut end = start;
+        for expr in exprs {
+            self.patterns.insert(end, expr.as_ref().to_owned());
+            end += 1;
+        }
+        let ids = start..end;
+        let (replace_last, shards) = match self.compile_added(ids.clone()) {
+            Ok(result) => result,
+            Err(err) => {
+                for id in ids {
+                    self.patterns.remove(&id);
+                }
+                return Err(err);
+            }
+        };
+        if replace_last {
+            self.shards.pop();
+        }
+        self.shards.extend(shards);
+        self.next_id = end;
+        Ok(ids)
+    }
+
+    /// Remove the regular expression with the given ID from this set and
+    /// return it.
+    ///
+    /// If no expression in this set has the given ID, then `None` is
+    /// returned.
+    pub fn remove(&mut self, id: usize) -> Option<String> {
+        let pattern = self.patterns.remove(&id)?;
+        let i = match self
+            .shards
+            .binary_search_by(|shard| shard.ids[shard.ids.len() - 1].cmp(&id))
+        {
+            Ok(i) | Err(i) => i,
+        };
+        let mut ids = self.shards[i].ids.clone();
+        ids.retain(|&other| other != id);
+        if ids.is_empty() {
+            self.shards.remove(i);
+            return Some(pattern);
+        }
+
+        // Merge with a neighbor if the two fit into one shard. If the merged
+        // shard can't be compiled (e.g., it's too big), then just keep them
+        // apart. A shard with fewer patterns than before always compiles.
+        let size = self.options.shard_size;
+        if i + 1 < self.shards.len()
+            && ids.len() + self.shards[i + 1].ids.len() <= size
+        {
+            let mut merged = ids.clone();
+            merged.extend_from_slice(&self.shards[i + 1].ids);
+            if let Ok(shard) = self.compile(merged) {
+                self.shards[i] = shard;
+                self.shards.remove(i + 1);
+                return Some(pattern);
+            }
+        } else if i > 0 && self.shards[i - 1].ids.len() + ids.len() <= size {
+            let mut merged = self.shards[i - 1].ids.clone();
+            merged.extend_from_slice(&ids);
+            if let Ok(shard) = self.compile(merged) {
+                self.shards[i - 1] = shard;
+                self.shards.remove(i);
+                return Some(pattern);
+            }
+        }
+        self.shards[i] =
+            self.compile(ids).expect("BUG: a subset of a shard must compile");
+        Some(pattern)
+    }
+
+    /// Returns true if and only if one of the regexes in this set matches
+    /// the text given.
+    pub fn is_match(&self, text: $text_ty) -> bool {
+        self.shards.iter().any(|shard| shard.set.is_match(text))
+    }
+
+    /// Returns the set of regular expressions that match in the given text.
+    ///
+    /// Matches are reported by ID. The set returned has a length equal to
+    /// the number of IDs assigned so far, including the IDs of regexes that
+    /// have been removed, which never match. Only the IDs that matched are
+    /// stored, so removed IDs cost nothing.
+    pub fn matches(&self, text: $text_ty) -> SetMatches {
+        let mut matches = vec![];
+        let mut shard_matches = vec![];
+        // The shards are in ascending order of ID, so the matched IDs are
+        // collected in ascending order too.
+        for shard in &self.shards {
+            shard_matches.clear();
+            shard_matches.resize(shard.ids.len(), false);
+            if !shard.set.read_matches_at(&mut shard_matches, text, 0) {
+                continue;
+            }
+            for (&id, &yes) in shard.ids.iter().zip(&shard_matches) {
+                if yes {
+                    matches.push(id);
+                }
+            }
+        }
+        SetMatches { len: self.next_id, matches: matches }
+    }
+
+    /// Returns the regular expression with the given ID, if it is in this
+    /// set.
+    pub fn pattern(&self, id: usize) -> Option<&str> {
+        self.patterns.get(&id).map(|pattern| &**pattern)
+    }
+
+    /// Returns the number of regular expressions in this set.
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    /// Returns the number of shards that the regular expressions in this set
+    /// are compiled into.
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Compile the shards needed to add the patterns with the given IDs.
+    ///
+    /// The new patterns first fill up the last shard, which is recompiled,
+    /// and then go into new shards. The returned flag is true when the first
+    /// shard returned replaces the last shard.
+    fn compile_added(
+        &self,
+        ids: Range<usize>,
+    ) -> Result<(bool, Vec<Shard>), Error> {
+        let size = self.options.shard_size;
+        let mut shards = vec![];
+        let mut replace_last = false;
+        let mut next = ids.start;
+        if let Some(last) = self.shards.last() {
+            if last.ids.len() < size && next < ids.end {
+                let end = cmp::min(ids.end, next + size - last.ids.len());
+                let mut shard_ids = last.ids.clone();
+                shard_ids.extend(next..end);
+                shards.push(self.compile(shard_ids)?);
+                replace_last = true;
+                next = end;
+            }
+        }
+        while next < ids.end {
+            let end = cmp::min(ids.end, next + size);
+            shards.push(self.compile((next..end).collect())?);
+            next = end;
+        }
+        Ok((replace_last, shards))
+    }
+
+    /// Compile the patterns with the given IDs into one shard.
+    fn compile(&self, ids: Vec<usize>) -> Result<Shard, Error> {
+        let mut options = self.options.clone();
+        options.pats = ids
+            .iter()
+            .map(|id| self.patterns[id].clone())
+            .collect();
+        let exec = ExecBuilder::new_options(options)
+            .only_utf8(self.only_utf8)
+            .build()?;
+        Ok(Shard { ids: ids, set: RegexSet::from(exec) })
+    }
+}
+
 /// A set of matches returned by a regex set.
 #[derive(Clone, Debug)]
 pub struct SetMatches {
-    matched_any: bool,
-    matches: Vec<bool>,
+    /// The total number of regexes (or IDs) that could have matched.
+    len: usize,
+    /// The indexes of the regexes that matched, in ascending order.
+    matches: Vec<usize>,
 }
 
 #[cfg_attr(test, ::mutagen::mutate)] impl SetMatches {
+    /// Create a set of matches from a flag for every regex in a set.
+    fn from_dense(matched: &[bool]) -> SetMatches {
+        let matches = matched
+            .iter()
+            .enumerate()
+            .filter(|&(_, &yes)| yes)
+            .map(|(i, _)| i)
+            .collect();
+        SetMatches { len: matched.len(), matches: matches }
+    }
+
     /// Whether this set contains any matches.
     pub fn matched_any(&self) -> bool {
-        self.matched_any
+        !self.matches.is_empty()
     }
 
     /// Whether the regex at the given index matched.
@@ -261,12 +619,18 @@ pub struct SetMatches {
     ///
     /// If `regex_index` is greater than or equal to `self.len()`.
     pub fn matched(&self, regex_index: usize) -> bool {
-        self.matches[regex_index]
+        assert!(
+            regex_index < self.len,
+            "regex index {} out of range for {} regexes",
+            regex_index,
+            self.len
+        );
+        self.matches.binary_search(&regex_index).is_ok()
     }
 
     /// The total number of regexes in the set that created these matches.
     pub fn len(&self) -> usize {
-        self.matches.len()
+        self.len
     }
 
     /// Returns an iterator over indexes in the regex that matched.
@@ -275,7 +639,7 @@ pub struct SetMatches {
     /// the index corresponds to the index of the regex that matched with
     /// respect to its position when initially building the set.
     pub fn iter(&self) -> SetMatchesIter {
-        SetMatchesIter((&*self.matches).into_iter().enumerate())
+        SetMatchesIter(self.matches.iter())
     }
 }
 
@@ -284,7 +648,7 @@ pub struct SetMatches {
     type Item = usize;
 
     fn into_iter(self) -> Self::IntoIter {
-        SetMatchesIntoIter(self.matches.into_iter().enumerate())
+        SetMatchesIntoIter(self.matches.into_iter())
     }
 }
 
@@ -302,19 +666,13 @@ pub struct SetMatches {
 /// This will always produces matches in ascending order of index, where the
 /// index corresponds to the index of the regex that matched with respect to
 /// its position when initially building the set.
-pub struct SetMatchesIntoIter(iter::Enumerate<vec::IntoIter<bool>>);
+pub struct SetMatchesIntoIter(vec::IntoIter<usize>);
 
 #[cfg_attr(test, ::mutagen::mutate)] impl Iterator for SetMatchesIntoIter {
     type Item = usize;
 
     fn next(&mut self) -> Option<usize> {
-        loop {
-            match self.0.next() {
-                None => return None,
-                Some((_, false)) => {}
-                Some((i, true)) => return Some(i),
-            }
-        }
+        self.0.next()
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
@@ -324,13 +682,7 @@ pub struct SetMatchesIntoIter(iter::Enumerate<vec::IntoIter<bool>>);
 
 #[cfg_attr(test, ::mutagen::mutate)] impl DoubleEndedIterator for SetMatchesIntoIter {
     fn next_back(&mut self) -> Option<usize> {
-        loop {
-            match self.0.next_back() {
-                None => return None,
-                Some((_, false)) => {}
-                Some((i, true)) => return Some(i),
-            }
-        }
+        self.0.next_back()
     }
 }
 
@@ -342,19 +694,13 @@ pub struct SetMatchesIntoIter(iter::Enumerate<vec::IntoIter<bool>>);
 /// index corresponds to the index of the regex that matched with respect to
 /// its position when initially building the set.
 #[derive(Clone)]
-pub struct SetMatchesIter<'a>(iter::Enumerate<slice::Iter<'a, bool>>);
+pub struct SetMatchesIter<'a>(slice::Iter<'a, usize>);
 
 #[cfg_attr(test, ::mutagen::mutate)] impl<'a> Iterator for SetMatchesIter<'a> {
     type Item = usize;
 
     fn next(&mut self) -> Option<usize> {
-        loop {
-            match self.0.next() {
-                None => return None,
-                Some((_, &false)) => {}
-                Some((i, &true)) => return Some(i),
-            }
-        }
+        self.0.next().cloned()
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
@@ -364,13 +710,7 @@ pub struct SetMatchesIter<'a>(iter::Enumerate<slice::Iter<'a, bool>>);
 
 #[cfg_attr(test, ::mutagen::mutate)] impl<'a> DoubleEndedIterator for SetMatchesIter<'a> {
     fn next_back(&mut self) -> Option<usize> {
-        loop {
-            match self.0.next_back() {
-                None => return None,
-                Some((_, &false)) => {}
-                Some((i, &true)) => return Some(i),
-            }
-        }
+        self.0.next_back().cloned()
     }
 }
 
@@ -387,6 +727,14 @@ pub struct SetMatchesIter<'a>(iter::Enumerate<slice::Iter<'a, bool>>);
     }
 }
 
+#[cfg_attr(test, ::mutagen::mutate)] impl fmt::Debug for DynamicRegexSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "DynamicRegexSet(")?;
+        f.debug_map().entries(&self.patterns).finish()?;
+        write!(f, ")")
+    }
+}
+
 #[allow(dead_code)] fn as_bytes_str(text: &str) -> &[u8] { text.as_bytes() }
 #[allow(dead_code)] fn as_bytes_bytes(text: &[u8]) -> &[u8] { text }
         }
@@ -1,16 +1,18 @@
-use std::borrow::Cow;
-use std::collections::HashMap;
-use std::fmt;
-use std::ops::Index;
-use std::str::FromStr;
-use std::sync::Arc;
+use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::sync::Arc;
+use core::fmt;
+use core::ops::Index;
+use core::str::FromStr;
 
 use crate::find_byte::find_byte;
 use crate::syntax;
 
 use crate::error::Error;
-use crate::exec::{Exec, ExecNoSyncStr};
+use crate::exec::{Cache, Exec, ExecNoSyncStr};
 use crate::expand::expand_str;
+use crate::prog::CaptureNameMap;
 use crate::re_builder::unicode::RegexBuilder;
 use crate::re_trait::{self, RegularExpression, SubCapturesPosIter};
 
@@ -678,6 +680,95 @@ pub struct Regex(Exec);
             .map(|(s, e)| Match::new(text, s, e))
     }
 
+    /// Returns the leftmost-first match that begins exactly at `start`, if
+    /// one exists.
+    ///
+    /// This is like `find_at`, except that a match beginning after `start`
+    /// is never reported. This is useful for tokenizers, which need to know
+    /// whether a token begins at the current position without adding a `^`
+    /// to every pattern. As with `find_at`, the text before `start` is still
+    /// taken into consideration. For example, the `\A` anchor can only match
+    /// when `start == 0`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"[0-9]+").unwrap();
+    /// let text = "ab 123";
+    /// assert!(re.find_anchored_at(text, 0).is_none());
+    /// let mat = re.find_anchored_at(text, 3).unwrap();
+    /// assert_eq!((mat.start(), mat.end()), (3, 6));
+    /// # }
+    /// ```
+    pub fn find_anchored_at<'t>(
+        &self,
+        text: &'t str,
+        start: usize,
+    ) -> Option<Match<'t>> {
+        self.0
+            .searcher_str()
+            .find_anchored_at(text, start)
+            .map(|(s, e)| Match::new(text, s, e))
+    }
+
+    /// Returns the same as `find_anchored_at`, but also populates the capture
+    /// locations given.
+    pub fn captures_read_anchored_at<'t>(
+        &self,
+        locs: &mut CaptureLocations,
+        text: &'t str,
+        start: usize,
+    ) -> Option<Match<'t>> {
+        self.0
+            .searcher_str()
+            .captures_read_anchored_at(&mut locs.0, text, start)
+            .map(|(s, e)| Match::new(text, s, e))
+    }
+
+    /// Returns the match that ends closest to `end` without extending past
+    /// it, if one exists.
+    ///
+    /// Of the matches ending at that position, the one that begins first is
+    /// returned. Since this searches backwards, the match found is not
+    /// necessarily one that `find_iter` would report.
+    ///
+    /// The text after `end` is still taken into consideration. For example,
+    /// the `\z` anchor can only match when `end` is the length of `text`.
+    ///
+    /// The search scans `text` backwards from `end`, so it usually only
+    /// looks at the text near the match it returns. (If the regex contains
+    /// constructs that the lazy DFA can't handle, like Unicode word
+    /// boundaries next to non-ASCII text, then `text` is instead scanned from
+    /// its beginning up to `end`.)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"[0-9]+").unwrap();
+    /// let text = "a1 b22 c333";
+    /// let mat = re.rfind_at(text, text.len()).unwrap();
+    /// assert_eq!(mat.as_str(), "333");
+    /// let mat = re.rfind_at(text, 10).unwrap();
+    /// assert_eq!(mat.as_str(), "33");
+    /// let mat = re.rfind_at(text, 8).unwrap();
+    /// assert_eq!(mat.as_str(), "22");
+    /// # }
+    /// ```
+    pub fn rfind_at<'t>(
+        &self,
+        text: &'t str,
+        end: usize,
+    ) -> Option<Match<'t>> {
+        self.0
+            .searcher_str()
+            .rfind_at(text, end)
+            .map(|(s, e)| Match::new(text, s, e))
+    }
+
     /// An undocumented alias for `captures_read_at`.
     ///
     /// The `regex-capi` crate previously used this routine, so to avoid
@@ -694,6 +785,166 @@ pub struct Regex(Exec);
     }
 }
 
+/// Searching with a caller-managed cache.
+#[cfg_attr(test, ::mutagen::mutate)] impl Regex {
+    /// Returns a new cache of scratch space for use with this regex's
+    /// `_with_cache` search methods.
+    ///
+    /// Search methods that don't take a cache find scratch space on their
+    /// own. When the `std` feature is enabled, scratch space is kept in a
+    /// cache internal to the regex and reused across searches. Without it,
+    /// fresh scratch space is allocated for every such search, so the
+    /// `_with_cache` methods should be preferred.
+    ///
+    /// Owning the cache is also useful with `std`. The internal cache keeps
+    /// scratch space for every thread that has ever used the regex, which
+    /// adds up when a regex is shared by many short-lived threads. A cache
+    /// created here is freed when it is dropped, can be moved to another
+    /// thread and reports its size with `Cache::memory_usage`.
+    ///
+    /// A cache should only be used with the regex that created it (or a
+    /// clone of it). Using it with any other regex resets the cache.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"[0-9]{3}").unwrap();
+    /// let mut cache = re.create_cache();
+    /// assert!(re.is_match_with_cache(&mut cache, "abc123"));
+    /// let mat = re.find_with_cache(&mut cache, "a1b234").unwrap();
+    /// assert_eq!((mat.start(), mat.end()), (3, 6));
+    /// # }
+    /// ```
+    pub fn create_cache(&self) -> Cache {
+        self.0.create_cache()
+    }
+
+    /// Returns true if and only if the regex matches the string given, using
+    /// the given cache for scratch space.
+    ///
+    /// This is otherwise the same as `is_match`.
+    pub fn is_match_with_cache(&self, cache: &mut Cache, text: &str) -> bool {
+        self.shortest_match_with_cache(cache, text).is_some()
+    }
+
+    /// Returns the end location of a match in the text given, using the
+    /// given cache for scratch space.
+    ///
+    /// This is otherwise the same as `shortest_match`.
+    pub fn shortest_match_with_cache(
+        &self,
+        cache: &mut Cache,
+        text: &str,
+    ) -> Option<usize> {
+        self.shortest_match_at_with_cache(cache, text, 0)
+    }
+
+    /// Returns the same as `shortest_match_with_cache`, but starts the search
+    /// at the given offset.
+    ///
+    /// This is otherwise the same as `shortest_match_at`.
+    pub fn shortest_match_at_with_cache(
+        &self,
+        cache: &mut Cache,
+        text: &str,
+        start: usize,
+    ) -> Option<usize> {
+        self.0.searcher_str_with_cache(cache).shortest_match_at(text, start)
+    }
+
+    /// Returns the start and end byte range of the leftmost-first match in
+    /// `text`, using the given cache for scratch space.
+    ///
+    /// This is otherwise the same as `find`.
+    pub fn find_with_cache<'t>(
+        &self,
+        cache: &mut Cache,
+        text: &'t str,
+    ) -> Option<Match<'t>> {
+        self.find_at_with_cache(cache, text, 0)
+    }
+
+    /// Returns the same as `find_with_cache`, but starts the search at the
+    /// given offset.
+    ///
+    /// This is otherwise the same as `find_at`.
+    pub fn find_at_with_cache<'t>(
+        &self,
+        cache: &mut Cache,
+        text: &'t str,
+        start: usize,
+    ) -> Option<Match<'t>> {
+        self.0
+            .searcher_str_with_cache(cache)
+            .find_at(text, start)
+            .map(|(s, e)| Match::new(text, s, e))
+    }
+
+    /// Returns an iterator for each successive non-overlapping match in
+    /// `text`, using the given cache for scratch space.
+    ///
+    /// The cache is borrowed for as long as the iterator is alive. This is
+    /// otherwise the same as `find_iter`.
+    pub fn find_iter_with_cache<'r, 't>(
+        &'r self,
+        cache: &'r mut Cache,
+        text: &'t str,
+    ) -> Matches<'r, 't> {
+        Matches(self.0.searcher_str_with_cache(cache).find_iter(text))
+    }
+
+    /// Returns the capture groups corresponding to the leftmost-first match
+    /// in `text`, using the given cache for scratch space.
+    ///
+    /// This is otherwise the same as `captures`.
+    pub fn captures_with_cache<'t>(
+        &self,
+        cache: &mut Cache,
+        text: &'t str,
+    ) -> Option<Captures<'t>> {
+        let mut locs = self.capture_locations();
+        self.captures_read_at_with_cache(cache, &mut locs, text, 0).map(
+            move |_| Captures {
+                text: text,
+                locs: locs.0,
+                named_groups: self.0.capture_name_idx().clone(),
+            },
+        )
+    }
+
+    /// Returns an iterator over all the non-overlapping capture groups
+    /// matched in `text`, using the given cache for scratch space.
+    ///
+    /// The cache is borrowed for as long as the iterator is alive. This is
+    /// otherwise the same as `captures_iter`.
+    pub fn captures_iter_with_cache<'r, 't>(
+        &'r self,
+        cache: &'r mut Cache,
+        text: &'t str,
+    ) -> CaptureMatches<'r, 't> {
+        CaptureMatches(
+            self.0.searcher_str_with_cache(cache).captures_iter(text),
+        )
+    }
+
+    /// Returns the same as `captures_read_at`, but uses the given cache for
+    /// scratch space.
+    pub fn captures_read_at_with_cache<'t>(
+        &self,
+        cache: &mut Cache,
+        locs: &mut CaptureLocations,
+        text: &'t str,
+        start: usize,
+    ) -> Option<Match<'t>> {
+        self.0
+            .searcher_str_with_cache(cache)
+            .captures_read_at(&mut locs.0, text, start)
+            .map(|(s, e)| Match::new(text, s, e))
+    }
+}
+
 /// Auxiliary methods.
 #[cfg_attr(test, ::mutagen::mutate)] impl Regex {
     /// Returns the original string of this regex.
@@ -733,7 +984,7 @@ pub struct Regex(Exec);
 /// whole matched region) is always unnamed.
 ///
 /// `'r` is the lifetime of the compiled regular expression.
-pub struct CaptureNames<'r>(::std::slice::Iter<'r, Option<String>>);
+pub struct CaptureNames<'r>(::core::slice::Iter<'r, Option<String>>);
 
 #[cfg_attr(test, ::mutagen::mutate)] impl<'r> Iterator for CaptureNames<'r> {
     type Item = Option<&'r str>;
@@ -882,7 +1133,7 @@ pub type Locations = CaptureLocations;
 pub struct Captures<'t> {
     text: &'t str,
     locs: re_trait::Locations,
-    named_groups: Arc<HashMap<String, usize>>,
+    named_groups: Arc<CaptureNameMap>,
 }
 
 #[cfg_attr(test, ::mutagen::mutate)] impl<'t> Captures<'t> {
@@ -966,7 +1217,7 @@ struct CapturesDebug<'c, 't: 'c>(&'c Captures<'t>);
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         // We'd like to show something nice here, even if it means an
         // allocation to build a reverse index.
-        let slot_to_name: HashMap<&usize, &String> =
+        let slot_to_name: BTreeMap<&usize, &String> =
             self.0.named_groups.iter().map(|(a, b)| (b, a)).collect();
         let mut map = f.debug_map();
         for (slot, m) in self.0.locs.iter().enumerate() {
@@ -1,5 +1,7 @@
-use std::ops::Deref;
-use std::slice;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::ops::Deref;
+use core::slice;
 
 /// A sparse set used for representing ordered NFA states.
 ///
@@ -11,7 +11,7 @@
 /// Should this be factored out into a separate crate? It seems independently
 /// useful. There are other crates that already exist (e.g., `utf-8`) that have
 /// overlapping use cases. Not sure what to do.
-use std::char;
+use core::char;
 
 const TAG_CONT: u8 = 0b1000_0000;
 const TAG_TWO: u8 = 0b1100_0000;
@@ -145,7 +145,7 @@ pub fn decode_last_utf8(src: &[u8]) -> Option<(char, usize)> {
 
 #[cfg(test)]
 mod tests {
-    use std::str;
+    use core::str;
 
     use quickcheck::quickcheck;
 
@@ -0,0 +1,111 @@
+macro_rules! anchored {
+    ($name:ident, $re:expr, $text:expr, $start:expr, $expected:expr) => {
+        #[test]
+        fn $name() {
+            let re = regex!($re);
+            let got = re
+                .find_anchored_at(text!($text), $start)
+                .map(|m| (m.start(), m.end()));
+            assert_eq!($expected, got);
+        }
+    };
+}
+
+macro_rules! rfind {
+    ($name:ident, $re:expr, $text:expr, $end:expr, $expected:expr) => {
+        #[test]
+        fn $name() {
+            let re = regex!($re);
+            let got =
+                re.rfind_at(text!($text), $end).map(|m| (m.start(), m.end()));
+            assert_eq!($expected, got);
+        }
+    };
+}
+
+anchored!(anchored1, r"[0-9]+", "abc 123", 4, Some((4, 7)));
+anchored!(anchored2, r"[0-9]+", "abc 123", 3, None);
+anchored!(anchored3, r"[0-9]+", "abc 123", 5, Some((5, 7)));
+anchored!(anchored4, r"\bfoo", "xfoo foo", 1, None);
+anchored!(anchored5, r"\bfoo", "xfoo foo", 5, Some((5, 8)));
+anchored!(anchored6, r"^a", "aa", 1, None);
+anchored!(anchored7, r"(?m)^a", "b\na", 2, Some((2, 3)));
+anchored!(anchored8, r"a|ab", "xab", 1, Some((1, 2)));
+anchored!(anchored9, r"", "abc", 2, Some((2, 2)));
+anchored!(anchored10, r"foo$", "xfoo", 1, Some((1, 4)));
+anchored!(anchored11, r"abc", "xabcabc", 1, Some((1, 4)));
+anchored!(anchored12, r"abc", "xabcabc", 2, None);
+anchored!(anchored13, r"[a-z]+ing", "a going", 1, None);
+anchored!(anchored14, r"[a-z]+ing", "a going", 2, Some((2, 7)));
+anchored!(anchored15, r"[a-z]+ing", "a going", 3, Some((3, 7)));
+
+rfind!(rfind1, r"[0-9]+", "a1 b22 c333", 11, Some((8, 11)));
+rfind!(rfind2, r"[0-9]+", "a1 b22 c333", 10, Some((8, 10)));
+rfind!(rfind3, r"[0-9]+", "a1 b22 c333", 8, Some((4, 6)));
+rfind!(rfind4, r"[0-9]+", "a1 b22 c333", 2, Some((1, 2)));
+rfind!(rfind5, r"[0-9]+", "a1 b22 c333", 1, None);
+rfind!(rfind6, r"a|ab", "ab", 2, Some((0, 2)));
+rfind!(rfind7, r"a+?", "aaa", 3, Some((0, 3)));
+rfind!(rfind8, r"foo$", "foo foo", 7, Some((4, 7)));
+rfind!(rfind9, r"foo$", "foo foo", 6, None);
+rfind!(rfind10, r"\bfoo\b", "foo foobar", 10, Some((0, 3)));
+rfind!(rfind11, r"", "abc", 2, Some((2, 2)));
+rfind!(rfind12, r"(?m)^[a-z]+$", "one\ntwo\nthree", 13, Some((8, 13)));
+rfind!(rfind13, r"(?m)^[a-z]+$", "one\ntwo\nthree", 12, Some((4, 7)));
+rfind!(rfind14, r"abc", "abcxabcx", 8, Some((4, 7)));
+rfind!(rfind15, r"^abc", "abcxabcx", 8, Some((0, 3)));
+rfind!(rfind16, r"\bx\b", "é x é", 7, Some((3, 4)));
+rfind!(rfind17, r"a|ba", "xba", 3, Some((1, 3)));
+rfind!(rfind18, r"a+?b", "aab aab", 7, Some((4, 7)));
+rfind!(rfind19, r"[0-9]+", "a1 b22 c333 d", 13, Some((8, 11)));
+rfind!(
+    rfind20,
+    r"(?m)^ERR.*$",
+    "ERR a\nOK b\nERR c\nOK d",
+    19,
+    Some((11, 16))
+);
+rfind!(rfind21, r"a\b", "aa a", 3, Some((1, 2)));
+rfind!(rfind22, r"é+", "éé xéé", 8, Some((6, 8)));
+
+#[test]
+fn anchored_agrees_with_find_at() {
+    let re = regex!(r"[a-z]+[0-9]|[0-9]{2}");
+    let text = text!("ab1 c23 45d6");
+    for start in 0..text.len() + 1 {
+        let expected = re.find_at(text, start).filter(|m| m.start() == start);
+        let got = re.find_anchored_at(text, start);
+        assert_eq!(expected, got, "start: {}", start);
+    }
+}
+
+#[test]
+fn rfind_agrees_with_find_iter() {
+    // Searching backwards finds the same matches as searching forwards,
+    // since no match of this regex ends inside another one.
+    let re = regex!(r"[a-z]+[0-9]");
+    let text = text!("ab1 c23 d4");
+    let matches: Vec<_> = re.find_iter(text).collect();
+    for end in 0..text.len() + 1 {
+        let expected = matches.iter().rev().find(|m| m.end() <= end).cloned();
+        let got = re.rfind_at(text, end);
+        assert_eq!(expected, got, "end: {}", end);
+    }
+}
+
+#[test]
+fn captures_anchored() {
+    let re = regex!(r"([0-9]+)-([0-9]+)");
+    let mut locs = re.capture_locations();
+    let text = text!("x 12-34");
+    assert!(re.captures_read_anchored_at(&mut locs, text, 1).is_none());
+
+    let m = re.captures_read_anchored_at(&mut locs, text, 2).unwrap();
+    assert_eq!((2, 7), (m.start(), m.end()));
+    assert_eq!(Some((2, 4)), locs.get(1));
+    assert_eq!(Some((5, 7)), locs.get(2));
+
+    let m = re.captures_read_anchored_at(&mut locs, text, 3).unwrap();
+    assert_eq!((3, 7), (m.start(), m.end()));
+    assert_eq!(Some((3, 4)), locs.get(1));
+}
@@ -24,6 +24,15 @@ fn empty_match_unicode_captures_iter() {
     assert_eq!(vec![(0, 0), (3, 3), (4, 4), (7, 7), (8, 8)], ms);
 }
 
+#[test]
+#[cfg(feature = "unicode-perl")]
+fn empty_match_unicode_not_word_boundary() {
+    // Found by differential fuzzing: engines running on bytes used to match
+    // \B between the bytes of a UTF-8 encoded word character.
+    let re = regex!(r"\B");
+    assert_eq!(vec![(2, 2)], findall!(re, "éé"));
+}
+
 #[test]
 fn match_as_str() {
     let re = regex!(r"fo+");
@@ -42,6 +42,14 @@ pub fn backends_are_consistent(re: &str) -> Result<u64, String> {
                 .map(|exec| exec.into_regex())
                 .map_err(|err| format!("{}", err))?,
         ),
+        (
+            "simplified_re",
+            ExecBuilder::new(re)
+                .simplify(true)
+                .build()
+                .map(|exec| exec.into_regex())
+                .map_err(|err| format!("{}", err))?,
+        ),
     ];
 
     let utf8bytes_backends = vec![
@@ -100,6 +108,15 @@ pub fn backends_are_consistent(re: &str) -> Result<u64, String> {
                 .map(|exec| exec.into_byte_regex())
                 .map_err(|err| format!("{}", err))?,
         ),
+        (
+            "simplified_bytes_re",
+            ExecBuilder::new(re)
+                .only_utf8(false)
+                .simplify(true)
+                .build()
+                .map(|exec| exec.into_byte_regex())
+                .map_err(|err| format!("{}", err))?,
+        ),
     ];
 
     Ok(string_checker::check_backends(&standard_backends)?
@@ -240,3 +257,160 @@ checker!(string_checker, ::regex::Regex, |gen| String::arbitrary(gen));
 checker!(bytes_checker, ::regex::bytes::Regex, |gen| Vec::<u8>::arbitrary(
     gen
 ));
+
+//
+// A property checking that simplifying a regex's HIR doesn't change how it
+// matches.
+//
+// Random regexes rarely match random strings, so both the patterns and the
+// haystacks are drawn from a tiny alphabet. The patterns lean on
+// alternations of literals, classes and groups, since those are what the
+// simplifier rewrites.
+//
+
+use quickcheck::{Arbitrary, Gen, QuickCheck, TestResult};
+use regex::{Regex, RegexBuilder};
+
+const ALPHABET: &[&str] = &["a", "b", "c"];
+
+const LEAVES: &[&str] = &[
+    "a", "b", "c", "ab", "abc", "[ab]", "[a-c]", "[^a]", ".", "", "^", "$",
+    r"\b", r"\B",
+];
+
+const REPETITIONS: &[&str] =
+    &["*", "+", "?", "{1,2}", "*?", "+?", "??", "{0,2}?"];
+
+/// A randomly generated regular expression.
+#[derive(Clone, Debug)]
+pub struct Pattern(String);
+
+/// A randomly generated haystack over the same alphabet as `Pattern`.
+#[derive(Clone, Debug)]
+pub struct Haystack(String);
+
+impl Arbitrary for Pattern {
+    fn arbitrary<G: Gen>(g: &mut G) -> Pattern {
+        let mut pat = String::new();
+        push_expr(g, 3, &mut pat);
+        Pattern(pat)
+    }
+}
+
+impl Arbitrary for Haystack {
+    fn arbitrary<G: Gen>(g: &mut G) -> Haystack {
+        let len = pick(g, 12);
+        let mut hay = String::new();
+        for _ in 0..len {
+            // Throw in a non-word character now and then so that word
+            // boundaries have something to match.
+            if pick(g, 8) == 0 {
+                hay.push(' ');
+            } else {
+                hay.push_str(ALPHABET[pick(g, ALPHABET.len())]);
+            }
+        }
+        Haystack(hay)
+    }
+}
+
+fn pick<G: Gen>(g: &mut G, n: usize) -> usize {
+    usize::arbitrary(g) % n
+}
+
+fn push_expr<G: Gen>(g: &mut G, depth: usize, pat: &mut String) {
+    let choice = if depth == 0 { 0 } else { pick(g, 6) };
+    match choice {
+        1 | 2 => {
+            let n = 2 + pick(g, 3);
+            for i in 0..n {
+                if i > 0 {
+                    pat.push('|');
+                }
+                push_expr(g, depth - 1, pat);
+            }
+        }
+        3 => {
+            let n = 2 + pick(g, 2);
+            for _ in 0..n {
+                pat.push_str("(?:");
+                push_expr(g, depth - 1, pat);
+                pat.push(')');
+            }
+        }
+        4 => {
+            pat.push_str(if pick(g, 2) == 0 { "(" } else { "(?:" });
+            push_expr(g, depth - 1, pat);
+            pat.push(')');
+        }
+        5 => {
+            pat.push_str("(?:");
+            push_expr(g, depth - 1, pat);
+            pat.push(')');
+            pat.push_str(REPETITIONS[pick(g, REPETITIONS.len())]);
+        }
+        _ => pat.push_str(LEAVES[pick(g, LEAVES.len())]),
+    }
+}
+
+/// Check that a regex matches exactly like its simplified counterpart,
+/// including the offsets of every capture group, on generated patterns and
+/// haystacks.
+pub fn simplify_is_consistent() {
+    fn prop(pat: Pattern, hay: Haystack) -> TestResult {
+        let (re1, re2) = match (
+            Regex::new(&pat.0),
+            RegexBuilder::new(&pat.0).simplify(true).build(),
+        ) {
+            (Ok(re1), Ok(re2)) => (re1, re2),
+            (Err(_), Err(_)) => return TestResult::discard(),
+            (r1, r2) => {
+                return TestResult::error(format!(
+                    "/{}/ compiled inconsistently: {:?} vs {:?}",
+                    pat.0,
+                    r1.err(),
+                    r2.err()
+                ))
+            }
+        };
+        if re1.captures_len() != re2.captures_len() {
+            return TestResult::error(format!(
+                "/{}/ has {} groups but the simplified regex has {}",
+                pat.0,
+                re1.captures_len(),
+                re2.captures_len()
+            ));
+        }
+        let all1: Vec<Vec<_>> = re1
+            .captures_iter(&hay.0)
+            .map(|caps| {
+                caps.iter().map(|m| m.map(|m| (m.start(), m.end()))).collect()
+            })
+            .collect();
+        let all2: Vec<Vec<_>> = re2
+            .captures_iter(&hay.0)
+            .map(|caps| {
+                caps.iter().map(|m| m.map(|m| (m.start(), m.end()))).collect()
+            })
+            .collect();
+        if all1 != all2 {
+            return TestResult::error(format!(
+                "/{}/ on {:?}: {:?} vs simplified {:?}",
+                pat.0, hay.0, all1, all2
+            ));
+        }
+        let found1: Vec<_> = re1.find_iter(&hay.0).collect();
+        let found2: Vec<_> = re2.find_iter(&hay.0).collect();
+        if found1 != found2 {
+            return TestResult::error(format!(
+                "/{}/ on {:?}: {:?} vs simplified {:?}",
+                pat.0, hay.0, found1, found2
+            ));
+        }
+        TestResult::passed()
+    }
+    QuickCheck::new()
+        .tests(1000)
+        .max_tests(10000)
+        .quickcheck(prop as fn(Pattern, Haystack) -> TestResult);
+}
@@ -0,0 +1,21 @@
+mat!(
+    t01,
+    r"[a-z]+@example\.com[ \t]",
+    r"mail bob@example.com now",
+    Some((5, 21))
+);
+mat!(t02, r"[a-z]+@example\.com[ \t]", r"@example.com bob@example.com", None);
+mat!(t03, r"[a-z]+[0-9]+xyz[a-z]", r"xyz abc123xyzq", Some((4, 14)));
+mat!(t04, r"[a-z]+[0-9]+xyz[a-z]", r"ab1xyz1 cd2xyzq", Some((8, 15)));
+mat!(t05, r"[0-9]+abc[a-z]", r"abcd 1abcd", Some((5, 10)));
+mat!(t06, r"(?m)[0-9]+$\nabc[a-z]", "12x\n34\nabcd", Some((4, 11)));
+mat!(
+    t07,
+    r"([a-z]+)@(example)\.com[ \t]",
+    r"to: joe@example.com ",
+    Some((4, 20)),
+    Some((4, 7)),
+    Some((8, 15))
+);
+matiter!(t08, r"[0-9]+foo[0-9]", r"1foo2 33foo4 foo5", (0, 5), (6, 12));
+matiter!(t09, r"[a-z]+[0-9]xyz[a-z]", r"a1xyzb c2xyzd", (0, 6), (7, 13));
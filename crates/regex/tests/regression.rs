@@ -199,3 +199,53 @@ fn regression_nfa_stops1() {
     let re = ::regex::bytes::Regex::new(r"\bs(?:[ab])").unwrap();
     assert_eq!(0, re.find_iter(b"s\xE4").count());
 }
+
+// Found by differential fuzzing: the reverse DFA ignored the text preceding
+// the start of a search when looking for the start of a match.
+mat!(regression_word_boundary_reverse_context1, r"\b.", "0-", Some((0, 1)));
+#[test]
+fn regression_word_boundary_reverse_context2() {
+    let re = regex!(r"\b.");
+    assert_eq!(vec![(0, 1), (1, 2)], findall!(re, "0-"));
+    let re = regex!(r"\B.");
+    assert_eq!(vec![(1, 2), (2, 3)], findall!(re, "c_9"));
+}
+
+// See: https://github.com/rust-lang/regex/issues/659
+//
+// Also found by differential fuzzing: an empty sub-expression followed by a
+// multi-byte codepoint was miscompiled when matching on bytes.
+ismatch!(empty_group_match, r"()Ј01", "zЈ01", true);
+matiter!(empty_group_find, r"()Ј01", "zЈ01", (1, 5));
+matiter!(empty_group_repeat_find, r"(?:){0}Δ", "aΔ", (1, 3));
+
+// Found by differential fuzzing: the lazy DFA assumed that a non-ASCII byte
+// preceding the start of a search was never part of a word character.
+#[test]
+#[cfg(feature = "unicode-perl")]
+fn regression_word_boundary_after_non_ascii() {
+    let re = regex!(r"\b");
+    assert_eq!(vec![(0, 0), (3, 3)], findall!(re, "\u{212A}"));
+}
+
+// Found by differential fuzzing: literal extraction ignored the laziness of
+// `??`, so the literal searcher preferred the longer match.
+#[test]
+fn regression_lazy_zero_or_one_literal() {
+    let re = regex!(r"KA??");
+    assert_eq!(vec![(0, 1)], findall!(re, "KA"));
+    let re = regex!(r"\-é??");
+    assert_eq!(vec![(0, 1)], findall!(re, "-é"));
+}
+
+// Found by the simplifier's consistency property: crossing a literal set
+// with another set or a class put the new literals in the outer loop, so the
+// literal searcher preferred `abc` over `abcc` and `b` over `bc`.
+matiter!(
+    regression_class_literal_order,
+    r"(?:ab|[a-b])[a-c]c",
+    "cbccabcc",
+    (1, 4),
+    (4, 8)
+);
+matiter!(regression_cross_literal_order, r"(b?)[a-c]", "cbc", (0, 1), (1, 3));
@@ -0,0 +1,42 @@
+// Extended grapheme clusters.
+matiter!(grapheme_ascii, r"\X", "ab", (0, 1), (1, 2));
+matiter!(grapheme_crlf, r"\X", "\r\n\n", (0, 2), (2, 3));
+matiter!(grapheme_combining, r"\X", "e\u{301}x", (0, 3), (3, 4));
+matiter!(
+    grapheme_flags,
+    r"\X",
+    "\u{1F1FA}\u{1F1F8}\u{1F1EB}",
+    (0, 8),
+    (8, 12)
+);
+matiter!(
+    grapheme_zwj,
+    r"\X",
+    "\u{1F468}\u{200D}\u{1F469}!",
+    (0, 11),
+    (11, 12)
+);
+matiter!(
+    grapheme_hangul,
+    r"\X",
+    "\u{1100}\u{1161}\u{11A8}\u{AC00}",
+    (0, 9),
+    (9, 12)
+);
+mat!(grapheme_anchored, r"^\X$", "\u{1F468}\u{200D}\u{1F469}", Some((0, 11)));
+mat!(grapheme_casei, r"(?i)^\X\X$", "A\u{301}b", Some((0, 4)));
+
+// UAX#29 word boundaries.
+matiter!(
+    wb_contraction,
+    r"\b{wb}.+?\b{wb}",
+    "can't stop",
+    (0, 5),
+    (5, 6),
+    (6, 10)
+);
+matiter!(wb_decimal, r"\b{wb}.+?\b{wb}", "3.14 x", (0, 4), (4, 5), (5, 6));
+matiter!(wb_zwj, r"\b{wb}.+?\b{wb}", "\u{1F468}\u{200D}\u{1F469}", (0, 11));
+matiter!(wb_not, r"\B{wb}", "a'b", (1, 1), (2, 2));
+mat!(wb_apostrophe, r"n\b{wb}", "can't", None);
+mat!(wb_perl_apostrophe, r"n\b", "can't", Some((2, 3)));
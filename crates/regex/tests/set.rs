@@ -43,3 +43,11 @@ fn get_set_patterns() {
     let set = regex_set!(&["a", "b"]);
     assert_eq!(vec!["a", "b"], set.patterns());
 }
+
+#[test]
+fn shortest_match() {
+    let set = regex_set!(&["a+", "[0-9]{3}"]);
+    assert_eq!(Some(5), set.shortest_match(text!("xx123aaa")));
+    assert_eq!(Some(1), set.shortest_match(text!("aaa")));
+    assert_eq!(None, set.shortest_match(text!("xyz")));
+}
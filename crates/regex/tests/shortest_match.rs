@@ -12,3 +12,5 @@ macro_rules! shortmat {
 shortmat!(t01, r"a+", r"aa", Some(1));
 // Test that the reverse suffix optimization gets it right.
 shortmat!(t02, r".*(?:abcd)+", r"abcdabcd", Some(4));
+// Test that the inner literal optimization gets it right.
+shortmat!(t03, r"[a-z]+@[a-z]+\.com[ \t]", r"a@b x@example.com y", Some(18));
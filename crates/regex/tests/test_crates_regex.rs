@@ -35,6 +35,11 @@ fn word_boundary_backtracking_default_mismatch() {
 
 mod consistent;
 
+#[test]
+fn simplify_is_consistent() {
+    consistent::simplify_is_consistent();
+}
+
 mod crates_regex {
 
     macro_rules! consistent {
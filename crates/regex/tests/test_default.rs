@@ -2,6 +2,7 @@
 
 extern crate rand;
 extern crate regex;
+extern crate regex_syntax;
 
 // Due to macro scoping rules, this definition only applies for the modules
 // defined below. Effectively, it allows us to use the same tests for both
@@ -40,17 +41,21 @@ macro_rules! regex_set {
 include!("macros_str.rs");
 include!("macros.rs");
 
+mod anchored_reverse;
 mod api;
 mod api_str;
 mod crazy;
 mod flags;
 mod fowler;
+mod inner_literal;
 mod misc;
 mod multiline;
 mod noparse;
 mod regression;
 mod replace;
 mod searcher;
+#[cfg(all(feature = "unicode-segment", feature = "unicode-bool"))]
+mod segment;
 mod set;
 mod shortest_match;
 mod suffix_reverse;
@@ -74,15 +79,176 @@ fn disallow_octal() {
     assert!(regex::Regex::new(r"\0").is_err());
 }
 
+#[test]
+fn parse_error_spans() {
+    let err = regex::Regex::new(r"ab{2").unwrap_err();
+    match err {
+        regex::Error::Parse(ref err) => {
+            assert!(err.to_string().contains("unclosed counted repetition"))
+        }
+        ref err => panic!("expected a syntax error, got {:?}", err),
+    }
+
+    let err = err.syntax_error().unwrap();
+    assert_eq!(err.pattern(), r"ab{2");
+    assert_eq!(err.span(), 2..4);
+    assert_eq!(err.kind(), "unclosed counted repetition");
+
+    let suggestion = err.suggestion().unwrap();
+    assert_eq!(suggestion.apply(err.pattern()), r"ab\{2");
+    assert!(err.diagnostic().contains("did you mean `\\{`?"));
+}
+
+#[test]
+fn parse_error_spans_options() {
+    // Errors that only occur with some options, or in multi-line patterns,
+    // carry their spans too.
+    let err = regex::Regex::new("a\n(?P<x>b)\n(?P<x>c)").unwrap_err();
+    let err = err.syntax_error().unwrap();
+    assert_eq!(err.span(), 15..16);
+    assert_eq!(err.auxiliary_span(), Some(6..7));
+
+    let err =
+        regex::RegexBuilder::new(r"a.").unicode(false).build().unwrap_err();
+    assert_eq!(err.syntax_error().unwrap().span(), 1..2);
+
+    let err = regex::RegexBuilder::new(r"((a))")
+        .nest_limit(1)
+        .build()
+        .unwrap_err();
+    let err = err.syntax_error().unwrap();
+    assert!(err.kind().starts_with("exceed the maximum number of nested"));
+    assert_eq!(err.span(), 1..4);
+
+    let err = regex::RegexBuilder::new(r"\p{OurVowel}(")
+        .unicode_property("OurVowel", &[('a', 'a'), ('e', 'e')])
+        .build()
+        .unwrap_err();
+    let err = err.syntax_error().unwrap();
+    assert_eq!(err.kind(), "unclosed group");
+    assert_eq!(err.span(), 12..13);
+
+    let err = regex::RegexBuilder::new("a").size_limit(0).build().unwrap_err();
+    assert_eq!(err.syntax_error(), None);
+}
+
 #[test]
 fn allow_octal() {
     assert!(regex::RegexBuilder::new(r"\0").octal(true).build().is_ok());
 }
 
+#[test]
+fn simplify_captures() {
+    let re = regex::RegexBuilder::new(r"(?:ab|ac)(x)|(?:ab|ac)(y)")
+        .simplify(true)
+        .build()
+        .unwrap();
+    let caps = re.captures("zacy").unwrap();
+    assert_eq!(caps.get(0).map(|m| (m.start(), m.end())), Some((1, 4)));
+    assert!(caps.get(1).is_none());
+    assert_eq!(caps.get(2).map(|m| (m.start(), m.end())), Some((3, 4)));
+}
+
+#[test]
+fn dialect_posix_ere() {
+    use regex::{Dialect, RegexBuilder};
+
+    let re = RegexBuilder::new(r"^[[:alpha:]\]+$")
+        .dialect(Dialect::PosixEre)
+        .build()
+        .unwrap();
+    assert!(re.is_match(r"ab\c"));
+    assert!(!re.is_match("ab1"));
+}
+
+#[test]
+#[cfg(feature = "unicode-perl")]
+fn dialect_pcre() {
+    use regex::{Dialect, RegexBuilder};
+
+    let re = RegexBuilder::new(r"(?<word>\w+)\h+\Q(*)\E{x}")
+        .dialect(Dialect::Pcre)
+        .build()
+        .unwrap();
+    let caps = re.captures("hello \t(*){x}").unwrap();
+    assert_eq!(&caps["word"], "hello");
+
+    let err = RegexBuilder::new(r"a(?>b)").dialect(Dialect::Pcre).build();
+    assert!(err.unwrap_err().to_string().contains("atomic groups"));
+}
+
+#[test]
+#[cfg(feature = "unicode-segment")]
+fn dialect_pcre_grapheme_cluster() {
+    use regex::{Dialect, RegexBuilder};
+
+    let re =
+        RegexBuilder::new(r"^\X\X$").dialect(Dialect::Pcre).build().unwrap();
+    assert!(re.is_match("e\u{301}\r\n"));
+    assert!(!re.is_match("e\u{301}\r\n!"));
+}
+
+#[test]
+fn dialect_javascript() {
+    use regex::{Dialect, RegexBuilder};
+
+    let re = RegexBuilder::new(r"^\d+[^]\uD83D\uDE00$")
+        .dialect(Dialect::JavaScript)
+        .build()
+        .unwrap();
+    assert!(re.is_match("42\n\u{1F600}"));
+    assert!(!re.is_match("\u{0664}\n\u{1F600}"));
+
+    // An empty class never matches, which this crate doesn't support.
+    let err = RegexBuilder::new(r"a[]").dialect(Dialect::JavaScript).build();
+    assert!(err.is_err());
+}
+
+#[test]
+fn dialect_nonexhaustive() {
+    use regex::{Dialect, RegexBuilder};
+
+    let err = RegexBuilder::new(r"a")
+        .dialect(Dialect::__Nonexhaustive)
+        .build()
+        .unwrap_err();
+    assert_eq!(err.to_string(), "unsupported regex dialect");
+}
+
+#[test]
+fn unicode_property_custom() {
+    use regex::RegexBuilder;
+
+    let vowels = &[('a', 'a'), ('e', 'e'), ('i', 'i'), ('o', 'o'), ('u', 'u')];
+    let re = RegexBuilder::new(r"^\p{our_vowel}+\P{OurVowel}$")
+        .unicode_property("OurVowel", vowels)
+        .build()
+        .unwrap();
+    assert!(re.is_match("aeiz"));
+    assert!(!re.is_match("aeiou"));
+
+    let re = RegexBuilder::new(r"\p{OurVowel}")
+        .unicode_property("OurVowel", vowels)
+        .case_insensitive(true)
+        .build();
+    #[cfg(feature = "unicode-case")]
+    assert!(re.unwrap().is_match("E"));
+    #[cfg(not(feature = "unicode-case"))]
+    assert!(re.is_err());
+
+    let set = regex::RegexSetBuilder::new(&[r"\p{OurVowel}", "z"])
+        .unicode_property("OurVowel", vowels)
+        .build()
+        .unwrap();
+    assert_eq!(vec![0], set.matches("xoy").into_iter().collect::<Vec<_>>());
+
+    assert!(RegexBuilder::new(r"\p{OurVowel}").build().is_err());
+}
+
 #[test]
 fn oibits() {
     use regex::bytes;
-    use regex::{Regex, RegexBuilder};
+    use regex::{DynamicRegexSet, Regex, RegexBuilder};
     use std::panic::UnwindSafe;
 
     fn assert_send<T: Send>() {}
@@ -102,6 +268,13 @@ fn oibits() {
     assert_send::<bytes::RegexBuilder>();
     assert_sync::<bytes::RegexBuilder>();
     assert_unwind_safe::<bytes::RegexBuilder>();
+
+    assert_send::<DynamicRegexSet>();
+    assert_sync::<DynamicRegexSet>();
+    assert_unwind_safe::<DynamicRegexSet>();
+    assert_send::<bytes::DynamicRegexSet>();
+    assert_sync::<bytes::DynamicRegexSet>();
+    assert_unwind_safe::<bytes::DynamicRegexSet>();
 }
 
 // See: https://github.com/rust-lang/regex/issues/568
@@ -112,3 +285,232 @@ fn oibits_regression() {
 
     let _ = panic::catch_unwind(|| Regex::new("a").unwrap());
 }
+
+#[test]
+#[cfg(feature = "unicode")]
+fn generated_strings_match() {
+    use regex::bytes::Regex;
+    use regex_syntax::hir::generate::Generator;
+    use regex_syntax::ParserBuilder;
+
+    let patterns = &[
+        r"[a-c]+x?",
+        r"(?:foo|ba[rz])\d{1,3}",
+        r"\w+\b.\S",
+        r"(?m)^a$\n^\pL",
+        r"(?-u:[\x00-\xFF]{2})",
+        r"(?i)k\s*Σ",
+    ];
+    for &pattern in patterns {
+        let hir = ParserBuilder::new()
+            .allow_invalid_utf8(true)
+            .build()
+            .parse(pattern)
+            .unwrap();
+        let re = Regex::new(&format!("^(?:{})$", pattern)).unwrap();
+        let mut gen = Generator::new();
+        gen.max_length(6);
+        let strings = gen.enumerate(&hir, 200);
+        assert!(!strings.is_empty(), "nothing enumerated for {:?}", pattern);
+        for s in strings {
+            assert!(re.is_match(&s), "{:?} does not match {:?}", s, pattern);
+        }
+        let mut sampler = gen.sampler(&hir, 0x5EED);
+        for _ in 0..200 {
+            let s = sampler.sample().unwrap();
+            assert!(re.is_match(&s), "{:?} does not match {:?}", s, pattern);
+        }
+    }
+}
+
+#[test]
+fn explicit_cache() {
+    use regex::{bytes, Regex};
+
+    let re = Regex::new(r"(?P<word>[a-z]+)([0-9]+)").unwrap();
+    let mut cache = re.create_cache();
+    assert!(re.is_match_with_cache(&mut cache, "abc123"));
+    assert!(!re.is_match_with_cache(&mut cache, "123abc"));
+    let m = re.find_with_cache(&mut cache, "--ab12--").unwrap();
+    assert_eq!((m.start(), m.end()), (2, 6));
+    let caps = re.captures_with_cache(&mut cache, "x ab12").unwrap();
+    assert_eq!(&caps["word"], "ab");
+    assert_eq!(&caps[2], "12");
+
+    // A cache created by one regex may be used with another.
+    let other = Regex::new(r"z+").unwrap();
+    assert!(other.is_match_with_cache(&mut cache, "zzz"));
+    assert!(re.is_match_with_cache(&mut cache, "abc123"));
+    // ... as may a cache created by a clone.
+    assert!(re.clone().is_match_with_cache(&mut cache, "abc123"));
+
+    let re = bytes::Regex::new(r"(?-u)[\xFF]+").unwrap();
+    let mut cache = re.create_cache();
+    let m = re.find_with_cache(&mut cache, b"a\xFF\xFFb").unwrap();
+    assert_eq!((m.start(), m.end()), (1, 3));
+    assert!(re.captures_with_cache(&mut cache, b"abc").is_none());
+}
+
+#[test]
+fn explicit_cache_iter() {
+    use regex::{Regex, RegexSet};
+
+    let re = Regex::new(r"[a-z]+([0-9])").unwrap();
+    let mut cache = re.create_cache();
+    let ms: Vec<&str> = re
+        .find_iter_with_cache(&mut cache, "a1 bc2 3 d4")
+        .map(|m| m.as_str())
+        .collect();
+    assert_eq!(ms, vec!["a1", "bc2", "d4"]);
+    let digits: Vec<&str> = re
+        .captures_iter_with_cache(&mut cache, "a1 bc2 3 d4")
+        .map(|caps| caps.get(1).unwrap().as_str())
+        .collect();
+    assert_eq!(digits, vec!["1", "2", "4"]);
+    assert_eq!(re.shortest_match_with_cache(&mut cache, "xy9"), Some(3));
+    let end = re.shortest_match_at_with_cache(&mut cache, "a1 b2", 2);
+    assert_eq!(end, Some(5));
+    let m = re.find_at_with_cache(&mut cache, "a1 bc2", 1).unwrap();
+    assert_eq!((m.start(), m.end()), (3, 6));
+    let mut locs = re.capture_locations();
+    let m = re
+        .captures_read_at_with_cache(&mut cache, &mut locs, "a1 bc2", 2)
+        .unwrap();
+    assert_eq!((m.start(), m.end()), (3, 6));
+    assert_eq!(locs.get(1), Some((5, 6)));
+
+    let set = RegexSet::new(&[r"[a-z]+", r"[0-9]+", r"z"]).unwrap();
+    let mut cache = set.create_cache();
+    assert!(set.is_match_with_cache(&mut cache, "abc"));
+    assert!(!set.is_match_with_cache(&mut cache, "!!!"));
+    let matches: Vec<usize> =
+        set.matches_with_cache(&mut cache, "a1").into_iter().collect();
+    assert_eq!(matches, vec![0, 1]);
+}
+
+#[test]
+fn explicit_cache_memory() {
+    use regex::Regex;
+    use std::sync::Arc;
+    use std::thread;
+
+    let re = Arc::new(Regex::new(r"[a-z]+[0-9]+").unwrap());
+    let mut cache = re.create_cache();
+    let before = cache.memory_usage();
+    assert!(re.is_match_with_cache(&mut cache, "abc123"));
+    assert!(cache.memory_usage() >= before);
+
+    // A cache can be moved to (and reused by) another thread.
+    let handle = {
+        let re = re.clone();
+        thread::spawn(move || {
+            assert!(re.is_match_with_cache(&mut cache, "xyz789"));
+            cache
+        })
+    };
+    let mut cache = handle.join().unwrap();
+    cache.clear();
+    assert!(re.is_match_with_cache(&mut cache, "abc123"));
+}
+
+#[test]
+fn dynamic_set_add_remove() {
+    use regex::RegexSetBuilder;
+
+    let mut set = RegexSetBuilder::new(&["a", "b", "c"])
+        .shard_size(2)
+        .build_dynamic()
+        .unwrap();
+    assert_eq!(set.shard_count(), 2);
+    assert_eq!(set.add("d").unwrap(), 3);
+    assert_eq!(set.shard_count(), 2);
+    assert_eq!(set.add_many(&["e", "f", "g"]).unwrap(), 4..7);
+    assert_eq!(set.shard_count(), 4);
+    let matches: Vec<usize> = set.matches("bdg").into_iter().collect();
+    assert_eq!(matches, vec![1, 3, 6]);
+
+    // Removing a pattern keeps the IDs of every other pattern.
+    assert_eq!(set.remove(3), Some("d".to_string()));
+    assert_eq!(set.remove(3), None);
+    assert_eq!(set.len(), 6);
+    assert_eq!(set.pattern(3), None);
+    assert_eq!(set.pattern(4), Some("e"));
+    let matches = set.matches("bdg");
+    assert_eq!(matches.len(), 7);
+    assert_eq!(matches.into_iter().collect::<Vec<usize>>(), vec![1, 6]);
+    assert_eq!(set.shard_count(), 4);
+
+    // Emptied shards are dropped and small neighbors are merged.
+    set.remove(6);
+    assert_eq!(set.shard_count(), 3);
+    set.remove(5);
+    assert_eq!(set.shard_count(), 2);
+    assert!(set.is_match("e"));
+    assert!(!set.is_match("f"));
+
+    // A failed addition leaves the set unchanged.
+    assert!(set.add("(").is_err());
+    assert!(set.add_many(&["x", "("]).is_err());
+    assert_eq!(set.len(), 4);
+    assert_eq!(set.add("h").unwrap(), 7);
+    assert!(set.matches("h").matched(7));
+}
+
+#[test]
+fn dynamic_set_agrees_with_set() {
+    use regex::{RegexSet, RegexSetBuilder};
+
+    let patterns = &[
+        r"^/admin",
+        r"\.php$",
+        r"[0-9]{3}",
+        r"(?i)select",
+        r"foo|bar",
+        r"\bwp-[a-z]+",
+        r"^$",
+        r"a+b",
+    ];
+    let texts = &["/admin/x.php", "SELECT 123", "", "aaab wp-login", "baz"];
+    let set = RegexSet::new(patterns).unwrap();
+    let dynamic =
+        RegexSetBuilder::new(patterns).shard_size(3).build_dynamic().unwrap();
+    for text in texts {
+        let expected: Vec<usize> = set.matches(text).into_iter().collect();
+        let got: Vec<usize> = dynamic.matches(text).into_iter().collect();
+        assert_eq!(expected, got, "text: {:?}", text);
+        assert_eq!(set.is_match(text), dynamic.is_match(text));
+    }
+}
+
+#[test]
+fn dynamic_set_churn() {
+    use regex::RegexSetBuilder;
+
+    // Replacing the oldest pattern over and over keeps the size of the set,
+    // and the number of its shards, bounded.
+    let patterns: Vec<String> = (0..20).map(|i| format!("x{}y", i)).collect();
+    let mut set = RegexSetBuilder::new(&patterns)
+        .shard_size(4)
+        .build_dynamic()
+        .unwrap();
+    for i in 20..2000 {
+        assert_eq!(set.add(&format!("x{}y", i)).unwrap(), i);
+        assert!(set.remove(i - 20).is_some());
+        assert_eq!(set.len(), 20);
+        // No two neighboring shards fit into one, so at most every other
+        // shard is less than half full.
+        assert!(set.shard_count() <= 11, "shards: {}", set.shard_count());
+    }
+    assert_eq!(set.len(), 20);
+    assert_eq!(set.pattern(1979), None);
+    assert_eq!(set.pattern(1980), Some("x1980y"));
+
+    let matches = set.matches("x5y x1985y x1999y");
+    assert_eq!(matches.len(), 2000);
+    assert!(!matches.matched(5));
+    assert!(matches.matched(1985));
+    let ids: Vec<usize> = matches.iter().collect();
+    assert_eq!(ids, vec![1985, 1999]);
+    let ids: Vec<usize> = matches.into_iter().rev().collect();
+    assert_eq!(ids, vec![1999, 1985]);
+}
@@ -58,15 +58,19 @@ mat!(
     None
 );
 
+mod anchored_reverse;
 mod api;
 mod bytes;
 mod crazy;
 mod flags;
 mod fowler;
+mod inner_literal;
 mod multiline;
 mod noparse;
 mod regression;
 mod replace;
+#[cfg(all(feature = "unicode-segment", feature = "unicode-bool"))]
+mod segment;
 mod set;
 mod shortest_match;
 mod suffix_reverse;
@@ -39,15 +39,19 @@ macro_rules! regex_set {
 include!("macros_bytes.rs");
 include!("macros.rs");
 
+mod anchored_reverse;
 mod api;
 mod bytes;
 mod crazy;
 mod flags;
 mod fowler;
+mod inner_literal;
 mod multiline;
 mod noparse;
 mod regression;
 mod replace;
+#[cfg(all(feature = "unicode-segment", feature = "unicode-bool"))]
+mod segment;
 mod set;
 mod suffix_reverse;
 #[cfg(feature = "unicode")]
@@ -37,16 +37,20 @@ macro_rules! regex_set {
 include!("macros_str.rs");
 include!("macros.rs");
 
+mod anchored_reverse;
 mod api;
 mod api_str;
 mod crazy;
 mod flags;
 mod fowler;
+mod inner_literal;
 mod multiline;
 mod noparse;
 mod regression;
 mod replace;
 mod searcher;
+#[cfg(all(feature = "unicode-segment", feature = "unicode-bool"))]
+mod segment;
 mod set;
 mod suffix_reverse;
 #[cfg(feature = "unicode")]
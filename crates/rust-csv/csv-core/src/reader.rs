@@ -154,7 +154,7 @@ impl Default for Reader {
 /// This builder can be used to tweak the field delimiter, record terminator
 /// and more for parsing CSV. Once a CSV `Reader` is built, its configuration
 /// cannot be changed.
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct ReaderBuilder {
     rdr: Reader,
 }
@@ -505,6 +505,28 @@ impl Reader {
         self.line = line;
     }
 
+    /// Returns the record terminator used by this parser.
+    pub fn terminator(&self) -> Terminator {
+        self.term
+    }
+
+    /// Returns true if the parser is in the middle of a quoted field.
+    ///
+    /// When all CSV data has been given to the parser, but the empty input
+    /// buffer that terminates it has not, this indicates that the closing
+    /// quote of the last field is missing.
+    pub fn is_in_quoted_field(&self) -> bool {
+        if self.use_nfa {
+            match self.nfa_state {
+                NfaState::InQuotedField | NfaState::InEscapedQuote => true,
+                _ => false,
+            }
+        } else {
+            let escaped = self.dfa.new_state(NfaState::InEscapedQuote);
+            self.dfa_state == self.dfa.in_quoted || self.dfa_state == escaped
+        }
+    }
+
     /// Parse a single CSV field in `input` and copy field data to `output`.
     ///
     /// This routine requires a caller provided buffer of CSV data as the
@@ -1938,6 +1960,34 @@ mod tests {
         assert_eq!(6, rdr.line());
     }
 
+    // Test that unterminated quoted fields are reported.
+    #[test]
+    fn in_quoted_field() {
+        use crate::ReadFieldResult::*;
+
+        for &nfa in &[false, true] {
+            let out = &mut [0; 10];
+            let mut rdr = ReaderBuilder::new().nfa(nfa).build();
+            assert!(!rdr.is_in_quoted_field());
+
+            assert_read!(
+                rdr,
+                b("a,\"b"),
+                out,
+                2,
+                1,
+                Field { record_end: false }
+            );
+            assert!(!rdr.is_in_quoted_field());
+            assert_read!(rdr, b("\"b"), out, 2, 1, InputEmpty);
+            assert!(rdr.is_in_quoted_field());
+            assert_read!(rdr, b("\""), out, 1, 0, InputEmpty);
+            assert!(!rdr.is_in_quoted_field());
+            assert_read!(rdr, &[], out, 0, 0, Field { record_end: true });
+            assert!(!rdr.is_in_quoted_field());
+        }
+    }
+
     macro_rules! assert_read_record {
         (
             $rdr:expr, $input:expr, $output:expr, $ends:expr,
@@ -0,0 +1,1076 @@
+use std::marker::PhantomData;
+use std::mem;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use csv_core::Reader as CoreReader;
+use futures_core::{ready, Stream};
+use futures_io::{AsyncBufRead, AsyncRead};
+use futures_util::future::poll_fn;
+use futures_util::io::BufReader;
+use serde::de::DeserializeOwned;
+
+use crate::byte_record::{ByteRecord, Position};
+use crate::error::Result;
+use crate::reader::{ReaderBuilder, ReaderState};
+use crate::string_record::StringRecord;
+
+/// An already configured CSV reader over an asynchronous source of bytes.
+///
+/// This is the asynchronous counterpart to [`Reader`](struct.Reader.html).
+/// It reads CSV data from any type implementing `futures::io::AsyncRead`,
+/// and otherwise behaves exactly like a `Reader` built from the same
+/// configuration: headers, trimming, flexible record lengths, error handling
+/// and position tracking all work the same way. The main difference is that
+/// methods which read data are `async`, and the `records`, `byte_records`
+/// and `deserialize` iterators are exposed as `Stream`s instead.
+///
+/// Records may be split across the chunks yielded by the underlying reader
+/// at arbitrary boundaries. Moreover, every read is cancellation safe: if a
+/// read future is dropped before it completes, then any partially parsed
+/// record is retained by the reader and the next read picks up where the
+/// previous one left off.
+///
+/// This requires the `async` feature.
+///
+/// # Example
+///
+/// ```
+/// use std::error::Error;
+/// use csv::AsyncReader;
+/// use futures::executor::block_on;
+/// use futures::stream::TryStreamExt;
+///
+/// # fn main() { block_on(example()).unwrap(); }
+/// async fn example() -> Result<(), Box<dyn Error>> {
+///     let data = "\
+/// city,country,pop
+/// Boston,United States,4628910
+/// Concord,United States,42695
+/// ";
+///     let mut rdr = AsyncReader::from_reader(data.as_bytes());
+///     let records: Vec<csv::StringRecord> =
+///         rdr.records().try_collect().await?;
+///     assert_eq!(records, vec![
+///         vec!["Boston", "United States", "4628910"],
+///         vec!["Concord", "United States", "42695"],
+///     ]);
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct AsyncReader<R> {
+    /// The underlying CSV parser.
+    ///
+    /// This is on the heap for the same reason as in `Reader`.
+    core: Box<CoreReader>,
+    /// The underlying reader.
+    rdr: BufReader<R>,
+    /// Various state tracking.
+    ///
+    /// This is the same state used by the synchronous `Reader`. Seeking
+    /// isn't supported, so `seeked` is never set.
+    state: ReaderState,
+    /// The record currently being parsed.
+    ///
+    /// Parsing a single record may require polling the underlying reader
+    /// many times, any of which may return `Pending`. Keeping the partial
+    /// record here, rather than in the caller's record, means a dropped read
+    /// future never loses data that has already been consumed.
+    partial: ByteRecord,
+    /// The number of field bytes written to `partial` so far.
+    outlen: usize,
+    /// The number of field ends written to `partial` so far.
+    endlen: usize,
+    /// Whether `partial` contains a record that is still being parsed.
+    in_record: bool,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<R: AsyncRead + Unpin> AsyncReader<R> {
+    /// Create a new asynchronous CSV reader given a builder and a source of
+    /// underlying bytes.
+    pub(crate) fn new(builder: &ReaderBuilder, rdr: R) -> AsyncReader<R> {
+        AsyncReader {
+            core: Box::new(builder.builder.build()),
+            rdr: BufReader::with_capacity(builder.capacity, rdr),
+            state: ReaderState::new(builder),
+            partial: ByteRecord::new(),
+            outlen: 0,
+            endlen: 0,
+            in_record: false,
+        }
+    }
+
+    /// Create a new asynchronous CSV parser with a default configuration for
+    /// the given reader.
+    ///
+    /// To customize CSV parsing, use a `ReaderBuilder` and its
+    /// `from_async_reader` method.
+    pub fn from_reader(rdr: R) -> AsyncReader<R> {
+        ReaderBuilder::new().from_async_reader(rdr)
+    }
+
+    /// Returns a borrowed stream over deserialized records.
+    ///
+    /// Each item yielded by this stream is a `Result<D, Error>`. This
+    /// behaves like `Reader::deserialize`. In particular, if `has_headers`
+    /// is enabled, then the header row is read when the stream is first
+    /// polled and is used to match up the values in each row to the fields
+    /// of a struct.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::error::Error;
+    ///
+    /// use csv::AsyncReader;
+    /// use futures::executor::block_on;
+    /// use futures::stream::TryStreamExt;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, Eq, PartialEq)]
+    /// struct Row {
+    ///     city: String,
+    ///     country: String,
+    ///     #[serde(rename = "popcount")]
+    ///     population: u64,
+    /// }
+    ///
+    /// # fn main() { block_on(example()).unwrap(); }
+    /// async fn example() -> Result<(), Box<dyn Error>> {
+    ///     let data = "\
+    /// city,country,popcount
+    /// Boston,United States,4628910
+    /// ";
+    ///     let mut rdr = AsyncReader::from_reader(data.as_bytes());
+    ///     let rows: Vec<Row> = rdr.deserialize().try_collect().await?;
+    ///     assert_eq!(rows, vec![Row {
+    ///         city: "Boston".to_string(),
+    ///         country: "United States".to_string(),
+    ///         population: 4628910,
+    ///     }]);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn deserialize<D>(&mut self) -> DeserializeRecordsStream<R, D>
+    where
+        D: DeserializeOwned,
+    {
+        DeserializeRecordsStream::new(self)
+    }
+
+    /// Returns an owned stream over deserialized records.
+    ///
+    /// This is mostly useful when you want to return a CSV stream or store
+    /// it somewhere.
+    pub fn into_deserialize<D>(self) -> DeserializeRecordsIntoStream<R, D>
+    where
+        D: DeserializeOwned,
+    {
+        DeserializeRecordsIntoStream::new(self)
+    }
+
+    /// Returns a borrowed stream over all records as strings.
+    ///
+    /// Each item yielded by this stream is a `Result<StringRecord, Error>`.
+    ///
+    /// If `has_headers` was enabled via a `ReaderBuilder` (which is the
+    /// default), then this does not include the first record.
+    pub fn records(&mut self) -> StringRecordsStream<R> {
+        StringRecordsStream::new(self)
+    }
+
+    /// Returns an owned stream over all records as strings.
+    pub fn into_records(self) -> StringRecordsIntoStream<R> {
+        StringRecordsIntoStream::new(self)
+    }
+
+    /// Returns a borrowed stream over all records as raw bytes.
+    ///
+    /// Each item yielded by this stream is a `Result<ByteRecord, Error>`.
+    ///
+    /// If `has_headers` was enabled via a `ReaderBuilder` (which is the
+    /// default), then this does not include the first record.
+    pub fn byte_records(&mut self) -> ByteRecordsStream<R> {
+        ByteRecordsStream::new(self)
+    }
+
+    /// Returns an owned stream over all records as raw bytes.
+    pub fn into_byte_records(self) -> ByteRecordsIntoStream<R> {
+        ByteRecordsIntoStream::new(self)
+    }
+
+    /// Returns a reference to the first row read by this parser.
+    ///
+    /// If no row has been read yet, then this will force parsing of the first
+    /// row.
+    ///
+    /// If there was a problem parsing the row or if it wasn't valid UTF-8,
+    /// then this returns an error.
+    ///
+    /// If the underlying reader emits EOF before any data, then this returns
+    /// an empty record.
+    pub async fn headers(&mut self) -> Result<&StringRecord> {
+        poll_fn(|cx| self.poll_headers(cx)).await?;
+        self.state.headers.as_ref().unwrap().as_string_record()
+    }
+
+    /// Returns a reference to the first row read by this parser as raw bytes.
+    ///
+    /// If no row has been read yet, then this will force parsing of the first
+    /// row.
+    ///
+    /// If there was a problem parsing the row then this returns an error.
+    ///
+    /// If the underlying reader emits EOF before any data, then this returns
+    /// an empty record.
+    pub async fn byte_headers(&mut self) -> Result<&ByteRecord> {
+        poll_fn(|cx| self.poll_headers(cx)).await?;
+        Ok(&self.state.headers.as_ref().unwrap().byte_record)
+    }
+
+    /// Set the headers of this CSV parser manually.
+    ///
+    /// This overrides any other setting (including `set_byte_headers`). Any
+    /// automatic detection of headers is disabled. This may be called at any
+    /// time.
+    pub fn set_headers(&mut self, headers: StringRecord) {
+        self.state.set_headers_by_caller(Ok(headers));
+    }
+
+    /// Set the headers of this CSV parser manually as raw bytes.
+    ///
+    /// This overrides any other setting (including `set_headers`). Any
+    /// automatic detection of headers is disabled. This may be called at any
+    /// time.
+    pub fn set_byte_headers(&mut self, headers: ByteRecord) {
+        self.state.set_headers_by_caller(Err(headers));
+    }
+
+    /// Read a single row into the given record. Returns false when no more
+    /// records could be read.
+    ///
+    /// If `has_headers` was enabled via a `ReaderBuilder` (which is the
+    /// default), then this will never read the first record.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::error::Error;
+    /// use csv::{AsyncReader, StringRecord};
+    /// use futures::executor::block_on;
+    ///
+    /// # fn main() { block_on(example()).unwrap(); }
+    /// async fn example() -> Result<(), Box<dyn Error>> {
+    ///     let data = "\
+    /// city,country,pop
+    /// Boston,United States,4628910
+    /// ";
+    ///     let mut rdr = AsyncReader::from_reader(data.as_bytes());
+    ///     let mut record = StringRecord::new();
+    ///
+    ///     if rdr.read_record(&mut record).await? {
+    ///         assert_eq!(record, vec!["Boston", "United States", "4628910"]);
+    ///         Ok(())
+    ///     } else {
+    ///         Err(From::from("expected at least one record but got none"))
+    ///     }
+    /// }
+    /// ```
+    pub async fn read_record(
+        &mut self,
+        record: &mut StringRecord,
+    ) -> Result<bool> {
+        poll_fn(|cx| self.poll_read_record(cx, record)).await
+    }
+
+    /// Read a single row into the given byte record. Returns false when no
+    /// more records could be read.
+    ///
+    /// If `has_headers` was enabled via a `ReaderBuilder` (which is the
+    /// default), then this will never read the first record.
+    pub async fn read_byte_record(
+        &mut self,
+        record: &mut ByteRecord,
+    ) -> Result<bool> {
+        poll_fn(|cx| self.poll_read_byte_record(cx, record)).await
+    }
+
+    /// Return the current position of this CSV reader.
+    ///
+    /// Like the position of a `Reader`, this is only meaningful immediately
+    /// before the start of a record.
+    pub fn position(&self) -> &Position {
+        &self.state.cur_pos
+    }
+
+    /// Returns true if and only if this reader has been exhausted.
+    pub fn is_done(&self) -> bool {
+        self.state.eof
+    }
+
+    /// Returns true if and only if this reader has been configured to
+    /// interpret the first record as a header record.
+    pub fn has_headers(&self) -> bool {
+        self.state.has_headers
+    }
+
+    /// Returns a reference to the underlying reader.
+    pub fn get_ref(&self) -> &R {
+        self.rdr.get_ref()
+    }
+
+    /// Returns a mutable reference to the underlying reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        self.rdr.get_mut()
+    }
+
+    /// Unwraps this CSV reader, returning the underlying reader.
+    ///
+    /// Note that any leftover data inside this reader's internal buffer is
+    /// lost.
+    pub fn into_inner(self) -> R {
+        self.rdr.into_inner()
+    }
+
+    /// Ensure the header row has been read.
+    fn poll_headers(&mut self, cx: &mut Context) -> Poll<Result<()>> {
+        if self.state.headers.is_none() {
+            let mut record = ByteRecord::new();
+            ready!(self.poll_read_byte_record_impl(cx, &mut record))?;
+            self.state.set_headers(Err(record));
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    /// Read the headers used to deserialize records, if any.
+    ///
+    /// As with `Reader::deserialize`, a header row that can't be read is
+    /// treated as if there were no header row at all.
+    fn poll_deserialize_headers(
+        &mut self,
+        cx: &mut Context,
+    ) -> Poll<Option<StringRecord>> {
+        if !self.state.has_headers {
+            return Poll::Ready(None);
+        }
+        Poll::Ready(match ready!(self.poll_headers(cx)) {
+            Err(_) => None,
+            Ok(()) => {
+                let headers = self.state.headers.as_ref().unwrap();
+                headers.as_string_record().ok().map(Clone::clone)
+            }
+        })
+    }
+
+    fn poll_read_record(
+        &mut self,
+        cx: &mut Context,
+        record: &mut StringRecord,
+    ) -> Poll<Result<bool>> {
+        let result = ready!(record.poll_read(cx, self));
+        // See `Reader::read_record` for why we trim again.
+        if self.state.trim.should_trim_fields() {
+            record.trim();
+        }
+        Poll::Ready(result)
+    }
+
+    /// Poll for the next record, accounting for headers.
+    ///
+    /// This mirrors `Reader::read_byte_record`, but is written so that it
+    /// may be called again after returning `Pending`.
+    pub(crate) fn poll_read_byte_record(
+        &mut self,
+        cx: &mut Context,
+        record: &mut ByteRecord,
+    ) -> Poll<Result<bool>> {
+        let result = ready!(self.poll_read_byte_record_headers(cx, record));
+        self.state.attach_headers(record);
+        Poll::Ready(result)
+    }
+
+    fn poll_read_byte_record_headers(
+        &mut self,
+        cx: &mut Context,
+        record: &mut ByteRecord,
+    ) -> Poll<Result<bool>> {
+        let trim = self.state.trim.should_trim_fields();
+        if !self.state.has_headers && !self.state.first {
+            // If the caller indicated "no headers" and we haven't yielded the
+            // first record yet, then we should yield our header row if we have
+            // one.
+            if let Some(ref headers) = self.state.headers {
+                self.state.first = true;
+                record.clone_from(&headers.byte_record);
+                if trim {
+                    record.trim();
+                }
+                return Poll::Ready(Ok(!record.is_empty()));
+            }
+        }
+        if self.state.headers.is_none() {
+            let ok = ready!(self.poll_read_byte_record_impl(cx, record))?;
+            self.state.first = true;
+            self.state.set_headers(Err(record.clone()));
+            // Only return the first row if the end user indicated that it
+            // isn't a header row. Otherwise, the next row is read below,
+            // which is also where we resume if that read returns `Pending`.
+            if !self.state.has_headers {
+                if trim {
+                    record.trim();
+                }
+                return Poll::Ready(Ok(ok));
+            }
+        }
+        let result = ready!(self.poll_read_byte_record_impl(cx, record));
+        self.state.first = true;
+        if trim {
+            record.trim();
+        }
+        Poll::Ready(result)
+    }
+
+    /// Poll for the next byte record from the underlying CSV reader, without
+    /// accounting for headers.
+    fn poll_read_byte_record_impl(
+        &mut self,
+        cx: &mut Context,
+        record: &mut ByteRecord,
+    ) -> Poll<Result<bool>> {
+        use csv_core::ReadRecordResult::*;
+
+        if self.state.eof {
+            record.clear();
+            record.set_position(Some(self.state.cur_pos.clone()));
+            return Poll::Ready(Ok(false));
+        }
+        if !self.in_record {
+            self.partial.clear();
+            self.partial.set_position(Some(self.state.cur_pos.clone()));
+            self.outlen = 0;
+            self.endlen = 0;
+            self.in_record = true;
+        }
+        loop {
+            let (res, nin, nout, nend) = {
+                let input =
+                    match ready!(Pin::new(&mut self.rdr).poll_fill_buf(cx)) {
+                        Ok(input) => input,
+                        Err(err) => {
+                            // Hand over whatever we have so far, just like
+                            // `Reader` does when reading fails part way
+                            // through a record.
+                            self.in_record = false;
+                            mem::swap(record, &mut self.partial);
+                            return Poll::Ready(Err(err.into()));
+                        }
+                    };
+                let (fields, ends) = self.partial.as_parts();
+                self.core.read_record(
+                    input,
+                    &mut fields[self.outlen..],
+                    &mut ends[self.endlen..],
+                )
+            };
+            Pin::new(&mut self.rdr).consume(nin);
+            let byte = self.state.cur_pos.byte();
+            self.state
+                .cur_pos
+                .set_byte(byte + nin as u64)
+                .set_line(self.core.line());
+            self.outlen += nout;
+            self.endlen += nend;
+            match res {
+                InputEmpty => continue,
+                OutputFull => {
+                    self.partial.expand_fields();
+                    continue;
+                }
+                OutputEndsFull => {
+                    self.partial.expand_ends();
+                    continue;
+                }
+                Record => {
+                    self.in_record = false;
+                    self.partial.set_len(self.endlen);
+                    mem::swap(record, &mut self.partial);
+                    let result = self
+                        .state
+                        .project(record)
+                        .and_then(|()| self.state.add_record(record));
+                    return Poll::Ready(result.map(|()| true));
+                }
+                End => {
+                    self.in_record = false;
+                    self.state.eof = true;
+                    mem::swap(record, &mut self.partial);
+                    return Poll::Ready(Ok(false));
+                }
+            }
+        }
+    }
+}
+
+/// An owned stream over deserialized records.
+///
+/// The type parameter `R` refers to the underlying `AsyncRead` type, and `D`
+/// refers to the type that this stream will deserialize a record into.
+pub struct DeserializeRecordsIntoStream<R, D> {
+    rdr: AsyncReader<R>,
+    rec: StringRecord,
+    /// The headers used for deserialization. This is `None` until the
+    /// stream is first polled.
+    headers: Option<Option<StringRecord>>,
+    _priv: PhantomData<fn() -> D>,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<R: AsyncRead + Unpin, D: DeserializeOwned>
+    DeserializeRecordsIntoStream<R, D>
+{
+    fn new(rdr: AsyncReader<R>) -> DeserializeRecordsIntoStream<R, D> {
+        DeserializeRecordsIntoStream {
+            rdr: rdr,
+            rec: StringRecord::new(),
+            headers: None,
+            _priv: PhantomData,
+        }
+    }
+
+    /// Return a reference to the underlying CSV reader.
+    pub fn reader(&self) -> &AsyncReader<R> {
+        &self.rdr
+    }
+
+    /// Return a mutable reference to the underlying CSV reader.
+    pub fn reader_mut(&mut self) -> &mut AsyncReader<R> {
+        &mut self.rdr
+    }
+
+    /// Drop this stream and return the underlying CSV reader.
+    pub fn into_reader(self) -> AsyncReader<R> {
+        self.rdr
+    }
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<R: AsyncRead + Unpin, D: DeserializeOwned> Stream
+    for DeserializeRecordsIntoStream<R, D>
+{
+    type Item = Result<D>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<D>>> {
+        let this = self.get_mut();
+        if this.headers.is_none() {
+            let headers = ready!(this.rdr.poll_deserialize_headers(cx));
+            this.headers = Some(headers);
+        }
+        let headers = this.headers.as_ref().unwrap().as_ref();
+        let res = ready!(this.rdr.poll_read_record(cx, &mut this.rec));
+        Poll::Ready(match res {
+            Err(err) => Some(Err(err)),
+            Ok(false) => None,
+            Ok(true) => Some(this.rec.deserialize(headers)),
+        })
+    }
+}
+
+/// A borrowed stream over deserialized records.
+///
+/// The lifetime parameter `'r` refers to the lifetime of the underlying
+/// CSV `AsyncReader`. The type parameter `R` refers to the underlying
+/// `AsyncRead` type, and `D` refers to the type that this stream will
+/// deserialize a record into.
+pub struct DeserializeRecordsStream<'r, R: 'r, D> {
+    rdr: &'r mut AsyncReader<R>,
+    rec: StringRecord,
+    /// The headers used for deserialization. This is `None` until the
+    /// stream is first polled.
+    headers: Option<Option<StringRecord>>,
+    _priv: PhantomData<fn() -> D>,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<'r, R: AsyncRead + Unpin, D: DeserializeOwned>
+    DeserializeRecordsStream<'r, R, D>
+{
+    fn new(rdr: &'r mut AsyncReader<R>) -> DeserializeRecordsStream<'r, R, D> {
+        DeserializeRecordsStream {
+            rdr: rdr,
+            rec: StringRecord::new(),
+            headers: None,
+            _priv: PhantomData,
+        }
+    }
+
+    /// Return a reference to the underlying CSV reader.
+    pub fn reader(&self) -> &AsyncReader<R> {
+        &self.rdr
+    }
+
+    /// Return a mutable reference to the underlying CSV reader.
+    pub fn reader_mut(&mut self) -> &mut AsyncReader<R> {
+        &mut self.rdr
+    }
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<'r, R: AsyncRead + Unpin, D: DeserializeOwned> Stream
+    for DeserializeRecordsStream<'r, R, D>
+{
+    type Item = Result<D>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<D>>> {
+        let this = self.get_mut();
+        if this.headers.is_none() {
+            let headers = ready!(this.rdr.poll_deserialize_headers(cx));
+            this.headers = Some(headers);
+        }
+        let headers = this.headers.as_ref().unwrap().as_ref();
+        let res = ready!(this.rdr.poll_read_record(cx, &mut this.rec));
+        Poll::Ready(match res {
+            Err(err) => Some(Err(err)),
+            Ok(false) => None,
+            Ok(true) => Some(this.rec.deserialize(headers)),
+        })
+    }
+}
+
+/// An owned stream over records as strings.
+pub struct StringRecordsIntoStream<R> {
+    rdr: AsyncReader<R>,
+    rec: StringRecord,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<R: AsyncRead + Unpin> StringRecordsIntoStream<R> {
+    fn new(rdr: AsyncReader<R>) -> StringRecordsIntoStream<R> {
+        StringRecordsIntoStream { rdr: rdr, rec: StringRecord::new() }
+    }
+
+    /// Return a reference to the underlying CSV reader.
+    pub fn reader(&self) -> &AsyncReader<R> {
+        &self.rdr
+    }
+
+    /// Return a mutable reference to the underlying CSV reader.
+    pub fn reader_mut(&mut self) -> &mut AsyncReader<R> {
+        &mut self.rdr
+    }
+
+    /// Drop this stream and return the underlying CSV reader.
+    pub fn into_reader(self) -> AsyncReader<R> {
+        self.rdr
+    }
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<R: AsyncRead + Unpin> Stream for StringRecordsIntoStream<R> {
+    type Item = Result<StringRecord>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<StringRecord>>> {
+        let this = self.get_mut();
+        let res = ready!(this.rdr.poll_read_record(cx, &mut this.rec));
+        Poll::Ready(match res {
+            Err(err) => Some(Err(err)),
+            Ok(true) => Some(Ok(this.rec.clone())),
+            Ok(false) => None,
+        })
+    }
+}
+
+/// A borrowed stream over records as strings.
+///
+/// The lifetime parameter `'r` refers to the lifetime of the underlying
+/// CSV `AsyncReader`.
+pub struct StringRecordsStream<'r, R: 'r> {
+    rdr: &'r mut AsyncReader<R>,
+    rec: StringRecord,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<'r, R: AsyncRead + Unpin> StringRecordsStream<'r, R> {
+    fn new(rdr: &'r mut AsyncReader<R>) -> StringRecordsStream<'r, R> {
+        StringRecordsStream { rdr: rdr, rec: StringRecord::new() }
+    }
+
+    /// Return a reference to the underlying CSV reader.
+    pub fn reader(&self) -> &AsyncReader<R> {
+        &self.rdr
+    }
+
+    /// Return a mutable reference to the underlying CSV reader.
+    pub fn reader_mut(&mut self) -> &mut AsyncReader<R> {
+        &mut self.rdr
+    }
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<'r, R: AsyncRead + Unpin> Stream for StringRecordsStream<'r, R> {
+    type Item = Result<StringRecord>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<StringRecord>>> {
+        let this = self.get_mut();
+        let res = ready!(this.rdr.poll_read_record(cx, &mut this.rec));
+        Poll::Ready(match res {
+            Err(err) => Some(Err(err)),
+            Ok(true) => Some(Ok(this.rec.clone())),
+            Ok(false) => None,
+        })
+    }
+}
+
+/// An owned stream over records as raw bytes.
+pub struct ByteRecordsIntoStream<R> {
+    rdr: AsyncReader<R>,
+    rec: ByteRecord,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<R: AsyncRead + Unpin> ByteRecordsIntoStream<R> {
+    fn new(rdr: AsyncReader<R>) -> ByteRecordsIntoStream<R> {
+        ByteRecordsIntoStream { rdr: rdr, rec: ByteRecord::new() }
+    }
+
+    /// Return a reference to the underlying CSV reader.
+    pub fn reader(&self) -> &AsyncReader<R> {
+        &self.rdr
+    }
+
+    /// Return a mutable reference to the underlying CSV reader.
+    pub fn reader_mut(&mut self) -> &mut AsyncReader<R> {
+        &mut self.rdr
+    }
+
+    /// Drop this stream and return the underlying CSV reader.
+    pub fn into_reader(self) -> AsyncReader<R> {
+        self.rdr
+    }
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<R: AsyncRead + Unpin> Stream for ByteRecordsIntoStream<R> {
+    type Item = Result<ByteRecord>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<ByteRecord>>> {
+        let this = self.get_mut();
+        let res = ready!(this.rdr.poll_read_byte_record(cx, &mut this.rec));
+        Poll::Ready(match res {
+            Err(err) => Some(Err(err)),
+            Ok(true) => Some(Ok(this.rec.clone())),
+            Ok(false) => None,
+        })
+    }
+}
+
+/// A borrowed stream over records as raw bytes.
+///
+/// The lifetime parameter `'r` refers to the lifetime of the underlying
+/// CSV `AsyncReader`.
+pub struct ByteRecordsStream<'r, R: 'r> {
+    rdr: &'r mut AsyncReader<R>,
+    rec: ByteRecord,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<'r, R: AsyncRead + Unpin> ByteRecordsStream<'r, R> {
+    fn new(rdr: &'r mut AsyncReader<R>) -> ByteRecordsStream<'r, R> {
+        ByteRecordsStream { rdr: rdr, rec: ByteRecord::new() }
+    }
+
+    /// Return a reference to the underlying CSV reader.
+    pub fn reader(&self) -> &AsyncReader<R> {
+        &self.rdr
+    }
+
+    /// Return a mutable reference to the underlying CSV reader.
+    pub fn reader_mut(&mut self) -> &mut AsyncReader<R> {
+        &mut self.rdr
+    }
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<'r, R: AsyncRead + Unpin> Stream for ByteRecordsStream<'r, R> {
+    type Item = Result<ByteRecord>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+    ) -> Poll<Option<Result<ByteRecord>>> {
+        let this = self.get_mut();
+        let res = ready!(this.rdr.poll_read_byte_record(cx, &mut this.rec));
+        Poll::Ready(match res {
+            Err(err) => Some(Err(err)),
+            Ok(true) => Some(Ok(this.rec.clone())),
+            Ok(false) => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use futures::executor::block_on;
+    use futures::future::{poll_fn, FutureExt};
+    use futures::stream::TryStreamExt;
+    use futures::task::noop_waker_ref;
+    use futures_io::AsyncRead;
+    use serde::Deserialize;
+
+    use crate::byte_record::ByteRecord;
+    use crate::error::ErrorKind;
+    use crate::string_record::StringRecord;
+    use crate::{ReaderBuilder, Trim};
+
+    use super::AsyncReader;
+
+    /// An async reader that yields at most `chunk` bytes at a time, and
+    /// returns `Pending` before every chunk.
+    struct Trickle {
+        data: Vec<u8>,
+        pos: usize,
+        chunk: usize,
+        ready: bool,
+    }
+
+    impl Trickle {
+        fn new(data: &str, chunk: usize) -> Trickle {
+            Trickle {
+                data: data.as_bytes().to_vec(),
+                pos: 0,
+                chunk: chunk,
+                ready: false,
+            }
+        }
+    }
+
+    impl AsyncRead for Trickle {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            if !self.ready {
+                self.ready = true;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            self.ready = false;
+            let end = (self.pos + self.chunk).min(self.data.len());
+            let n = (end - self.pos).min(buf.len());
+            buf[..n].copy_from_slice(&self.data[self.pos..self.pos + n]);
+            self.pos += n;
+            Poll::Ready(Ok(n))
+        }
+    }
+
+    fn b(s: &str) -> &[u8] {
+        s.as_bytes()
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "not supported by asynchronous readers")]
+    fn lenient_unsupported() {
+        ReaderBuilder::new().lenient(true).from_async_reader(b("a,b\n"));
+    }
+
+    #[test]
+    fn read_records_in_chunks() {
+        let data = "h1,h2\nfoo,\"b,\nar\"\r\n\"qu\"\"ux\",baz\nlast,row";
+        for chunk in 1..data.len() + 1 {
+            let rdr = AsyncReader::from_reader(Trickle::new(data, chunk));
+            let records: Vec<StringRecord> =
+                block_on(rdr.into_records().try_collect()).unwrap();
+            assert_eq!(
+                records,
+                vec![
+                    vec!["foo", "b,\nar"],
+                    vec!["qu\"ux", "baz"],
+                    vec!["last", "row"],
+                ],
+                "chunk size: {}",
+                chunk
+            );
+        }
+    }
+
+    #[test]
+    fn agrees_with_sync_reader() {
+        let data = "a,b,c\n1,2,3\n\n  x , y ,z\n4,5,6\n";
+        let mut builder = ReaderBuilder::new();
+        builder.trim(Trim::All);
+
+        let expected: Vec<ByteRecord> = builder
+            .from_reader(b(data))
+            .byte_records()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let mut rdr = builder.from_async_reader(Trickle::new(data, 3));
+        let got: Vec<ByteRecord> =
+            block_on(rdr.byte_records().try_collect()).unwrap();
+        assert_eq!(expected, got);
+        assert_eq!(block_on(rdr.headers()).unwrap(), vec!["a", "b", "c"]);
+        assert!(rdr.is_done());
+        assert_eq!(rdr.position().line(), 6);
+        assert_eq!(rdr.position().record(), 4);
+    }
+
+    #[test]
+    fn read_record_no_headers() {
+        let data = "foo,bar\nbaz,quux";
+        let mut rdr = ReaderBuilder::new()
+            .has_headers(false)
+            .from_async_reader(Trickle::new(data, 2));
+        let mut rec = StringRecord::new();
+
+        assert_eq!(block_on(rdr.headers()).unwrap(), vec!["foo", "bar"]);
+        assert!(block_on(rdr.read_record(&mut rec)).unwrap());
+        assert_eq!(rec, vec!["foo", "bar"]);
+        assert!(block_on(rdr.read_record(&mut rec)).unwrap());
+        assert_eq!(rec, vec!["baz", "quux"]);
+        assert!(!block_on(rdr.read_record(&mut rec)).unwrap());
+        assert!(!block_on(rdr.read_record(&mut rec)).unwrap());
+    }
+
+    #[test]
+    fn read_record_unequal_fails() {
+        let data = "foo\nbar,baz";
+        let mut rdr = AsyncReader::from_reader(Trickle::new(data, 4));
+        let mut rec = StringRecord::new();
+
+        match block_on(rdr.read_record(&mut rec)) {
+            Err(err) => match *err.kind() {
+                ErrorKind::UnequalLengths {
+                    expected_len: 1,
+                    ref pos,
+                    len: 2,
+                } => {
+                    assert_eq!(pos.as_ref().map(|p| p.byte()), Some(4));
+                }
+                ref wrong => panic!("match failed, got {:?}", wrong),
+            },
+            wrong => panic!("match failed, got {:?}", wrong),
+        }
+    }
+
+    #[test]
+    fn read_record_invalid_utf8() {
+        let data = b"a,b\nfoo,\xFF\nbar,baz".to_vec();
+        let mut rdr = AsyncReader::from_reader(&data[..]);
+        let mut rec = StringRecord::new();
+
+        match block_on(rdr.read_record(&mut rec)) {
+            Err(err) => match *err.kind() {
+                ErrorKind::Utf8 { ref pos, ref err } => {
+                    assert_eq!(pos.as_ref().map(|p| p.byte()), Some(4));
+                    assert_eq!(err.field(), 1);
+                }
+                ref wrong => panic!("match failed, got {:?}", wrong),
+            },
+            wrong => panic!("match failed, got {:?}", wrong),
+        }
+        assert!(rec.is_empty());
+        assert!(block_on(rdr.read_record(&mut rec)).unwrap());
+        assert_eq!(rec, vec!["bar", "baz"]);
+    }
+
+    #[test]
+    fn dropped_read_keeps_partial_record() {
+        let data = "a,b\nfoo,bar\nbaz,quux\n";
+        let mut rdr = AsyncReader::from_reader(Trickle::new(data, 5));
+        let mut rec = StringRecord::new();
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        // Drive a read until it has consumed part of the first record, and
+        // then drop it.
+        loop {
+            let mut read = poll_fn(|cx| rdr.poll_read_record(cx, &mut rec));
+            assert!(read.poll_unpin(&mut cx).is_pending());
+            drop(read);
+            if rdr.position().byte() > 4 {
+                break;
+            }
+        }
+        assert!(rdr.position().byte() < 12);
+        assert!(block_on(rdr.read_record(&mut rec)).unwrap());
+        assert_eq!(rec, vec!["foo", "bar"]);
+        assert!(block_on(rdr.read_record(&mut rec)).unwrap());
+        assert_eq!(rec, vec!["baz", "quux"]);
+        assert!(!block_on(rdr.read_record(&mut rec)).unwrap());
+    }
+
+    #[test]
+    fn deserialize_with_headers() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Row {
+            y: i32,
+            x: String,
+        }
+
+        let data = "x,y\nfoo,1\nbar,2\n";
+        let rdr = AsyncReader::from_reader(Trickle::new(data, 1));
+        let rows: Vec<Row> =
+            block_on(rdr.into_deserialize().try_collect()).unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                Row { y: 1, x: "foo".to_string() },
+                Row { y: 2, x: "bar".to_string() },
+            ]
+        );
+    }
+
+    #[test]
+    fn deserialize_no_headers() {
+        let data = "foo,1\nbar,2\n";
+        let mut rdr = ReaderBuilder::new()
+            .has_headers(false)
+            .from_async_reader(Trickle::new(data, 3));
+        let rows: Vec<(String, i32)> =
+            block_on(rdr.deserialize().try_collect()).unwrap();
+        assert_eq!(rows, vec![("foo".to_string(), 1), ("bar".to_string(), 2)]);
+    }
+
+    #[test]
+    fn headers_on_empty_data() {
+        let mut rdr = AsyncReader::from_reader(b(""));
+        let r = block_on(rdr.byte_headers()).unwrap();
+        assert_eq!(r.len(), 0);
+        assert!(rdr.is_done());
+    }
+
+    #[test]
+    fn set_headers_before_reading() {
+        let data = "foo,bar\nbaz,quux";
+        let mut rdr = AsyncReader::from_reader(b(data));
+        rdr.set_headers(StringRecord::from(vec!["a", "b"]));
+        let mut rec = StringRecord::new();
+
+        assert!(block_on(rdr.read_record(&mut rec)).unwrap());
+        assert_eq!(rec, vec!["foo", "bar"]);
+        assert_eq!(block_on(rdr.headers()).unwrap(), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn select_columns() {
+        let data = "a,b,c\n1,2,3\n4,5,6\n";
+        for chunk in 1..data.len() + 1 {
+            let rdr = ReaderBuilder::new()
+                .select(vec!["c", "a"])
+                .from_async_reader(Trickle::new(data, chunk));
+            let records: Vec<StringRecord> =
+                block_on(rdr.into_records().try_collect()).unwrap();
+            assert_eq!(records, vec![vec!["1", "3"], vec!["4", "6"]]);
+            assert_eq!(records[1].get_by_name("c"), Some("6"));
+            assert_eq!(records[1].get_by_name("b"), None);
+        }
+    }
+}
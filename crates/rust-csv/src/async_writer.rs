@@ -0,0 +1,397 @@
+use std::io;
+use std::pin::Pin;
+use std::result;
+use std::task::{Context, Poll};
+
+use futures_core::ready;
+use futures_io::AsyncWrite;
+use futures_util::future::poll_fn;
+use serde::Serialize;
+
+use crate::byte_record::ByteRecord;
+use crate::error::{IntoInnerError, Result};
+use crate::writer::{Writer, WriterBuilder};
+
+/// An already configured CSV writer over an asynchronous sink of bytes.
+///
+/// This is the asynchronous counterpart to [`Writer`](struct.Writer.html).
+/// It writes CSV data to any type implementing `futures::io::AsyncWrite`,
+/// and otherwise behaves exactly like a `Writer` built from the same
+/// configuration. Records are encoded into an internal buffer, which is
+/// written to the underlying writer once it grows past the configured buffer
+/// capacity.
+///
+/// Unlike a `Writer`, an `AsyncWriter` cannot flush itself when it is
+/// dropped. Callers must call `flush` (or `into_inner`) once they are done
+/// writing records, or else any buffered data is lost.
+///
+/// This requires the `async` feature.
+///
+/// # Example
+///
+/// ```
+/// use std::error::Error;
+/// use csv::AsyncWriter;
+/// use futures::executor::block_on;
+///
+/// # fn main() { block_on(example()).unwrap(); }
+/// async fn example() -> Result<(), Box<dyn Error>> {
+///     let mut wtr = AsyncWriter::from_writer(vec![]);
+///     wtr.write_record(&["a", "b", "c"]).await?;
+///     wtr.write_record(&["x", "y", "z"]).await?;
+///
+///     let data = String::from_utf8(wtr.into_inner().await?)?;
+///     assert_eq!(data, "a,b,c\nx,y,z\n");
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct AsyncWriter<W> {
+    /// A synchronous CSV writer that encodes records into memory.
+    wtr: Writer<Vec<u8>>,
+    /// The underlying writer.
+    out: W,
+    /// The number of bytes at the start of the encoded buffer that have
+    /// already been written to `out`.
+    ///
+    /// Tracking this lets a write future be dropped part way through without
+    /// writing any byte twice.
+    pos: usize,
+    /// The number of encoded bytes to accumulate before writing to `out`.
+    capacity: usize,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<W: AsyncWrite + Unpin> AsyncWriter<W> {
+    /// Create a new asynchronous CSV writer given a builder and a sink of
+    /// underlying bytes.
+    pub(crate) fn new(builder: &WriterBuilder, wtr: W) -> AsyncWriter<W> {
+        AsyncWriter {
+            wtr: builder.from_writer(Vec::with_capacity(builder.capacity)),
+            out: wtr,
+            pos: 0,
+            capacity: builder.capacity,
+        }
+    }
+
+    /// Build an asynchronous CSV writer with a default configuration that
+    /// writes data to `wtr`.
+    ///
+    /// To customize CSV writing, use a `WriterBuilder` and its
+    /// `from_async_writer` method.
+    pub fn from_writer(wtr: W) -> AsyncWriter<W> {
+        WriterBuilder::new().from_async_writer(wtr)
+    }
+
+    /// Serialize a single record using Serde.
+    ///
+    /// This behaves exactly like `Writer::serialize`, including automatically
+    /// writing a header row for structs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::error::Error;
+    ///
+    /// use csv::AsyncWriter;
+    /// use futures::executor::block_on;
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Row<'a> {
+    ///     city: &'a str,
+    ///     population: u64,
+    /// }
+    ///
+    /// # fn main() { block_on(example()).unwrap(); }
+    /// async fn example() -> Result<(), Box<dyn Error>> {
+    ///     let mut wtr = AsyncWriter::from_writer(vec![]);
+    ///     wtr.serialize(Row { city: "Boston", population: 4628910 }).await?;
+    ///     wtr.serialize(Row { city: "Concord", population: 42695 }).await?;
+    ///
+    ///     let data = String::from_utf8(wtr.into_inner().await?)?;
+    ///     assert_eq!(data, "\
+    /// city,population
+    /// Boston,4628910
+    /// Concord,42695
+    /// ");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn serialize<S: Serialize>(&mut self, record: S) -> Result<()> {
+        self.wtr.serialize(record)?;
+        self.write_buffered(false).await?;
+        Ok(())
+    }
+
+    /// Write a single record.
+    ///
+    /// This method accepts something that can be turned into an iterator that
+    /// yields elements that can be represented by a `&[u8]`.
+    ///
+    /// This may be called with an empty iterator, which will cause a record
+    /// terminator to be written. If no fields had been written, then a single
+    /// empty field is written before the terminator.
+    pub async fn write_record<I, T>(&mut self, record: I) -> Result<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        self.wtr.write_record(record)?;
+        self.write_buffered(false).await?;
+        Ok(())
+    }
+
+    /// Write a single `ByteRecord`.
+    pub async fn write_byte_record(
+        &mut self,
+        record: &ByteRecord,
+    ) -> Result<()> {
+        self.wtr.write_byte_record(record)?;
+        self.write_buffered(false).await?;
+        Ok(())
+    }
+
+    /// Write a single field.
+    ///
+    /// One should prefer using `write_record` over this method. Note that if
+    /// this API is used, `write_record` should be called with an empty
+    /// iterator to write a record terminator.
+    pub async fn write_field<T: AsRef<[u8]>>(
+        &mut self,
+        field: T,
+    ) -> Result<()> {
+        self.wtr.write_field(field)?;
+        self.write_buffered(false).await?;
+        Ok(())
+    }
+
+    /// Write all buffered data to the underlying writer and flush it.
+    ///
+    /// If there was a problem writing to the underlying writer, then an error
+    /// is returned.
+    pub async fn flush(&mut self) -> io::Result<()> {
+        self.write_buffered(true).await?;
+        let out = &mut self.out;
+        poll_fn(|cx| Pin::new(&mut *out).poll_flush(cx)).await
+    }
+
+    /// Flush the contents of the internal buffer and return the underlying
+    /// writer.
+    pub async fn into_inner(
+        mut self,
+    ) -> result::Result<W, IntoInnerError<AsyncWriter<W>>> {
+        match self.flush().await {
+            Ok(()) => Ok(self.out),
+            Err(err) => Err(IntoInnerError::new(self, err)),
+        }
+    }
+
+    /// Returns a reference to the underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.out
+    }
+
+    /// Returns a mutable reference to the underlying writer.
+    ///
+    /// Note that writing to the underlying writer directly while there is
+    /// buffered CSV data will interleave the two.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.out
+    }
+
+    /// Write the encoded buffer to the underlying writer if it has grown
+    /// past the configured capacity, or unconditionally if `force` is set.
+    async fn write_buffered(&mut self, force: bool) -> io::Result<()> {
+        // Flushing a `Writer<Vec<u8>>` just moves its internal buffer into
+        // the `Vec`, which never fails. Records buffered to derive a header
+        // row from are only written when a flush is forced.
+        if force {
+            self.wtr.flush()?;
+        } else {
+            self.wtr.flush_buffer()?;
+        }
+        if !force && self.wtr.get_mut().len() < self.capacity {
+            return Ok(());
+        }
+        poll_fn(|cx| self.poll_write_buffered(cx)).await
+    }
+
+    fn poll_write_buffered(
+        &mut self,
+        cx: &mut Context,
+    ) -> Poll<io::Result<()>> {
+        let buf = self.wtr.get_mut();
+        while self.pos < buf.len() {
+            let n = ready!(
+                Pin::new(&mut self.out).poll_write(cx, &buf[self.pos..])
+            )?;
+            if n == 0 {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write buffered CSV data",
+                )));
+            }
+            self.pos += n;
+        }
+        buf.clear();
+        self.pos = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    use futures::executor::block_on;
+    use futures_io::AsyncWrite;
+    use serde::Serialize;
+
+    use crate::byte_record::ByteRecord;
+    use crate::error::ErrorKind;
+    use crate::WriterBuilder;
+
+    use super::AsyncWriter;
+
+    /// An async writer that accepts at most `chunk` bytes at a time, and
+    /// returns `Pending` before every chunk.
+    struct Trickle {
+        data: Vec<u8>,
+        chunk: usize,
+        ready: bool,
+        flushed: bool,
+    }
+
+    impl Trickle {
+        fn new(chunk: usize) -> Trickle {
+            Trickle {
+                data: vec![],
+                chunk: chunk,
+                ready: false,
+                flushed: false,
+            }
+        }
+    }
+
+    impl AsyncWrite for Trickle {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            if !self.ready {
+                self.ready = true;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            self.ready = false;
+            self.flushed = false;
+            let n = self.chunk.min(buf.len());
+            self.data.extend_from_slice(&buf[..n]);
+            Poll::Ready(Ok(n))
+        }
+
+        fn poll_flush(
+            mut self: Pin<&mut Self>,
+            _: &mut Context,
+        ) -> Poll<io::Result<()>> {
+            self.flushed = true;
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(
+            self: Pin<&mut Self>,
+            _: &mut Context,
+        ) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn wtr_as_string(wtr: AsyncWriter<Trickle>) -> String {
+        let trickle = block_on(wtr.into_inner()).unwrap();
+        assert!(trickle.flushed);
+        String::from_utf8(trickle.data).unwrap()
+    }
+
+    #[test]
+    fn write_records() {
+        let mut wtr = AsyncWriter::from_writer(Trickle::new(3));
+        block_on(wtr.write_record(&["a", "b,c", "d"])).unwrap();
+        let rec = ByteRecord::from(vec!["x", "y\"z", ""]);
+        block_on(wtr.write_byte_record(&rec)).unwrap();
+        block_on(wtr.write_field("1")).unwrap();
+        block_on(wtr.write_field("2")).unwrap();
+        block_on(wtr.write_field("3")).unwrap();
+        block_on(wtr.write_record(None::<&[u8]>)).unwrap();
+        assert_eq!(wtr_as_string(wtr), "a,\"b,c\",d\nx,\"y\"\"z\",\n1,2,3\n");
+    }
+
+    #[test]
+    fn write_past_capacity() {
+        let mut wtr = WriterBuilder::new()
+            .buffer_capacity(4)
+            .from_async_writer(Trickle::new(2));
+        let mut expected = String::new();
+        for i in 0..100 {
+            block_on(wtr.write_record(&[i.to_string(), "abc".to_string()]))
+                .unwrap();
+            expected.push_str(&format!("{},abc\n", i));
+            // Everything but the last few bytes should have been written.
+            assert!(wtr.get_ref().data.len() + 4 >= expected.len());
+        }
+        assert_eq!(wtr_as_string(wtr), expected);
+    }
+
+    #[test]
+    fn unequal_records_bad() {
+        let mut wtr = AsyncWriter::from_writer(Trickle::new(8));
+        block_on(wtr.write_record(&["a", "b", "c"])).unwrap();
+        let err = block_on(wtr.write_record(&["x", "y"])).unwrap_err();
+        match *err.kind() {
+            ErrorKind::UnequalLengths { ref pos, expected_len, len } => {
+                assert!(pos.is_none());
+                assert_eq!(expected_len, 3);
+                assert_eq!(len, 2);
+            }
+            ref x => panic!("expected UnequalLengths error, but got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn serialize_with_headers() {
+        #[derive(Serialize)]
+        struct Row {
+            foo: i32,
+            bar: f64,
+            baz: bool,
+        }
+
+        let mut wtr = AsyncWriter::from_writer(Trickle::new(5));
+        block_on(wtr.serialize(Row { foo: 42, bar: 42.5, baz: true }))
+            .unwrap();
+        block_on(wtr.serialize(Row { foo: 1, bar: 0.5, baz: false })).unwrap();
+        assert_eq!(
+            wtr_as_string(wtr),
+            "foo,bar,baz\n42,42.5,true\n1,0.5,false\n"
+        );
+    }
+
+    #[test]
+    fn serialize_union_headers() {
+        #[derive(Serialize)]
+        struct Row {
+            id: i32,
+            tags: Vec<&'static str>,
+        }
+
+        let mut wtr = WriterBuilder::new()
+            .union_headers(3)
+            .buffer_capacity(1)
+            .from_async_writer(Trickle::new(5));
+        block_on(wtr.serialize(Row { id: 1, tags: vec!["a"] })).unwrap();
+        block_on(wtr.serialize(Row { id: 2, tags: vec!["b", "c"] })).unwrap();
+        assert_eq!(wtr_as_string(wtr), "id,tags[0],tags[1]\n1,a,\n2,b,c\n");
+    }
+}
@@ -1,8 +1,10 @@
 use std::cmp;
+use std::collections::HashMap;
 use std::fmt;
 use std::iter::FromIterator;
 use std::ops::{self, Range};
 use std::result;
+use std::sync::Arc;
 
 use bstr::{BString, ByteSlice};
 use serde::de::Deserialize;
@@ -90,6 +92,11 @@ struct ByteRecordInner {
     fields: Vec<u8>,
     /// The number of and location of each field in this record.
     bounds: Bounds,
+    /// The headers of the reader that read this record, if any.
+    ///
+    /// This is shared by every record read by the same reader, and is used
+    /// to look up fields by name.
+    headers: Option<Arc<HeaderIndex>>,
 }
 
 impl Default for ByteRecord {
@@ -138,6 +145,7 @@ impl ByteRecord {
             pos: None,
             fields: vec![0; buffer],
             bounds: Bounds::with_capacity(fields),
+            headers: None,
         }))
     }
 
@@ -270,6 +278,40 @@ impl ByteRecord {
         self.0.bounds.get(i).map(|range| &self.0.fields[range])
     }
 
+    /// Return the field in the column with the given name.
+    ///
+    /// Names are looked up in the headers of the CSV reader that read this
+    /// record. If a name occurs more than once in the headers, then the first
+    /// occurrence is used.
+    ///
+    /// If this record wasn't read by a CSV reader, if the reader had no
+    /// headers or if no column has the given name, then this returns `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::error::Error;
+    /// use csv::{ByteRecord, Reader};
+    ///
+    /// # fn main() { example().unwrap(); }
+    /// fn example() -> Result<(), Box<dyn Error>> {
+    ///     let data = "\
+    /// city,country,pop
+    /// Boston,United States,4628910
+    /// ";
+    ///     let mut rdr = Reader::from_reader(data.as_bytes());
+    ///     let mut record = ByteRecord::new();
+    ///     assert!(rdr.read_byte_record(&mut record)?);
+    ///     assert_eq!(record.get_by_name(b"pop"), Some(&b"4628910"[..]));
+    ///     assert_eq!(record.get_by_name(b"state"), None);
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn get_by_name(&self, name: &[u8]) -> Option<&[u8]> {
+        self.index_of(name).and_then(|i| self.get(i))
+    }
+
     /// Returns true if and only if this record is empty.
     ///
     /// # Example
@@ -373,6 +415,7 @@ impl ByteRecord {
         for field in &*self {
             trimmed.push_field(field.trim());
         }
+        trimmed.0.headers = self.0.headers.take();
         *self = trimmed;
     }
 
@@ -497,6 +540,30 @@ impl ByteRecord {
         &self.0.fields[..self.0.bounds.end()]
     }
 
+    /// Returns the index of the column with the given name in the headers
+    /// associated with this record.
+    #[inline]
+    pub(crate) fn index_of(&self, name: &[u8]) -> Option<usize> {
+        self.0.headers.as_ref().and_then(|headers| headers.get(name))
+    }
+
+    /// Associate the given headers with this record.
+    #[inline]
+    pub(crate) fn set_header_index(&mut self, headers: &Arc<HeaderIndex>) {
+        // Records are usually reused, so avoid touching the reference count
+        // when the headers haven't changed.
+        match self.0.headers {
+            Some(ref old) if Arc::ptr_eq(old, headers) => {}
+            _ => self.0.headers = Some(headers.clone()),
+        }
+    }
+
+    /// Remove the headers associated with this record, if any.
+    #[inline]
+    pub(crate) fn clear_header_index(&mut self) {
+        self.0.headers = None;
+    }
+
     /// Retrieve the underlying parts of a byte record.
     #[inline]
     pub(crate) fn as_parts(&mut self) -> (&mut Vec<u8>, &mut Vec<usize>) {
@@ -627,6 +694,27 @@ impl Position {
     }
 }
 
+/// A map from the names in a header row to their indices.
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct HeaderIndex(HashMap<Vec<u8>, usize>);
+
+impl HeaderIndex {
+    /// Build an index of the given header row.
+    pub(crate) fn new(headers: &ByteRecord) -> HeaderIndex {
+        let mut names = HashMap::with_capacity(headers.len());
+        for (i, name) in headers.iter().enumerate() {
+            names.entry(name.to_vec()).or_insert(i);
+        }
+        HeaderIndex(names)
+    }
+
+    /// Returns the index of the first column with the given name.
+    #[inline]
+    fn get(&self, name: &[u8]) -> Option<usize> {
+        self.0.get(name).cloned()
+    }
+}
+
 /// The bounds of fields in a single record.
 #[derive(Clone, Debug, Eq, PartialEq)]
 struct Bounds {
@@ -1,6 +1,7 @@
+use std::cmp;
 use std::error::Error as StdError;
 use std::fmt;
-use std::iter;
+use std::marker::PhantomData;
 use std::num;
 use std::str;
 
@@ -12,9 +13,9 @@ use serde::de::{
 };
 use serde::serde_if_integer128;
 
-use crate::byte_record::{ByteRecord, ByteRecordIter};
+use crate::byte_record::ByteRecord;
 use crate::error::{Error, ErrorKind};
-use crate::string_record::{StringRecord, StringRecordIter};
+use crate::string_record::StringRecord;
 
 use self::DeserializeErrorKind as DEK;
 
@@ -22,12 +23,24 @@ pub fn deserialize_string_record<'de, D: Deserialize<'de>>(
     record: &'de StringRecord,
     headers: Option<&'de StringRecord>,
 ) -> Result<D, Error> {
+    deserialize_string_record_seed(PhantomData, record, headers)
+}
+
+pub fn deserialize_string_record_seed<'de, S: DeserializeSeed<'de>>(
+    seed: S,
+    record: &'de StringRecord,
+    headers: Option<&'de StringRecord>,
+) -> Result<S::Value, Error> {
     let mut deser = DeRecordWrap(DeStringRecord {
-        it: record.iter().peekable(),
-        headers: headers.map(|r| r.iter()),
+        record: record,
+        headers: headers,
+        cursor: Cursor::new(
+            record.as_byte_record(),
+            headers.map(|r| r.as_byte_record()),
+        ),
         field: 0,
     });
-    D::deserialize(&mut deser).map_err(|err| {
+    seed.deserialize(&mut deser).map_err(|err| {
         Error::new(ErrorKind::Deserialize {
             pos: record.position().map(Clone::clone),
             err: err,
@@ -40,8 +53,7 @@ pub fn deserialize_byte_record<'de, D: Deserialize<'de>>(
     headers: Option<&'de ByteRecord>,
 ) -> Result<D, Error> {
     let mut deser = DeRecordWrap(DeByteRecord {
-        it: record.iter().peekable(),
-        headers: headers.map(|r| r.iter()),
+        cursor: Cursor::new(record, headers),
         field: 0,
     });
     D::deserialize(&mut deser).map_err(|err| {
@@ -89,6 +101,9 @@ trait DeRecord<'r> {
     /// Peeks at the next field from the underlying record.
     fn peek_field(&mut self) -> Option<&'r [u8]>;
 
+    /// Returns the cursor that tracks the position in the underlying record.
+    fn cursor(&mut self) -> &mut Cursor<'r>;
+
     /// Returns an error corresponding to the most recently extracted field.
     fn error(&self, kind: DeserializeErrorKind) -> DeserializeError;
 
@@ -134,6 +149,11 @@ struct DeRecordWrap<T>(T);
         self.0.peek_field()
     }
 
+    #[inline]
+    fn cursor(&mut self) -> &mut Cursor<'r> {
+        self.0.cursor()
+    }
+
     #[inline]
     fn error(&self, kind: DeserializeErrorKind) -> DeserializeError {
         self.0.error(kind)
@@ -148,9 +168,244 @@ struct DeRecordWrap<T>(T);
     }
 }
 
+/// Tracks which headers and fields of a record have been consumed.
+///
+/// A nested struct is deserialized from the columns whose headers start with
+/// the name of its field followed by a `.`, e.g., `address.city` for a field
+/// named `address`. While such a struct is deserialized, the cursor is
+/// confined to a scope that covers only those columns, and the name of the
+/// field is stripped from their headers.
+///
+/// Positions in a record usually correspond to column indices, but the
+/// columns of a nested struct need not be adjacent. When they aren't, the
+/// cursor reorders the columns so that they are.
+struct Cursor<'r> {
+    record: &'r ByteRecord,
+    headers: Option<&'r ByteRecord>,
+    /// The column at each position, if the columns have been reordered.
+    /// Positions past the end of this map to the column with the same index.
+    order: Option<Vec<usize>>,
+    /// The position of the next header.
+    header: usize,
+    /// The position of the next field.
+    field: usize,
+    scope: Scope,
+}
+
+/// A range of positions visible to a cursor.
+#[derive(Clone, Copy, Debug)]
+struct Scope {
+    start: usize,
+    end: usize,
+    /// The number of bytes stripped from the start of each header.
+    prefix: usize,
+    /// Whether the positions are the elements of a sequence, e.g., of a
+    /// tuple, rather than the values of a map.
+    seq: bool,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<'r> Cursor<'r> {
+    fn new(
+        record: &'r ByteRecord,
+        headers: Option<&'r ByteRecord>,
+    ) -> Cursor<'r> {
+        Cursor {
+            record: record,
+            headers: headers,
+            order: None,
+            header: 0,
+            field: 0,
+            scope: Scope { start: 0, end: usize::MAX, prefix: 0, seq: false },
+        }
+    }
+
+    /// Returns the column at the given position.
+    #[inline]
+    fn column(&self, pos: usize) -> usize {
+        match self.order {
+            Some(ref order) if pos < order.len() => order[pos],
+            _ => pos,
+        }
+    }
+
+    /// Returns the number of bytes stripped from the start of each header.
+    #[inline]
+    fn prefix(&self) -> usize {
+        self.scope.prefix
+    }
+
+    /// Returns true if and only if nothing in the current scope has been
+    /// consumed.
+    #[inline]
+    fn at_start(&self) -> bool {
+        self.header == self.scope.start && self.field == self.scope.start
+    }
+
+    /// Returns true if and only if the cursor is confined to the columns of
+    /// a nested struct.
+    #[inline]
+    fn in_group(&self) -> bool {
+        self.scope.prefix > 0
+    }
+
+    /// Returns true if and only if the positions in the current scope are
+    /// being deserialized as the elements of a sequence.
+    #[inline]
+    fn in_seq(&self) -> bool {
+        self.scope.seq
+    }
+
+    /// Mark the positions in the current scope as the elements of a
+    /// sequence.
+    #[inline]
+    fn start_seq(&mut self) {
+        self.scope.seq = true;
+    }
+
+    /// Returns the column of the next header, and advances past it.
+    #[inline]
+    fn next_header(&mut self) -> Option<usize> {
+        if self.header >= self.scope.end {
+            return None;
+        }
+        self.header += 1;
+        Some(self.column(self.header - 1))
+    }
+
+    /// Returns the next header, with the prefix of the current scope
+    /// stripped, without advancing past it.
+    #[inline]
+    fn peek_header(&self) -> Option<&'r [u8]> {
+        self.header_at(self.header)
+    }
+
+    fn header_at(&self, pos: usize) -> Option<&'r [u8]> {
+        if pos >= self.scope.end {
+            return None;
+        }
+        let header = self.headers.and_then(|r| r.get(self.column(pos)))?;
+        Some(&header[self.scope.prefix..])
+    }
+
+    /// Returns the name of the group that the given header belongs to, i.e.,
+    /// the part of the header before its first `.`.
+    ///
+    /// A header doesn't belong to a group if there's a column in the current
+    /// scope whose header is exactly the name of that group, since that
+    /// column is the value of the field with that name.
+    fn group_of(&self, header: &'r [u8]) -> Option<&'r [u8]> {
+        let i = header.iter().position(|&b| b == b'.')?;
+        let name = &header[..i];
+        let mut pos = self.scope.start;
+        while let Some(h) = self.header_at(pos) {
+            if h == name {
+                return None;
+            }
+            pos += 1;
+        }
+        Some(name)
+    }
+
+    /// Returns the column of the next field, and advances past it.
+    #[inline]
+    fn next_field(&mut self) -> Option<usize> {
+        if self.field >= self.scope.end {
+            return None;
+        }
+        self.field += 1;
+        Some(self.column(self.field - 1))
+    }
+
+    /// Returns the next field without advancing past it.
+    #[inline]
+    fn peek_field(&self) -> Option<&'r [u8]> {
+        if self.field >= self.scope.end {
+            return None;
+        }
+        self.record.get(self.column(self.field))
+    }
+
+    /// Returns true if and only if every remaining field in the current
+    /// scope is empty.
+    fn rest_is_empty(&self) -> bool {
+        let end = cmp::min(self.scope.end, self.record.len());
+        (self.field..end).all(|pos| self.record[self.column(pos)].is_empty())
+    }
+
+    /// Gather the columns, starting at the next header, whose headers start
+    /// with the given name followed by a `.`, and return how many there are.
+    ///
+    /// Afterwards, those columns are at adjacent positions, starting at the
+    /// next header.
+    fn group(&mut self, name: &[u8]) -> usize {
+        let prefix = self.scope.prefix;
+        let is_member = |header: &[u8]| {
+            let header = &header[prefix..];
+            header.len() > name.len()
+                && header.starts_with(name)
+                && header[name.len()] == b'.'
+        };
+        let headers = match self.headers {
+            None => return 0,
+            Some(headers) => headers,
+        };
+        let end = cmp::min(self.scope.end, headers.len());
+        let mut len = 0;
+        while self.header + len < end
+            && is_member(&headers[self.column(self.header + len)])
+        {
+            len += 1;
+        }
+        let rest = (self.header + len..end)
+            .filter(|&pos| is_member(&headers[self.column(pos)]))
+            .count();
+        if rest == 0 {
+            return len;
+        }
+
+        let mut order = match self.order.take() {
+            Some(order) => order,
+            None => (0..headers.len()).collect(),
+        };
+        let (mut members, others): (Vec<usize>, Vec<usize>) = order
+            [self.header..end]
+            .iter()
+            .partition(|&&column| is_member(&headers[column]));
+        members.extend(others);
+        order[self.header..end].copy_from_slice(&members);
+        self.order = Some(order);
+        len + rest
+    }
+
+    /// Confine this cursor to the given number of positions starting at the
+    /// next header, and strip `prefix` more bytes from their headers.
+    ///
+    /// This returns the previous scope, which should be passed to `leave`.
+    fn enter(&mut self, len: usize, prefix: usize) -> Scope {
+        let scope = self.scope;
+        self.field = self.header;
+        self.scope = Scope {
+            start: self.header,
+            end: self.header + len,
+            prefix: scope.prefix + prefix,
+            seq: false,
+        };
+        scope
+    }
+
+    /// Skip whatever remains of the current scope, and then restore the
+    /// given scope.
+    fn leave(&mut self, scope: Scope) {
+        self.header = self.scope.end;
+        self.field = self.scope.end;
+        self.scope = scope;
+    }
+}
+
 struct DeStringRecord<'r> {
-    it: iter::Peekable<StringRecordIter<'r>>,
-    headers: Option<StringRecordIter<'r>>,
+    record: &'r StringRecord,
+    headers: Option<&'r StringRecord>,
+    cursor: Cursor<'r>,
     field: u64,
 }
 
@@ -162,7 +417,11 @@ struct DeStringRecord<'r> {
 
     #[inline]
     fn next_header(&mut self) -> Result<Option<&'r str>, DeserializeError> {
-        Ok(self.headers.as_mut().and_then(|it| it.next()))
+        let prefix = self.cursor.prefix();
+        Ok(match (self.headers, self.cursor.next_header()) {
+            (Some(headers), Some(i)) => headers.get(i).map(|h| &h[prefix..]),
+            _ => None,
+        })
     }
 
     #[inline]
@@ -174,11 +433,11 @@ struct DeStringRecord<'r> {
 
     #[inline]
     fn next_field(&mut self) -> Result<&'r str, DeserializeError> {
-        match self.it.next() {
-            Some(field) => {
-                self.field += 1;
-                Ok(field)
-            }
+        match self.cursor.next_field().and_then(|i| {
+            self.field = i as u64 + 1;
+            self.record.get(i)
+        }) {
+            Some(field) => Ok(field),
             None => Err(DeserializeError {
                 field: None,
                 kind: DEK::UnexpectedEndOfRow,
@@ -193,7 +452,12 @@ struct DeStringRecord<'r> {
 
     #[inline]
     fn peek_field(&mut self) -> Option<&'r [u8]> {
-        self.it.peek().map(|s| s.as_bytes())
+        self.cursor.peek_field()
+    }
+
+    #[inline]
+    fn cursor(&mut self) -> &mut Cursor<'r> {
+        &mut self.cursor
     }
 
     fn error(&self, kind: DeserializeErrorKind) -> DeserializeError {
@@ -233,15 +497,14 @@ struct DeStringRecord<'r> {
 }
 
 struct DeByteRecord<'r> {
-    it: iter::Peekable<ByteRecordIter<'r>>,
-    headers: Option<ByteRecordIter<'r>>,
+    cursor: Cursor<'r>,
     field: u64,
 }
 
 #[cfg_attr(test, ::mutagen::mutate)] impl<'r> DeRecord<'r> for DeByteRecord<'r> {
     #[inline]
     fn has_headers(&self) -> bool {
-        self.headers.is_some()
+        self.cursor.headers.is_some()
     }
 
     #[inline]
@@ -260,7 +523,11 @@ struct DeByteRecord<'r> {
     fn next_header_bytes(
         &mut self,
     ) -> Result<Option<&'r [u8]>, DeserializeError> {
-        Ok(self.headers.as_mut().and_then(|it| it.next()))
+        let prefix = self.cursor.prefix();
+        Ok(match (self.cursor.headers, self.cursor.next_header()) {
+            (Some(headers), Some(i)) => headers.get(i).map(|h| &h[prefix..]),
+            _ => None,
+        })
     }
 
     #[inline]
@@ -273,11 +540,12 @@ struct DeByteRecord<'r> {
 
     #[inline]
     fn next_field_bytes(&mut self) -> Result<&'r [u8], DeserializeError> {
-        match self.it.next() {
-            Some(field) => {
-                self.field += 1;
-                Ok(field)
-            }
+        let record = self.cursor.record;
+        match self.cursor.next_field().and_then(|i| {
+            self.field = i as u64 + 1;
+            record.get(i)
+        }) {
+            Some(field) => Ok(field),
             None => Err(DeserializeError {
                 field: None,
                 kind: DEK::UnexpectedEndOfRow,
@@ -287,7 +555,12 @@ struct DeByteRecord<'r> {
 
     #[inline]
     fn peek_field(&mut self) -> Option<&'r [u8]> {
-        self.it.peek().map(|s| *s)
+        self.cursor.peek_field()
+    }
+
+    #[inline]
+    fn cursor(&mut self) -> &mut Cursor<'r> {
+        &mut self.cursor
     }
 
     fn error(&self, kind: DeserializeErrorKind) -> DeserializeError {
@@ -355,7 +628,21 @@ macro_rules! deserialize_int {
         self,
         visitor: V,
     ) -> Result<V::Value, Self::Error> {
-        self.infer_deserialize(visitor)
+        // A whole record, or the columns of a nested struct, is deserialized
+        // as a map when its type isn't known in advance, e.g., when it's an
+        // internally tagged enum or a flattened field. A nested struct whose
+        // fields are all empty is absent. The elements of a tuple are
+        // deserialized one field at a time.
+        if !self.has_headers()
+            || !self.cursor().at_start()
+            || self.cursor().in_seq()
+        {
+            self.infer_deserialize(visitor)
+        } else if self.cursor().in_group() && self.cursor().rest_is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_map(DeMap { de: self, fields: None, group: None })
+        }
     }
 
     fn deserialize_bool<V: Visitor<'de>>(
@@ -454,6 +741,14 @@ macro_rules! deserialize_int {
         self,
         visitor: V,
     ) -> Result<V::Value, Self::Error> {
+        if self.cursor().in_group() && self.cursor().at_start() {
+            // A nested struct is absent if all of its fields are empty.
+            return if self.cursor().rest_is_empty() {
+                visitor.visit_none()
+            } else {
+                visitor.visit_some(self)
+            };
+        }
         match self.peek_field() {
             None => visitor.visit_none(),
             Some(f) if f.is_empty() => {
@@ -518,28 +813,34 @@ macro_rules! deserialize_int {
         if !self.has_headers() {
             visitor.visit_seq(self)
         } else {
-            visitor.visit_map(self)
+            visitor.visit_map(DeMap { de: self, fields: None, group: None })
         }
     }
 
     fn deserialize_struct<V: Visitor<'de>>(
         self,
         _name: &'static str,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, Self::Error> {
         if !self.has_headers() {
             visitor.visit_seq(self)
         } else {
-            visitor.visit_map(self)
+            visitor.visit_map(DeMap {
+                de: self,
+                fields: Some(fields),
+                group: None,
+            })
         }
     }
 
     fn deserialize_identifier<V: Visitor<'de>>(
         self,
-        _visitor: V,
+        visitor: V,
     ) -> Result<V::Value, Self::Error> {
-        Err(self.error(DEK::Unsupported("deserialize_identifier".into())))
+        // This is reached when the tag of an adjacently tagged enum is
+        // deserialized.
+        self.next_field().and_then(|f| visitor.visit_borrowed_str(f))
     }
 
     fn deserialize_enum<V: Visitor<'de>>(
@@ -626,36 +927,133 @@ macro_rules! deserialize_int {
         if self.peek_field().is_none() {
             Ok(None)
         } else {
+            self.cursor().start_seq();
             seed.deserialize(&mut **self).map(Some)
         }
     }
 }
 
-#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'de: 'a, T: DeRecord<'de>> MapAccess<'de>
-    for &'a mut DeRecordWrap<T>
-{
+/// Deserializes a map or a struct from a record with headers.
+///
+/// The columns whose headers start with the name of a struct field followed
+/// by a `.`, e.g., `address.city`, are grouped together and deserialized as
+/// the value of that field.
+struct DeMap<'a, T: 'a> {
+    de: &'a mut DeRecordWrap<T>,
+    /// The names of the struct's fields, if they're known.
+    fields: Option<&'static [&'static str]>,
+    /// The number of columns in the group whose name was the most recently
+    /// deserialized key, and the length of that name.
+    group: Option<(usize, usize)>,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'de: 'a, T: DeRecord<'de>> MapAccess<'de> for DeMap<'a, T> {
     type Error = DeserializeError;
 
     fn next_key_seed<K: DeserializeSeed<'de>>(
         &mut self,
         seed: K,
     ) -> Result<Option<K::Value>, Self::Error> {
-        assert!(self.has_headers());
-        let field = match self.next_header_bytes()? {
+        let header = match self.de.cursor().peek_header() {
             None => return Ok(None),
-            Some(field) => field,
+            Some(header) => header,
+        };
+        let mut group = None;
+        let name = self.de.cursor().group_of(header);
+        let key = match self.fields {
+            Some(fields) => {
+                group = name.filter(|&name| is_group(name, header, fields));
+                let name = group.unwrap_or(header);
+                seed.deserialize(BorrowedBytesDeserializer::new(name))?
+            }
+            None => seed.deserialize(DeMapKey {
+                header: header,
+                name: name,
+                group: &mut group,
+            })?,
         };
-        seed.deserialize(BorrowedBytesDeserializer::new(field)).map(Some)
+        match group {
+            None => {
+                self.de.next_header_bytes()?;
+            }
+            Some(name) => {
+                let len = self.de.cursor().group(name);
+                self.group = Some((len, name.len() + 1));
+            }
+        }
+        Ok(Some(key))
     }
 
     fn next_value_seed<K: DeserializeSeed<'de>>(
         &mut self,
         seed: K,
     ) -> Result<K::Value, Self::Error> {
-        seed.deserialize(&mut **self)
+        let (len, prefix) = match self.group.take() {
+            None => return seed.deserialize(&mut *self.de),
+            Some(group) => group,
+        };
+        let scope = self.de.cursor().enter(len, prefix);
+        let value = seed.deserialize(&mut *self.de)?;
+        self.de.cursor().leave(scope);
+        Ok(value)
+    }
+}
+
+/// Deserializes a map key from a header.
+///
+/// When the key is deserialized as an identifier, i.e., as the name of a
+/// struct field, a header like `address.city` is treated as part of the group
+/// `address`. This happens for structs with flattened fields, whose field
+/// names aren't known otherwise. Other keys, such as the keys of a
+/// `HashMap<String, _>`, are deserialized from the whole header.
+struct DeMapKey<'de, 'g> {
+    header: &'de [u8],
+    /// The name of the group that the header belongs to, if any.
+    name: Option<&'de [u8]>,
+    group: &'g mut Option<&'de [u8]>,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<'de, 'g> Deserializer<'de> for DeMapKey<'de, 'g> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_borrowed_bytes(self.header)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.name {
+            None => visitor.visit_borrowed_bytes(self.header),
+            Some(name) => {
+                *self.group = Some(name);
+                visitor.visit_borrowed_bytes(name)
+            }
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum ignored_any
     }
 }
 
+/// Returns true if and only if the column with the given header should be
+/// deserialized as part of the nested struct of the field with the given
+/// name.
+///
+/// A header like `address.city` belongs to the field `address`, unless the
+/// struct also has a field named `address.city`.
+#[cfg_attr(test, ::mutagen::mutate)] fn is_group(name: &[u8], header: &[u8], fields: &[&str]) -> bool {
+    let is_field = |name: &[u8]| fields.iter().any(|f| f.as_bytes() == name);
+    is_field(name) && !is_field(header)
+}
+
 /// An Serde deserialization error.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct DeserializeError {
@@ -806,7 +1204,7 @@ mod tests {
 
     use super::{deserialize_byte_record, deserialize_string_record};
     use crate::byte_record::ByteRecord;
-    use crate::error::Error;
+    use crate::error::{Error, ErrorKind};
     use crate::string_record::StringRecord;
 
     fn de<D: DeserializeOwned>(fields: &[&str]) -> Result<D, Error> {
@@ -1250,4 +1648,328 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn nested_struct() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Row {
+            name: String,
+            address: Address,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Address {
+            city: String,
+            zip: String,
+        }
+
+        let got: Row = de_headers(
+            &["name", "address.city", "address.zip"],
+            &["foo", "Boston", "02134"],
+        )
+        .unwrap();
+        assert_eq!(
+            got,
+            Row {
+                name: "foo".into(),
+                address: Address {
+                    city: "Boston".into(),
+                    zip: "02134".into()
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn nested_struct_not_adjacent() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Row {
+            a: Point,
+            b: Point,
+            label: String,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        let got: Row = de_headers(
+            &["b.y", "a.x", "label", "b.x", "a.y"],
+            &["1", "2", "foo", "3", "4"],
+        )
+        .unwrap();
+        assert_eq!(
+            got,
+            Row {
+                a: Point { x: 2, y: 4 },
+                b: Point { x: 3, y: 1 },
+                label: "foo".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn nested_struct_deep() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Row {
+            a: A,
+            z: i32,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct A {
+            b: B,
+            y: i32,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct B {
+            x: i32,
+        }
+
+        let got: Row =
+            de_headers(&["a.y", "z", "a.b.x"], &["1", "2", "3"]).unwrap();
+        assert_eq!(got, Row { a: A { b: B { x: 3 }, y: 1 }, z: 2 });
+    }
+
+    #[test]
+    fn nested_struct_option() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Row {
+            id: i32,
+            point: Option<Point>,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Point {
+            x: i32,
+            y: Option<i32>,
+        }
+
+        let headers = &["id", "point.x", "point.y"];
+        let got: Row = de_headers(headers, &["1", "2", ""]).unwrap();
+        assert_eq!(got, Row { id: 1, point: Some(Point { x: 2, y: None }) });
+        let got: Row = de_headers(headers, &["1", "", ""]).unwrap();
+        assert_eq!(got, Row { id: 1, point: None });
+        let got: Row = de_headers(&["id"], &["1"]).unwrap();
+        assert_eq!(got, Row { id: 1, point: None });
+    }
+
+    #[test]
+    fn nested_struct_error_field() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Row {
+            a: i32,
+            point: Point,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        let err = de_headers::<Row>(
+            &["point.x", "a", "point.y"],
+            &["1", "2", "foo"],
+        )
+        .unwrap_err();
+        match *err.kind() {
+            ErrorKind::Deserialize { ref err, .. } => {
+                assert_eq!(err.field(), Some(2));
+            }
+            ref kind => panic!("expected deserialize error, got {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn nested_struct_bytes() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Row {
+            name: BString,
+            point: Point,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        let headers = ByteRecord::from(vec!["point.y", "name", "point.x"]);
+        let record = ByteRecord::from(vec![b(b"1"), b(b"\xFF"), b(b"2")]);
+        let got: Row =
+            deserialize_byte_record(&record, Some(&headers)).unwrap();
+        assert_eq!(
+            got,
+            Row {
+                name: BString::from(vec![b'\xFF']),
+                point: Point { x: 2, y: 1 }
+            }
+        );
+    }
+
+    #[test]
+    fn flatten_nested_struct() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Row {
+            id: i32,
+            #[serde(flatten)]
+            place: Place,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Place {
+            name: String,
+            location: Option<Point>,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Point {
+            x: f64,
+            y: f64,
+        }
+
+        let headers = &["location.x", "id", "name", "location.y"];
+        let got: Row =
+            de_headers(headers, &["1.5", "1", "home", "2"]).unwrap();
+        assert_eq!(
+            got,
+            Row {
+                id: 1,
+                place: Place {
+                    name: "home".into(),
+                    location: Some(Point { x: 1.5, y: 2.0 }),
+                },
+            }
+        );
+        let got: Row = de_headers(headers, &["", "1", "home", ""]).unwrap();
+        assert_eq!(
+            got,
+            Row {
+                id: 1,
+                place: Place { name: "home".into(), location: None }
+            }
+        );
+    }
+
+    #[test]
+    fn nested_struct_exact_column() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Row {
+            a: String,
+            b: i32,
+        }
+
+        let got: Row =
+            de_headers(&["a", "a.b", "b"], &["foo", "bar", "1"]).unwrap();
+        assert_eq!(got, Row { a: "foo".into(), b: 1 });
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Price {
+            price: f64,
+        }
+
+        let got: Price =
+            de_headers(&["price.usd", "price"], &["1.5", "2.5"]).unwrap();
+        assert_eq!(got, Price { price: 2.5 });
+    }
+
+    #[test]
+    fn flatten_exact_column() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Row {
+            a: String,
+            #[serde(flatten)]
+            rest: HashMap<String, i32>,
+        }
+
+        let got: Row =
+            de_headers(&["a.b", "a", "b"], &["2", "foo", "1"]).unwrap();
+        assert_eq!(got.a, "foo");
+        assert_eq!(got.rest.len(), 2);
+        assert_eq!(got.rest["a.b"], 2);
+        assert_eq!(got.rest["b"], 1);
+    }
+
+    #[test]
+    fn enum_untagged_in_tuple() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(untagged)]
+        enum Untagged {
+            I(i64),
+            S(String),
+        }
+
+        let got: (Untagged,) = de_headers(&["a"], &["5"]).unwrap();
+        assert_eq!(got, (Untagged::I(5),));
+        let got: (i32, Untagged) =
+            de_headers(&["a", "b"], &["1", "foo"]).unwrap();
+        assert_eq!(got, (1, Untagged::S("foo".into())));
+    }
+
+    #[test]
+    fn nested_map_keeps_headers() {
+        let got: HashMap<String, i32> =
+            de_headers(&["a.x", "b"], &["1", "2"]).unwrap();
+        assert_eq!(got.len(), 2);
+        assert_eq!(got["a.x"], 1);
+        assert_eq!(got["b"], 2);
+    }
+
+    #[test]
+    fn enum_internally_tagged() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(tag = "type", rename_all = "snake_case")]
+        enum Shape {
+            Circle { r: f64 },
+            Rect { w: f64, h: f64 },
+        }
+
+        let headers = &["type", "r", "w", "h"];
+        let got: Shape =
+            de_headers(headers, &["circle", "1.5", "", ""]).unwrap();
+        assert_eq!(got, Shape::Circle { r: 1.5 });
+        let got: Shape = de_headers(headers, &["rect", "", "2", "3"]).unwrap();
+        assert_eq!(got, Shape::Rect { w: 2.0, h: 3.0 });
+    }
+
+    #[test]
+    fn enum_internally_tagged_nested() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Row {
+            id: i32,
+            shape: Shape,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(tag = "type", rename_all = "snake_case")]
+        enum Shape {
+            Circle { r: f64 },
+            Square { side: f64 },
+        }
+
+        let headers = &["id", "shape.type", "shape.r", "shape.side"];
+        let got: Row = de_headers(headers, &["1", "square", "", "2"]).unwrap();
+        assert_eq!(got, Row { id: 1, shape: Shape::Square { side: 2.0 } });
+    }
+
+    #[test]
+    fn enum_adjacently_tagged() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(tag = "kind", content = "data")]
+        enum Event {
+            Click { x: i32, y: i32 },
+            Key(String),
+        }
+
+        let got: Event =
+            de_headers(&["kind", "data.x", "data.y"], &["Click", "1", "2"])
+                .unwrap();
+        assert_eq!(got, Event::Click { x: 1, y: 2 });
+        let got: Event = de_headers(&["kind", "data"], &["Key", "5"]).unwrap();
+        assert_eq!(got, Event::Key("5".into()));
+    }
 }
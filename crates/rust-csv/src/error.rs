@@ -98,6 +98,9 @@ pub enum ErrorKind {
         /// The deserialization error.
         err: DeserializeError,
     },
+    /// This error occurs when a CSV reader is asked to select a column by
+    /// name, but no column in the header row has that name.
+    UnknownColumn(String),
     /// Hints that destructuring should not be exhaustive.
     ///
     /// This enum may grow additional variants, so this makes sure clients
@@ -143,6 +146,7 @@ impl StdError for Error {
             ErrorKind::Seek => None,
             ErrorKind::Serialize(_) => None,
             ErrorKind::Deserialize { ref err, .. } => Some(err),
+            ErrorKind::UnknownColumn(_) => None,
             _ => unreachable!(),
         }
     }
@@ -210,6 +214,12 @@ impl fmt::Display for Error {
                 pos.byte(),
                 err
             ),
+            ErrorKind::UnknownColumn(ref name) => write!(
+                f,
+                "CSV error: cannot select column '{}' since no column in \
+                 the header row has that name",
+                name
+            ),
             _ => unreachable!(),
         }
     }
@@ -297,6 +307,103 @@ impl fmt::Display for Utf8Error {
     }
 }
 
+/// A malformed record that was skipped by a lenient CSV reader.
+///
+/// Diagnostics are only collected when lenient parsing is enabled via the
+/// `lenient` method on [`ReaderBuilder`](struct.ReaderBuilder.html). They
+/// can be retrieved with `Reader::diagnostics`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Diagnostic {
+    kind: DiagnosticKind,
+    pos: Position,
+    raw: Vec<u8>,
+}
+
+impl Diagnostic {
+    /// A crate private constructor for `Diagnostic`.
+    pub(crate) fn new(
+        kind: DiagnosticKind,
+        pos: Position,
+        raw: Vec<u8>,
+    ) -> Diagnostic {
+        Diagnostic { kind: kind, pos: pos, raw: raw }
+    }
+
+    /// Return what is wrong with the record.
+    pub fn kind(&self) -> &DiagnosticKind {
+        &self.kind
+    }
+
+    /// Return the position of the record.
+    pub fn position(&self) -> &Position {
+        &self.pos
+    }
+
+    /// Return the record as it appears in the CSV data.
+    ///
+    /// This usually ends with the record's terminator, although only the
+    /// `\r` of a `\r\n` terminator is included.
+    ///
+    /// For a record with an unterminated quote, this is only the line on
+    /// which the record starts.
+    pub fn raw(&self) -> &[u8] {
+        &self.raw
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "CSV parse error: record {} (line: {}, byte: {}): ",
+            self.pos.record(),
+            self.pos.line(),
+            self.pos.byte()
+        )?;
+        match self.kind {
+            DiagnosticKind::UnterminatedQuote => {
+                write!(f, "found a quoted field without a closing quote")
+            }
+            DiagnosticKind::UnequalLengths { expected_len, len } => write!(
+                f,
+                "found record with {} fields, but the previous record \
+                 has {} fields",
+                len, expected_len
+            ),
+            DiagnosticKind::Utf8(ref err) => err.fmt(f),
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// The specific problem with a record skipped by a lenient CSV reader.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DiagnosticKind {
+    /// A quoted field without a closing quote. Without lenient parsing, such
+    /// a field extends to the end of the CSV data. Instead, the data after
+    /// the line on which the record starts is parsed again.
+    UnterminatedQuote,
+    /// A record with a different number of fields than the first record.
+    /// This only occurs when the `flexible` option is disabled.
+    UnequalLengths {
+        /// The expected number of fields in a record. This is the number of
+        /// fields in the first record read.
+        expected_len: u64,
+        /// The number of fields in the record.
+        len: u64,
+    },
+    /// A record with invalid UTF-8, which only occurs when reading
+    /// `StringRecord`s (or deserializing records).
+    Utf8(Utf8Error),
+    /// Hints that destructuring should not be exhaustive.
+    ///
+    /// This enum may grow additional variants, so this makes sure clients
+    /// don't count on exhaustive matching. (Otherwise, adding a new variant
+    /// could break existing code.)
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
 /// `IntoInnerError` occurs when consuming a `Writer` fails.
 ///
 /// Consuming the `Writer` causes a flush to happen. If the flush fails, then
@@ -57,6 +57,18 @@ then add this to your `[dependencies]` section of `Cargo.toml`:
 serde = { version = "1", features = ["derive"] }
 ```
 
+If you want to read or write CSV data asynchronously, via
+[`AsyncReader`](struct.AsyncReader.html)
+and
+[`AsyncWriter`](struct.AsyncWriter.html),
+then enable the `async` feature. These work with any type implementing the
+`AsyncRead` or `AsyncWrite` traits from the `futures` crate:
+
+```toml
+[dependencies]
+csv = { version = "1.1", features = ["async"] }
+```
+
 # Example
 
 This example shows how to read CSV data from stdin and print each record to
@@ -153,25 +165,47 @@ use std::result;
 
 use serde::{Deserialize, Deserializer};
 
+#[cfg(feature = "async")]
+pub use crate::async_reader::{
+    AsyncReader, ByteRecordsIntoStream, ByteRecordsStream,
+    DeserializeRecordsIntoStream, DeserializeRecordsStream,
+    StringRecordsIntoStream, StringRecordsStream,
+};
+#[cfg(feature = "async")]
+pub use crate::async_writer::AsyncWriter;
 pub use crate::byte_record::{ByteRecord, ByteRecordIter, Position};
 pub use crate::deserializer::{DeserializeError, DeserializeErrorKind};
 pub use crate::error::{
-    Error, ErrorKind, FromUtf8Error, IntoInnerError, Result, Utf8Error,
+    Diagnostic, DiagnosticKind, Error, ErrorKind, FromUtf8Error,
+    IntoInnerError, Result, Utf8Error,
+};
+pub use crate::parallel_reader::{
+    ParallelByteRecordsIter, ParallelDeserializeRecordsIter, ParallelReader,
+    ParallelStringRecordsIter,
 };
+pub use crate::projection::Selector;
 pub use crate::reader::{
     ByteRecordsIntoIter, ByteRecordsIter, DeserializeRecordsIntoIter,
     DeserializeRecordsIter, Reader, ReaderBuilder, StringRecordsIntoIter,
     StringRecordsIter,
 };
+pub use crate::sniffer::{Column, ColumnType, Schema, Sniffer, Value};
 pub use crate::string_record::{StringRecord, StringRecordIter};
 pub use crate::writer::{Writer, WriterBuilder};
 
+#[cfg(feature = "async")]
+mod async_reader;
+#[cfg(feature = "async")]
+mod async_writer;
 mod byte_record;
 pub mod cookbook;
 mod deserializer;
 mod error;
+mod parallel_reader;
+mod projection;
 mod reader;
 mod serializer;
+mod sniffer;
 mod string_record;
 pub mod tutorial;
 mod writer;
@@ -222,11 +256,43 @@ pub enum QuoteStyle {
     }
 }
 
+/// How sequences in struct fields are written when serializing records.
+///
+/// This applies to sequences, tuples and tuple structs that are the value of
+/// a struct field or map entry, e.g., a `Vec<u32>` field. Sequences that make
+/// up a record themselves are always written as one field per element.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SequenceStyle {
+    /// Writes each element of the sequence in its own field. The header of
+    /// each field is the name of the struct field followed by the index of
+    /// the element, e.g., `values[0]`, `values[1]`.
+    ///
+    /// This is the default.
+    Expand,
+    /// Writes all elements of the sequence in a single field, separated by
+    /// the byte given. The header of the field is the name of the struct
+    /// field. Only sequences of scalars can be joined.
+    Join(u8),
+    /// Hints that destructuring should not be exhaustive.
+    ///
+    /// This enum may grow additional variants, so this makes sure clients
+    /// don't count on exhaustive matching. (Otherwise, adding a new variant
+    /// could break existing code.)
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl Default for SequenceStyle {
+    fn default() -> SequenceStyle {
+        SequenceStyle::Expand
+    }
+}
+
 /// A record terminator.
 ///
 /// Use this to specify the record terminator while parsing CSV. The default is
 /// CRLF, which treats `\r`, `\n` or `\r\n` as a single record terminator.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Terminator {
     /// Parses `\r`, `\n` or `\r\n` as a single record terminator.
     CRLF,
@@ -0,0 +1,1025 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::vec;
+
+use serde::de::DeserializeOwned;
+
+use crate::byte_record::{ByteRecord, Position};
+use crate::error::{Error, Result};
+use crate::reader::{is_terminator, ReaderBuilder, ReaderState};
+use crate::string_record::StringRecord;
+use crate::Trim;
+
+/// The number of threads used when `ReaderBuilder::threads` is `0`.
+///
+/// This is fixed rather than based on the number of CPUs, since querying
+/// that requires a newer Rust than this crate supports.
+const DEFAULT_THREADS: usize = 4;
+
+/// A CSV reader that parses a file using multiple threads.
+///
+/// A `ParallelReader` is built with
+/// [`ReaderBuilder::from_path_parallel`](struct.ReaderBuilder.html#method.from_path_parallel).
+/// It splits a file into chunks, parses the chunks concurrently and returns
+/// the resulting records in their original order. Every record is identical
+/// to the record that a [`Reader`](struct.Reader.html) with the same
+/// configuration would return, including its position.
+///
+/// # Finding record boundaries
+///
+/// A chunk generally starts in the middle of a record. To find the start of
+/// its first record, the thread parsing a chunk skips ahead to the end of
+/// the first record it sees and starts parsing from there, unless the chunk
+/// starts right after a record terminator. This is only a guess, since the
+/// chunk may have started in the middle of a quoted field (which may contain
+/// record terminators).
+///
+/// Once the preceding chunk has been parsed, the true start of every chunk is
+/// known: it's wherever the preceding chunk stopped parsing. If a guess turns
+/// out to be wrong, then the chunk is parsed again from its true start before
+/// any of its records are returned. This makes the result correct for any
+/// CSV data, while data without quoted record terminators rarely needs to be
+/// parsed twice.
+///
+/// # Memory usage
+///
+/// Every record in a chunk is buffered in memory, and a bounded number of
+/// chunks (a small multiple of the number of threads) may be parsed ahead of
+/// the record most recently returned. Memory usage is therefore proportional
+/// to the number of threads times the chunk size, which may be configured
+/// with [`ReaderBuilder::chunk_size`](struct.ReaderBuilder.html#method.chunk_size).
+///
+/// # Example
+///
+/// ```no_run
+/// use std::error::Error;
+/// use csv::ReaderBuilder;
+///
+/// # fn main() { example().unwrap(); }
+/// fn example() -> Result<(), Box<dyn Error>> {
+///     let mut rdr = ReaderBuilder::new().from_path_parallel("foo.csv")?;
+///     let headers = rdr.headers()?.clone();
+///     for result in rdr.records() {
+///         let record = result?;
+///         let position = record.position().expect("a record position");
+///         println!("line {}: {:?}", position.line(), record);
+///     }
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ParallelReader {
+    /// Everything needed to parse a chunk, shared with the worker threads.
+    shared: Arc<Shared>,
+    /// Header, trimming and record length state.
+    ///
+    /// Since records are always returned in order, this is the same state
+    /// used by a `Reader`.
+    state: ReaderState,
+    /// Sends the index of a chunk to parse to the worker threads.
+    ///
+    /// This is only `None` while the reader is being dropped.
+    jobs: Option<Sender<usize>>,
+    /// Receives parsed chunks from the worker threads.
+    results: Receiver<(usize, Result<Chunk>)>,
+    workers: Vec<JoinHandle<()>>,
+    /// Chunks that have been parsed, but aren't next in line.
+    finished: HashMap<usize, Result<Chunk>>,
+    /// The maximum number of chunks to submit ahead of the current one.
+    window: usize,
+    /// The index of the next chunk to submit to the worker threads.
+    submitted: usize,
+    /// The index of the next chunk to return records from.
+    next: usize,
+    /// The true byte offset at which the next chunk starts.
+    start: u64,
+    /// The line number at which the next chunk starts.
+    line: u64,
+    /// The number of records that precede the next chunk.
+    record: u64,
+    /// The remaining records of the current chunk.
+    records: vec::IntoIter<ByteRecord>,
+    /// Set once an error has been returned from parsing a chunk. No records
+    /// are returned after that.
+    failed: bool,
+    /// The number of chunks whose start was guessed wrong, and which were
+    /// parsed again.
+    reparsed: usize,
+}
+
+/// The configuration shared by every thread of a `ParallelReader`.
+#[derive(Debug)]
+struct Shared {
+    path: PathBuf,
+    /// The configuration to use for parsing each chunk.
+    ///
+    /// Headers, trimming, projection and record length checks are disabled,
+    /// since they are handled by `ParallelReader` after the chunks are put
+    /// back in order.
+    builder: ReaderBuilder,
+    /// The record terminator of the parser.
+    term: csv_core::Terminator,
+    /// The length of the file when the reader was built.
+    len: u64,
+    chunk_size: u64,
+    chunks: usize,
+}
+
+/// The records parsed from a single chunk.
+#[derive(Debug)]
+struct Chunk {
+    /// The byte offset of the first record in this chunk.
+    start: u64,
+    /// The byte offset immediately after the last record in this chunk.
+    end: u64,
+    /// The number of lines between `start` and `end`.
+    lines: u64,
+    /// The records in this chunk.
+    ///
+    /// The byte offset of each record's position is correct, but its line
+    /// and record numbers are relative to the start of the chunk (where the
+    /// first line is `1` and the first record is `0`).
+    records: Vec<ByteRecord>,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl ParallelReader {
+    /// Create a new parallel CSV reader given a builder and a file path.
+    pub(crate) fn new(
+        builder: &ReaderBuilder,
+        path: &Path,
+    ) -> Result<ParallelReader> {
+        if builder.lenient {
+            return Err(Error::from(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "lenient parsing is not supported by parallel readers",
+            )));
+        }
+        // Open the file now so that errors are reported by the constructor.
+        File::open(path)?;
+        let len = fs::metadata(path)?.len();
+        let chunks =
+            cmp::max(1, (len + builder.chunk_size - 1) / builder.chunk_size);
+        let mut chunk_builder = builder.clone();
+        chunk_builder
+            .has_headers(false)
+            .flexible(true)
+            .trim(Trim::None);
+        chunk_builder.projection = None;
+        let shared = Arc::new(Shared {
+            path: path.to_path_buf(),
+            term: builder.builder.build().terminator(),
+            builder: chunk_builder,
+            len: len,
+            chunk_size: builder.chunk_size,
+            chunks: chunks as usize,
+        });
+
+        let threads = if builder.threads > 0 {
+            builder.threads
+        } else {
+            DEFAULT_THREADS
+        };
+        let threads = cmp::min(threads, shared.chunks);
+        let (jobs, job_rx) = mpsc::channel();
+        let (result_tx, results) = mpsc::channel();
+        let job_rx = Arc::new(Mutex::new(job_rx));
+        let mut workers = Vec::with_capacity(threads);
+        for _ in 0..threads {
+            let shared = shared.clone();
+            let job_rx = job_rx.clone();
+            let result_tx = result_tx.clone();
+            workers.push(thread::spawn(move || {
+                worker(&shared, &job_rx, &result_tx)
+            }));
+        }
+        Ok(ParallelReader {
+            shared: shared,
+            state: ReaderState::new(builder),
+            jobs: Some(jobs),
+            results: results,
+            workers: workers,
+            finished: HashMap::new(),
+            window: 2 * threads,
+            submitted: 0,
+            next: 0,
+            start: 0,
+            line: 1,
+            record: 0,
+            records: vec![].into_iter(),
+            failed: false,
+            reparsed: 0,
+        })
+    }
+
+    /// Create a new parallel CSV parser with a default configuration for the
+    /// given file path.
+    ///
+    /// To customize CSV parsing, use a `ReaderBuilder`.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<ParallelReader> {
+        ReaderBuilder::new().from_path_parallel(path)
+    }
+
+    /// Returns a borrowed iterator over deserialized records.
+    ///
+    /// This behaves like `Reader::deserialize`. In particular, if
+    /// `has_headers` is enabled, then the header row is used to match up the
+    /// values in each row to the fields of a struct.
+    pub fn deserialize<D>(&mut self) -> ParallelDeserializeRecordsIter<D>
+    where
+        D: DeserializeOwned,
+    {
+        ParallelDeserializeRecordsIter::new(self)
+    }
+
+    /// Returns a borrowed iterator over all records as strings.
+    ///
+    /// If `has_headers` was enabled via a `ReaderBuilder` (which is the
+    /// default), then this does not include the first record.
+    pub fn records(&mut self) -> ParallelStringRecordsIter {
+        ParallelStringRecordsIter::new(self)
+    }
+
+    /// Returns a borrowed iterator over all records as raw bytes.
+    ///
+    /// If `has_headers` was enabled via a `ReaderBuilder` (which is the
+    /// default), then this does not include the first record.
+    pub fn byte_records(&mut self) -> ParallelByteRecordsIter {
+        ParallelByteRecordsIter::new(self)
+    }
+
+    /// Returns a reference to the first row read by this parser.
+    ///
+    /// If no row has been read yet, then this will force parsing of the first
+    /// row.
+    ///
+    /// If there was a problem parsing the row or if it wasn't valid UTF-8,
+    /// then this returns an error.
+    ///
+    /// If the file is empty, then this returns an empty record.
+    pub fn headers(&mut self) -> Result<&StringRecord> {
+        self.read_headers()?;
+        self.state.headers.as_ref().unwrap().as_string_record()
+    }
+
+    /// Returns a reference to the first row read by this parser as raw bytes.
+    ///
+    /// If no row has been read yet, then this will force parsing of the first
+    /// row.
+    ///
+    /// If there was a problem parsing the row then this returns an error.
+    ///
+    /// If the file is empty, then this returns an empty record.
+    pub fn byte_headers(&mut self) -> Result<&ByteRecord> {
+        self.read_headers()?;
+        Ok(&self.state.headers.as_ref().unwrap().byte_record)
+    }
+
+    /// Set the headers of this CSV parser manually.
+    ///
+    /// This overrides any other setting (including `set_byte_headers`). Any
+    /// automatic detection of headers is disabled. This may be called at any
+    /// time.
+    pub fn set_headers(&mut self, headers: StringRecord) {
+        self.state.set_headers_by_caller(Ok(headers));
+    }
+
+    /// Set the headers of this CSV parser manually as raw bytes.
+    ///
+    /// This overrides any other setting (including `set_headers`). Any
+    /// automatic detection of headers is disabled. This may be called at any
+    /// time.
+    pub fn set_byte_headers(&mut self, headers: ByteRecord) {
+        self.state.set_headers_by_caller(Err(headers));
+    }
+
+    /// Read a single row into the given record. Returns false when no more
+    /// records could be read.
+    ///
+    /// If `has_headers` was enabled via a `ReaderBuilder` (which is the
+    /// default), then this will never read the first record.
+    pub fn read_record(&mut self, record: &mut StringRecord) -> Result<bool> {
+        let result = record.read_parallel(self);
+        // See `Reader::read_record` for why we trim again.
+        if self.state.trim.should_trim_fields() {
+            record.trim();
+        }
+        result
+    }
+
+    /// Read a single row into the given byte record. Returns false when no
+    /// more records could be read.
+    ///
+    /// If `has_headers` was enabled via a `ReaderBuilder` (which is the
+    /// default), then this will never read the first record.
+    pub fn read_byte_record(
+        &mut self,
+        record: &mut ByteRecord,
+    ) -> Result<bool> {
+        let result = self.read_byte_record_headers(record);
+        self.state.attach_headers(record);
+        result
+    }
+
+    /// Read a single row, accounting for headers.
+    fn read_byte_record_headers(
+        &mut self,
+        record: &mut ByteRecord,
+    ) -> Result<bool> {
+        let trim = self.state.trim.should_trim_fields();
+        if !self.state.has_headers && !self.state.first {
+            // If the caller indicated "no headers" and we haven't yielded the
+            // first record yet, then we should yield our header row if we have
+            // one.
+            if let Some(ref headers) = self.state.headers {
+                self.state.first = true;
+                record.clone_from(&headers.byte_record);
+                if trim {
+                    record.trim();
+                }
+                return Ok(!record.is_empty());
+            }
+        }
+        let mut ok = self.read_byte_record_impl(record)?;
+        self.state.first = true;
+        if self.state.headers.is_none() {
+            self.state.set_headers(Err(record.clone()));
+            // If the end user indicated that we have headers, then we should
+            // never return the first row. Instead, we should attempt to
+            // read and return the next one.
+            if self.state.has_headers {
+                ok = self.read_byte_record_impl(record)?;
+            }
+        }
+        if trim {
+            record.trim();
+        }
+        Ok(ok)
+    }
+
+    /// Returns true if and only if this reader has been exhausted.
+    pub fn is_done(&self) -> bool {
+        self.state.eof
+    }
+
+    /// Returns true if and only if this reader has been configured to
+    /// interpret the first record as a header record.
+    pub fn has_headers(&self) -> bool {
+        self.state.has_headers
+    }
+
+    /// Ensure the header row has been read.
+    fn read_headers(&mut self) -> Result<()> {
+        if self.state.headers.is_none() {
+            let mut record = ByteRecord::new();
+            self.read_byte_record_impl(&mut record)?;
+            self.state.set_headers(Err(record));
+        }
+        Ok(())
+    }
+
+    /// Read the next record, without accounting for headers.
+    fn read_byte_record_impl(
+        &mut self,
+        record: &mut ByteRecord,
+    ) -> Result<bool> {
+        loop {
+            if let Some(next) = self.records.next() {
+                *record = next;
+                self.state.cur_pos = record.position().unwrap().clone();
+                self.state.project(record)?;
+                self.state.add_record(record)?;
+                return Ok(true);
+            }
+            if self.failed || self.next >= self.shared.chunks {
+                self.state.eof = true;
+                record.clear();
+                let mut pos = Position::new();
+                pos.set_byte(self.start)
+                    .set_line(self.line)
+                    .set_record(self.record);
+                record.set_position(Some(pos));
+                return Ok(false);
+            }
+            self.next_chunk()?;
+        }
+    }
+
+    /// Load the records of the next chunk, in order.
+    fn next_chunk(&mut self) -> Result<()> {
+        let jobs = self.jobs.as_ref().unwrap();
+        while self.submitted < self.shared.chunks
+            && self.submitted < self.next + self.window
+        {
+            // The worker threads only go away when we're dropped.
+            jobs.send(self.submitted).unwrap();
+            self.submitted += 1;
+        }
+        let result = loop {
+            if let Some(result) = self.finished.remove(&self.next) {
+                break result;
+            }
+            // Worker threads never panic or exit while we're alive, so a
+            // result for every submitted chunk will eventually arrive.
+            let (index, result) = self.results.recv().unwrap();
+            self.finished.insert(index, result);
+        };
+        let index = self.next;
+        self.next += 1;
+        let chunk = match result {
+            Ok(ref chunk) if chunk.start == self.start => result,
+            // Either the guessed start of this chunk was wrong, or reading
+            // failed. Since an error might have been caused by a bad guess
+            // (for example, because it made a quoted field appear to never
+            // end), parse the chunk again before giving up.
+            _ => {
+                self.reparsed += 1;
+                parse_chunk(&self.shared, index, Some(self.start))
+            }
+        };
+        let mut chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(err) => {
+                self.failed = true;
+                return Err(err);
+            }
+        };
+        for record in &mut chunk.records {
+            let mut pos = record.position().unwrap().clone();
+            let (line, rec) = (pos.line(), pos.record());
+            pos.set_line(self.line + line - 1).set_record(self.record + rec);
+            record.set_position(Some(pos));
+        }
+        self.start = chunk.end;
+        self.line += chunk.lines;
+        self.record += chunk.records.len() as u64;
+        self.records = chunk.records.into_iter();
+        Ok(())
+    }
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl Drop for ParallelReader {
+    fn drop(&mut self) {
+        // Hanging up makes every worker exit once it finishes its current
+        // chunk.
+        self.jobs = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Parse chunks submitted by a `ParallelReader` until it hangs up.
+fn worker(
+    shared: &Shared,
+    jobs: &Mutex<Receiver<usize>>,
+    results: &Sender<(usize, Result<Chunk>)>,
+) {
+    loop {
+        let index = match jobs.lock().unwrap().recv() {
+            Ok(index) => index,
+            Err(_) => return,
+        };
+        let chunk = parse_chunk(shared, index, None);
+        if results.send((index, chunk)).is_err() {
+            return;
+        }
+    }
+}
+
+/// Parse the records that belong to the chunk at `index`.
+///
+/// A chunk owns every record whose position lies within its byte range,
+/// which is `[index * chunk_size, (index + 1) * chunk_size)`. If `start` is
+/// given, then it must be the true position of the chunk's first record.
+/// Otherwise, the start of the first record is guessed: if the byte before
+/// the chunk's byte range is a record terminator, then the range starts with
+/// a record. If not, the first (partial) record in the range is skipped.
+///
+/// Note that parsing always starts with a fresh parser, so a UTF-8 BOM at the
+/// start of a chunk is stripped. This is only observable if a record in the
+/// middle of a file starts with a BOM.
+fn parse_chunk(
+    shared: &Shared,
+    index: usize,
+    start: Option<u64>,
+) -> Result<Chunk> {
+    let offset = index as u64 * shared.chunk_size;
+    let limit = cmp::min(offset + shared.chunk_size, shared.len);
+    let seek_to = start.unwrap_or(offset);
+    let mut file = File::open(&shared.path)?;
+    let mut partial = start.is_none() && index > 0;
+    if partial {
+        let mut byte = [0];
+        file.seek(io::SeekFrom::Start(offset - 1))?;
+        file.read_exact(&mut byte)?;
+        partial = !is_terminator(shared.term, byte[0]);
+    }
+    file.seek(io::SeekFrom::Start(seek_to))?;
+    let mut rdr = shared.builder.from_reader(file);
+    let mut record = ByteRecord::new();
+    if partial {
+        rdr.read_byte_record(&mut record)?;
+    }
+
+    let first = rdr.position().clone();
+    let mut records = vec![];
+    while seek_to + rdr.position().byte() < limit
+        && rdr.read_byte_record(&mut record)?
+    {
+        let mut pos = record.position().unwrap().clone();
+        let (byte, line, rec) = (pos.byte(), pos.line(), pos.record());
+        pos.set_byte(seek_to + byte)
+            .set_line(line - first.line() + 1)
+            .set_record(rec - first.record());
+        record.set_position(Some(pos));
+        records.push(record.clone());
+    }
+    Ok(Chunk {
+        start: seek_to + first.byte(),
+        end: seek_to + rdr.position().byte(),
+        lines: rdr.position().line() - first.line(),
+        records: records,
+    })
+}
+
+/// A borrowed iterator over deserialized records from a `ParallelReader`.
+///
+/// The lifetime parameter `'r` refers to the lifetime of the underlying
+/// `ParallelReader`, and `D` refers to the type that this iterator will
+/// deserialize a record into.
+pub struct ParallelDeserializeRecordsIter<'r, D> {
+    rdr: &'r mut ParallelReader,
+    rec: StringRecord,
+    headers: Option<StringRecord>,
+    _priv: PhantomData<D>,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<'r, D: DeserializeOwned> ParallelDeserializeRecordsIter<'r, D> {
+    fn new(
+        rdr: &'r mut ParallelReader,
+    ) -> ParallelDeserializeRecordsIter<'r, D> {
+        let headers = if !rdr.state.has_headers {
+            None
+        } else {
+            rdr.headers().ok().map(Clone::clone)
+        };
+        ParallelDeserializeRecordsIter {
+            rdr: rdr,
+            rec: StringRecord::new(),
+            headers: headers,
+            _priv: PhantomData,
+        }
+    }
+
+    /// Return a reference to the underlying CSV reader.
+    pub fn reader(&self) -> &ParallelReader {
+        &self.rdr
+    }
+
+    /// Return a mutable reference to the underlying CSV reader.
+    pub fn reader_mut(&mut self) -> &mut ParallelReader {
+        &mut self.rdr
+    }
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<'r, D: DeserializeOwned> Iterator
+    for ParallelDeserializeRecordsIter<'r, D>
+{
+    type Item = Result<D>;
+
+    fn next(&mut self) -> Option<Result<D>> {
+        match self.rdr.read_record(&mut self.rec) {
+            Err(err) => Some(Err(err)),
+            Ok(false) => None,
+            Ok(true) => Some(self.rec.deserialize(self.headers.as_ref())),
+        }
+    }
+}
+
+/// A borrowed iterator over records as strings from a `ParallelReader`.
+///
+/// The lifetime parameter `'r` refers to the lifetime of the underlying
+/// `ParallelReader`.
+pub struct ParallelStringRecordsIter<'r> {
+    rdr: &'r mut ParallelReader,
+    rec: StringRecord,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<'r> ParallelStringRecordsIter<'r> {
+    fn new(rdr: &'r mut ParallelReader) -> ParallelStringRecordsIter<'r> {
+        ParallelStringRecordsIter { rdr: rdr, rec: StringRecord::new() }
+    }
+
+    /// Return a reference to the underlying CSV reader.
+    pub fn reader(&self) -> &ParallelReader {
+        &self.rdr
+    }
+
+    /// Return a mutable reference to the underlying CSV reader.
+    pub fn reader_mut(&mut self) -> &mut ParallelReader {
+        &mut self.rdr
+    }
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<'r> Iterator for ParallelStringRecordsIter<'r> {
+    type Item = Result<StringRecord>;
+
+    fn next(&mut self) -> Option<Result<StringRecord>> {
+        match self.rdr.read_record(&mut self.rec) {
+            Err(err) => Some(Err(err)),
+            Ok(true) => Some(Ok(self.rec.clone())),
+            Ok(false) => None,
+        }
+    }
+}
+
+/// A borrowed iterator over records as raw bytes from a `ParallelReader`.
+///
+/// The lifetime parameter `'r` refers to the lifetime of the underlying
+/// `ParallelReader`.
+pub struct ParallelByteRecordsIter<'r> {
+    rdr: &'r mut ParallelReader,
+    rec: ByteRecord,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<'r> ParallelByteRecordsIter<'r> {
+    fn new(rdr: &'r mut ParallelReader) -> ParallelByteRecordsIter<'r> {
+        ParallelByteRecordsIter { rdr: rdr, rec: ByteRecord::new() }
+    }
+
+    /// Return a reference to the underlying CSV reader.
+    pub fn reader(&self) -> &ParallelReader {
+        &self.rdr
+    }
+
+    /// Return a mutable reference to the underlying CSV reader.
+    pub fn reader_mut(&mut self) -> &mut ParallelReader {
+        &mut self.rdr
+    }
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<'r> Iterator for ParallelByteRecordsIter<'r> {
+    type Item = Result<ByteRecord>;
+
+    fn next(&mut self) -> Option<Result<ByteRecord>> {
+        match self.rdr.read_byte_record(&mut self.rec) {
+            Err(err) => Some(Err(err)),
+            Ok(true) => Some(Ok(self.rec.clone())),
+            Ok(false) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::io;
+    use std::path::PathBuf;
+    use std::process;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use serde::Deserialize;
+
+    use crate::byte_record::ByteRecord;
+    use crate::error::ErrorKind;
+    use crate::reader::ReaderBuilder;
+    use crate::string_record::StringRecord;
+    use crate::{Terminator, Trim};
+
+    use super::ParallelReader;
+
+    /// A file in the temporary directory that is removed on drop.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(data: &str) -> TempFile {
+            static COUNT: AtomicUsize = AtomicUsize::new(0);
+            let name = format!(
+                "csv-parallel-{}-{}.csv",
+                process::id(),
+                COUNT.fetch_add(1, Ordering::SeqCst)
+            );
+            let path = env::temp_dir().join(name);
+            fs::write(&path, data).unwrap();
+            TempFile(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn sync_records(
+        builder: &ReaderBuilder,
+        data: &str,
+    ) -> Vec<Result<ByteRecord, String>> {
+        let mut rdr = builder.from_reader(data.as_bytes());
+        rdr.byte_records().map(|r| r.map_err(|e| e.to_string())).collect()
+    }
+
+    fn parallel_records(
+        builder: &ReaderBuilder,
+        data: &str,
+    ) -> Vec<Result<ByteRecord, String>> {
+        let file = TempFile::new(data);
+        let mut rdr = builder.from_path_parallel(&file.0).unwrap();
+        rdr.byte_records().map(|r| r.map_err(|e| e.to_string())).collect()
+    }
+
+    /// Check that parallel reading agrees with `Reader` on both records and
+    /// positions, for every chunk size up to the length of `data`.
+    fn agree(builder: &ReaderBuilder, data: &str) {
+        let expected = sync_records(builder, data);
+        for chunk_size in 1..data.len() as u64 + 2 {
+            for &threads in &[1, 3] {
+                let mut builder = builder.clone();
+                builder.chunk_size(chunk_size).threads(threads);
+                let got = parallel_records(&builder, data);
+                assert_eq!(
+                    expected.len(),
+                    got.len(),
+                    "chunk size: {}, threads: {}",
+                    chunk_size,
+                    threads
+                );
+                for (e, g) in expected.iter().zip(&got) {
+                    let e = e.as_ref().map(|r| (r, r.position()));
+                    let g = g.as_ref().map(|r| (r, r.position()));
+                    assert_eq!(
+                        e, g,
+                        "chunk size: {}, threads: {}",
+                        chunk_size, threads
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn simple() {
+        agree(&ReaderBuilder::new(), "a,b,c\nfoo,bar,baz\n1,2,3\nx,y,z");
+    }
+
+    #[test]
+    fn quoted_terminators() {
+        let data = "h1,h2\n\"a\nb\nc\",d\n\"\n\",\"\"\"\n\"\ne,\"\r\nf\"\n";
+        agree(&ReaderBuilder::new(), data);
+        agree(ReaderBuilder::new().has_headers(false), data);
+    }
+
+    #[test]
+    fn crlf_and_blank_lines() {
+        let data = "a,b\r\n\r\n\r\nc,d\r\n\ne,f\n\n\n\r\ng,h\r\n\n";
+        agree(&ReaderBuilder::new(), data);
+        agree(ReaderBuilder::new().has_headers(false), data);
+    }
+
+    #[test]
+    fn trim() {
+        let data = " a , b \n c ,d\n\" e\"  ,  \"f \"\n";
+        agree(ReaderBuilder::new().trim(Trim::All), data);
+        agree(ReaderBuilder::new().trim(Trim::Headers), data);
+        agree(ReaderBuilder::new().trim(Trim::Fields), data);
+    }
+
+    #[test]
+    fn comments_and_escapes() {
+        let data = "a,b\n#c,\"d\ne\n\"f\\\"\n\",g\n";
+        agree(
+            ReaderBuilder::new().comment(Some(b'#')).escape(Some(b'\\')),
+            data,
+        );
+    }
+
+    #[test]
+    fn unequal_lengths() {
+        let data = "a,b\nc,d\ne,f,g\nh,i\n";
+        agree(&ReaderBuilder::new(), data);
+        agree(ReaderBuilder::new().flexible(true), data);
+
+        let file = TempFile::new(data);
+        let mut rdr = ReaderBuilder::new()
+            .chunk_size(4)
+            .from_path_parallel(&file.0)
+            .unwrap();
+        let mut record = ByteRecord::new();
+        assert!(rdr.read_byte_record(&mut record).unwrap());
+        let err = rdr.read_byte_record(&mut record).unwrap_err();
+        match *err.kind() {
+            ErrorKind::UnequalLengths { ref pos, expected_len, len } => {
+                let pos = pos.as_ref().unwrap();
+                assert_eq!(pos.byte(), 8);
+                assert_eq!(pos.line(), 3);
+                assert_eq!(pos.record(), 2);
+                assert_eq!(expected_len, 2);
+                assert_eq!(len, 3);
+            }
+            ref x => panic!("expected UnequalLengths error, but got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn empty() {
+        agree(&ReaderBuilder::new(), "");
+        let file = TempFile::new("");
+        let mut rdr = ParallelReader::from_path(&file.0).unwrap();
+        assert!(rdr.headers().unwrap().is_empty());
+        assert_eq!(rdr.records().count(), 0);
+        assert!(rdr.is_done());
+    }
+
+    #[test]
+    fn headers() {
+        let file = TempFile::new("a,b\nc,d\ne,f\n");
+        let mut rdr = ReaderBuilder::new()
+            .chunk_size(3)
+            .from_path_parallel(&file.0)
+            .unwrap();
+        assert_eq!(rdr.headers().unwrap(), vec!["a", "b"]);
+        let records = rdr.records().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(records, vec![vec!["c", "d"], vec!["e", "f"]]);
+
+        let mut rdr = ReaderBuilder::new()
+            .has_headers(false)
+            .chunk_size(3)
+            .from_path_parallel(&file.0)
+            .unwrap();
+        assert_eq!(rdr.byte_headers().unwrap(), vec!["a", "b"]);
+        assert_eq!(rdr.records().count(), 3);
+    }
+
+    #[test]
+    fn set_headers() {
+        let file = TempFile::new("a,b\nc,d\n");
+        let mut rdr = ReaderBuilder::new()
+            .has_headers(false)
+            .chunk_size(2)
+            .from_path_parallel(&file.0)
+            .unwrap();
+        rdr.set_headers(StringRecord::from(vec!["x", "y"]));
+        assert_eq!(rdr.headers().unwrap(), vec!["x", "y"]);
+        // Like `Reader`, headers set manually are returned as the first
+        // record when `has_headers` is disabled.
+        let records = rdr.records().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(
+            records,
+            vec![vec!["x", "y"], vec!["a", "b"], vec!["c", "d"]]
+        );
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let file = TempFile::new("a,b\n");
+        let mut bytes = fs::read(&file.0).unwrap();
+        bytes.extend_from_slice(b"c,\xff\ne,f\n");
+        fs::write(&file.0, bytes).unwrap();
+
+        let mut rdr = ReaderBuilder::new()
+            .has_headers(false)
+            .chunk_size(4)
+            .from_path_parallel(&file.0)
+            .unwrap();
+        let mut record = StringRecord::new();
+        assert!(rdr.read_record(&mut record).unwrap());
+        let err = rdr.read_record(&mut record).unwrap_err();
+        match *err.kind() {
+            ErrorKind::Utf8 { ref pos, ref err } => {
+                assert_eq!(pos.as_ref().unwrap().byte(), 4);
+                assert_eq!(pos.as_ref().unwrap().record(), 1);
+                assert_eq!(err.field(), 1);
+            }
+            ref x => panic!("expected Utf8 error, but got {:?}", x),
+        }
+        assert!(rdr.read_record(&mut record).unwrap());
+        assert_eq!(record, vec!["e", "f"]);
+    }
+
+    #[test]
+    fn deserialize() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Row {
+            name: String,
+            value: i32,
+        }
+
+        let file = TempFile::new("value,name\n1,\"a\nb\"\n2,c\n");
+        let mut rdr = ReaderBuilder::new()
+            .chunk_size(5)
+            .threads(2)
+            .from_path_parallel(&file.0)
+            .unwrap();
+        let rows = rdr.deserialize().collect::<Result<Vec<Row>, _>>().unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                Row { name: "a\nb".to_string(), value: 1 },
+                Row { name: "c".to_string(), value: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn many_records() {
+        let mut data = String::new();
+        for i in 0..2000 {
+            if i % 7 == 0 {
+                data.push_str(&format!("{},\"multi\nline {}\"\r\n", i, i));
+            } else {
+                data.push_str(&format!("{},field {}\n", i, i));
+            }
+        }
+        let builder = ReaderBuilder::new();
+        let expected = sync_records(&builder, &data);
+        for &chunk_size in &[10, 97, 1000, 4096, 1 << 20] {
+            for &threads in &[0, 1, 4] {
+                let mut builder = builder.clone();
+                builder.chunk_size(chunk_size).threads(threads);
+                assert_eq!(expected, parallel_records(&builder, &data));
+            }
+        }
+    }
+
+    #[test]
+    fn drop_early() {
+        let data = "a,b\n".repeat(1000);
+        let file = TempFile::new(&data);
+        let mut rdr = ReaderBuilder::new()
+            .chunk_size(16)
+            .threads(4)
+            .from_path_parallel(&file.0)
+            .unwrap();
+        assert_eq!(rdr.byte_records().take(3).count(), 3);
+    }
+
+    #[test]
+    fn select_columns() {
+        let data = "a,b,c\n1,\"x\ny\",3\n4,5,6\n7,8,9\n";
+        let file = TempFile::new(data);
+        for chunk_size in 1..data.len() as u64 + 1 {
+            let mut rdr = ReaderBuilder::new()
+                .chunk_size(chunk_size)
+                .select(vec!["b", "a"])
+                .from_path_parallel(&file.0)
+                .unwrap();
+            assert_eq!(rdr.headers().unwrap(), vec!["a", "b"]);
+            let records =
+                rdr.records().collect::<Result<Vec<_>, _>>().unwrap();
+            assert_eq!(
+                records,
+                vec![vec!["1", "x\ny"], vec!["4", "5"], vec!["7", "8"]]
+            );
+            assert_eq!(records[2].get_by_name("b"), Some("8"));
+        }
+    }
+
+    #[test]
+    fn aligned_chunks_not_reparsed() {
+        // Every chunk starts exactly at a record, so every guess is right.
+        for &term in &[b'\n', b';'] {
+            let row = format!("aaa,bbb{}", term as char);
+            let file = TempFile::new(&row.repeat(64));
+            let mut rdr = ReaderBuilder::new()
+                .has_headers(false)
+                .terminator(Terminator::Any(term))
+                .chunk_size(16)
+                .threads(3)
+                .from_path_parallel(&file.0)
+                .unwrap();
+            let records = rdr.byte_records().collect::<Result<Vec<_>, _>>();
+            assert_eq!(records.unwrap().len(), 64);
+            assert_eq!(rdr.reparsed, 0);
+        }
+    }
+
+    #[test]
+    fn lenient_unsupported() {
+        let file = TempFile::new("a,b\n1,2\n");
+        let mut builder = ReaderBuilder::new();
+        builder.lenient(true);
+        match builder.from_path_parallel(&file.0) {
+            Err(err) => match *err.kind() {
+                ErrorKind::Io(ref err) => {
+                    assert_eq!(err.kind(), io::ErrorKind::InvalidInput)
+                }
+                ref wrong => panic!("match failed, got {:?}", wrong),
+            },
+            Ok(_) => panic!("lenient parallel reader was built"),
+        }
+    }
+
+    #[test]
+    fn missing_file() {
+        let path = env::temp_dir().join("csv-parallel-does-not-exist.csv");
+        assert!(ParallelReader::from_path(path).is_err());
+    }
+}
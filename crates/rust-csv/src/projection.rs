@@ -0,0 +1,152 @@
+use crate::byte_record::ByteRecord;
+use crate::error::{Error, ErrorKind, Result};
+
+/// A column selected by
+/// [`ReaderBuilder::select`](struct.ReaderBuilder.html#method.select).
+///
+/// A column may be selected either by its index or by its name in the header
+/// row. Values of this type are usually built with `From`, for example, from
+/// a `usize` or a `&str`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Selector {
+    /// Selects the column at the given index, starting at `0`.
+    Index(usize),
+    /// Selects the first column whose header has the given name.
+    Name(String),
+    /// Hints that destructuring should not be exhaustive.
+    ///
+    /// This enum may grow additional variants, so this makes sure clients
+    /// don't count on exhaustive matching. (Otherwise, adding a new variant
+    /// could break existing code.)
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+impl From<usize> for Selector {
+    fn from(i: usize) -> Selector {
+        Selector::Index(i)
+    }
+}
+
+impl<'a> From<&'a str> for Selector {
+    fn from(name: &'a str) -> Selector {
+        Selector::Name(name.to_string())
+    }
+}
+
+impl From<String> for Selector {
+    fn from(name: String) -> Selector {
+        Selector::Name(name)
+    }
+}
+
+/// The set of columns that a reader keeps from each record.
+#[derive(Clone, Debug)]
+pub(crate) struct Projection {
+    selectors: Vec<Selector>,
+    /// Whether the column at each index is selected. Columns past the end
+    /// are not selected.
+    ///
+    /// This is `None` until names have been resolved against the header row.
+    mask: Option<Vec<bool>>,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl Projection {
+    /// Create a new projection from the given selectors.
+    ///
+    /// If every column is selected by index, then the projection is resolved
+    /// immediately.
+    pub(crate) fn new(selectors: Vec<Selector>) -> Projection {
+        let mut projection = Projection { selectors: selectors, mask: None };
+        let by_index = projection.selectors.iter().all(|s| match *s {
+            Selector::Index(_) => true,
+            _ => false,
+        });
+        if by_index {
+            // Resolving indices can't fail, since no name is looked up.
+            projection.resolve(&ByteRecord::new(), false).unwrap();
+        }
+        projection
+    }
+
+    /// Returns true if and only if this projection knows which columns are
+    /// selected.
+    #[inline]
+    pub(crate) fn is_resolved(&self) -> bool {
+        self.mask.is_some()
+    }
+
+    /// Returns true if and only if the column at index `i` is selected.
+    ///
+    /// This must only be called on a resolved projection.
+    #[inline]
+    pub(crate) fn is_selected(&self, i: usize) -> bool {
+        self.mask.as_ref().unwrap().get(i).map_or(false, |&b| b)
+    }
+
+    /// Resolve the names in this projection against the given header row.
+    ///
+    /// If the header row should be trimmed, then the names are compared with
+    /// trimmed headers.
+    pub(crate) fn resolve(
+        &mut self,
+        headers: &ByteRecord,
+        trim: bool,
+    ) -> Result<()> {
+        let mask = self.build_mask(headers, trim)?;
+        self.mask = Some(mask);
+        Ok(())
+    }
+
+    /// Remove every column that isn't selected from the given record.
+    ///
+    /// This must only be called on a resolved projection.
+    pub(crate) fn apply(&self, record: &mut ByteRecord) {
+        let mut projected =
+            ByteRecord::with_capacity(record.as_slice().len(), record.len());
+        projected.set_position(record.position().cloned());
+        for (i, field) in record.iter().enumerate() {
+            if self.is_selected(i) {
+                projected.push_field(field);
+            }
+        }
+        *record = projected;
+    }
+
+    fn build_mask(
+        &self,
+        headers: &ByteRecord,
+        trim: bool,
+    ) -> Result<Vec<bool>> {
+        let mut trimmed;
+        let headers = if trim {
+            trimmed = headers.clone();
+            trimmed.trim();
+            &trimmed
+        } else {
+            headers
+        };
+        let mut mask = vec![];
+        for selector in &self.selectors {
+            let i = match *selector {
+                Selector::Index(i) => i,
+                Selector::Name(ref name) => {
+                    match headers.iter().position(|h| h == name.as_bytes()) {
+                        Some(i) => i,
+                        None => {
+                            return Err(Error::new(ErrorKind::UnknownColumn(
+                                name.clone(),
+                            )))
+                        }
+                    }
+                }
+                Selector::__Nonexhaustive => unreachable!(),
+            };
+            if i >= mask.len() {
+                mask.resize(i + 1, false);
+            }
+            mask[i] = true;
+        }
+        Ok(mask)
+    }
+}
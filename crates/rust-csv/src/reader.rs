@@ -1,14 +1,27 @@
 use std::fs::File;
 use std::io::{self, BufRead, Seek};
 use std::marker::PhantomData;
+use std::mem;
 use std::path::Path;
 use std::result;
-
-use csv_core::{Reader as CoreReader, ReaderBuilder as CoreReaderBuilder};
+use std::sync::Arc;
+
+use csv_core::{
+    Reader as CoreReader, ReaderBuilder as CoreReaderBuilder,
+    Terminator as CoreTerminator,
+};
+#[cfg(feature = "async")]
+use futures_io::AsyncRead;
 use serde::de::DeserializeOwned;
 
-use crate::byte_record::{ByteRecord, Position};
-use crate::error::{Error, ErrorKind, Result, Utf8Error};
+#[cfg(feature = "async")]
+use crate::async_reader::AsyncReader;
+use crate::byte_record::{ByteRecord, HeaderIndex, Position};
+use crate::error::{
+    Diagnostic, DiagnosticKind, Error, ErrorKind, Result, Utf8Error,
+};
+use crate::parallel_reader::ParallelReader;
+use crate::projection::{Projection, Selector};
 use crate::string_record::StringRecord;
 use crate::{Terminator, Trim};
 
@@ -17,18 +30,33 @@ use crate::{Terminator, Trim};
 /// This builder can be used to tweak the field delimiter, record terminator
 /// and more. Once a CSV `Reader` is built, its configuration cannot be
 /// changed.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct ReaderBuilder {
-    capacity: usize,
+    pub(crate) capacity: usize,
     flexible: bool,
     has_headers: bool,
     trim: Trim,
+    /// The columns to keep from each record, if not all of them.
+    pub(crate) projection: Option<Projection>,
+    /// The number of threads used by a `ParallelReader`, or `0` to use the
+    /// default.
+    pub(crate) threads: usize,
+    /// The approximate number of bytes parsed at a time by each thread of a
+    /// `ParallelReader`.
+    pub(crate) chunk_size: u64,
+    /// Whether malformed records are skipped and reported as diagnostics.
+    pub(crate) lenient: bool,
+    /// The maximum number of malformed records skipped in lenient mode.
+    max_errors: Option<usize>,
+    /// The number of bytes a record with an open quote may span in lenient
+    /// mode before the closing quote is assumed to be missing.
+    max_quoted_len: Option<usize>,
     /// The underlying CSV parser builder.
     ///
     /// We explicitly put this on the heap because CoreReaderBuilder embeds an
     /// entire DFA transition table, which along with other things, tallies up
     /// to almost 500 bytes on the stack.
-    builder: Box<CoreReaderBuilder>,
+    pub(crate) builder: Box<CoreReaderBuilder>,
 }
 
 #[cfg_attr(test, ::mutagen::mutate)] impl Default for ReaderBuilder {
@@ -38,6 +66,12 @@ pub struct ReaderBuilder {
             flexible: false,
             has_headers: true,
             trim: Trim::default(),
+            projection: None,
+            threads: 0,
+            chunk_size: 16 * (1 << 20),
+            lenient: false,
+            max_errors: None,
+            max_quoted_len: Some(1 << 20),
             builder: Box::new(CoreReaderBuilder::default()),
         }
     }
@@ -104,6 +138,44 @@ pub struct ReaderBuilder {
         Ok(Reader::new(self, File::open(path)?))
     }
 
+    /// Build a CSV parser from this configuration that reads data from the
+    /// given file path using multiple threads.
+    ///
+    /// The file is split into chunks of roughly `chunk_size` bytes, which are
+    /// parsed concurrently by `threads` threads. Records are still returned
+    /// in their original order and with the same positions that a `Reader`
+    /// would report. See [`ParallelReader`](struct.ParallelReader.html) for
+    /// more details.
+    ///
+    /// If there was a problem opening the file at the given path, then this
+    /// returns the corresponding error. Since parallel readers don't support
+    /// lenient parsing, this also returns an error if `lenient` is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::error::Error;
+    /// use csv::ReaderBuilder;
+    ///
+    /// # fn main() { example().unwrap(); }
+    /// fn example() -> Result<(), Box<dyn Error>> {
+    ///     let mut rdr = ReaderBuilder::new()
+    ///         .threads(8)
+    ///         .from_path_parallel("foo.csv")?;
+    ///     for result in rdr.byte_records() {
+    ///         let record = result?;
+    ///         println!("{:?}", record);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn from_path_parallel<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> Result<ParallelReader> {
+        ParallelReader::new(self, path.as_ref())
+    }
+
     /// Build a CSV parser from this configuration that reads data from `rdr`.
     ///
     /// Note that the CSV reader is buffered automatically, so you should not
@@ -134,6 +206,50 @@ pub struct ReaderBuilder {
         Reader::new(self, rdr)
     }
 
+    /// Build an asynchronous CSV parser from this configuration that reads
+    /// data from `rdr`.
+    ///
+    /// As with `from_reader`, the CSV reader is buffered automatically, so
+    /// `rdr` should not be wrapped in a buffered reader.
+    ///
+    /// Asynchronous readers don't support lenient parsing, so `lenient`
+    /// must not be enabled. Otherwise, this panics in debug builds, and
+    /// malformed records are reported as errors in release builds.
+    ///
+    /// This requires the `async` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::error::Error;
+    /// use csv::ReaderBuilder;
+    /// use futures::executor::block_on;
+    ///
+    /// # fn main() { block_on(example()).unwrap(); }
+    /// async fn example() -> Result<(), Box<dyn Error>> {
+    ///     let data = "\
+    /// city;country;pop
+    /// Boston;United States;4628910
+    /// ";
+    ///     let mut rdr = ReaderBuilder::new()
+    ///         .delimiter(b';')
+    ///         .from_async_reader(data.as_bytes());
+    ///     assert_eq!(rdr.headers().await?, vec!["city", "country", "pop"]);
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "async")]
+    pub fn from_async_reader<R: AsyncRead + Unpin>(
+        &self,
+        rdr: R,
+    ) -> AsyncReader<R> {
+        debug_assert!(
+            !self.lenient,
+            "lenient parsing is not supported by asynchronous readers"
+        );
+        AsyncReader::new(self, rdr)
+    }
+
     /// The field delimiter to use when parsing CSV.
     ///
     /// The default is `b','`.
@@ -305,6 +421,182 @@ pub struct ReaderBuilder {
         self
     }
 
+    /// Whether malformed records are skipped instead of stopping the reader.
+    ///
+    /// When enabled, a record that would otherwise cause an error is skipped
+    /// and described by a [`Diagnostic`](struct.Diagnostic.html), which can
+    /// be retrieved with `Reader::diagnostics`. The following problems are
+    /// recovered from:
+    ///
+    /// * A record with a different number of fields than the first record,
+    ///   unless `flexible` is enabled.
+    /// * A record that isn't valid UTF-8, when reading `StringRecord`s or
+    ///   deserializing records.
+    /// * A quoted field whose closing quote is missing. Normally, such a
+    ///   field swallows all remaining CSV data. In lenient mode, the record
+    ///   is assumed to end at its first record terminator (as configured by
+    ///   `terminator`), and parsing resumes after it. A quote is considered
+    ///   unclosed when the end of the data is reached, or when the record
+    ///   grows past `max_quoted_len` bytes.
+    ///
+    /// All other errors, such as I/O errors or deserialization errors, are
+    /// still returned. The number of records skipped can be limited with
+    /// `max_errors`.
+    ///
+    /// This is disabled by default. Only `Reader` supports lenient parsing.
+    /// `from_path_parallel` returns an error if it is enabled, and
+    /// `from_async_reader` panics in debug builds.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::error::Error;
+    /// use csv::{DiagnosticKind, ReaderBuilder};
+    ///
+    /// # fn main() { example().unwrap(); }
+    /// fn example() -> Result<(), Box<dyn Error>> {
+    ///     // Notice that the second row has an unclosed quote, and that the
+    ///     // third row is missing the population count.
+    ///     let data = "\
+    /// city,country,pop
+    /// Boston,United States,4628910
+    /// \"Concord,United States,42695
+    /// Chicago,United States
+    /// Seattle,United States,737015
+    /// ";
+    ///     let mut rdr = ReaderBuilder::new()
+    ///         .lenient(true)
+    ///         .from_reader(data.as_bytes());
+    ///
+    ///     let mut cities = vec![];
+    ///     for result in rdr.records() {
+    ///         let record = result?;
+    ///         cities.push(record[0].to_string());
+    ///     }
+    ///     assert_eq!(cities, vec!["Boston", "Seattle"]);
+    ///
+    ///     let diagnostics = rdr.diagnostics();
+    ///     assert_eq!(diagnostics.len(), 2);
+    ///     let kind = diagnostics[0].kind();
+    ///     assert_eq!(kind, &DiagnosticKind::UnterminatedQuote);
+    ///     assert_eq!(diagnostics[0].position().line(), 3);
+    ///     assert_eq!(diagnostics[1].position().line(), 4);
+    ///     assert_eq!(diagnostics[1].raw(), b"Chicago,United States\n");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn lenient(&mut self, yes: bool) -> &mut ReaderBuilder {
+        self.lenient = yes;
+        self
+    }
+
+    /// The maximum number of malformed records skipped in lenient mode.
+    ///
+    /// Once this many records have been skipped, the reader behaves as if
+    /// lenient mode were disabled, and the next malformed record causes an
+    /// error to be returned.
+    ///
+    /// This has no effect unless `lenient` is enabled. By default, there is
+    /// no limit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::error::Error;
+    /// use csv::{ErrorKind, ReaderBuilder};
+    ///
+    /// # fn main() { example().unwrap(); }
+    /// fn example() -> Result<(), Box<dyn Error>> {
+    ///     let data = "\
+    /// city,country,pop
+    /// Boston,United States
+    /// Concord,United States
+    /// ";
+    ///     let mut rdr = ReaderBuilder::new()
+    ///         .lenient(true)
+    ///         .max_errors(Some(1))
+    ///         .from_reader(data.as_bytes());
+    ///
+    ///     match rdr.records().next() {
+    ///         Some(Err(ref err)) => match *err.kind() {
+    ///             ErrorKind::UnequalLengths { .. } => {}
+    ///             ref wrong => {
+    ///                 return Err(From::from(format!(
+    ///                     "expected UnequalLengths error but got {:?}",
+    ///                     wrong)));
+    ///             }
+    ///         },
+    ///         _ => return Err(From::from("expected an error")),
+    ///     }
+    ///     assert_eq!(rdr.diagnostics().len(), 1);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn max_errors(&mut self, limit: Option<usize>) -> &mut ReaderBuilder {
+        self.max_errors = limit;
+        self
+    }
+
+    /// The number of bytes that a record containing an open quote may span
+    /// in lenient mode before its closing quote is assumed to be missing.
+    ///
+    /// A lenient reader keeps the bytes of the record it's reading, so that
+    /// it can parse them again if the record turns out to be malformed.
+    /// Without a limit, a quote that is never closed makes the reader keep
+    /// everything up to the end of the data. Once a record with an open
+    /// quote reaches this many bytes, it is skipped up to its first record
+    /// terminator as if the data ended there, and the raw bytes of its
+    /// diagnostic are cut short if it is longer than the limit. A quoted
+    /// field that legitimately spans more bytes than this is skipped too.
+    ///
+    /// The limit is checked each time the reader's buffer is refilled, so a
+    /// record may grow past it by up to `buffer_capacity` bytes.
+    ///
+    /// This has no effect unless `lenient` is enabled. By default, the limit
+    /// is 1 MB. Setting it to `None` removes the limit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::error::Error;
+    /// use csv::{DiagnosticKind, ReaderBuilder};
+    ///
+    /// # fn main() { example().unwrap(); }
+    /// fn example() -> Result<(), Box<dyn Error>> {
+    ///     let data = "\
+    /// city,pop
+    /// \"Boston,4628910
+    /// Concord,42695
+    /// Seattle,737015
+    /// \"";
+    ///     let mut rdr = ReaderBuilder::new()
+    ///         .lenient(true)
+    ///         .max_quoted_len(Some(16))
+    ///         .buffer_capacity(16)
+    ///         .from_reader(data.as_bytes());
+    ///
+    ///     let mut cities = vec![];
+    ///     for result in rdr.records() {
+    ///         let record = result?;
+    ///         cities.push(record[0].to_string());
+    ///     }
+    ///     assert_eq!(cities, vec!["Concord", "Seattle"]);
+    ///
+    ///     let diagnostics = rdr.diagnostics();
+    ///     let kind = diagnostics[0].kind();
+    ///     assert_eq!(kind, &DiagnosticKind::UnterminatedQuote);
+    ///     assert_eq!(diagnostics[0].raw(), b"\"Boston,4628910\n");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn max_quoted_len(
+        &mut self,
+        limit: Option<usize>,
+    ) -> &mut ReaderBuilder {
+        self.max_quoted_len = limit;
+        self
+    }
+
     /// Whether fields are trimmed of leading and trailing whitespace or not.
     ///
     /// By default, no trimming is performed. This method permits one to
@@ -608,6 +900,57 @@ pub struct ReaderBuilder {
         self
     }
 
+    /// Only keep the given columns of each record.
+    ///
+    /// Columns may be selected by index or by name. Names are resolved
+    /// against the first row of the CSV data (trimmed, if headers are
+    /// trimmed), regardless of whether `has_headers` is enabled. If no column
+    /// in the first row has a selected name, then reading the first record
+    /// returns an error.
+    ///
+    /// The header row and every record contain only the selected columns, in
+    /// the order in which they appear in the CSV data. Selecting a column
+    /// more than once has no additional effect, and selecting an index past
+    /// the end of a record is not an error. Fields that aren't selected are
+    /// never copied into a record, so selecting a few columns of a wide file
+    /// can make reading faster.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::error::Error;
+    /// use csv::{ReaderBuilder, Selector};
+    ///
+    /// # fn main() { example().unwrap(); }
+    /// fn example() -> Result<(), Box<dyn Error>> {
+    ///     let data = "\
+    /// city,country,pop
+    /// Boston,United States,4628910
+    /// ";
+    ///     let mut rdr = ReaderBuilder::new()
+    ///         .select(vec![Selector::from("pop"), Selector::from(0)])
+    ///         .from_reader(data.as_bytes());
+    ///
+    ///     assert_eq!(rdr.headers()?, vec!["city", "pop"]);
+    ///     if let Some(result) = rdr.records().next() {
+    ///         let record = result?;
+    ///         assert_eq!(record, vec!["Boston", "4628910"]);
+    ///         Ok(())
+    ///     } else {
+    ///         Err(From::from("expected at least one record but got none"))
+    ///     }
+    /// }
+    /// ```
+    pub fn select<I, T>(&mut self, columns: I) -> &mut ReaderBuilder
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<Selector>,
+    {
+        let selectors = columns.into_iter().map(Into::into).collect();
+        self.projection = Some(Projection::new(selectors));
+        self
+    }
+
     /// Set the capacity (in bytes) of the buffer used in the CSV reader.
     /// This defaults to a reasonable setting.
     pub fn buffer_capacity(&mut self, capacity: usize) -> &mut ReaderBuilder {
@@ -615,6 +958,32 @@ pub struct ReaderBuilder {
         self
     }
 
+    /// Set the number of threads used to parse CSV data in parallel.
+    ///
+    /// This only applies to readers built with `from_path_parallel`. When
+    /// set to `0` (the default), `4` threads are used.
+    pub fn threads(&mut self, threads: usize) -> &mut ReaderBuilder {
+        self.threads = threads;
+        self
+    }
+
+    /// Set the approximate size (in bytes) of the chunks that a file is split
+    /// into when parsing CSV data in parallel.
+    ///
+    /// This only applies to readers built with `from_path_parallel`. Larger
+    /// chunks use more memory, since every record in a chunk is buffered
+    /// until it is returned, while smaller chunks increase the overhead of
+    /// coordinating threads. This defaults to a reasonable setting.
+    ///
+    /// # Panics
+    ///
+    /// This panics if `chunk_size` is `0`.
+    pub fn chunk_size(&mut self, chunk_size: u64) -> &mut ReaderBuilder {
+        assert!(chunk_size > 0, "chunk size must be non-zero");
+        self.chunk_size = chunk_size;
+        self
+    }
+
     /// Enable or disable the NFA for parsing CSV.
     ///
     /// This is intended to be a debug option. The NFA is always slower than
@@ -697,6 +1066,11 @@ pub struct ReaderBuilder {
 ///   for a number of additional errors to occur. For example, deserializing
 ///   a field `xyz` into an `i32` field will result in an error.
 ///
+/// Records of unequal length, invalid UTF-8 and quoted fields that are never
+/// closed can instead be skipped by enabling lenient parsing via the
+/// `lenient` method on [`ReaderBuilder`](struct.ReaderBuilder.html). Each
+/// skipped record is then reported by `Reader::diagnostics`.
+///
 /// For more details on the precise semantics of errors, see the
 /// [`Error`](enum.Error.html) type.
 #[derive(Debug)]
@@ -716,43 +1090,276 @@ pub struct Reader<R> {
 }
 
 #[derive(Debug)]
-struct ReaderState {
+pub(crate) struct ReaderState {
     /// When set, this contains the first row of any parsed CSV data.
     ///
     /// This is always populated, regardless of whether `has_headers` is set.
-    headers: Option<Headers>,
+    pub(crate) headers: Option<Headers>,
     /// When set, the first row of parsed CSV data is excluded from things
     /// that read records, like iterators and `read_record`.
-    has_headers: bool,
+    pub(crate) has_headers: bool,
+    /// Whether the headers were set by the caller, in which case they name
+    /// the columns of records even if `has_headers` is disabled.
+    pub(crate) headers_set: bool,
     /// When set, there is no restriction on the length of records. When not
     /// set, every record must have the same number of fields, or else an error
     /// is reported.
     flexible: bool,
-    trim: Trim,
+    pub(crate) trim: Trim,
+    /// The columns to keep from each record, if not all of them.
+    projection: Option<Projection>,
     /// The number of fields in the first record parsed.
     first_field_count: Option<u64>,
     /// The current position of the parser.
     ///
     /// Note that this position is only observable by callers at the start
     /// of a record. More granular positions are not supported.
-    cur_pos: Position,
+    pub(crate) cur_pos: Position,
     /// Whether the first record has been read or not.
-    first: bool,
+    pub(crate) first: bool,
     /// Whether the reader has been seeked or not.
     seeked: bool,
     /// Whether EOF of the underlying reader has been reached or not.
-    eof: bool,
+    pub(crate) eof: bool,
+    /// The state used to skip malformed records, if lenient parsing is
+    /// enabled.
+    recovery: Option<Recovery>,
+}
+
+/// Recovery tracks the malformed records skipped by a lenient reader.
+///
+/// The raw bytes of the record being read are left in the buffer of the
+/// underlying reader until the next record starts, so that they can be
+/// copied into a diagnostic if the record is skipped. They are only copied
+/// earlier when the buffer must be refilled in the middle of the record.
+#[derive(Debug)]
+struct Recovery {
+    /// The maximum number of diagnostics to record, if any.
+    max_errors: Option<usize>,
+    /// The number of bytes after which a record with an open quote is
+    /// considered unterminated, if any.
+    max_quoted_len: Option<usize>,
+    /// The record terminator of the parser.
+    term: CoreTerminator,
+    /// A description of each record skipped so far.
+    diagnostics: Vec<Diagnostic>,
+    /// The position of the record currently being read.
+    start: Position,
+    /// The raw bytes of the record currently being read that were read
+    /// before the data they came from was refilled.
+    spilled: Vec<u8>,
+    /// The number of bytes at the start of the underlying reader's buffer
+    /// that have been parsed but not yet consumed.
+    pending: usize,
+    /// Data that has already been read from the underlying reader, but must
+    /// be parsed again. This is used after an unterminated quote, since the
+    /// quoted field consumes all remaining data.
+    replay: Vec<u8>,
+    /// The offset in `replay` at which the current record starts.
+    replay_start: usize,
+    /// The number of bytes in `replay` that have been parsed.
+    replay_pos: usize,
+    /// Set when the end of the data is reached inside a quoted field.
+    unterminated: bool,
+    /// Set when the data is cut short because a record with an open quote
+    /// grew past `max_quoted_len`.
+    truncated: bool,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl Recovery {
+    fn new(
+        max_errors: Option<usize>,
+        max_quoted_len: Option<usize>,
+        term: CoreTerminator,
+    ) -> Recovery {
+        Recovery {
+            max_errors: max_errors,
+            max_quoted_len: max_quoted_len,
+            term: term,
+            diagnostics: vec![],
+            start: Position::new(),
+            spilled: vec![],
+            pending: 0,
+            replay: vec![],
+            replay_start: 0,
+            replay_pos: 0,
+            unterminated: false,
+            truncated: false,
+        }
+    }
+
+    /// Prepare to read a new record starting at the given position.
+    ///
+    /// The data of the previous record is consumed.
+    fn begin<R: io::Read>(
+        &mut self,
+        rdr: &mut io::BufReader<R>,
+        start: &Position,
+    ) {
+        self.discard(rdr);
+        self.start = start.clone();
+        self.unterminated = false;
+        self.truncated = false;
+    }
+
+    /// Forget any data that was going to be parsed again.
+    fn clear<R: io::Read>(&mut self, rdr: &mut io::BufReader<R>) {
+        self.discard(rdr);
+        self.replay.clear();
+        self.replay_start = 0;
+        self.replay_pos = 0;
+        self.unterminated = false;
+        self.truncated = false;
+    }
+
+    /// Returns true if no more malformed records may be skipped.
+    fn is_full(&self) -> bool {
+        self.max_errors.map_or(false, |max| self.diagnostics.len() >= max)
+    }
+
+    /// Return the number of bytes of the current record parsed so far.
+    fn record_len(&self) -> usize {
+        let len = if self.replay.is_empty() {
+            self.pending
+        } else {
+            self.replay_pos - self.replay_start
+        };
+        self.spilled.len() + len
+    }
+
+    /// Return the next chunk of data to parse, preferring data that must be
+    /// parsed again over the underlying reader.
+    ///
+    /// If the current record has an open quote and has grown past
+    /// `max_quoted_len`, then the data is cut short here.
+    fn fill_buf<'a, R: io::Read>(
+        &'a mut self,
+        rdr: &'a mut io::BufReader<R>,
+        core: &CoreReader,
+    ) -> io::Result<&'a [u8]> {
+        let too_long =
+            self.max_quoted_len.map_or(false, |max| self.record_len() >= max);
+        if too_long && core.is_in_quoted_field() && !self.is_full() {
+            self.unterminated = true;
+            self.truncated = true;
+            return Ok(&[]);
+        }
+        if !self.replay.is_empty() {
+            if self.replay_pos < self.replay.len() {
+                return Ok(&self.replay[self.replay_pos..]);
+            }
+            self.spill(rdr);
+        }
+        if self.pending == rdr.buffer().len() {
+            self.spill(rdr);
+        }
+        let input = &rdr.fill_buf()?[self.pending..];
+        if input.is_empty() && core.is_in_quoted_field() {
+            self.unterminated = true;
+        }
+        Ok(input)
+    }
+
+    /// Mark `amt` bytes returned by `fill_buf` as parsed.
+    fn consume(&mut self, amt: usize) {
+        if self.replay.is_empty() {
+            self.pending += amt;
+        } else {
+            self.replay_pos += amt;
+        }
+    }
+
+    /// Copy the bytes of the current record out of the data that is about
+    /// to be refilled, and then consume them.
+    ///
+    /// Once no more records may be skipped, nothing is copied.
+    fn spill<R: io::Read>(&mut self, rdr: &mut io::BufReader<R>) {
+        if !self.is_full() {
+            if self.replay.is_empty() {
+                self.spilled.extend_from_slice(&rdr.buffer()[..self.pending]);
+            } else {
+                self.spilled.extend_from_slice(
+                    &self.replay[self.replay_start..self.replay_pos],
+                );
+            }
+        }
+        if self.replay_pos == self.replay.len() {
+            self.replay.clear();
+            self.replay_pos = 0;
+        }
+        self.replay_start = self.replay_pos;
+        rdr.consume(self.pending);
+        self.pending = 0;
+    }
+
+    /// Consume the bytes of the current record without copying them.
+    fn discard<R: io::Read>(&mut self, rdr: &mut io::BufReader<R>) {
+        self.spilled.clear();
+        self.replay_start = self.replay_pos;
+        rdr.consume(self.pending);
+        self.pending = 0;
+    }
+
+    /// Return the raw bytes of the current record, and consume them.
+    fn take_raw<R: io::Read>(
+        &mut self,
+        rdr: &mut io::BufReader<R>,
+    ) -> Vec<u8> {
+        let mut raw = mem::replace(&mut self.spilled, vec![]);
+        if self.replay.is_empty() {
+            raw.extend_from_slice(&rdr.buffer()[..self.pending]);
+        } else {
+            raw.extend_from_slice(
+                &self.replay[self.replay_start..self.replay_pos],
+            );
+        }
+        self.discard(rdr);
+        raw
+    }
+
+    /// Record a diagnostic for the current record, whose raw bytes are
+    /// `raw`.
+    ///
+    /// Any record terminator left over from the previous record is stripped
+    /// from `raw`, and the position of the record is adjusted to match.
+    fn push(&mut self, kind: DiagnosticKind, raw: &[u8]) {
+        let skip = leftover_terminator_len(self.term, raw);
+        let lines = raw[..skip].iter().filter(|&&b| b == b'\n').count();
+        let mut pos = self.start.clone();
+        let (byte, line) = (pos.byte(), pos.line());
+        pos.set_byte(byte + skip as u64).set_line(line + lines as u64);
+        self.diagnostics.push(Diagnostic::new(
+            kind,
+            pos,
+            raw[skip..].to_vec(),
+        ));
+    }
 }
 
 /// Headers encapsulates any data associated with the headers of CSV data.
 ///
 /// The headers always correspond to the first row.
 #[derive(Debug)]
-struct Headers {
+pub(crate) struct Headers {
     /// The header, as raw bytes.
-    byte_record: ByteRecord,
+    pub(crate) byte_record: ByteRecord,
     /// The header, as valid UTF-8 (or a UTF-8 error).
     string_record: result::Result<StringRecord, Utf8Error>,
+    /// An index of the names in the header, shared with every record read.
+    index: Arc<HeaderIndex>,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl Headers {
+    /// Return the header as valid UTF-8, or an error if it isn't.
+    pub(crate) fn as_string_record(&self) -> Result<&StringRecord> {
+        match self.string_record {
+            Ok(ref record) => Ok(record),
+            Err(ref err) => Err(Error::new(ErrorKind::Utf8 {
+                pos: self.byte_record.position().map(Clone::clone),
+                err: err.clone(),
+            })),
+        }
+    }
 }
 
 #[cfg_attr(test, ::mutagen::mutate)] impl Reader<Reader<File>> {
@@ -786,20 +1393,22 @@ struct Headers {
     /// Create a new CSV reader given a builder and a source of underlying
     /// bytes.
     fn new(builder: &ReaderBuilder, rdr: R) -> Reader<R> {
+        let core = builder.builder.build();
         Reader {
-            core: Box::new(builder.builder.build()),
             rdr: io::BufReader::with_capacity(builder.capacity, rdr),
             state: ReaderState {
-                headers: None,
-                has_headers: builder.has_headers,
-                flexible: builder.flexible,
-                trim: builder.trim,
-                first_field_count: None,
-                cur_pos: Position::new(),
-                first: false,
-                seeked: false,
-                eof: false,
+                recovery: if builder.lenient {
+                    Some(Recovery::new(
+                        builder.max_errors,
+                        builder.max_quoted_len,
+                        core.terminator(),
+                    ))
+                } else {
+                    None
+                },
+                ..ReaderState::new(builder)
             },
+            core: Box::new(core),
         }
     }
 
@@ -904,6 +1513,16 @@ struct Headers {
     /// defined in the header row. (If there is no header row, then
     /// deserializing into a map will result in an error.)
     ///
+    /// When a struct is deserialized based on the header row, a field whose
+    /// value is itself a struct (or a map) is deserialized from the columns
+    /// whose headers start with the field's name followed by a `.`. For
+    /// example, a field named `address` with a `city` field of its own
+    /// corresponds to a column named `address.city`. These columns don't
+    /// need to be adjacent. If such a field has an `Option` type and all of
+    /// its columns are empty, then it is `None`. This also works with
+    /// `#[serde(flatten)]`, and is how `Writer::serialize` writes nested
+    /// structs.
+    ///
     /// Nested sequences are supported in a limited capacity. Namely, they
     /// are flattened. As a result, it's often useful to use a `Vec` to capture
     /// a "tail" of fields in a record:
@@ -1017,6 +1636,57 @@ struct Headers {
     ///     }
     /// }
     /// ```
+    ///
+    /// When there is a header row, internally and adjacently tagged enums
+    /// are supported too, including enums with struct variants. Like nested
+    /// structs, the content of an adjacently tagged enum is deserialized from
+    /// the columns prefixed with the name of its content field. Note that
+    /// internally tagged enums and flattened fields are buffered by Serde
+    /// before being deserialized, so the types of their fields are inferred
+    /// from the data. (For example, a field that looks like a number can't
+    /// be deserialized into a `String`.)
+    ///
+    /// ```
+    /// use std::error::Error;
+    ///
+    /// use csv::Reader;
+    /// use serde::Deserialize;
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// struct Row {
+    ///     name: String,
+    ///     shape: Shape,
+    /// }
+    ///
+    /// #[derive(Debug, Deserialize, PartialEq)]
+    /// #[serde(tag = "type", rename_all = "lowercase")]
+    /// enum Shape {
+    ///     Circle { radius: f64 },
+    ///     Rectangle { width: f64, height: f64 },
+    /// }
+    ///
+    /// # fn main() { example().unwrap(); }
+    /// fn example() -> Result<(), Box<dyn Error>> {
+    ///     let data = "\
+    /// name,shape.type,shape.radius,shape.width,shape.height
+    /// wheel,circle,0.5,,
+    /// door,rectangle,,1,2
+    /// ";
+    ///     let mut rdr = Reader::from_reader(data.as_bytes());
+    ///     let rows = rdr.deserialize().collect::<Result<Vec<Row>, _>>()?;
+    ///     assert_eq!(rows, vec![
+    ///         Row {
+    ///             name: "wheel".to_string(),
+    ///             shape: Shape::Circle { radius: 0.5 },
+    ///         },
+    ///         Row {
+    ///             name: "door".to_string(),
+    ///             shape: Shape::Rectangle { width: 1.0, height: 2.0 },
+    ///         },
+    ///     ]);
+    ///     Ok(())
+    /// }
+    /// ```
     pub fn deserialize<D>(&mut self) -> DeserializeRecordsIter<R, D>
     where
         D: DeserializeOwned,
@@ -1299,16 +1969,9 @@ struct Headers {
         if self.state.headers.is_none() {
             let mut record = ByteRecord::new();
             self.read_byte_record_impl(&mut record)?;
-            self.set_headers_impl(Err(record));
-        }
-        let headers = self.state.headers.as_ref().unwrap();
-        match headers.string_record {
-            Ok(ref record) => Ok(record),
-            Err(ref err) => Err(Error::new(ErrorKind::Utf8 {
-                pos: headers.byte_record.position().map(Clone::clone),
-                err: err.clone(),
-            })),
+            self.state.set_headers(Err(record));
         }
+        self.state.headers.as_ref().unwrap().as_string_record()
     }
 
     /// Returns a reference to the first row read by this parser as raw bytes.
@@ -1368,7 +2031,7 @@ struct Headers {
         if self.state.headers.is_none() {
             let mut record = ByteRecord::new();
             self.read_byte_record_impl(&mut record)?;
-            self.set_headers_impl(Err(record));
+            self.state.set_headers(Err(record));
         }
         Ok(&self.state.headers.as_ref().unwrap().byte_record)
     }
@@ -1401,7 +2064,7 @@ struct Headers {
     /// }
     /// ```
     pub fn set_headers(&mut self, headers: StringRecord) {
-        self.set_headers_impl(Ok(headers));
+        self.state.set_headers_by_caller(Ok(headers));
     }
 
     /// Set the headers of this CSV parser manually as raw bytes.
@@ -1432,37 +2095,7 @@ struct Headers {
     /// }
     /// ```
     pub fn set_byte_headers(&mut self, headers: ByteRecord) {
-        self.set_headers_impl(Err(headers));
-    }
-
-    fn set_headers_impl(
-        &mut self,
-        headers: result::Result<StringRecord, ByteRecord>,
-    ) {
-        // If we have string headers, then get byte headers. But if we have
-        // byte headers, then get the string headers (or a UTF-8 error).
-        let (mut str_headers, mut byte_headers) = match headers {
-            Ok(string) => {
-                let bytes = string.clone().into_byte_record();
-                (Ok(string), bytes)
-            }
-            Err(bytes) => {
-                match StringRecord::from_byte_record(bytes.clone()) {
-                    Ok(str_headers) => (Ok(str_headers), bytes),
-                    Err(err) => (Err(err.utf8_error().clone()), bytes),
-                }
-            }
-        };
-        if self.state.trim.should_trim_headers() {
-            if let Ok(ref mut str_headers) = str_headers.as_mut() {
-                str_headers.trim();
-            }
-            byte_headers.trim();
-        }
-        self.state.headers = Some(Headers {
-            byte_record: byte_headers,
-            string_record: str_headers,
-        });
+        self.state.set_headers_by_caller(Err(headers));
     }
 
     /// Read a single row into the given record. Returns false when no more
@@ -1503,7 +2136,12 @@ struct Headers {
     /// }
     /// ```
     pub fn read_record(&mut self, record: &mut StringRecord) -> Result<bool> {
-        let result = record.read(self);
+        let result = loop {
+            match record.read(self) {
+                Err(err) => self.state.recover(&mut self.rdr, err)?,
+                result => break result,
+            }
+        };
         // We need to trim again because trimming string records includes
         // Unicode whitespace. (ByteRecord trimming only includes ASCII
         // whitespace.)
@@ -1553,6 +2191,17 @@ struct Headers {
     pub fn read_byte_record(
         &mut self,
         record: &mut ByteRecord,
+    ) -> Result<bool> {
+        let result = self.read_byte_record_headers(record);
+        self.state.attach_headers(record);
+        result
+    }
+
+    /// Read a byte record, accounting for headers.
+    #[inline(always)]
+    fn read_byte_record_headers(
+        &mut self,
+        record: &mut ByteRecord,
     ) -> Result<bool> {
         if !self.state.seeked && !self.state.has_headers && !self.state.first {
             // If the caller indicated "no headers" and we haven't yielded the
@@ -1570,7 +2219,7 @@ struct Headers {
         let ok = self.read_byte_record_impl(record)?;
         self.state.first = true;
         if !self.state.seeked && self.state.headers.is_none() {
-            self.set_headers_impl(Err(record.clone()));
+            self.state.set_headers(Err(record.clone()));
             // If the end user indicated that we have headers, then we should
             // never return the first row. Instead, we should attempt to
             // read and return the next one.
@@ -1589,11 +2238,92 @@ struct Headers {
 
     /// Read a byte record from the underlying CSV reader, without accounting
     /// for headers.
+    ///
+    /// If lenient parsing is enabled, then malformed records are skipped.
     #[inline(always)]
     fn read_byte_record_impl(
         &mut self,
         record: &mut ByteRecord,
     ) -> Result<bool> {
+        if self.state.recovery.is_none() {
+            return self.parse_byte_record(record);
+        }
+        loop {
+            let field_count = self.state.first_field_count;
+            if let Some(ref mut recovery) = self.state.recovery {
+                recovery.begin(&mut self.rdr, &self.state.cur_pos);
+            }
+            let result = self.parse_byte_record(record);
+            let unterminated =
+                self.state.recovery.as_ref().map_or(false, |r| r.unterminated);
+            if unterminated {
+                if self.recover_unterminated(field_count)? {
+                    continue;
+                }
+                return result;
+            }
+            match result {
+                Err(err) => self.state.recover(&mut self.rdr, err)?,
+                result => return result,
+            }
+        }
+    }
+
+    /// Skip the record that was just read up to its first record terminator,
+    /// since it contains a quoted field that was never closed. Parsing
+    /// resumes after the terminator.
+    ///
+    /// `field_count` is the number of fields expected in each record before
+    /// the malformed record was read.
+    ///
+    /// If the line can't be skipped, then this returns false.
+    fn recover_unterminated(
+        &mut self,
+        field_count: Option<u64>,
+    ) -> Result<bool> {
+        let recovery = self.state.recovery.as_mut().unwrap();
+        recovery.unterminated = false;
+        let truncated = mem::replace(&mut recovery.truncated, false);
+        if recovery.is_full() {
+            return Ok(false);
+        }
+        // Everything after the first line is parsed again, including data
+        // that was going to be parsed again but hasn't been yet.
+        let mut data = recovery.take_raw(&mut self.rdr);
+        data.extend_from_slice(&recovery.replay[recovery.replay_pos..]);
+        let term = recovery.term;
+        let skip = leftover_terminator_len(term, &data);
+        let (end, rest) = match terminator_end(term, &data[skip..]) {
+            Some(i) => (skip + i, (0, 0)),
+            // If the data was cut short before the first terminator, then
+            // the rest of the record hasn't been read yet.
+            None if truncated => {
+                (data.len(), skip_record(term, &mut self.rdr)?)
+            }
+            None => (data.len(), (0, 0)),
+        };
+        recovery.push(DiagnosticKind::UnterminatedQuote, &data[..end]);
+        let lines = data[..end].iter().filter(|&&b| b == b'\n').count();
+        let mut pos = recovery.start.clone();
+        recovery.replay = data;
+        recovery.replay_start = end;
+        recovery.replay_pos = end;
+
+        let (byte, line, i) = (pos.byte(), pos.line(), pos.record());
+        pos.set_byte(byte + end as u64 + rest.0)
+            .set_line(line + lines as u64 + rest.1)
+            .set_record(i.checked_add(1).unwrap());
+        self.core.reset();
+        self.core.set_line(pos.line());
+        self.state.cur_pos = pos;
+        self.state.first_field_count = field_count;
+        self.state.eof = false;
+        Ok(true)
+    }
+
+    /// Parse a single byte record, without skipping malformed records.
+    #[inline(always)]
+    fn parse_byte_record(&mut self, record: &mut ByteRecord) -> Result<bool> {
         use csv_core::ReadRecordResult::*;
 
         record.clear();
@@ -1601,10 +2331,18 @@ struct Headers {
         if self.state.eof {
             return Ok(false);
         }
+        if self.state.can_project_fields() {
+            return self.read_projected_record(record);
+        }
         let (mut outlen, mut endlen) = (0, 0);
         loop {
             let (res, nin, nout, nend) = {
-                let input = self.rdr.fill_buf()?;
+                let input = match self.state.recovery {
+                    Some(ref mut r) => {
+                        r.fill_buf(&mut self.rdr, &self.core)?
+                    }
+                    None => self.rdr.fill_buf()?,
+                };
                 let (fields, ends) = record.as_parts();
                 self.core.read_record(
                     input,
@@ -1612,7 +2350,10 @@ struct Headers {
                     &mut ends[endlen..],
                 )
             };
-            self.rdr.consume(nin);
+            match self.state.recovery {
+                Some(ref mut r) => r.consume(nin),
+                None => self.rdr.consume(nin),
+            }
             let byte = self.state.cur_pos.byte();
             self.state
                 .cur_pos
@@ -1632,6 +2373,7 @@ struct Headers {
                 }
                 Record => {
                     record.set_len(endlen);
+                    self.state.project(record)?;
                     self.state.add_record(record)?;
                     return Ok(true);
                 }
@@ -1643,6 +2385,80 @@ struct Headers {
         }
     }
 
+    /// Like `read_byte_record_impl`, but only copies the fields selected by
+    /// this reader's projection into `record`.
+    ///
+    /// The projection must be resolved.
+    fn read_projected_record(
+        &mut self,
+        record: &mut ByteRecord,
+    ) -> Result<bool> {
+        use csv_core::ReadFieldResult::*;
+
+        // Fields that aren't selected are parsed into this buffer and then
+        // discarded, regardless of whether they fit.
+        let mut scratch = [0; 64];
+        let (mut field, mut outlen, mut endlen) = (0, 0, 0);
+        loop {
+            let selected =
+                self.state.projection.as_ref().unwrap().is_selected(field);
+            let (res, nin, nout) = {
+                let input = match self.state.recovery {
+                    Some(ref mut r) => {
+                        r.fill_buf(&mut self.rdr, &self.core)?
+                    }
+                    None => self.rdr.fill_buf()?,
+                };
+                if selected {
+                    let (fields, _) = record.as_parts();
+                    self.core.read_field(input, &mut fields[outlen..])
+                } else {
+                    self.core.read_field(input, &mut scratch)
+                }
+            };
+            match self.state.recovery {
+                Some(ref mut r) => r.consume(nin),
+                None => self.rdr.consume(nin),
+            }
+            let byte = self.state.cur_pos.byte();
+            self.state
+                .cur_pos
+                .set_byte(byte + nin as u64)
+                .set_line(self.core.line());
+            if selected {
+                outlen += nout;
+            }
+            match res {
+                InputEmpty => continue,
+                OutputFull => {
+                    if selected {
+                        record.expand_fields();
+                    }
+                    continue;
+                }
+                Field { record_end } => {
+                    if selected {
+                        if endlen >= record.as_parts().1.len() {
+                            record.expand_ends();
+                        }
+                        record.as_parts().1[endlen] = outlen;
+                        endlen += 1;
+                    }
+                    field += 1;
+                    if record_end {
+                        record.set_len(endlen);
+                        self.state.add_record(record)?;
+                        return Ok(true);
+                    }
+                }
+                End => {
+                    self.state.eof = true;
+                    return Ok(false);
+                }
+            }
+        }
+    }
+
     /// Return the current position of this CSV reader.
     ///
     /// The byte offset in the position returned can be used to `seek` this
@@ -1725,6 +2541,48 @@ struct Headers {
         self.state.has_headers
     }
 
+    /// Returns a description of each malformed record skipped so far, in
+    /// the order they were found.
+    ///
+    /// Records are only skipped when lenient parsing has been enabled via
+    /// `ReaderBuilder::lenient`. Otherwise, this is always empty.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::error::Error;
+    /// use csv::{DiagnosticKind, ReaderBuilder};
+    ///
+    /// # fn main() { example().unwrap(); }
+    /// fn example() -> Result<(), Box<dyn Error>> {
+    ///     let data = "\
+    /// city,country,pop
+    /// Boston,United States
+    /// Concord,United States,42695
+    /// ";
+    ///     let mut rdr = ReaderBuilder::new()
+    ///         .lenient(true)
+    ///         .from_reader(data.as_bytes());
+    ///     assert_eq!(rdr.records().count(), 1);
+    ///
+    ///     let diagnostic = &rdr.diagnostics()[0];
+    ///     assert_eq!(diagnostic.kind(), &DiagnosticKind::UnequalLengths {
+    ///         expected_len: 3,
+    ///         len: 2,
+    ///     });
+    ///     assert_eq!(diagnostic.position().byte(), 17);
+    ///     assert_eq!(diagnostic.position().line(), 2);
+    ///     assert_eq!(diagnostic.position().record(), 1);
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        match self.state.recovery {
+            Some(ref recovery) => &recovery.diagnostics,
+            None => &[],
+        }
+    }
+
     /// Returns a reference to the underlying reader.
     pub fn get_ref(&self) -> &R {
         self.rdr.get_ref()
@@ -1813,6 +2671,9 @@ struct Headers {
         if pos.byte() == self.state.cur_pos.byte() {
             return Ok(());
         }
+        if let Some(ref mut recovery) = self.state.recovery {
+            recovery.clear(&mut self.rdr);
+        }
         self.rdr.seek(io::SeekFrom::Start(pos.byte()))?;
         self.core.reset();
         self.core.set_line(pos.line());
@@ -1841,6 +2702,9 @@ struct Headers {
     ) -> Result<()> {
         self.byte_headers()?;
         self.state.seeked = true;
+        if let Some(ref mut recovery) = self.state.recovery {
+            recovery.clear(&mut self.rdr);
+        }
         self.rdr.seek(seek_from)?;
         self.core.reset();
         self.core.set_line(pos.line());
@@ -1851,8 +2715,137 @@ struct Headers {
 }
 
 #[cfg_attr(test, ::mutagen::mutate)] impl ReaderState {
+    /// Create the initial state of a reader from its configuration.
+    pub(crate) fn new(builder: &ReaderBuilder) -> ReaderState {
+        ReaderState {
+            headers: None,
+            has_headers: builder.has_headers,
+            headers_set: false,
+            flexible: builder.flexible,
+            trim: builder.trim,
+            projection: builder.projection.clone(),
+            first_field_count: None,
+            cur_pos: Position::new(),
+            first: false,
+            seeked: false,
+            eof: false,
+            recovery: None,
+        }
+    }
+
+    /// Skip the record that caused the given error, if lenient parsing is
+    /// enabled and the error can be recovered from.
+    ///
+    /// If the record can't be skipped, then the error is returned.
+    fn recover<R: io::Read>(
+        &mut self,
+        rdr: &mut io::BufReader<R>,
+        err: Error,
+    ) -> Result<()> {
+        let recovery = match self.recovery {
+            Some(ref mut recovery) if !recovery.is_full() => recovery,
+            _ => return Err(err),
+        };
+        let kind = match *err.kind() {
+            ErrorKind::UnequalLengths { expected_len, len, .. } => {
+                DiagnosticKind::UnequalLengths {
+                    expected_len: expected_len,
+                    len: len,
+                }
+            }
+            ErrorKind::Utf8 { ref err, .. } => {
+                DiagnosticKind::Utf8(err.clone())
+            }
+            _ => return Err(err),
+        };
+        let raw = recovery.take_raw(rdr);
+        recovery.push(kind, &raw);
+        Ok(())
+    }
+
+    /// Set the headers of this reader, trimming them if so configured.
+    pub(crate) fn set_headers(
+        &mut self,
+        headers: result::Result<StringRecord, ByteRecord>,
+    ) {
+        // If we have string headers, then get byte headers. But if we have
+        // byte headers, then get the string headers (or a UTF-8 error).
+        let (mut str_headers, mut byte_headers) = match headers {
+            Ok(string) => {
+                let bytes = string.clone().into_byte_record();
+                (Ok(string), bytes)
+            }
+            Err(bytes) => {
+                match StringRecord::from_byte_record(bytes.clone()) {
+                    Ok(str_headers) => (Ok(str_headers), bytes),
+                    Err(err) => (Err(err.utf8_error().clone()), bytes),
+                }
+            }
+        };
+        if self.trim.should_trim_headers() {
+            if let Ok(ref mut str_headers) = str_headers.as_mut() {
+                str_headers.trim();
+            }
+            byte_headers.trim();
+        }
+        let index = Arc::new(HeaderIndex::new(&byte_headers));
+        self.headers = Some(Headers {
+            byte_record: byte_headers,
+            string_record: str_headers,
+            index: index,
+        });
+    }
+
+    /// Associate the headers of this reader, if known, with a record that
+    /// was just read.
+    ///
+    /// When the first row isn't a header row and no headers were set by the
+    /// caller, the columns have no names, so any headers the record was
+    /// associated with before are removed.
+    #[inline(always)]
+    pub(crate) fn attach_headers(&self, record: &mut ByteRecord) {
+        match self.headers {
+            Some(ref headers) if self.has_headers || self.headers_set => {
+                record.set_header_index(&headers.index);
+            }
+            _ => record.clear_header_index(),
+        }
+    }
+
+    /// Set the headers of this reader on behalf of the caller.
+    pub(crate) fn set_headers_by_caller(
+        &mut self,
+        headers: result::Result<StringRecord, ByteRecord>,
+    ) {
+        self.set_headers(headers);
+        self.headers_set = true;
+    }
+
+    /// Returns true if and only if records can be projected while they are
+    /// parsed.
+    #[inline(always)]
+    fn can_project_fields(&self) -> bool {
+        self.projection.as_ref().map_or(false, |p| p.is_resolved())
+    }
+
+    /// Remove the columns that aren't selected from a record that was just
+    /// read.
+    ///
+    /// If names in the projection haven't been resolved yet, then the record
+    /// must be the first row of the CSV data, and is used to resolve them.
+    #[inline(always)]
+    pub(crate) fn project(&mut self, record: &mut ByteRecord) -> Result<()> {
+        if let Some(ref mut projection) = self.projection {
+            if !projection.is_resolved() {
+                projection.resolve(record, self.trim.should_trim_headers())?;
+            }
+            projection.apply(record);
+        }
+        Ok(())
+    }
+
     #[inline(always)]
-    fn add_record(&mut self, record: &ByteRecord) -> Result<()> {
+    pub(crate) fn add_record(&mut self, record: &ByteRecord) -> Result<()> {
         let i = self.cur_pos.record();
         self.cur_pos.set_record(i.checked_add(1).unwrap());
         if !self.flexible {
@@ -1873,6 +2866,70 @@ struct Headers {
     }
 }
 
+/// Returns the number of bytes at the start of `raw` that belong to the
+/// terminator of a previous record (or to empty lines).
+#[cfg_attr(test, ::mutagen::mutate)] fn leftover_terminator_len(
+    term: CoreTerminator,
+    raw: &[u8],
+) -> usize {
+    raw.iter().take_while(|&&b| is_terminator(term, b)).count()
+}
+
+/// Returns true if `b` is a record terminator.
+#[cfg_attr(test, ::mutagen::mutate)] pub(crate) fn is_terminator(
+    term: CoreTerminator,
+    b: u8,
+) -> bool {
+    match term {
+        CoreTerminator::CRLF => b == b'\r' || b == b'\n',
+        CoreTerminator::Any(t) => b == t,
+        _ => unreachable!(),
+    }
+}
+
+/// Returns the offset just past the first record terminator in `data`, if
+/// there is one. A `\r\n` is treated as a single terminator.
+#[cfg_attr(test, ::mutagen::mutate)] fn terminator_end(
+    term: CoreTerminator,
+    data: &[u8],
+) -> Option<usize> {
+    let i = data.iter().position(|&b| is_terminator(term, b))?;
+    let crlf = data[i] == b'\r' && data.get(i + 1) == Some(&b'\n');
+    if crlf && is_terminator(term, b'\n') {
+        Some(i + 2)
+    } else {
+        Some(i + 1)
+    }
+}
+
+/// Discard the data up to and including the next record terminator, and
+/// return the number of bytes and lines discarded.
+#[cfg_attr(test, ::mutagen::mutate)] fn skip_record<R: io::Read>(
+    term: CoreTerminator,
+    rdr: &mut io::BufReader<R>,
+) -> io::Result<(u64, u64)> {
+    let (mut len, mut lines) = (0, 0);
+    loop {
+        let (amt, found) = {
+            let buf = rdr.fill_buf()?;
+            let (amt, found) = match terminator_end(term, buf) {
+                Some(i) => (i, true),
+                None => (buf.len(), false),
+            };
+            lines += buf[..amt].iter().filter(|&&b| b == b'\n').count();
+            (amt, found)
+        };
+        if amt == 0 {
+            return Ok((len, lines as u64));
+        }
+        rdr.consume(amt);
+        len += amt as u64;
+        if found {
+            return Ok((len, lines as u64));
+        }
+    }
+}
+
 /// An owned iterator over deserialized records.
 ///
 /// The type parameter `R` refers to the underlying `io::Read` type, and `D`
@@ -2138,11 +3195,14 @@ pub struct ByteRecordsIter<'r, R: 'r> {
 mod tests {
     use std::io;
 
+    use serde::Deserialize;
+
     use crate::byte_record::ByteRecord;
-    use crate::error::ErrorKind;
+    use crate::error::{DiagnosticKind, ErrorKind};
+    use crate::projection::Selector;
     use crate::string_record::StringRecord;
 
-    use super::{Position, ReaderBuilder, Trim};
+    use super::{Position, ReaderBuilder, Terminator, Trim};
 
     fn b(s: &str) -> &[u8] {
         s.as_bytes()
@@ -2595,4 +3655,422 @@ mod tests {
         assert_eq!(rdr.headers().unwrap().len(), 0);
         assert_eq!(rdr.records().count(), 0);
     }
+
+    #[test]
+    fn get_by_name() {
+        let data = b("a, b ,a\n1,2,3\n4,5,6");
+        let mut rdr =
+            ReaderBuilder::new().trim(Trim::Headers).from_reader(data);
+        let records = rdr.records().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(records[0].get_by_name("a"), Some("1"));
+        assert_eq!(records[0].get_by_name("b"), Some("2"));
+        assert_eq!(records[1].get_by_name("a"), Some("4"));
+        assert_eq!(records[1].get_by_name(" b "), None);
+        assert_eq!(records[1].get_by_name("c"), None);
+
+        assert_eq!(StringRecord::from(vec!["x"]).get_by_name("a"), None);
+    }
+
+    #[test]
+    fn get_by_name_no_headers() {
+        let mut rdr = ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(b("a,b\n1,2\n"));
+        let records = rdr.records().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(records[0].get_by_name("a"), None);
+        assert_eq!(records[1].get_by_name("a"), None);
+
+        // A reused record forgets the headers of the reader that read it
+        // before.
+        let mut rec = ByteRecord::new();
+        let mut rdr = ReaderBuilder::new().from_reader(b("a,b\n1,2\n"));
+        assert!(rdr.read_byte_record(&mut rec).unwrap());
+        assert_eq!(rec.get_by_name(b"a"), Some(b("1")));
+        let mut rdr = ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(b("a,b\n1,2\n"));
+        assert!(rdr.read_byte_record(&mut rec).unwrap());
+        assert_eq!(rec.get_by_name(b"a"), None);
+    }
+
+    #[test]
+    fn get_by_name_set_headers() {
+        let mut rdr =
+            ReaderBuilder::new().has_headers(false).from_reader(b("1,2\n3,4"));
+        rdr.set_headers(StringRecord::from(vec!["x", "y"]));
+        let mut rdr = rdr.into_records();
+        assert_eq!(rdr.next().unwrap().unwrap().get_by_name("y"), Some("y"));
+        assert_eq!(rdr.next().unwrap().unwrap().get_by_name("y"), Some("2"));
+        assert_eq!(rdr.next().unwrap().unwrap().get_by_name("y"), Some("4"));
+    }
+
+    #[test]
+    fn select_by_index() {
+        let data = b("a,b,c,d\n1,2,3,4\n5,6,7,8");
+        let mut rdr =
+            ReaderBuilder::new().select(vec![3, 1, 3, 10]).from_reader(data);
+        assert_eq!(rdr.headers().unwrap(), vec!["b", "d"]);
+        let records = rdr.records().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(records, vec![vec!["2", "4"], vec!["6", "8"]]);
+        assert_eq!(records[1].get_by_name("d"), Some("8"));
+    }
+
+    #[test]
+    fn select_by_name() {
+        let data = b("a, b ,c\n1,2,3\n4,5,6");
+        let mut rdr = ReaderBuilder::new()
+            .trim(Trim::Headers)
+            .select(vec!["c", "b"])
+            .from_reader(data);
+        let records = rdr.records().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(records, vec![vec!["2", "3"], vec!["5", "6"]]);
+        assert_eq!(rdr.headers().unwrap(), vec!["b", "c"]);
+
+        let mut rdr = ReaderBuilder::new()
+            .has_headers(false)
+            .select(vec![Selector::from("a"), Selector::from(2)])
+            .from_reader(data);
+        let records = rdr.records().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(
+            records,
+            vec![vec!["a", "c"], vec!["1", "3"], vec!["4", "6"]]
+        );
+    }
+
+    #[test]
+    fn select_unknown_name() {
+        let data = b("a,b\n1,2");
+        let mut rdr =
+            ReaderBuilder::new().select(vec!["a", "z"]).from_reader(data);
+        match *rdr.headers().unwrap_err().kind() {
+            ErrorKind::UnknownColumn(ref name) => assert_eq!(name, "z"),
+            ref x => panic!("expected UnknownColumn error, but got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn select_agrees_with_full_read() {
+        let long = "x".repeat(200);
+        let data = format!(
+            "h1,h2,h3\n\"a\nb\",{},c\r\n\n\"{}\",\"e\"\"f\",\n# no\n,,\nlast,{}",
+            long, long, long
+        );
+        for &nfa in &[false, true] {
+            for selected in &[vec![0], vec![1], vec![2], vec![0, 2], vec![5]] {
+                let mut full = ReaderBuilder::new()
+                    .flexible(true)
+                    .comment(Some(b'#'))
+                    .buffer_capacity(16)
+                    .from_reader(data.as_bytes());
+                let mut projected = ReaderBuilder::new()
+                    .nfa(nfa)
+                    .flexible(true)
+                    .comment(Some(b'#'))
+                    .buffer_capacity(16)
+                    .select(selected.clone())
+                    .from_reader(data.as_bytes());
+                let (mut a, mut b) = (ByteRecord::new(), ByteRecord::new());
+                loop {
+                    let more = full.read_byte_record(&mut a).unwrap();
+                    assert_eq!(
+                        more,
+                        projected.read_byte_record(&mut b).unwrap()
+                    );
+                    let expected: Vec<&[u8]> = a
+                        .iter()
+                        .enumerate()
+                        .filter(|&(i, _)| selected.contains(&i))
+                        .map(|(_, f)| f)
+                        .collect();
+                    assert_eq!(b, expected);
+                    // The NFA doesn't count lines.
+                    if !nfa {
+                        assert_eq!(a.position(), b.position());
+                    }
+                    if !more {
+                        break;
+                    }
+                }
+                assert_eq!(
+                    full.position().byte(),
+                    projected.position().byte()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn select_unequal_lengths() {
+        let data = b("a,b,c\n1,2,3\n4,5\n6");
+        let mut rdr = ReaderBuilder::new().select(vec![1]).from_reader(data);
+        let mut rec = StringRecord::new();
+        assert!(rdr.read_record(&mut rec).unwrap());
+        assert!(rdr.read_record(&mut rec).unwrap());
+        assert_eq!(rec, vec!["5"]);
+        match *rdr.read_record(&mut rec).unwrap_err().kind() {
+            ErrorKind::UnequalLengths { expected_len: 1, len: 0, .. } => {}
+            ref x => panic!("expected UnequalLengths error, but got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn select_deserialize() {
+        #[derive(Debug, Deserialize, PartialEq)]
+        struct Row {
+            c: i32,
+            a: String,
+        }
+
+        let data = b("a,b,c\nx,unused,1\ny,unused,2");
+        let mut rdr =
+            ReaderBuilder::new().select(vec!["a", "c"]).from_reader(data);
+        let rows = rdr.deserialize().collect::<Result<Vec<Row>, _>>().unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                Row { c: 1, a: "x".to_string() },
+                Row { c: 2, a: "y".to_string() },
+            ]
+        );
+    }
+
+    #[test]
+    fn lenient_unequal_lengths() {
+        let data = b("a,b\n1,2\n3\n4,5\n");
+        let mut rdr = ReaderBuilder::new().lenient(true).from_reader(data);
+        let mut rec = ByteRecord::new();
+
+        assert!(rdr.read_byte_record(&mut rec).unwrap());
+        assert_eq!(rec, vec!["1", "2"]);
+        assert!(rdr.read_byte_record(&mut rec).unwrap());
+        assert_eq!(rec, vec!["4", "5"]);
+        assert_eq!(rec.position(), Some(&newpos(10, 4, 3)));
+        assert!(!rdr.read_byte_record(&mut rec).unwrap());
+
+        let diags = rdr.diagnostics();
+        assert_eq!(diags.len(), 1);
+        assert_eq!(
+            diags[0].kind(),
+            &DiagnosticKind::UnequalLengths { expected_len: 2, len: 1 }
+        );
+        assert_eq!(diags[0].position(), &newpos(8, 3, 2));
+        assert_eq!(diags[0].raw(), b"3\n");
+    }
+
+    #[test]
+    fn lenient_unequal_lengths_crlf() {
+        let data = b("a,b\r\n1\r\n2,3\r\n");
+        let mut rdr = ReaderBuilder::new().lenient(true).from_reader(data);
+        let rows = rdr.byte_records().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(rows, vec![vec!["2", "3"]]);
+
+        let diags = rdr.diagnostics();
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].position(), &newpos(5, 2, 1));
+        assert_eq!(diags[0].raw(), b"1\r");
+    }
+
+    #[test]
+    fn lenient_invalid_utf8() {
+        let data = &b"a,b\n1,\xFF\n2,3\n"[..];
+        let mut rdr = ReaderBuilder::new().lenient(true).from_reader(data);
+        let rows = rdr.records().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(rows, vec![vec!["2", "3"]]);
+
+        let diags = rdr.diagnostics();
+        assert_eq!(diags.len(), 1);
+        match *diags[0].kind() {
+            DiagnosticKind::Utf8(ref err) => {
+                assert_eq!(err.field(), 1);
+                assert_eq!(err.valid_up_to(), 0);
+            }
+            ref wrong => panic!("match failed, got {:?}", wrong),
+        }
+        assert_eq!(diags[0].position(), &newpos(4, 2, 1));
+        assert_eq!(diags[0].raw(), b"1,\xFF\n");
+    }
+
+    #[test]
+    fn lenient_unterminated_quote() {
+        let data = b("a,b\n1,\"2\n3,4\n5,6");
+        let mut rdr = ReaderBuilder::new().lenient(true).from_reader(data);
+        let mut rec = ByteRecord::new();
+
+        assert!(rdr.read_byte_record(&mut rec).unwrap());
+        assert_eq!(rec, vec!["3", "4"]);
+        assert_eq!(rec.position(), Some(&newpos(9, 3, 2)));
+        assert!(rdr.read_byte_record(&mut rec).unwrap());
+        assert_eq!(rec, vec!["5", "6"]);
+        assert_eq!(rec.position(), Some(&newpos(13, 4, 3)));
+        assert!(!rdr.read_byte_record(&mut rec).unwrap());
+
+        let diags = rdr.diagnostics();
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].kind(), &DiagnosticKind::UnterminatedQuote);
+        assert_eq!(diags[0].position(), &newpos(4, 2, 1));
+        assert_eq!(diags[0].raw(), b"1,\"2\n");
+    }
+
+    #[test]
+    fn lenient_unterminated_quote_last_line() {
+        let data = b("a,b\n1,2\n\"3,4");
+        let mut rdr = ReaderBuilder::new().lenient(true).from_reader(data);
+        let rows = rdr.byte_records().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(rows, vec![vec!["1", "2"]]);
+        assert!(rdr.is_done());
+
+        let diags = rdr.diagnostics();
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].position(), &newpos(8, 3, 2));
+        assert_eq!(diags[0].raw(), b"\"3,4");
+    }
+
+    #[test]
+    fn lenient_unterminated_quote_projected() {
+        let data = b("a,b\n1,\"2\n3,4\n");
+        let mut rdr = ReaderBuilder::new()
+            .lenient(true)
+            .select(vec!["b"])
+            .from_reader(data);
+        let rows = rdr.records().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(rows, vec![vec!["4"]]);
+        assert_eq!(rdr.diagnostics().len(), 1);
+        assert_eq!(rdr.diagnostics()[0].raw(), b"1,\"2\n");
+    }
+
+    #[test]
+    fn lenient_unterminated_quote_terminator() {
+        let data = b("a,b;1,\"2;3,4;5,6");
+        let mut rdr = ReaderBuilder::new()
+            .lenient(true)
+            .terminator(Terminator::Any(b';'))
+            .from_reader(data);
+        let rows = rdr.byte_records().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(rows, vec![vec!["3", "4"], vec!["5", "6"]]);
+
+        let diags = rdr.diagnostics();
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].position().byte(), 4);
+        assert_eq!(diags[0].raw(), b"1,\"2;");
+    }
+
+    #[test]
+    fn lenient_small_buffer() {
+        // Malformed records that don't fit in the buffer are reported in
+        // full.
+        let data = &b"a,b\n1,2\n3,33,333\n4,\xFF\xFF\xFF\n5,\"6\n7,8\n"[..];
+        let mut rdr = ReaderBuilder::new()
+            .lenient(true)
+            .buffer_capacity(2)
+            .from_reader(data);
+        let rows = rdr.records().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(rows, vec![vec!["1", "2"], vec!["7", "8"]]);
+
+        let diags = rdr.diagnostics();
+        assert_eq!(diags.len(), 3);
+        assert_eq!(diags[0].position(), &newpos(8, 3, 2));
+        assert_eq!(diags[0].raw(), b"3,33,333\n");
+        assert_eq!(diags[1].position(), &newpos(17, 4, 3));
+        assert_eq!(diags[1].raw(), b"4,\xFF\xFF\xFF\n");
+        assert_eq!(diags[2].position(), &newpos(23, 5, 4));
+        assert_eq!(diags[2].raw(), b"5,\"6\n");
+    }
+
+    #[test]
+    fn lenient_max_errors() {
+        let data = b("a,b\n1\n2\n\"3\n4,5\n");
+        let mut rdr = ReaderBuilder::new()
+            .lenient(true)
+            .max_errors(Some(2))
+            .from_reader(data);
+        let mut rec = ByteRecord::new();
+
+        // The unterminated quote can no longer be skipped, so it swallows
+        // the rest of the data.
+        match rdr.read_byte_record(&mut rec) {
+            Err(err) => match *err.kind() {
+                ErrorKind::UnequalLengths {
+                    expected_len: 2, len: 1, ..
+                } => {}
+                ref wrong => panic!("match failed, got {:?}", wrong),
+            },
+            wrong => panic!("match failed, got {:?}", wrong),
+        }
+        assert_eq!(rdr.diagnostics().len(), 2);
+        assert!(!rdr.read_byte_record(&mut rec).unwrap());
+    }
+
+    #[test]
+    fn lenient_max_quoted_len() {
+        let data = b("a,b\n1,\"2\n3,4\n5,6\n\"7\n");
+        let mut rdr = ReaderBuilder::new()
+            .lenient(true)
+            .max_quoted_len(Some(6))
+            .buffer_capacity(1)
+            .from_reader(data);
+        let mut rec = ByteRecord::new();
+
+        assert!(rdr.read_byte_record(&mut rec).unwrap());
+        assert_eq!(rec, vec!["3", "4"]);
+        assert_eq!(rec.position(), Some(&newpos(9, 3, 2)));
+        assert!(rdr.read_byte_record(&mut rec).unwrap());
+        assert_eq!(rec, vec!["5", "6"]);
+        assert_eq!(rec.position(), Some(&newpos(13, 4, 3)));
+        assert!(!rdr.read_byte_record(&mut rec).unwrap());
+
+        let diags = rdr.diagnostics();
+        assert_eq!(diags.len(), 2);
+        assert_eq!(diags[0].position(), &newpos(4, 2, 1));
+        assert_eq!(diags[0].raw(), b"1,\"2\n");
+        assert_eq!(diags[1].position(), &newpos(17, 5, 4));
+        assert_eq!(diags[1].raw(), b"\"7\n");
+    }
+
+    #[test]
+    fn lenient_max_quoted_len_long_line() {
+        let data = b("a,b\n\"1,2345678,9\n3,4\n");
+        let mut rdr = ReaderBuilder::new()
+            .lenient(true)
+            .max_quoted_len(Some(4))
+            .buffer_capacity(1)
+            .from_reader(data);
+        let mut rec = ByteRecord::new();
+
+        assert!(rdr.read_byte_record(&mut rec).unwrap());
+        assert_eq!(rec, vec!["3", "4"]);
+        assert_eq!(rec.position(), Some(&newpos(17, 3, 2)));
+        assert!(!rdr.read_byte_record(&mut rec).unwrap());
+
+        let diags = rdr.diagnostics();
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].position(), &newpos(4, 2, 1));
+        assert_eq!(diags[0].raw(), b"\"1,2");
+    }
+
+    #[test]
+    fn lenient_disabled() {
+        let data = b("a,b\n1\n2,3\n");
+        let mut rdr = ReaderBuilder::new().from_reader(data);
+        let mut rec = ByteRecord::new();
+
+        assert!(rdr.read_byte_record(&mut rec).is_err());
+        assert!(rdr.diagnostics().is_empty());
+        assert!(rdr.read_byte_record(&mut rec).unwrap());
+        assert_eq!(rec, vec!["2", "3"]);
+    }
+
+    #[test]
+    fn lenient_seek() {
+        let data = b("a,b\n1,\"2\n3,4\n");
+        let mut rdr = ReaderBuilder::new()
+            .lenient(true)
+            .from_reader(io::Cursor::new(data));
+        let rows = rdr.byte_records().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(rows, vec![vec!["3", "4"]]);
+
+        rdr.seek(newpos(9, 3, 2)).unwrap();
+        let rows = rdr.byte_records().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(rows, vec![vec!["3", "4"]]);
+        assert_eq!(rdr.diagnostics().len(), 1);
+    }
 }
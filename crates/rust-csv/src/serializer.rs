@@ -5,29 +5,136 @@ use std::mem;
 use itoa;
 use ryu;
 use serde::ser::{
-    Error as SerdeError, Serialize, SerializeMap, SerializeSeq,
+    Error as SerdeError, Impossible, Serialize, SerializeMap, SerializeSeq,
     SerializeStruct, SerializeStructVariant, SerializeTuple,
     SerializeTupleStruct, SerializeTupleVariant, Serializer,
 };
 use serde::serde_if_integer128;
 
+use crate::byte_record::ByteRecord;
 use crate::error::{Error, ErrorKind};
 use crate::writer::Writer;
+use crate::SequenceStyle;
+
+/// A destination for the fields written by the serializers in this module.
+///
+/// Fields are usually written straight to a `Writer`, but can also be
+/// collected in a `ByteRecord`, e.g., to line them up with a header first.
+pub trait Sink {
+    /// Write a single field.
+    fn write_field<T: AsRef<[u8]>>(&mut self, field: T) -> Result<(), Error>;
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<W: io::Write> Sink for Writer<W> {
+    fn write_field<T: AsRef<[u8]>>(&mut self, field: T) -> Result<(), Error> {
+        Writer::write_field(self, field)
+    }
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl Sink for ByteRecord {
+    fn write_field<T: AsRef<[u8]>>(&mut self, field: T) -> Result<(), Error> {
+        self.push_field(field.as_ref());
+        Ok(())
+    }
+}
 
 /// Serialize the given value to the given writer, and return an error if
 /// anything went wrong.
-pub fn serialize<S: Serialize, W: io::Write>(
-    wtr: &mut Writer<W>,
+pub fn serialize<S: Serialize, W: Sink>(
+    wtr: &mut W,
+    style: SequenceStyle,
     value: S,
 ) -> Result<(), Error> {
-    value.serialize(&mut SeRecord { wtr: wtr })
+    value.serialize(&mut SeRecord {
+        wtr: wtr,
+        style: style,
+        depth: 0,
+        joined: None,
+    })
+}
+
+struct SeRecord<'w, W: 'w + Sink> {
+    wtr: &'w mut W,
+    style: SequenceStyle,
+    /// The number of struct fields and map entries whose values are being
+    /// serialized.
+    depth: usize,
+    /// The sequence being joined into a single field, if any.
+    joined: Option<Joined>,
 }
 
-struct SeRecord<'w, W: 'w + io::Write> {
-    wtr: &'w mut Writer<W>,
+/// The elements of a sequence that are joined into a single field.
+struct Joined {
+    /// The byte written between elements.
+    sep: u8,
+    /// The field written so far.
+    field: Vec<u8>,
+    /// The number of elements written so far.
+    len: usize,
 }
 
-#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: io::Write> Serializer for &'a mut SeRecord<'w, W> {
+#[cfg_attr(test, ::mutagen::mutate)] impl<'w, W: Sink> SeRecord<'w, W> {
+    fn write_field<T: AsRef<[u8]>>(&mut self, field: T) -> Result<(), Error> {
+        match self.joined {
+            None => self.wtr.write_field(field),
+            Some(ref mut joined) => {
+                if joined.len > 0 {
+                    joined.field.push(joined.sep);
+                }
+                joined.field.extend_from_slice(field.as_ref());
+                joined.len += 1;
+                Ok(())
+            }
+        }
+    }
+
+    fn handle_sequence<T: fmt::Display>(
+        &mut self,
+        name: T,
+    ) -> Result<&mut Self, Error> {
+        if self.joined.is_some() {
+            return Err(error_container_inside_join(name));
+        }
+        if let SequenceStyle::Join(sep) = self.style {
+            // Sequences that make up the record itself are never joined.
+            if self.depth > 0 {
+                self.joined = Some(Joined { sep: sep, field: vec![], len: 0 });
+            }
+        }
+        Ok(self)
+    }
+
+    fn end_sequence(&mut self) -> Result<(), Error> {
+        // Joined sequences can't contain other sequences, so if a sequence is
+        // being joined, it's the one that is ending.
+        match self.joined.take() {
+            None => Ok(()),
+            Some(joined) => self.wtr.write_field(joined.field),
+        }
+    }
+
+    fn handle_struct<T: fmt::Display>(
+        &mut self,
+        name: T,
+    ) -> Result<&mut Self, Error> {
+        if self.joined.is_some() {
+            return Err(error_container_inside_join(name));
+        }
+        Ok(self)
+    }
+
+    fn handle_value<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.depth += 1;
+        value.serialize(&mut *self)?;
+        self.depth -= 1;
+        Ok(())
+    }
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: Sink> Serializer for &'a mut SeRecord<'w, W> {
     type Ok = ();
     type Error = Error;
     type SerializeSeq = Self;
@@ -40,30 +147,30 @@ struct SeRecord<'w, W: 'w + io::Write> {
 
     fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
         if v {
-            self.wtr.write_field("true")
+            self.write_field("true")
         } else {
-            self.wtr.write_field("false")
+            self.write_field("false")
         }
     }
 
     fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
         let mut buffer = itoa::Buffer::new();
-        self.wtr.write_field(buffer.format(v))
+        self.write_field(buffer.format(v))
     }
 
     fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
         let mut buffer = itoa::Buffer::new();
-        self.wtr.write_field(buffer.format(v))
+        self.write_field(buffer.format(v))
     }
 
     fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
         let mut buffer = itoa::Buffer::new();
-        self.wtr.write_field(buffer.format(v))
+        self.write_field(buffer.format(v))
     }
 
     fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
         let mut buffer = itoa::Buffer::new();
-        self.wtr.write_field(buffer.format(v))
+        self.write_field(buffer.format(v))
     }
 
     serde_if_integer128! {
@@ -74,22 +181,22 @@ struct SeRecord<'w, W: 'w + io::Write> {
 
     fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
         let mut buffer = itoa::Buffer::new();
-        self.wtr.write_field(buffer.format(v))
+        self.write_field(buffer.format(v))
     }
 
     fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
         let mut buffer = itoa::Buffer::new();
-        self.wtr.write_field(buffer.format(v))
+        self.write_field(buffer.format(v))
     }
 
     fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
         let mut buffer = itoa::Buffer::new();
-        self.wtr.write_field(buffer.format(v))
+        self.write_field(buffer.format(v))
     }
 
     fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
         let mut buffer = itoa::Buffer::new();
-        self.wtr.write_field(buffer.format(v))
+        self.write_field(buffer.format(v))
     }
 
     serde_if_integer128! {
@@ -100,28 +207,28 @@ struct SeRecord<'w, W: 'w + io::Write> {
 
     fn serialize_f32(self, v: f32) -> Result<Self::Ok, Self::Error> {
         let mut buffer = ryu::Buffer::new();
-        self.wtr.write_field(buffer.format(v))
+        self.write_field(buffer.format(v))
     }
 
     fn serialize_f64(self, v: f64) -> Result<Self::Ok, Self::Error> {
         let mut buffer = ryu::Buffer::new();
-        self.wtr.write_field(buffer.format(v))
+        self.write_field(buffer.format(v))
     }
 
     fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
-        self.wtr.write_field(v.encode_utf8(&mut [0; 4]))
+        self.write_field(v.encode_utf8(&mut [0; 4]))
     }
 
     fn serialize_str(self, value: &str) -> Result<Self::Ok, Self::Error> {
-        self.wtr.write_field(value)
+        self.write_field(value)
     }
 
     fn serialize_bytes(self, value: &[u8]) -> Result<Self::Ok, Self::Error> {
-        self.wtr.write_field(value)
+        self.write_field(value)
     }
 
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
-        self.wtr.write_field(&[])
+        self.write_field(&[])
     }
 
     fn serialize_some<T: ?Sized + Serialize>(
@@ -139,7 +246,7 @@ struct SeRecord<'w, W: 'w + io::Write> {
         self,
         name: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
-        self.wtr.write_field(name)
+        self.write_field(name)
     }
 
     fn serialize_unit_variant(
@@ -148,7 +255,7 @@ struct SeRecord<'w, W: 'w + io::Write> {
         _variant_index: u32,
         variant: &'static str,
     ) -> Result<Self::Ok, Self::Error> {
-        self.wtr.write_field(variant)
+        self.write_field(variant)
     }
 
     fn serialize_newtype_struct<T: ?Sized + Serialize>(
@@ -173,22 +280,22 @@ struct SeRecord<'w, W: 'w + io::Write> {
         self,
         _len: Option<usize>,
     ) -> Result<Self::SerializeSeq, Self::Error> {
-        Ok(self)
+        self.handle_sequence("sequence")
     }
 
     fn serialize_tuple(
         self,
         _len: usize,
     ) -> Result<Self::SerializeTuple, Self::Error> {
-        Ok(self)
+        self.handle_sequence("tuple")
     }
 
     fn serialize_tuple_struct(
         self,
-        _name: &'static str,
+        name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleStruct, Self::Error> {
-        Ok(self)
+        self.handle_sequence(name)
     }
 
     fn serialize_tuple_variant(
@@ -205,20 +312,17 @@ struct SeRecord<'w, W: 'w + io::Write> {
         self,
         _len: Option<usize>,
     ) -> Result<Self::SerializeMap, Self::Error> {
-        // The right behavior for serializing maps isn't clear.
-        Err(Error::custom(
-            "serializing maps is not supported, \
-             if you have a use case, please file an issue at \
-             https://github.com/BurntSushi/rust-csv",
-        ))
+        // This is reached for maps, and for structs with flattened fields.
+        // Like a struct, only the values are written.
+        self.handle_struct("map")
     }
 
     fn serialize_struct(
         self,
-        _name: &'static str,
+        name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
-        Ok(self)
+        self.handle_struct(name)
     }
 
     fn serialize_struct_variant(
@@ -232,7 +336,7 @@ struct SeRecord<'w, W: 'w + io::Write> {
     }
 }
 
-#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: io::Write> SerializeSeq for &'a mut SeRecord<'w, W> {
+#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: Sink> SerializeSeq for &'a mut SeRecord<'w, W> {
     type Ok = ();
     type Error = Error;
 
@@ -244,11 +348,11 @@ struct SeRecord<'w, W: 'w + io::Write> {
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(())
+        self.end_sequence()
     }
 }
 
-#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: io::Write> SerializeTuple for &'a mut SeRecord<'w, W> {
+#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: Sink> SerializeTuple for &'a mut SeRecord<'w, W> {
     type Ok = ();
     type Error = Error;
 
@@ -260,11 +364,11 @@ struct SeRecord<'w, W: 'w + io::Write> {
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(())
+        self.end_sequence()
     }
 }
 
-#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: io::Write> SerializeTupleStruct for &'a mut SeRecord<'w, W> {
+#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: Sink> SerializeTupleStruct for &'a mut SeRecord<'w, W> {
     type Ok = ();
     type Error = Error;
 
@@ -276,11 +380,11 @@ struct SeRecord<'w, W: 'w + io::Write> {
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(())
+        self.end_sequence()
     }
 }
 
-#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: io::Write> SerializeTupleVariant for &'a mut SeRecord<'w, W> {
+#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: Sink> SerializeTupleVariant for &'a mut SeRecord<'w, W> {
     type Ok = ();
     type Error = Error;
 
@@ -296,7 +400,7 @@ struct SeRecord<'w, W: 'w + io::Write> {
     }
 }
 
-#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: io::Write> SerializeMap for &'a mut SeRecord<'w, W> {
+#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: Sink> SerializeMap for &'a mut SeRecord<'w, W> {
     type Ok = ();
     type Error = Error;
 
@@ -304,22 +408,22 @@ struct SeRecord<'w, W: 'w + io::Write> {
         &mut self,
         _key: &T,
     ) -> Result<(), Self::Error> {
-        unreachable!()
+        Ok(())
     }
 
     fn serialize_value<T: ?Sized + Serialize>(
         &mut self,
-        _value: &T,
+        value: &T,
     ) -> Result<(), Self::Error> {
-        unreachable!()
+        self.handle_value(value)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        unreachable!()
+        Ok(())
     }
 }
 
-#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: io::Write> SerializeStruct for &'a mut SeRecord<'w, W> {
+#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: Sink> SerializeStruct for &'a mut SeRecord<'w, W> {
     type Ok = ();
     type Error = Error;
 
@@ -328,7 +432,7 @@ struct SeRecord<'w, W: 'w + io::Write> {
         _key: &'static str,
         value: &T,
     ) -> Result<(), Self::Error> {
-        value.serialize(&mut **self)
+        self.handle_value(value)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
@@ -336,7 +440,7 @@ struct SeRecord<'w, W: 'w + io::Write> {
     }
 }
 
-#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: io::Write> SerializeStructVariant for &'a mut SeRecord<'w, W> {
+#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: Sink> SerializeStructVariant for &'a mut SeRecord<'w, W> {
     type Ok = ();
     type Error = Error;
 
@@ -367,10 +471,17 @@ struct SeRecord<'w, W: 'w + io::Write> {
     ))
 }
 
-#[cfg_attr(test, ::mutagen::mutate)] fn error_container_inside_struct<T: fmt::Display>(name: T) -> Error {
+#[cfg_attr(test, ::mutagen::mutate)] fn error_container_inside_join<T: fmt::Display>(name: T) -> Error {
     Error::custom(format!(
-        "cannot serialize {} container inside struct \
-         when writing headers from structs",
+        "cannot serialize {} container inside a sequence \
+         that is joined into a single field",
+        name
+    ))
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] fn error_map_key<T: fmt::Display>(name: T) -> Error {
+    Error::custom(format!(
+        "cannot serialize {} as a map key when writing headers",
         name
     ))
 }
@@ -383,11 +494,12 @@ struct SeRecord<'w, W: 'w + io::Write> {
 ///
 /// If the type to be serialized doesn't have field names, then nothing is
 /// written, and the `Ok` return value is `false`.
-pub fn serialize_header<S: Serialize, W: io::Write>(
-    wtr: &mut Writer<W>,
+pub fn serialize_header<S: Serialize, W: Sink>(
+    wtr: &mut W,
+    style: SequenceStyle,
     value: S,
 ) -> Result<bool, Error> {
-    let mut ser = SeHeader::new(wtr);
+    let mut ser = SeHeader::new(wtr, style);
     value.serialize(&mut ser).map(|_| ser.wrote_header())
 }
 
@@ -432,6 +544,19 @@ pub fn serialize_header<S: Serialize, W: io::Write>(
 ///                                         v          v
 ///                                       Err(_)    Ok(())
 /// ```
+///
+/// A struct encountered in the `InStructField` state is an exception to the
+/// above: it's a nested struct, and its fields are written as headers
+/// prefixed with the name of the enclosing field, e.g., `address.city`. The
+/// header for a field is only written once a scalar is encountered in its
+/// value, since a nested struct writes one header for each of its fields
+/// instead. Maps are treated like structs, with their keys as field names.
+///
+/// Sequences (and tuples) encountered in the `InStructField` state are
+/// exceptions too. With `SequenceStyle::Expand`, each element is treated like
+/// the value of a field named after its index, e.g., `values[0]`. With
+/// `SequenceStyle::Join`, the header is written right away, and the
+/// `InJoinedField` state ignores the scalars in the sequence.
 enum HeaderState {
     /// Start here. Headers need to be written if the type has field names.
     Write,
@@ -443,23 +568,44 @@ enum HeaderState {
     EncounteredStructField,
     /// The serializer is currently in a struct field value.
     InStructField,
+    /// The serializer is currently in a sequence that is joined into a
+    /// single field, whose header was already written.
+    InJoinedField,
 }
 
-struct SeHeader<'w, W: 'w + io::Write> {
-    wtr: &'w mut Writer<W>,
+struct SeHeader<'w, W: 'w + Sink> {
+    wtr: &'w mut W,
+    style: SequenceStyle,
     state: HeaderState,
+    /// The header for the current field, including the names of the fields
+    /// that enclose it.
+    name: String,
+    /// The length of `name` before the key of each map entry being
+    /// serialized was appended to it.
+    keys: Vec<usize>,
+    /// For each sequence being serialized whose elements get headers of
+    /// their own, the length of `name` before the sequence and the index of
+    /// its next element.
+    sequences: Vec<Option<(usize, usize)>>,
 }
 
-#[cfg_attr(test, ::mutagen::mutate)] impl<'w, W: io::Write> SeHeader<'w, W> {
-    fn new(wtr: &'w mut Writer<W>) -> Self {
-        SeHeader { wtr: wtr, state: HeaderState::Write }
+#[cfg_attr(test, ::mutagen::mutate)] impl<'w, W: Sink> SeHeader<'w, W> {
+    fn new(wtr: &'w mut W, style: SequenceStyle) -> Self {
+        SeHeader {
+            wtr: wtr,
+            style: style,
+            state: HeaderState::Write,
+            name: String::new(),
+            keys: vec![],
+            sequences: vec![],
+        }
     }
 
     fn wrote_header(&self) -> bool {
         use self::HeaderState::*;
         match self.state {
             Write | ErrorIfWrite(_) => false,
-            EncounteredStructField | InStructField => true,
+            EncounteredStructField | InStructField | InJoinedField => true,
         }
     }
 
@@ -474,24 +620,107 @@ struct SeHeader<'w, W: 'w + io::Write> {
                 self.state = ErrorIfWrite(error_scalar_outside_struct(name));
                 Ok(())
             }
-            ErrorIfWrite(_) | InStructField => Ok(()),
+            ErrorIfWrite(_) => Ok(()),
+            InStructField => self.wtr.write_field(&self.name),
             EncounteredStructField => Err(error_scalar_outside_struct(name)),
+            InJoinedField => Ok(()),
         }
     }
 
-    fn handle_container<T: fmt::Display>(
+    fn handle_struct<T: fmt::Display>(
         &mut self,
         name: T,
     ) -> Result<&mut Self, Error> {
-        if let HeaderState::InStructField = self.state {
-            Err(error_container_inside_struct(name))
-        } else {
-            Ok(self)
+        match self.state {
+            HeaderState::InStructField => {
+                // A nested struct, whose fields are named after the field
+                // that contains it.
+                self.name.push('.');
+                self.state = HeaderState::EncounteredStructField;
+            }
+            HeaderState::InJoinedField => {
+                return Err(error_container_inside_join(name));
+            }
+            _ => {}
+        }
+        Ok(self)
+    }
+
+    /// Start writing the header for a struct field or map entry named by
+    /// `key`.
+    ///
+    /// The given function should append the name of the field to `name`.
+    fn start_field<F>(&mut self, key: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut String) -> Result<(), Error>,
+    {
+        // Grab old state and update state to `InStructField`.
+        let old_state =
+            mem::replace(&mut self.state, HeaderState::InStructField);
+        if let HeaderState::ErrorIfWrite(err) = old_state {
+            return Err(err);
         }
+        key(&mut self.name)
+    }
+
+    /// Finish writing the header for a struct field or map entry, and
+    /// restore `name` to the given length.
+    fn end_field(&mut self, len: usize) {
+        self.name.truncate(len);
+        self.state = HeaderState::EncounteredStructField;
+    }
+
+    fn handle_sequence<T: fmt::Display>(
+        &mut self,
+        name: T,
+    ) -> Result<&mut Self, Error> {
+        let sequence = match self.state {
+            HeaderState::InStructField => match self.style {
+                SequenceStyle::Join(_) => {
+                    self.wtr.write_field(&self.name)?;
+                    self.state = HeaderState::InJoinedField;
+                    None
+                }
+                _ => Some((self.name.len(), 0)),
+            },
+            HeaderState::InJoinedField => {
+                return Err(error_container_inside_join(name));
+            }
+            _ => None,
+        };
+        self.sequences.push(sequence);
+        Ok(self)
+    }
+
+    fn handle_element<T: ?Sized + Serialize>(
+        &mut self,
+        value: &T,
+    ) -> Result<(), Error> {
+        let (len, index) = match self.sequences.last_mut() {
+            Some(&mut Some((len, ref mut index))) => {
+                *index += 1;
+                (len, *index - 1)
+            }
+            _ => return value.serialize(self),
+        };
+        self.start_field(|name| {
+            name.push('[');
+            name.push_str(itoa::Buffer::new().format(index));
+            name.push(']');
+            Ok(())
+        })?;
+        value.serialize(&mut *self)?;
+        self.end_field(len);
+        Ok(())
+    }
+
+    fn end_sequence(&mut self) -> Result<(), Error> {
+        self.sequences.pop();
+        Ok(())
     }
 }
 
-#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: io::Write> Serializer for &'a mut SeHeader<'w, W> {
+#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: Sink> Serializer for &'a mut SeHeader<'w, W> {
     type Ok = ();
     type Error = Error;
     type SerializeSeq = Self;
@@ -576,9 +805,9 @@ struct SeHeader<'w, W: 'w + io::Write> {
 
     fn serialize_some<T: ?Sized + Serialize>(
         self,
-        _value: &T,
+        value: &T,
     ) -> Result<Self::Ok, Self::Error> {
-        self.handle_scalar("Some(_)")
+        value.serialize(self)
     }
 
     fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
@@ -604,9 +833,15 @@ struct SeHeader<'w, W: 'w + io::Write> {
     fn serialize_newtype_struct<T: ?Sized + Serialize>(
         self,
         name: &'static str,
-        _value: &T,
+        value: &T,
     ) -> Result<Self::Ok, Self::Error> {
-        self.handle_scalar(format!("{}(_)", name))
+        // In a field, the wrapped value is written just like the record
+        // serializer writes it, since it might be a sequence or a struct.
+        if let HeaderState::InStructField = self.state {
+            value.serialize(self)
+        } else {
+            self.handle_scalar(format!("{}(_)", name))
+        }
     }
 
     fn serialize_newtype_variant<T: ?Sized + Serialize>(
@@ -623,14 +858,14 @@ struct SeHeader<'w, W: 'w + io::Write> {
         self,
         _len: Option<usize>,
     ) -> Result<Self::SerializeSeq, Self::Error> {
-        self.handle_container("sequence")
+        self.handle_sequence("sequence")
     }
 
     fn serialize_tuple(
         self,
         _len: usize,
     ) -> Result<Self::SerializeTuple, Self::Error> {
-        self.handle_container("tuple")
+        self.handle_sequence("tuple")
     }
 
     fn serialize_tuple_struct(
@@ -638,7 +873,7 @@ struct SeHeader<'w, W: 'w + io::Write> {
         name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleStruct, Self::Error> {
-        self.handle_container(name)
+        self.handle_sequence(name)
     }
 
     fn serialize_tuple_variant(
@@ -655,12 +890,7 @@ struct SeHeader<'w, W: 'w + io::Write> {
         self,
         _len: Option<usize>,
     ) -> Result<Self::SerializeMap, Self::Error> {
-        // The right behavior for serializing maps isn't clear.
-        Err(Error::custom(
-            "serializing maps is not supported, \
-             if you have a use case, please file an issue at \
-             https://github.com/BurntSushi/rust-csv",
-        ))
+        self.handle_struct("map")
     }
 
     fn serialize_struct(
@@ -668,7 +898,7 @@ struct SeHeader<'w, W: 'w + io::Write> {
         name: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStruct, Self::Error> {
-        self.handle_container(name)
+        self.handle_struct(name)
     }
 
     fn serialize_struct_variant(
@@ -682,7 +912,7 @@ struct SeHeader<'w, W: 'w + io::Write> {
     }
 }
 
-#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: io::Write> SerializeSeq for &'a mut SeHeader<'w, W> {
+#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: Sink> SerializeSeq for &'a mut SeHeader<'w, W> {
     type Ok = ();
     type Error = Error;
 
@@ -690,15 +920,15 @@ struct SeHeader<'w, W: 'w + io::Write> {
         &mut self,
         value: &T,
     ) -> Result<(), Self::Error> {
-        value.serialize(&mut **self)
+        self.handle_element(value)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(())
+        self.end_sequence()
     }
 }
 
-#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: io::Write> SerializeTuple for &'a mut SeHeader<'w, W> {
+#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: Sink> SerializeTuple for &'a mut SeHeader<'w, W> {
     type Ok = ();
     type Error = Error;
 
@@ -706,15 +936,15 @@ struct SeHeader<'w, W: 'w + io::Write> {
         &mut self,
         value: &T,
     ) -> Result<(), Self::Error> {
-        value.serialize(&mut **self)
+        self.handle_element(value)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(())
+        self.end_sequence()
     }
 }
 
-#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: io::Write> SerializeTupleStruct for &'a mut SeHeader<'w, W> {
+#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: Sink> SerializeTupleStruct for &'a mut SeHeader<'w, W> {
     type Ok = ();
     type Error = Error;
 
@@ -722,15 +952,15 @@ struct SeHeader<'w, W: 'w + io::Write> {
         &mut self,
         value: &T,
     ) -> Result<(), Self::Error> {
-        value.serialize(&mut **self)
+        self.handle_element(value)
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        Ok(())
+        self.end_sequence()
     }
 }
 
-#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: io::Write> SerializeTupleVariant for &'a mut SeHeader<'w, W> {
+#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: Sink> SerializeTupleVariant for &'a mut SeHeader<'w, W> {
     type Ok = ();
     type Error = Error;
 
@@ -746,30 +976,34 @@ struct SeHeader<'w, W: 'w + io::Write> {
     }
 }
 
-#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: io::Write> SerializeMap for &'a mut SeHeader<'w, W> {
+#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: Sink> SerializeMap for &'a mut SeHeader<'w, W> {
     type Ok = ();
     type Error = Error;
 
     fn serialize_key<T: ?Sized + Serialize>(
         &mut self,
-        _key: &T,
+        key: &T,
     ) -> Result<(), Self::Error> {
-        unreachable!()
+        self.keys.push(self.name.len());
+        self.start_field(|name| key.serialize(SeMapKey { name: name }))
     }
 
     fn serialize_value<T: ?Sized + Serialize>(
         &mut self,
-        _value: &T,
+        value: &T,
     ) -> Result<(), Self::Error> {
-        unreachable!()
+        value.serialize(&mut **self)?;
+        let len = self.keys.pop().expect("map key before value");
+        self.end_field(len);
+        Ok(())
     }
 
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        unreachable!()
+        Ok(())
     }
 }
 
-#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: io::Write> SerializeStruct for &'a mut SeHeader<'w, W> {
+#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: Sink> SerializeStruct for &'a mut SeHeader<'w, W> {
     type Ok = ();
     type Error = Error;
 
@@ -778,19 +1012,15 @@ struct SeHeader<'w, W: 'w + io::Write> {
         key: &'static str,
         value: &T,
     ) -> Result<(), Self::Error> {
-        // Grab old state and update state to `EncounteredStructField`.
-        let old_state =
-            mem::replace(&mut self.state, HeaderState::EncounteredStructField);
-        if let HeaderState::ErrorIfWrite(err) = old_state {
-            return Err(err);
-        }
-        self.wtr.write_field(key)?;
-
-        // Check that there aren't any containers in the value.
-        self.state = HeaderState::InStructField;
+        let len = self.name.len();
+        self.start_field(|name| {
+            name.push_str(key);
+            Ok(())
+        })?;
+        // Writes the headers for the value, which are several if it's a
+        // struct or a sequence.
         value.serialize(&mut **self)?;
-        self.state = HeaderState::EncounteredStructField;
-
+        self.end_field(len);
         Ok(())
     }
 
@@ -799,7 +1029,7 @@ struct SeHeader<'w, W: 'w + io::Write> {
     }
 }
 
-#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: io::Write> SerializeStructVariant for &'a mut SeHeader<'w, W> {
+#[cfg_attr(test, ::mutagen::mutate)] impl<'a, 'w, W: Sink> SerializeStructVariant for &'a mut SeHeader<'w, W> {
     type Ok = ();
     type Error = Error;
 
@@ -816,19 +1046,220 @@ struct SeHeader<'w, W: 'w + io::Write> {
     }
 }
 
+/// Appends a map key to the name of a header.
+///
+/// Only keys that are strings, characters, booleans or integers are
+/// supported.
+struct SeMapKey<'a> {
+    name: &'a mut String,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<'a> SeMapKey<'a> {
+    fn push_integer<I: itoa::Integer>(self, v: I) -> Result<(), Error> {
+        self.name.push_str(itoa::Buffer::new().format(v));
+        Ok(())
+    }
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<'a> Serializer for SeMapKey<'a> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Impossible<(), Error>;
+    type SerializeTuple = Impossible<(), Error>;
+    type SerializeTupleStruct = Impossible<(), Error>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = Impossible<(), Error>;
+    type SerializeStruct = Impossible<(), Error>;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
+        self.name.push_str(if v { "true" } else { "false" });
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
+        self.push_integer(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok, Self::Error> {
+        self.push_integer(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok, Self::Error> {
+        self.push_integer(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
+        self.push_integer(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
+        self.push_integer(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok, Self::Error> {
+        self.push_integer(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok, Self::Error> {
+        self.push_integer(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok, Self::Error> {
+        self.push_integer(v)
+    }
+
+    fn serialize_f32(self, _v: f32) -> Result<Self::Ok, Self::Error> {
+        Err(error_map_key("f32"))
+    }
+
+    fn serialize_f64(self, _v: f64) -> Result<Self::Ok, Self::Error> {
+        Err(error_map_key("f64"))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok, Self::Error> {
+        self.name.push(v);
+        Ok(())
+    }
+
+    fn serialize_str(self, value: &str) -> Result<Self::Ok, Self::Error> {
+        self.name.push_str(value);
+        Ok(())
+    }
+
+    fn serialize_bytes(self, _value: &[u8]) -> Result<Self::Ok, Self::Error> {
+        Err(error_map_key("&[u8]"))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
+        Err(error_map_key("None"))
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(
+        self,
+        _value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        Err(error_map_key("Some(_)"))
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Self::Error> {
+        Err(error_map_key("()"))
+    }
+
+    fn serialize_unit_struct(
+        self,
+        name: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        Err(error_map_key(name))
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Self::Error> {
+        self.name.push_str(variant);
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _value: &T,
+    ) -> Result<Self::Ok, Self::Error> {
+        Err(error_map_key(format!("{}::{}(_)", name, variant)))
+    }
+
+    fn serialize_seq(
+        self,
+        _len: Option<usize>,
+    ) -> Result<Self::SerializeSeq, Self::Error> {
+        Err(error_map_key("sequence"))
+    }
+
+    fn serialize_tuple(
+        self,
+        _len: usize,
+    ) -> Result<Self::SerializeTuple, Self::Error> {
+        Err(error_map_key("tuple"))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Self::Error> {
+        Err(error_map_key(name))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        Err(error_map_key(format!("{}::{}", name, variant)))
+    }
+
+    fn serialize_map(
+        self,
+        _len: Option<usize>,
+    ) -> Result<Self::SerializeMap, Self::Error> {
+        Err(error_map_key("map"))
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Self::Error> {
+        Err(error_map_key(name))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        Err(error_map_key(format!("{}::{}", name, variant)))
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeMap;
+
     use bstr::ByteSlice;
     use serde::{serde_if_integer128, Serialize};
 
+    use crate::byte_record::ByteRecord;
     use crate::error::{Error, ErrorKind};
     use crate::writer::Writer;
+    use crate::SequenceStyle;
 
-    use super::{SeHeader, SeRecord};
+    use super::SeHeader;
 
     fn serialize<S: Serialize>(s: S) -> String {
+        serialize_style(SequenceStyle::Expand, s)
+    }
+
+    fn serialize_style<S: Serialize>(style: SequenceStyle, s: S) -> String {
         let mut wtr = Writer::from_writer(vec![]);
-        s.serialize(&mut SeRecord { wtr: &mut wtr }).unwrap();
+        super::serialize(&mut wtr, style, s).unwrap();
         wtr.write_record(None::<&[u8]>).unwrap();
         String::from_utf8(wtr.into_inner().unwrap()).unwrap()
     }
@@ -836,9 +1267,16 @@ mod tests {
     /// Serialize using `SeHeader`. Returns whether a header was written and
     /// the output of the writer.
     fn serialize_header<S: Serialize>(s: S) -> (bool, String) {
+        serialize_header_style(SequenceStyle::Expand, s)
+    }
+
+    fn serialize_header_style<S: Serialize>(
+        style: SequenceStyle,
+        s: S,
+    ) -> (bool, String) {
         let mut wtr = Writer::from_writer(vec![]);
         let wrote = {
-            let mut ser = SeHeader::new(&mut wtr);
+            let mut ser = SeHeader::new(&mut wtr, style);
             s.serialize(&mut ser).unwrap();
             ser.wrote_header()
         };
@@ -846,13 +1284,24 @@ mod tests {
     }
 
     fn serialize_err<S: Serialize>(s: S) -> Error {
+        serialize_err_style(SequenceStyle::Expand, s)
+    }
+
+    fn serialize_err_style<S: Serialize>(style: SequenceStyle, s: S) -> Error {
         let mut wtr = Writer::from_writer(vec![]);
-        s.serialize(&mut SeRecord { wtr: &mut wtr }).unwrap_err()
+        super::serialize(&mut wtr, style, s).unwrap_err()
     }
 
     fn serialize_header_err<S: Serialize>(s: S) -> Error {
+        serialize_header_err_style(SequenceStyle::Expand, s)
+    }
+
+    fn serialize_header_err_style<S: Serialize>(
+        style: SequenceStyle,
+        s: S,
+    ) -> Error {
         let mut wtr = Writer::from_writer(vec![]);
-        s.serialize(&mut SeHeader::new(&mut wtr)).unwrap_err()
+        s.serialize(&mut SeHeader::new(&mut wtr, style)).unwrap_err()
     }
 
     #[test]
@@ -1197,11 +1646,9 @@ mod tests {
         let got = serialize(row.clone());
         assert_eq!(got, "foo,bar,5\n");
 
-        let err = serialize_header_err(row.clone());
-        match *err.kind() {
-            ErrorKind::Serialize(_) => {}
-            ref x => panic!("expected ErrorKind::Serialize but got '{:?}'", x),
-        }
+        let (wrote, got) = serialize_header(row.clone());
+        assert!(wrote);
+        assert_eq!(got, "label,nest.label2,nest.value");
     }
 
     #[test]
@@ -1216,13 +1663,129 @@ mod tests {
         let got = serialize(row.clone());
         assert_eq!(got, "foo,1,2,3\n");
 
-        let err = serialize_header_err(row.clone());
+        let (wrote, got) = serialize_header(row.clone());
+        assert!(wrote);
+        assert_eq!(got, "label,values[0],values[1],values[2]");
+    }
+
+    #[test]
+    fn struct_headers_nested_seq_joined() {
+        #[derive(Clone, Serialize)]
+        struct Foo {
+            label: String,
+            values: Vec<i32>,
+            empty: Vec<i32>,
+        }
+        let row =
+            Foo { label: "foo".into(), values: vec![1, 2, 3], empty: vec![] };
+        let style = SequenceStyle::Join(b';');
+
+        let got = serialize_style(style, row.clone());
+        assert_eq!(got, "foo,1;2;3,\n");
+
+        let (wrote, got) = serialize_header_style(style, row.clone());
+        assert!(wrote);
+        assert_eq!(got, "label,values,empty");
+    }
+
+    #[test]
+    fn struct_headers_nested_seq_joined_container() {
+        #[derive(Clone, Serialize)]
+        struct Point {
+            x: i32,
+        }
+        #[derive(Clone, Serialize)]
+        struct Foo {
+            points: Vec<Point>,
+        }
+        let row = Foo { points: vec![Point { x: 1 }] };
+        let style = SequenceStyle::Join(b';');
+
+        let err = serialize_err_style(style, row.clone());
+        match *err.kind() {
+            ErrorKind::Serialize(_) => {}
+            ref x => panic!("expected ErrorKind::Serialize but got '{:?}'", x),
+        }
+        let err = serialize_header_err_style(style, row.clone());
         match *err.kind() {
             ErrorKind::Serialize(_) => {}
             ref x => panic!("expected ErrorKind::Serialize but got '{:?}'", x),
         }
     }
 
+    #[test]
+    fn seq_not_joined() {
+        let got = serialize_style(SequenceStyle::Join(b';'), vec![1, 2, 3]);
+        assert_eq!(got, "1,2,3\n");
+    }
+
+    #[test]
+    fn struct_headers_nested_seq_structs() {
+        #[derive(Clone, Serialize)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+        #[derive(Clone, Serialize)]
+        struct Foo {
+            points: Vec<Point>,
+            pos: (f64, f64),
+        }
+        let row = Foo {
+            points: vec![Point { x: 1, y: 2 }, Point { x: 3, y: 4 }],
+            pos: (0.5, 1.5),
+        };
+
+        let got = serialize(row.clone());
+        assert_eq!(got, "1,2,3,4,0.5,1.5\n");
+
+        let (wrote, got) = serialize_header(row.clone());
+        assert!(wrote);
+        assert_eq!(
+            got,
+            "points[0].x,points[0].y,points[1].x,points[1].y,pos[0],pos[1]"
+        );
+    }
+
+    #[test]
+    fn struct_headers_nested_newtype() {
+        #[derive(Clone, Serialize)]
+        struct Tags(Vec<&'static str>);
+        #[derive(Clone, Serialize)]
+        struct Foo {
+            tags: Tags,
+        }
+        let row = Foo { tags: Tags(vec!["a", "b"]) };
+
+        let got = serialize(row.clone());
+        assert_eq!(got, "a,b\n");
+
+        let (wrote, got) = serialize_header(row.clone());
+        assert!(wrote);
+        assert_eq!(got, "tags[0],tags[1]");
+    }
+
+    #[test]
+    fn struct_fields_to_byte_record() {
+        #[derive(Clone, Serialize)]
+        struct Foo {
+            label: &'static str,
+            values: Vec<i32>,
+        }
+        let row = Foo { label: "foo", values: vec![1, 2] };
+
+        let mut names = ByteRecord::new();
+        let wrote =
+            super::serialize_header(&mut names, SequenceStyle::Expand, &row)
+                .unwrap();
+        assert!(wrote);
+        assert_eq!(names, vec!["label", "values[0]", "values[1]"]);
+
+        let mut values = ByteRecord::new();
+        super::serialize(&mut values, SequenceStyle::Expand, &row).unwrap();
+        assert_eq!(values, vec!["foo", "1", "2"]);
+    }
+
     #[test]
     fn struct_headers_inside_tuple() {
         #[derive(Clone, Serialize)]
@@ -1338,4 +1901,136 @@ mod tests {
         assert!(wrote);
         assert_eq!(got, "label,num,label2,value,empty,label,num");
     }
+
+    #[test]
+    fn struct_headers_nested_option() {
+        #[derive(Clone, Serialize)]
+        struct Foo {
+            label: String,
+            nest: Option<Nested>,
+        }
+        #[derive(Clone, Serialize)]
+        struct Nested {
+            value: i32,
+        }
+
+        let row = Foo { label: "foo".into(), nest: Some(Nested { value: 5 }) };
+        let (wrote, got) = serialize_header(row.clone());
+        assert!(wrote);
+        assert_eq!(got, "label,nest.value");
+        let got = serialize(row.clone());
+        assert_eq!(got, "foo,5\n");
+    }
+
+    #[test]
+    fn struct_headers_flatten() {
+        #[derive(Clone, Serialize)]
+        struct Foo {
+            label: String,
+            #[serde(flatten)]
+            nest: Nested,
+        }
+        #[derive(Clone, Serialize)]
+        struct Nested {
+            x: i32,
+            inner: Inner,
+        }
+        #[derive(Clone, Serialize)]
+        struct Inner {
+            y: bool,
+        }
+
+        let row = Foo {
+            label: "foo".into(),
+            nest: Nested { x: 5, inner: Inner { y: true } },
+        };
+        let (wrote, got) = serialize_header(row.clone());
+        assert!(wrote);
+        assert_eq!(got, "label,x,inner.y");
+        let got = serialize(row.clone());
+        assert_eq!(got, "foo,5,true\n");
+    }
+
+    #[test]
+    fn map_headers() {
+        let mut row = BTreeMap::new();
+        row.insert("b", 2);
+        row.insert("a", 1);
+
+        let (wrote, got) = serialize_header(row.clone());
+        assert!(wrote);
+        assert_eq!(got, "a,b");
+        let got = serialize(row.clone());
+        assert_eq!(got, "1,2\n");
+
+        let mut row = BTreeMap::new();
+        row.insert(7, "x");
+        let (wrote, got) = serialize_header(row.clone());
+        assert!(wrote);
+        assert_eq!(got, "7");
+    }
+
+    #[test]
+    fn map_headers_bad_key() {
+        let mut row = BTreeMap::new();
+        row.insert((1, 2), "x");
+
+        let err = serialize_header_err(row.clone());
+        match *err.kind() {
+            ErrorKind::Serialize(_) => {}
+            ref x => panic!("expected ErrorKind::Serialize but got '{:?}'", x),
+        }
+    }
+
+    #[test]
+    fn struct_headers_nested_map() {
+        #[derive(Clone, Serialize)]
+        struct Foo {
+            label: String,
+            attrs: BTreeMap<&'static str, i32>,
+        }
+
+        let mut attrs = BTreeMap::new();
+        attrs.insert("width", 3);
+        attrs.insert("height", 4);
+        let row = Foo { label: "foo".into(), attrs: attrs };
+
+        let (wrote, got) = serialize_header(row.clone());
+        assert!(wrote);
+        assert_eq!(got, "label,attrs.height,attrs.width");
+        let got = serialize(row.clone());
+        assert_eq!(got, "foo,4,3\n");
+    }
+
+    #[test]
+    fn enum_internally_tagged() {
+        #[derive(Clone, Serialize)]
+        #[serde(tag = "type")]
+        enum Shape {
+            Circle { r: f64 },
+        }
+
+        let row = Shape::Circle { r: 1.5 };
+        let (wrote, got) = serialize_header(row.clone());
+        assert!(wrote);
+        assert_eq!(got, "type,r");
+        let got = serialize(row.clone());
+        assert_eq!(got, "Circle,1.5\n");
+    }
+
+    #[test]
+    fn enum_adjacently_tagged() {
+        #[derive(Clone, Serialize)]
+        #[serde(tag = "kind", content = "data")]
+        enum Event {
+            Click { x: i32, y: i32 },
+        }
+
+        let row = Event::Click { x: 1, y: 2 };
+        let (wrote, got) = serialize_header(row.clone());
+        assert!(wrote);
+        assert_eq!(got, "kind,data.x,data.y");
+        let got = serialize(row.clone());
+        assert_eq!(got, "Click,1,2\n");
+    }
 }
@@ -0,0 +1,923 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::result;
+use std::str;
+
+use serde::de::{
+    DeserializeSeed, Deserializer, Error as SerdeError, SeqAccess, Unexpected,
+    Visitor,
+};
+use serde::ser::{Serialize, Serializer};
+
+use crate::byte_record::ByteRecord;
+use crate::deserializer::deserialize_string_record_seed;
+use crate::error::Result;
+use crate::reader::ReaderBuilder;
+use crate::string_record::StringRecord;
+use crate::Terminator;
+
+/// The delimiters that a `Sniffer` considers, in order of preference.
+const DELIMITERS: &[u8] = b",\t;|:";
+
+/// The quote characters that a `Sniffer` considers, in order of preference.
+const QUOTES: &[u8] = b"\"'";
+
+/// Detects the format of CSV data from a sample of it.
+///
+/// A sniffer reads a bounded sample from the start of some CSV data and
+/// guesses its delimiter, quote character and record terminator, whether its
+/// first record is a header row and the type of each of its columns. The
+/// result is a [`Schema`](struct.Schema.html), which can configure a
+/// `ReaderBuilder` to read the rest of the data and deserialize its records
+/// into dynamically typed [`Value`](enum.Value.html)s.
+///
+/// Since only a sample is inspected, the result is a best guess. For
+/// example, a column whose sampled values are all integers is reported as an
+/// integer column even if a later value is not.
+///
+/// # Example
+///
+/// ```
+/// use std::error::Error;
+/// use csv::{ColumnType, Sniffer, Value};
+///
+/// # fn main() { example().unwrap(); }
+/// fn example() -> Result<(), Box<dyn Error>> {
+///     let data = "\
+/// city;population;capital
+/// Boston;4628910;true
+/// Concord;;false
+/// ";
+///     let schema = Sniffer::new().sniff_reader(data.as_bytes())?;
+///     assert_eq!(schema.delimiter(), b';');
+///     assert!(schema.has_headers());
+///
+///     let column = &schema.columns()[1];
+///     assert_eq!(column.name(), Some("population"));
+///     assert_eq!(column.column_type(), ColumnType::Integer);
+///     assert!(column.is_nullable());
+///
+///     let mut rdr = schema.reader_builder().from_reader(data.as_bytes());
+///     let mut rows = vec![];
+///     for result in rdr.records() {
+///         rows.push(schema.deserialize(&result?)?);
+///     }
+///     assert_eq!(rows[1], vec![
+///         Value::String("Concord".to_string()),
+///         Value::Null,
+///         Value::Boolean(false),
+///     ]);
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Sniffer {
+    sample_size: usize,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl Default for Sniffer {
+    fn default() -> Sniffer {
+        Sniffer { sample_size: 64 * (1 << 10) }
+    }
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl Sniffer {
+    /// Create a new sniffer with a default configuration.
+    pub fn new() -> Sniffer {
+        Sniffer::default()
+    }
+
+    /// Set the maximum number of bytes to read when sniffing CSV data.
+    ///
+    /// Larger samples give more reliable results, at the cost of reading and
+    /// parsing more data. If the sample ends in the middle of the data, then
+    /// its last (possibly partial) record is ignored.
+    ///
+    /// # Panics
+    ///
+    /// This panics if `sample_size` is `0`.
+    pub fn sample_size(&mut self, sample_size: usize) -> &mut Sniffer {
+        assert!(sample_size > 0, "sample size must be non-zero");
+        self.sample_size = sample_size;
+        self
+    }
+
+    /// Sniff the format of the CSV data in the file at the given path.
+    ///
+    /// If there was a problem opening or reading the file, then this returns
+    /// the corresponding error.
+    pub fn sniff_path<P: AsRef<Path>>(&self, path: P) -> Result<Schema> {
+        self.sniff_reader(File::open(path)?)
+    }
+
+    /// Sniff the format of the CSV data read from `rdr`.
+    ///
+    /// This reads at most one byte more than the configured sample size from
+    /// `rdr`. To read the data again afterwards, either pass a mutable
+    /// reference to a seekable reader and rewind it, or open the data again.
+    ///
+    /// If there was a problem reading from `rdr`, then this returns the
+    /// corresponding error.
+    pub fn sniff_reader<R: io::Read>(&self, rdr: R) -> Result<Schema> {
+        let mut sample = vec![];
+        rdr.take(self.sample_size as u64 + 1).read_to_end(&mut sample)?;
+        let truncated = sample.len() > self.sample_size;
+        sample.truncate(self.sample_size);
+
+        let terminator = sniff_terminator(&sample);
+        let mut best: Option<Candidate> = None;
+        for &quote in QUOTES {
+            for &delimiter in DELIMITERS {
+                let mut rdr = ReaderBuilder::new()
+                    .delimiter(delimiter)
+                    .quote(quote)
+                    .terminator(terminator)
+                    .has_headers(false)
+                    .flexible(true)
+                    .from_reader(&sample[..]);
+                let mut records =
+                    rdr.byte_records().collect::<Result<Vec<_>>>()?;
+                if truncated {
+                    records.pop();
+                }
+                let candidate = Candidate::new(delimiter, quote, records);
+                if best.as_ref().map_or(true, |b| candidate.beats(b)) {
+                    best = Some(candidate);
+                }
+            }
+        }
+        let best = best.unwrap();
+
+        let has_headers = sniff_headers(&best.records, best.fields);
+        let (header, body) = match best.records.split_first() {
+            Some((header, body)) if has_headers => (Some(header), body),
+            _ => (None, &best.records[..]),
+        };
+        let columns = infer_columns(body, best.fields)
+            .into_iter()
+            .enumerate()
+            .map(|(i, (ty, nullable))| Column {
+                name: header
+                    .and_then(|h| h.get(i))
+                    .map(|name| String::from_utf8_lossy(name).into_owned()),
+                ty: ty.unwrap_or(ColumnType::String),
+                nullable: nullable,
+            })
+            .collect();
+        Ok(Schema {
+            delimiter: best.delimiter,
+            quote: best.quote,
+            terminator: terminator,
+            has_headers: has_headers,
+            flexible: best.records.iter().any(|r| r.len() != best.fields),
+            columns: columns,
+        })
+    }
+}
+
+/// The result of parsing a sample with a particular delimiter and quote.
+struct Candidate {
+    delimiter: u8,
+    quote: u8,
+    records: Vec<ByteRecord>,
+    /// The most common number of fields in a record.
+    fields: usize,
+    /// The fraction of records that have `fields` fields.
+    consistency: f64,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl Candidate {
+    fn new(delimiter: u8, quote: u8, records: Vec<ByteRecord>) -> Candidate {
+        let mut counts = HashMap::new();
+        for record in &records {
+            *counts.entry(record.len()).or_insert(0) += 1;
+        }
+        // Prefer the larger number of fields when two are equally common.
+        let (fields, count) = counts
+            .into_iter()
+            .max_by_key(|&(fields, count)| (count, fields))
+            .unwrap_or((0, 0));
+        let consistency = if records.is_empty() {
+            0.0
+        } else {
+            count as f64 / records.len() as f64
+        };
+        Candidate {
+            delimiter: delimiter,
+            quote: quote,
+            records: records,
+            fields: fields,
+            consistency: consistency,
+        }
+    }
+
+    /// Returns true if this candidate is a strictly better guess than
+    /// `other`.
+    ///
+    /// A delimiter that splits records into multiple fields is always
+    /// preferred, followed by the one that splits records most consistently.
+    fn beats(&self, other: &Candidate) -> bool {
+        (self.fields > 1, self.consistency)
+            > (other.fields > 1, other.consistency)
+    }
+}
+
+/// Guess the record terminator used by the given sample.
+///
+/// `\n` and `\r` are only reported on their own if they are used
+/// exclusively. Otherwise, the default `CRLF` terminator handles all of them.
+fn sniff_terminator(sample: &[u8]) -> Terminator {
+    let crlf = sample.windows(2).filter(|w| w == b"\r\n").count();
+    let lf = sample.iter().filter(|&&b| b == b'\n').count() - crlf;
+    let cr = sample.iter().filter(|&&b| b == b'\r').count() - crlf;
+    match (crlf, lf, cr) {
+        (0, lf, 0) if lf > 0 => Terminator::Any(b'\n'),
+        (0, 0, cr) if cr > 0 => Terminator::Any(b'\r'),
+        _ => Terminator::CRLF,
+    }
+}
+
+/// Guess whether the first record is a header row.
+///
+/// Each column votes on whether the first record looks different from the
+/// rest: either because the rest have a type that the first record's value
+/// doesn't have, or because the rest have a fixed length that the first
+/// record's value doesn't have.
+fn sniff_headers(records: &[ByteRecord], fields: usize) -> bool {
+    if records.len() < 2 {
+        return false;
+    }
+    let types = infer_columns(&records[1..], fields);
+    let mut votes = 0i64;
+    for (i, field) in records[0].iter().enumerate().take(fields) {
+        let field = match str::from_utf8(field) {
+            Ok(field) if !field.is_empty() => field,
+            _ => continue,
+        };
+        match types[i].0 {
+            None => {}
+            Some(ColumnType::String) => {
+                let mut lens = records[1..]
+                    .iter()
+                    .filter_map(|r| r.get(i))
+                    .filter(|f| !f.is_empty())
+                    .map(|f| f.len());
+                let len = match lens.next() {
+                    None => continue,
+                    Some(len) => len,
+                };
+                if lens.all(|l| l == len) {
+                    votes += if field.len() != len { 1 } else { -1 };
+                }
+            }
+            Some(ty) => {
+                votes +=
+                    if ty.merge(infer_type(field)) != ty { 1 } else { -1 };
+            }
+        }
+    }
+    votes > 0
+}
+
+/// Infer the type of each of the first `fields` columns of the given
+/// records, along with whether the column is nullable.
+///
+/// The type of a column with no non-empty values is `None`.
+fn infer_columns(
+    records: &[ByteRecord],
+    fields: usize,
+) -> Vec<(Option<ColumnType>, bool)> {
+    let mut columns = vec![(None, false); fields];
+    for record in records {
+        for (i, column) in columns.iter_mut().enumerate() {
+            let ty = match record.get(i) {
+                None => {
+                    column.1 = true;
+                    continue;
+                }
+                Some(field) if field.is_empty() => {
+                    column.1 = true;
+                    continue;
+                }
+                Some(field) => match str::from_utf8(field) {
+                    Ok(field) => infer_type(field),
+                    Err(_) => ColumnType::String,
+                },
+            };
+            column.0 = Some(column.0.map_or(ty, |t: ColumnType| t.merge(ty)));
+        }
+    }
+    columns
+}
+
+/// Infer the most specific type of a single non-empty field.
+fn infer_type(field: &str) -> ColumnType {
+    if field.parse::<i64>().is_ok() {
+        ColumnType::Integer
+    } else if field.parse::<f64>().is_ok()
+        && field.bytes().any(|b| b.is_ascii_digit())
+    {
+        ColumnType::Float
+    } else if field.parse::<bool>().is_ok() {
+        ColumnType::Boolean
+    } else if is_date(field) {
+        ColumnType::Date
+    } else {
+        ColumnType::String
+    }
+}
+
+/// Returns true if and only if the given field is a date of the form
+/// `YYYY-MM-DD`.
+fn is_date(field: &str) -> bool {
+    let b = field.as_bytes();
+    if b.len() != 10 || b[4] != b'-' || b[7] != b'-' {
+        return false;
+    }
+    let digits = |r: &[u8]| {
+        r.iter().try_fold(0u32, |n, &d| {
+            if d.is_ascii_digit() {
+                Some(n * 10 + (d - b'0') as u32)
+            } else {
+                None
+            }
+        })
+    };
+    match (digits(&b[0..4]), digits(&b[5..7]), digits(&b[8..10])) {
+        (Some(_), Some(month), Some(day)) => {
+            1 <= month && month <= 12 && 1 <= day && day <= 31
+        }
+        _ => false,
+    }
+}
+
+/// The format of some CSV data, as detected by a
+/// [`Sniffer`](struct.Sniffer.html).
+///
+/// A schema describes the CSV dialect of the data, which can be used to
+/// configure a `ReaderBuilder`, and the columns of its records, which can be
+/// used to deserialize records into dynamically typed values.
+#[derive(Clone, Debug)]
+pub struct Schema {
+    delimiter: u8,
+    quote: u8,
+    terminator: Terminator,
+    has_headers: bool,
+    flexible: bool,
+    columns: Vec<Column>,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl Schema {
+    /// The field delimiter.
+    pub fn delimiter(&self) -> u8 {
+        self.delimiter
+    }
+
+    /// The quote character.
+    pub fn quote(&self) -> u8 {
+        self.quote
+    }
+
+    /// The record terminator.
+    ///
+    /// This is `Terminator::Any` when the sample exclusively used either
+    /// `\n` or `\r` to terminate records, and `Terminator::CRLF` otherwise.
+    pub fn terminator(&self) -> Terminator {
+        self.terminator
+    }
+
+    /// Whether the first record is a header row.
+    pub fn has_headers(&self) -> bool {
+        self.has_headers
+    }
+
+    /// Whether records have differing numbers of fields.
+    pub fn is_flexible(&self) -> bool {
+        self.flexible
+    }
+
+    /// The columns of each record, in order.
+    ///
+    /// The number of columns is the most common number of fields in a record.
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    /// Configure the given builder to read data in the format of this schema.
+    ///
+    /// This sets the delimiter, quote character, record terminator, whether
+    /// the data has a header row and whether records may have differing
+    /// numbers of fields.
+    pub fn configure<'b>(
+        &self,
+        builder: &'b mut ReaderBuilder,
+    ) -> &'b mut ReaderBuilder {
+        builder
+            .delimiter(self.delimiter)
+            .quote(self.quote)
+            .terminator(self.terminator)
+            .has_headers(self.has_headers)
+            .flexible(self.flexible)
+    }
+
+    /// Create a new builder configured to read data in the format of this
+    /// schema.
+    pub fn reader_builder(&self) -> ReaderBuilder {
+        let mut builder = ReaderBuilder::new();
+        self.configure(&mut builder);
+        builder
+    }
+
+    /// Deserialize a record into a value for every column of this schema.
+    ///
+    /// Each field is parsed according to the type of its column. Empty and
+    /// missing fields in nullable columns become `Value::Null`, and fields
+    /// beyond the last column are ignored.
+    ///
+    /// If a field can't be parsed as the type of its column, or if a
+    /// non-nullable column is missing, then this returns an error.
+    pub fn deserialize(&self, record: &StringRecord) -> Result<Vec<Value>> {
+        deserialize_string_record_seed(RecordSeed(&self.columns), record, None)
+    }
+}
+
+/// A column of a [`Schema`](struct.Schema.html).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Column {
+    name: Option<String>,
+    ty: ColumnType,
+    nullable: bool,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl Column {
+    /// The name of this column, taken from the header row.
+    ///
+    /// This is `None` if the data has no header row, or if the header row
+    /// doesn't have a field for this column.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_ref().map(|name| &**name)
+    }
+
+    /// The type of every value in this column.
+    pub fn column_type(&self) -> ColumnType {
+        self.ty
+    }
+
+    /// Whether this column has empty or missing values.
+    pub fn is_nullable(&self) -> bool {
+        self.nullable
+    }
+}
+
+/// The type of the values in a column.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ColumnType {
+    /// Integers that fit in an `i64`.
+    Integer,
+    /// Floating point numbers, as parsed by `f64`.
+    Float,
+    /// Either `true` or `false`.
+    Boolean,
+    /// Dates of the form `YYYY-MM-DD`.
+    Date,
+    /// Arbitrary text.
+    String,
+    /// Hints that destructuring should not be exhaustive.
+    ///
+    /// This enum may grow additional variants, so this makes sure clients
+    /// don't count on exhaustive matching. (Otherwise, adding a new variant
+    /// could break existing code.)
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl ColumnType {
+    /// Returns the most specific type that describes values of both types.
+    fn merge(self, other: ColumnType) -> ColumnType {
+        use self::ColumnType::*;
+
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Integer, Float) | (Float, Integer) => Float,
+            _ => String,
+        }
+    }
+}
+
+/// A dynamically typed field, deserialized according to a
+/// [`Schema`](struct.Schema.html).
+///
+/// Values implement `Serialize`, so a record of values can be written with
+/// `Writer::serialize`. A null value is written as an empty field.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// An empty or missing field in a nullable column.
+    Null,
+    /// A field in an integer column.
+    Integer(i64),
+    /// A field in a floating point column.
+    Float(f64),
+    /// A field in a boolean column.
+    Boolean(bool),
+    /// A field in a date column, of the form `YYYY-MM-DD`.
+    Date(String),
+    /// A field in a text column.
+    String(String),
+    /// Hints that destructuring should not be exhaustive.
+    ///
+    /// This enum may grow additional variants, so this makes sure clients
+    /// don't count on exhaustive matching. (Otherwise, adding a new variant
+    /// could break existing code.)
+    #[doc(hidden)]
+    __Nonexhaustive,
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl Serialize for Value {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> result::Result<S::Ok, S::Error> {
+        match *self {
+            Value::Null => serializer.serialize_none(),
+            Value::Integer(n) => serializer.serialize_i64(n),
+            Value::Float(n) => serializer.serialize_f64(n),
+            Value::Boolean(b) => serializer.serialize_bool(b),
+            Value::Date(ref s) | Value::String(ref s) => {
+                serializer.serialize_str(s)
+            }
+            Value::__Nonexhaustive => unreachable!(),
+        }
+    }
+}
+
+/// Deserializes a record into a value for each of the given columns.
+struct RecordSeed<'a>(&'a [Column]);
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<'de, 'a> DeserializeSeed<'de> for RecordSeed<'a> {
+    type Value = Vec<Value>;
+
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> result::Result<Vec<Value>, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<'de, 'a> Visitor<'de> for RecordSeed<'a> {
+    type Value = Vec<Value>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a record with {} fields", self.0.len())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(
+        self,
+        mut seq: A,
+    ) -> result::Result<Vec<Value>, A::Error> {
+        let mut values = Vec::with_capacity(self.0.len());
+        for column in self.0 {
+            match seq.next_element_seed(ValueSeed(column))? {
+                Some(value) => values.push(value),
+                None if column.nullable => values.push(Value::Null),
+                None => {
+                    return Err(A::Error::invalid_length(values.len(), &self))
+                }
+            }
+        }
+        Ok(values)
+    }
+}
+
+/// Deserializes a single field according to its column.
+struct ValueSeed<'a>(&'a Column);
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<'de, 'a> DeserializeSeed<'de> for ValueSeed<'a> {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> result::Result<Value, D::Error> {
+        if self.0.nullable {
+            deserializer.deserialize_option(self)
+        } else {
+            deserialize_typed(self.0.ty, deserializer)
+        }
+    }
+}
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<'de, 'a> Visitor<'de> for ValueSeed<'a> {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an optional {:?} value", self.0.ty)
+    }
+
+    fn visit_none<E: SerdeError>(self) -> result::Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> result::Result<Value, D::Error> {
+        deserialize_typed(self.0.ty, deserializer)
+    }
+}
+
+/// Deserialize a single non-null field of the given type.
+fn deserialize_typed<'de, D: Deserializer<'de>>(
+    ty: ColumnType,
+    deserializer: D,
+) -> result::Result<Value, D::Error> {
+    let visitor = TypedVisitor(ty);
+    match ty {
+        ColumnType::Integer => deserializer.deserialize_i64(visitor),
+        ColumnType::Float => deserializer.deserialize_f64(visitor),
+        ColumnType::Boolean => deserializer.deserialize_bool(visitor),
+        ColumnType::Date => deserializer.deserialize_str(visitor),
+        _ => deserializer.deserialize_string(visitor),
+    }
+}
+
+struct TypedVisitor(ColumnType);
+
+#[cfg_attr(test, ::mutagen::mutate)] impl<'de> Visitor<'de> for TypedVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            ColumnType::Date => write!(f, "a date of the form YYYY-MM-DD"),
+            ty => write!(f, "a {:?} value", ty),
+        }
+    }
+
+    fn visit_i64<E: SerdeError>(self, v: i64) -> result::Result<Value, E> {
+        Ok(Value::Integer(v))
+    }
+
+    fn visit_f64<E: SerdeError>(self, v: f64) -> result::Result<Value, E> {
+        Ok(Value::Float(v))
+    }
+
+    fn visit_bool<E: SerdeError>(self, v: bool) -> result::Result<Value, E> {
+        Ok(Value::Boolean(v))
+    }
+
+    fn visit_str<E: SerdeError>(self, v: &str) -> result::Result<Value, E> {
+        if self.0 != ColumnType::Date {
+            Ok(Value::String(v.to_string()))
+        } else if is_date(v) {
+            Ok(Value::Date(v.to_string()))
+        } else {
+            Err(E::invalid_value(Unexpected::Str(v), &self))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::ErrorKind;
+    use crate::string_record::StringRecord;
+    use crate::writer::Writer;
+    use crate::Terminator;
+
+    use super::{ColumnType, Schema, Sniffer, Value};
+
+    fn sniff(data: &str) -> Schema {
+        Sniffer::new().sniff_reader(data.as_bytes()).unwrap()
+    }
+
+    fn types(schema: &Schema) -> Vec<(ColumnType, bool)> {
+        schema
+            .columns()
+            .iter()
+            .map(|c| (c.column_type(), c.is_nullable()))
+            .collect()
+    }
+
+    fn names(schema: &Schema) -> Vec<Option<&str>> {
+        schema.columns().iter().map(|c| c.name()).collect()
+    }
+
+    #[test]
+    fn delimiters() {
+        assert_eq!(sniff("a,b,c\n1,2,3\n").delimiter(), b',');
+        assert_eq!(sniff("a\tb\tc\n1\t2\t3\n").delimiter(), b'\t');
+        assert_eq!(sniff("a;b,c\n1;2,3\n4;5,6\n").delimiter(), b',');
+        assert_eq!(sniff("a;b;c\n1;2,5;3\n4;5;6\n").delimiter(), b';');
+        assert_eq!(sniff("a|b\n1|2\n").delimiter(), b'|');
+        assert_eq!(sniff("a:b\n1:2\n").delimiter(), b':');
+        // A single column defaults to the standard delimiter.
+        assert_eq!(sniff("a\nb\n").delimiter(), b',');
+        assert_eq!(sniff("").delimiter(), b',');
+    }
+
+    #[test]
+    fn quotes() {
+        let schema = sniff("a,b\n\"x, y\",1\n\"it's\",2\n");
+        assert_eq!(schema.quote(), b'"');
+        assert_eq!(schema.columns().len(), 2);
+
+        let schema = sniff("a,b\n'x, y',1\n'\"z, w\"',2\n");
+        assert_eq!(schema.quote(), b'\'');
+        assert_eq!(schema.columns().len(), 2);
+
+        assert_eq!(sniff("a,b\n1,2\n").quote(), b'"');
+    }
+
+    #[test]
+    fn terminators() {
+        assert_eq!(sniff("a,b\n1,2\n").terminator(), Terminator::Any(b'\n'));
+        assert_eq!(sniff("a,b\r1,2\r").terminator(), Terminator::Any(b'\r'));
+        assert_eq!(sniff("a,b\r\n1,2\r\n").terminator(), Terminator::CRLF);
+        assert_eq!(sniff("a,b\r\n1,2\n").terminator(), Terminator::CRLF);
+        assert_eq!(sniff("a,b").terminator(), Terminator::CRLF);
+    }
+
+    #[test]
+    fn headers() {
+        let schema = sniff("name,age\nfoo,1\nbar,2\n");
+        assert!(schema.has_headers());
+        assert_eq!(names(&schema), vec![Some("name"), Some("age")]);
+
+        let schema = sniff("foo,1\nbar,2\nbaz,3\n");
+        assert!(!schema.has_headers());
+        assert_eq!(names(&schema), vec![None, None]);
+
+        // All text, but the header differs from fixed length values.
+        assert!(sniff("code,state\nab,MA\ncd,NH\n").has_headers());
+        assert!(!sniff("ab,MA\ncd,NH\nef,VT\n").has_headers());
+
+        // A lone record is never a header row.
+        assert!(!sniff("name,age\n").has_headers());
+    }
+
+    #[test]
+    fn column_types() {
+        let schema = sniff(
+            "\
+i,f,b,d,s,n,m
+1,1.5,true,2020-01-31,x,,1
+-2,3,false,1999-12-01,2,5,
+3,1e3,true,2021-02-03,true,,1.5
+",
+        );
+        assert!(schema.has_headers());
+        assert_eq!(
+            types(&schema),
+            vec![
+                (ColumnType::Integer, false),
+                (ColumnType::Float, false),
+                (ColumnType::Boolean, false),
+                (ColumnType::Date, false),
+                (ColumnType::String, false),
+                (ColumnType::Integer, true),
+                (ColumnType::Float, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn not_dates() {
+        let schema = sniff("d\n2020-13-01\n2020-01-01\n");
+        assert_eq!(types(&schema), vec![(ColumnType::String, false)]);
+        let schema = sniff("d\n2020-1-01\n2020-01-01\n");
+        assert_eq!(types(&schema), vec![(ColumnType::String, false)]);
+    }
+
+    #[test]
+    fn flexible() {
+        let schema = sniff("a,b,c\n1,2,3\n4,5\n6,7,8\n");
+        assert!(schema.is_flexible());
+        assert_eq!(schema.columns().len(), 3);
+        assert_eq!(
+            types(&schema),
+            vec![
+                (ColumnType::Integer, false),
+                (ColumnType::Integer, false),
+                (ColumnType::Integer, true),
+            ]
+        );
+        assert!(!sniff("a,b\n1,2\n").is_flexible());
+    }
+
+    #[test]
+    fn truncated_sample() {
+        let data = "a,b\n1,2\n3,4\n5,xyz\n";
+        let schema = Sniffer::new()
+            .sample_size(15)
+            .sniff_reader(data.as_bytes())
+            .unwrap();
+        // The partial last record "5,x" is ignored.
+        assert_eq!(
+            types(&schema),
+            vec![(ColumnType::Integer, false), (ColumnType::Integer, false)]
+        );
+
+        let schema = Sniffer::new()
+            .sample_size(data.len())
+            .sniff_reader(data.as_bytes())
+            .unwrap();
+        assert_eq!(
+            types(&schema),
+            vec![(ColumnType::Integer, false), (ColumnType::String, false)]
+        );
+    }
+
+    #[test]
+    fn configure_reader() {
+        let data = "a;b\r1;\"x;y\"\r2;z\r";
+        let schema = sniff(data);
+        let mut rdr = schema.reader_builder().from_reader(data.as_bytes());
+        assert_eq!(rdr.headers().unwrap(), vec!["a", "b"]);
+        let records = rdr.records().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(records, vec![vec!["1", "x;y"], vec!["2", "z"]]);
+    }
+
+    #[test]
+    fn deserialize_values() {
+        let schema = sniff(
+            "\
+i,f,b,d,s,n
+1,2.5,true,2020-01-01,x,
+2,3,false,2021-01-01,y,4
+",
+        );
+        let record = StringRecord::from(vec![
+            "7",
+            "0.5",
+            "false",
+            "2000-02-29",
+            "",
+            "",
+        ]);
+        assert_eq!(
+            schema.deserialize(&record).unwrap(),
+            vec![
+                Value::Integer(7),
+                Value::Float(0.5),
+                Value::Boolean(false),
+                Value::Date("2000-02-29".to_string()),
+                Value::String("".to_string()),
+                Value::Null,
+            ]
+        );
+
+        // Missing nullable fields are null, and extra fields are ignored.
+        let schema = sniff("a,b\n1,\n2,3\n");
+        let record = StringRecord::from(vec!["1"]);
+        assert_eq!(
+            schema.deserialize(&record).unwrap(),
+            vec![Value::Integer(1), Value::Null]
+        );
+        let record = StringRecord::from(vec!["1", "2", "3"]);
+        assert_eq!(
+            schema.deserialize(&record).unwrap(),
+            vec![Value::Integer(1), Value::Integer(2)]
+        );
+    }
+
+    #[test]
+    fn deserialize_errors() {
+        let schema = sniff("a,b,d\n1,x,2020-01-01\n2,y,2020-01-02\n");
+        let record = StringRecord::from(vec!["1", "x", "2020-01-32"]);
+        match *schema.deserialize(&record).unwrap_err().kind() {
+            ErrorKind::Deserialize { ref err, .. } => {
+                assert!(err.to_string().contains("YYYY-MM-DD"), "{}", err);
+            }
+            ref x => panic!("expected Deserialize error, but got {:?}", x),
+        }
+
+        let record = StringRecord::from(vec!["x", "x", "2020-01-01"]);
+        match *schema.deserialize(&record).unwrap_err().kind() {
+            ErrorKind::Deserialize { ref err, .. } => {
+                assert_eq!(err.field(), Some(0));
+            }
+            ref x => panic!("expected Deserialize error, but got {:?}", x),
+        }
+
+        let record = StringRecord::from(vec!["1"]);
+        assert!(schema.deserialize(&record).is_err());
+    }
+
+    #[test]
+    fn serialize_values() {
+        let mut wtr = Writer::from_writer(vec![]);
+        wtr.serialize(vec![
+            Value::Integer(1),
+            Value::Null,
+            Value::Float(1.5),
+            Value::Boolean(true),
+            Value::Date("2020-01-01".to_string()),
+            Value::String("a,b".to_string()),
+        ])
+        .unwrap();
+        let data = String::from_utf8(wtr.into_inner().unwrap()).unwrap();
+        assert_eq!(data, "1,,1.5,true,2020-01-01,\"a,b\"\n");
+    }
+}
@@ -4,12 +4,22 @@ use std::iter::FromIterator;
 use std::ops::{self, Range};
 use std::result;
 use std::str;
+#[cfg(feature = "async")]
+use std::task::{Context, Poll};
 
+#[cfg(feature = "async")]
+use futures_core::ready;
+#[cfg(feature = "async")]
+use futures_io::AsyncRead;
 use serde::de::Deserialize;
 
+#[cfg(feature = "async")]
+use crate::async_reader::AsyncReader;
+
 use crate::byte_record::{ByteRecord, ByteRecordIter, Position};
 use crate::deserializer::deserialize_string_record;
 use crate::error::{Error, ErrorKind, FromUtf8Error, Result};
+use crate::parallel_reader::ParallelReader;
 use crate::reader::Reader;
 
 /// A single CSV record stored as valid UTF-8 bytes.
@@ -338,6 +348,41 @@ impl StringRecord {
         })
     }
 
+    /// Return the field in the column with the given name.
+    ///
+    /// Names are looked up in the headers of the CSV reader that read this
+    /// record. If a name occurs more than once in the headers, then the first
+    /// occurrence is used.
+    ///
+    /// If this record wasn't read by a CSV reader, if the reader had no
+    /// headers or if no column has the given name, then this returns `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::error::Error;
+    /// use csv::Reader;
+    ///
+    /// # fn main() { example().unwrap(); }
+    /// fn example() -> Result<(), Box<dyn Error>> {
+    ///     let data = "\
+    /// city,country,pop
+    /// Boston,United States,4628910
+    /// ";
+    ///     let mut rdr = Reader::from_reader(data.as_bytes());
+    ///     for result in rdr.records() {
+    ///         let record = result?;
+    ///         assert_eq!(record.get_by_name("city"), Some("Boston"));
+    ///         assert_eq!(record.get_by_name("state"), None);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    #[inline]
+    pub fn get_by_name(&self, name: &str) -> Option<&str> {
+        self.0.index_of(name.as_bytes()).and_then(|i| self.get(i))
+    }
+
     /// Returns true if and only if this record is empty.
     ///
     /// # Example
@@ -626,6 +671,50 @@ impl StringRecord {
         // is valid UTF-8.)
         let pos = rdr.position().clone();
         let read_res = rdr.read_byte_record(&mut self.0);
+        self.validate_read(pos, read_res)
+    }
+
+    /// Like `read`, but reads from an asynchronous CSV reader.
+    ///
+    /// This relies on the internal representation of `StringRecord`.
+    #[cfg(feature = "async")]
+    pub(crate) fn poll_read<R: AsyncRead + Unpin>(
+        &mut self,
+        cx: &mut Context,
+        rdr: &mut AsyncReader<R>,
+    ) -> Poll<Result<bool>> {
+        // SAFETY: See `read`. Since this may be polled many times for the
+        // same record, the position reported on UTF-8 errors is the position
+        // of the record itself rather than the position of the reader.
+        let read_res = ready!(rdr.poll_read_byte_record(cx, &mut self.0));
+        let pos = self.0.position().map_or_else(Position::new, Clone::clone);
+        Poll::Ready(self.validate_read(pos, read_res))
+    }
+
+    /// Like `read`, but reads from a parallel CSV reader.
+    ///
+    /// This relies on the internal representation of `StringRecord`.
+    #[inline(always)]
+    pub(crate) fn read_parallel(
+        &mut self,
+        rdr: &mut ParallelReader,
+    ) -> Result<bool> {
+        // SAFETY: See `read`. A parallel reader doesn't track a position
+        // between records, so UTF-8 errors report the position of the record
+        // itself.
+        let read_res = rdr.read_byte_record(&mut self.0);
+        let pos = self.0.position().map_or_else(Position::new, Clone::clone);
+        self.validate_read(pos, read_res)
+    }
+
+    /// Check that the record just read into this `StringRecord` is valid
+    /// UTF-8, and wipe it if it isn't.
+    #[inline(always)]
+    fn validate_read(
+        &mut self,
+        pos: Position,
+        read_res: Result<bool>,
+    ) -> Result<bool> {
         let utf8_res = match self.0.validate() {
             Ok(()) => Ok(()),
             Err(err) => {
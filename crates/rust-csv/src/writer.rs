@@ -1,5 +1,7 @@
+use std::collections::HashMap;
 use std::fs::File;
 use std::io;
+use std::mem;
 use std::path::Path;
 use std::result;
 
@@ -7,12 +9,16 @@ use csv_core::{
     self, WriteResult, Writer as CoreWriter,
     WriterBuilder as CoreWriterBuilder,
 };
+#[cfg(feature = "async")]
+use futures_io::AsyncWrite;
 use serde::Serialize;
 
+#[cfg(feature = "async")]
+use crate::async_writer::AsyncWriter;
 use crate::byte_record::ByteRecord;
 use crate::error::{Error, ErrorKind, IntoInnerError, Result};
 use crate::serializer::{serialize, serialize_header};
-use crate::{QuoteStyle, Terminator};
+use crate::{QuoteStyle, SequenceStyle, Terminator};
 
 /// Builds a CSV writer with various configuration knobs.
 ///
@@ -22,9 +28,11 @@ use crate::{QuoteStyle, Terminator};
 #[derive(Debug)]
 pub struct WriterBuilder {
     builder: CoreWriterBuilder,
-    capacity: usize,
+    pub(crate) capacity: usize,
     flexible: bool,
     has_headers: bool,
+    sequence_style: SequenceStyle,
+    union_headers: usize,
 }
 
 #[cfg_attr(test, ::mutagen::mutate)] impl Default for WriterBuilder {
@@ -34,6 +42,8 @@ pub struct WriterBuilder {
             capacity: 8 * (1 << 10),
             flexible: false,
             has_headers: true,
+            sequence_style: SequenceStyle::default(),
+            union_headers: 0,
         }
     }
 }
@@ -116,6 +126,42 @@ pub struct WriterBuilder {
         Writer::new(self, wtr)
     }
 
+    /// Build an asynchronous CSV writer from this configuration that writes
+    /// data to `wtr`.
+    ///
+    /// As with `from_writer`, the CSV writer is buffered automatically, so
+    /// `wtr` should not be wrapped in a buffered writer.
+    ///
+    /// This requires the `async` feature.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::error::Error;
+    /// use csv::WriterBuilder;
+    /// use futures::executor::block_on;
+    ///
+    /// # fn main() { block_on(example()).unwrap(); }
+    /// async fn example() -> Result<(), Box<dyn Error>> {
+    ///     let mut wtr = WriterBuilder::new()
+    ///         .delimiter(b';')
+    ///         .from_async_writer(vec![]);
+    ///     wtr.write_record(&["a", "b", "c"]).await?;
+    ///     wtr.write_record(&["x", "y", "z"]).await?;
+    ///
+    ///     let data = String::from_utf8(wtr.into_inner().await?)?;
+    ///     assert_eq!(data, "a;b;c\nx;y;z\n");
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "async")]
+    pub fn from_async_writer<W: AsyncWrite + Unpin>(
+        &self,
+        wtr: W,
+    ) -> AsyncWriter<W> {
+        AsyncWriter::new(self, wtr)
+    }
+
     /// The field delimiter to use when writing CSV.
     ///
     /// The default is `b','`.
@@ -290,6 +336,109 @@ pub struct WriterBuilder {
         self
     }
 
+    /// The style to use when serializing sequences in struct fields.
+    ///
+    /// By default, each element of a sequence (or tuple) in a struct field is
+    /// written in its own field, with headers like `values[0]`. Use
+    /// `SequenceStyle::Join` to write all of the elements in a single field
+    /// instead.
+    ///
+    /// # Example: joining sequences
+    ///
+    /// ```
+    /// use std::error::Error;
+    ///
+    /// use csv::{SequenceStyle, WriterBuilder};
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Row<'a> {
+    ///     city: &'a str,
+    ///     zips: Vec<u32>,
+    /// }
+    ///
+    /// # fn main() { example().unwrap(); }
+    /// fn example() -> Result<(), Box<dyn Error>> {
+    ///     let mut wtr = WriterBuilder::new()
+    ///         .sequence_style(SequenceStyle::Join(b';'))
+    ///         .from_writer(vec![]);
+    ///     wtr.serialize(Row { city: "Boston", zips: vec![2108, 2109] })?;
+    ///     wtr.serialize(Row { city: "Concord", zips: vec![1742] })?;
+    ///
+    ///     let data = String::from_utf8(wtr.into_inner()?)?;
+    ///     assert_eq!(data, "\
+    /// city,zips
+    /// Boston,2108;2109
+    /// Concord,1742
+    /// ");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn sequence_style(
+        &mut self,
+        style: SequenceStyle,
+    ) -> &mut WriterBuilder {
+        self.sequence_style = style;
+        self
+    }
+
+    /// Derive the header row from the first `n` records serialized.
+    ///
+    /// By default, the header row is derived from the field names of the
+    /// first record passed to `Writer::serialize`, which doesn't work when
+    /// records have different fields, e.g., when they contain sequences of
+    /// different lengths, maps with different keys or `Option`s of nested
+    /// structs. When this is set to a non-zero `n`, the first `n` records are
+    /// buffered instead, and the header row is the union of their field
+    /// names, in the order in which they were first seen. Every record is
+    /// then written with its fields lined up with the header, leaving empty
+    /// the fields that the record doesn't have. Serializing a record with a
+    /// field that isn't in the header returns an error.
+    ///
+    /// Buffered records are written when the `n`th record is serialized, or
+    /// when the writer is flushed. Records written with methods other than
+    /// `serialize` are not buffered, so mixing the two while records are
+    /// buffered writes them out of order.
+    ///
+    /// This has no effect if `has_headers` is disabled, and serializing
+    /// records without field names returns an error when it is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use std::error::Error;
+    ///
+    /// use csv::WriterBuilder;
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Row<'a> {
+    ///     city: &'a str,
+    ///     zips: Vec<u32>,
+    /// }
+    ///
+    /// # fn main() { example().unwrap(); }
+    /// fn example() -> Result<(), Box<dyn Error>> {
+    ///     let mut wtr = WriterBuilder::new()
+    ///         .union_headers(100)
+    ///         .from_writer(vec![]);
+    ///     wtr.serialize(Row { city: "Concord", zips: vec![1742] })?;
+    ///     wtr.serialize(Row { city: "Boston", zips: vec![2108, 2109] })?;
+    ///
+    ///     let data = String::from_utf8(wtr.into_inner()?)?;
+    ///     assert_eq!(data, "\
+    /// city,zips[0],zips[1]
+    /// Concord,1742,
+    /// Boston,2108,2109
+    /// ");
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn union_headers(&mut self, n: usize) -> &mut WriterBuilder {
+        self.union_headers = n;
+        self
+    }
+
     /// The record terminator to use when writing CSV.
     ///
     /// A record terminator can be any single byte. The default is `\n`.
@@ -518,6 +667,11 @@ struct WriterState {
     header: HeaderState,
     /// Whether inconsistent record lengths are allowed.
     flexible: bool,
+    /// The style to use when serializing sequences in struct fields.
+    sequence_style: SequenceStyle,
+    /// The header row derived from the first records serialized, if
+    /// `union_headers` is enabled.
+    union: Option<HeaderUnion>,
     /// The number of fields writtein in the first record. This is compared
     /// with `fields_written` on all subsequent records to check for
     /// inconsistent record lengths.
@@ -546,6 +700,23 @@ enum HeaderState {
     None,
 }
 
+/// The union of the field names of the first records serialized, which is
+/// written as the header row.
+#[derive(Debug)]
+struct HeaderUnion {
+    /// The number of records to buffer before writing the header row.
+    limit: usize,
+    /// Each column of the header row: its name, and the number of columns
+    /// before it with the same name.
+    columns: Vec<(Vec<u8>, usize)>,
+    /// The positions of the columns with each name, once the header row is
+    /// written.
+    index: Option<HashMap<Vec<u8>, Vec<usize>>>,
+    /// The records buffered until the header row is written: the names of
+    /// their fields, and the fields themselves.
+    pending: Vec<(ByteRecord, ByteRecord)>,
+}
+
 /// A simple internal buffer for buffering writes.
 ///
 /// We need this because the `csv_core` APIs want to write into a `&mut [u8]`,
@@ -600,12 +771,19 @@ struct Buffer {
         } else {
             HeaderState::None
         };
+        let union = if builder.has_headers && builder.union_headers > 0 {
+            Some(HeaderUnion::new(builder.union_headers))
+        } else {
+            None
+        };
         Writer {
             core: builder.builder.build(),
             wtr: Some(wtr),
             buf: Buffer { buf: vec![0; builder.capacity], len: 0 },
             state: WriterState {
                 header: header_state,
+                sequence_style: builder.sequence_style,
+                union: union,
                 flexible: builder.flexible,
                 first_field_count: None,
                 fields_written: 0,
@@ -786,7 +964,7 @@ struct Buffer {
     /// | tuple struct | `Foo(u8, bool)` | `Foo(5, true)` | `5,true` |
     /// | tuple enum variant | `enum E { A(u8, bool) }` | `E::A(5, true)` | *error* |
     /// | struct enum variant | `enum E { V { a: u8, b: bool } }` | `E::V { a: 5, b: true }` | *error* |
-    /// | map | `BTreeMap<K, V>` | `BTreeMap::new()` | *error* |
+    /// | map | `BTreeMap<K, V>` | `{"a": 5, "b": 6}` | `5,6` |
     ///
     /// ## Structs
     ///
@@ -834,24 +1012,52 @@ struct Buffer {
     /// serialization would return an error. Speficially, when `has_headers` is
     /// `true`, there are two restrictions:
     ///
-    /// 1. Named field values in structs must be scalars.
+    /// 1. Named field values in structs must not be sequences (or tuples) of
+    ///    containers, if sequences are joined with `SequenceStyle::Join`.
     ///
     /// 2. All scalars must be named field values in structs.
     ///
+    /// The fields of a struct nested in another struct's field get headers
+    /// prefixed with the name of that field, e.g., `address.city`, which
+    /// `Reader::deserialize` groups back into a nested struct. (Since `None`
+    /// is written as a single empty field, an `Option` of a nested struct
+    /// must be `Some` in every record for the records to line up.) For the
+    /// purpose of these restrictions, maps are treated like structs whose
+    /// field names are the map's keys, which means structs with
+    /// `#[serde(flatten)]` fields are supported too. Internally and
+    /// adjacently tagged enums are serialized as structs by Serde, and so
+    /// are supported as well.
+    ///
+    /// The elements of a sequence in a struct field get headers suffixed with
+    /// their index, e.g., `values[0]`, unless the writer is configured to join
+    /// them into a single field with
+    /// [`WriterBuilder::sequence_style`](struct.WriterBuilder.html#method.sequence_style).
+    /// When records don't all have the same fields, e.g., because their
+    /// sequences have different lengths, use
+    /// [`WriterBuilder::union_headers`](struct.WriterBuilder.html#method.union_headers)
+    /// to derive the header row from several records.
+    ///
     /// Other than these two restrictions, types can be nested arbitrarily.
     /// Here are a few examples:
     ///
     /// | Value | Header | Record |
     /// | ---- | ---- | ---- |
     /// | `(Foo { x: 5, y: 6 }, Bar { z: true })` | `x,y,z` | `5,6,true` |
+    /// | `Foo { x: 5, y: Bar { z: true } }` | `x,y.z` | `5,true` |
+    /// | `{"x": 5, "y": 6}` | `x,y` | `5,6` |
     /// | `vec![Foo { x: 5, y: 6 }, Foo { x: 7, y: 8 }]` | `x,y,x,y` | `5,6,7,8` |
     /// | `(Foo { x: 5, y: 6 }, vec![Bar { z: Baz(true) }])` | `x,y,z` | `5,6,true` |
-    /// | `Foo { x: 5, y: (6, 7) }` | *error: restriction 1* | `5,6,7` |
+    /// | `Foo { x: 5, y: (6, 7) }` | `x,y[0],y[1]` | `5,6,7` |
+    /// | `Foo { x: 5, y: vec![Bar { z: true }] }` | `x,y[0].z` | `5,true` |
     /// | `(5, Foo { x: 6, y: 7 }` | *error: restriction 2* | `5,6,7` |
     /// | `(Foo { x: 5, y: 6 }, true)` | *error: restriction 2* | `5,6,true` |
     pub fn serialize<S: Serialize>(&mut self, record: S) -> Result<()> {
+        if self.state.union.is_some() {
+            return self.serialize_union(record);
+        }
+        let style = self.state.sequence_style;
         if let HeaderState::Write = self.state.header {
-            let wrote_header = serialize_header(self, &record)?;
+            let wrote_header = serialize_header(self, style, &record)?;
             if wrote_header {
                 self.write_terminator()?;
                 self.state.header = HeaderState::DidWrite;
@@ -859,11 +1065,69 @@ struct Buffer {
                 self.state.header = HeaderState::DidNotWrite;
             };
         }
-        serialize(self, &record)?;
+        serialize(self, style, &record)?;
         self.write_terminator()?;
         Ok(())
     }
 
+    /// Serialize a single record when the header row is derived from the
+    /// first records serialized.
+    fn serialize_union<S: Serialize>(&mut self, record: S) -> Result<()> {
+        let style = self.state.sequence_style;
+        let mut names = ByteRecord::new();
+        if !serialize_header(&mut names, style, &record)? {
+            return Err(Error::new(ErrorKind::Serialize(
+                "cannot derive a header row from a record without \
+                 field names"
+                    .to_string(),
+            )));
+        }
+        let mut fields = ByteRecord::new();
+        serialize(&mut fields, style, &record)?;
+        if names.len() != fields.len() {
+            return Err(Error::new(ErrorKind::Serialize(format!(
+                "cannot line up a record of {} fields with its {} field names",
+                fields.len(),
+                names.len()
+            ))));
+        }
+        let union = self.state.union.as_mut().unwrap();
+        if union.index.is_some() {
+            let record = union.align(&names, &fields)?;
+            return self.write_byte_record(&record);
+        }
+        union.add(&names);
+        union.pending.push((names, fields));
+        if union.pending.len() >= union.limit {
+            self.write_union()?;
+        }
+        Ok(())
+    }
+
+    /// Write the header row derived from the records buffered so far, and
+    /// then the records themselves.
+    ///
+    /// This does nothing if there are no buffered records.
+    fn write_union(&mut self) -> Result<()> {
+        let (header, records) = match self.state.union {
+            Some(ref mut union) if !union.pending.is_empty() => {
+                let header = union.finish();
+                let mut records = vec![];
+                for (names, fields) in mem::replace(&mut union.pending, vec![])
+                {
+                    records.push(union.align(&names, &fields)?);
+                }
+                (header, records)
+            }
+            _ => return Ok(()),
+        };
+        self.write_byte_record(&header)?;
+        for record in &records {
+            self.write_byte_record(record)?;
+        }
+        Ok(())
+    }
+
     /// Write a single record.
     ///
     /// This method accepts something that can be turned into an iterator that
@@ -1043,7 +1307,7 @@ struct Buffer {
                     self.state.fields_written += 1;
                     return Ok(());
                 }
-                WriteResult::OutputFull => self.flush()?,
+                WriteResult::OutputFull => self.flush_buffer()?,
             }
         }
     }
@@ -1054,7 +1318,18 @@ struct Buffer {
     /// is returned.
     ///
     /// Note that this also flushes the underlying writer.
+    ///
+    /// If `union_headers` is enabled and records are still buffered to derive
+    /// the header row from, then the header row is derived from them, and
+    /// they are written first.
     pub fn flush(&mut self) -> io::Result<()> {
+        self.write_union()?;
+        self.flush_buffer()
+    }
+
+    /// Flush the contents of the internal buffer to the underlying writer,
+    /// without writing any records buffered for `union_headers`.
+    pub(crate) fn flush_buffer(&mut self) -> io::Result<()> {
         self.state.panicked = true;
         let result = self.wtr.as_mut().unwrap().write_all(self.buf.readable());
         self.state.panicked = false;
@@ -1064,6 +1339,12 @@ struct Buffer {
         Ok(())
     }
 
+    /// Returns a mutable reference to the underlying writer.
+    #[cfg(feature = "async")]
+    pub(crate) fn get_mut(&mut self) -> &mut W {
+        self.wtr.as_mut().unwrap()
+    }
+
     /// Flush the contents of the internal buffer and return the underlying
     /// writer.
     pub fn into_inner(
@@ -1082,7 +1363,7 @@ struct Buffer {
             self.buf.written(nout);
             match res {
                 WriteResult::InputEmpty => return Ok(()),
-                WriteResult::OutputFull => self.flush()?,
+                WriteResult::OutputFull => self.flush_buffer()?,
             }
         }
     }
@@ -1098,7 +1379,7 @@ struct Buffer {
                     self.state.fields_written = 0;
                     return Ok(());
                 }
-                WriteResult::OutputFull => self.flush()?,
+                WriteResult::OutputFull => self.flush_buffer()?,
             }
         }
     }
@@ -1145,6 +1426,90 @@ struct Buffer {
     }
 }
 
+#[cfg_attr(test, ::mutagen::mutate)] impl HeaderUnion {
+    fn new(limit: usize) -> HeaderUnion {
+        HeaderUnion {
+            limit: limit,
+            columns: vec![],
+            index: None,
+            pending: vec![],
+        }
+    }
+
+    /// Returns each of the given names, along with the number of names
+    /// before it that are the same.
+    fn number(names: &ByteRecord) -> Vec<(&[u8], usize)> {
+        let mut seen = HashMap::new();
+        names
+            .iter()
+            .map(|name| {
+                let count = seen.entry(name).or_insert(0);
+                *count += 1;
+                (name, *count - 1)
+            })
+            .collect()
+    }
+
+    /// Add the given field names to the header row.
+    ///
+    /// Names that are new are inserted after the name preceding them in the
+    /// record, so that, e.g., the elements of a longer sequence end up next
+    /// to the others.
+    fn add(&mut self, names: &ByteRecord) {
+        let mut pos = 0;
+        for (name, n) in HeaderUnion::number(names) {
+            let found = self
+                .columns
+                .iter()
+                .position(|&(ref column, m)| column == name && m == n);
+            match found {
+                Some(i) => pos = i + 1,
+                None => {
+                    self.columns.insert(pos, (name.to_vec(), n));
+                    pos += 1;
+                }
+            }
+        }
+    }
+
+    /// Returns the header row, after which no more names can be added.
+    fn finish(&mut self) -> ByteRecord {
+        let mut header = ByteRecord::new();
+        let mut index = HashMap::new();
+        for (i, &(ref name, _)) in self.columns.iter().enumerate() {
+            header.push_field(name);
+            index.entry(name.clone()).or_insert_with(Vec::new).push(i);
+        }
+        self.index = Some(index);
+        header
+    }
+
+    /// Returns the given fields lined up with the header row.
+    fn align(
+        &self,
+        names: &ByteRecord,
+        fields: &ByteRecord,
+    ) -> Result<ByteRecord> {
+        let index = self.index.as_ref().expect("header row is finished");
+        let mut record = vec![&b""[..]; self.columns.len()];
+        for ((name, n), field) in
+            HeaderUnion::number(names).into_iter().zip(fields)
+        {
+            match index.get(name).and_then(|columns| columns.get(n)) {
+                Some(&i) => record[i] = field,
+                None => {
+                    return Err(Error::new(ErrorKind::Serialize(format!(
+                        "field `{}` is not in the header row derived from \
+                         the first records",
+                        String::from_utf8_lossy(name)
+                    ))));
+                }
+            }
+        }
+        Ok(ByteRecord::from(record))
+    }
+}
+
 #[cfg_attr(test, ::mutagen::mutate)] impl Buffer {
     /// Returns a slice of the buffer's current contents.
     ///
@@ -1177,11 +1542,15 @@ struct Buffer {
 
 #[cfg(test)]
 mod tests {
-    use serde::{serde_if_integer128, Serialize};
+    use std::collections::BTreeMap;
+
+    use serde::{serde_if_integer128, Deserialize, Serialize};
 
     use crate::byte_record::ByteRecord;
     use crate::error::ErrorKind;
+    use crate::reader::Reader;
     use crate::string_record::StringRecord;
+    use crate::SequenceStyle;
 
     use super::{Writer, WriterBuilder};
 
@@ -1335,6 +1704,151 @@ mod tests {
         assert_eq!(wtr_as_string(wtr), "42,42.5,true\n");
     }
 
+    #[test]
+    fn serialize_union_headers() {
+        #[derive(Serialize)]
+        struct Row {
+            id: u32,
+            tags: Vec<&'static str>,
+            attrs: BTreeMap<&'static str, u32>,
+        }
+
+        let mut wtr =
+            WriterBuilder::new().union_headers(2).from_writer(vec![]);
+        let mut attrs = BTreeMap::new();
+        attrs.insert("w", 1);
+        wtr.serialize(Row { id: 1, tags: vec!["a"], attrs: attrs.clone() })
+            .unwrap();
+        attrs.insert("h", 2);
+        wtr.serialize(Row { id: 2, tags: vec!["b", "c"], attrs: attrs })
+            .unwrap();
+        wtr.serialize(Row { id: 3, tags: vec![], attrs: BTreeMap::new() })
+            .unwrap();
+
+        let err = wtr
+            .serialize(Row {
+                id: 4,
+                tags: vec!["d", "e", "f"],
+                attrs: BTreeMap::new(),
+            })
+            .unwrap_err();
+        match *err.kind() {
+            ErrorKind::Serialize(_) => {}
+            ref x => panic!("expected ErrorKind::Serialize but got '{:?}'", x),
+        }
+        assert_eq!(
+            wtr_as_string(wtr),
+            "id,tags[0],tags[1],attrs.h,attrs.w\n1,a,,,1\n2,b,c,2,1\n3,,,,\n"
+        );
+    }
+
+    #[test]
+    fn serialize_union_headers_flush() {
+        #[derive(Serialize)]
+        struct Row {
+            values: Vec<u32>,
+        }
+
+        let mut wtr = WriterBuilder::new()
+            .union_headers(10)
+            .sequence_style(SequenceStyle::Join(b' '))
+            .from_writer(vec![]);
+        wtr.serialize(Row { values: vec![1, 2] }).unwrap();
+        wtr.flush().unwrap();
+        wtr.serialize(Row { values: vec![3] }).unwrap();
+        assert_eq!(wtr_as_string(wtr), "values\n1 2\n3\n");
+    }
+
+    #[test]
+    fn serialize_union_headers_no_field_names() {
+        let mut wtr =
+            WriterBuilder::new().union_headers(2).from_writer(vec![]);
+        let err = wtr.serialize((1, 2)).unwrap_err();
+        match *err.kind() {
+            ErrorKind::Serialize(_) => {}
+            ref x => panic!("expected ErrorKind::Serialize but got '{:?}'", x),
+        }
+
+        let mut wtr = WriterBuilder::new()
+            .union_headers(2)
+            .has_headers(false)
+            .from_writer(vec![]);
+        wtr.serialize((1, 2)).unwrap();
+        assert_eq!(wtr_as_string(wtr), "1,2\n");
+    }
+
+    #[test]
+    fn serialize_round_trip_nested() {
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Row {
+            id: u32,
+            #[serde(flatten)]
+            place: Place,
+            shape: Shape,
+            event: Event,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Place {
+            name: String,
+            location: Option<Point>,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Point {
+            x: f64,
+            y: f64,
+        }
+
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        #[serde(tag = "type")]
+        enum Shape {
+            Square { side: f64 },
+        }
+
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        #[serde(tag = "kind", content = "data")]
+        enum Event {
+            Moved(Point),
+        }
+
+        let rows = vec![
+            Row {
+                id: 1,
+                place: Place {
+                    name: "home".into(),
+                    location: Some(Point { x: 1.5, y: 2.0 }),
+                },
+                shape: Shape::Square { side: 3.0 },
+                event: Event::Moved(Point { x: 4.0, y: 5.5 }),
+            },
+            Row {
+                id: 2,
+                place: Place {
+                    name: "work".into(),
+                    location: Some(Point { x: -1.0, y: 0.0 }),
+                },
+                shape: Shape::Square { side: 0.5 },
+                event: Event::Moved(Point { x: 0.0, y: 1.0 }),
+            },
+        ];
+        let mut wtr = WriterBuilder::new().from_writer(vec![]);
+        for row in &rows {
+            wtr.serialize(row).unwrap();
+        }
+        let data = wtr_as_string(wtr);
+        assert_eq!(
+            data.lines().next().unwrap(),
+            "id,name,location.x,location.y,shape.type,shape.side,\
+             event.kind,event.data.x,event.data.y"
+        );
+
+        let mut rdr = Reader::from_reader(data.as_bytes());
+        let got: Vec<Row> =
+            rdr.deserialize().collect::<Result<_, _>>().unwrap();
+        assert_eq!(got, rows);
+    }
+
     serde_if_integer128! {
         #[test]
         fn serialize_no_headers_128() {